@@ -0,0 +1,128 @@
+use std::{collections::VecDeque, time::Duration};
+
+use naia_shared::{ActorRef, Instant, MessageReceipt};
+
+/// Holds received Events of the types set to be actor-ordered, until every
+/// Actor they reference has come into scope, or they time out. Events are
+/// handed over in the order they were received, so one still waiting holds
+/// back those behind it
+#[derive(Debug)]
+pub struct ActorOrderedEvents<T> {
+    timeout: Duration,
+    // oldest first, and so in order of when they time out, along with the time
+    // they do
+    pending: VecDeque<(T, Option<MessageReceipt>, Instant)>,
+}
+
+impl<T> ActorOrderedEvents<T> {
+    /// Create a new ActorOrderedEvents, given how long an Event waits for the
+    /// Actors it references before it's handed over anyway
+    pub fn new(timeout: Duration) -> Self {
+        ActorOrderedEvents {
+            timeout,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Returns whether an Event with the given ActorRefs has to be held, which
+    /// is if any of them doesn't resolve, or other Events are being held
+    /// already
+    pub fn must_hold<F: Fn(&ActorRef) -> bool>(
+        &self,
+        actor_refs: &[ActorRef],
+        resolves: F,
+    ) -> bool {
+        return !self.pending.is_empty() || !get_unresolved(actor_refs, &resolves).is_empty();
+    }
+
+    /// Holds a received Event, along with its MessageReceipt
+    pub fn hold(&mut self, event: T, receipt: Option<MessageReceipt>, now: &Instant) {
+        let mut expires_at = now.clone();
+        expires_at.add_millis(self.timeout.as_millis() as u32);
+        self.pending.push_back((event, receipt, expires_at));
+    }
+
+    /// Takes the oldest held Event, if every ActorRef it holds resolves, or it
+    /// has timed out, in which case it's returned along with the ActorRefs
+    /// which still don't resolve
+    pub fn take_ready<G: Fn(&T) -> Vec<ActorRef>, F: Fn(&ActorRef) -> bool>(
+        &mut self,
+        now: &Instant,
+        get_actor_refs: G,
+        resolves: F,
+    ) -> Option<(T, Option<MessageReceipt>, Vec<ActorRef>)> {
+        let (event, _, expires_at) = self.pending.front()?;
+        let unresolved = get_unresolved(&get_actor_refs(event), &resolves);
+        if !unresolved.is_empty() && now < expires_at {
+            return None;
+        }
+        let (event, receipt, _) = self.pending.pop_front()?;
+        return Some((event, receipt, unresolved));
+    }
+}
+
+// the ActorRefs which point to an Actor, yet don't resolve
+fn get_unresolved<F: Fn(&ActorRef) -> bool>(
+    actor_refs: &[ActorRef],
+    resolves: &F,
+) -> Vec<ActorRef> {
+    return actor_refs
+        .iter()
+        .filter(|actor_ref| actor_ref.is_some() && !resolves(actor_ref))
+        .copied()
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashSet, time::Duration};
+
+    use naia_shared::{ActorRef, Clock, SystemClock};
+
+    use super::ActorOrderedEvents;
+
+    #[test]
+    fn events_wait_in_order_for_their_actors() {
+        let now = SystemClock.now();
+        let in_scope = RefCell::new(HashSet::new());
+        let resolves = |actor_ref: &ActorRef| in_scope.borrow().contains(&actor_ref.get_net_id());
+        let get_actor_refs = |refs: &Vec<ActorRef>| refs.clone();
+        let mut events = ActorOrderedEvents::new(Duration::from_secs(1));
+
+        // ActorRefs to no Actor need nothing to resolve
+        assert!(!events.must_hold(&[ActorRef::none()], resolves));
+        let first = vec![ActorRef::new(1), ActorRef::none()];
+        assert!(events.must_hold(&first, resolves));
+        events.hold(first, None, &now);
+        // resolved, yet held behind the first
+        let second = vec![ActorRef::new(2)];
+        in_scope.borrow_mut().insert(Some(2));
+        assert!(events.must_hold(&second, resolves));
+        events.hold(second, None, &now);
+
+        assert_eq!(events.take_ready(&now, get_actor_refs, resolves), None);
+        in_scope.borrow_mut().insert(Some(1));
+        let (event, _, unresolved) = events.take_ready(&now, get_actor_refs, resolves).unwrap();
+        assert_eq!(event[0], ActorRef::new(1));
+        assert!(unresolved.is_empty());
+        let (event, _, _) = events.take_ready(&now, get_actor_refs, resolves).unwrap();
+        assert_eq!(event, vec![ActorRef::new(2)]);
+        assert!(!events.must_hold(&[], resolves));
+    }
+
+    #[test]
+    fn timed_out_events_are_flagged() {
+        let mut now = SystemClock.now();
+        let resolves = |_: &ActorRef| false;
+        let get_actor_refs = |refs: &Vec<ActorRef>| refs.clone();
+        let mut events = ActorOrderedEvents::new(Duration::from_millis(500));
+        events.hold(vec![ActorRef::new(1), ActorRef::none()], None, &now);
+
+        now.add_millis(499);
+        assert_eq!(events.take_ready(&now, get_actor_refs, resolves), None);
+        now.add_millis(1);
+        let (_, _, unresolved) = events.take_ready(&now, get_actor_refs, resolves).unwrap();
+        assert_eq!(unresolved, vec![ActorRef::new(1)]);
+        assert!(!events.must_hold(&[], resolves));
+    }
+}
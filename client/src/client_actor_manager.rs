@@ -1,12 +1,13 @@
-use log::warn;
 use naia_shared::{
-    ActorType, EventType, LocalActorKey, Manifest, PacketReader, SequenceBuffer, StateMask,
+    byte_order, generation_greater_than, sequence_less_than, strict_assert, varint,
+    ActorGeneration, ActorRef, ActorType, DespawnReason, EventType, LocalActorKey, Manifest,
+    PacketReader, SchemaVersions, SequenceBuffer, StateMask,
 };
 use std::collections::{HashMap, VecDeque};
 
 use super::client_actor_message::ClientActorMessage;
 use crate::{command_receiver::CommandReceiver, interpolation_manager::InterpolationManager};
-use std::collections::hash_map::Keys;
+use std::collections::hash_map::{Iter, Keys};
 
 const PAWN_HISTORY_SIZE: u16 = 64;
 
@@ -16,6 +17,23 @@ pub struct ClientActorManager<U: ActorType> {
     queued_incoming_messages: VecDeque<ClientActorMessage>,
     pawn_store: HashMap<LocalActorKey, U>,
     pawn_history: HashMap<LocalActorKey, SequenceBuffer<U>>,
+    // the predicted state of each Pawn before it was last corrected
+    pawn_corrections: HashMap<LocalActorKey, U>,
+    unreported_corrections: VecDeque<LocalActorKey>,
+    net_id_to_local_key_map: HashMap<u64, LocalActorKey>,
+    local_key_to_net_id_map: HashMap<LocalActorKey, u64>,
+    // the generation of the Actor each key was last created for, which is kept
+    // once the Actor is deleted, so that late messages for it are discarded
+    generations: HashMap<LocalActorKey, ActorGeneration>,
+    // Actors whose whole state has been asked of the Server, after a long run
+    // of packets was missed, along with the updates held back until it comes:
+    // the index of the packet each arrived in, its mask & its payload
+    resyncing: HashMap<LocalActorKey, Vec<(u16, StateMask, Box<[u8]>)>>,
+    // the tick of the newest update applied to each Actor, as every update
+    // holds all the changes an older one in flight did, so that an update
+    // from an older tick arriving after it is discarded whole
+    update_ticks: HashMap<LocalActorKey, u16>,
+    world_sync_complete: bool,
 }
 
 impl<U: ActorType> ClientActorManager<U> {
@@ -25,12 +43,21 @@ impl<U: ActorType> ClientActorManager<U> {
             local_actor_store: HashMap::new(),
             pawn_store: HashMap::new(),
             pawn_history: HashMap::new(),
+            pawn_corrections: HashMap::new(),
+            unreported_corrections: VecDeque::new(),
+            net_id_to_local_key_map: HashMap::new(),
+            local_key_to_net_id_map: HashMap::new(),
+            generations: HashMap::new(),
+            resyncing: HashMap::new(),
+            update_ticks: HashMap::new(),
+            world_sync_complete: false,
         }
     }
 
     pub fn process_data<T: EventType>(
         &mut self,
         manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
         command_receiver: &mut CommandReceiver<T>,
         interpolator: &mut InterpolationManager<U>,
         packet_tick: u16,
@@ -43,63 +70,125 @@ impl<U: ActorType> ClientActorManager<U> {
             let message_type: u8 = reader.read_u8();
 
             match message_type {
-                0 => {
-                    // Creation
-                    let naia_id: u16 = reader.read_u16();
-                    let local_key: u16 = reader.read_u16();
+                0 | 7 | 8 => {
+                    // Creation, tagged with a PredictionId if the Actor was
+                    // spawned for a request of this Client, or written as the
+                    // Properties which differ from an Archetype
+                    let naia_id = match message_type {
+                        8 => None,
+                        _ => Some(byte_order::read_u16(reader)),
+                    };
+                    let local_key: u16 = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    let net_id: u64 = byte_order::read_u64(reader);
+                    let prediction_id = match message_type {
+                        7 => Some(byte_order::read_u16(reader)),
+                        _ => None,
+                    };
 
-                    match manifest.create_actor(naia_id, reader) {
+                    let new_actor = match naia_id {
+                        Some(naia_id) => manifest.create_actor(naia_id, reader, schema_versions),
+                        None => {
+                            let archetype_id = match varint::read_from(reader, varint::read_u16) {
+                                Ok(archetype_id) => archetype_id,
+                                Err(_) => return,
+                            };
+                            let diff_mask = StateMask::read(reader);
+                            manifest.create_archetype_actor(archetype_id, &diff_mask, reader)
+                        }
+                    };
+                    match new_actor {
                         Some(new_actor) => {
+                            if !self.is_new_generation(&local_key, generation) {
+                                // a late copy of this creation, or the creation
+                                // of an Actor which used the key before
+                                continue;
+                            }
                             if self.local_actor_store.contains_key(&local_key) {
-                                warn!("duplicate local key inserted");
-                            } else {
-                                //info!("creation of actor w/ key of {}", local_key);
-                                let is_interpolated = new_actor.is_interpolated();
-                                self.local_actor_store.insert(local_key, new_actor);
-                                if is_interpolated {
-                                    interpolator.create_interpolation(&self, &local_key);
-                                }
-                                self.queued_incoming_messages
-                                    .push_back(ClientActorMessage::Create(local_key));
+                                // the deletion of the Actor which used the key
+                                // before hasn't arrived yet, so it is deleted
+                                // now, before the new Actor is created
+                                self.delete_actor(
+                                    local_key,
+                                    DespawnReason::OutOfScope,
+                                    command_receiver,
+                                    interpolator,
+                                );
                             }
+                            //info!("creation of actor w/ key of {}", local_key);
+                            let is_interpolated = new_actor.is_interpolated();
+                            self.generations.insert(local_key, generation);
+                            self.resyncing.remove(&local_key);
+                            self.update_ticks.remove(&local_key);
+                            self.local_actor_store.insert(local_key, new_actor);
+                            self.net_id_to_local_key_map.insert(net_id, local_key);
+                            self.local_key_to_net_id_map.insert(local_key, net_id);
+                            if is_interpolated {
+                                interpolator.create_interpolation(&self, &local_key);
+                            }
+                            let message = match prediction_id {
+                                Some(prediction_id) => {
+                                    ClientActorMessage::CreatePredicted(local_key, prediction_id)
+                                }
+                                None => ClientActorMessage::Create(local_key),
+                            };
+                            self.queued_incoming_messages.push_back(message);
                         }
                         _ => {}
                     }
                 }
                 1 => {
                     // Deletion
-                    let local_key = reader.read_u16();
-                    self.local_actor_store.remove(&local_key);
-                    interpolator.delete_interpolation(&local_key);
-
-                    if self.pawn_store.contains_key(&local_key) {
-                        self.pawn_store.remove(&local_key);
-                        self.pawn_history.remove(&local_key);
-                        command_receiver.pawn_cleanup(&local_key);
-                        interpolator.delete_pawn_interpolation(&local_key);
+                    let local_key = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    let reason = DespawnReason::read(reader);
+                    if self.is_current_generation(&local_key, generation) {
+                        self.delete_actor(local_key, reason, command_receiver, interpolator);
                     }
-
-                    self.queued_incoming_messages
-                        .push_back(ClientActorMessage::Delete(local_key));
                 }
                 2 => {
                     // Update Actor
-                    let local_key = reader.read_u16();
+                    let local_key = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    let state_mask: StateMask = StateMask::read(reader);
+                    let payload = match read_payload(reader) {
+                        Some(payload) => payload,
+                        None => return,
+                    };
 
+                    if !self.is_current_generation(&local_key, generation)
+                        || !self.is_newest_update(&local_key, packet_tick)
+                    {
+                        continue;
+                    }
+                    if let Some(held_updates) = self.resyncing.get_mut(&local_key) {
+                        // held back until the Actor's whole state arrives, so
+                        // it isn't applied onto a state missing lost changes
+                        held_updates.push((packet_index, state_mask, payload.into()));
+                        continue;
+                    }
                     if let Some(actor_ref) = self.local_actor_store.get_mut(&local_key) {
                         // Actor is not a Pawn
-                        let state_mask: StateMask = StateMask::read(reader);
-
-                        actor_ref.read_partial(&state_mask, reader, packet_index);
+                        actor_ref.read_partial(
+                            &state_mask,
+                            &mut PacketReader::new(payload),
+                            packet_index,
+                        );
 
                         self.queued_incoming_messages
-                            .push_back(ClientActorMessage::Update(local_key));
+                            .push_back(ClientActorMessage::Update(local_key, packet_tick));
                     }
                 }
                 3 => {
                     // Assign Pawn
-                    let local_key: u16 = reader.read_u16();
+                    let local_key: u16 = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    if !self.is_current_generation(&local_key, generation) {
+                        continue;
+                    }
 
+                    // Pawns are always updated whole, so need no resync
+                    self.release_held_updates(&local_key);
                     if let Some(actor_ref) = self.local_actor_store.get_mut(&local_key) {
                         self.pawn_store.insert(
                             local_key,
@@ -121,10 +210,15 @@ impl<U: ActorType> ClientActorManager<U> {
                 }
                 4 => {
                     // Unassign Pawn
-                    let local_key: u16 = reader.read_u16();
+                    let local_key: u16 = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    if !self.is_current_generation(&local_key, generation) {
+                        continue;
+                    }
                     if self.pawn_store.contains_key(&local_key) {
                         self.pawn_store.remove(&local_key);
                         self.pawn_history.remove(&local_key);
+                        self.remove_pawn_correction(&local_key);
                         command_receiver.pawn_cleanup(&local_key);
                         interpolator.delete_pawn_interpolation(&local_key);
                     }
@@ -133,10 +227,20 @@ impl<U: ActorType> ClientActorManager<U> {
                 }
                 5 => {
                     // Update Pawn
-                    let local_key = reader.read_u16();
+                    let local_key = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    let payload = match read_payload(reader) {
+                        Some(payload) => payload,
+                        None => return,
+                    };
 
+                    if !self.is_current_generation(&local_key, generation)
+                        || !self.is_newest_update(&local_key, packet_tick)
+                    {
+                        continue;
+                    }
                     if let Some(actor_ref) = self.local_actor_store.get_mut(&local_key) {
-                        actor_ref.read_full(reader, packet_index);
+                        actor_ref.read_full(&mut PacketReader::new(payload), packet_index);
 
                         // check it against it's history
                         if let Some(pawn_history) = self.pawn_history.get_mut(&local_key) {
@@ -154,7 +258,39 @@ impl<U: ActorType> ClientActorManager<U> {
                         command_receiver.remove_history_until(packet_tick, local_key);
 
                         self.queued_incoming_messages
-                            .push_back(ClientActorMessage::Update(local_key));
+                            .push_back(ClientActorMessage::Update(local_key, packet_tick));
+                    }
+                }
+                9 => {
+                    // Resync Actor, with its whole state
+                    let local_key = byte_order::read_u16(reader);
+                    let generation = reader.read_u8();
+                    let payload = match read_payload(reader) {
+                        Some(payload) => payload,
+                        None => return,
+                    };
+
+                    if !self.is_current_generation(&local_key, generation) {
+                        continue;
+                    }
+                    // updates from before the whole state was written are
+                    // discarded from now on
+                    self.is_newest_update(&local_key, packet_tick);
+                    if let Some(actor_ref) = self.local_actor_store.get_mut(&local_key) {
+                        actor_ref.read_full(&mut PacketReader::new(payload), packet_index);
+                    }
+                    // the updates held back are applied on top, each Property
+                    // only taking those newer than the state it was given
+                    self.release_held_updates(&local_key);
+                    self.queued_incoming_messages
+                        .push_back(ClientActorMessage::Update(local_key, packet_tick));
+                }
+                6 => {
+                    // World Sync Complete
+                    if !self.world_sync_complete {
+                        self.world_sync_complete = true;
+                        self.queued_incoming_messages
+                            .push_back(ClientActorMessage::WorldSyncComplete);
                     }
                 }
                 _ => {}
@@ -162,6 +298,112 @@ impl<U: ActorType> ClientActorManager<U> {
         }
     }
 
+    /// Holds back updates to every Actor but Pawns, until the whole state of
+    /// each has been resent by the Server, after a long run of packets from
+    /// it was missed
+    pub fn begin_resync(&mut self) {
+        for local_key in self.local_actor_store.keys() {
+            if !self.pawn_store.contains_key(local_key) {
+                self.resyncing.entry(*local_key).or_default();
+            }
+        }
+    }
+
+    /// Returns whether any Actor is waiting for its whole state to be resent
+    pub fn is_resyncing(&self) -> bool {
+        return !self.resyncing.is_empty();
+    }
+
+    /// Gets the LocalActorKey & ActorGeneration of every Actor waiting for its
+    /// whole state to be resent
+    pub fn get_resyncing_actors(&self) -> Vec<(LocalActorKey, ActorGeneration)> {
+        let mut actors: Vec<(LocalActorKey, ActorGeneration)> = self
+            .resyncing
+            .keys()
+            .filter_map(|local_key| {
+                self.generations
+                    .get(local_key)
+                    .map(|generation| (*local_key, *generation))
+            })
+            .collect();
+        actors.sort();
+        return actors;
+    }
+
+    // stops holding back updates to an Actor, applying those held so far
+    fn release_held_updates(&mut self, local_key: &LocalActorKey) {
+        let held_updates = match self.resyncing.remove(local_key) {
+            Some(held_updates) => held_updates,
+            None => return,
+        };
+        if let Some(actor_ref) = self.local_actor_store.get_mut(local_key) {
+            for (packet_index, state_mask, payload) in held_updates.iter() {
+                actor_ref.read_partial(state_mask, &mut PacketReader::new(payload), *packet_index);
+            }
+        }
+    }
+
+    // returns whether an update to an Actor is from the tick of the newest
+    // one it has been given, or a later one, recording the tick if so
+    fn is_newest_update(&mut self, key: &LocalActorKey, tick: u16) -> bool {
+        if let Some(last_tick) = self.update_ticks.get(key) {
+            if sequence_less_than(tick, *last_tick) {
+                return false;
+            }
+        }
+        self.update_ticks.insert(*key, tick);
+        return true;
+    }
+
+    // returns whether a creation is for a newer generation of the key than the
+    // one it was last created for, if it has been created before
+    fn is_new_generation(&self, key: &LocalActorKey, generation: ActorGeneration) -> bool {
+        match self.generations.get(key) {
+            Some(current_generation) => {
+                return generation_greater_than(generation, *current_generation)
+            }
+            None => return true,
+        }
+    }
+
+    // returns whether a message is for the Actor currently using the key
+    fn is_current_generation(&self, key: &LocalActorKey, generation: ActorGeneration) -> bool {
+        return self.local_actor_store.contains_key(key)
+            && self.generations.get(key) == Some(&generation);
+    }
+
+    fn delete_actor<T: EventType>(
+        &mut self,
+        local_key: LocalActorKey,
+        reason: DespawnReason,
+        command_receiver: &mut CommandReceiver<T>,
+        interpolator: &mut InterpolationManager<U>,
+    ) {
+        let removed_actor = self.local_actor_store.remove(&local_key);
+        self.resyncing.remove(&local_key);
+        self.update_ticks.remove(&local_key);
+        strict_assert!(
+            removed_actor.is_some(),
+            "Actor {} from the Server was deleted before it was created",
+            local_key
+        );
+        if let Some(net_id) = self.local_key_to_net_id_map.remove(&local_key) {
+            self.net_id_to_local_key_map.remove(&net_id);
+        }
+        interpolator.delete_interpolation(&local_key);
+
+        if self.pawn_store.contains_key(&local_key) {
+            self.pawn_store.remove(&local_key);
+            self.pawn_history.remove(&local_key);
+            self.remove_pawn_correction(&local_key);
+            command_receiver.pawn_cleanup(&local_key);
+            interpolator.delete_pawn_interpolation(&local_key);
+        }
+
+        self.queued_incoming_messages
+            .push_back(ClientActorMessage::Delete(local_key, reason));
+    }
+
     pub fn pop_incoming_message(&mut self) -> Option<ClientActorMessage> {
         return self.queued_incoming_messages.pop_front();
     }
@@ -170,10 +412,21 @@ impl<U: ActorType> ClientActorManager<U> {
         return self.local_actor_store.keys();
     }
 
+    pub fn actors_iter(&self) -> Iter<LocalActorKey, U> {
+        return self.local_actor_store.iter();
+    }
+
     pub fn get_actor(&self, key: &LocalActorKey) -> Option<&U> {
         return self.local_actor_store.get(key);
     }
 
+    pub fn resolve_actor_ref(&self, actor_ref: &ActorRef) -> Option<LocalActorKey> {
+        if let Some(net_id) = actor_ref.get_net_id() {
+            return self.net_id_to_local_key_map.get(&net_id).copied();
+        }
+        return None;
+    }
+
     pub fn pawn_keys(&self) -> Keys<LocalActorKey, U> {
         return self.pawn_store.keys();
     }
@@ -184,7 +437,14 @@ impl<U: ActorType> ClientActorManager<U> {
 
     pub fn pawn_reset(&mut self, key: &LocalActorKey) {
         if let Some(actor_ref) = self.local_actor_store.get_mut(key) {
-            self.pawn_store.remove(key);
+            if let Some(predicted_pawn) = self.pawn_store.remove(key) {
+                // corrections made before the last was reported add up, so the
+                // state predicted before the first of them is kept
+                if !self.unreported_corrections.contains(key) {
+                    self.pawn_corrections.insert(*key, predicted_pawn);
+                    self.unreported_corrections.push_back(*key);
+                }
+            }
             self.pawn_store.insert(
                 *key,
                 actor_ref.inner_ref().as_ref().borrow().get_typed_copy(),
@@ -192,6 +452,20 @@ impl<U: ActorType> ClientActorManager<U> {
         }
     }
 
+    pub fn pop_pawn_correction(&mut self) -> Option<LocalActorKey> {
+        return self.unreported_corrections.pop_front();
+    }
+
+    pub fn get_pawn_before_correction(&self, key: &LocalActorKey) -> Option<&U> {
+        return self.pawn_corrections.get(key);
+    }
+
+    fn remove_pawn_correction(&mut self, key: &LocalActorKey) {
+        self.pawn_corrections.remove(key);
+        self.unreported_corrections
+            .retain(|corrected_key| corrected_key != key);
+    }
+
     pub fn pawn_clear_history(&mut self, key: &LocalActorKey) {
         if let Some(pawn_history) = self.pawn_history.get_mut(&key) {
             pawn_history.clear();
@@ -209,3 +483,20 @@ impl<U: ActorType> ClientActorManager<U> {
         }
     }
 }
+
+// reads an Actor's payload, written after its size, or returns None if the size
+// can't be read, in which case nothing after it in the packet can be either
+fn read_payload<'s>(reader: &mut PacketReader<'s>) -> Option<&'s [u8]> {
+    let buffer = reader.get_buffer();
+    let size = match varint::read_from(reader, varint::read_u16) {
+        Ok(size) => size as usize,
+        Err(_) => {
+            reader.get_cursor().set_position(buffer.len() as u64);
+            return None;
+        }
+    };
+    let start = (reader.get_cursor().position() as usize).min(buffer.len());
+    let end = (start + size).min(buffer.len());
+    reader.get_cursor().set_position(end as u64);
+    return Some(&buffer[start..end]);
+}
@@ -1,10 +1,12 @@
-use naia_shared::LocalActorKey;
+use naia_shared::{DespawnReason, LocalActorKey, PredictionId};
 
 #[derive(Debug, Clone)]
 pub enum ClientActorMessage {
     Create(LocalActorKey),
-    Update(LocalActorKey),
-    Delete(LocalActorKey),
+    CreatePredicted(LocalActorKey, PredictionId),
+    Update(LocalActorKey, u16),
+    Delete(LocalActorKey, DespawnReason),
     AssignPawn(LocalActorKey),
     UnassignPawn(LocalActorKey),
+    WorldSyncComplete,
 }
@@ -1,10 +1,24 @@
-use std::{default::Default, time::Duration};
+use std::{default::Default, sync::Arc, time::Duration};
+
+use naia_shared::{Clock, PacketPadding, SystemClock};
+
+use super::{
+    command_config::CommandConfig, flush_mode::FlushMode, reconnect_policy::ReconnectPolicy,
+};
 
 /// Contains Config properties which will be used by a Server or Client
 #[derive(Clone, Debug)]
 pub struct ClientConfig {
     /// The duration between the resend of certain connection handshake messages
     pub send_handshake_interval: Duration,
+    /// The factor by which the handshake resend interval grows after each
+    /// attempt which goes unanswered
+    pub handshake_backoff_multiplier: f32,
+    /// The longest the handshake resend interval is allowed to grow to
+    pub max_send_handshake_interval: Duration,
+    /// The number of times each handshake message is sent before giving up on
+    /// connecting to the Server
+    pub max_handshake_attempts: u16,
     /// The duration to wait for communication from a remote host before
     /// initiating a disconnect
     pub disconnection_timeout_duration: Duration,
@@ -17,6 +31,95 @@ pub struct ClientConfig {
     /// Number of samples to measure RTT & Jitter by. A higher number will
     /// smooth out RTT measurements, but at the cost of responsiveness.
     pub rtt_sample_size: u16,
+    /// Decides when queued Events & Commands are sent to the Server. Defaults
+    /// to sending them on every call to `NaiaClient::receive()`
+    pub flush_mode: FlushMode,
+    /// When coalescing messages, the longest a queued message waits before
+    /// being sent without an explicit call to `NaiaClient::flush()`
+    pub auto_flush_after: Option<Duration>,
+    /// The most Data packets from the Server applied per frame. After a hitch,
+    /// packets beyond the budget stay buffered & are applied over the
+    /// following calls to `NaiaClient::receive()`, rather than all at once.
+    /// Defaults to None, applying every packet which is due
+    pub receive_budget: Option<usize>,
+    /// How packets sent to the Server are padded, to hide their sizes from
+    /// anyone observing the traffic. None, the default, sends packets unpadded
+    pub packet_padding: Option<PacketPadding>,
+    /// The duration between cover heartbeats, which are sent to the Server
+    /// whether or not anything else has been sent. None, the default, only
+    /// sends heartbeats when the connection is otherwise idle
+    pub cover_heartbeat_interval: Option<Duration>,
+    /// The DSCP value to mark packets sent to the Server with, so that networks
+    /// which honour it can prioritize them. Only native IPv4 sockets can be
+    /// marked, through IP_TOS: over IPv6 or in the browser, packets are sent
+    /// unmarked and a warning is logged if this is set. None, the default,
+    /// leaves packets unmarked
+    pub dscp: Option<u8>,
+    /// How long a predicted spawn waits for the Server to spawn the Actor
+    /// before it fails with a PredictionFailed event
+    pub spawn_prediction_timeout: Duration,
+    /// The most bytes per second to send to the Server, for players whose
+    /// upstream bandwidth is scarce. As the budget is used up, Commands are
+    /// sent along with fewer past Commands, rather than holding anything
+    /// reliable back, so the budget may be exceeded. None, the default, sends
+    /// without limit
+    pub max_upload_rate: Option<u32>,
+    /// How many past Commands are sent along with each Command, and whether
+    /// that's sized from the measured packet loss
+    pub command_config: CommandConfig,
+    /// The longest the Client can go between calls to `NaiaClient::receive()`
+    /// before it's taken to have been frozen, such as by a laptop suspending
+    /// or a browser tab being backgrounded. The time frozen doesn't count
+    /// towards the disconnection timeout, and the Client's estimates of RTT &
+    /// the Server's tick are restarted afterwards, rather than being skewed by
+    /// the gap
+    pub clock_gap_threshold: Duration,
+    /// How the Client reconnects to the Server by itself, once its connection
+    /// has dropped. None, the default, starts a new handshake straight away,
+    /// giving up once it has run out of attempts
+    pub reconnect: Option<ReconnectPolicy>,
+    /// The most packets of Commands sent while resuming a dropped connection,
+    /// once the Server has answered the challenge, which are attached to the
+    /// handshake as early data, for the Server to release as soon as it
+    /// resumes the connection, rather than a round trip later. The oldest are
+    /// dropped past this, or past `early_data_max_bytes`. 0, the default,
+    /// sends no early data, and Commands sent while reconnecting are refused
+    pub early_data_max_packets: usize,
+    /// The most bytes of Commands, across every packet, attached to the
+    /// handshake as early data, which is to be no more than the Server's own
+    /// limit for all of it to be released
+    pub early_data_max_bytes: usize,
+    /// How long an Event of a type set to be actor-ordered, with
+    /// `Manifest::set_event_actor_ordered()`, waits for the Actors it
+    /// references to come into scope, before it's received anyway as an
+    /// UnresolvedEvent
+    pub actor_ordered_event_timeout: Duration,
+    /// Whether a panic while reading an Event from the Server, such as over
+    /// a payload shorter than its type expects, is caught & the Event skipped
+    /// over as a SkippedFrame, rather than taking the Client down. True by
+    /// default
+    pub catch_decode_panics: bool,
+    /// How many malformed Events the Server can send before the Client
+    /// disconnects from it. None, the default, never disconnects over
+    /// malformed Events
+    pub malformed_frame_limit: Option<u32>,
+    /// The longest run of packets from the Server the Client can miss before
+    /// it asks for the whole state of its Actors to be resent, as the Server
+    /// only learns which of its packets were lost from acknowledgements of the
+    /// 32 packets before the newest received, so changes held by those lost
+    /// further back are never resent. Updates to each Actor are held back
+    /// until its whole state arrives. None never asks for a resync. 32 by
+    /// default
+    pub resync_gap_threshold: Option<u16>,
+    /// How many guaranteed Events & EventGroups sent before the connection is
+    /// established, or while reconnecting, are held to be sent once it is.
+    /// Sending more returns `NaiaError::PreConnectionQueueFull`. 64 by default
+    pub pre_connection_queue_max: usize,
+    /// The Clock every time on the Client & its connection is read from. The
+    /// SystemClock by default, while a ManualClock makes the Client's
+    /// behaviour depend only on the packets it receives & how it's driven, so
+    /// that a recorded session can be replayed exactly
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Default for ClientConfig {
@@ -25,8 +128,30 @@ impl Default for ClientConfig {
             disconnection_timeout_duration: Duration::from_secs(10),
             heartbeat_interval: Duration::from_secs(4),
             send_handshake_interval: Duration::from_secs(1),
+            handshake_backoff_multiplier: 1.5,
+            max_send_handshake_interval: Duration::from_secs(5),
+            max_handshake_attempts: 10,
             ping_interval: Duration::from_secs(1),
             rtt_sample_size: 20,
+            flush_mode: FlushMode::Immediate,
+            auto_flush_after: None,
+            receive_budget: None,
+            packet_padding: None,
+            cover_heartbeat_interval: None,
+            dscp: None,
+            spawn_prediction_timeout: Duration::from_secs(2),
+            max_upload_rate: None,
+            command_config: CommandConfig::default(),
+            clock_gap_threshold: Duration::from_secs(3),
+            reconnect: None,
+            early_data_max_packets: 0,
+            early_data_max_bytes: 512,
+            actor_ordered_event_timeout: Duration::from_secs(1),
+            catch_decode_panics: true,
+            malformed_frame_limit: None,
+            resync_gap_threshold: Some(32),
+            pre_connection_queue_max: 64,
+            clock: Arc::new(SystemClock),
         }
     }
 }
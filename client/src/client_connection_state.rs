@@ -1,6 +0,0 @@
-#[derive(Debug, PartialEq)]
-pub enum ClientConnectionState {
-    AwaitingChallengeResponse,
-    AwaitingConnectResponse,
-    Connected,
-}
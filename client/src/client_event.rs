@@ -1,24 +1,92 @@
-use naia_shared::{EventType, LocalActorKey};
+use naia_shared::{
+    ActorRef, ConnectionInfo, DespawnReason, EventType, LocalActorKey, PredictionId, RawEvent,
+    SkippedFrame,
+};
+
+use super::connection_state::ConnectionState;
 
 /// An Event that is be emitted by the Client, usually as a result of some
 /// communication with the Server
+///
+/// However packets are lost or reordered, events are received in this order:
+/// * Connection, or ConnectionResumed, comes before anything else the
+///   connection gives, such as Events, Ticks & Actors
+/// * CreateActor, or PredictionMatched, comes before every UpdateActor,
+///   AssignPawn, UnassignPawn, ActorEvent & DeleteActor of the Actor. An
+///   Actor deleted before its creation arrived is never received at all
+/// * WorldSyncComplete comes once per connection, after the CreateActor of
+///   every Actor which was in scope for the Client when it connected, so
+///   every Tick after it follows the whole initial world
 #[derive(Debug)]
 pub enum ClientEvent<T: EventType> {
     /// Occurs when the Client has successfully established a connection with
-    /// the Server
-    Connection,
+    /// the Server, given the parameters agreed for the connection. These can
+    /// be read again later with `NaiaClient::connection_info()`
+    Connection(ConnectionInfo),
     /// Occurs when the Client has lost connection with the Server, usually as a
-    /// result of a timeout
+    /// result of a timeout, or once the connection has been closed with
+    /// `NaiaClient::disconnect()`
     Disconnection,
+    /// Occurs when the connection to the Server has dropped, but the Server
+    /// allows it to be resumed. The Client starts a new handshake straight
+    /// away, and is followed by ConnectionResumed if the Server resumes the
+    /// connection, with every Actor & Pawn kept, or by Connection if it
+    /// doesn't, in which case they are gone as after a Disconnection. Actors
+    /// can't be accessed until then
+    ConnectionSuspended,
+    /// Occurs when the Server has resumed the Client's suspended connection
+    ConnectionResumed,
+    /// Occurs when the Client starts another attempt at reconnecting to the
+    /// Server, as set by the ReconnectPolicy in its ClientConfig, given the
+    /// number of the attempt, starting at 1. Each attempt follows a
+    /// Disconnection or ConnectionSuspended
+    Reconnecting(u16),
+    /// Occurs when the Client has given up on reconnecting to the Server,
+    /// having run out of attempts, or been rejected by the Server. The
+    /// connection state is then ConnectFailed
+    ReconnectFailed,
+    /// Occurs when the Server has closed the connection, either because the
+    /// Server is shutting down or the Client has been kicked, given the reason
+    /// the Server gave
+    DisconnectedByServer(String),
     /// An Event emitted to the Client from the Server
     Event(T),
-    /// Occurs when an Actor on the Server has come into scope for the Client
+    /// An Event emitted to the Client from the Server about the Actor with the
+    /// given LocalActorKey, sent with `NaiaServer::queue_actor_event()` to the
+    /// Clients which have the Actor in scope. Only received while the Actor is
+    /// still in scope
+    ActorEvent(LocalActorKey, T),
+    /// An Event emitted to the Client from the Server, of a type set to be
+    /// actor-ordered, which was received while the given Actors it references
+    /// were still out of scope, and timed out waiting for them
+    UnresolvedEvent(T, Vec<ActorRef>),
+    /// An Event emitted to the Client from the Server, of a type set to be
+    /// received raw with `NaiaClient::receive_raw()`
+    RawEvent(RawEvent),
+    /// Occurs when an Event from the Server was skipped over instead of
+    /// received, because it couldn't be read. The Events sent alongside it are
+    /// still received
+    SkippedFrame(SkippedFrame),
+    /// The payload the Server set for the given Server tick, with
+    /// `NaiaServer::set_tick_payload()`. Each is received once, in tick order,
+    /// however many packets of the tick carried it
+    TickPayload(u16, T),
+    /// Occurs when the payloads the Server set for a run of ticks never
+    /// arrived, given the first & last of them, inclusive, ahead of the next
+    /// `ClientEvent::TickPayload` which did
+    TickPayloadGap(u16, u16),
+    /// Occurs when an Actor on the Server has come into scope for the Client,
+    /// after it has been added to `NaiaClient::actors_iter()`
     CreateActor(LocalActorKey),
     /// Occurs when an Actor has had a state change on the Server while in
-    /// scope for the Client
-    UpdateActor(LocalActorKey),
-    /// Occurs when an Actor on the Server has left the Client's scope
-    DeleteActor(LocalActorKey),
+    /// scope for the Client, given the Server tick the change was made on.
+    /// All of the Actor's Properties which changed on that tick have been
+    /// applied by the time this is emitted
+    UpdateActor(LocalActorKey, u16),
+    /// Occurs when an Actor on the Server has left the Client's scope, given
+    /// the reason it was deleted, after it has been removed from
+    /// `NaiaClient::actors_iter()`
+    DeleteActor(LocalActorKey, DespawnReason),
     /// A Tick Event, the duration between Tick events is defined in the Config
     /// object passed to the Client on initialization
     Tick,
@@ -31,4 +99,27 @@ pub enum ClientEvent<T: EventType> {
     /// A Command received which is to be simulated on the Client as well as on
     /// the Server
     Command(LocalActorKey, T),
+    /// Occurs when the Server has corrected a Pawn's predicted state, once
+    /// every Command since the corrected tick has been replayed on it. The
+    /// state it was predicted to have before, which can be read with
+    /// `NaiaClient::get_pawn_before_correction()`, can be compared against
+    /// its state now to smooth the difference out with a CorrectionSmoother
+    PawnCorrected(LocalActorKey),
+    /// Occurs instead of CreateActor when an Actor the Server has spawned for
+    /// a request of the Client comes into scope, and the Client predicted
+    /// its spawn with the given PredictionId. The predicted Actor is dropped,
+    /// and the Actor with the given key, holding the state the Server spawned
+    /// it with, takes its place
+    PredictionMatched(PredictionId, LocalActorKey),
+    /// Occurs when the Server hasn't spawned an Actor for a predicted spawn
+    /// within `ClientConfig::spawn_prediction_timeout`, and the predicted
+    /// Actor is dropped. Should the Server spawn it later after all, it comes
+    /// into scope as any other Actor, with CreateActor
+    PredictionFailed(PredictionId),
+    /// Occurs once after connecting, when every Actor that was in scope for
+    /// the Client at the time of connection has been received
+    WorldSyncComplete,
+    /// Occurs whenever the state of the Client's connection to the Server
+    /// changes, including each new handshake attempt
+    ConnectionStateChanged(ConnectionState),
 }
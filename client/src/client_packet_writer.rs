@@ -1,14 +1,12 @@
-use byteorder::{BigEndian, WriteBytesExt};
+use byteorder::WriteBytesExt;
 
 use naia_shared::{
-    wrapping_diff, ActorType, Event, EventPacketWriter, EventType, LocalActorKey, ManagerType,
-    Manifest, MTU_SIZE,
+    byte_order, wrapping_diff, ActorType, Event, EventPacketWriter, EventType, Instant,
+    LocalActorKey, ManagerType, Manifest, OutgoingEvent, SchemaVersions, SubTick, MTU_SIZE,
 };
 
 use super::command_receiver::CommandReceiver;
 
-const MAX_PAST_COMMANDS: u8 = 2;
-
 /// Handles writing of Event & Actor data into an outgoing packet
 pub struct ClientPacketWriter {
     command_working_bytes: Vec<u8>,
@@ -27,6 +25,12 @@ impl ClientPacketWriter {
         }
     }
 
+    /// Sets whether the trace id of each Event is written ahead of its frame,
+    /// once both hosts have agreed to message tracing
+    pub fn set_writes_trace_ids(&mut self, enabled: bool) {
+        self.event_writer.set_writes_trace_ids(enabled);
+    }
+
     /// Returns whether the writer has bytes to write into the outgoing packet
     pub fn has_bytes(&self) -> bool {
         return self.command_count != 0 || self.event_writer.has_bytes();
@@ -56,36 +60,61 @@ impl ClientPacketWriter {
     }
 
     /// Writes a Command into the Writer's internal buffer, which will
-    /// eventually be put into the outgoing packet
+    /// eventually be put into the outgoing packet, as the schema version of its
+    /// type agreed on for the connection, along with up to the given number of
+    /// past Commands. Each is preceded by the SubTick it was issued at, if the
+    /// Manifest is set to send them. A Command whose type has not been
+    /// registered in the Manifest is dropped
     pub fn write_command<T: EventType, U: ActorType>(
         &mut self,
         host_tick: u16,
         manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
         command_receiver: &CommandReceiver<T>,
+        max_past_commands: u8,
         pawn_key: LocalActorKey,
         command: &Box<dyn Event<T>>,
+        sub_tick: SubTick,
     ) -> bool {
+        let type_id = command.as_ref().get_type_id();
+        let naia_id = match manifest.get_event_naia_id(&type_id) {
+            Ok(naia_id) => naia_id,
+            Err(_) => return true,
+        };
+        let version = schema_versions.get_event_version(naia_id);
+        let writes_sub_ticks = manifest.has_command_sub_ticks();
+
         //Write command payload
         let mut command_payload_bytes = Vec::<u8>::new();
 
-        command.as_ref().write(&mut command_payload_bytes);
+        if writes_sub_ticks {
+            sub_tick.write(&mut command_payload_bytes);
+        }
+        write_command_payload(command.as_ref(), version, &mut command_payload_bytes);
 
         // write past commands
         let past_commands_number = command_receiver
             .command_history_count(pawn_key)
-            .min(MAX_PAST_COMMANDS);
+            .min(max_past_commands);
         let mut past_command_index: u8 = 0;
 
         if let Some(mut iter) = command_receiver.command_history_iter(pawn_key, true) {
             while past_command_index < past_commands_number {
-                if let Some((past_tick, past_command)) = iter.next() {
+                if let Some((past_tick, (past_command, past_sub_tick))) = iter.next() {
                     // get tick diff between commands
                     let diff_i8: i16 = wrapping_diff(past_tick, host_tick);
                     if diff_i8 > 0 && diff_i8 <= 255 {
                         // write the tick diff
                         command_payload_bytes.write_u8(diff_i8 as u8).unwrap();
+                        if writes_sub_ticks {
+                            past_sub_tick.write(&mut command_payload_bytes);
+                        }
                         // write the command payload
-                        past_command.write(&mut command_payload_bytes);
+                        write_command_payload(
+                            past_command.as_ref().as_ref(),
+                            version,
+                            &mut command_payload_bytes,
+                        );
 
                         past_command_index += 1;
                     }
@@ -98,12 +127,8 @@ impl ClientPacketWriter {
         //Write command "header"
         let mut command_total_bytes = Vec::<u8>::new();
 
-        let type_id = command.as_ref().get_type_id();
-        command_total_bytes
-            .write_u16::<BigEndian>(pawn_key)
-            .unwrap(); // write pawn key
-        let naia_id = manifest.get_event_naia_id(&type_id); // get naia id
-        command_total_bytes.write_u16::<BigEndian>(naia_id).unwrap(); // write naia id
+        byte_order::write_u16(&mut command_total_bytes, pawn_key); // write pawn key
+        byte_order::write_u16(&mut command_total_bytes, naia_id); // write naia id
         command_total_bytes.write_u8(past_command_index).unwrap(); // write past command number
         command_total_bytes.append(&mut command_payload_bytes); // write payload
 
@@ -121,12 +146,30 @@ impl ClientPacketWriter {
     }
 
     /// Writes an Event into the Writer's internal buffer, which will eventually
-    /// be put into the outgoing packet
+    /// be put into the outgoing packet, as the schema version of its type
+    /// agreed on for the connection, given the current time
     pub fn write_event<T: EventType, U: ActorType>(
         &mut self,
         manifest: &Manifest<T, U>,
-        event: &Box<dyn Event<T>>,
+        schema_versions: &SchemaVersions,
+        event: &OutgoingEvent<T>,
+        now: &Instant,
     ) -> bool {
-        return self.event_writer.write_event(manifest, event);
+        return self
+            .event_writer
+            .write_event(manifest, schema_versions, event, now);
+    }
+}
+
+// writes a Command as the given schema version of its type, or whole if the
+// Server has the same version of it
+fn write_command_payload<T: EventType>(
+    command: &dyn Event<T>,
+    version: Option<u16>,
+    out_bytes: &mut Vec<u8>,
+) {
+    match version {
+        Some(version) => command.write_versioned(version, out_bytes),
+        None => command.write(out_bytes),
     }
 }
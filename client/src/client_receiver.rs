@@ -0,0 +1,32 @@
+use naia_shared::{ActorType, EventType, NaiaError};
+
+use super::{client_event::ClientEvent, naia_client::NaiaClient};
+
+/// Receives events from the Server, as `NaiaClient::receive()` does, while
+/// the ClientSender it was split from the NaiaClient with, by
+/// `NaiaClient::split()`, queues Events in reply. Iterating over it receives
+/// every event available until `receive()` returns None
+#[derive(Debug)]
+pub struct ClientReceiver<'c, T: EventType, U: ActorType> {
+    client: &'c mut NaiaClient<T, U>,
+}
+
+impl<'c, T: EventType, U: ActorType> ClientReceiver<'c, T, U> {
+    pub(crate) fn new(client: &'c mut NaiaClient<T, U>) -> Self {
+        ClientReceiver { client }
+    }
+
+    /// Retrieves the next incoming event, exactly as `NaiaClient::receive()`
+    /// does
+    pub fn receive(&mut self) -> Option<Result<ClientEvent<T>, NaiaError>> {
+        return self.client.receive();
+    }
+}
+
+impl<'c, T: EventType, U: ActorType> Iterator for ClientReceiver<'c, T, U> {
+    type Item = Result<ClientEvent<T>, NaiaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.receive();
+    }
+}
@@ -0,0 +1,38 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use naia_shared::{ChannelIndex, Event, EventType};
+
+// Events queued through ClientSenders, along with the channel they were sent
+// on, if not that registered for their type
+pub(crate) type SplitEventQueue<T> =
+    Rc<RefCell<VecDeque<(Box<dyn Event<T>>, Option<ChannelIndex>)>>>;
+
+/// Queues Events to be sent to the Server, while the ClientReceiver it was
+/// split from the NaiaClient with, by `NaiaClient::split()`, is in use. The
+/// Events are handed to the NaiaClient on its next call to `receive()`, in
+/// the order they were queued, which returns any Error `send_event()` would
+/// have instead. Can be cloned, and kept after the ClientReceiver is dropped
+#[derive(Clone, Debug)]
+pub struct ClientSender<T: EventType> {
+    queue: SplitEventQueue<T>,
+}
+
+impl<T: EventType> ClientSender<T> {
+    pub(crate) fn new(queue: SplitEventQueue<T>) -> Self {
+        ClientSender { queue }
+    }
+
+    /// Queues up an Event to be sent to the Server, on the ordered channel
+    /// registered for its type in the Manifest, if any
+    pub fn send_event(&self, event: &impl Event<T>) {
+        self.queue.borrow_mut().push_back((event.clone_box(), None));
+    }
+
+    /// Queues up an Event to be sent to the Server, on a specific ordered
+    /// channel
+    pub fn send_event_on_channel(&self, channel_index: ChannelIndex, event: &impl Event<T>) {
+        self.queue
+            .borrow_mut()
+            .push_back((event.clone_box(), Some(channel_index)));
+    }
+}
@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use naia_shared::{wrapping_diff, Instant};
+use naia_shared::{utils::duration_between, wrapping_diff, Instant};
 
 /// Manages the current tick for the host
 #[derive(Debug)]
@@ -15,11 +15,13 @@ pub struct ClientTickManager {
     pub fraction: f32,
     accumulator: f32,
     has_ticked: bool,
+    reanchoring: bool,
 }
 
 impl ClientTickManager {
-    /// Create a new HostTickManager with a given tick interval duration
-    pub fn new(tick_interval: Duration) -> Self {
+    /// Create a new HostTickManager with a given tick interval duration,
+    /// given the current time
+    pub fn new(tick_interval: Duration, now: &Instant) -> Self {
         ClientTickManager {
             tick_interval,
             tick_interval_f32: tick_interval.as_nanos() as f32 / 1000000000.0,
@@ -27,21 +29,23 @@ impl ClientTickManager {
             client_tick_adjust: 0,
             server_tick_adjust: 0,
             server_tick_running_diff: 0,
-            last_tick_instant: Instant::now(),
+            last_tick_instant: now.clone(),
             accumulator: 0.0,
             fraction: 0.0,
             has_ticked: false,
+            reanchoring: false,
         }
     }
 
-    pub fn mark_frame(&mut self) -> bool {
+    pub fn mark_frame(&mut self, now: &Instant) -> bool {
         let mut ticked = false;
-        let mut frame_time = self.last_tick_instant.elapsed().as_nanos() as f32 / 1000000000.0;
+        let mut frame_time =
+            duration_between(&self.last_tick_instant, now).as_nanos() as f32 / 1000000000.0;
         if frame_time > 0.25 {
             frame_time = 0.25;
         }
         self.accumulator += frame_time;
-        self.last_tick_instant = Instant::now();
+        self.last_tick_instant = now.clone();
         if self.accumulator >= self.tick_interval_f32 {
             while self.accumulator >= self.tick_interval_f32 {
                 self.accumulator -= self.tick_interval_f32;
@@ -72,6 +76,12 @@ impl ClientTickManager {
         self.client_tick_adjust = ((3000 / (self.tick_interval.as_millis())) + 1) as u16;
     }
 
+    /// After a clock gap, adopts the next Server tick received outright,
+    /// rather than drifting towards it
+    pub fn reanchor(&mut self) {
+        self.reanchoring = true;
+    }
+
     /// Using information from the Server and RTT/Jitter measurements, determine
     /// the appropriate future intended tick
     pub fn record_server_tick(
@@ -91,7 +101,8 @@ impl ClientTickManager {
         }
 
         // If the server tick is far off enough, reset to the received server tick
-        if self.server_tick_running_diff.abs() > 8 {
+        if self.reanchoring || self.server_tick_running_diff.abs() > 8 {
+            self.reanchoring = false;
             self.server_tick = server_tick;
             self.server_tick_running_diff = 0;
         }
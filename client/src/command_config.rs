@@ -0,0 +1,25 @@
+/// Decides how many past Commands are sent along with each Command, so that
+/// a Command reaches the Server in time for its tick even if the packets it
+/// was first sent in are lost
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommandConfig {
+    /// The fewest past Commands sent along with each Command
+    pub min_redundancy: u8,
+    /// The most past Commands sent along with each Command
+    pub max_redundancy: u8,
+    /// The share of Commands the Server may miss, used to size the number of
+    /// past Commands sent from the measured packet loss & jitter, within the
+    /// bounds above. None, the default, always sends `max_redundancy` past
+    /// Commands, unless the upload limit is being reached
+    pub target_miss_rate: Option<f32>,
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        Self {
+            min_redundancy: 0,
+            max_redundancy: 2,
+            target_miss_rate: None,
+        }
+    }
+}
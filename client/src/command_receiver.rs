@@ -4,7 +4,9 @@ use std::{
 };
 
 use crate::{client_actor_manager::ClientActorManager, naia_client::LocalActorKey};
-use naia_shared::{wrapping_diff, ActorType, Event, EventType, SequenceBuffer, SequenceIterator};
+use naia_shared::{
+    wrapping_diff, ActorType, Event, EventType, SequenceBuffer, SequenceIterator, SubTick,
+};
 
 const COMMAND_HISTORY_SIZE: u16 = 64;
 
@@ -12,7 +14,7 @@ const COMMAND_HISTORY_SIZE: u16 = 64;
 #[derive(Debug)]
 pub struct CommandReceiver<T: EventType> {
     queued_incoming_commands: VecDeque<(u16, LocalActorKey, Rc<Box<dyn Event<T>>>)>,
-    command_history: HashMap<LocalActorKey, SequenceBuffer<Rc<Box<dyn Event<T>>>>>,
+    command_history: HashMap<LocalActorKey, SequenceBuffer<(Rc<Box<dyn Event<T>>>, SubTick)>>,
     queued_command_replays: VecDeque<(u16, LocalActorKey, Rc<Box<dyn Event<T>>>)>,
     replay_trigger: HashMap<LocalActorKey, u16>,
 }
@@ -52,7 +54,7 @@ impl<T: EventType> CommandReceiver<T> {
 
                 let current_tick = command_buffer.sequence_num();
                 for tick in *history_tick..=current_tick {
-                    if let Some(command) = command_buffer.get_mut(tick) {
+                    if let Some((command, _)) = command_buffer.get_mut(tick) {
                         self.queued_command_replays
                             .push_back((tick, *pawn_key, command.clone()));
                     }
@@ -65,18 +67,20 @@ impl<T: EventType> CommandReceiver<T> {
         self.queued_command_replays.pop_front()
     }
 
-    /// Queues an Command to be ran locally on the Client
+    /// Queues an Command to be ran locally on the Client, keeping it in the
+    /// command history along with the SubTick it was issued at
     pub fn queue_command(
         &mut self,
         host_tick: u16,
         pawn_key: LocalActorKey,
         command: &Rc<Box<dyn Event<T>>>,
+        sub_tick: SubTick,
     ) {
         self.queued_incoming_commands
             .push_back((host_tick, pawn_key, command.clone()));
 
         if let Some(command_buffer) = self.command_history.get_mut(&pawn_key) {
-            command_buffer.insert(host_tick, command.clone());
+            command_buffer.insert(host_tick, (command.clone(), sub_tick));
         }
     }
 
@@ -88,12 +92,13 @@ impl<T: EventType> CommandReceiver<T> {
         return 0;
     }
 
-    /// Get an iterator of Commands in the command history for a given Pawn
+    /// Get an iterator of Commands in the command history for a given Pawn,
+    /// along with the SubTicks they were issued at
     pub fn command_history_iter(
         &self,
         pawn_key: LocalActorKey,
         reverse: bool,
-    ) -> Option<SequenceIterator<Rc<Box<dyn Event<T>>>>> {
+    ) -> Option<SequenceIterator<(Rc<Box<dyn Event<T>>>, SubTick)>> {
         if let Some(command_buffer) = self.command_history.get(&pawn_key) {
             return Some(command_buffer.iter(reverse));
         }
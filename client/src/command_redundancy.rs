@@ -0,0 +1,235 @@
+use std::{collections::VecDeque, time::Duration};
+
+use super::command_config::CommandConfig;
+
+// the number of the Server's most recent reports the miss rate is measured over
+const MISS_REPORT_WINDOW: usize = 10;
+
+/// Sizes the number of past Commands sent along with each Command, from the
+/// measured packet loss & jitter, and keeps track of the share of Commands the
+/// Server reports having missed
+#[derive(Debug)]
+pub struct CommandRedundancy {
+    config: CommandConfig,
+    tick_millis: f32,
+    redundancy: u8,
+    // the number of Commands received & missed in each of the Server's most
+    // recent reports, oldest first
+    miss_reports: VecDeque<(u16, u16)>,
+}
+
+impl CommandRedundancy {
+    /// Create a new CommandRedundancy, given the CommandConfig & the interval
+    /// between ticks, which each carry a Command
+    pub fn new(config: CommandConfig, tick_interval: Duration) -> Self {
+        let max_redundancy = config.max_redundancy.max(config.min_redundancy);
+        CommandRedundancy {
+            config,
+            tick_millis: tick_interval.as_secs_f32() * 1000.0,
+            redundancy: max_redundancy,
+            miss_reports: VecDeque::new(),
+        }
+    }
+
+    /// Sizes the redundancy again from the fraction of recently sent packets
+    /// which have been lost, and the jitter in milliseconds, if a target miss
+    /// rate has been set
+    pub fn update(&mut self, packet_loss: f32, jitter_millis: f32) {
+        if let Some(target_miss_rate) = self.config.target_miss_rate {
+            let jitter_ticks = if self.tick_millis > 0.0 {
+                (jitter_millis / self.tick_millis).ceil() as u32
+            } else {
+                1
+            };
+            self.redundancy = size_redundancy(
+                packet_loss,
+                jitter_ticks,
+                target_miss_rate,
+                self.get_min_redundancy(),
+                self.get_max_redundancy(),
+            );
+        }
+    }
+
+    /// Gets the number of past Commands to send along with each Command
+    pub fn get_redundancy(&self) -> u8 {
+        return self.redundancy;
+    }
+
+    /// Gets the fewest past Commands to send along with each Command
+    pub fn get_min_redundancy(&self) -> u8 {
+        return self.config.min_redundancy;
+    }
+
+    /// Gets the most past Commands to send along with each Command
+    pub fn get_max_redundancy(&self) -> u8 {
+        return self.config.max_redundancy.max(self.config.min_redundancy);
+    }
+
+    /// Records a report from the Server of how many Commands it received in
+    /// time for their tick, and how many it missed
+    pub fn record_miss_report(&mut self, received_count: u16, missed_count: u16) {
+        if self.miss_reports.len() >= MISS_REPORT_WINDOW {
+            self.miss_reports.pop_front();
+        }
+        self.miss_reports.push_back((received_count, missed_count));
+    }
+
+    /// Gets the share of Commands the Server has reported missing recently, or
+    /// None if it hasn't reported on any
+    pub fn get_miss_rate(&self) -> Option<f32> {
+        let mut total_count: u32 = 0;
+        let mut missed_count: u32 = 0;
+        for (received, missed) in self.miss_reports.iter() {
+            total_count += u32::from(*received) + u32::from(*missed);
+            missed_count += u32::from(*missed);
+        }
+        if total_count == 0 {
+            return None;
+        }
+        return Some(missed_count as f32 / total_count as f32);
+    }
+}
+
+// Gets the fewest past Commands to send along with each Command for the chance
+// of a Command & every copy of it being lost to stay under the target miss
+// rate. Each Command is sent in a packet per tick, and copies sent within the
+// jitter of each other tend to be delayed or lost together, so they only
+// count as a single chance of getting through
+fn size_redundancy(
+    packet_loss: f32,
+    jitter_ticks: u32,
+    target_miss_rate: f32,
+    min_redundancy: u8,
+    max_redundancy: u8,
+) -> u8 {
+    if packet_loss <= 0.0 {
+        return min_redundancy;
+    }
+    if packet_loss >= 1.0 || target_miss_rate <= 0.0 {
+        return max_redundancy;
+    }
+    let chances = (target_miss_rate.ln() / packet_loss.ln()).ceil().max(1.0);
+    let copies = chances * jitter_ticks.max(1) as f32;
+    let redundancy = (copies - 1.0).min(f32::from(max_redundancy)) as u8;
+    return redundancy.max(min_redundancy);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, time::Duration};
+
+    use super::{size_redundancy, CommandRedundancy};
+    use crate::CommandConfig;
+
+    const TARGET_MISS_RATE: f32 = 0.001;
+    // the number of packets loss is measured over, as by the connection
+    const LOSS_WINDOW: usize = 64;
+
+    // sends a Command every tick over a link losing the given share of
+    // packets, sizing redundancy from the loss measured over the most recent
+    // packets, and gets the share of Commands of which every copy was lost,
+    // along with the redundancy used most
+    fn simulate(packet_loss: f32, tick_count: usize) -> (f32, u8) {
+        let mut redundancy = CommandRedundancy::new(
+            CommandConfig {
+                min_redundancy: 0,
+                max_redundancy: 16,
+                target_miss_rate: Some(TARGET_MISS_RATE),
+            },
+            Duration::from_millis(50),
+        );
+        let mut recent_lost: VecDeque<bool> = VecDeque::new();
+        let mut recent_lost_count = 0;
+        // whether each of the Commands still being sent has got through
+        let mut pending: VecDeque<bool> = VecDeque::new();
+        let mut missed_count = 0;
+        let mut highest_redundancy = 0;
+        // a fixed xorshift sequence, so that the outcome doesn't vary by run
+        let mut state: u32 = 0x9e37_79b9;
+        for _ in 0..tick_count {
+            let measured_loss = if recent_lost.is_empty() {
+                0.0
+            } else {
+                recent_lost_count as f32 / recent_lost.len() as f32
+            };
+            redundancy.update(measured_loss, 0.0);
+            let copies = usize::from(redundancy.get_redundancy()) + 1;
+            highest_redundancy = highest_redundancy.max(redundancy.get_redundancy());
+
+            pending.push_back(false);
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let lost = (state as f32 / u32::MAX as f32) < packet_loss;
+            let carried = pending.len().min(copies);
+            let start = pending.len() - carried;
+            for delivered in pending.iter_mut().skip(start) {
+                *delivered |= !lost;
+            }
+            // Commands no longer carried have either got through or not
+            while pending.len() > copies {
+                if !pending.pop_front().unwrap() {
+                    missed_count += 1;
+                }
+            }
+
+            recent_lost.push_back(lost);
+            if lost {
+                recent_lost_count += 1;
+            }
+            if recent_lost.len() > LOSS_WINDOW && recent_lost.pop_front().unwrap() {
+                recent_lost_count -= 1;
+            }
+        }
+        return (missed_count as f32 / tick_count as f32, highest_redundancy);
+    }
+
+    #[test]
+    fn redundancy_is_sized_to_the_target_miss_rate() {
+        // no redundancy is needed without loss
+        assert_eq!(size_redundancy(0.0, 1, TARGET_MISS_RATE, 0, 8), 0);
+        assert_eq!(size_redundancy(0.0, 1, TARGET_MISS_RATE, 1, 8), 1);
+        // 0.1^3 is just at the target, which 0.1^2 isn't
+        assert_eq!(size_redundancy(0.1, 1, TARGET_MISS_RATE, 0, 8), 2);
+        assert_eq!(size_redundancy(0.3, 1, TARGET_MISS_RATE, 0, 8), 5);
+        assert_eq!(size_redundancy(0.3, 1, TARGET_MISS_RATE, 0, 3), 3);
+        // copies sent within two ticks of each other count as one chance
+        assert_eq!(size_redundancy(0.1, 2, TARGET_MISS_RATE, 0, 8), 5);
+        assert_eq!(size_redundancy(1.0, 1, TARGET_MISS_RATE, 0, 8), 8);
+    }
+
+    #[test]
+    fn miss_rate_stays_near_the_target_across_loss_rates() {
+        let (miss_rate, highest_redundancy) = simulate(0.0, 10_000);
+        assert_eq!(miss_rate, 0.0);
+        assert_eq!(highest_redundancy, 0);
+
+        for packet_loss in [0.02, 0.05, 0.1, 0.2, 0.3].iter() {
+            let (miss_rate, _) = simulate(*packet_loss, 500_000);
+            // the loss measured over a short window runs below the true loss
+            // at times, so the target is only met on average
+            assert!(
+                miss_rate < TARGET_MISS_RATE * 2.0,
+                "{} missed at {} loss",
+                miss_rate,
+                packet_loss
+            );
+        }
+    }
+
+    #[test]
+    fn miss_rate_is_measured_over_recent_reports() {
+        let mut redundancy = CommandRedundancy::new(CommandConfig::default(), Duration::ZERO);
+        assert_eq!(redundancy.get_miss_rate(), None);
+        redundancy.record_miss_report(98, 2);
+        assert_eq!(redundancy.get_miss_rate(), Some(0.02));
+        for _ in 0..10 {
+            redundancy.record_miss_report(100, 0);
+        }
+        assert_eq!(redundancy.get_miss_rate(), Some(0.0));
+        // without a target, every past Command allowed is sent
+        redundancy.update(0.5, 0.0);
+        assert_eq!(redundancy.get_redundancy(), 2);
+    }
+}
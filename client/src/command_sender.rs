@@ -1,12 +1,12 @@
 use std::{collections::HashMap, rc::Rc};
 
 use crate::naia_client::LocalActorKey;
-use naia_shared::{Event, EventClone, EventType};
+use naia_shared::{Event, EventClone, EventType, SubTick};
 
 /// Handles outgoing Commands
 #[derive(Debug)]
 pub struct CommandSender<T: EventType> {
-    queued_outgoing_command: HashMap<LocalActorKey, Rc<Box<dyn Event<T>>>>,
+    queued_outgoing_command: HashMap<LocalActorKey, (Rc<Box<dyn Event<T>>>, SubTick)>,
 }
 
 impl<T: EventType> CommandSender<T> {
@@ -22,16 +22,17 @@ impl<T: EventType> CommandSender<T> {
         self.queued_outgoing_command.len() != 0
     }
 
-    /// Gets the next queued Command to be transmitted
-    pub fn pop_command(&mut self) -> Option<(LocalActorKey, Rc<Box<dyn Event<T>>>)> {
+    /// Gets the next queued Command to be transmitted, along with the SubTick
+    /// it was issued at
+    pub fn pop_command(&mut self) -> Option<(LocalActorKey, Rc<Box<dyn Event<T>>>, SubTick)> {
         let mut out_key = None;
         if let Some((key, _)) = self.queued_outgoing_command.iter().next() {
             out_key = Some(*key);
         }
 
         if let Some(key) = out_key {
-            if let Some(command) = self.queued_outgoing_command.remove(&key) {
-                return Some((key, command));
+            if let Some((command, sub_tick)) = self.queued_outgoing_command.remove(&key) {
+                return Some((key, command, sub_tick));
             }
         }
         return None;
@@ -39,16 +40,27 @@ impl<T: EventType> CommandSender<T> {
 
     /// If  the last popped Command from the queue somehow wasn't able to be
     /// written into a packet, put the Command back into the front of the queue
-    pub fn unpop_command(&mut self, pawn_key: LocalActorKey, command: &Rc<Box<dyn Event<T>>>) {
+    pub fn unpop_command(
+        &mut self,
+        pawn_key: LocalActorKey,
+        command: &Rc<Box<dyn Event<T>>>,
+        sub_tick: SubTick,
+    ) {
         let cloned_command = command.clone();
         self.queued_outgoing_command
-            .insert(pawn_key, cloned_command);
+            .insert(pawn_key, (cloned_command, sub_tick));
     }
 
-    /// Queues an Command to be transmitted to the remote host
-    pub fn queue_command(&mut self, pawn_key: LocalActorKey, command: &impl Event<T>) {
+    /// Queues an Command to be transmitted to the remote host, along with the
+    /// SubTick it was issued at
+    pub fn queue_command(
+        &mut self,
+        pawn_key: LocalActorKey,
+        command: &impl Event<T>,
+        sub_tick: SubTick,
+    ) {
         let cloned_command = Rc::new(EventClone::clone_box(command));
         self.queued_outgoing_command
-            .insert(pawn_key, cloned_command);
+            .insert(pawn_key, (cloned_command, sub_tick));
     }
 }
@@ -0,0 +1,12 @@
+use naia_shared::RejectReason;
+
+/// The reason the Client gave up on connecting to the Server
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectFailReason {
+    /// The Server did not respond to any of the handshake attempts
+    Timeout,
+    /// The Server rejected the last handshake attempt
+    Rejected(RejectReason),
+    /// Reconnecting was cancelled with `NaiaClient::cancel_reconnect()`
+    Cancelled,
+}
@@ -0,0 +1,33 @@
+use super::connect_fail_reason::ConnectFailReason;
+
+/// The state of the Client's connection to the Server
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectionState {
+    /// Not connected to the Server. The Client will begin connecting again on
+    /// the next call to `receive()`, or once the wait before its next attempt
+    /// has passed when reconnecting with a ReconnectPolicy, unless it was
+    /// closed with `NaiaClient::disconnect()`
+    Disconnected,
+    /// Waiting for the Server to respond to a challenge request, given the
+    /// number of the current attempt
+    Connecting {
+        /// The number of the current attempt, starting at 1
+        attempt: u16,
+    },
+    /// The challenge has been answered, and the Client is waiting for the
+    /// Server to accept the connection, given the number of the current
+    /// attempt
+    Handshaking {
+        /// The number of the current attempt, starting at 1
+        attempt: u16,
+    },
+    /// Connected to the Server
+    Connected,
+    /// Closing the connection after `NaiaClient::disconnect()`, once the
+    /// guaranteed Events sent before it was called have been delivered, or
+    /// the Server has stopped answering. Nothing more can be sent
+    Disconnecting,
+    /// The Client has given up on connecting to the Server. This state is
+    /// terminal, a new Client must be created to try again
+    ConnectFailed(ConnectFailReason),
+}
@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use naia_shared::{utils::duration_between, Instant, InterpLerpable};
+
+// How sharply an exponential correction falls off. The remaining error is
+// scaled so that it still reaches zero at the end of the duration
+const EXPONENTIAL_RATE: f32 = 5.0;
+
+/// The shape of the curve a CorrectionSmoother's visual offset decays along
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SmoothingCurve {
+    /// The offset shrinks by the same amount every millisecond
+    Linear,
+    /// Most of the offset is gone early on, with the rest easing out towards
+    /// the end of the duration
+    Exponential,
+}
+
+/// Blends the error out of a predicted Property when the Server corrects it,
+/// rather than letting it snap to the corrected value. Each correction adds
+/// the difference between the predicted & corrected values to a visual
+/// offset, which decays to zero over the configured duration. Corrections
+/// which would leave the offset larger than the snap threshold, such as
+/// teleports, clear it instead, so that they take effect immediately
+#[derive(Clone, Debug)]
+pub struct CorrectionSmoother {
+    duration: Duration,
+    curve: SmoothingCurve,
+    snap_threshold: f32,
+    offset: f32,
+    corrected_at: Option<Instant>,
+}
+
+impl CorrectionSmoother {
+    /// Create a new CorrectionSmoother, which decays each correction along the
+    /// given curve over the given duration, and snaps past the given threshold
+    pub fn new(duration: Duration, curve: SmoothingCurve, snap_threshold: f32) -> Self {
+        CorrectionSmoother {
+            duration,
+            curve,
+            snap_threshold,
+            offset: 0.0,
+            corrected_at: None,
+        }
+    }
+
+    /// Records a correction of a Property from the value it was predicted to
+    /// have to the value the Server corrected it to, at the given time. Any
+    /// offset which hasn't decayed yet is carried over
+    pub fn correct<T: InterpLerpable>(&mut self, predicted: &T, corrected: &T, now: &Instant) {
+        let offset = self.visual_offset(now) + predicted.to_f32() - corrected.to_f32();
+        if offset.abs() > self.snap_threshold {
+            self.offset = 0.0;
+            self.corrected_at = None;
+            return;
+        }
+        self.offset = offset;
+        self.corrected_at = Some(now.clone());
+    }
+
+    /// Gets the offset to add to the corrected value of the Property at the
+    /// given time, for it to be drawn where the prediction left off
+    pub fn visual_offset(&self, now: &Instant) -> f32 {
+        let corrected_at = match &self.corrected_at {
+            Some(corrected_at) => corrected_at,
+            None => return 0.0,
+        };
+        let elapsed = duration_between(corrected_at, now);
+        if elapsed >= self.duration {
+            return 0.0;
+        }
+        let fraction = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        let remaining = match self.curve {
+            SmoothingCurve::Linear => 1.0 - fraction,
+            SmoothingCurve::Exponential => {
+                let floor = (-EXPONENTIAL_RATE).exp();
+                ((-EXPONENTIAL_RATE * fraction).exp() - floor) / (1.0 - floor)
+            }
+        };
+        return self.offset * remaining;
+    }
+
+    /// Gets the value to draw a Property at, given its corrected value, at the
+    /// given time
+    pub fn smooth<T: InterpLerpable>(&self, corrected: &T, now: &Instant) -> T {
+        return T::from_f32(corrected.to_f32() + self.visual_offset(now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::{CorrectionSmoother, SmoothingCurve};
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn linear_offset_decays_over_duration() {
+        let mut now = SystemClock.now();
+        let mut smoother =
+            CorrectionSmoother::new(Duration::from_millis(200), SmoothingCurve::Linear, 50.0);
+        assert_close(smoother.visual_offset(&now), 0.0);
+
+        smoother.correct(&10.0f32, &6.0f32, &now);
+        assert_close(smoother.visual_offset(&now), 4.0);
+        assert_close(smoother.smooth(&6.0f32, &now), 10.0);
+
+        now.add_millis(50);
+        assert_close(smoother.visual_offset(&now), 3.0);
+        now.add_millis(50);
+        assert_close(smoother.visual_offset(&now), 2.0);
+        assert_close(smoother.smooth(&6.0f32, &now), 8.0);
+
+        // a second correction adds to what's left of the first
+        smoother.correct(&6.0f32, &7.0f32, &now);
+        assert_close(smoother.visual_offset(&now), 1.0);
+        now.add_millis(199);
+        assert!(smoother.visual_offset(&now) > 0.0);
+        now.add_millis(1);
+        assert_close(smoother.visual_offset(&now), 0.0);
+        now.add_millis(1000);
+        assert_close(smoother.visual_offset(&now), 0.0);
+    }
+
+    #[test]
+    fn exponential_offset_decays_faster_early_on() {
+        let mut now = SystemClock.now();
+        let mut linear =
+            CorrectionSmoother::new(Duration::from_millis(100), SmoothingCurve::Linear, 50.0);
+        let mut exponential = CorrectionSmoother::new(
+            Duration::from_millis(100),
+            SmoothingCurve::Exponential,
+            50.0,
+        );
+        linear.correct(&0.0f32, &-10.0f32, &now);
+        exponential.correct(&0.0f32, &-10.0f32, &now);
+        assert_close(exponential.visual_offset(&now), 10.0);
+
+        let mut last_offset = exponential.visual_offset(&now);
+        for _ in 0..9 {
+            now.add_millis(10);
+            let offset = exponential.visual_offset(&now);
+            assert!(offset < last_offset);
+            assert!(offset < linear.visual_offset(&now));
+            last_offset = offset;
+        }
+        now.add_millis(10);
+        assert_close(exponential.visual_offset(&now), 0.0);
+    }
+
+    #[test]
+    fn large_corrections_snap() {
+        let mut now = SystemClock.now();
+        let mut smoother =
+            CorrectionSmoother::new(Duration::from_millis(200), SmoothingCurve::Linear, 5.0);
+
+        smoother.correct(&0u16, &5u16, &now);
+        assert_close(smoother.visual_offset(&now), -5.0);
+
+        // a teleport takes effect straight away, & clears the earlier offset
+        now.add_millis(20);
+        smoother.correct(&5u16, &100u16, &now);
+        assert_close(smoother.visual_offset(&now), 0.0);
+        assert_eq!(smoother.smooth(&100u16, &now), 100);
+
+        // as does a correction which only crosses the threshold added to
+        // what's left of the last one
+        smoother.correct(&100u16, &97u16, &now);
+        assert_close(smoother.visual_offset(&now), 3.0);
+        smoother.correct(&97u16, &94u16, &now);
+        assert_close(smoother.visual_offset(&now), 0.0);
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use naia_shared::{EventType, LocalActorKey};
+
+use super::client_event::ClientEvent;
+
+/// Holds back the events the Client produces until they can be received in
+/// the order ClientEvent promises: nothing of a connection before its
+/// Connection or ConnectionResumed, nothing about an Actor before its
+/// CreateActor or PredictionMatched, and a single WorldSyncComplete, after
+/// every creation of the initial world sync
+#[derive(Debug)]
+pub struct EventOrder<T: EventType> {
+    ready: VecDeque<ClientEvent<T>>,
+    connected: bool,
+    // events of a connection produced before its Connection was
+    held_until_connected: VecDeque<ClientEvent<T>>,
+    // the Actors whose creation has been received
+    created: HashSet<LocalActorKey>,
+    // events about an Actor produced before its creation was
+    held_until_created: HashMap<LocalActorKey, VecDeque<ClientEvent<T>>>,
+    world_synced: bool,
+}
+
+impl<T: EventType> EventOrder<T> {
+    pub fn new() -> Self {
+        EventOrder {
+            ready: VecDeque::new(),
+            connected: false,
+            held_until_connected: VecDeque::new(),
+            created: HashSet::new(),
+            held_until_created: HashMap::new(),
+            world_synced: false,
+        }
+    }
+
+    /// Adds an event the Client has produced, which is either ready to be
+    /// received straight away, or held until the events it must follow are
+    pub fn push(&mut self, event: ClientEvent<T>) {
+        match event {
+            ClientEvent::Connection(_) => {
+                // a new connection starts with an empty world
+                self.created.clear();
+                self.held_until_created.clear();
+                self.world_synced = false;
+                self.connected = true;
+                self.ready.push_back(event);
+                self.release_held_until_connected();
+            }
+            ClientEvent::ConnectionResumed => {
+                // the world is kept as it was
+                self.connected = true;
+                self.ready.push_back(event);
+                self.release_held_until_connected();
+            }
+            ClientEvent::ConnectionSuspended => {
+                self.connected = false;
+                self.ready.push_back(event);
+            }
+            ClientEvent::Disconnection
+            | ClientEvent::DisconnectedByServer(_)
+            | ClientEvent::ReconnectFailed => {
+                // whatever is held belongs to the connection which is gone
+                self.connected = false;
+                self.held_until_connected.clear();
+                self.created.clear();
+                self.held_until_created.clear();
+                self.ready.push_back(event);
+            }
+            ClientEvent::Reconnecting(_) | ClientEvent::ConnectionStateChanged(_) => {
+                self.ready.push_back(event);
+            }
+            event if !self.connected => {
+                self.held_until_connected.push_back(event);
+            }
+            ClientEvent::CreateActor(local_key) | ClientEvent::PredictionMatched(_, local_key) => {
+                self.created.insert(local_key);
+                self.ready.push_back(event);
+                if let Some(held_events) = self.held_until_created.remove(&local_key) {
+                    for held_event in held_events {
+                        self.push(held_event);
+                    }
+                }
+            }
+            ClientEvent::DeleteActor(local_key, _) => {
+                if self.created.remove(&local_key) {
+                    self.ready.push_back(event);
+                } else {
+                    // the Actor was never received, so neither is anything
+                    // about it
+                    self.held_until_created.remove(&local_key);
+                }
+            }
+            ClientEvent::UpdateActor(local_key, _)
+            | ClientEvent::AssignPawn(local_key)
+            | ClientEvent::UnassignPawn(local_key)
+            | ClientEvent::ActorEvent(local_key, _) => {
+                if self.created.contains(&local_key) {
+                    self.ready.push_back(event);
+                } else {
+                    self.held_until_created
+                        .entry(local_key)
+                        .or_default()
+                        .push_back(event);
+                }
+            }
+            ClientEvent::WorldSyncComplete => {
+                if !self.world_synced {
+                    self.world_synced = true;
+                    self.ready.push_back(event);
+                }
+            }
+            event => {
+                self.ready.push_back(event);
+            }
+        }
+    }
+
+    /// Gets the next event which is ready to be received
+    pub fn pop(&mut self) -> Option<ClientEvent<T>> {
+        return self.ready.pop_front();
+    }
+
+    fn release_held_until_connected(&mut self) {
+        let held_events: Vec<ClientEvent<T>> = self.held_until_connected.drain(..).collect();
+        for held_event in held_events {
+            self.push(held_event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::TypeId, collections::HashSet, time::Duration};
+
+    use naia_shared::{ConnectionInfo, DespawnReason, EventType, LocalActorKey};
+
+    use super::EventOrder;
+    use crate::client_event::ClientEvent;
+
+    #[derive(Clone, Debug)]
+    struct Ping;
+
+    impl EventType for Ping {
+        fn write(&self, _buffer: &mut Vec<u8>) {}
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<Ping>();
+        }
+    }
+
+    fn connection() -> ClientEvent<Ping> {
+        return ClientEvent::Connection(ConnectionInfo {
+            compression_enabled: false,
+            message_tracing_enabled: false,
+            mtu: 1200,
+            tick_interval: Duration::from_millis(50),
+            resumable: false,
+        });
+    }
+
+    // whether an event is one a connection produces, rather than one about the
+    // connection itself
+    fn is_of_connection(event: &ClientEvent<Ping>) -> bool {
+        match event {
+            ClientEvent::Connection(_)
+            | ClientEvent::ConnectionResumed
+            | ClientEvent::ConnectionSuspended
+            | ClientEvent::Disconnection
+            | ClientEvent::DisconnectedByServer(_)
+            | ClientEvent::ReconnectFailed
+            | ClientEvent::Reconnecting(_)
+            | ClientEvent::ConnectionStateChanged(_) => return false,
+            _ => return true,
+        }
+    }
+
+    fn drain(order: &mut EventOrder<Ping>) -> Vec<ClientEvent<Ping>> {
+        let mut events = Vec::new();
+        while let Some(event) = order.pop() {
+            events.push(event);
+        }
+        return events;
+    }
+
+    #[test]
+    fn events_of_a_connection_wait_for_it() {
+        let mut order = EventOrder::new();
+        order.push(ClientEvent::CreateActor(1));
+        order.push(ClientEvent::Tick);
+        assert!(order.pop().is_none());
+
+        order.push(connection());
+        let events = drain(&mut order);
+        assert!(matches!(events[0], ClientEvent::Connection(_)));
+        assert!(matches!(events[1], ClientEvent::CreateActor(1)));
+        assert!(matches!(events[2], ClientEvent::Tick));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn actor_events_wait_for_the_creation() {
+        let mut order = EventOrder::new();
+        order.push(connection());
+        order.push(ClientEvent::UpdateActor(4, 10));
+        order.push(ClientEvent::ActorEvent(4, Ping));
+        order.push(ClientEvent::Tick);
+        order.push(ClientEvent::CreateActor(4));
+        let events = drain(&mut order);
+        assert!(matches!(events[1], ClientEvent::Tick));
+        assert!(matches!(events[2], ClientEvent::CreateActor(4)));
+        assert!(matches!(events[3], ClientEvent::UpdateActor(4, 10)));
+        assert!(matches!(events[4], ClientEvent::ActorEvent(4, _)));
+
+        // an Actor deleted before it was created is never received at all
+        order.push(ClientEvent::UpdateActor(5, 11));
+        order.push(ClientEvent::DeleteActor(5, DespawnReason::Destroyed));
+        order.push(ClientEvent::CreateActor(5));
+        let events = drain(&mut order);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ClientEvent::CreateActor(5)));
+    }
+
+    #[test]
+    fn world_sync_completes_once_per_connection() {
+        let mut order = EventOrder::new();
+        order.push(connection());
+        order.push(ClientEvent::WorldSyncComplete);
+        order.push(ClientEvent::WorldSyncComplete);
+        assert_eq!(drain(&mut order).len(), 2);
+
+        // a resumed connection keeps its world, a new one syncs again
+        order.push(ClientEvent::ConnectionSuspended);
+        order.push(ClientEvent::ConnectionResumed);
+        order.push(ClientEvent::WorldSyncComplete);
+        assert_eq!(drain(&mut order).len(), 2);
+        order.push(ClientEvent::Disconnection);
+        order.push(connection());
+        order.push(ClientEvent::WorldSyncComplete);
+        assert_eq!(drain(&mut order).len(), 3);
+    }
+
+    // Checks the order ClientEvent promises against every event received,
+    // given the keys of the Actors in the initial world sync
+    fn assert_contract(events: &[ClientEvent<Ping>], initial_keys: &HashSet<LocalActorKey>) {
+        let mut connected = false;
+        let mut created: HashSet<LocalActorKey> = HashSet::new();
+        let mut initial_created: HashSet<LocalActorKey> = HashSet::new();
+        let mut world_synced = false;
+        for event in events {
+            if is_of_connection(event) {
+                assert!(connected, "{:?} before Connection", event);
+            }
+            match event {
+                ClientEvent::Connection(_) => connected = true,
+                ClientEvent::CreateActor(local_key) => {
+                    assert!(created.insert(*local_key), "created twice");
+                    if initial_keys.contains(local_key) {
+                        initial_created.insert(*local_key);
+                    }
+                }
+                ClientEvent::DeleteActor(local_key, _) => {
+                    assert!(created.remove(local_key), "deleted before created");
+                }
+                ClientEvent::UpdateActor(local_key, _) | ClientEvent::ActorEvent(local_key, _) => {
+                    assert!(created.contains(local_key), "updated before created");
+                }
+                ClientEvent::WorldSyncComplete => {
+                    assert!(!world_synced, "world sync completed twice");
+                    assert_eq!(&initial_created, initial_keys);
+                    world_synced = true;
+                }
+                _ => {}
+            }
+        }
+        assert!(world_synced);
+    }
+
+    #[test]
+    fn randomized_interleavings_keep_the_contract() {
+        let mut seed: u32 = 0x2545_f491;
+        let mut random = move |range: u32| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            return seed % range;
+        };
+
+        for _run in 0..5000 {
+            // each Actor of the initial world sync is created, then updated &
+            // sent events about, the messages of different Actors arriving
+            // interleaved, and those about an Actor overtaking its creation
+            let actor_count = 1 + random(6) as u16;
+            let initial_keys: HashSet<LocalActorKey> = (0..actor_count).collect();
+            let mut pending: Vec<Vec<ClientEvent<Ping>>> = (0..actor_count)
+                .map(|local_key| {
+                    let mut events = vec![ClientEvent::CreateActor(local_key)];
+                    for tick in 0..random(4) as u16 {
+                        events.push(ClientEvent::UpdateActor(local_key, tick));
+                    }
+                    if random(2) == 0 {
+                        events.push(ClientEvent::ActorEvent(local_key, Ping));
+                    }
+                    if random(4) == 0 {
+                        events.push(ClientEvent::DeleteActor(
+                            local_key,
+                            DespawnReason::Destroyed,
+                        ));
+                    }
+                    let overtaking = random(events.len() as u32) as usize;
+                    events.swap(0, overtaking);
+                    return events;
+                })
+                .collect();
+
+            let mut order = EventOrder::new();
+            let mut connect_after = Some(random(4));
+            let mut produced = 0;
+            let mut received = Vec::new();
+            while pending.iter().any(|events| !events.is_empty()) {
+                if connect_after == Some(produced) {
+                    order.push(connection());
+                    connect_after = None;
+                }
+                let actor = random(pending.len() as u32) as usize;
+                if pending[actor].is_empty() {
+                    continue;
+                }
+                order.push(pending[actor].remove(0));
+                if random(3) == 0 {
+                    order.push(ClientEvent::Tick);
+                }
+                produced += 1;
+                received.append(&mut drain(&mut order));
+            }
+            if connect_after.is_some() {
+                order.push(connection());
+            }
+            order.push(ClientEvent::WorldSyncComplete);
+            received.append(&mut drain(&mut order));
+
+            assert_contract(&received, &initial_keys);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+/// Decides when Events & Commands queued on the Client are sent to the Server
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FlushMode {
+    /// Queued messages are sent on every call to `NaiaClient::receive()`
+    Immediate,
+    /// Queued messages are held until `NaiaClient::flush()` is called, enough
+    /// have been queued to fill a packet, or `auto_flush_after` has elapsed
+    /// since the first of them was queued. Fewer, fuller packets are sent
+    Coalesce,
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use naia_shared::Instant;
+
+/// The result of polling a HandshakeRetry
+#[derive(Debug, PartialEq)]
+pub enum HandshakeRetryStatus {
+    /// Not yet time to send another handshake message
+    Wait,
+    /// A handshake message should be sent now, given the number of the attempt
+    Send(u16),
+    /// Every attempt has been used up without the handshake progressing
+    Exhausted,
+}
+
+/// Decides when the current handshake message should be resent, backing off
+/// between attempts, and when to give up
+#[derive(Debug)]
+pub struct HandshakeRetry {
+    initial_interval: Duration,
+    max_interval: Duration,
+    backoff_multiplier: f32,
+    max_attempts: u16,
+    attempt: u16,
+    current_interval: Duration,
+    next_send: Option<Instant>,
+}
+
+impl HandshakeRetry {
+    /// Create a new HandshakeRetry, which will send the first attempt as soon
+    /// as it is polled
+    pub fn new(
+        initial_interval: Duration,
+        max_interval: Duration,
+        backoff_multiplier: f32,
+        max_attempts: u16,
+    ) -> Self {
+        HandshakeRetry {
+            initial_interval,
+            max_interval,
+            backoff_multiplier,
+            max_attempts,
+            attempt: 0,
+            current_interval: initial_interval,
+            next_send: None,
+        }
+    }
+
+    /// Start counting attempts over, should be called whenever the handshake
+    /// moves on to a new stage
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+        self.current_interval = self.initial_interval;
+        self.next_send = None;
+    }
+
+    /// Checks whether a handshake message should be sent at the given time
+    pub fn poll(&mut self, now: &Instant) -> HandshakeRetryStatus {
+        if let Some(next_send) = &self.next_send {
+            if now < next_send {
+                return HandshakeRetryStatus::Wait;
+            }
+            if self.attempt >= self.max_attempts {
+                return HandshakeRetryStatus::Exhausted;
+            }
+            self.current_interval = self
+                .current_interval
+                .mul_f32(self.backoff_multiplier)
+                .min(self.max_interval);
+        }
+
+        self.attempt += 1;
+        let mut next_send = now.clone();
+        next_send.add_millis(self.current_interval.as_millis() as u32);
+        self.next_send = Some(next_send);
+        return HandshakeRetryStatus::Send(self.attempt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::{HandshakeRetry, HandshakeRetryStatus};
+
+    #[test]
+    fn backs_off_until_exhausted() {
+        let mut now = SystemClock.now();
+        let mut retry = HandshakeRetry::new(
+            Duration::from_millis(100),
+            Duration::from_millis(300),
+            2.0,
+            4,
+        );
+
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(1));
+        now.add_millis(99);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Wait);
+        now.add_millis(1);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(2));
+
+        // interval doubles
+        now.add_millis(199);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Wait);
+        now.add_millis(1);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(3));
+
+        // interval is capped
+        now.add_millis(300);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(4));
+
+        now.add_millis(299);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Wait);
+        now.add_millis(1);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Exhausted);
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let mut now = SystemClock.now();
+        let mut retry =
+            HandshakeRetry::new(Duration::from_millis(100), Duration::from_secs(1), 2.0, 2);
+
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(1));
+        now.add_millis(100);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(2));
+
+        retry.reset();
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(1));
+        now.add_millis(100);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Send(2));
+        now.add_millis(200);
+        assert_eq!(retry.poll(&now), HandshakeRetryStatus::Exhausted);
+    }
+}
@@ -1,6 +1,18 @@
 //! # Naia Client
 //! A cross-platform client that can send/receive events to/from a server, and
 //! has a pool of in-scope actors that are synced with the server.
+//!
+//! A NaiaClient holds a single connection. To connect to several Servers at
+//! once (for example a gameplay Server and a chat Server), create one
+//! NaiaClient per Server: each owns its own socket, Manifest, connection state
+//! and diagnostics, so instances are independent of each other. The exception
+//! is the miniquad target, where the underlying socket is backed by global
+//! state and only one NaiaClient may exist at a time.
+//!
+//! Optional subsystems are behind features, so that builds which don't need
+//! them, such as for the web, are kept small: "link-conditioner", on by
+//! default, for simulating network conditions, "compression" for payload
+//! compression, and "wasm-transport" for the wasm-bindgen WebRTC socket.
 
 #![deny(
     missing_docs,
@@ -12,25 +24,59 @@
     unused_import_braces
 )]
 
+mod actor_ordered_events;
 mod client_actor_manager;
 mod client_actor_message;
 mod client_config;
-mod client_connection_state;
 mod client_event;
 mod client_packet_writer;
+mod client_receiver;
+mod client_sender;
 mod client_tick_manager;
+mod command_config;
 mod command_receiver;
+mod command_redundancy;
 mod command_sender;
-mod error;
+mod connect_fail_reason;
+mod connection_state;
+mod correction_smoother;
+mod event_order;
+mod flush_mode;
+mod handshake_retry;
 mod interpolation_manager;
 mod naia_client;
-mod ping_manager;
+mod packet_coalescer;
+mod packet_sender;
+mod pre_connection_queue;
+mod predicted_spawns;
+mod reconnect_policy;
 mod server_connection;
+mod server_query;
+mod server_time_estimator;
+mod tick_payload_receiver;
 mod tick_queue;
+#[cfg(not(target_arch = "wasm32"))]
+mod udp_client_socket;
+mod upload_budget;
 
-pub use naia_shared::{find_my_ip_address, Instant, LinkConditionerConfig, Random};
+#[cfg(feature = "link-conditioner")]
+pub use naia_shared::LinkConditionerConfig;
+pub use naia_shared::{
+    find_my_ip_address, ActorRef, ChannelIndex, Clock, ConnectionInfo, DespawnReason, HookAction,
+    Instant, InterpLerpable, ManifestError, ManualClock, MessageReceipt, NaiaError, PacketPadding,
+    PredictionId, Random, RawEvent, RejectReason, SkippedFrame, SubTick, SystemClock,
+};
 
 pub use client_config::ClientConfig;
 pub use client_event::ClientEvent;
+pub use client_receiver::ClientReceiver;
+pub use client_sender::ClientSender;
+pub use command_config::CommandConfig;
+pub use connect_fail_reason::ConnectFailReason;
+pub use connection_state::ConnectionState;
+pub use correction_smoother::{CorrectionSmoother, SmoothingCurve};
+pub use flush_mode::FlushMode;
 pub use naia_client::NaiaClient;
 pub use naia_client_socket::Packet;
+pub use reconnect_policy::ReconnectPolicy;
+pub use server_query::{QueryStatus, ServerQuery};
@@ -1,22 +1,52 @@
-use std::net::SocketAddr;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    net::{SocketAddr, ToSocketAddrs},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
 
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use cfg_if::cfg_if;
+use log::warn;
 
-use naia_client_socket::{ClientSocket, ClientSocketTrait, MessageSender};
+use naia_client_socket::{ClientSocket, ClientSocketTrait, NaiaClientSocketError};
+use naia_shared::{
+    byte_order,
+    handshake::{self, EarlyData, SessionToken},
+    Clock, HookAction, PacketHook, SchemaVersions,
+};
 
 pub use naia_shared::{
-    ActorType, ConnectionConfig, Event, EventType, HostTickManager, Instant, LocalActorKey,
-    ManagerType, Manifest, PacketReader, PacketType, SequenceIterator, SharedConfig,
-    StandardHeader, Timer, Timestamp,
+    ActorRef, ActorType, ChannelIndex, ConnectionConfig, ConnectionInfo, Event, EventGroup,
+    EventPacketWriter, EventType, HostTickManager, Instant, LocalActorKey, ManagerType, Manifest,
+    MessageReceipt, NaiaError, PacketReader, PacketType, PredictionId, RawEvent, RejectReason,
+    SequenceIterator, SharedConfig, StandardHeader, SubTick, Timestamp,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use super::udp_client_socket::UdpClientSocket;
 use super::{
-    client_actor_message::ClientActorMessage, client_config::ClientConfig,
-    client_event::ClientEvent, client_tick_manager::ClientTickManager, error::NaiaClientError,
-    server_connection::ServerConnection, Packet,
-};
-use crate::client_connection_state::{
-    ClientConnectionState, ClientConnectionState::AwaitingChallengeResponse,
+    client_actor_message::ClientActorMessage,
+    client_config::ClientConfig,
+    client_event::ClientEvent,
+    client_receiver::ClientReceiver,
+    client_sender::{ClientSender, SplitEventQueue},
+    client_tick_manager::ClientTickManager,
+    command_config::CommandConfig,
+    connect_fail_reason::ConnectFailReason,
+    connection_state::ConnectionState,
+    event_order::EventOrder,
+    flush_mode::FlushMode,
+    handshake_retry::{HandshakeRetry, HandshakeRetryStatus},
+    packet_sender::PacketSender,
+    pre_connection_queue::PreConnectionQueue,
+    predicted_spawns::PredictedSpawns,
+    reconnect_policy::{ReconnectPolicy, ReconnectStatus, Reconnection},
+    server_connection::ServerConnection,
+    server_query::ServerQuery,
+    tick_payload_receiver::IncomingTickPayload,
+    Packet,
 };
 
 /// Client can send/receive events to/from a server, and has a pool of in-scope
@@ -27,71 +57,282 @@ pub struct NaiaClient<T: EventType, U: ActorType> {
     server_address: SocketAddr,
     connection_config: ConnectionConfig,
     socket: Box<dyn ClientSocketTrait>,
-    sender: MessageSender,
+    sender: PacketSender,
+    // sockets to the Server's other addresses, raced against the first while
+    // connecting, until the Server answers a challenge through one of them
+    raced_sockets: Vec<(SocketAddr, Box<dyn ClientSocketTrait>)>,
+    incoming_hook: PacketHook,
     server_connection: Option<ServerConnection<T, U>>,
+    suspended_connection: Option<ServerConnection<T, U>>,
+    session_token: Option<SessionToken>,
+    // packets of Commands sent while resuming, attached to the connect request
+    early_data: Vec<EarlyData>,
+    early_data_max_packets: usize,
+    early_data_max_bytes: usize,
     pre_connection_timestamp: Option<Timestamp>,
     pre_connection_digest: Option<Box<[u8]>>,
-    handshake_timer: Timer,
-    connection_state: ClientConnectionState,
+    handshake_retry: HandshakeRetry,
+    reconnect_policy: Option<ReconnectPolicy>,
+    // reconnecting after a dropped connection, as set by the ReconnectPolicy
+    reconnection: Option<Reconnection>,
+    connection_state: ConnectionState,
+    state_changes: VecDeque<ConnectionState>,
+    // the tail of the Events queued when disconnect() was called, which are
+    // delivered before the connection is closed
+    disconnect_tail: Option<u64>,
+    // whether the Client has been closed with disconnect(), after which it
+    // never connects again
+    closed: bool,
+    last_reject_reason: Option<RejectReason>,
     auth_event: Option<T>,
     tick_manager: ClientTickManager,
+    flush_mode: FlushMode,
+    auto_flush_after: Option<Duration>,
+    receive_budget: Option<usize>,
+    max_upload_rate: Option<u32>,
+    command_config: CommandConfig,
+    actor_ordered_event_timeout: Duration,
+    resync_gap_threshold: Option<u16>,
+    predicted_spawns: PredictedSpawns<U>,
+    last_event_receipt: Option<MessageReceipt>,
+    split_events: SplitEventQueue<T>,
+    pre_connection_queue: PreConnectionQueue<T, U>,
+    event_order: EventOrder<T>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T: EventType, U: ActorType> NaiaClient<T, U> {
     /// Create a new client, given the server's address, a shared manifest, an
-    /// optional Config, and an optional Authentication event
+    /// optional Config, and an optional Authentication event. Returns an Error
+    /// if the Authentication event's type has not been registered in the
+    /// Manifest
     pub fn new(
         server_address: SocketAddr,
         manifest: Manifest<T, U>,
         client_config: Option<ClientConfig>,
         shared_config: SharedConfig,
         auth: Option<T>,
-    ) -> Self {
+    ) -> Result<Self, NaiaError> {
+        return Self::new_with_addresses(
+            &[server_address],
+            manifest,
+            client_config,
+            shared_config,
+            auth,
+        );
+    }
+
+    /// Create a new client, given every address the Server can be reached at,
+    /// such as its IPv6 & IPv4 addresses from `resolve()`. The handshake is
+    /// started with all of them at once, IPv6 addresses first, and the
+    /// connection is made through whichever the Server answers first, an
+    /// IPv6 address being kept if both are answered together. Addresses
+    /// which no socket can be opened for, such as IPv6 ones on a host without
+    /// IPv6 support, are skipped. Returns an Error if none are left, or if the
+    /// Authentication event's type has not been registered in the Manifest
+    pub fn new_with_addresses(
+        server_addresses: &[SocketAddr],
+        manifest: Manifest<T, U>,
+        client_config: Option<ClientConfig>,
+        shared_config: SharedConfig,
+        auth: Option<T>,
+    ) -> Result<Self, NaiaError> {
+        if let Some(auth_event) = &auth {
+            manifest.get_event_naia_id(&auth_event.get_type_id())?;
+        }
+
         let client_config = match client_config {
             Some(config) => config,
             None => ClientConfig::default(),
         };
 
-        let connection_config = ConnectionConfig::new(
+        let mut connection_config = ConnectionConfig::new(
             client_config.disconnection_timeout_duration,
             client_config.heartbeat_interval,
             client_config.ping_interval,
             client_config.rtt_sample_size,
+            shared_config.ordered_channel_count,
+            shared_config.tick_interval,
+            client_config.packet_padding,
+            client_config.cover_heartbeat_interval,
         );
+        connection_config.clock_gap_threshold = client_config.clock_gap_threshold;
+        connection_config.catch_decode_panics = client_config.catch_decode_panics;
+        connection_config.malformed_frame_limit = client_config.malformed_frame_limit;
+        connection_config.clock = client_config.clock.clone();
 
-        let mut client_socket = ClientSocket::connect(server_address);
-        if let Some(config) = shared_config.link_condition_config {
-            client_socket = client_socket.with_link_conditioner(&config);
+        // IPv6 addresses are raced ahead of IPv4 ones
+        let mut server_addresses = server_addresses.to_vec();
+        server_addresses.sort_by_key(|address| address.is_ipv4());
+        let mut sockets = Vec::new();
+        for server_address in server_addresses {
+            match open_socket(server_address, client_config.dscp) {
+                Ok(mut socket) => {
+                    #[cfg(feature = "link-conditioner")]
+                    {
+                        if let Some(config) = &shared_config.link_condition_config {
+                            socket = socket.with_link_conditioner(config);
+                        }
+                    }
+                    sockets.push((server_address, socket));
+                }
+                Err(error) => {
+                    warn!(
+                        "can't open a socket to server address {}, skipping it: {}",
+                        server_address, error
+                    );
+                }
+            }
+        }
+        if sockets.is_empty() {
+            return Err(NaiaError::Transport(
+                "no socket could be opened to any of the server's addresses".into(),
+            ));
         }
+        let (server_address, mut client_socket) = sockets.remove(0);
 
-        let mut handshake_timer = Timer::new(client_config.send_handshake_interval);
-        handshake_timer.ring_manual();
-        let message_sender = client_socket.get_sender();
+        let handshake_retry = HandshakeRetry::new(
+            client_config.send_handshake_interval,
+            client_config.max_send_handshake_interval,
+            client_config.handshake_backoff_multiplier,
+            client_config.max_handshake_attempts,
+        );
+        let sender = PacketSender::new(client_socket.get_sender(), server_address);
 
-        NaiaClient {
+        Ok(NaiaClient {
             server_address,
             manifest,
             socket: client_socket,
-            sender: message_sender,
+            sender,
+            raced_sockets: sockets,
+            incoming_hook: PacketHook::default(),
             connection_config,
-            handshake_timer,
+            handshake_retry,
+            reconnect_policy: client_config.reconnect,
+            reconnection: None,
             server_connection: None,
+            suspended_connection: None,
+            session_token: None,
+            early_data: Vec::new(),
+            early_data_max_packets: client_config.early_data_max_packets,
+            early_data_max_bytes: client_config.early_data_max_bytes,
             pre_connection_timestamp: None,
             pre_connection_digest: None,
-            connection_state: AwaitingChallengeResponse,
+            connection_state: ConnectionState::Disconnected,
+            state_changes: VecDeque::new(),
+            disconnect_tail: None,
+            closed: false,
+            last_reject_reason: None,
             auth_event: auth,
-            tick_manager: ClientTickManager::new(shared_config.tick_interval),
-        }
+            tick_manager: ClientTickManager::new(
+                shared_config.tick_interval,
+                &client_config.clock.now(),
+            ),
+            flush_mode: client_config.flush_mode,
+            auto_flush_after: client_config.auto_flush_after,
+            receive_budget: client_config.receive_budget,
+            max_upload_rate: client_config.max_upload_rate,
+            command_config: client_config.command_config,
+            actor_ordered_event_timeout: client_config.actor_ordered_event_timeout,
+            resync_gap_threshold: client_config.resync_gap_threshold,
+            predicted_spawns: PredictedSpawns::new(client_config.spawn_prediction_timeout),
+            last_event_receipt: None,
+            split_events: Rc::new(RefCell::new(VecDeque::new())),
+            pre_connection_queue: PreConnectionQueue::new(client_config.pre_connection_queue_max),
+            event_order: EventOrder::new(),
+            clock: client_config.clock,
+        })
+    }
+
+    /// Registers a hook which is shown every packet sent to the Server, and
+    /// decides whether it goes out. The hook sees the packet as it is on the
+    /// wire, after padding & compression, and can't change it. A dropped
+    /// packet is lost just as if the network had dropped it, so anything
+    /// reliable in it is re-sent
+    pub fn set_outgoing_hook<F: 'static + FnMut(&SocketAddr, &[u8]) -> HookAction>(
+        &mut self,
+        hook: F,
+    ) {
+        self.sender.set_hook(Box::new(hook));
+    }
+
+    /// Registers a hook which is shown every packet received from the Server,
+    /// before the Client reads anything from it, and decides whether it is
+    /// let through. The hook sees the packet as it is on the wire, and can't
+    /// change it. A dropped packet is ignored entirely, just as if the network
+    /// had dropped it
+    pub fn set_incoming_hook<F: 'static + FnMut(&SocketAddr, &[u8]) -> HookAction>(
+        &mut self,
+        hook: F,
+    ) {
+        self.incoming_hook.set(Box::new(hook));
+    }
+
+    /// Resolves the Server's host name & port, such as "example.com:14191",
+    /// to every address it can be reached at, IPv6 addresses first, for
+    /// `new_with_addresses()`. Returns an Error if the name can't be resolved
+    pub fn resolve(host: &str) -> Result<Vec<SocketAddr>, NaiaError> {
+        let mut addresses: Vec<SocketAddr> = host
+            .to_socket_addrs()
+            .map_err(|error| NaiaError::Transport(Box::new(error)))?
+            .collect();
+        addresses.sort_by_key(|address| address.is_ipv4());
+        return Ok(addresses);
+    }
+
+    /// Sends an out-of-band query to the Server at the given address, without
+    /// connecting to it, as a server browser would. Poll the returned
+    /// ServerQuery for the reply. The query is padded, as the Server never
+    /// replies with more bytes than it was sent. Each query uses its own
+    /// socket, so this doesn't disturb any existing connection; on the
+    /// miniquad target, where the socket is backed by global state, queries
+    /// can't be used alongside a NaiaClient
+    pub fn query(server_address: SocketAddr, payload: &[u8], timeout: Duration) -> ServerQuery {
+        return ServerQuery::new(server_address, payload, timeout);
     }
 
     /// Must call this regularly (preferably at the beginning of every draw
     /// frame), in a loop until it returns None.
     /// Retrieves incoming events/updates, and performs updates to maintain the
-    /// connection.
-    pub fn receive(&mut self) -> Option<Result<ClientEvent<T>, NaiaClientError>> {
+    /// connection. Events are received in the order documented on
+    /// ClientEvent, any that arrive early being held until they can be
+    pub fn receive(&mut self) -> Option<Result<ClientEvent<T>, NaiaError>> {
+        loop {
+            if let Some(event) = self.event_order.pop() {
+                return Some(Ok(event));
+            }
+            match self.receive_unordered()? {
+                Ok(event) => self.event_order.push(event),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+
+    // Retrieves the next event as it is produced, which `receive()` puts in
+    // order
+    fn receive_unordered(&mut self) -> Option<Result<ClientEvent<T>, NaiaError>> {
+        // queue the Events sent through ClientSenders
+        if let Err(error) = self.queue_split_events() {
+            return Some(Err(error));
+        }
+
+        // receive connection state changes
+        if let Some(state) = self.state_changes.pop_front() {
+            return Some(Ok(ClientEvent::ConnectionStateChanged(state)));
+        }
+
         // send ticks, handshakes, heartbeats, pings, timeout if need be
         match &mut self.server_connection {
             Some(connection) => {
+                // restart the estimates of time if the Client was frozen
+                if let Some(gap) =
+                    connection.detect_clock_gap(&self.clock.now(), &mut self.tick_manager)
+                {
+                    warn!(
+                        "no update from the client for {:?}, resyncing with server {}",
+                        gap, self.server_address
+                    );
+                }
                 // receive command
                 if let Some((pawn_key, command)) = connection.get_incoming_command() {
                     return Some(Ok(ClientEvent::Command(
@@ -99,9 +340,47 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                         command.as_ref().get_typed_copy(),
                     )));
                 }
+                // receive pawn correction
+                if let Some(pawn_key) = connection.get_pawn_correction() {
+                    return Some(Ok(ClientEvent::PawnCorrected(pawn_key)));
+                }
                 // receive event
-                if let Some(event) = connection.get_incoming_event() {
-                    return Some(Ok(ClientEvent::Event(event)));
+                if let Some((event, receipt, actor)) = connection.get_incoming_event(&self.manifest)
+                {
+                    self.last_event_receipt = receipt;
+                    match actor {
+                        Some(actor_key) => {
+                            return Some(Ok(ClientEvent::ActorEvent(actor_key, event)))
+                        }
+                        None => return Some(Ok(ClientEvent::Event(event))),
+                    }
+                }
+                if let Some(raw_event) = connection.get_incoming_raw_event() {
+                    return Some(Ok(ClientEvent::RawEvent(raw_event)));
+                }
+                if let Some(skipped_frame) = connection.get_skipped_frame() {
+                    if connection.take_malformed_frame_limit_exceeded() {
+                        // the Server would carry on sending them, so isn't
+                        // reconnected to either
+                        warn!(
+                            "dropping connection to server {}, which sent too many malformed events",
+                            self.server_address
+                        );
+                        self.drop_connection();
+                        return Some(Ok(ClientEvent::Disconnection));
+                    }
+                    return Some(Ok(ClientEvent::SkippedFrame(skipped_frame)));
+                }
+                // receive tick payloads
+                if let Some(tick_payload) = connection.get_incoming_tick_payload() {
+                    match tick_payload {
+                        IncomingTickPayload::Payload(tick, payload) => {
+                            return Some(Ok(ClientEvent::TickPayload(tick, payload)));
+                        }
+                        IncomingTickPayload::Gap(first_tick, last_tick) => {
+                            return Some(Ok(ClientEvent::TickPayloadGap(first_tick, last_tick)));
+                        }
+                    }
                 }
                 // receive actor message
                 if let Some(message) = connection.get_incoming_actor_message() {
@@ -109,11 +388,21 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                         ClientActorMessage::Create(local_key) => {
                             return Some(Ok(ClientEvent::CreateActor(local_key)));
                         }
-                        ClientActorMessage::Delete(local_key) => {
-                            return Some(Ok(ClientEvent::DeleteActor(local_key)));
+                        ClientActorMessage::CreatePredicted(local_key, prediction_id) => {
+                            if self.predicted_spawns.confirm(&prediction_id) {
+                                return Some(Ok(ClientEvent::PredictionMatched(
+                                    prediction_id,
+                                    local_key,
+                                )));
+                            }
+                            // the prediction has failed already
+                            return Some(Ok(ClientEvent::CreateActor(local_key)));
+                        }
+                        ClientActorMessage::Delete(local_key, reason) => {
+                            return Some(Ok(ClientEvent::DeleteActor(local_key, reason)));
                         }
-                        ClientActorMessage::Update(local_key) => {
-                            return Some(Ok(ClientEvent::UpdateActor(local_key)));
+                        ClientActorMessage::Update(local_key, tick) => {
+                            return Some(Ok(ClientEvent::UpdateActor(local_key, tick)));
                         }
                         ClientActorMessage::AssignPawn(local_key) => {
                             return Some(Ok(ClientEvent::AssignPawn(local_key)));
@@ -121,19 +410,57 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                         ClientActorMessage::UnassignPawn(local_key) => {
                             return Some(Ok(ClientEvent::UnassignPawn(local_key)));
                         }
+                        ClientActorMessage::WorldSyncComplete => {
+                            return Some(Ok(ClientEvent::WorldSyncComplete));
+                        }
                     }
                 }
+                // receive actor-ordered Events, after the creation of any Actor
+                // they were waiting on
+                if let Some((event, receipt, actor, unresolved)) =
+                    connection.get_actor_ordered_event(&self.clock.now())
+                {
+                    self.last_event_receipt = receipt;
+                    if unresolved.is_empty() {
+                        match actor {
+                            Some(actor_key) => {
+                                return Some(Ok(ClientEvent::ActorEvent(actor_key, event)))
+                            }
+                            None => return Some(Ok(ClientEvent::Event(event))),
+                        }
+                    } else {
+                        return Some(Ok(ClientEvent::UnresolvedEvent(event, unresolved)));
+                    }
+                }
+                // fail predicted spawns the Server hasn't confirmed in time
+                if let Some(prediction_id) = self.predicted_spawns.take_expired(&self.clock.now()) {
+                    return Some(Ok(ClientEvent::PredictionFailed(prediction_id)));
+                }
                 // update current tick
                 if self.tick_manager.take_tick() {
                     return Some(Ok(ClientEvent::Tick));
                 }
+                // close the connection once the Events sent before disconnect()
+                // was called have been delivered, or the Server has stopped
+                // answering
+                if let Some(tail) = self.disconnect_tail {
+                    if connection.is_delivered_up_to(tail)
+                        || connection.should_drop(&self.clock.now())
+                    {
+                        NaiaClient::internal_send_with_connection(
+                            self.tick_manager.get_client_tick(),
+                            &mut self.sender,
+                            connection,
+                            PacketType::ClientDisconnect,
+                            Packet::empty(),
+                        );
+                        self.close();
+                        return Some(Ok(ClientEvent::Disconnection));
+                    }
+                }
                 // drop connection if necessary
-                if connection.should_drop() {
-                    self.server_connection = None;
-                    self.pre_connection_timestamp = None;
-                    self.pre_connection_digest = None;
-                    self.connection_state = AwaitingChallengeResponse;
-                    return Some(Ok(ClientEvent::Disconnection));
+                if connection.should_drop(&self.clock.now()) {
+                    return Some(Ok(self.handle_dropped_connection()));
                 } else {
                     // send heartbeats
                     if connection.should_send_heartbeat() {
@@ -156,77 +483,123 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                             ping_payload,
                         );
                     }
+                    // ask for the whole state of Actors after missing a long
+                    // run of packets
+                    if let Some(resync_payload) = connection.get_resync_request_payload() {
+                        NaiaClient::internal_send_with_connection(
+                            self.tick_manager.get_client_tick(),
+                            &mut self.sender,
+                            connection,
+                            PacketType::ResyncRequest,
+                            resync_payload,
+                        );
+                    }
                     // send a packet
-                    while let Some(payload) = connection
-                        .get_outgoing_packet(self.tick_manager.get_client_tick(), &self.manifest)
-                    {
-                        self.sender
-                            .send(Packet::new_raw(payload))
-                            .expect("send failed!");
-                        connection.mark_sent();
+                    while connection.should_send_packet() {
+                        if !NaiaClient::send_outgoing_packet(
+                            self.tick_manager.get_client_tick(),
+                            &self.manifest,
+                            &mut self.sender,
+                            connection,
+                        ) {
+                            connection.mark_flushed();
+                            break;
+                        }
                     }
                 }
             }
             None => {
-                if self.handshake_timer.ringing() {
-                    match self.connection_state {
-                        ClientConnectionState::AwaitingChallengeResponse => {
-                            if self.pre_connection_timestamp.is_none() {
-                                self.pre_connection_timestamp = Some(Timestamp::now());
-                            }
-
-                            let mut timestamp_bytes = Vec::new();
-                            self.pre_connection_timestamp
-                                .as_mut()
-                                .unwrap()
-                                .write(&mut timestamp_bytes);
-                            NaiaClient::<T, U>::internal_send_connectionless(
-                                &mut self.sender,
-                                PacketType::ClientChallengeRequest,
-                                Packet::new(timestamp_bytes),
-                            );
+                // once the Server has answered the challenge of a handshake
+                // resuming the connection, the tick is kept running, so that
+                // Commands sent as early data are issued for the ticks ahead
+                if self.suspended_connection.is_some() && self.pre_connection_digest.is_some() {
+                    self.tick_manager.mark_frame(&self.clock.now());
+                }
+                // wait out the backoff before each attempt at reconnecting
+                let mut reconnect_pending = false;
+                if let Some(reconnection) = &mut self.reconnection {
+                    match reconnection.poll(&self.clock.now()) {
+                        ReconnectStatus::Wait => reconnect_pending = true,
+                        ReconnectStatus::Attempt(attempt) => {
+                            self.handshake_retry.reset();
+                            return Some(Ok(ClientEvent::Reconnecting(attempt)));
                         }
-                        ClientConnectionState::AwaitingConnectResponse => {
-                            // write timestamp & digest into payload
-                            let mut payload_bytes = Vec::new();
-                            self.pre_connection_timestamp
-                                .as_mut()
-                                .unwrap()
-                                .write(&mut payload_bytes);
-                            for digest_byte in self.pre_connection_digest.as_ref().unwrap().as_ref()
-                            {
-                                payload_bytes.push(*digest_byte);
+                        ReconnectStatus::InProgress => {}
+                    }
+                }
+                if let ConnectionState::ConnectFailed(_) = self.connection_state {
+                    // terminal, stop sending handshake messages
+                } else if self.closed {
+                    // closed with disconnect(), never connect again
+                } else if reconnect_pending {
+                    // the next attempt isn't due yet
+                } else {
+                    match self.handshake_retry.poll(&self.clock.now()) {
+                        HandshakeRetryStatus::Wait => {}
+                        HandshakeRetryStatus::Send(attempt) => {
+                            if self.pre_connection_digest.is_none() {
+                                self.set_connection_state(ConnectionState::Connecting { attempt });
+                                self.send_challenge_request();
+                            } else {
+                                self.set_connection_state(ConnectionState::Handshaking { attempt });
+                                self.send_connect_request();
                             }
-                            // write auth event object if there is one
-                            if let Some(auth_event) = &mut self.auth_event {
-                                let type_id = auth_event.get_type_id();
-                                let naia_id = self.manifest.get_event_naia_id(&type_id); // get naia id
-                                payload_bytes.write_u16::<BigEndian>(naia_id).unwrap(); // write naia id
-                                auth_event.write(&mut payload_bytes);
+                        }
+                        HandshakeRetryStatus::Exhausted => {
+                            // a rejected handshake isn't retried by reconnecting
+                            if self.last_reject_reason.is_none() {
+                                if let Some(reconnection) = &mut self.reconnection {
+                                    if reconnection.fail_attempt(&self.clock.now()) {
+                                        warn!(
+                                            "reconnect attempt {} to server {} failed",
+                                            reconnection.get_attempt(),
+                                            self.server_address
+                                        );
+                                        self.pre_connection_timestamp = None;
+                                        self.pre_connection_digest = None;
+                                        self.early_data.clear();
+                                        self.set_connection_state(ConnectionState::Disconnected);
+                                        return None;
+                                    }
+                                }
                             }
-                            NaiaClient::<T, U>::internal_send_connectionless(
-                                &mut self.sender,
-                                PacketType::ClientConnectRequest,
-                                Packet::new(payload_bytes),
+                            let reason = match self.last_reject_reason {
+                                Some(reject_reason) => ConnectFailReason::Rejected(reject_reason),
+                                None => ConnectFailReason::Timeout,
+                            };
+                            warn!(
+                                "giving up on connecting to server {}: {:?}",
+                                self.server_address, reason
                             );
+                            self.suspended_connection = None;
+                            self.session_token = None;
+                            self.early_data.clear();
+                            self.set_connection_state(ConnectionState::ConnectFailed(reason));
+                            if self.reconnection.take().is_some() {
+                                return Some(Ok(ClientEvent::ReconnectFailed));
+                            }
                         }
-                        _ => {}
                     }
-
-                    self.handshake_timer.reset();
                 }
             }
         }
 
         // receive from socket
         loop {
-            match self.socket.receive() {
+            match self.receive_packet() {
                 Ok(event) => {
                     if let Some(packet) = event {
+                        let received_at = self.clock.now();
+                        if !self
+                            .incoming_hook
+                            .allows(&self.server_address, packet.payload())
+                        {
+                            continue;
+                        }
                         let server_connection_wrapper = self.server_connection.as_mut();
 
                         if let Some(server_connection) = server_connection_wrapper {
-                            server_connection.mark_heard();
+                            server_connection.mark_heard(&received_at);
 
                             let (header, payload) = StandardHeader::read(packet.payload());
                             server_connection
@@ -244,39 +617,121 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                                 PacketType::Heartbeat => {
                                     continue;
                                 }
+                                PacketType::Ping => {
+                                    let pong_payload =
+                                        server_connection.process_ping(&payload, &received_at);
+                                    NaiaClient::internal_send_with_connection(
+                                        self.tick_manager.get_client_tick(),
+                                        &mut self.sender,
+                                        server_connection,
+                                        PacketType::Pong,
+                                        pong_payload,
+                                    );
+                                    continue;
+                                }
                                 PacketType::Pong => {
                                     server_connection.process_pong(&payload);
                                     continue;
                                 }
+                                PacketType::CommandReport => {
+                                    server_connection.process_command_report(&payload);
+                                    continue;
+                                }
+                                PacketType::ManifestExtension => {
+                                    // answered with the Event types this Client has
+                                    // registered too
+                                    if let Some(ack_packet) = server_connection
+                                        .process_manifest_extension(&self.manifest, &payload)
+                                    {
+                                        NaiaClient::internal_send_with_connection(
+                                            self.tick_manager.get_client_tick(),
+                                            &mut self.sender,
+                                            server_connection,
+                                            PacketType::ManifestExtensionAck,
+                                            ack_packet,
+                                        );
+                                    }
+                                    continue;
+                                }
+                                PacketType::MtuProbe => {
+                                    // echo the probe's index, with the size it arrived at
+                                    let mut ack_payload = Vec::new();
+                                    byte_order::write_u16(
+                                        &mut ack_payload,
+                                        byte_order::try_read_u16(&mut PacketReader::new(&payload))
+                                            .unwrap_or(0),
+                                    ); // write probe index
+                                    byte_order::write_u16(
+                                        &mut ack_payload,
+                                        packet.payload().len().min(u16::MAX as usize) as u16,
+                                    ); // write received size
+                                    NaiaClient::internal_send_with_connection(
+                                        self.tick_manager.get_client_tick(),
+                                        &mut self.sender,
+                                        server_connection,
+                                        PacketType::MtuProbeAck,
+                                        Packet::new(ack_payload),
+                                    );
+                                    continue;
+                                }
+                                PacketType::ServerDisconnect => {
+                                    let mut reader = PacketReader::new(&payload);
+                                    let reason = handshake::read_disconnect(&mut reader);
+                                    self.drop_connection();
+                                    return Some(Ok(ClientEvent::DisconnectedByServer(reason)));
+                                }
+                                PacketType::ServerNotConnected => {
+                                    // the Server has already dropped this connection, start a
+                                    // new handshake rather than waiting to time out
+                                    warn!(
+                                        "server at {} has no connection for this client, reconnecting",
+                                        self.server_address
+                                    );
+                                    return Some(Ok(self.handle_dropped_connection()));
+                                }
                                 _ => {}
                             }
                         } else {
                             let (header, payload) = StandardHeader::read(packet.payload());
                             match header.packet_type() {
                                 PacketType::ServerChallengeResponse => {
-                                    if self.connection_state
-                                        == ClientConnectionState::AwaitingChallengeResponse
+                                    if let ConnectionState::Connecting { .. } =
+                                        self.connection_state
                                     {
-                                        if let Some(my_timestamp) = self.pre_connection_timestamp {
-                                            let mut reader = PacketReader::new(&payload);
-                                            let server_tick = reader
-                                                .get_cursor()
-                                                .read_u16::<BigEndian>()
-                                                .unwrap();
-                                            let payload_timestamp = Timestamp::read(&mut reader);
-
-                                            if my_timestamp == payload_timestamp {
-                                                let mut digest_bytes: Vec<u8> = Vec::new();
-                                                for _ in 0..32 {
-                                                    digest_bytes.push(reader.read_u8());
-                                                }
-                                                self.pre_connection_digest =
-                                                    Some(digest_bytes.into_boxed_slice());
+                                        let mut reader = PacketReader::new(&payload);
+                                        let challenge_response =
+                                            handshake::read_challenge_response(&mut reader);
+                                        if let (Some(my_timestamp), Some(challenge_response)) =
+                                            (self.pre_connection_timestamp, challenge_response)
+                                        {
+                                            if my_timestamp == challenge_response.timestamp {
+                                                // the race is over
+                                                self.raced_sockets.clear();
+                                                self.pre_connection_digest = Some(
+                                                    challenge_response
+                                                        .timestamp_digest
+                                                        .into_boxed_slice(),
+                                                );
 
-                                                self.tick_manager.set_initial_tick(server_tick);
+                                                self.tick_manager.set_initial_tick(
+                                                    challenge_response.server_tick,
+                                                );
+                                                // when resuming, the link is assumed to be
+                                                // as it was, so that Commands sent as early
+                                                // data are issued for the ticks just ahead
+                                                // of the Server, rather than well past them
+                                                if let Some(suspended_connection) =
+                                                    &self.suspended_connection
+                                                {
+                                                    self.tick_manager.record_server_tick(
+                                                        challenge_response.server_tick,
+                                                        suspended_connection.get_rtt(),
+                                                        suspended_connection.get_jitter(),
+                                                    );
+                                                }
 
-                                                self.connection_state =
-                                                    ClientConnectionState::AwaitingConnectResponse;
+                                                // move on to sending connect requests
+                                                self.handshake_retry.reset();
                                             }
                                         }
                                     }
@@ -284,15 +739,91 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                                     continue;
                                 }
                                 PacketType::ServerConnectResponse => {
-                                    let server_connection = ServerConnection::new(
-                                        self.server_address,
-                                        &self.connection_config,
-                                        &self.tick_manager,
-                                    );
+                                    if !matches!(
+                                        self.connection_state,
+                                        ConnectionState::Handshaking { .. }
+                                    ) {
+                                        continue;
+                                    }
+
+                                    // the Server decides whether compression & message
+                                    // tracing are used, and whether the suspended
+                                    // connection is resumed
+                                    let mut reader = PacketReader::new(&payload);
+                                    let handshake::ConnectResponse {
+                                        compression_enabled,
+                                        message_tracing_enabled,
+                                        resumed,
+                                        session_token,
+                                    } = handshake::read_connect_response(&mut reader);
+                                    // the versions of each type both hosts can read & write,
+                                    // which Events & Actors are sent as on the connection
+                                    let schema_versions =
+                                        match SchemaVersions::read(&self.manifest, &mut reader) {
+                                            Ok(schema_versions) => schema_versions,
+                                            Err(_) => continue,
+                                        };
+                                    self.session_token = session_token;
+                                    self.early_data.clear();
 
+                                    let suspended_connection = self.suspended_connection.take();
+                                    let (mut server_connection, event) =
+                                        match (resumed, suspended_connection) {
+                                            (true, Some(mut server_connection)) => {
+                                                server_connection.resume(
+                                                    self.server_address,
+                                                    &self.connection_config,
+                                                );
+                                                (
+                                                    server_connection,
+                                                    Some(ClientEvent::ConnectionResumed),
+                                                )
+                                            }
+                                            _ => (
+                                                ServerConnection::new(
+                                                    self.server_address,
+                                                    &self.connection_config,
+                                                    &self.tick_manager,
+                                                    self.flush_mode,
+                                                    self.auto_flush_after,
+                                                    self.receive_budget,
+                                                    self.max_upload_rate,
+                                                    self.command_config,
+                                                    self.actor_ordered_event_timeout,
+                                                    self.resync_gap_threshold,
+                                                ),
+                                                None,
+                                            ),
+                                        };
+                                    server_connection.set_compression_enabled(compression_enabled);
+                                    server_connection
+                                        .set_message_tracing_enabled(message_tracing_enabled);
+                                    server_connection.set_schema_versions(schema_versions);
+                                    let event = event.unwrap_or_else(|| {
+                                        ClientEvent::Connection(
+                                            server_connection
+                                                .get_connection_info(self.session_token.is_some()),
+                                        )
+                                    });
+
+                                    self.pre_connection_queue.flush_into(&mut server_connection);
                                     self.server_connection = Some(server_connection);
-                                    self.connection_state = ClientConnectionState::Connected;
-                                    return Some(Ok(ClientEvent::Connection));
+                                    self.reconnection = None;
+                                    self.last_reject_reason = None;
+                                    self.set_connection_state(ConnectionState::Connected);
+                                    return Some(Ok(event));
+                                }
+                                PacketType::ServerRejectResponse => {
+                                    // keep retrying the handshake until out of attempts, the
+                                    // Server may accept the connection later
+                                    let mut reader = PacketReader::new(&payload);
+                                    let reason = handshake::read_reject_response(&mut reader);
+                                    warn!(
+                                        "connection rejected by server {}: {:?}",
+                                        self.server_address, reason
+                                    );
+                                    self.last_reject_reason = Some(reason);
+                                    continue;
                                 }
                                 _ => {}
                             }
@@ -302,7 +833,9 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
                     }
                 }
                 Err(error) => {
-                    return Some(Err(NaiaClientError::Wrapped(Box::new(error))));
+                    // the socket's errors can't be shared between threads, so
+                    // only their message is kept
+                    return Some(Err(NaiaError::Transport(error.to_string().into())));
                 }
             }
         }
@@ -315,18 +848,169 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
         return None;
     }
 
-    /// Queues up an Event to be sent to the Server
-    pub fn send_event(&mut self, event: &impl Event<T>) {
-        if let Some(connection) = &mut self.server_connection {
-            connection.queue_event(event);
+    /// Queues up an Event to be sent to the Server. If the Event's type has
+    /// been registered on an ordered channel in the Manifest, it will be sent
+    /// on that channel. A guaranteed Event sent before the connection is
+    /// established, or while reconnecting, is held until it is, then queued
+    /// in the order it was sent, up to `pre_connection_queue_max` in the
+    /// ClientConfig. Returns an Error if not connected to the Server and the
+    /// Event can't be held, if the Event's type has not been registered in the
+    /// Manifest, or if the Event is too large to fit into a packet
+    pub fn send_event(&mut self, event: &impl Event<T>) -> Result<(), NaiaError> {
+        let channel = self.manifest.get_event_channel(&Event::get_type_id(event));
+        return self.queue_event(event, channel);
+    }
+
+    /// Queues up an Event to be sent to the Server, on a specific ordered
+    /// channel. Events sent on the same channel are received in the order they
+    /// were sent. Returns an Error in the same cases as `send_event()`, or if
+    /// the channel isn't one of the `ordered_channel_count` configured in the
+    /// SharedConfig
+    pub fn send_event_on_channel(
+        &mut self,
+        channel_index: ChannelIndex,
+        event: &impl Event<T>,
+    ) -> Result<(), NaiaError> {
+        return self.queue_event(event, Some(channel_index));
+    }
+
+    /// Splits the Client into a ClientReceiver, which receives events as
+    /// `receive()` does, and a ClientSender, which queues Events to be sent to
+    /// the Server, so that Events can be sent in reply to each event as it's
+    /// received, without holding on to the events first
+    pub fn split(&mut self) -> (ClientReceiver<'_, T, U>, ClientSender<T>) {
+        let sender = ClientSender::new(self.split_events.clone());
+        return (ClientReceiver::new(self), sender);
+    }
+
+    /// Queues up a group of Events, added to the group by the given closure, to
+    /// be sent to the Server. Every Event in the group is written into the
+    /// same packet, so that they are either all lost or all delivered, and the
+    /// Server receives them one after another, in the order they were added.
+    /// The group is held until connected as a guaranteed Event is, only if
+    /// every Event in it is guaranteed. Returns an Error if the Events together are too large to fit into a
+    /// single packet, or in the same cases as `send_event()`
+    pub fn send_event_group<F: FnOnce(&mut EventGroup<T>)>(
+        &mut self,
+        build: F,
+    ) -> Result<(), NaiaError> {
+        let mut group = EventGroup::new();
+        build(&mut group);
+        EventPacketWriter::validate_event_group(&self.manifest, group.get_events())?;
+        if let Some(connection) = self.get_sendable_connection() {
+            connection.queue_event_group(group.get_events());
+            return Ok(());
+        }
+        if self.can_hold_messages() {
+            return self
+                .pre_connection_queue
+                .hold_event_group(group.get_events());
+        }
+        return Err(NaiaError::NotConnected);
+    }
+
+    /// Queues up a RawEvent to be sent to the Server. It is written exactly as
+    /// an Event of the type with the RawEvent's NaiaId would be, on the
+    /// channel registered for that type, if any, and is re-transmitted until
+    /// delivered if `guaranteed` is set. Returns an Error in the same cases as
+    /// `send_event()`
+    pub fn send_raw_event(
+        &mut self,
+        raw_event: &RawEvent,
+        guaranteed: bool,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        let type_id = EventPacketWriter::validate_raw_event(&self.manifest, raw_event)?;
+        let channel = self.manifest.get_event_channel(&type_id);
+        self.connection_config.check_channel(channel)?;
+        if let Some(connection) = self.get_sendable_connection() {
+            return connection.queue_raw_event(raw_event, type_id, guaranteed, channel);
+        }
+        if self.can_hold_messages() {
+            return self
+                .pre_connection_queue
+                .hold_raw_event(raw_event, type_id, guaranteed, channel);
         }
+        return Err(NaiaError::NotConnected);
     }
 
-    /// Queues up an Command to be sent to the Server
-    pub fn send_command(&mut self, pawn_key: LocalActorKey, command: &impl Event<T>) {
+    /// Sets Events of the given type to be returned from `receive()` as
+    /// RawEvents, holding the bytes they were written as, rather than being
+    /// read into the EventType. Every Event is written with its size, so they
+    /// are never read at all. RawEvents are
+    /// received in order only with the other RawEvents on their channel.
+    /// Returns an Error if the Event's type has not been registered in the
+    /// Manifest
+    pub fn receive_raw<E: Event<T> + 'static>(&mut self) -> Result<(), NaiaError> {
+        return self.manifest.set_event_receive_raw::<E>();
+    }
+
+    /// Sends every queued Event & Command to the Server straight away. Only
+    /// needed when the Client is configured to coalesce messages with
+    /// `FlushMode::Coalesce`, otherwise queued messages are sent on every call
+    /// to `receive()`
+    pub fn flush(&mut self) {
+        // Events sent through ClientSenders which can't be queued are left to
+        // return their Error from `receive()`
+        let _ = self.queue_split_events();
         if let Some(connection) = &mut self.server_connection {
-            connection.queue_command(pawn_key, command);
+            while NaiaClient::send_outgoing_packet(
+                self.tick_manager.get_client_tick(),
+                &self.manifest,
+                &mut self.sender,
+                connection,
+            ) {}
+            connection.mark_flushed();
+        }
+    }
+
+    /// Queues up an Command to be sent to the Server. If the Manifest is set
+    /// to send SubTicks along with Commands, it's sent at the fraction of the
+    /// current tick which had passed as of the last call to `receive()`.
+    /// Commands are never held until connected, as they're only of use on the
+    /// tick they were issued for, except while resuming a dropped connection
+    /// with `ClientConfig::early_data_max_packets` set, when those sent after
+    /// the Server has answered the challenge are sent along with the
+    /// handshake. Returns an Error in the same cases as `send_event()`
+    pub fn send_command(
+        &mut self,
+        pawn_key: LocalActorKey,
+        command: &impl Event<T>,
+    ) -> Result<(), NaiaError> {
+        let sub_tick = SubTick::from_fraction(self.tick_manager.fraction);
+        return self.send_command_at(pawn_key, command, sub_tick);
+    }
+
+    /// Queues up an Command to be sent to the Server, issued at the given
+    /// SubTick of the current tick, such as when the input it was made from
+    /// happened. The SubTick is only sent if the Manifest is set to with
+    /// `Manifest::set_command_sub_ticks()`. Returns an Error in the same cases
+    /// as `send_command()`
+    pub fn send_command_at(
+        &mut self,
+        pawn_key: LocalActorKey,
+        command: &impl Event<T>,
+        sub_tick: SubTick,
+    ) -> Result<(), NaiaError> {
+        EventPacketWriter::validate_event(&self.manifest, command)?;
+        if let Some(connection) = self.get_sendable_connection() {
+            connection.queue_command(pawn_key, command, sub_tick);
+            return Ok(());
+        }
+        // only once the Server has answered the challenge, as Commands sent
+        // before then would be long stale by the time it could act on them
+        if self.early_data_max_packets > 0
+            && self.session_token.is_some()
+            && self.pre_connection_digest.is_some()
+        {
+            if let Some(connection) = &mut self.suspended_connection {
+                connection.queue_command(pawn_key, command, sub_tick);
+                return Ok(());
+            }
         }
+        return Err(NaiaError::NotConnected);
     }
 
     /// Get the address currently associated with the Server
@@ -339,6 +1023,53 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
         return self.server_connection.is_some();
     }
 
+    /// Get the current state of the connection to the Server
+    pub fn connection_state(&self) -> ConnectionState {
+        return self.connection_state;
+    }
+
+    /// Closes the connection to the Server. Once connected, the Client is
+    /// Disconnecting until the guaranteed Events already sent have been
+    /// delivered, then lets the Server know & emits a Disconnection, or emits
+    /// it once the Server has stopped answering. Otherwise any attempt to
+    /// connect is abandoned straight away. Either way, the Client never
+    /// connects again, and nothing more can be sent
+    pub fn disconnect(&mut self) {
+        if self.closed || self.disconnect_tail.is_some() {
+            return;
+        }
+        if let ConnectionState::ConnectFailed(_) = self.connection_state {
+            return;
+        }
+        match &self.server_connection {
+            Some(connection) => {
+                self.disconnect_tail = Some(connection.get_reliable_tail());
+                // the connection isn't resumed once it's closed
+                self.session_token = None;
+                self.reconnection = None;
+                self.set_connection_state(ConnectionState::Disconnecting);
+            }
+            None => self.close(),
+        }
+    }
+
+    /// Stops reconnecting to the Server after a dropped connection, as set by
+    /// the ReconnectPolicy in the ClientConfig, dropping anything kept to
+    /// resume the connection with. The connection state is left ConnectFailed.
+    /// Returns whether the Client was reconnecting
+    pub fn cancel_reconnect(&mut self) -> bool {
+        if self.reconnection.take().is_none() {
+            return false;
+        }
+        self.suspended_connection = None;
+        self.session_token = None;
+        self.early_data.clear();
+        self.pre_connection_timestamp = None;
+        self.pre_connection_digest = None;
+        self.set_connection_state(ConnectionState::ConnectFailed(ConnectFailReason::Cancelled));
+        return true;
+    }
+
     // actors
 
     /// Get a reference to an Actor currently in scope for the Client, given
@@ -351,6 +1082,28 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
             .get_actor(&self.tick_manager, key);
     }
 
+    /// Get a reference to an Actor currently in scope for the Client, given
+    /// that Actor's Key, in the state last received from the Server, rather
+    /// than interpolated as by `get_actor()`
+    pub fn get_received_actor(&self, key: &LocalActorKey) -> Option<&U> {
+        if let Some(connection) = &self.server_connection {
+            return connection.get_received_actor(key);
+        }
+        return None;
+    }
+
+    /// Iterate through every Actor currently in scope for the Client, in the
+    /// state last received from the Server. The Client keeps this up to date
+    /// itself, so the CreateActor, UpdateActor & DeleteActor events only tell
+    /// of changes already made to it, and there's no need to keep a copy.
+    /// Empty while not connected
+    pub fn actors_iter(&self) -> impl Iterator<Item = (&LocalActorKey, &U)> {
+        return self
+            .server_connection
+            .iter()
+            .flat_map(|connection| connection.actors_iter());
+    }
+
     /// Return an iterator to the collection of keys to all actors tracked by
     /// the Client
     pub fn actor_keys(&self) -> Option<Vec<LocalActorKey>> {
@@ -365,6 +1118,40 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
         return None;
     }
 
+    /// Resolve an ActorRef (usually held by a synced Property) into the Key
+    /// of the referenced Actor. Returns None if the referenced Actor is not
+    /// currently in scope for the Client
+    pub fn resolve_actor_ref(&self, actor_ref: &ActorRef) -> Option<LocalActorKey> {
+        if let Some(connection) = &self.server_connection {
+            return connection.resolve_actor_ref(actor_ref);
+        }
+        return None;
+    }
+
+    /// Predicts the spawn of an Actor the Client is about to request from the
+    /// Server, such as a projectile it fires, so that it can be shown straight
+    /// away. Returns the PredictionId to send along with the request, which
+    /// the Server passes to `NaiaServer::register_predicted_actor()` when it
+    /// spawns the Actor. The predicted Actor is held until the Actor spawned by
+    /// the Server comes into scope, with a PredictionMatched event, or until
+    /// the prediction times out, with a PredictionFailed event
+    pub fn predict_spawn(&mut self, actor: U) -> PredictionId {
+        return self.predicted_spawns.predict(actor, &self.clock.now());
+    }
+
+    /// Get a reference to an Actor whose spawn has been predicted, until its
+    /// prediction is matched or fails
+    pub fn get_predicted_actor(&self, prediction_id: &PredictionId) -> Option<&U> {
+        return self.predicted_spawns.get(prediction_id);
+    }
+
+    /// Gets an estimate of when the Event most recently returned from
+    /// `receive()` was sent, if its type is set to be timestamped in the
+    /// Manifest
+    pub fn last_event_receipt(&self) -> Option<MessageReceipt> {
+        return self.last_event_receipt;
+    }
+
     // pawns
 
     /// Get a reference to a Pawn
@@ -381,6 +1168,24 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
         return self.server_connection.as_mut().unwrap().get_pawn_mut(key);
     }
 
+    /// Get the parameters the connection to the Server is running with, or
+    /// None if not connected
+    pub fn connection_info(&self) -> Option<ConnectionInfo> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_connection_info(self.session_token.is_some()));
+        }
+        return None;
+    }
+
+    /// Get the state a Pawn was predicted to have before the Server last
+    /// corrected it, as reported by a PawnCorrected event
+    pub fn get_pawn_before_correction(&self, key: &LocalActorKey) -> Option<&U> {
+        if let Some(connection) = &self.server_connection {
+            return connection.get_pawn_before_correction(key);
+        }
+        return None;
+    }
+
     /// Return an iterator to the collection of keys to all Pawns tracked by
     /// the Client
     pub fn pawn_keys(&self) -> Option<Vec<LocalActorKey>> {
@@ -407,6 +1212,153 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
         return self.server_connection.as_ref().unwrap().get_jitter();
     }
 
+    /// Gets the estimated one-way delay of packets from the Server, in
+    /// milliseconds. The Client & Server clocks are not synchronized, so the
+    /// least delay is assumed to be the same in each direction, with any delay
+    /// beyond that, measured against the tick sync's estimate of the Server's
+    /// clock, added on to this direction. Returns None if not connected
+    pub fn get_one_way_delay(&self) -> Option<f32> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_one_way_delay());
+        }
+        return None;
+    }
+
+    /// Sends a ping to the Server right away, rather than waiting for the ping
+    /// interval to elapse, so that a fresh Round Trip Time sample is taken.
+    /// Pings are answered within the connection layer, with the time the ping
+    /// was held for left out of the sample
+    pub fn ping(&mut self) {
+        if let Some(connection) = self.server_connection.as_mut() {
+            connection.force_ping();
+        }
+    }
+
+    /// Gets the average number of Events & Commands sent in each packet to
+    /// the Server, which rises when messages are coalesced.
+    /// Returns None if not connected
+    pub fn get_messages_per_packet(&self) -> Option<f32> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_messages_per_packet());
+        }
+        return None;
+    }
+
+    /// Gets the number of Events which were dropped instead of sent to the
+    /// Server, after waiting longer than the expiry set for their type with
+    /// `Manifest::set_event_expiry()`. Returns None if not connected
+    pub fn get_expired_event_count(&self) -> Option<u64> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_expired_event_count());
+        }
+        return None;
+    }
+
+    /// Gets the number of times a Data packet from the Server which was due to
+    /// be applied was left for a later call to `receive()`, because the
+    /// `receive_budget` set in the ClientConfig had been used up.
+    /// Returns None if not connected
+    pub fn get_deferred_packet_count(&self) -> Option<u64> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_deferred_packet_count());
+        }
+        return None;
+    }
+
+    /// Gets how many bytes of padding have been sent to the Server, added as
+    /// configured by `ClientConfig::packet_padding`.
+    /// Returns None if not connected
+    pub fn get_padding_bytes_sent(&self) -> Option<u64> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_padding_bytes_sent());
+        }
+        return None;
+    }
+
+    /// Gets the number of bytes allocated for the protocol buffers of the
+    /// connection to the Server: the record of packets received from it, and
+    /// the buffers of each ordered channel, which are only allocated once the
+    /// channel is first used. Returns None if not connected
+    pub fn get_protocol_memory(&self) -> Option<usize> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_protocol_memory());
+        }
+        return None;
+    }
+
+    /// Gets the most bytes per second the Client sends to the Server, as set
+    /// by `ClientConfig::max_upload_rate`, or None if it sends without limit.
+    /// Returns an error if not connected
+    pub fn get_upload_limit(&self) -> Result<Option<u32>, NaiaError> {
+        if let Some(connection) = &self.server_connection {
+            return Ok(connection.get_upload_limit());
+        }
+        return Err(NaiaError::NotConnected);
+    }
+
+    /// Gets the share of the upload limit used over the last second, or None
+    /// if the Client sends without limit. Returns an error if not connected
+    pub fn get_upload_utilization(&self) -> Result<Option<f32>, NaiaError> {
+        if let Some(connection) = &self.server_connection {
+            return Ok(connection.get_upload_utilization());
+        }
+        return Err(NaiaError::NotConnected);
+    }
+
+    /// Gets the number of past Commands currently sent along with each
+    /// Command. This drops below the usual number when the upload limit is
+    /// being reached, which a game can show as a limited connection, and
+    /// follows the measured packet loss if `CommandConfig::target_miss_rate`
+    /// is set. Returns an error if not connected
+    pub fn get_command_redundancy(&self) -> Result<u8, NaiaError> {
+        if let Some(connection) = &self.server_connection {
+            return Ok(connection.get_command_redundancy());
+        }
+        return Err(NaiaError::NotConnected);
+    }
+
+    /// Gets the share of Commands the Server has recently reported receiving
+    /// too late for their tick, or not at all, or None if not connected or if
+    /// the Server hasn't reported on any Commands yet. Ticks on which a pawn
+    /// had no Command, between ticks on which it had, are counted as missed
+    pub fn get_command_miss_rate(&self) -> Option<f32> {
+        if let Some(connection) = &self.server_connection {
+            return connection.get_command_miss_rate();
+        }
+        return None;
+    }
+
+    /// Gets how many bytes have been sent to the Server in cover heartbeats,
+    /// as configured by `ClientConfig::cover_heartbeat_interval`.
+    /// Returns None if not connected
+    pub fn get_cover_heartbeat_bytes_sent(&self) -> Option<u64> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_cover_heartbeat_bytes_sent());
+        }
+        return None;
+    }
+
+    /// Gets the number of times the Client has gone longer than
+    /// `ClientConfig::clock_gap_threshold` between calls to `receive()`, and
+    /// resynced with the Server afterwards. Returns None if not connected
+    pub fn get_clock_gap_count(&self) -> Option<u64> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_clock_gap_count());
+        }
+        return None;
+    }
+
+    /// Gets the number of times the Client has missed a run of packets from
+    /// the Server longer than `ClientConfig::resync_gap_threshold`, and asked
+    /// for the whole state of its Actors to be resent.
+    /// Returns None if not connected
+    pub fn get_resync_count(&self) -> Option<u64> {
+        if let Some(connection) = &self.server_connection {
+            return Some(connection.get_resync_count());
+        }
+        return None;
+    }
+
     // ticks
 
     /// Gets the current tick of the Client
@@ -423,11 +1375,278 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
             .get_last_received_tick();
     }
 
+    /// Gets an estimate of the Server's tick at the given time, including the
+    /// fraction of the tick elapsed. This is fit to the ticks of every packet
+    /// received, taking the drift between the Server's & the Client's clocks
+    /// into account, and so is smoother than `get_server_tick()`. Returns None
+    /// until enough packets have been received from the Server
+    pub fn server_time_estimate(&self, now: &Instant) -> Option<f64> {
+        let connection = self.server_connection.as_ref()?;
+        let estimate = connection.get_server_time_estimate(now)?;
+        // the estimate trails the Server by the one-way latency
+        let latency_ticks = (connection.get_rtt() / 2.0) as f64
+            / self.tick_manager.get_tick_interval().as_millis() as f64;
+        return Some((estimate + latency_ticks).rem_euclid(65536.0));
+    }
+
     // internal functions
 
+    fn send_outgoing_packet(
+        host_tick: u16,
+        manifest: &Manifest<T, U>,
+        sender: &mut PacketSender,
+        connection: &mut ServerConnection<T, U>,
+    ) -> bool {
+        if let Some(payload) = connection.get_outgoing_packet(host_tick, manifest) {
+            sender.send(Packet::new_raw(payload)).expect("send failed!");
+            connection.mark_sent();
+            return true;
+        }
+        return false;
+    }
+
+    fn set_connection_state(&mut self, state: ConnectionState) {
+        if self.connection_state != state {
+            if let ConnectionState::ConnectFailed(_) = state {
+                // nothing held will ever be sent
+                self.pre_connection_queue.clear();
+            }
+            self.connection_state = state;
+            self.state_changes.push_back(state);
+        }
+    }
+
+    // Whether guaranteed messages sent now are held until connected, as they
+    // are unless the Client has given up on connecting, or is being closed
+    fn can_hold_messages(&self) -> bool {
+        if let ConnectionState::ConnectFailed(_) = self.connection_state {
+            return false;
+        }
+        return !self.closed && self.disconnect_tail.is_none();
+    }
+
+    // Gets the connection to queue messages on, unless it's being closed
+    fn get_sendable_connection(&mut self) -> Option<&mut ServerConnection<T, U>> {
+        if self.disconnect_tail.is_some() {
+            return None;
+        }
+        return self.server_connection.as_mut();
+    }
+
+    // Closes the Client for good, dropping the connection & anything held
+    fn close(&mut self) {
+        self.closed = true;
+        self.disconnect_tail = None;
+        self.reconnection = None;
+        self.raced_sockets.clear();
+        self.pre_connection_queue.clear();
+        self.drop_connection();
+    }
+
+    fn drop_connection(&mut self) {
+        self.server_connection = None;
+        self.suspended_connection = None;
+        self.session_token = None;
+        self.early_data.clear();
+        self.pre_connection_timestamp = None;
+        self.pre_connection_digest = None;
+        self.handshake_retry.reset();
+        self.set_connection_state(ConnectionState::Disconnected);
+    }
+
+    fn queue_event(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        EventPacketWriter::validate_event(&self.manifest, event)?;
+        self.connection_config.check_channel(channel)?;
+        if let Some(connection) = self.get_sendable_connection() {
+            return connection.queue_event(event, channel);
+        }
+        if self.can_hold_messages() {
+            return self.pre_connection_queue.hold_event(event, channel);
+        }
+        return Err(NaiaError::NotConnected);
+    }
+
+    // Queues the Events sent through ClientSenders, in order, stopping at the
+    // first which can't be
+    fn queue_split_events(&mut self) -> Result<(), NaiaError> {
+        loop {
+            let split_event = self.split_events.borrow_mut().pop_front();
+            let (event, channel) = match split_event {
+                Some(split_event) => split_event,
+                None => return Ok(()),
+            };
+            let channel = channel.or_else(|| {
+                self.manifest
+                    .get_event_channel(&event.as_ref().get_type_id())
+            });
+            self.queue_event(event.as_ref(), channel)?;
+        }
+    }
+
+    // Handles a connection which has dropped without being closed by the
+    // Server, keeping its state if the Server may resume it, & reconnecting as
+    // set by the ReconnectPolicy if there is one. Returns the event to emit
+    fn handle_dropped_connection(&mut self) -> ClientEvent<T> {
+        let mut event = ClientEvent::Disconnection;
+        if self.session_token.is_some() {
+            self.suspend_connection();
+            event = ClientEvent::ConnectionSuspended;
+        } else {
+            self.drop_connection();
+        }
+        if let Some(policy) = &self.reconnect_policy {
+            self.reconnection = Some(Reconnection::new(policy, &self.clock.now()));
+        }
+        return event;
+    }
+
+    // Keeps the state of a connection which has dropped, and starts a new
+    // handshake, through which the Server may resume it
+    fn suspend_connection(&mut self) {
+        self.suspended_connection = self.server_connection.take();
+        self.early_data.clear();
+        self.pre_connection_timestamp = None;
+        self.pre_connection_digest = None;
+        self.handshake_retry.reset();
+        self.set_connection_state(ConnectionState::Disconnected);
+    }
+
+    fn send_challenge_request(&mut self) {
+        if self.pre_connection_timestamp.is_none() {
+            self.pre_connection_timestamp = Some(self.clock.timestamp());
+        }
+
+        let payload_bytes =
+            handshake::write_challenge_request(self.pre_connection_timestamp.as_ref().unwrap());
+        NaiaClient::<T, U>::internal_send_connectionless(
+            &mut self.sender,
+            PacketType::ClientChallengeRequest,
+            Packet::new(payload_bytes.clone()),
+        );
+        // the Server's other addresses are sent the same challenge request,
+        // without the outgoing hook, which only sees packets sent to the
+        // address currently associated with the Server
+        for (_, socket) in &mut self.raced_sockets {
+            let payload = naia_shared::utils::write_connectionless_payload(
+                PacketType::ClientChallengeRequest,
+                &payload_bytes,
+            );
+            // an unreachable address is left to lose the race
+            let _ = socket.get_sender().send(Packet::new_raw(payload));
+        }
+    }
+
+    // Receives the next packet from the socket, or while racing the Server's
+    // addresses, from the others' sockets once it has none. The first of them
+    // the Server answers the challenge through becomes the address associated
+    // with the Server, & the rest are closed
+    fn receive_packet(&mut self) -> Result<Option<Packet>, NaiaClientSocketError> {
+        let packet = self.socket.receive()?;
+        if packet.is_some() || self.raced_sockets.is_empty() {
+            return Ok(packet);
+        }
+        for index in 0..self.raced_sockets.len() {
+            // an address which can't be reached just loses the race
+            while let Ok(Some(packet)) = self.raced_sockets[index].1.receive() {
+                if !self.answers_challenge(packet.payload()) {
+                    continue;
+                }
+                let (server_address, mut socket) = self.raced_sockets.swap_remove(index);
+                self.raced_sockets.clear();
+                self.sender.redirect(socket.get_sender(), server_address);
+                self.server_address = server_address;
+                self.socket = socket;
+                return Ok(Some(packet));
+            }
+        }
+        return Ok(None);
+    }
+
+    // Whether the packet is the Server's response to the challenge currently
+    // being sent
+    fn answers_challenge(&self, packet: &[u8]) -> bool {
+        let (header, payload) = StandardHeader::read(packet);
+        if header.packet_type() != PacketType::ServerChallengeResponse {
+            return false;
+        }
+        let challenge_response =
+            handshake::read_challenge_response(&mut PacketReader::new(&payload));
+        return match (self.pre_connection_timestamp, challenge_response) {
+            (Some(timestamp), Some(challenge_response)) => {
+                timestamp == challenge_response.timestamp
+            }
+            _ => false,
+        };
+    }
+
+    fn send_connect_request(&mut self) {
+        // write auth event object if there is one, its type was checked to be
+        // registered when the Client was created
+        let mut auth_event_bytes = Vec::new();
+        let mut auth_naia_id = None;
+        if let Some(auth_event) = &mut self.auth_event {
+            let type_id = auth_event.get_type_id();
+            if let Ok(naia_id) = self.manifest.get_event_naia_id(&type_id) {
+                auth_event.write(&mut auth_event_bytes);
+                auth_naia_id = Some(naia_id);
+            }
+        }
+        let mut schema_versions = Vec::new();
+        self.manifest.write_schema_versions(&mut schema_versions);
+        // only a suspended connection can be resumed
+        let resuming = self.suspended_connection.is_some() && self.session_token.is_some();
+        if resuming {
+            self.queue_early_data();
+        }
+        let payload_bytes = handshake::write_connect_request(
+            self.pre_connection_timestamp.as_ref().unwrap(),
+            self.pre_connection_digest.as_ref().unwrap(),
+            naia_shared::compression::is_supported(),
+            naia_shared::message_tracing::is_supported(),
+            self.session_token
+                .as_ref()
+                .filter(|_| resuming)
+                .map(|session_token| (session_token, self.early_data.as_slice())),
+            &schema_versions,
+            auth_naia_id.map(|naia_id| (naia_id, auth_event_bytes.as_slice())),
+        );
+        NaiaClient::<T, U>::internal_send_connectionless(
+            &mut self.sender,
+            PacketType::ClientConnectRequest,
+            Packet::new(payload_bytes),
+        );
+    }
+
+    // Writes the Commands sent since the last connect request into a packet of
+    // early data, dropping the oldest packets past the limits in the
+    // ClientConfig
+    fn queue_early_data(&mut self) {
+        if let Some(connection) = &mut self.suspended_connection {
+            if let Some(packet) =
+                connection.get_early_data(self.tick_manager.get_client_tick(), &self.manifest)
+            {
+                self.early_data.push(packet);
+            }
+        }
+        while self.early_data.len() > self.early_data_max_packets
+            || self
+                .early_data
+                .iter()
+                .map(|packet| packet.payload.len())
+                .sum::<usize>()
+                > self.early_data_max_bytes
+        {
+            self.early_data.remove(0);
+        }
+    }
+
     fn internal_send_with_connection(
         host_tick: u16,
-        sender: &mut MessageSender,
+        sender: &mut PacketSender,
         connection: &mut ServerConnection<T, U>,
         packet_type: PacketType,
         packet: Packet,
@@ -445,7 +1664,7 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
     }
 
     fn internal_send_connectionless(
-        sender: &mut MessageSender,
+        sender: &mut PacketSender,
         packet_type: PacketType,
         packet: Packet,
     ) {
@@ -456,3 +1675,41 @@ impl<T: EventType, U: ActorType> NaiaClient<T, U> {
             .expect("send failed!");
     }
 }
+
+// Opens a socket to the Server at the given address, marking the packets sent
+// through it with the DSCP value where it can. naia-client-socket's native
+// socket is bound to the host's IPv4 address, & can't be marked, so IPv6
+// addresses & marked sockets are given a UdpClientSocket instead
+fn open_socket(
+    server_address: SocketAddr,
+    dscp: Option<u8>,
+) -> Result<Box<dyn ClientSocketTrait>, NaiaError> {
+    cfg_if! {
+        if #[cfg(not(target_arch = "wasm32"))] {
+            let dscp = match dscp {
+                Some(dscp) if server_address.is_ipv6() => {
+                    warn!(
+                        "DSCP marking ({}) isn't supported on IPv6 sockets, packets will be sent \
+                         unmarked",
+                        dscp
+                    );
+                    None
+                }
+                dscp => dscp,
+            };
+            if server_address.is_ipv6() || dscp.is_some() {
+                return UdpClientSocket::connect(server_address, dscp)
+                    .map_err(|error| NaiaError::Transport(Box::new(error)));
+            }
+        } else {
+            if let Some(dscp) = dscp {
+                warn!(
+                    "DSCP marking ({}) isn't supported by the browser's socket, packets will be \
+                     sent unmarked",
+                    dscp
+                );
+            }
+        }
+    }
+    return Ok(ClientSocket::connect(server_address));
+}
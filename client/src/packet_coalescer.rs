@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use naia_shared::{varint, Instant, MTU_SIZE};
+
+use super::flush_mode::FlushMode;
+
+/// Tracks the messages waiting to be sent to the Server, to decide when a
+/// packet should go out, and measures how well messages are being packed
+#[derive(Debug)]
+pub struct PacketCoalescer {
+    flush_mode: FlushMode,
+    auto_flush_after: Option<Duration>,
+    queued_bytes: usize,
+    flush_at: Option<Instant>,
+    messages_sent: u64,
+    packets_sent: u64,
+}
+
+impl PacketCoalescer {
+    /// Create a new PacketCoalescer
+    pub fn new(flush_mode: FlushMode, auto_flush_after: Option<Duration>) -> Self {
+        PacketCoalescer {
+            flush_mode,
+            auto_flush_after,
+            queued_bytes: 0,
+            flush_at: None,
+            messages_sent: 0,
+            packets_sent: 0,
+        }
+    }
+
+    /// Returns whether the size of queued messages needs to be tracked
+    pub fn is_coalescing(&self) -> bool {
+        return self.flush_mode == FlushMode::Coalesce;
+    }
+
+    /// Record that a message with a payload of the given size has been queued
+    pub fn queue(&mut self, payload_size: usize, now: &Instant) {
+        // the NaiaId & payload size are written ahead of the payload, the
+        // NaiaId taking a single byte for all but the largest Manifests
+        let frame_size = 1 + varint::encoded_len(payload_size as u64) + payload_size;
+        self.add_queued_bytes(frame_size, now);
+    }
+
+    /// Record that the given number of messages, with payloads of the given
+    /// total size, have been queued again to be re-transmitted
+    pub fn queue_retransmitted(
+        &mut self,
+        message_count: usize,
+        payload_size: usize,
+        now: &Instant,
+    ) {
+        // each NaiaId & payload size take at least a byte
+        self.add_queued_bytes(2 * message_count + payload_size, now);
+    }
+
+    fn add_queued_bytes(&mut self, bytes: usize, now: &Instant) {
+        // the queued bytes are counted low rather than high, so that a packet
+        // is never sent before there are enough messages to fill it
+        self.queued_bytes += bytes;
+        if self.flush_at.is_none() {
+            if let Some(auto_flush_after) = self.auto_flush_after {
+                let mut flush_at = now.clone();
+                flush_at.add_millis(auto_flush_after.as_millis() as u32);
+                self.flush_at = Some(flush_at);
+            }
+        }
+    }
+
+    /// Returns whether a packet should be sent, without being explicitly
+    /// flushed
+    pub fn should_send(&self, now: &Instant) -> bool {
+        if self.flush_mode == FlushMode::Immediate || self.queued_bytes >= MTU_SIZE {
+            return true;
+        }
+        if let Some(flush_at) = &self.flush_at {
+            return now >= flush_at;
+        }
+        return false;
+    }
+
+    /// Record that a packet has been sent, containing the given number of
+    /// bytes & messages
+    pub fn mark_packet_sent(&mut self, bytes: usize, message_count: u16) {
+        self.queued_bytes = self.queued_bytes.saturating_sub(bytes);
+        self.messages_sent += u64::from(message_count);
+        self.packets_sent += 1;
+    }
+
+    /// Record that every queued message has been sent
+    pub fn mark_flushed(&mut self) {
+        self.queued_bytes = 0;
+        self.flush_at = None;
+    }
+
+    /// Get the average number of messages in each packet sent
+    pub fn get_messages_per_packet(&self) -> f32 {
+        if self.packets_sent == 0 {
+            return 0.0;
+        }
+        return self.messages_sent as f32 / self.packets_sent as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::{FlushMode, PacketCoalescer};
+
+    const MESSAGE_SIZE: usize = 12;
+
+    #[test]
+    fn auto_flushes_after_duration() {
+        let mut now = SystemClock.now();
+        let mut coalescer =
+            PacketCoalescer::new(FlushMode::Coalesce, Some(Duration::from_millis(20)));
+
+        coalescer.queue(MESSAGE_SIZE, &now);
+        now.add_millis(19);
+        coalescer.queue(MESSAGE_SIZE, &now);
+        assert!(!coalescer.should_send(&now));
+        now.add_millis(1);
+        assert!(coalescer.should_send(&now));
+
+        coalescer.mark_flushed();
+        assert!(!coalescer.should_send(&now));
+    }
+}
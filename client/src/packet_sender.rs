@@ -0,0 +1,45 @@
+use std::{error::Error, net::SocketAddr};
+
+use naia_client_socket::{MessageSender, Packet};
+use naia_shared::{HookAction, PacketHook};
+
+/// Sends packets to the Server through the Client's socket, showing each to
+/// the outgoing packet hook first
+#[derive(Debug)]
+pub struct PacketSender {
+    sender: MessageSender,
+    server_address: SocketAddr,
+    hook: PacketHook,
+}
+
+impl PacketSender {
+    /// Create a new PacketSender, sending through the given MessageSender to
+    /// the Server at the given address
+    pub fn new(sender: MessageSender, server_address: SocketAddr) -> Self {
+        PacketSender {
+            sender,
+            server_address,
+            hook: PacketHook::default(),
+        }
+    }
+
+    /// Sends through the given MessageSender to the Server at the given
+    /// address from now on, keeping the hook
+    pub fn redirect(&mut self, sender: MessageSender, server_address: SocketAddr) {
+        self.sender = sender;
+        self.server_address = server_address;
+    }
+
+    /// Sets the hook shown each outgoing packet
+    pub fn set_hook(&mut self, hook: Box<dyn FnMut(&SocketAddr, &[u8]) -> HookAction>) {
+        self.hook.set(hook);
+    }
+
+    /// Send a Packet to the Server, unless the hook drops it
+    pub fn send(&mut self, packet: Packet) -> Result<(), Box<dyn Error + Send>> {
+        if !self.hook.allows(&self.server_address, packet.payload()) {
+            return Ok(());
+        }
+        return self.sender.send(packet);
+    }
+}
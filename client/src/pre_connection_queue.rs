@@ -0,0 +1,132 @@
+use std::{any::TypeId, collections::VecDeque, fmt};
+
+use naia_shared::{ActorType, ChannelIndex, Event, EventType, NaiaError, RawEvent};
+
+use super::server_connection::ServerConnection;
+
+// Queues a held RawEvent on the connection, made where the EventType is known
+// to be 'static, as queueing a RawEvent requires
+type QueueRawEvent<T, U> = Box<dyn FnOnce(&mut ServerConnection<T, U>)>;
+
+// A guaranteed message sent while the Client wasn't connected
+enum HeldMessage<T: EventType, U: ActorType> {
+    Event(Box<dyn Event<T>>, Option<ChannelIndex>),
+    EventGroup(Vec<Box<dyn Event<T>>>),
+    RawEvent(QueueRawEvent<T, U>),
+}
+
+impl<T: EventType, U: ActorType> fmt::Debug for HeldMessage<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeldMessage::Event(event, channel) => {
+                f.debug_tuple("Event").field(event).field(channel).finish()
+            }
+            HeldMessage::EventGroup(events) => f.debug_tuple("EventGroup").field(events).finish(),
+            HeldMessage::RawEvent(_) => f.write_str("RawEvent"),
+        }
+    }
+}
+
+/// Holds the guaranteed Events sent while the Client isn't connected to the
+/// Server, whether it has yet to connect or is reconnecting, to be queued on
+/// the connection in the order they were sent once it's established. Events
+/// which aren't guaranteed are never held, as they would be stale by then
+#[derive(Debug)]
+pub struct PreConnectionQueue<T: EventType, U: ActorType> {
+    messages: VecDeque<HeldMessage<T, U>>,
+    max_messages: usize,
+}
+
+impl<T: EventType, U: ActorType> PreConnectionQueue<T, U> {
+    /// Creates a new PreConnectionQueue, which holds up to the given number of
+    /// Events & EventGroups
+    pub fn new(max_messages: usize) -> Self {
+        PreConnectionQueue {
+            messages: VecDeque::new(),
+            max_messages,
+        }
+    }
+
+    /// Holds an Event to be sent once connected, on the given channel.
+    /// Returns an Error if it isn't guaranteed, or if the queue is full
+    pub fn hold_event(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        self.check_room(event.is_guaranteed())?;
+        self.messages
+            .push_back(HeldMessage::Event(event.clone_box(), channel));
+        return Ok(());
+    }
+
+    /// Holds a group of Events to be sent together once connected. Returns an
+    /// Error unless every Event in it is guaranteed, or if the queue is full
+    pub fn hold_event_group(&mut self, events: &[Box<dyn Event<T>>]) -> Result<(), NaiaError> {
+        self.check_room(events.iter().all(|event| event.is_guaranteed()))?;
+        let events = events.iter().map(|event| event.clone_box()).collect();
+        self.messages.push_back(HeldMessage::EventGroup(events));
+        return Ok(());
+    }
+
+    /// Holds a RawEvent to be sent once connected, as an Event of the type
+    /// with the given TypeId would be. Returns an Error if it isn't
+    /// guaranteed, or if the queue is full
+    pub fn hold_raw_event(
+        &mut self,
+        raw_event: &RawEvent,
+        type_id: TypeId,
+        guaranteed: bool,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        self.check_room(guaranteed)?;
+        let raw_event = raw_event.clone();
+        self.messages.push_back(HeldMessage::RawEvent(Box::new(
+            move |connection: &mut ServerConnection<T, U>| {
+                // the channel was checked when the RawEvent was held
+                let _ = connection.queue_raw_event(&raw_event, type_id, true, channel);
+            },
+        )));
+        return Ok(());
+    }
+
+    /// Queues every held message on a connection which has just been
+    /// established, in the order they were sent
+    pub fn flush_into(&mut self, connection: &mut ServerConnection<T, U>) {
+        while let Some(message) = self.messages.pop_front() {
+            match message {
+                HeldMessage::Event(event, channel) => {
+                    // the channel was checked when the Event was held
+                    let _ = connection.queue_event(event.as_ref(), channel);
+                }
+                HeldMessage::EventGroup(events) => {
+                    connection.queue_event_group(&events);
+                }
+                HeldMessage::RawEvent(queue_raw_event) => {
+                    queue_raw_event(connection);
+                }
+            }
+        }
+    }
+
+    /// Drops every held message, once the Client has given up on connecting
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    // only guaranteed messages are held, up to the cap
+    fn check_room(&self, guaranteed: bool) -> Result<(), NaiaError> {
+        if !guaranteed {
+            return Err(NaiaError::NotConnected);
+        }
+        if self.messages.len() >= self.max_messages {
+            return Err(NaiaError::PreConnectionQueueFull {
+                max_messages: self.max_messages,
+            });
+        }
+        return Ok(());
+    }
+}
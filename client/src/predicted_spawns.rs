@@ -0,0 +1,144 @@
+use std::{collections::VecDeque, time::Duration};
+
+use naia_shared::{Instant, PredictionId};
+
+/// Holds the Actors the Client has predicted the spawn of, until the Server
+/// spawns them, or the predictions time out
+#[derive(Debug)]
+pub struct PredictedSpawns<U> {
+    timeout: Duration,
+    next_id: PredictionId,
+    // oldest first, and so in order of when they time out, along with the time
+    // they do
+    pending: VecDeque<(PredictionId, U, Instant)>,
+}
+
+impl<U> PredictedSpawns<U> {
+    /// Create a new PredictedSpawns, given how long a prediction waits for the
+    /// Server to spawn the Actor before it fails
+    pub fn new(timeout: Duration) -> Self {
+        PredictedSpawns {
+            timeout,
+            next_id: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Holds the predicted Actor, returning the PredictionId the Server is to
+    /// tag it with. Ids are never reused while a prediction using them is
+    /// still pending
+    pub fn predict(&mut self, actor: U, now: &Instant) -> PredictionId {
+        while self.is_pending(&self.next_id) {
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+        let prediction_id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        let mut expires_at = now.clone();
+        expires_at.add_millis(self.timeout.as_millis() as u32);
+        self.pending.push_back((prediction_id, actor, expires_at));
+        return prediction_id;
+    }
+
+    /// Gets the predicted Actor, if its prediction is still pending
+    pub fn get(&self, prediction_id: &PredictionId) -> Option<&U> {
+        return self
+            .pending
+            .iter()
+            .find(|(id, _, _)| id == prediction_id)
+            .map(|(_, actor, _)| actor);
+    }
+
+    /// Ends a pending prediction because the Server has spawned the Actor,
+    /// dropping the predicted Actor in favour of the Server's. Returns false
+    /// if the prediction isn't pending, having timed out already
+    pub fn confirm(&mut self, prediction_id: &PredictionId) -> bool {
+        match self
+            .pending
+            .iter()
+            .position(|(id, _, _)| id == prediction_id)
+        {
+            Some(index) => {
+                self.pending.remove(index);
+                return true;
+            }
+            None => return false,
+        }
+    }
+
+    /// Ends & returns the oldest prediction which has timed out, if any
+    pub fn take_expired(&mut self, now: &Instant) -> Option<PredictionId> {
+        if let Some((prediction_id, _, expires_at)) = self.pending.front() {
+            if now >= expires_at {
+                let prediction_id = *prediction_id;
+                self.pending.pop_front();
+                return Some(prediction_id);
+            }
+        }
+        return None;
+    }
+
+    fn is_pending(&self, prediction_id: &PredictionId) -> bool {
+        return self.pending.iter().any(|(id, _, _)| id == prediction_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::PredictedSpawns;
+
+    #[test]
+    fn confirmed_prediction_ends() {
+        let now = SystemClock.now();
+        let mut predictions = PredictedSpawns::new(Duration::from_millis(500));
+        let first = predictions.predict("first", &now);
+        let second = predictions.predict("second", &now);
+        assert_ne!(first, second);
+        assert_eq!(predictions.get(&second), Some(&"second"));
+
+        assert!(predictions.confirm(&second));
+        assert_eq!(predictions.get(&second), None);
+        // a repeated creation for the same prediction is not matched again
+        assert!(!predictions.confirm(&second));
+        assert_eq!(predictions.get(&first), Some(&"first"));
+    }
+
+    #[test]
+    fn unconfirmed_predictions_time_out_in_order() {
+        let mut now = SystemClock.now();
+        let mut predictions = PredictedSpawns::new(Duration::from_millis(500));
+        let first = predictions.predict(1, &now);
+        now.add_millis(100);
+        let second = predictions.predict(2, &now);
+
+        now.add_millis(399);
+        assert_eq!(predictions.take_expired(&now), None);
+        now.add_millis(1);
+        assert_eq!(predictions.take_expired(&now), Some(first));
+        assert_eq!(predictions.take_expired(&now), None);
+        now.add_millis(100);
+        assert_eq!(predictions.take_expired(&now), Some(second));
+
+        // the Server spawning the Actor after its prediction failed is too late
+        assert!(!predictions.confirm(&first));
+    }
+
+    #[test]
+    fn pending_ids_are_not_reused() {
+        let now = SystemClock.now();
+        let mut predictions = PredictedSpawns::new(Duration::from_secs(1));
+        let held = predictions.predict(0, &now);
+        for index in 1..=u16::MAX {
+            let prediction_id = predictions.predict(index, &now);
+            assert!(predictions.confirm(&prediction_id));
+        }
+        // the ids have wrapped around, past the one still pending
+        let prediction_id = predictions.predict(0, &now);
+        assert_ne!(prediction_id, held);
+        assert_eq!(predictions.get(&held), Some(&0));
+    }
+}
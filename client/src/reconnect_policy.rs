@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use naia_shared::{Instant, Random};
+
+/// How the Client reconnects to the Server by itself, once a connection it
+/// had has dropped. Each attempt is a whole handshake, resent as set by the
+/// handshake options of the ClientConfig, which resumes the dropped
+/// connection if the Server has kept its session. A connection the Server
+/// closed, or a handshake it rejected, isn't reconnected
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    /// The most attempts to make before giving up, with a ReconnectFailed
+    /// event
+    pub max_attempts: u16,
+    /// How long to wait before the first attempt, which is doubled before
+    /// each attempt after it
+    pub base_backoff: Duration,
+    /// The longest to wait before any attempt
+    pub max_backoff: Duration,
+    /// The share of each wait, from 0 to 1, which is randomly added or taken
+    /// away, so that Clients which dropped together don't all reconnect at
+    /// once
+    pub jitter: f32,
+}
+
+impl ReconnectPolicy {
+    /// Gets how long to wait before the given attempt, starting at 1, given a
+    /// random number from -1 to 1 to jitter the wait by
+    pub fn get_backoff(&self, attempt: u16, jitter_sample: f32) -> Duration {
+        let doublings = u32::from(attempt.max(1) - 1).min(31);
+        let backoff = self
+            .base_backoff
+            .checked_mul(1 << doublings)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        let jitter = self.jitter.max(0.0).min(1.0) * jitter_sample.max(-1.0).min(1.0);
+        let millis = backoff.as_millis() as f32 * (1.0 + jitter);
+        return Duration::from_millis(millis.round() as u64);
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            jitter: 0.2,
+        }
+    }
+}
+
+/// The result of polling a Reconnection
+#[derive(Debug, PartialEq)]
+pub enum ReconnectStatus {
+    /// Not yet time to start the next attempt
+    Wait,
+    /// The given attempt should be started now
+    Attempt(u16),
+    /// Attempting to reconnect is underway
+    InProgress,
+}
+
+/// Keeps track of reconnecting after a connection has dropped, deciding when
+/// each attempt starts, as set by a ReconnectPolicy
+#[derive(Debug)]
+pub struct Reconnection {
+    policy: ReconnectPolicy,
+    attempt: u16,
+    next_attempt_at: Option<Instant>,
+}
+
+impl Reconnection {
+    /// Create a new Reconnection, given the time the connection dropped,
+    /// which waits out the backoff before the first attempt
+    pub fn new(policy: &ReconnectPolicy, now: &Instant) -> Self {
+        let mut reconnection = Reconnection {
+            policy: policy.clone(),
+            attempt: 0,
+            next_attempt_at: None,
+        };
+        reconnection.schedule_next_attempt(now);
+        return reconnection;
+    }
+
+    /// Checks whether the next attempt should be started at the given time
+    pub fn poll(&mut self, now: &Instant) -> ReconnectStatus {
+        match &self.next_attempt_at {
+            None => return ReconnectStatus::InProgress,
+            Some(next_attempt_at) => {
+                if now < next_attempt_at {
+                    return ReconnectStatus::Wait;
+                }
+            }
+        }
+        self.next_attempt_at = None;
+        self.attempt += 1;
+        return ReconnectStatus::Attempt(self.attempt);
+    }
+
+    /// Records the current attempt having failed at the given time, returning
+    /// whether there are attempts left, in which case the next is scheduled
+    pub fn fail_attempt(&mut self, now: &Instant) -> bool {
+        if self.attempt >= self.policy.max_attempts {
+            return false;
+        }
+        self.schedule_next_attempt(now);
+        return true;
+    }
+
+    /// Gets the number of the current attempt, or 0 before the first
+    pub fn get_attempt(&self) -> u16 {
+        return self.attempt;
+    }
+
+    fn schedule_next_attempt(&mut self, now: &Instant) {
+        let mut jitter_sample = 0.0;
+        if self.policy.jitter > 0.0 {
+            jitter_sample = Random::gen_range_f32(-1.0, 1.0);
+        }
+        let backoff = self.policy.get_backoff(self.attempt + 1, jitter_sample);
+        let mut next_attempt_at = now.clone();
+        next_attempt_at.add_millis(backoff.as_millis().min(u128::from(u32::MAX)) as u32);
+        self.next_attempt_at = Some(next_attempt_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::{ReconnectPolicy, ReconnectStatus, Reconnection};
+
+    fn get_policy(jitter: f32) -> ReconnectPolicy {
+        return ReconnectPolicy {
+            max_attempts: 4,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            jitter,
+        };
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let policy = get_policy(0.0);
+        assert_eq!(policy.get_backoff(1, 1.0), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(2, 0.0), Duration::from_millis(200));
+        assert_eq!(policy.get_backoff(3, 0.0), Duration::from_millis(300));
+        assert_eq!(
+            policy.get_backoff(u16::MAX, 0.0),
+            Duration::from_millis(300)
+        );
+
+        let policy = get_policy(0.5);
+        assert_eq!(policy.get_backoff(1, -1.0), Duration::from_millis(50));
+        assert_eq!(policy.get_backoff(2, 1.0), Duration::from_millis(300));
+        assert_eq!(policy.get_backoff(2, 4.0), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn attempts_follow_the_backoff_schedule() {
+        let mut now = SystemClock.now();
+        let mut reconnection = Reconnection::new(&get_policy(0.0), &now);
+
+        now.add_millis(99);
+        assert_eq!(reconnection.poll(&now), ReconnectStatus::Wait);
+        now.add_millis(1);
+        assert_eq!(reconnection.poll(&now), ReconnectStatus::Attempt(1));
+        // the handshake is left to run until it fails
+        now.add_millis(1000);
+        assert_eq!(reconnection.poll(&now), ReconnectStatus::InProgress);
+
+        for (attempt, backoff) in [(2, 200), (3, 300), (4, 300)] {
+            assert!(reconnection.fail_attempt(&now));
+            now.add_millis(backoff - 1);
+            assert_eq!(reconnection.poll(&now), ReconnectStatus::Wait);
+            now.add_millis(1);
+            assert_eq!(reconnection.poll(&now), ReconnectStatus::Attempt(attempt));
+        }
+
+        // out of attempts
+        assert!(!reconnection.fail_attempt(&now));
+        assert_eq!(reconnection.get_attempt(), 4);
+    }
+
+    #[test]
+    fn jitter_keeps_within_bounds() {
+        let now = SystemClock.now();
+        for _ in 0..100 {
+            let mut reconnection = Reconnection::new(&get_policy(0.5), &now);
+            let mut early = now.clone();
+            early.add_millis(49);
+            assert_eq!(reconnection.poll(&early), ReconnectStatus::Wait);
+            let mut late = now.clone();
+            late.add_millis(150);
+            assert_eq!(reconnection.poll(&late), ReconnectStatus::Attempt(1));
+        }
+    }
+}
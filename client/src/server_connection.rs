@@ -1,17 +1,37 @@
-use std::{net::SocketAddr, rc::Rc};
+use std::{any::TypeId, net::SocketAddr, rc::Rc, sync::Arc, time::Duration};
+
+use byteorder::WriteBytesExt;
 
 use naia_shared::{
-    ActorType, Connection, ConnectionConfig, Event, EventType, LocalActorKey, ManagerType,
-    Manifest, PacketReader, PacketType, SequenceNumber, StandardHeader,
+    byte_order,
+    handshake::EarlyData,
+    manifest_extension::{read_manifest_extension, write_manifest_extension_ack},
+    sequence_greater_than,
+    utils::duration_between,
+    wrapping_diff, ActorRef, ActorType, ChannelIndex, Clock, Connection, ConnectionConfig,
+    ConnectionInfo, Event, EventType, Instant, LocalActorKey, ManagerType, Manifest,
+    MessageReceipt, NaiaError, PacketReader, PacketType, PingManager, RawEvent, SchemaVersions,
+    SequenceNumber, SkippedFrame, StandardHeader, SubTick, Timer, MTU_SIZE,
 };
 
 use super::{
-    client_actor_manager::ClientActorManager, client_actor_message::ClientActorMessage,
-    client_packet_writer::ClientPacketWriter, command_sender::CommandSender,
-    interpolation_manager::InterpolationManager, ping_manager::PingManager, tick_queue::TickQueue,
+    actor_ordered_events::ActorOrderedEvents,
+    client_actor_manager::ClientActorManager,
+    client_actor_message::ClientActorMessage,
+    client_packet_writer::ClientPacketWriter,
+    command_config::CommandConfig,
+    command_redundancy::CommandRedundancy,
+    command_sender::CommandSender,
+    flush_mode::FlushMode,
+    interpolation_manager::InterpolationManager,
+    packet_coalescer::PacketCoalescer,
+    server_time_estimator::ServerTimeEstimator,
+    tick_payload_receiver::{IncomingTickPayload, TickPayloadReceiver},
+    tick_queue::TickQueue,
+    upload_budget::UploadBudget,
 };
 use crate::{client_tick_manager::ClientTickManager, command_receiver::CommandReceiver, Packet};
-use std::collections::hash_map::Keys;
+use std::collections::hash_map::{Iter, Keys};
 
 #[derive(Debug)]
 pub struct ServerConnection<T: EventType, U: ActorType> {
@@ -22,15 +42,56 @@ pub struct ServerConnection<T: EventType, U: ActorType> {
     command_receiver: CommandReceiver<T>,
     last_replay_tick: Option<(u16, LocalActorKey)>,
     interpolation_manager: InterpolationManager<U>,
-    jitter_buffer: TickQueue<(u16, Box<[u8]>)>,
+    // along with the time each packet arrived at
+    jitter_buffer: TickQueue<(u16, Box<[u8]>, Instant)>,
+    time_estimator: ServerTimeEstimator,
+    created_at: Instant,
+    coalescer: PacketCoalescer,
+    receive_budget: Option<usize>,
+    has_deferred_data: bool,
+    deferred_packet_count: u64,
+    upload_budget: Option<UploadBudget>,
+    command_redundancy: CommandRedundancy,
+    // along with the Actor they're about, if sent as actor-scoped Events
+    actor_ordered_events: ActorOrderedEvents<(T, Option<LocalActorKey>)>,
+    resync_gap_threshold: Option<u16>,
+    newest_packet_index: Option<u16>,
+    resync_timer: Timer,
+    resync_count: u64,
+    tick_payload_receiver: TickPayloadReceiver<T>,
+    clock: Arc<dyn Clock>,
 }
 
+// The most Data packets left buffered past the receive budget. They have
+// already been acknowledged, and may hold guaranteed Events, so rather than
+// dropping any, the budget is exceeded to keep the buffer at this size
+const MAX_DEFERRED_DATA_PACKETS: usize = 1024;
+
+// How often a resync is asked for again, while the Server has yet to resend
+// the whole state of every Actor it was asked for
+const RESYNC_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+
+// The bytes each Actor asked to be resynced is written as, its local key &
+// generation
+const RESYNC_REQUEST_ENTRY_SIZE: usize = 3;
+
 impl<T: EventType, U: ActorType> ServerConnection<T, U> {
     pub fn new(
         address: SocketAddr,
         connection_config: &ConnectionConfig,
         tick_manager: &ClientTickManager,
+        flush_mode: FlushMode,
+        auto_flush_after: Option<Duration>,
+        receive_budget: Option<usize>,
+        max_upload_rate: Option<u32>,
+        command_config: CommandConfig,
+        actor_ordered_event_timeout: Duration,
+        resync_gap_threshold: Option<u16>,
     ) -> Self {
+        let command_redundancy =
+            CommandRedundancy::new(command_config, *tick_manager.get_tick_interval());
+        let clock = connection_config.clock.clone();
+        let now = clock.now();
         return ServerConnection {
             connection: Connection::new(address, connection_config),
             actor_manager: ClientActorManager::new(),
@@ -38,50 +99,109 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
             ping_manager: PingManager::new(
                 connection_config.ping_interval,
                 connection_config.rtt_sample_size,
+                &now,
             ),
             command_sender: CommandSender::new(),
             command_receiver: CommandReceiver::new(),
             last_replay_tick: None,
             jitter_buffer: TickQueue::new(),
+            time_estimator: ServerTimeEstimator::new(
+                tick_manager.get_tick_interval().as_secs_f64(),
+            ),
+            created_at: now.clone(),
+            coalescer: PacketCoalescer::new(flush_mode, auto_flush_after),
+            receive_budget,
+            has_deferred_data: false,
+            deferred_packet_count: 0,
+            upload_budget: max_upload_rate.map(|max_upload_rate| {
+                UploadBudget::new(
+                    max_upload_rate,
+                    command_redundancy.get_min_redundancy(),
+                    command_redundancy.get_max_redundancy(),
+                )
+            }),
+            command_redundancy,
+            actor_ordered_events: ActorOrderedEvents::new(actor_ordered_event_timeout),
+            resync_gap_threshold,
+            newest_packet_index: None,
+            resync_timer: Timer::new(RESYNC_REQUEST_INTERVAL, &now),
+            resync_count: 0,
+            tick_payload_receiver: TickPayloadReceiver::new(),
+            clock,
         };
     }
 
+    /// Resumes the connection after it has dropped, with packet indices
+    /// starting over with a new underlying connection. Actors & Pawns are kept,
+    /// while Events queued before the connection dropped are not carried over
+    pub fn resume(&mut self, address: SocketAddr, connection_config: &ConnectionConfig) {
+        self.connection = Connection::new(address, connection_config);
+        self.jitter_buffer = TickQueue::new();
+        self.has_deferred_data = false;
+        self.newest_packet_index = None;
+    }
+
     pub fn get_outgoing_packet(
         &mut self,
         host_tick: u16,
         manifest: &Manifest<T, U>,
     ) -> Option<Box<[u8]>> {
+        let now = self.clock.now();
+        self.connection.expire_outgoing_events(manifest, &now);
+        if let Some(upload_budget) = &mut self.upload_budget {
+            upload_budget.update(&now);
+        }
+        self.command_redundancy.update(
+            self.connection.get_settled_packet_loss(),
+            self.ping_manager.get_jitter(),
+        );
         if self.connection.has_outgoing_events() || self.command_sender.has_command() {
+            let command_redundancy = self.get_command_redundancy();
             let mut writer = ClientPacketWriter::new();
+            writer.set_writes_trace_ids(self.connection.is_message_tracing_enabled());
+            let mut message_count: u16 = 0;
 
-            while let Some((pawn_key, command)) = self.command_sender.pop_command() {
+            while let Some((pawn_key, command, sub_tick)) = self.command_sender.pop_command() {
                 if writer.write_command(
                     host_tick,
                     manifest,
+                    self.connection.get_schema_versions(),
                     &self.command_receiver,
+                    command_redundancy,
                     pawn_key,
                     &command,
+                    sub_tick,
                 ) {
                     self.command_receiver
-                        .queue_command(host_tick, pawn_key, &command);
+                        .queue_command(host_tick, pawn_key, &command, sub_tick);
+                    message_count += 1;
                 } else {
-                    self.command_sender.unpop_command(pawn_key, &command);
+                    self.command_sender
+                        .unpop_command(pawn_key, &command, sub_tick);
                     break;
                 }
             }
 
             let next_packet_index: u16 = self.get_next_packet_index();
             while let Some(popped_event) = self.connection.pop_outgoing_event(next_packet_index) {
-                if !writer.write_event(manifest, &popped_event) {
+                if !writer.write_event(
+                    manifest,
+                    self.connection.get_schema_versions(),
+                    &popped_event,
+                    &now,
+                ) {
                     self.connection
                         .unpop_outgoing_event(next_packet_index, &popped_event);
                     break;
                 }
+                message_count += 1;
             }
 
             if writer.has_bytes() {
                 // Get bytes from writer
                 let out_bytes = writer.get_bytes();
+                self.coalescer
+                    .mark_packet_sent(out_bytes.len(), message_count);
 
                 // Add header to it
                 let payload = self.process_outgoing_header(
@@ -90,6 +210,9 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
                     PacketType::Data,
                     &out_bytes,
                 );
+                if let Some(upload_budget) = &mut self.upload_budget {
+                    upload_budget.record_sent(payload.len(), &now);
+                }
                 return Some(payload);
             }
         }
@@ -97,23 +220,72 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         return None;
     }
 
+    /// Writes the Commands queued while this connection is suspended into a
+    /// packet of early data, stamped with the given tick, to be attached to
+    /// the request resuming it. Returns None if there are no Commands queued
+    pub fn get_early_data(
+        &mut self,
+        host_tick: u16,
+        manifest: &Manifest<T, U>,
+    ) -> Option<EarlyData> {
+        if !self.command_sender.has_command() {
+            return None;
+        }
+        let command_redundancy = self.get_command_redundancy();
+        let mut writer = ClientPacketWriter::new();
+        while let Some((pawn_key, command, sub_tick)) = self.command_sender.pop_command() {
+            if writer.write_command(
+                host_tick,
+                manifest,
+                self.connection.get_schema_versions(),
+                &self.command_receiver,
+                command_redundancy,
+                pawn_key,
+                &command,
+                sub_tick,
+            ) {
+                self.command_receiver
+                    .queue_command(host_tick, pawn_key, &command, sub_tick);
+            } else {
+                self.command_sender
+                    .unpop_command(pawn_key, &command, sub_tick);
+                break;
+            }
+        }
+        if !writer.has_bytes() {
+            return None;
+        }
+        return Some(EarlyData {
+            client_tick: host_tick,
+            perceived_tick: self.connection.get_last_received_tick(),
+            payload: writer.get_bytes().into_vec(),
+        });
+    }
+
     pub fn process_incoming_data(
         &mut self,
         packet_tick: u16,
         packet_index: u16,
         manifest: &Manifest<T, U>,
         data: &[u8],
+        received_at: &Instant,
     ) {
         let mut reader = PacketReader::new(data);
         while reader.has_more() {
             let manager_type: ManagerType = reader.read_u8().into();
             match manager_type {
                 ManagerType::Event => {
-                    self.connection.process_event_data(&mut reader, manifest);
+                    self.connection.process_event_data(
+                        &mut reader,
+                        manifest,
+                        packet_tick,
+                        received_at,
+                    );
                 }
                 ManagerType::Actor => {
                     self.actor_manager.process_data(
                         manifest,
+                        self.connection.get_schema_versions(),
                         &mut self.command_receiver,
                         &mut self.interpolation_manager,
                         packet_tick,
@@ -121,6 +293,13 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
                         &mut reader,
                     );
                 }
+                ManagerType::TickPayload => {
+                    self.tick_payload_receiver.process_data(
+                        &mut reader,
+                        manifest,
+                        self.connection.get_schema_versions(),
+                    );
+                }
                 _ => {}
             }
         }
@@ -134,13 +313,22 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
     ) {
         self.jitter_buffer.add_item(
             incoming_tick,
-            (incoming_packet_index, incoming_payload.clone()),
+            (
+                incoming_packet_index,
+                incoming_payload.clone(),
+                self.clock.now(),
+            ),
         );
     }
 
-    pub fn get_buffered_data_packet(&mut self, current_tick: u16) -> Option<(u16, u16, Box<[u8]>)> {
-        if let Some((tick, (index, payload))) = self.jitter_buffer.pop_item(current_tick) {
-            return Some((tick, index, payload));
+    pub fn get_buffered_data_packet(
+        &mut self,
+        current_tick: u16,
+    ) -> Option<(u16, u16, Box<[u8]>, Instant)> {
+        if let Some((tick, (index, payload, received_at))) =
+            self.jitter_buffer.pop_item(current_tick)
+        {
+            return Some((tick, index, payload, received_at));
         }
         return None;
     }
@@ -154,6 +342,14 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         return self.actor_manager.actor_keys();
     }
 
+    pub fn actors_iter(&self) -> Iter<LocalActorKey, U> {
+        return self.actor_manager.actors_iter();
+    }
+
+    pub fn get_received_actor(&self, key: &LocalActorKey) -> Option<&U> {
+        return self.actor_manager.get_actor(key);
+    }
+
     pub fn get_actor(
         &mut self,
         tick_manager: &ClientTickManager,
@@ -168,6 +364,10 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         return self.actor_manager.get_actor(key);
     }
 
+    pub fn resolve_actor_ref(&self, actor_ref: &ActorRef) -> Option<LocalActorKey> {
+        return self.actor_manager.resolve_actor_ref(actor_ref);
+    }
+
     pub fn pawn_keys(&self) -> Keys<LocalActorKey, U> {
         return self.actor_manager.pawn_keys();
     }
@@ -190,30 +390,79 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         return self.actor_manager.get_pawn(key);
     }
 
+    pub fn get_pawn_before_correction(&self, key: &LocalActorKey) -> Option<&U> {
+        return self.actor_manager.get_pawn_before_correction(key);
+    }
+
+    // only called once every replayed Command has been handed out, so the
+    // Pawn has caught back up to the present by then
+    pub fn get_pawn_correction(&mut self) -> Option<LocalActorKey> {
+        return self.actor_manager.pop_pawn_correction();
+    }
+
     // Pass-through methods to underlying interpolation manager
 
     /// This doesn't actually interpolate all actors, but rather it marks the
     /// current time & tick in order to later present interpolated actors
     /// correctly. Call this at the beginning of any frame
     pub fn frame_begin(&mut self, manifest: &Manifest<T, U>, tick_manager: &mut ClientTickManager) {
-        if tick_manager.mark_frame() {
+        let is_new_frame = tick_manager.mark_frame(&self.clock.now());
+        if is_new_frame || self.has_deferred_data {
             // interpolation manager snapshots current state of all actors
-            self.interpolation_manager
-                .update_actors(&self.actor_manager);
-
-            // then we apply all received updates to actors at once
-            let target_tick = tick_manager.get_server_tick();
-            while let Some((tick, packet_index, data_packet)) =
-                self.get_buffered_data_packet(target_tick)
-            {
-                self.process_incoming_data(tick, packet_index, manifest, &data_packet);
+            if is_new_frame {
+                self.interpolation_manager
+                    .update_actors(&self.actor_manager);
             }
 
+            // then we apply received updates to actors at once, up to the
+            // receive budget
+            self.process_buffered_data(manifest, tick_manager.get_server_tick());
+
             // finally, we must update pawns since they may have been reconciled
             self.interpolation_manager.update_pawns(&self.actor_manager);
         }
     }
 
+    /// Applies the buffered Data packets which are due by the given tick, up
+    /// to the receive budget. Any left over are applied on the next call,
+    /// without waiting for another tick. Returns the number of packets applied
+    pub fn process_buffered_data(&mut self, manifest: &Manifest<T, U>, target_tick: u16) -> usize {
+        let mut processed_count: usize = 0;
+        loop {
+            if let Some(receive_budget) = self.receive_budget {
+                if processed_count >= receive_budget
+                    && self.jitter_buffer.len() <= MAX_DEFERRED_DATA_PACKETS
+                {
+                    break;
+                }
+            }
+            match self.get_buffered_data_packet(target_tick) {
+                Some((tick, packet_index, data_packet, received_at)) => {
+                    self.process_incoming_data(
+                        tick,
+                        packet_index,
+                        manifest,
+                        &data_packet,
+                        &received_at,
+                    );
+                    processed_count += 1;
+                }
+                None => break,
+            }
+        }
+
+        let deferred_count = self.jitter_buffer.ready_count(target_tick);
+        self.has_deferred_data = deferred_count != 0;
+        self.deferred_packet_count += deferred_count as u64;
+        return processed_count;
+    }
+
+    /// Gets the number of times a Data packet which was due to be applied was
+    /// left for a later call, because the receive budget had been used up
+    pub fn get_deferred_packet_count(&self) -> u64 {
+        return self.deferred_packet_count;
+    }
+
     // Pass-through methods to underlying common connection
 
     pub fn mark_sent(&mut self) {
@@ -224,12 +473,56 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         return self.connection.should_send_heartbeat();
     }
 
-    pub fn mark_heard(&mut self) {
-        return self.connection.mark_heard();
+    /// Checks for a clock gap since the last update, as
+    /// `Connection::detect_clock_gap()` does. After one, pings sent before the
+    /// gap are discarded, as their round trips would take in the gap, and the
+    /// estimates of the Server's tick are re-anchored to the next packets
+    /// received, rather than slewing towards them
+    pub fn detect_clock_gap(
+        &mut self,
+        now: &Instant,
+        tick_manager: &mut ClientTickManager,
+    ) -> Option<Duration> {
+        let gap = self.connection.detect_clock_gap(now)?;
+        self.ping_manager.discard_sent_pings();
+        self.ping_manager.force_ping();
+        self.time_estimator.reanchor();
+        tick_manager.reanchor();
+        return Some(gap);
+    }
+
+    pub fn get_clock_gap_count(&self) -> u64 {
+        return self.connection.get_clock_gap_count();
     }
 
-    pub fn should_drop(&self) -> bool {
-        return self.connection.should_drop();
+    pub fn mark_heard(&mut self, now: &Instant) {
+        return self.connection.mark_heard(now);
+    }
+
+    pub fn should_drop(&self, now: &Instant) -> bool {
+        return self.connection.should_drop(now);
+    }
+
+    pub fn get_connection_info(&self, resumable: bool) -> ConnectionInfo {
+        return ConnectionInfo {
+            compression_enabled: self.connection.is_compression_enabled(),
+            message_tracing_enabled: self.connection.is_message_tracing_enabled(),
+            mtu: self.connection.get_max_packet_size(),
+            tick_interval: self.connection.get_tick_interval(),
+            resumable,
+        };
+    }
+
+    pub fn set_compression_enabled(&mut self, enabled: bool) {
+        self.connection.set_compression_enabled(enabled);
+    }
+
+    pub fn set_message_tracing_enabled(&mut self, enabled: bool) {
+        self.connection.set_message_tracing_enabled(enabled);
+    }
+
+    pub fn set_schema_versions(&mut self, schema_versions: SchemaVersions) {
+        self.connection.set_schema_versions(schema_versions);
     }
 
     pub fn process_incoming_header(
@@ -237,12 +530,81 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         header: &StandardHeader,
         tick_manager: &mut ClientTickManager,
     ) {
+        self.time_estimator.record(
+            header.local_packet_index(),
+            header.host_tick(),
+            duration_between(&self.created_at, &self.clock.now()).as_secs_f64(),
+        );
         tick_manager.record_server_tick(
             header.host_tick(),
             self.ping_manager.get_rtt(),
             self.ping_manager.get_jitter(),
         );
+        self.detect_receive_gap(header.local_packet_index());
         self.connection.process_incoming_header(header, &mut None);
+        // the Events of dropped packets are sent again, so count towards
+        // filling the next packet
+        let (event_count, event_bytes) = self.connection.take_retransmitted_events();
+        if self.coalescer.is_coalescing() && event_count > 0 {
+            self.coalescer
+                .queue_retransmitted(event_count, event_bytes, &self.clock.now());
+        }
+    }
+
+    // after a run of packets from the Server longer than the resync gap
+    // threshold is missed, the Server can no longer tell which of them were
+    // lost, so the changes they held to Actors may never be resent. The whole
+    // state of every Actor is asked for instead
+    fn detect_receive_gap(&mut self, packet_index: u16) {
+        if let Some(newest_packet_index) = self.newest_packet_index {
+            if !sequence_greater_than(packet_index, newest_packet_index) {
+                return;
+            }
+            let missed_count = wrapping_diff(newest_packet_index, packet_index) - 1;
+            if let Some(resync_gap_threshold) = self.resync_gap_threshold {
+                if missed_count as u16 > resync_gap_threshold {
+                    self.actor_manager.begin_resync();
+                    self.resync_timer.ring_manual();
+                    self.resync_count += 1;
+                }
+            }
+        }
+        self.newest_packet_index = Some(packet_index);
+    }
+
+    /// Gets the payload of a ResyncRequest, asking for the whole state of the
+    /// Actors which have yet to be resent since a long run of packets from
+    /// the Server was missed, if one is due
+    pub fn get_resync_request_payload(&mut self) -> Option<Packet> {
+        let now = self.clock.now();
+        if !self.actor_manager.is_resyncing() || !self.resync_timer.ringing(&now) {
+            return None;
+        }
+        self.resync_timer.reset(&now);
+        let mut payload = Vec::new();
+        for (local_key, generation) in self
+            .actor_manager
+            .get_resyncing_actors()
+            .iter()
+            .take(MTU_SIZE / RESYNC_REQUEST_ENTRY_SIZE)
+        {
+            byte_order::write_u16(&mut payload, *local_key);
+            payload.write_u8(*generation).unwrap();
+        }
+        return Some(Packet::new_raw(payload.into_boxed_slice()));
+    }
+
+    /// Gets the number of times a run of packets from the Server longer than
+    /// the resync gap threshold has been missed, and a resync asked for
+    pub fn get_resync_count(&self) -> u64 {
+        return self.resync_count;
+    }
+
+    /// Gets the estimated Server tick at the given time, trailing the Server
+    /// by the minimum one-way latency
+    pub fn get_server_time_estimate(&self, now: &Instant) -> Option<f64> {
+        let local_time = duration_between(&self.created_at, now).as_secs_f64();
+        return self.time_estimator.estimate(local_time);
     }
 
     pub fn process_outgoing_header(
@@ -260,16 +622,232 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
         );
     }
 
+    pub fn get_upload_limit(&self) -> Option<u32> {
+        return self
+            .upload_budget
+            .as_ref()
+            .map(|budget| budget.get_max_bytes_per_second());
+    }
+
+    pub fn get_upload_utilization(&self) -> Option<f32> {
+        return self
+            .upload_budget
+            .as_ref()
+            .map(|budget| budget.get_utilization());
+    }
+
+    pub fn get_command_redundancy(&self) -> u8 {
+        let redundancy = self.command_redundancy.get_redundancy();
+        return self.upload_budget.as_ref().map_or(redundancy, |budget| {
+            redundancy.min(budget.get_command_redundancy())
+        });
+    }
+
+    pub fn get_command_miss_rate(&self) -> Option<f32> {
+        return self.command_redundancy.get_miss_rate();
+    }
+
+    pub fn process_command_report(&mut self, report_payload: &[u8]) {
+        let mut reader = PacketReader::new(report_payload);
+        let received_count = byte_order::try_read_u16(&mut reader).unwrap_or(0);
+        let missed_count = byte_order::try_read_u16(&mut reader).unwrap_or(0);
+        self.command_redundancy
+            .record_miss_report(received_count, missed_count);
+    }
+
+    /// Gets the answer to a ManifestExtension from the Server, listing the
+    /// Event types in it which the given Manifest has registered under the
+    /// same NaiaId & name, or None if it can't be read
+    pub fn process_manifest_extension(
+        &self,
+        manifest: &Manifest<T, U>,
+        extension_payload: &[u8],
+    ) -> Option<Packet> {
+        let extension = read_manifest_extension(&mut PacketReader::new(extension_payload)).ok()?;
+        let naia_ids: Vec<u16> = extension
+            .event_types
+            .iter()
+            .filter(|(naia_id, name)| manifest.get_event_name(*naia_id).as_ref() == Some(name))
+            .map(|(naia_id, _)| *naia_id)
+            .collect();
+        return Some(Packet::new(write_manifest_extension_ack(
+            extension.epoch,
+            &naia_ids,
+        )));
+    }
+
+    pub fn get_protocol_memory(&self) -> usize {
+        return self.connection.get_protocol_memory();
+    }
+
+    pub fn get_padding_bytes_sent(&self) -> u64 {
+        return self.connection.get_padding_bytes_sent();
+    }
+
+    pub fn get_cover_heartbeat_bytes_sent(&self) -> u64 {
+        return self.connection.get_cover_heartbeat_bytes_sent();
+    }
+
     pub fn get_next_packet_index(&self) -> SequenceNumber {
         return self.connection.get_next_packet_index();
     }
 
-    pub fn queue_event(&mut self, event: &impl Event<T>) {
-        return self.connection.queue_event(event);
+    /// Gets the tail of the Events queued so far, which `is_delivered_up_to()`
+    /// can be asked about
+    pub fn get_reliable_tail(&self) -> u64 {
+        return self.connection.get_reliable_tail();
+    }
+
+    /// Returns whether the Server has acknowledged every guaranteed Event
+    /// queued before the given tail
+    pub fn is_delivered_up_to(&self, tail: u64) -> bool {
+        return self.connection.is_delivered_up_to(tail);
+    }
+
+    pub fn queue_event(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        self.connection.check_channel(channel)?;
+        self.queue_message_bytes(event);
+        return self.connection.queue_event(event, channel);
+    }
+
+    pub fn queue_event_group(&mut self, events: &[Box<dyn Event<T>>]) {
+        for event in events.iter() {
+            self.queue_message_bytes(event.as_ref());
+        }
+        return self.connection.queue_event_group(events);
+    }
+
+    pub fn queue_raw_event(
+        &mut self,
+        raw_event: &RawEvent,
+        type_id: TypeId,
+        guaranteed: bool,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        self.connection.check_channel(channel)?;
+        if self.coalescer.is_coalescing() {
+            self.coalescer
+                .queue(raw_event.bytes.len(), &self.clock.now());
+        }
+        return self
+            .connection
+            .queue_raw_event(raw_event, type_id, guaranteed, channel);
     }
 
-    pub fn get_incoming_event(&mut self) -> Option<T> {
-        return self.connection.get_incoming_event();
+    /// Returns whether queued messages should be sent now, without waiting to
+    /// be explicitly flushed
+    pub fn should_send_packet(&self) -> bool {
+        return self.coalescer.should_send(&self.clock.now());
+    }
+
+    pub fn mark_flushed(&mut self) {
+        self.coalescer.mark_flushed();
+    }
+
+    pub fn get_messages_per_packet(&self) -> f32 {
+        return self.coalescer.get_messages_per_packet();
+    }
+
+    pub fn get_expired_event_count(&self) -> u64 {
+        return self.connection.get_expired_event_count();
+    }
+
+    fn queue_message_bytes(&mut self, message: &dyn Event<T>) {
+        if self.coalescer.is_coalescing() {
+            let mut bytes = Vec::new();
+            message.write(&mut bytes);
+            self.coalescer.queue(bytes.len(), &self.clock.now());
+        }
+    }
+
+    /// Gets the next received Event, along with its MessageReceipt if its type
+    /// is timestamped, and the LocalActorKey of the Actor it's about if it was
+    /// sent as an actor-scoped Event. Events of actor-ordered types are held
+    /// instead, while any Actor they reference is out of scope, and those
+    /// about an Actor which has since left scope are dropped
+    pub fn get_incoming_event(
+        &mut self,
+        manifest: &Manifest<T, U>,
+    ) -> Option<(T, Option<MessageReceipt>, Option<LocalActorKey>)> {
+        loop {
+            let (event, stamp, actor) = self.connection.get_incoming_event()?;
+            if !self.has_scoped_actor(&actor) {
+                continue;
+            }
+            let now = self.clock.now();
+            let receipt = stamp.map(|stamp| stamp.to_receipt(self.get_rtt(), &now));
+            let actor_manager = &self.actor_manager;
+            if manifest.is_event_actor_ordered(&event.get_type_id())
+                && self
+                    .actor_ordered_events
+                    .must_hold(&event.get_actor_refs(), |actor_ref| {
+                        actor_manager.resolve_actor_ref(actor_ref).is_some()
+                    })
+            {
+                self.actor_ordered_events
+                    .hold((event, actor), receipt, &now);
+                continue;
+            }
+            return Some((event, receipt, actor));
+        }
+    }
+
+    /// Gets the oldest held actor-ordered Event, once every Actor it
+    /// references is in scope, or it has timed out waiting, along with the
+    /// Actor it's about, if any, & the ActorRefs which still don't resolve
+    pub fn get_actor_ordered_event(
+        &mut self,
+        now: &Instant,
+    ) -> Option<(
+        T,
+        Option<MessageReceipt>,
+        Option<LocalActorKey>,
+        Vec<ActorRef>,
+    )> {
+        loop {
+            let actor_manager = &self.actor_manager;
+            let ((event, actor), receipt, unresolved) = self.actor_ordered_events.take_ready(
+                now,
+                |(event, _)| event.get_actor_refs(),
+                |actor_ref| actor_manager.resolve_actor_ref(actor_ref).is_some(),
+            )?;
+            if !self.has_scoped_actor(&actor) {
+                continue;
+            }
+            return Some((event, receipt, actor, unresolved));
+        }
+    }
+
+    // whether the Actor an actor-scoped Event is about is still in scope, as
+    // it always is for Events which aren't about one
+    fn has_scoped_actor(&self, actor: &Option<LocalActorKey>) -> bool {
+        match actor {
+            Some(actor_key) => return self.actor_manager.get_actor(actor_key).is_some(),
+            None => return true,
+        }
+    }
+
+    pub fn get_incoming_raw_event(&mut self) -> Option<RawEvent> {
+        return self.connection.get_incoming_raw_event();
+    }
+
+    pub fn get_incoming_tick_payload(&mut self) -> Option<IncomingTickPayload<T>> {
+        return self.tick_payload_receiver.pop_incoming();
+    }
+
+    pub fn get_skipped_frame(&mut self) -> Option<SkippedFrame> {
+        return self.connection.get_skipped_frame();
+    }
+
+    pub fn take_malformed_frame_limit_exceeded(&mut self) -> bool {
+        return self.connection.take_malformed_frame_limit_exceeded();
     }
 
     pub fn get_last_received_tick(&self) -> u16 {
@@ -277,8 +855,16 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
     }
 
     // command related
-    pub fn queue_command(&mut self, pawn_key: LocalActorKey, command: &impl Event<T>) {
-        return self.command_sender.queue_command(pawn_key, command);
+    pub fn queue_command(
+        &mut self,
+        pawn_key: LocalActorKey,
+        command: &impl Event<T>,
+        sub_tick: SubTick,
+    ) {
+        self.queue_message_bytes(command);
+        return self
+            .command_sender
+            .queue_command(pawn_key, command, sub_tick);
     }
 
     pub fn get_incoming_command(&mut self) -> Option<(LocalActorKey, Rc<Box<dyn Event<T>>>)> {
@@ -304,16 +890,28 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
 
     // ping related
     pub fn should_send_ping(&self) -> bool {
-        return self.ping_manager.should_send_ping();
+        return self.ping_manager.should_send_ping(&self.clock.now());
+    }
+
+    pub fn force_ping(&mut self) {
+        self.ping_manager.force_ping();
     }
 
     pub fn get_ping_payload(&mut self) -> Packet {
-        let payload = self.ping_manager.get_ping_payload();
+        let payload = self.ping_manager.get_ping_payload(&self.clock.now());
+        return Packet::new_raw(payload);
+    }
+
+    pub fn process_ping(&self, ping_payload: &[u8], received_at: &Instant) -> Packet {
+        let payload = self
+            .ping_manager
+            .process_ping(ping_payload, received_at, &self.clock.now());
         return Packet::new_raw(payload);
     }
 
     pub fn process_pong(&mut self, pong_payload: &[u8]) {
-        self.ping_manager.process_pong(pong_payload);
+        self.ping_manager
+            .process_pong(pong_payload, &self.clock.now());
     }
 
     pub fn get_rtt(&self) -> f32 {
@@ -323,4 +921,811 @@ impl<T: EventType, U: ActorType> ServerConnection<T, U> {
     pub fn get_jitter(&self) -> f32 {
         return self.ping_manager.get_jitter();
     }
+
+    pub fn get_one_way_delay(&self) -> f32 {
+        // the least delay is taken to be the same both ways, with the delay
+        // beyond that measured against the Server's clock coming from its
+        // side, as it only ever can be from here
+        let rtt = self.ping_manager.get_rtt();
+        let excess_delay = (self.time_estimator.get_excess_delay() * 1000.0) as f32;
+        return (rtt + excess_delay.min(rtt)) / 2.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::TypeId, cell::RefCell, net::SocketAddr, rc::Rc, time::Duration};
+
+    use naia_shared::{
+        byte_order,
+        test_support::{NoActors, Note, NoteBuilder, TestEvents},
+        Actor, ActorBuilder, ActorMutator, ActorRef, ActorType, Clock, ConnectionConfig,
+        DespawnReason, Event, EventPacketWriter, ManagerType, Manifest, OutgoingEvent,
+        PacketReader, PacketType, PingManager, SchemaVersions, StandardHeader, StateMask, SubTick,
+        SystemClock, MTU_SIZE,
+    };
+
+    use super::ServerConnection;
+    use crate::{
+        client_actor_message::ClientActorMessage, client_tick_manager::ClientTickManager,
+        command_config::CommandConfig, flush_mode::FlushMode, predicted_spawns::PredictedSpawns,
+    };
+
+    // an Actor which holds the generation of the key it was created with, and
+    // which every update for it repeats, so that an update for another
+    // generation of the key would show
+    #[derive(Clone, Debug, PartialEq)]
+    struct Marker {
+        generation: u8,
+    }
+
+    impl Actor<TestActors> for Marker {
+        fn get_state_mask_size(&self) -> u8 {
+            return 1;
+        }
+        fn get_typed_copy(&self) -> TestActors {
+            return TestActors::Marker(Rc::new(RefCell::new(self.clone())));
+        }
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<Marker>();
+        }
+        fn write(&self, out_bytes: &mut Vec<u8>) {
+            out_bytes.push(self.generation);
+        }
+        fn write_partial(&self, _state_mask: &StateMask, out_bytes: &mut Vec<u8>) {
+            out_bytes.push(self.generation);
+        }
+        fn read_full(&mut self, reader: &mut PacketReader, _packet_index: u16) {
+            self.generation = reader.read_u8();
+        }
+        fn read_partial(
+            &mut self,
+            _state_mask: &StateMask,
+            reader: &mut PacketReader,
+            _packet_index: u16,
+        ) {
+            self.generation = reader.read_u8();
+        }
+        fn set_mutator(&mut self, _mutator: &Rc<RefCell<dyn ActorMutator>>) {}
+        fn is_interpolated(&self) -> bool {
+            return false;
+        }
+        fn is_predicted(&self) -> bool {
+            return false;
+        }
+        fn get_actor_refs(&self) -> Vec<ActorRef> {
+            return Vec::new();
+        }
+    }
+
+    #[derive(Clone)]
+    enum TestActors {
+        Marker(Rc<RefCell<Marker>>),
+    }
+
+    impl ActorType for TestActors {
+        fn read_full(&mut self, reader: &mut PacketReader, packet_index: u16) {
+            match self {
+                TestActors::Marker(marker) => marker.borrow_mut().read_full(reader, packet_index),
+            }
+        }
+        fn read_partial(
+            &mut self,
+            state_mask: &StateMask,
+            reader: &mut PacketReader,
+            packet_index: u16,
+        ) {
+            match self {
+                TestActors::Marker(marker) => {
+                    marker
+                        .borrow_mut()
+                        .read_partial(state_mask, reader, packet_index)
+                }
+            }
+        }
+        fn inner_ref(&self) -> Rc<RefCell<dyn Actor<TestActors>>> {
+            match self {
+                TestActors::Marker(marker) => return marker.clone(),
+            }
+        }
+        fn equals(&self, _other: &TestActors) -> bool {
+            return true;
+        }
+        fn equals_prediction(&self, _other: &TestActors) -> bool {
+            return true;
+        }
+        fn set_to_interpolation(&mut self, _old: &TestActors, _new: &TestActors, _fraction: f32) {}
+        fn mirror(&mut self, _other: &TestActors) {}
+        fn is_interpolated(&self) -> bool {
+            return false;
+        }
+        fn is_predicted(&self) -> bool {
+            return false;
+        }
+    }
+
+    struct MarkerBuilder;
+
+    impl ActorBuilder<TestActors> for MarkerBuilder {
+        fn build(&self, reader: &mut PacketReader) -> TestActors {
+            return TestActors::Marker(Rc::new(RefCell::new(Marker {
+                generation: reader.read_u8(),
+            })));
+        }
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<Marker>();
+        }
+    }
+
+    fn new_connection<U: ActorType>() -> ServerConnection<TestEvents, U> {
+        return new_connection_flushed_by(FlushMode::Immediate);
+    }
+
+    fn new_connection_flushed_by<U: ActorType>(
+        flush_mode: FlushMode,
+    ) -> ServerConnection<TestEvents, U> {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        return ServerConnection::new(
+            address,
+            &ConnectionConfig::default(),
+            &ClientTickManager::new(Duration::from_millis(50), &SystemClock.now()),
+            flush_mode,
+            None,
+            None,
+            None,
+            CommandConfig::default(),
+            Duration::from_secs(1),
+            Some(32),
+        );
+    }
+
+    // the bytes of the Actor messages for a Marker, each with its local key
+    // & generation
+    fn create_marker(local_key: u16, generation: u8) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0];
+        byte_order::write_u16(&mut bytes, local_key);
+        bytes.push(generation);
+        byte_order::write_u64(
+            &mut bytes,
+            u64::from(local_key) << 8 | u64::from(generation),
+        );
+        bytes.push(generation);
+        return bytes;
+    }
+
+    // the creation of a Marker spawned for a predicted spawn, holding the
+    // given state in place of its generation
+    fn create_predicted_marker(local_key: u16, prediction_id: u16, state: u8) -> Vec<u8> {
+        let mut bytes = create_marker(local_key, 0);
+        bytes[0] = 7;
+        bytes.pop();
+        byte_order::write_u16(&mut bytes, prediction_id);
+        bytes.push(state);
+        return bytes;
+    }
+
+    fn update_marker(local_key: u16, generation: u8) -> Vec<u8> {
+        let mut bytes = vec![2];
+        byte_order::write_u16(&mut bytes, local_key);
+        bytes.extend_from_slice(&[generation, 1, 1, 1, generation]);
+        return bytes;
+    }
+
+    // an update or resync of a Marker, holding the given state in place of
+    // its generation
+    fn update_marker_state(local_key: u16, generation: u8, state: u8) -> Vec<u8> {
+        let mut bytes = vec![2];
+        byte_order::write_u16(&mut bytes, local_key);
+        bytes.extend_from_slice(&[generation, 1, 1, 1, state]);
+        return bytes;
+    }
+
+    fn resync_marker(local_key: u16, generation: u8, state: u8) -> Vec<u8> {
+        let mut bytes = vec![9];
+        byte_order::write_u16(&mut bytes, local_key);
+        bytes.extend_from_slice(&[generation, 1, state]);
+        return bytes;
+    }
+
+    fn delete_marker(local_key: u16, generation: u8) -> Vec<u8> {
+        let mut bytes = vec![1];
+        byte_order::write_u16(&mut bytes, local_key);
+        bytes.extend_from_slice(&[generation, 1]);
+        return bytes;
+    }
+
+    fn receive_actor_messages<U: ActorType>(
+        connection: &mut ServerConnection<TestEvents, U>,
+        manifest: &Manifest<TestEvents, U>,
+        messages: &[Vec<u8>],
+    ) {
+        receive_actor_messages_at(connection, manifest, 0, 0, messages);
+    }
+
+    // receives Actor messages in a packet sent on the given tick
+    fn receive_actor_messages_at<U: ActorType>(
+        connection: &mut ServerConnection<TestEvents, U>,
+        manifest: &Manifest<TestEvents, U>,
+        packet_tick: u16,
+        packet_index: u16,
+        messages: &[Vec<u8>],
+    ) {
+        let mut payload = vec![ManagerType::Actor as u8, messages.len() as u8];
+        for message in messages.iter() {
+            payload.extend_from_slice(message);
+        }
+        connection.process_incoming_data(
+            packet_tick,
+            packet_index,
+            manifest,
+            &payload,
+            &SystemClock.now(),
+        );
+    }
+
+    // sends packets as the Client does while receiving, for as long as they
+    // should be sent, or as it does when flushed, until there is nothing left
+    // to send. Returns the number of packets sent
+    fn send_packets(
+        connection: &mut ServerConnection<TestEvents, NoActors>,
+        manifest: &Manifest<TestEvents, NoActors>,
+        flush: bool,
+    ) -> usize {
+        let mut packet_count = 0;
+        while flush || connection.should_send_packet() {
+            if connection.get_outgoing_packet(0, manifest).is_none() {
+                connection.mark_flushed();
+                break;
+            }
+            connection.mark_sent();
+            packet_count += 1;
+        }
+        return packet_count;
+    }
+
+    fn get_marker_generation(
+        connection: &ServerConnection<TestEvents, TestActors>,
+        local_key: u16,
+    ) -> Option<u8> {
+        match connection.actor_manager.get_actor(&local_key) {
+            Some(TestActors::Marker(marker)) => return Some(marker.borrow().generation),
+            None => return None,
+        }
+    }
+
+    #[test]
+    fn receive_budget_spreads_burst_over_calls() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(NoteBuilder));
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut connection: ServerConnection<TestEvents, NoActors> = ServerConnection::new(
+            address,
+            &ConnectionConfig::default(),
+            &ClientTickManager::new(Duration::from_millis(50), &SystemClock.now()),
+            FlushMode::Immediate,
+            None,
+            Some(50),
+            None,
+            CommandConfig::default(),
+            Duration::from_secs(1),
+            Some(32),
+        );
+
+        // a burst of packets held up by a hitch, all due by the same tick
+        let packet_count: u16 = 500;
+        for id in 0..packet_count {
+            let event: Box<dyn Event<TestEvents>> = Box::new(Note { id });
+            let outgoing_event = OutgoingEvent {
+                event: Rc::new(event),
+                ordering: None,
+                size: 0,
+                queued_at: SystemClock.now(),
+                grouped: Vec::new(),
+                trace_id: None,
+                actor: None,
+                queue_index: 0,
+            };
+            let mut writer = EventPacketWriter::new();
+            assert!(writer.write_event(
+                &manifest,
+                &SchemaVersions::new(),
+                &outgoing_event,
+                &SystemClock.now()
+            ));
+            let mut payload = Vec::new();
+            writer.get_bytes(&mut payload);
+            connection.buffer_data_packet(id % 4, id, &payload.into_boxed_slice());
+        }
+
+        let mut received_ids = Vec::new();
+        let mut expected_deferred_count = 0;
+        for call in 1..=10 {
+            assert_eq!(connection.process_buffered_data(&manifest, 4), 50);
+            while let Some((TestEvents::Note(note), _, _)) =
+                connection.get_incoming_event(&manifest)
+            {
+                received_ids.push(note.id);
+            }
+            assert_eq!(received_ids.len(), call * 50);
+            expected_deferred_count += 500 - call as u64 * 50;
+            assert_eq!(
+                connection.get_deferred_packet_count(),
+                expected_deferred_count
+            );
+        }
+        assert_eq!(connection.process_buffered_data(&manifest, 4), 0);
+
+        received_ids.sort();
+        let expected_ids: Vec<u16> = (0..packet_count).collect();
+        assert_eq!(received_ids, expected_ids);
+    }
+
+    #[test]
+    fn upload_limit_lowers_redundancy_not_events() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(NoteBuilder));
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        // a budget a single packet uses up
+        let mut connection: ServerConnection<TestEvents, NoActors> = ServerConnection::new(
+            address,
+            &ConnectionConfig::default(),
+            &ClientTickManager::new(Duration::from_millis(50), &SystemClock.now()),
+            FlushMode::Immediate,
+            None,
+            None,
+            Some(10),
+            CommandConfig::default(),
+            Duration::from_secs(1),
+            Some(32),
+        );
+        assert_eq!(connection.get_upload_limit(), Some(10));
+        assert_eq!(connection.get_command_redundancy(), 2);
+
+        connection.queue_event(&Note { id: 0 }, None).unwrap();
+        assert!(connection.get_outgoing_packet(0, &manifest).is_some());
+        assert!(connection.get_upload_utilization().unwrap() > 1.0);
+
+        // the next packet is sent all the same, with Commands stripped of
+        // some of their redundancy
+        connection.queue_event(&Note { id: 1 }, None).unwrap();
+        assert!(connection.get_outgoing_packet(1, &manifest).is_some());
+        assert_eq!(connection.get_command_redundancy(), 1);
+    }
+
+    #[test]
+    fn past_commands_are_sent_with_their_own_sub_ticks() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(NoteBuilder));
+        manifest.set_command_sub_ticks(true);
+        let mut connection = new_connection::<NoActors>();
+        connection.command_receiver.pawn_init(&7);
+
+        // a Command on each of ticks 10 to 12, issued later in each tick
+        let mut payload = Vec::new();
+        for (tick, fraction) in [(10, 0.25), (11, 0.5), (12, 0.75)].iter() {
+            let sub_tick = SubTick::from_fraction(*fraction);
+            connection.queue_command(7, &Note { id: *tick }, sub_tick);
+            payload = connection
+                .get_outgoing_packet(*tick, &manifest)
+                .unwrap()
+                .to_vec();
+        }
+
+        // pawn key, NaiaId & number of past Commands, then each Command's
+        // SubTick ahead of it, the past ones after their tick difference
+        let mut expected = vec![ManagerType::Command as u8, 1, 0, 7, 0, 0, 2];
+        expected.extend_from_slice(&[3, 0, 0, 12]);
+        expected.extend_from_slice(&[1, 2, 0, 0, 11]);
+        expected.extend_from_slice(&[2, 1, 0, 0, 10]);
+        assert_eq!(&payload[StandardHeader::bytes_number()..], &expected[..]);
+    }
+
+    #[test]
+    fn command_reports_give_the_miss_rate() {
+        let mut connection = new_connection::<NoActors>();
+        assert_eq!(connection.get_command_miss_rate(), None);
+
+        // Commands received & missed
+        let mut report = Vec::new();
+        byte_order::write_u16(&mut report, 995);
+        byte_order::write_u16(&mut report, 5);
+        connection.process_command_report(&report);
+        assert_eq!(connection.get_command_miss_rate(), Some(0.005));
+        // nothing is measured as lost yet, & there's no target to size to
+        assert_eq!(connection.get_command_redundancy(), 2);
+    }
+
+    #[test]
+    fn deletions_deliver_their_reason() {
+        let mut manifest = Manifest::<TestEvents, TestActors>::new();
+        manifest.register_actor(Box::new(MarkerBuilder));
+        let mut connection = new_connection();
+        let creations: Vec<Vec<u8>> = (5..=8).map(|key| create_marker(key, 0)).collect();
+        receive_actor_messages(&mut connection, &manifest, &creations);
+        while connection.get_incoming_actor_message().is_some() {}
+
+        // deletion message type, local key, generation & reason, followed by
+        // an Event to check that every reason is read to its end
+        let mut payload = vec![ManagerType::Actor as u8, 4];
+        payload.extend_from_slice(&[1, 0, 5, 0, 0]);
+        payload.extend_from_slice(&[1, 0, 6, 0, 1]);
+        payload.extend_from_slice(&[1, 0, 7, 0, 2, 9]);
+        payload.extend_from_slice(&[1, 0, 8, 0, 2, 255]);
+        payload.extend_from_slice(&[ManagerType::Event as u8, 0]);
+        connection.process_incoming_data(0, 0, &manifest, &payload, &SystemClock.now());
+
+        let expected = [
+            (5, DespawnReason::OutOfScope),
+            (6, DespawnReason::Destroyed),
+            (7, DespawnReason::Custom(9)),
+            (8, DespawnReason::Custom(255)),
+        ];
+        for (expected_key, expected_reason) in expected.iter() {
+            match connection.get_incoming_actor_message() {
+                Some(ClientActorMessage::Delete(local_key, reason)) => {
+                    assert_eq!(local_key, *expected_key);
+                    assert_eq!(reason, *expected_reason);
+                }
+                message => panic!("expected a deletion, got {:?}", message),
+            }
+        }
+        assert!(connection.get_incoming_actor_message().is_none());
+    }
+
+    #[test]
+    fn late_messages_for_previous_generation_are_discarded() {
+        let mut manifest = Manifest::<TestEvents, TestActors>::new();
+        manifest.register_actor(Box::new(MarkerBuilder));
+        let mut connection = new_connection();
+        receive_actor_messages(&mut connection, &manifest, &[create_marker(3, 0)]);
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Create(3))
+        ));
+
+        // the key is reused, and the new creation overtakes the deletion of the
+        // previous Actor, which is deleted first
+        receive_actor_messages(&mut connection, &manifest, &[create_marker(3, 1)]);
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Delete(3, DespawnReason::OutOfScope))
+        ));
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Create(3))
+        ));
+        assert_eq!(get_marker_generation(&connection, 3), Some(1));
+
+        // everything for the previous Actor which arrives after is discarded
+        receive_actor_messages(
+            &mut connection,
+            &manifest,
+            &[
+                update_marker(3, 0),
+                delete_marker(3, 0),
+                create_marker(3, 0),
+            ],
+        );
+        assert!(connection.get_incoming_actor_message().is_none());
+        assert_eq!(get_marker_generation(&connection, 3), Some(1));
+
+        receive_actor_messages(&mut connection, &manifest, &[update_marker(3, 1)]);
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Update(3, _))
+        ));
+        receive_actor_messages(&mut connection, &manifest, &[delete_marker(3, 1)]);
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Delete(3, DespawnReason::Destroyed))
+        ));
+        assert_eq!(get_marker_generation(&connection, 3), None);
+    }
+
+    #[test]
+    fn updates_from_an_older_tick_are_discarded_whole() {
+        let mut manifest = Manifest::<TestEvents, TestActors>::new();
+        manifest.register_actor(Box::new(MarkerBuilder));
+        let mut connection = new_connection();
+        receive_actor_messages(&mut connection, &manifest, &[create_marker(3, 0)]);
+        while connection.get_incoming_actor_message().is_some() {}
+
+        receive_actor_messages_at(
+            &mut connection,
+            &manifest,
+            10,
+            2,
+            &[update_marker_state(3, 0, 7)],
+        );
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Update(3, 10))
+        ));
+
+        // sent on an earlier tick, in a packet retransmitting it after the
+        // newer update, so is older than what's applied however its
+        // Properties were numbered
+        receive_actor_messages_at(
+            &mut connection,
+            &manifest,
+            9,
+            3,
+            &[update_marker_state(3, 0, 5)],
+        );
+        assert!(connection.get_incoming_actor_message().is_none());
+        assert_eq!(get_marker_generation(&connection, 3), Some(7));
+
+        // updates on the same tick or later still apply
+        receive_actor_messages_at(
+            &mut connection,
+            &manifest,
+            10,
+            4,
+            &[update_marker_state(3, 0, 8)],
+        );
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Update(3, 10))
+        ));
+        assert_eq!(get_marker_generation(&connection, 3), Some(8));
+
+        // a new Actor using the key starts afresh
+        receive_actor_messages(&mut connection, &manifest, &[delete_marker(3, 0)]);
+        receive_actor_messages(&mut connection, &manifest, &[create_marker(3, 1)]);
+        while connection.get_incoming_actor_message().is_some() {}
+        receive_actor_messages_at(
+            &mut connection,
+            &manifest,
+            2,
+            5,
+            &[update_marker_state(3, 1, 4)],
+        );
+        assert_eq!(get_marker_generation(&connection, 3), Some(4));
+    }
+
+    #[test]
+    fn recycled_key_never_receives_cross_generation_update() {
+        let mut manifest = Manifest::<TestEvents, TestActors>::new();
+        manifest.register_actor(Box::new(MarkerBuilder));
+        let mut connection = new_connection();
+
+        // the key is recycled 600 times, so that its generation wraps around,
+        // and each Actor is created, updated twice & deleted
+        let mut packets = Vec::new();
+        for recycle in 0..600u32 {
+            let generation = recycle as u8;
+            packets.push(create_marker(3, generation));
+            packets.push(update_marker(3, generation));
+            packets.push(update_marker(3, generation));
+            packets.push(delete_marker(3, generation));
+        }
+
+        // each packet is delayed by up to 8 places
+        let mut seed: u32 = 0x2545_f491;
+        let mut random = move |range: u32| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            return seed % range;
+        };
+        let mut delayed: Vec<Vec<u8>> = Vec::new();
+        let mut received = Vec::new();
+        for packet in packets.into_iter() {
+            delayed.push(packet);
+            if delayed.len() > 8 {
+                received.push(delayed.remove(random(delayed.len() as u32) as usize));
+            }
+        }
+        received.append(&mut delayed);
+
+        let mut created_generation: Option<u8> = None;
+        let mut created_count = 0;
+        for packet in received.iter() {
+            receive_actor_messages(&mut connection, &manifest, &[packet.clone()]);
+            while let Some(message) = connection.get_incoming_actor_message() {
+                match message {
+                    ClientActorMessage::Create(3) => {
+                        assert!(created_generation.is_none(), "created twice");
+                        created_generation = get_marker_generation(&connection, 3);
+                        created_count += 1;
+                    }
+                    ClientActorMessage::Delete(3, _) => {
+                        assert!(created_generation.is_some(), "deleted before created");
+                        created_generation = None;
+                    }
+                    ClientActorMessage::Update(3, _) => {
+                        assert!(created_generation.is_some(), "updated before created");
+                    }
+                    message => panic!("unexpected message {:?}", message),
+                }
+            }
+            // the Actor only ever holds what was sent for its own generation
+            assert_eq!(get_marker_generation(&connection, 3), created_generation);
+        }
+        assert!(created_count > 300, "only {} created", created_count);
+    }
+
+    #[test]
+    fn predicted_spawn_takes_state_spawned_by_server() {
+        let mut manifest = Manifest::<TestEvents, TestActors>::new();
+        manifest.register_actor(Box::new(MarkerBuilder));
+        let mut connection = new_connection();
+        let mut predictions = PredictedSpawns::new(Duration::from_secs(1));
+        let predicted = Marker { generation: 9 };
+        let prediction_id = predictions.predict(predicted.get_typed_copy(), &SystemClock.now());
+
+        // the Server spawns the Actor in another state than was predicted
+        receive_actor_messages(
+            &mut connection,
+            &manifest,
+            &[create_predicted_marker(4, prediction_id, 2)],
+        );
+        match connection.get_incoming_actor_message() {
+            Some(ClientActorMessage::CreatePredicted(4, id)) => {
+                assert_eq!(id, prediction_id);
+                assert!(predictions.confirm(&id));
+            }
+            message => panic!("expected a predicted creation, got {:?}", message),
+        }
+        assert!(predictions.get(&prediction_id).is_none());
+        assert_eq!(get_marker_generation(&connection, 4), Some(2));
+
+        // the Actor is then updated as any other
+        receive_actor_messages(&mut connection, &manifest, &[update_marker(4, 0)]);
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Update(4, _))
+        ));
+        assert_eq!(get_marker_generation(&connection, 4), Some(0));
+    }
+
+    #[test]
+    fn connection_survives_clock_gap() {
+        let mut connection = new_connection::<NoActors>();
+        let mut tick_manager =
+            ClientTickManager::new(Duration::from_millis(50), &SystemClock.now());
+        tick_manager.set_initial_tick(100);
+        let mut now = SystemClock.now();
+        connection.mark_heard(&now);
+        let ping = connection.ping_manager.get_ping_payload(&now);
+
+        // the Client is frozen for 30 seconds, while the Server kept the
+        // connection alive
+        now.add_millis(30_000);
+        assert!(connection
+            .detect_clock_gap(&now, &mut tick_manager)
+            .is_some());
+        assert!(!connection.should_drop(&now));
+        assert_eq!(connection.get_clock_gap_count(), 1);
+        connection.mark_heard(&now);
+        now.add_millis(5000);
+        assert!(!connection.should_drop(&now));
+
+        // the round trip of the ping sent before the gap isn't sampled, and a
+        // new one goes out straight away
+        let pong =
+            PingManager::new(Duration::from_secs(1), 8, &now).process_ping(&ping, &now, &now);
+        connection.ping_manager.process_pong(&pong, &now);
+        assert_eq!(connection.ping_manager.get_rtt(), 0.0);
+        assert!(connection.should_send_ping());
+
+        // the next Server tick is adopted outright, however close it is
+        tick_manager.record_server_tick(105, 0.0, 0.0);
+        assert_eq!(tick_manager.get_server_tick(), 105 - 1);
+    }
+
+    #[test]
+    fn updates_wait_for_resync_after_long_gap() {
+        let mut manifest = Manifest::<TestEvents, TestActors>::new();
+        manifest.register_actor(Box::new(MarkerBuilder));
+        let mut connection = new_connection();
+        let mut tick_manager =
+            ClientTickManager::new(Duration::from_millis(50), &SystemClock.now());
+        receive_actor_messages(&mut connection, &manifest, &[create_marker(3, 0)]);
+        connection.get_incoming_actor_message();
+
+        // a run of packets no longer than the threshold is missed, then one
+        // longer
+        for packet_index in [10, 43, 77].iter() {
+            let header = StandardHeader::new(PacketType::Data, *packet_index, 0, 0, 0, 0);
+            connection.process_incoming_header(&header, &mut tick_manager);
+        }
+        assert_eq!(connection.get_resync_count(), 1);
+        let request = connection.get_resync_request_payload().unwrap();
+        assert_eq!(request.payload(), &[0, 3, 0]);
+        assert!(connection.get_resync_request_payload().is_none());
+
+        // updates are held back until the whole state arrives, then applied
+        // on top of it
+        receive_actor_messages(&mut connection, &manifest, &[update_marker_state(3, 0, 7)]);
+        assert!(connection.get_incoming_actor_message().is_none());
+        assert_eq!(get_marker_generation(&connection, 3), Some(0));
+        receive_actor_messages(&mut connection, &manifest, &[resync_marker(3, 0, 5)]);
+        assert!(matches!(
+            connection.get_incoming_actor_message(),
+            Some(ClientActorMessage::Update(3, _))
+        ));
+        assert_eq!(get_marker_generation(&connection, 3), Some(7));
+        assert!(!connection.actor_manager.is_resyncing());
+
+        receive_actor_messages(&mut connection, &manifest, &[update_marker_state(3, 0, 9)]);
+        assert_eq!(get_marker_generation(&connection, 3), Some(9));
+    }
+
+    #[test]
+    fn coalescing_packs_queued_events_into_minimal_packets() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(NoteBuilder));
+        let note_count = 200;
+
+        // the fewest packets the Notes fit in, queued all at once
+        let mut connection = new_connection_flushed_by(FlushMode::Coalesce);
+        for id in 0..note_count {
+            connection.queue_event(&Note { id }, None).unwrap();
+        }
+        let minimal_packet_count = send_packets(&mut connection, &manifest, true);
+        assert!(minimal_packet_count > 1);
+
+        // queued one at a time, a packet only goes out once there are enough
+        // to fill it, and the rest when flushed
+        let mut connection = new_connection_flushed_by(FlushMode::Coalesce);
+        let mut packet_count = 0;
+        for id in 0..note_count {
+            connection.queue_event(&Note { id }, None).unwrap();
+            packet_count += send_packets(&mut connection, &manifest, false);
+        }
+        assert!(packet_count < minimal_packet_count);
+        packet_count += send_packets(&mut connection, &manifest, true);
+        assert_eq!(packet_count, minimal_packet_count);
+        assert_eq!(
+            connection.get_messages_per_packet(),
+            note_count as f32 / minimal_packet_count as f32
+        );
+
+        // whereas each is sent as it's queued by default
+        let mut connection = new_connection_flushed_by(FlushMode::Immediate);
+        let mut packet_count = 0;
+        for id in 0..10 {
+            connection.queue_event(&Note { id }, None).unwrap();
+            packet_count += send_packets(&mut connection, &manifest, false);
+        }
+        assert_eq!(packet_count, 10);
+        assert_eq!(connection.get_messages_per_packet(), 1.0);
+    }
+
+    #[test]
+    fn coalescing_counts_retransmitted_events() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(NoteBuilder));
+        let mut tick_manager =
+            ClientTickManager::new(Duration::from_millis(50), &SystemClock.now());
+        let mut connection = new_connection_flushed_by(FlushMode::Coalesce);
+
+        // just over half of a packet's worth of Notes, each written with a
+        // byte for its NaiaId & another for its size
+        let note_count = (MTU_SIZE / 4) / 2 + 1;
+        let mut next_id = 0;
+        let mut queue_notes = |connection: &mut ServerConnection<TestEvents, NoActors>| {
+            for _ in 0..note_count {
+                connection.queue_event(&Note { id: next_id }, None).unwrap();
+                next_id += 1;
+            }
+        };
+        queue_notes(&mut connection);
+        assert!(!connection.should_send_packet());
+        let sent_count = send_packets(&mut connection, &manifest, true) as u16;
+
+        // the Server hears of packets after those, but not of them
+        connection.queue_event(&Note { id: 0 }, None).unwrap();
+        assert_eq!(send_packets(&mut connection, &manifest, true), 1);
+        let header = StandardHeader::new(PacketType::Data, 0, sent_count, 0, 0, 0);
+        queue_notes(&mut connection);
+        assert!(!connection.should_send_packet());
+
+        // the Notes re-sent make up a whole packet along with those queued
+        connection.process_incoming_header(&header, &mut tick_manager);
+        assert!(connection.should_send_packet());
+        send_packets(&mut connection, &manifest, true);
+        assert!(!connection.should_send_packet());
+    }
 }
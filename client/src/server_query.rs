@@ -0,0 +1,98 @@
+use std::{net::SocketAddr, time::Duration};
+
+use log::warn;
+
+use naia_client_socket::{ClientSocket, ClientSocketTrait, MessageSender, Packet};
+use naia_shared::{
+    utils::{duration_between, write_padded_connectionless_payload},
+    Clock, Instant, PacketType, StandardHeader, SystemClock, MTU_SIZE,
+};
+
+// The size queries are padded up to, in bytes. A Server never replies with a
+// packet larger than the query, so that it can't be used to amplify traffic,
+// & this leaves room for a reply as large as any packet which is never
+// fragmented
+const QUERY_PACKET_SIZE: usize = MTU_SIZE + StandardHeader::bytes_number();
+
+/// The status of an out-of-band query sent to a Server
+#[derive(Debug)]
+pub enum QueryStatus {
+    /// No reply has arrived yet, and the query has not yet timed out
+    Pending,
+    /// The Server replied to the query with the given bytes
+    Reply(Box<[u8]>),
+    /// No reply arrived before the timeout elapsed. The Server may not answer
+    /// queries, may have declined to reply, or either packet may have been lost
+    TimedOut,
+}
+
+/// An out-of-band query sent to a Server, such as a server browser would send
+/// to fetch a Server's name & player count. Queries are sent over their own
+/// socket, without a connection, so they don't disturb any existing connection
+/// to the same Server
+#[derive(Debug)]
+pub struct ServerQuery {
+    socket: Box<dyn ClientSocketTrait>,
+    // kept so that the socket's outgoing channel stays open until the reply
+    _sender: MessageSender,
+    started: Instant,
+    timeout: Duration,
+    finished: bool,
+}
+
+impl ServerQuery {
+    /// Sends a query with the given payload to the Server at the given address,
+    /// padded to leave room for the reply
+    pub fn new(server_address: SocketAddr, payload: &[u8], timeout: Duration) -> Self {
+        let mut socket = ClientSocket::connect(server_address);
+        let mut sender = socket.get_sender();
+
+        let query =
+            write_padded_connectionless_payload(PacketType::Query, payload, QUERY_PACKET_SIZE);
+        if let Err(err) = sender.send(Packet::new_raw(query)) {
+            warn!("failed to send query: {}", err);
+        }
+
+        ServerQuery {
+            socket,
+            _sender: sender,
+            // a query isn't part of any session, so is always timed by the
+            // SystemClock
+            started: SystemClock.now(),
+            timeout,
+            finished: false,
+        }
+    }
+
+    /// Must be called regularly until it returns something other than
+    /// QueryStatus::Pending. Once the reply has been returned, or the query
+    /// has timed out, further calls return QueryStatus::TimedOut
+    pub fn poll(&mut self) -> QueryStatus {
+        if self.finished {
+            return QueryStatus::TimedOut;
+        }
+
+        loop {
+            match self.socket.receive() {
+                Ok(Some(packet)) => {
+                    let (header, payload) = StandardHeader::read(packet.payload());
+                    if header.packet_type() == PacketType::QueryResponse {
+                        self.finished = true;
+                        return QueryStatus::Reply(payload);
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    warn!("query socket error: {}", err);
+                    break;
+                }
+            }
+        }
+
+        if duration_between(&self.started, &SystemClock.now()) >= self.timeout {
+            self.finished = true;
+            return QueryStatus::TimedOut;
+        }
+        return QueryStatus::Pending;
+    }
+}
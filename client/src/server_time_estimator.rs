@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+
+use naia_shared::{wrapping_diff, SequenceBuffer, SequenceNumber};
+
+// number of received packets which are reduced into a single minimum-delay
+// sample
+const WINDOW_SIZE: u16 = 64;
+// number of minimum-delay samples kept, which bounds how far back the drift
+// fit looks
+const MAX_WINDOWS: usize = 64;
+// the drift is only fitted once there's enough history to tell it apart from
+// jitter, before that the clocks are assumed to run at the same rate
+const MIN_WINDOWS_FOR_DRIFT: usize = 8;
+// samples further than this from the fit, once scaled by the median residual,
+// are discarded as outliers before fitting again
+const OUTLIER_THRESHOLD: f64 = 3.0;
+// floor for the outlier cutoff, in seconds, so that a near-perfect fit does
+// not reject everything
+const MIN_OUTLIER_CUTOFF: f64 = 0.0005;
+// weight given to each packet's delay above the fit, in the running average of
+// how far packets are held up beyond the least delayed
+const EXCESS_DELAY_SMOOTHING: f64 = 0.1;
+
+#[derive(Clone, Debug)]
+struct TickSample {
+    tick: i64,
+    local_time: f64,
+}
+
+/// Estimates the current Server tick with sub-tick precision, from the ticks
+/// stamped on received packets and the local time they arrived at.
+///
+/// Packets only ever arrive late, so the history is reduced to the samples
+/// with the least delay in each window of packets, and a line is fit through
+/// those to find both the offset & the relative drift of the Server's clock
+#[derive(Debug)]
+pub struct ServerTimeEstimator {
+    tick_interval: f64,
+    received: SequenceBuffer<TickSample>,
+    first_tick: Option<u16>,
+    last_tick: u16,
+    last_unwrapped_tick: i64,
+    window_count: u16,
+    window_min: Option<TickSample>,
+    windows: VecDeque<TickSample>,
+    // (local time at the fit's center, tick at the fit's center, local seconds
+    // per Server tick)
+    fit: Option<(f64, f64, f64)>,
+    excess_delay: f64,
+}
+
+impl ServerTimeEstimator {
+    /// Create a new ServerTimeEstimator, given the nominal duration of a tick
+    /// in seconds
+    pub fn new(tick_interval: f64) -> Self {
+        ServerTimeEstimator {
+            tick_interval,
+            received: SequenceBuffer::with_capacity(WINDOW_SIZE * 2),
+            first_tick: None,
+            last_tick: 0,
+            last_unwrapped_tick: 0,
+            window_count: 0,
+            window_min: None,
+            windows: VecDeque::new(),
+            fit: None,
+            excess_delay: 0.0,
+        }
+    }
+
+    /// Record the Server tick of a packet with the given index, received at
+    /// the given local time in seconds. Duplicate packets & packets too old to
+    /// be tracked are ignored
+    pub fn record(&mut self, packet_index: SequenceNumber, server_tick: u16, local_time: f64) {
+        if self.received.exists(packet_index) {
+            return;
+        }
+
+        let tick = self.unwrap_tick(server_tick);
+        let sample = TickSample { tick, local_time };
+        if !self.received.insert(packet_index, sample.clone()) {
+            return;
+        }
+
+        if let Some(fit) = &self.fit {
+            let excess_delay = Self::residual(fit, &sample).max(0.0);
+            self.excess_delay += (excess_delay - self.excess_delay) * EXCESS_DELAY_SMOOTHING;
+        }
+
+        let is_new_min = match &self.window_min {
+            Some(window_min) => self.delay(&sample) < self.delay(window_min),
+            None => true,
+        };
+        if is_new_min {
+            // re-anchored after a clock gap, with no window to fit yet
+            if self.windows.is_empty() {
+                if let Some((_, _, seconds_per_tick)) = self.fit {
+                    self.fit = Some((sample.local_time, sample.tick as f64, seconds_per_tick));
+                }
+            }
+            self.window_min = Some(sample);
+        }
+
+        self.window_count += 1;
+        if self.window_count >= WINDOW_SIZE {
+            self.window_count = 0;
+            if let Some(window_min) = self.window_min.take() {
+                self.windows.push_back(window_min);
+                if self.windows.len() > MAX_WINDOWS {
+                    self.windows.pop_front();
+                }
+            }
+            self.refit();
+        }
+    }
+
+    /// Restarts the estimate after a clock gap, discarding the history from
+    /// before it. Until a full window of packets has been received since, the
+    /// estimate follows the least delayed packet received after the gap, at
+    /// the rate fitted before it
+    pub fn reanchor(&mut self) {
+        self.windows.clear();
+        self.window_min = None;
+        self.window_count = 0;
+    }
+
+    /// Get the estimated Server tick at the given local time in seconds,
+    /// including the fraction of the tick elapsed. This trails the Server by
+    /// the minimum one-way latency. Returns None until a full window of
+    /// packets has been received
+    pub fn estimate(&self, local_time: f64) -> Option<f64> {
+        if let Some((center_time, center_tick, seconds_per_tick)) = self.fit {
+            let tick = center_tick + ((local_time - center_time) / seconds_per_tick);
+            return Some(tick.rem_euclid(65536.0));
+        }
+        return None;
+    }
+
+    /// Get the running average of how long, in seconds, packets are held up
+    /// beyond the least delayed, measured against the fit of the Server's
+    /// clock. This is 0 until there's a fit to measure against
+    pub fn get_excess_delay(&self) -> f64 {
+        return self.excess_delay;
+    }
+
+    fn unwrap_tick(&mut self, server_tick: u16) -> i64 {
+        if self.first_tick.is_none() {
+            self.first_tick = Some(server_tick);
+            self.last_tick = server_tick;
+            self.last_unwrapped_tick = i64::from(server_tick);
+            return self.last_unwrapped_tick;
+        }
+
+        let unwrapped_tick =
+            self.last_unwrapped_tick + i64::from(wrapping_diff(self.last_tick, server_tick));
+        if unwrapped_tick > self.last_unwrapped_tick {
+            self.last_tick = server_tick;
+            self.last_unwrapped_tick = unwrapped_tick;
+        }
+        return unwrapped_tick;
+    }
+
+    // arrival time relative to when the tick was expected, at the nominal rate
+    fn delay(&self, sample: &TickSample) -> f64 {
+        return sample.local_time - (sample.tick as f64 * self.tick_interval);
+    }
+
+    fn refit(&mut self) {
+        if self.windows.len() == 0 {
+            return;
+        }
+
+        let samples: Vec<&TickSample> = self.windows.iter().collect();
+        let mut fit = self.fit_samples(&samples);
+
+        // discard outliers, such as windows where every packet was held up
+        let mut residuals: Vec<f64> = samples
+            .iter()
+            .map(|sample| Self::residual(&fit, sample).abs())
+            .collect();
+        residuals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let cutoff = (residuals[residuals.len() / 2] * OUTLIER_THRESHOLD).max(MIN_OUTLIER_CUTOFF);
+        let inliers: Vec<&TickSample> = samples
+            .into_iter()
+            .filter(|sample| Self::residual(&fit, sample).abs() <= cutoff)
+            .collect();
+        if inliers.len() > 0 {
+            fit = self.fit_samples(&inliers);
+        }
+
+        self.fit = Some(fit);
+    }
+
+    fn fit_samples(&self, samples: &[&TickSample]) -> (f64, f64, f64) {
+        let count = samples.len() as f64;
+        let origin = samples[0].tick;
+        let tick_mean = samples
+            .iter()
+            .map(|sample| (sample.tick - origin) as f64)
+            .sum::<f64>()
+            / count;
+        let time_mean = samples.iter().map(|sample| sample.local_time).sum::<f64>() / count;
+
+        let mut seconds_per_tick = self.tick_interval;
+        if samples.len() >= MIN_WINDOWS_FOR_DRIFT {
+            let mut covariance = 0.0;
+            let mut variance = 0.0;
+            for sample in samples {
+                let tick_offset = (sample.tick - origin) as f64 - tick_mean;
+                covariance += tick_offset * (sample.local_time - time_mean);
+                variance += tick_offset * tick_offset;
+            }
+            if variance > 0.0 {
+                seconds_per_tick = covariance / variance;
+            }
+        }
+
+        return (time_mean, origin as f64 + tick_mean, seconds_per_tick);
+    }
+
+    fn residual(fit: &(f64, f64, f64), sample: &TickSample) -> f64 {
+        let (center_time, center_tick, seconds_per_tick) = fit;
+        return sample.local_time
+            - (center_time + ((sample.tick as f64 - center_tick) * seconds_per_tick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServerTimeEstimator;
+
+    const TICK_INTERVAL: f64 = 0.05;
+    const DRIFT: f64 = 50.0 / 1_000_000.0;
+    const MIN_DELAY: f64 = 0.02;
+    const FIRST_TICK: u16 = 65000;
+
+    // local time at which the Server sent the packet for a given tick, with
+    // the Server's clock running slow
+    fn send_time(tick_count: u32) -> f64 {
+        return tick_count as f64 * TICK_INTERVAL * (1.0 + DRIFT);
+    }
+
+    fn fill(estimator: &mut ServerTimeEstimator, tick_count: u32) {
+        let mut random: u32 = 12345;
+        for count in 0..tick_count {
+            random = random.wrapping_mul(1103515245).wrapping_add(12345);
+            let mut delay = MIN_DELAY + ((random >> 16) % 60) as f64 / 1000.0;
+            if count % 97 == 0 {
+                delay += 0.5;
+            }
+            // a stretch where every packet is held up
+            if count >= 3000 && count < 3064 {
+                delay += 0.2;
+            }
+
+            estimator.record(
+                count as u16,
+                FIRST_TICK.wrapping_add(count as u16),
+                send_time(count) + delay,
+            );
+        }
+    }
+
+    fn tick_error(estimate: f64, expected: f64) -> f64 {
+        let error = (estimate - expected).rem_euclid(65536.0);
+        return error.min(65536.0 - error);
+    }
+
+    #[test]
+    fn no_estimate_until_window_filled() {
+        let mut estimator = ServerTimeEstimator::new(TICK_INTERVAL);
+        fill(&mut estimator, 63);
+        assert!(estimator.estimate(10.0).is_none());
+
+        // duplicates don't count towards the window
+        estimator.record(62, FIRST_TICK.wrapping_add(62), send_time(62));
+        assert!(estimator.estimate(10.0).is_none());
+
+        estimator.record(63, FIRST_TICK.wrapping_add(63), send_time(63));
+        assert!(estimator.estimate(10.0).is_some());
+    }
+
+    #[test]
+    fn converges_with_drift_across_wraparound() {
+        let mut estimator = ServerTimeEstimator::new(TICK_INTERVAL);
+        let tick_count = 8000;
+        fill(&mut estimator, tick_count);
+
+        // trails the Server by the minimum delay
+        let lag = MIN_DELAY / TICK_INTERVAL;
+        for elapsed_ticks in [0.0, 0.3, 4000.0, 10000.3].iter() {
+            let local_time =
+                send_time(tick_count) + (elapsed_ticks * TICK_INTERVAL * (1.0 + DRIFT));
+            let expected = f64::from(FIRST_TICK) + f64::from(tick_count) + elapsed_ticks - lag;
+            let estimate = estimator.estimate(local_time).unwrap();
+            assert!(
+                tick_error(estimate, expected) < 0.05,
+                "estimate {} expected {}",
+                estimate,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn excess_delay_follows_packets_held_up_beyond_the_fit() {
+        let mut estimator = ServerTimeEstimator::new(TICK_INTERVAL);
+        for count in 0..640u32 {
+            estimator.record(
+                count as u16,
+                FIRST_TICK.wrapping_add(count as u16),
+                send_time(count) + MIN_DELAY,
+            );
+        }
+        assert!(estimator.get_excess_delay() < 0.001);
+
+        // a queue builds up on the way from the Server
+        for count in 640..700u32 {
+            estimator.record(
+                count as u16,
+                FIRST_TICK.wrapping_add(count as u16),
+                send_time(count) + MIN_DELAY + 0.03,
+            );
+        }
+        let excess_delay = estimator.get_excess_delay();
+        assert!(
+            (excess_delay - 0.03).abs() < 0.002,
+            "excess delay {}",
+            excess_delay
+        );
+    }
+
+    #[test]
+    fn reanchors_after_clock_gap() {
+        let mut estimator = ServerTimeEstimator::new(TICK_INTERVAL);
+        fill(&mut estimator, 640);
+
+        // the local clock stood still through a 30 second suspend, while the
+        // Server kept ticking
+        estimator.reanchor();
+        let skipped_ticks = 600;
+        let local_time = send_time(640) + MIN_DELAY;
+        let tick = FIRST_TICK.wrapping_add(640 + skipped_ticks);
+        estimator.record(640, tick, local_time);
+        let expected = f64::from(tick);
+        let estimate = estimator.estimate(local_time).unwrap();
+        assert!(
+            tick_error(estimate, expected) < 0.05,
+            "estimate {} expected {}",
+            estimate,
+            expected
+        );
+    }
+}
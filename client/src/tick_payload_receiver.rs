@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use naia_shared::{
+    read_tick_payload_frame, sequence_greater_than, ActorType, EventType, Manifest, PacketReader,
+    SchemaVersions,
+};
+
+/// A tick payload received from the Server, or a run of ticks whose payloads
+/// never arrived
+#[derive(Debug, PartialEq)]
+pub enum IncomingTickPayload<T: EventType> {
+    /// The payload set for a Server tick
+    Payload(u16, T),
+    /// The first & last Server ticks, inclusive, whose payloads never arrived
+    Gap(u16, u16),
+}
+
+/// Reads the tick payloads carried by packets from the Server, passing each
+/// on once, in tick order, since every packet of a tick carries the same one
+#[derive(Debug)]
+pub struct TickPayloadReceiver<T: EventType> {
+    // the tick of the newest payload passed on
+    newest_tick: Option<u16>,
+    incoming: VecDeque<IncomingTickPayload<T>>,
+}
+
+impl<T: EventType> TickPayloadReceiver<T> {
+    /// Creates a TickPayloadReceiver, which has received no payloads yet
+    pub fn new() -> Self {
+        TickPayloadReceiver {
+            newest_tick: None,
+            incoming: VecDeque::new(),
+        }
+    }
+
+    /// Reads the frame of a tick payload, as the schema version of its type
+    /// agreed on for the connection. A payload no newer than the last one
+    /// passed on is dropped, while one which points back past it is passed on
+    /// after a gap, for the payloads in between which never arrived. Payloads
+    /// lost before the first to arrive are only reported as far back as the
+    /// tick it points back to
+    pub fn process_data<U: ActorType>(
+        &mut self,
+        reader: &mut PacketReader,
+        manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
+    ) {
+        let frame = match read_tick_payload_frame(reader) {
+            Ok(frame) => frame,
+            Err(_) => {
+                // there's no telling what follows a malformed frame, so the
+                // rest of the packet is dropped
+                let end = reader.get_buffer().len();
+                reader.get_cursor().set_position(end as u64);
+                return;
+            }
+        };
+        // every packet of a tick carries the same payload, so it's only
+        // decoded the first time
+        if !self.is_newer(frame.tick) {
+            return;
+        }
+        if let Some(payload) = manifest.create_event(
+            frame.naia_id,
+            &mut PacketReader::new(frame.payload),
+            schema_versions,
+        ) {
+            self.receive(frame.tick, frame.previous_tick, payload);
+        }
+    }
+
+    // passes on the payload of a tick, after a gap for any payloads between it
+    // & the newest passed on which never arrived, unless it's no newer
+    fn receive(&mut self, tick: u16, previous_tick: Option<u16>, payload: T) {
+        if !self.is_newer(tick) {
+            return;
+        }
+        if let Some(previous_tick) = previous_tick {
+            match self.newest_tick {
+                Some(newest_tick) => {
+                    if sequence_greater_than(previous_tick, newest_tick) {
+                        self.incoming.push_back(IncomingTickPayload::Gap(
+                            newest_tick.wrapping_add(1),
+                            previous_tick,
+                        ));
+                    }
+                }
+                None => {
+                    self.incoming
+                        .push_back(IncomingTickPayload::Gap(previous_tick, previous_tick));
+                }
+            }
+        }
+        self.incoming
+            .push_back(IncomingTickPayload::Payload(tick, payload));
+        self.newest_tick = Some(tick);
+    }
+
+    fn is_newer(&self, tick: u16) -> bool {
+        match self.newest_tick {
+            Some(newest_tick) => return sequence_greater_than(tick, newest_tick),
+            None => return true,
+        }
+    }
+
+    /// Gets the next tick payload or gap, in tick order
+    pub fn pop_incoming(&mut self) -> Option<IncomingTickPayload<T>> {
+        return self.incoming.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::TypeId;
+
+    use naia_shared::EventType;
+
+    use super::{IncomingTickPayload, TickPayloadReceiver};
+
+    // the tick each payload was set for
+    #[derive(Clone, Debug, PartialEq)]
+    struct Tick(u16);
+
+    impl EventType for Tick {
+        fn write(&self, buffer: &mut Vec<u8>) {
+            buffer.extend_from_slice(&self.0.to_be_bytes());
+        }
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<Tick>();
+        }
+    }
+
+    fn receive(receiver: &mut TickPayloadReceiver<Tick>, tick: u16, previous_tick: Option<u16>) {
+        receiver.receive(tick, previous_tick, Tick(tick));
+    }
+
+    fn drain(receiver: &mut TickPayloadReceiver<Tick>) -> Vec<IncomingTickPayload<Tick>> {
+        let mut incoming = Vec::new();
+        while let Some(item) = receiver.pop_incoming() {
+            incoming.push(item);
+        }
+        return incoming;
+    }
+
+    #[test]
+    fn each_payload_is_passed_on_once() {
+        let mut receiver = TickPayloadReceiver::new();
+        receive(&mut receiver, 10, None);
+        receive(&mut receiver, 10, None);
+        receive(&mut receiver, 11, Some(10));
+        receive(&mut receiver, 10, None);
+        receive(&mut receiver, 11, Some(10));
+        assert_eq!(
+            drain(&mut receiver),
+            [
+                IncomingTickPayload::Payload(10, Tick(10)),
+                IncomingTickPayload::Payload(11, Tick(11)),
+            ]
+        );
+    }
+
+    #[test]
+    fn missed_payloads_are_passed_on_as_a_gap() {
+        let mut receiver = TickPayloadReceiver::new();
+        receive(&mut receiver, 65534, None);
+        receive(&mut receiver, 3, Some(2));
+        // arriving late, after the payload of a newer tick
+        receive(&mut receiver, 2, Some(1));
+        receive(&mut receiver, 4, Some(3));
+        assert_eq!(
+            drain(&mut receiver),
+            [
+                IncomingTickPayload::Payload(65534, Tick(65534)),
+                IncomingTickPayload::Gap(65535, 2),
+                IncomingTickPayload::Payload(3, Tick(3)),
+                IncomingTickPayload::Payload(4, Tick(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn payloads_missed_before_the_first_are_a_gap_back_to_its_previous_tick() {
+        let mut receiver = TickPayloadReceiver::new();
+        receive(&mut receiver, 7, Some(6));
+        assert_eq!(
+            drain(&mut receiver),
+            [
+                IncomingTickPayload::Gap(6, 6),
+                IncomingTickPayload::Payload(7, Tick(7)),
+            ]
+        );
+    }
+}
@@ -5,11 +5,11 @@ use naia_shared::sequence_greater_than;
 /// A queue for items marked by tick, will only ever pop items from the queue if
 /// the tick has elapsed
 #[derive(Debug)]
-pub struct TickQueue<T: Eq + PartialEq> {
+pub struct TickQueue<T> {
     queue: BinaryHeap<ItemContainer<T>>,
 }
 
-impl<T: Eq + PartialEq> TickQueue<T> {
+impl<T> TickQueue<T> {
     /// Create a new TimeQueue
     pub fn new() -> Self {
         TickQueue {
@@ -22,8 +22,25 @@ impl<T: Eq + PartialEq> TickQueue<T> {
         self.queue.push(ItemContainer { tick, item });
     }
 
+    /// Gets the number of items in the queue, whether their tick has elapsed
+    /// or not
+    pub fn len(&self) -> usize {
+        return self.queue.len();
+    }
+
+    /// Gets the number of items in the queue whose tick has elapsed
+    pub fn ready_count(&self, current_tick: u16) -> usize {
+        return self
+            .queue
+            .iter()
+            .filter(|item| {
+                item.tick == current_tick || sequence_greater_than(current_tick, item.tick)
+            })
+            .count();
+    }
+
     /// Returns whether or not there is an item that is ready to be returned
-    fn has_item(&self, current_tick: u16) -> bool {
+    pub fn has_item(&self, current_tick: u16) -> bool {
         if self.queue.len() == 0 {
             return false;
         }
@@ -44,13 +61,13 @@ impl<T: Eq + PartialEq> TickQueue<T> {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct ItemContainer<T: Eq + PartialEq> {
+#[derive(Debug)]
+pub struct ItemContainer<T> {
     pub tick: u16,
     pub item: T,
 }
 
-impl<T: Eq + PartialEq> Ord for ItemContainer<T> {
+impl<T> Ord for ItemContainer<T> {
     fn cmp(&self, other: &ItemContainer<T>) -> Ordering {
         if self.tick == other.tick {
             return Ordering::Equal;
@@ -63,7 +80,17 @@ impl<T: Eq + PartialEq> Ord for ItemContainer<T> {
     }
 }
 
-impl<T: Eq + PartialEq> PartialOrd for ItemContainer<T> {
+// items are ordered by their tick alone, so items of the same tick are equal
+// however they differ
+impl<T> PartialEq for ItemContainer<T> {
+    fn eq(&self, other: &ItemContainer<T>) -> bool {
+        return self.tick == other.tick;
+    }
+}
+
+impl<T> Eq for ItemContainer<T> {}
+
+impl<T> PartialOrd for ItemContainer<T> {
     fn partial_cmp(&self, other: &ItemContainer<T>) -> Option<Ordering> {
         Some(self.cmp(other))
     }
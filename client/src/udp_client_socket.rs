@@ -0,0 +1,204 @@
+use std::{
+    io::{ErrorKind, Result as IoResult},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+};
+
+use naia_client_socket::{
+    ClientSocketTrait, LinkConditionerConfig, MessageSender, NaiaClientSocketError, Packet,
+};
+use naia_socket_shared::{link_condition_logic, Ref, TimeQueue};
+use socket2::{Domain, Socket, Type};
+
+/// A UDP socket which the Client sends & receives through, bound to the
+/// unspecified address of the Server's own family. naia-client-socket's
+/// socket binds to the host's IPv4 address, so can't reach a Server at an
+/// IPv6 address, nor mark the packets it sends
+#[derive(Debug)]
+pub struct UdpClientSocket {
+    server_address: SocketAddr,
+    socket: Ref<UdpSocket>,
+    receive_buffer: Vec<u8>,
+    message_sender: MessageSender,
+}
+
+impl UdpClientSocket {
+    /// Binds a new UdpClientSocket to an unused port, for talking to the
+    /// Server at the given address, along with a DSCP value to mark outgoing
+    /// packets with. Returns an Error if it can't be bound, such as on a host
+    /// without IPv6 support, or the DSCP value can't be applied
+    pub fn connect(
+        server_address: SocketAddr,
+        dscp: Option<u8>,
+    ) -> IoResult<Box<dyn ClientSocketTrait>> {
+        let socket = bind(server_address, dscp)?;
+        let socket = Ref::new(socket);
+        let message_sender = MessageSender::new(server_address, socket.clone());
+
+        return Ok(Box::new(UdpClientSocket {
+            server_address,
+            socket,
+            receive_buffer: vec![0; 0x10000],
+            message_sender,
+        }));
+    }
+}
+
+impl ClientSocketTrait for UdpClientSocket {
+    fn receive(&mut self) -> Result<Option<Packet>, NaiaClientSocketError> {
+        loop {
+            match self.socket.borrow().recv_from(&mut self.receive_buffer) {
+                Ok((length, address)) => {
+                    // anything not from the Server is ignored
+                    if address == self.server_address {
+                        return Ok(Some(Packet::new(self.receive_buffer[..length].to_vec())));
+                    }
+                }
+                Err(ref error) if error.kind() == ErrorKind::WouldBlock => {
+                    return Ok(None);
+                }
+                Err(error) => {
+                    return Err(NaiaClientSocketError::Wrapped(Box::new(error)));
+                }
+            }
+        }
+    }
+
+    fn get_sender(&mut self) -> MessageSender {
+        return self.message_sender.clone();
+    }
+
+    fn with_link_conditioner(
+        self: Box<Self>,
+        config: &LinkConditionerConfig,
+    ) -> Box<dyn ClientSocketTrait> {
+        return Box::new(LinkConditioner {
+            config: config.clone(),
+            inner_socket: self,
+            time_queue: TimeQueue::new(),
+        });
+    }
+}
+
+// binds a non-blocking socket to an unused port of the unspecified address of
+// the Server's family, marking the packets it sends with the DSCP value
+fn bind(server_address: SocketAddr, dscp: Option<u8>) -> IoResult<UdpSocket> {
+    let unspecified: IpAddr = match server_address {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = Socket::new(Domain::for_address(server_address), Type::DGRAM, None)?;
+    if let Some(dscp) = dscp {
+        // the DSCP is the upper six bits of the IPv4 type of service
+        socket.set_tos((dscp as u32) << 2)?;
+    }
+    socket.bind(&SocketAddr::new(unspecified, 0).into())?;
+    socket.set_nonblocking(true)?;
+    return Ok(UdpSocket::from(socket));
+}
+
+// holds back incoming packets to simulate the configured network conditions,
+// as naia-client-socket's own link conditioner does for its sockets
+#[derive(Debug)]
+struct LinkConditioner {
+    config: LinkConditionerConfig,
+    inner_socket: Box<dyn ClientSocketTrait>,
+    time_queue: TimeQueue<Packet>,
+}
+
+impl ClientSocketTrait for LinkConditioner {
+    fn receive(&mut self) -> Result<Option<Packet>, NaiaClientSocketError> {
+        while let Some(packet) = self.inner_socket.receive()? {
+            link_condition_logic::process_packet(&self.config, &mut self.time_queue, packet);
+        }
+        if self.time_queue.has_item() {
+            return Ok(self.time_queue.pop_item());
+        }
+        return Ok(None);
+    }
+
+    fn get_sender(&mut self) -> MessageSender {
+        return self.inner_socket.get_sender();
+    }
+
+    fn with_link_conditioner(
+        self: Box<Self>,
+        config: &LinkConditionerConfig,
+    ) -> Box<dyn ClientSocketTrait> {
+        return Box::new(LinkConditioner {
+            config: config.clone(),
+            inner_socket: self,
+            time_queue: TimeQueue::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::UdpSocket, thread::sleep, time::Duration};
+
+    use naia_client_socket::Packet;
+    use socket2::SockRef;
+
+    use super::{bind, UdpClientSocket};
+
+    const EXPEDITED_FORWARDING: u8 = 46;
+
+    #[test]
+    fn talks_to_an_ipv6_server() {
+        // the platform has no IPv6 support
+        let server = match UdpSocket::bind("[::1]:0") {
+            Ok(server) => server,
+            Err(_) => return,
+        };
+        let server_address = server.local_addr().unwrap();
+        let mut socket = UdpClientSocket::connect(server_address, None).unwrap();
+
+        socket
+            .get_sender()
+            .send(Packet::new(vec![1, 2, 3]))
+            .unwrap();
+        let mut buffer = [0; 8];
+        let (length, client_address) = server.recv_from(&mut buffer).unwrap();
+        assert_eq!(&buffer[..length], &[1, 2, 3]);
+        assert!(client_address.is_ipv6());
+
+        // packets from anywhere but the Server are ignored
+        let stranger = UdpSocket::bind("[::1]:0").unwrap();
+        stranger.send_to(&[9], client_address).unwrap();
+        server.send_to(&[4, 5], client_address).unwrap();
+        let mut received = None;
+        for _ in 0..100 {
+            received = socket.receive().unwrap();
+            if received.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        assert_eq!(received.unwrap().payload(), &[4, 5]);
+        assert!(socket.receive().unwrap().is_none());
+    }
+
+    #[test]
+    fn dscp_marks_packets_to_an_ipv4_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_address = server.local_addr().unwrap();
+        let unmarked = bind(server_address, None).unwrap();
+        assert_eq!(SockRef::from(&unmarked).tos().unwrap(), 0);
+        let marked = bind(server_address, Some(EXPEDITED_FORWARDING)).unwrap();
+        assert_eq!(
+            SockRef::from(&marked).tos().unwrap(),
+            u32::from(EXPEDITED_FORWARDING) << 2
+        );
+
+        // marked packets still reach the Server over loopback
+        let mut socket =
+            UdpClientSocket::connect(server_address, Some(EXPEDITED_FORWARDING)).unwrap();
+        socket
+            .get_sender()
+            .send(Packet::new(vec![1, 2, 3]))
+            .unwrap();
+        let mut buffer = [0; 8];
+        let (length, _) = server.recv_from(&mut buffer).unwrap();
+        assert_eq!(&buffer[..length], &[1, 2, 3]);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use naia_shared::Instant;
+
+// the window the upload rate is measured over, in milliseconds
+const WINDOW_MILLIS: u32 = 1000;
+
+// the share of the budget used past which Command redundancy is lowered, and
+// half of which it must drop to before redundancy is raised again, so that an
+// upload rate hovering around the limit doesn't flap between levels
+const SOFT_LIMIT: f32 = 0.8;
+
+/// Limits how many bytes per second the Client sends to the Server, for
+/// players on links with little upstream bandwidth. Nothing reliable is held
+/// back to keep to the budget: instead, Commands are sent with fewer past
+/// Commands alongside them as the budget is used up, and with more again once
+/// there is room
+#[derive(Debug)]
+pub struct UploadBudget {
+    max_bytes_per_second: u32,
+    // the size of each packet sent within the window, oldest first, along
+    // with when it leaves the window
+    sent: VecDeque<(usize, Instant)>,
+    sent_bytes: usize,
+    min_command_redundancy: u8,
+    max_command_redundancy: u8,
+    command_redundancy: u8,
+    // after each change of redundancy, the next waits until the packets sent
+    // before it have left the window
+    next_change_at: Option<Instant>,
+}
+
+impl UploadBudget {
+    /// Create a new UploadBudget, given the most bytes per second to send, and
+    /// the bounds Command redundancy is kept within
+    pub fn new(
+        max_bytes_per_second: u32,
+        min_command_redundancy: u8,
+        max_command_redundancy: u8,
+    ) -> Self {
+        UploadBudget {
+            max_bytes_per_second,
+            sent: VecDeque::new(),
+            sent_bytes: 0,
+            min_command_redundancy,
+            max_command_redundancy,
+            command_redundancy: max_command_redundancy,
+            next_change_at: None,
+        }
+    }
+
+    /// Records a packet of the given size having been sent
+    pub fn record_sent(&mut self, bytes: usize, now: &Instant) {
+        let mut leaves_at = now.clone();
+        leaves_at.add_millis(WINDOW_MILLIS);
+        self.sent.push_back((bytes, leaves_at));
+        self.sent_bytes += bytes;
+    }
+
+    /// Drops packets which have left the window from the upload rate, and
+    /// lowers or raises Command redundancy by a step, if the share of the
+    /// budget used calls for it
+    pub fn update(&mut self, now: &Instant) {
+        while let Some((bytes, leaves_at)) = self.sent.front() {
+            if now < leaves_at {
+                break;
+            }
+            self.sent_bytes -= *bytes;
+            self.sent.pop_front();
+        }
+
+        if let Some(next_change_at) = &self.next_change_at {
+            if now < next_change_at {
+                return;
+            }
+        }
+        let utilization = self.get_utilization();
+        if utilization > SOFT_LIMIT && self.command_redundancy > self.min_command_redundancy {
+            self.command_redundancy -= 1;
+        } else if utilization <= SOFT_LIMIT / 2.0
+            && self.command_redundancy < self.max_command_redundancy
+        {
+            self.command_redundancy += 1;
+        } else {
+            return;
+        }
+        let mut next_change_at = now.clone();
+        next_change_at.add_millis(WINDOW_MILLIS);
+        self.next_change_at = Some(next_change_at);
+    }
+
+    /// Gets the most bytes per second to send
+    pub fn get_max_bytes_per_second(&self) -> u32 {
+        return self.max_bytes_per_second;
+    }
+
+    /// Gets the share of the budget used over the last second, which is above
+    /// 1 if more has been sent than the budget allows
+    pub fn get_utilization(&self) -> f32 {
+        if self.max_bytes_per_second == 0 {
+            return 0.0;
+        }
+        return self.sent_bytes as f32 / self.max_bytes_per_second as f32;
+    }
+
+    /// Gets the most past Commands to send along with each Command, while
+    /// keeping to the budget
+    pub fn get_command_redundancy(&self) -> u8 {
+        return self.command_redundancy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{Clock, SystemClock};
+
+    use super::UploadBudget;
+
+    const MAX_COMMAND_REDUNDANCY: u8 = 2;
+
+    #[test]
+    fn redundancy_steps_down_as_the_budget_is_used() {
+        let mut now = SystemClock.now();
+        let mut budget = UploadBudget::new(1000, 0, MAX_COMMAND_REDUNDANCY);
+        budget.record_sent(500, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_command_redundancy(), MAX_COMMAND_REDUNDANCY);
+
+        budget.record_sent(400, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_utilization(), 0.9);
+        assert_eq!(budget.get_command_redundancy(), 1);
+
+        // the rate isn't judged again until the packets sent at the old
+        // redundancy have left the window
+        budget.record_sent(400, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_command_redundancy(), 1);
+        now.add_millis(999);
+        budget.record_sent(900, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_command_redundancy(), 1);
+
+        now.add_millis(1);
+        budget.update(&now);
+        assert_eq!(budget.get_utilization(), 0.9);
+        assert_eq!(budget.get_command_redundancy(), 0);
+    }
+
+    #[test]
+    fn redundancy_recovers_once_there_is_room() {
+        let mut now = SystemClock.now();
+        let mut budget = UploadBudget::new(1000, 0, MAX_COMMAND_REDUNDANCY);
+        budget.record_sent(2000, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_command_redundancy(), 1);
+
+        // sending at 60% of the budget is too close to the limit to raise
+        // redundancy, or to lower it further
+        now.add_millis(1000);
+        budget.record_sent(600, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_command_redundancy(), 1);
+
+        now.add_millis(1000);
+        budget.record_sent(400, &now);
+        budget.update(&now);
+        assert_eq!(budget.get_command_redundancy(), MAX_COMMAND_REDUNDANCY);
+    }
+}
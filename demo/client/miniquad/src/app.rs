@@ -47,7 +47,8 @@ impl App {
                 Some(client_config),
                 get_shared_config(),
                 Some(auth),
-            ),
+            )
+            .expect("the auth Event type should be registered in the manifest"),
             server_event_count: 0,
         }
     }
@@ -59,7 +60,7 @@ impl App {
             if let Some(result) = self.client.receive() {
                 match result {
                     Ok(event) => match event {
-                        ClientEvent::Connection => {
+                        ClientEvent::Connection(_) => {
                             info!("Client connected to: {}", self.client.server_address());
                         }
                         ClientEvent::Disconnection => {
@@ -75,7 +76,9 @@ impl App {
                                 info!("Client send: {}", new_message);
 
                                 let string_event = StringEvent::new(new_message);
-                                self.client.send_event(&string_event);
+                                if let Err(err) = self.client.send_event(&string_event) {
+                                    info!("Client Error: {}", err);
+                                }
                                 self.server_event_count += 1;
                             }
                             _ => {}
@@ -95,7 +98,7 @@ impl App {
                                 }
                             }
                         }
-                        ClientEvent::UpdateActor(local_key) => {
+                        ClientEvent::UpdateActor(local_key, _) => {
                             if let Some(actor) = self.client.get_actor(&local_key) {
                                 match actor {
                                     ExampleActor::PointActor(point_actor) => {
@@ -109,7 +112,7 @@ impl App {
                                 }
                             }
                         }
-                        ClientEvent::DeleteActor(local_key) => {
+                        ClientEvent::DeleteActor(local_key, _) => {
                             info!("deletion of point actor with key: {}", local_key);
                         }
                         ClientEvent::Tick => {
@@ -0,0 +1,88 @@
+#[macro_use]
+extern crate log;
+
+use simple_logger;
+use smol::io;
+
+use naia_server::{NaiaServer, ServerConfig, ServerEvent, UserKey};
+
+use naia_example_shared::{get_shared_config, manifest_load, StringEvent};
+
+use std::net::{IpAddr, SocketAddr};
+
+const DEFAULT_SERVER_PORT: u16 = 14191;
+
+// A relay, which forwards every StringEvent received from a Client to every
+// other connected Client, without ever reading it
+fn main() -> io::Result<()> {
+    let port: u16 = {
+        let args: Vec<String> = std::env::args().collect();
+        if args.len() > 1 {
+            args[1]
+                .parse()
+                .expect("Argument must be a valid u16 integer")
+        } else {
+            DEFAULT_SERVER_PORT
+        }
+    };
+
+    smol::block_on(async {
+        simple_logger::init_with_level(log::Level::Info).expect("A logger was already initialized");
+
+        info!("Naia Relay Example Started");
+
+        let server_ip_address: IpAddr = "127.0.0.1"
+            .parse()
+            .expect("couldn't parse input IP address");
+        let current_socket_address = SocketAddr::new(server_ip_address, port);
+
+        let mut server = NaiaServer::new(
+            current_socket_address,
+            manifest_load(),
+            Some(ServerConfig::default()),
+            get_shared_config(),
+        )
+        .await;
+
+        // every Event arrives with its size, so StringEvents are handed over as
+        // the bytes they were written as, without being read
+        server
+            .receive_raw::<StringEvent>()
+            .expect("StringEvent is registered in the Manifest");
+
+        loop {
+            match server.receive().await {
+                Ok(event) => match event {
+                    ServerEvent::Connection(user_key, _) => {
+                        if let Some(user) = server.get_user(&user_key) {
+                            info!("Naia Relay connected to: {}", user.address);
+                        }
+                    }
+                    ServerEvent::Disconnection(_, user) => {
+                        info!("Naia Relay disconnected from: {:?}", user.address);
+                    }
+                    ServerEvent::RawEvent(sender_key, raw_event) => {
+                        let recipients: Vec<UserKey> = server
+                            .users_iter()
+                            .map(|(user_key, _)| user_key)
+                            .filter(|user_key| *user_key != sender_key)
+                            .collect();
+                        for user_key in recipients {
+                            if let Err(error) = server.queue_raw_event(&user_key, &raw_event, true)
+                            {
+                                info!("Naia Relay Error: {}", error);
+                            }
+                        }
+                    }
+                    ServerEvent::Tick => {
+                        server.send_all_updates().await;
+                    }
+                    _ => {}
+                },
+                Err(error) => {
+                    info!("Naia Relay Error: {}", error);
+                }
+            }
+        }
+    })
+}
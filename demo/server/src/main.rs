@@ -4,7 +4,7 @@ extern crate log;
 use simple_logger;
 use smol::io;
 
-use naia_server::{NaiaServer, ServerConfig, ServerEvent, UserKey};
+use naia_server::{NaiaError, NaiaServer, ServerConfig, ServerEvent, UserKey};
 
 use naia_example_shared::{
     get_shared_config, manifest_load, ExampleActor, ExampleEvent, PointActor, StringEvent,
@@ -13,7 +13,7 @@ use naia_example_shared::{
 use std::{
     net::{IpAddr, SocketAddr},
     rc::Rc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 const DEFAULT_SERVER_PORT: u16 = 14191;
@@ -62,6 +62,7 @@ fn main() -> io::Result<()> {
         // provided
         server.on_auth(Rc::new(Box::new(|_, auth_type| {
             if let ExampleEvent::AuthEvent(auth_event) = auth_type {
+                // the accepted auth event can be read back later, from the User's UserRef
                 let username = auth_event.username.get();
                 let password = auth_event.password.get();
                 return username == "charlie" && password == "12345";
@@ -86,8 +87,10 @@ fn main() -> io::Result<()> {
             {
                 count += 1;
                 let point_actor = PointActor::new((count * 4) as u8, 0, first, last).wrap();
-                let actor_key = server.register_actor(ExampleActor::PointActor(point_actor));
-                server.room_add_actor(&main_room_key, &actor_key);
+                match server.register_actor(ExampleActor::PointActor(point_actor)) {
+                    Ok(actor_key) => server.room_add_actor(&main_room_key, &actor_key),
+                    Err(error) => info!("Naia Server Error: {}", error),
+                }
             }
         }
 
@@ -104,12 +107,13 @@ fn main() -> io::Result<()> {
         })));
 
         let mut tick_count: u32 = 0;
+        let mut last_status_print = Instant::now();
 
         loop {
             match server.receive().await {
                 Ok(event) => {
                     match event {
-                        ServerEvent::Connection(user_key) => {
+                        ServerEvent::Connection(user_key, _) => {
                             server.room_add_user(&main_room_key, &user_key);
                             if let Some(user) = server.get_user(&user_key) {
                                 info!("Naia Server connected to: {}", user.address);
@@ -130,7 +134,9 @@ fn main() -> io::Result<()> {
                             }
                         }
                         ServerEvent::Tick => {
-                            // Game logic, updating of the world, should happen here
+                            // Game logic, updating of the world, should happen here. This
+                            // runs exactly once per Server tick; if the Server falls behind,
+                            // several Tick events will be received in a row to catch up
 
                             // Event Sending
                             let mut iter_vec: Vec<UserKey> = Vec::new();
@@ -143,7 +149,15 @@ fn main() -> io::Result<()> {
                                 info!("Naia Server send -> {}: {}", user.address, new_message);
 
                                 let string_event = StringEvent::new(new_message);
-                                server.queue_event(&user_key, &string_event);
+                                match server.queue_event(&user_key, &string_event) {
+                                    Ok(()) => {}
+                                    // the User's connection is congested, this Event can
+                                    // be skipped
+                                    Err(NaiaError::ChannelFull) => {}
+                                    Err(error) => {
+                                        info!("Naia Server Error: {}", error);
+                                    }
+                                }
                             }
 
                             // Iterate through Point Actors, marching them from (0,0) to (20, N)
@@ -161,6 +175,33 @@ fn main() -> io::Result<()> {
                             server.send_all_updates().await;
 
                             tick_count = tick_count.wrapping_add(1);
+
+                            // Print a status table of all connections every 5 seconds
+                            if last_status_print.elapsed() >= Duration::from_secs(5) {
+                                last_status_print = Instant::now();
+                                info!(
+                                    "{:>20} {:>21} {:>8} {:>8} {:>10} {:>10} {:>10}",
+                                    "id", "address", "uptime", "rtt", "sent", "received", "user"
+                                );
+                                for user in server.users() {
+                                    let username = match user.auth_event() {
+                                        Some(ExampleEvent::AuthEvent(auth_event)) => {
+                                            auth_event.username.get().clone()
+                                        }
+                                        _ => String::new(),
+                                    };
+                                    info!(
+                                        "{:>20} {:>21} {:>7}s {:>6.1}ms {:>10} {:>10} {:>10}",
+                                        user.connection_id(),
+                                        user.address(),
+                                        user.uptime().as_secs(),
+                                        user.rtt(),
+                                        user.bytes_sent(),
+                                        user.bytes_received(),
+                                        username
+                                    );
+                                }
+                            }
                         }
                         _ => {}
                     }
@@ -2,17 +2,13 @@ extern crate log;
 extern crate naia_derive;
 
 mod auth_event;
-mod example_actor;
-mod example_event;
-mod manifest_load;
 mod point_actor;
+mod protocol;
 mod shared_config;
 mod string_event;
 
 pub use auth_event::AuthEvent;
-pub use example_actor::ExampleActor;
-pub use example_event::ExampleEvent;
-pub use manifest_load::manifest_load;
 pub use point_actor::PointActor;
+pub use protocol::{manifest_load, ExampleActor, ExampleEvent};
 pub use shared_config::get_shared_config;
 pub use string_event::StringEvent;
@@ -0,0 +1,16 @@
+use naia_derive::protocol;
+
+use crate::{AuthEvent, PointActor, StringEvent};
+
+protocol! {
+    pub events ExampleEvent {
+        AuthEvent,
+        // written along with their size, so a relay can forward them unread
+        #[passthrough]
+        StringEvent,
+    }
+    pub actors ExampleActor {
+        PointActor,
+    }
+    pub fn manifest_load;
+}
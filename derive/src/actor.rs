@@ -4,7 +4,7 @@ use syn::{
     parse_macro_input, Data, DeriveInput, Fields, GenericArgument, Ident, PathArguments, Type,
 };
 
-use super::utils;
+use super::utils::{self, PropertyVersion};
 
 pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -17,6 +17,8 @@ pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let type_name = utils::get_type_name(&input, "Actor");
 
     let properties = utils::get_properties(&input);
+    let property_versions = utils::get_property_versions(&input);
+    let schema_version = utils::get_schema_version(&property_versions);
     let interpolated_properties = get_interpolated_properties(&input);
     let predicted_properties = get_predicted_properties(&input);
 
@@ -24,10 +26,23 @@ pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let property_enum = get_property_enum(&enum_name, &properties);
 
     let new_complete_method = get_new_complete_method(actor_name, &enum_name, &properties);
-    let read_to_type_method =
-        get_read_to_type_method(&type_name, actor_name, &enum_name, &properties);
+    let read_to_type_method = get_read_to_type_method(
+        &type_name,
+        actor_name,
+        &enum_name,
+        &properties,
+        &property_versions,
+    );
     let actor_write_method = utils::get_write_method(&properties);
+    let actor_write_versioned_method =
+        utils::get_write_versioned_method(&properties, &property_versions);
     let actor_write_partial_method = get_write_partial_method(&enum_name, &properties);
+    let actor_write_partial_versioned_method =
+        get_write_partial_versioned_method(&enum_name, &properties, &property_versions);
+    let actor_write_masked_method =
+        get_write_masked_method(&enum_name, &properties, &property_versions);
+    let actor_write_masked_versioned_method =
+        get_write_masked_versioned_method(&enum_name, &properties, &property_versions);
     let actor_read_full_method = get_read_full_method(&properties);
     let actor_read_partial_method = get_read_partial_method(&enum_name, &properties);
     let set_mutator_method = get_set_mutator_method(&properties);
@@ -39,6 +54,15 @@ pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let is_interpolated_method = get_is_interpolated_method(&predicted_properties);
     let is_predicted_method = get_is_predicted_method(&predicted_properties);
     let mirror_method = get_mirror_method(actor_name, &properties);
+    let get_actor_refs_method = utils::get_get_actor_refs_method(&properties);
+    let describe_method = utils::get_describe_method(
+        actor_name,
+        &properties,
+        &property_versions,
+        quote! { None },
+        &interpolated_properties,
+        &predicted_properties,
+    );
 
     let state_mask_size: u8 = (((properties.len() - 1) / 8) + 1) as u8;
 
@@ -54,7 +78,16 @@ pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 return self.type_id;
             }
             fn build(&self, reader: &mut PacketReader) -> #type_name {
-                return #actor_name::read_to_type(reader);
+                return #actor_name::read_to_type(reader, #schema_version);
+            }
+            fn get_schema_version(&self) -> u16 {
+                return #schema_version;
+            }
+            fn build_versioned(&self, reader: &mut PacketReader, version: u16) -> #type_name {
+                return #actor_name::read_to_type(reader, version);
+            }
+            fn describe(&self) -> Option<naia_shared::TypeDescription> {
+                return Some(#actor_name::describe());
             }
         }
         impl #actor_name {
@@ -68,6 +101,7 @@ pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
             #new_complete_method
             #read_to_type_method
+            #describe_method
         }
         impl Actor<#type_name> for #actor_name {
             fn get_state_mask_size(&self) -> u8 { #state_mask_size }
@@ -76,12 +110,17 @@ pub fn actor_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
             #set_mutator_method
             #actor_write_method
+            #actor_write_versioned_method
             #actor_write_partial_method
+            #actor_write_partial_versioned_method
+            #actor_write_masked_method
+            #actor_write_masked_versioned_method
             #actor_read_full_method
             #actor_read_partial_method
             #get_typed_copy_method
             #is_interpolated_method
             #is_predicted_method
+            #get_actor_refs_method
         }
         impl ActorEq<#type_name> for #actor_name {
             #equals_method
@@ -119,7 +158,7 @@ fn get_property_enum(enum_name: &Ident, properties: &Vec<(Ident, Type)>) -> Toke
 
     return quote! {
         #hashtag[repr(u8)]
-        enum #enum_name {
+        pub enum #enum_name {
             #variant_list
         }
     };
@@ -193,6 +232,7 @@ fn get_read_to_type_method(
     actor_name: &Ident,
     enum_name: &Ident,
     properties: &Vec<(Ident, Type)>,
+    property_versions: &Vec<PropertyVersion>,
 ) -> TokenStream {
     let mut prop_names = quote! {};
     for (field_name, _) in properties.iter() {
@@ -207,15 +247,28 @@ fn get_read_to_type_method(
     }
 
     let mut prop_reads = quote! {};
-    for (field_name, field_type) in properties.iter() {
+    for ((field_name, field_type), property_version) in
+        properties.iter().zip(property_versions.iter())
+    {
         let uppercase_variant_name = Ident::new(
             field_name.to_string().to_uppercase().as_str(),
             Span::call_site(),
         );
 
-        let new_output_right = quote! {
-            let mut #field_name = Property::<#field_type>::new(Default::default(), #enum_name::#uppercase_variant_name as u8);
-            #field_name.read(reader, 1);
+        let new_output_right = match property_version.version {
+            1 => quote! {
+                let mut #field_name = Property::<#field_type>::new(Default::default(), #enum_name::#uppercase_variant_name as u8);
+                #field_name.read(reader, 1);
+            },
+            added_version => {
+                let default = utils::get_property_default(property_version);
+                quote! {
+                    let mut #field_name = Property::<#field_type>::new(#default, #enum_name::#uppercase_variant_name as u8);
+                    if version >= #added_version {
+                        #field_name.read(reader, 1);
+                    }
+                }
+            }
         };
         let new_output_result = quote! {
             #prop_reads
@@ -224,8 +277,10 @@ fn get_read_to_type_method(
         prop_reads = new_output_result;
     }
 
+    let version_arg = utils::get_version_arg(property_versions);
+
     return quote! {
-        fn read_to_type(reader: &mut PacketReader) -> #type_name {
+        fn read_to_type(reader: &mut PacketReader, #version_arg: u16) -> #type_name {
             #prop_reads
 
             return #type_name::#actor_name(Rc::new(RefCell::new(#actor_name {
@@ -288,6 +343,134 @@ fn get_write_partial_method(enum_name: &Ident, properties: &Vec<(Ident, Type)>)
     };
 }
 
+fn get_write_partial_versioned_method(
+    enum_name: &Ident,
+    properties: &Vec<(Ident, Type)>,
+    property_versions: &Vec<PropertyVersion>,
+) -> TokenStream {
+    let mut output = quote! {};
+
+    for ((field_name, _), property_version) in properties.iter().zip(property_versions.iter()) {
+        let uppercase_variant_name = Ident::new(
+            field_name.to_string().to_uppercase().as_str(),
+            Span::call_site(),
+        );
+
+        let new_output_right = match property_version.version {
+            1 => quote! {
+                if let Some(true) = state_mask.get_bit(#enum_name::#uppercase_variant_name as u8) {
+                    Property::write(&self.#field_name, buffer);
+                }
+            },
+            added_version => quote! {
+                if version >= #added_version {
+                    if let Some(true) = state_mask.get_bit(#enum_name::#uppercase_variant_name as u8) {
+                        Property::write(&self.#field_name, buffer);
+                    }
+                }
+            },
+        };
+        let new_output_result = quote! {
+            #output
+            #new_output_right
+        };
+        output = new_output_result;
+    }
+
+    let version_arg = utils::get_version_arg(property_versions);
+
+    return quote! {
+        fn write_partial_versioned(&self, state_mask: &StateMask, #version_arg: u16, buffer: &mut Vec<u8>) {
+            #output
+        }
+    };
+}
+
+// Gets the write of a single Property for a masked write, which writes its
+// default value in place of its own if it's hidden
+fn get_masked_property_write(
+    enum_name: &Ident,
+    field_name: &Ident,
+    field_type: &Type,
+    property_version: &PropertyVersion,
+) -> TokenStream {
+    let uppercase_variant_name = Ident::new(
+        field_name.to_string().to_uppercase().as_str(),
+        Span::call_site(),
+    );
+    let default = utils::get_property_default(property_version);
+
+    return quote! {
+        if let Some(true) = visibility.get_bit(#enum_name::#uppercase_variant_name as u8) {
+            Property::write(&self.#field_name, buffer);
+        } else {
+            Property::write(&Property::<#field_type>::new(#default, #enum_name::#uppercase_variant_name as u8), buffer);
+        }
+    };
+}
+
+fn get_write_masked_method(
+    enum_name: &Ident,
+    properties: &Vec<(Ident, Type)>,
+    property_versions: &Vec<PropertyVersion>,
+) -> TokenStream {
+    let mut output = quote! {};
+
+    for ((field_name, field_type), property_version) in
+        properties.iter().zip(property_versions.iter())
+    {
+        let new_output_right =
+            get_masked_property_write(enum_name, field_name, field_type, property_version);
+        let new_output_result = quote! {
+            #output
+            #new_output_right
+        };
+        output = new_output_result;
+    }
+
+    return quote! {
+        fn write_masked(&self, visibility: &StateMask, buffer: &mut Vec<u8>) {
+            #output
+        }
+    };
+}
+
+fn get_write_masked_versioned_method(
+    enum_name: &Ident,
+    properties: &Vec<(Ident, Type)>,
+    property_versions: &Vec<PropertyVersion>,
+) -> TokenStream {
+    let mut output = quote! {};
+
+    for ((field_name, field_type), property_version) in
+        properties.iter().zip(property_versions.iter())
+    {
+        let property_write =
+            get_masked_property_write(enum_name, field_name, field_type, property_version);
+        let new_output_right = match property_version.version {
+            1 => property_write,
+            added_version => quote! {
+                if version >= #added_version {
+                    #property_write
+                }
+            },
+        };
+        let new_output_result = quote! {
+            #output
+            #new_output_right
+        };
+        output = new_output_result;
+    }
+
+    let version_arg = utils::get_version_arg(property_versions);
+
+    return quote! {
+        fn write_masked_versioned(&self, visibility: &StateMask, #version_arg: u16, buffer: &mut Vec<u8>) {
+            #output
+        }
+    };
+}
+
 fn get_read_full_method(properties: &Vec<(Ident, Type)>) -> TokenStream {
     let mut output = quote! {};
 
@@ -487,7 +670,12 @@ fn get_interpolated_properties(input: &DeriveInput) -> Vec<(Ident, Type)> {
     if let Data::Struct(data_struct) = &input.data {
         if let Fields::Named(fields_named) = &data_struct.fields {
             for field in fields_named.named.iter() {
-                for attr in field.attrs.iter() {
+                // #[added] can hold any expression, so isn't read as a Meta
+                for attr in field
+                    .attrs
+                    .iter()
+                    .filter(|attr| !attr.path.is_ident("added"))
+                {
                     match attr.parse_meta().unwrap() {
                         syn::Meta::Path(ref path)
                             if path.get_ident().unwrap().to_string() == "interpolate" =>
@@ -525,7 +713,12 @@ fn get_predicted_properties(input: &DeriveInput) -> Vec<(Ident, Type)> {
     if let Data::Struct(data_struct) = &input.data {
         if let Fields::Named(fields_named) = &data_struct.fields {
             for field in fields_named.named.iter() {
-                for attr in field.attrs.iter() {
+                // #[added] can hold any expression, so isn't read as a Meta
+                for attr in field
+                    .attrs
+                    .iter()
+                    .filter(|attr| !attr.path.is_ident("added"))
+                {
                     match attr.parse_meta().unwrap() {
                         syn::Meta::Path(ref path)
                             if path.get_ident().unwrap().to_string() == "predict" =>
@@ -2,7 +2,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput, Ident, Type};
 
-use super::utils;
+use super::utils::{self, PropertyVersion};
 
 pub fn event_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -14,14 +14,30 @@ pub fn event_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     );
 
     let properties = utils::get_properties(&input);
+    let property_versions = utils::get_property_versions(&input);
+    let schema_version = utils::get_schema_version(&property_versions);
 
     let type_name = utils::get_type_name(&input, "Event");
 
     let event_write_method = utils::get_write_method(&properties);
+    let event_write_versioned_method =
+        utils::get_write_versioned_method(&properties, &property_versions);
+
+    let get_actor_refs_method = utils::get_get_actor_refs_method(&properties);
 
     let new_complete_method = get_new_complete_method(event_name, &properties);
 
-    let read_to_type_method = get_read_to_type_method(&type_name, event_name, &properties);
+    let read_to_type_method =
+        get_read_to_type_method(&type_name, event_name, &properties, &property_versions);
+
+    let describe_method = utils::get_describe_method(
+        event_name,
+        &properties,
+        &property_versions,
+        quote! { Some(#event_name::is_guaranteed()) },
+        &Vec::new(),
+        &Vec::new(),
+    );
 
     let gen = quote! {
         use std::{any::TypeId, io::Cursor};
@@ -34,7 +50,16 @@ pub fn event_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 return self.type_id;
             }
             fn build(&self, reader: &mut PacketReader) -> #type_name {
-                return #event_name::read_to_type(reader);
+                return #event_name::read_to_type(reader, #schema_version);
+            }
+            fn get_schema_version(&self) -> u16 {
+                return #schema_version;
+            }
+            fn build_versioned(&self, reader: &mut PacketReader, version: u16) -> #type_name {
+                return #event_name::read_to_type(reader, version);
+            }
+            fn describe(&self) -> Option<naia_shared::TypeDescription> {
+                return Some(#event_name::describe());
             }
         }
         impl #event_name {
@@ -45,18 +70,21 @@ pub fn event_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
             #new_complete_method
             #read_to_type_method
+            #describe_method
         }
         impl Event<#type_name> for #event_name {
             fn is_guaranteed(&self) -> bool {
                 #event_name::is_guaranteed()
             }
             #event_write_method
+            #event_write_versioned_method
             fn get_typed_copy(&self) -> #type_name {
                 return #type_name::#event_name(self.clone());
             }
             fn get_type_id(&self) -> TypeId {
                 return TypeId::of::<#event_name>();
             }
+            #get_actor_refs_method
         }
     };
 
@@ -100,6 +128,7 @@ fn get_read_to_type_method(
     type_name: &Ident,
     event_name: &Ident,
     properties: &Vec<(Ident, Type)>,
+    property_versions: &Vec<PropertyVersion>,
 ) -> TokenStream {
     let mut prop_names = quote! {};
     for (field_name, _) in properties.iter() {
@@ -114,10 +143,23 @@ fn get_read_to_type_method(
     }
 
     let mut prop_reads = quote! {};
-    for (field_name, field_type) in properties.iter() {
-        let new_output_right = quote! {
-            let mut #field_name = Property::<#field_type>::new(Default::default(), 0);
-            #field_name.read(reader, 1);
+    for ((field_name, field_type), property_version) in
+        properties.iter().zip(property_versions.iter())
+    {
+        let new_output_right = match property_version.version {
+            1 => quote! {
+                let mut #field_name = Property::<#field_type>::new(Default::default(), 0);
+                #field_name.read(reader, 1);
+            },
+            added_version => {
+                let default = utils::get_property_default(property_version);
+                quote! {
+                    let mut #field_name = Property::<#field_type>::new(#default, 0);
+                    if version >= #added_version {
+                        #field_name.read(reader, 1);
+                    }
+                }
+            }
         };
         let new_output_result = quote! {
             #prop_reads
@@ -126,8 +168,10 @@ fn get_read_to_type_method(
         prop_reads = new_output_result;
     }
 
+    let version_arg = utils::get_version_arg(property_versions);
+
     return quote! {
-        fn read_to_type(reader: &mut PacketReader) -> #type_name {
+        fn read_to_type(reader: &mut PacketReader, #version_arg: u16) -> #type_name {
             #prop_reads
 
             return #type_name::#event_name(#event_name {
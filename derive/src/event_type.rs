@@ -9,6 +9,7 @@ pub fn event_type_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStrea
 
     let write_variants = get_write_variants(&type_name, &input.data);
     let get_type_id_variants = get_type_id_variants(&type_name, &input.data);
+    let get_actor_refs_variants = get_actor_refs_variants(&type_name, &input.data);
 
     let gen = quote! {
         use std::any::TypeId;
@@ -24,6 +25,11 @@ pub fn event_type_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStrea
                     #get_type_id_variants
                 }
             }
+            fn get_actor_refs(&self) -> Vec<naia_shared::ActorRef> {
+                match self {
+                    #get_actor_refs_variants
+                }
+            }
         }
     };
 
@@ -75,3 +81,26 @@ fn get_type_id_variants(type_name: &Ident, data: &Data) -> TokenStream {
         _ => unimplemented!(),
     }
 }
+
+fn get_actor_refs_variants(type_name: &Ident, data: &Data) -> TokenStream {
+    match *data {
+        Data::Enum(ref data) => {
+            let mut output = quote! {};
+            for variant in data.variants.iter() {
+                let variant_name = &variant.ident;
+                let new_output_right = quote! {
+                    #type_name::#variant_name(idactor) => {
+                        return idactor.get_actor_refs();
+                    }
+                };
+                let new_output_result = quote! {
+                    #output
+                    #new_output_right
+                };
+                output = new_output_result;
+            }
+            output
+        }
+        _ => unimplemented!(),
+    }
+}
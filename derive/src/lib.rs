@@ -14,12 +14,14 @@ mod actor;
 mod actor_type;
 mod event;
 mod event_type;
+mod protocol;
 mod utils;
 
 use actor::actor_impl;
 use actor_type::actor_type_impl;
 use event::event_impl;
 use event_type::event_type_impl;
+use protocol::protocol_impl;
 
 /// Derives the ActorType trait for a given enum
 #[proc_macro_derive(ActorType)]
@@ -33,14 +35,47 @@ pub fn event_type_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStr
     event_type_impl(input)
 }
 
-/// Derives the Event trait for a given struct
-#[proc_macro_derive(Event, attributes(type_name))]
+/// Derives the Event trait for a given struct. Properties added after the
+/// first version of the struct are marked with
+/// `#[added(version = 2, default = ...)]`, and are left out when writing to a
+/// host with an older version, which reads them as the default instead
+#[proc_macro_derive(Event, attributes(type_name, added))]
 pub fn event_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     event_impl(input)
 }
 
-/// Derives the Actor trait for a given struct
-#[proc_macro_derive(Actor, attributes(type_name, interpolate, predict))]
+/// Derives the Actor trait for a given struct. Properties added after the
+/// first version of the struct are marked with
+/// `#[added(version = 2, default = ...)]`, and are left out when writing to a
+/// host with an older version, which reads them as the default instead
+#[proc_macro_derive(Actor, attributes(type_name, interpolate, predict, added))]
 pub fn actor_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     actor_impl(input)
 }
+
+/// Declares a whole protocol at once: the EventType & ActorType enums wrapping
+/// each listed Event & Actor type, and the function which registers them all
+/// in a Manifest, in the order listed. Each listed type is checked to
+/// implement the trait it's listed as, pointing at it in the list if it
+/// doesn't. Event types can be preceded by `#[channel(0)]`, to be registered
+/// on an ordered channel, `#[passthrough]`, `#[timestamped]`,
+/// `#[actor_ordered]` & `#[actor_scoped]`, which register them as the Manifest
+/// methods of the same names do
+///
+/// ```ignore
+/// protocol! {
+///     pub events ExampleEvent {
+///         AuthEvent,
+///         #[channel(0)]
+///         StringEvent,
+///     }
+///     pub actors ExampleActor {
+///         PointActor,
+///     }
+///     pub fn manifest_load;
+/// }
+/// ```
+#[proc_macro]
+pub fn protocol(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    protocol_impl(input)
+}
@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Ident, LitInt, Token, Visibility,
+};
+
+use super::{actor_type::actor_type_impl, event_type::event_type_impl};
+
+pub fn protocol_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let protocol = parse_macro_input!(input as Protocol);
+
+    let event_enum = get_event_enum(&protocol.events);
+    let actor_enum = get_actor_enum(&protocol.actors);
+    // the glue of each enum is derived as it would be by hand, kept in a block
+    // of its own so that the imports it brings in don't clash
+    let event_type_glue = TokenStream::from(event_type_impl(event_enum.clone().into()));
+    let actor_type_glue = TokenStream::from(actor_type_impl(actor_enum.clone().into()));
+    let trait_checks = get_trait_checks(&protocol);
+    let manifest_fn = get_manifest_fn(&protocol);
+
+    // the checks come first, so that their errors are too
+    let gen = quote! {
+        #trait_checks
+        #event_enum
+        const _: () = {
+            #event_type_glue
+        };
+        #actor_enum
+        const _: () = {
+            use std::{cell::RefCell, rc::Rc};
+            #actor_type_glue
+        };
+        #manifest_fn
+    };
+
+    proc_macro::TokenStream::from(gen)
+}
+
+// The input of `protocol!`: an `events` section, an `actors` section & a `fn`
+// naming the manifest-registration function, in any order
+struct Protocol {
+    events: Section<EventEntry>,
+    actors: Section<ActorEntry>,
+    manifest_fn: (Vec<Attribute>, Visibility, Ident),
+}
+
+// A section naming an enum, along with the types it wraps
+struct Section<E> {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    entries: Vec<E>,
+}
+
+// A listed Event type, along with how it's registered
+struct EventEntry {
+    name: Ident,
+    channel: Option<LitInt>,
+    passthrough: bool,
+    timestamped: bool,
+    actor_ordered: bool,
+    actor_scoped: bool,
+}
+
+// A listed Actor type
+struct ActorEntry {
+    name: Ident,
+}
+
+impl Parse for Protocol {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut events: Option<Section<EventEntry>> = None;
+        let mut actors: Option<Section<ActorEntry>> = None;
+        let mut manifest_fn: Option<(Vec<Attribute>, Visibility, Ident)> = None;
+
+        while !input.is_empty() {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis: Visibility = input.parse()?;
+            if input.peek(Token![fn]) {
+                let fn_token: Token![fn] = input.parse()?;
+                let name: Ident = input.parse()?;
+                input.parse::<Token![;]>()?;
+                if manifest_fn.is_some() {
+                    return Err(syn::Error::new(
+                        fn_token.span(),
+                        "protocol! takes a single `fn`",
+                    ));
+                }
+                manifest_fn = Some((attrs, vis, name));
+                continue;
+            }
+            let keyword: Ident = input.parse()?;
+            if keyword == "events" {
+                if events.is_some() {
+                    return Err(syn::Error::new(
+                        keyword.span(),
+                        "protocol! takes a single `events` section",
+                    ));
+                }
+                events = Some(parse_section(attrs, vis, input, parse_event_entry)?);
+            } else if keyword == "actors" {
+                if actors.is_some() {
+                    return Err(syn::Error::new(
+                        keyword.span(),
+                        "protocol! takes a single `actors` section",
+                    ));
+                }
+                actors = Some(parse_section(attrs, vis, input, parse_actor_entry)?);
+            } else {
+                return Err(syn::Error::new(
+                    keyword.span(),
+                    "expected `events`, `actors` or `fn`",
+                ));
+            }
+        }
+
+        let missing = |what: &str| {
+            return syn::Error::new(Span::call_site(), format!("protocol! is missing {}", what));
+        };
+        let events = events.ok_or_else(|| {
+            missing("its `events` section, as `pub events MyEvent { AuthEvent, .. }`")
+        })?;
+        let actors = actors.ok_or_else(|| {
+            missing("its `actors` section, as `pub actors MyActor { PointActor, .. }`")
+        })?;
+        let manifest_fn = manifest_fn.ok_or_else(|| {
+            missing("the function it registers the Manifest with, as `pub fn manifest_load;`")
+        })?;
+
+        let mut listed: HashSet<String> = HashSet::new();
+        let names = events
+            .entries
+            .iter()
+            .map(|entry| &entry.name)
+            .chain(actors.entries.iter().map(|entry| &entry.name));
+        for name in names {
+            if !listed.insert(name.to_string()) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("`{}` is listed more than once", name),
+                ));
+            }
+        }
+
+        return Ok(Protocol {
+            events,
+            actors,
+            manifest_fn,
+        });
+    }
+}
+
+fn parse_section<E>(
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    input: ParseStream,
+    parse_entry: fn(ParseStream) -> syn::Result<E>,
+) -> syn::Result<Section<E>> {
+    let name: Ident = input.parse()?;
+    let content;
+    let brace = braced!(content in input);
+    let entries: Punctuated<E, Token![,]> = content.parse_terminated(parse_entry)?;
+    if entries.is_empty() {
+        return Err(syn::Error::new(
+            brace.span,
+            format!("`{}` lists no types, and must list at least one", name),
+        ));
+    }
+    return Ok(Section {
+        attrs,
+        vis,
+        name,
+        entries: entries.into_iter().collect(),
+    });
+}
+
+// parses an Event type, preceded by any of `#[channel(0)]`, `#[passthrough]`,
+// `#[timestamped]`, `#[actor_ordered]` & `#[actor_scoped]`
+fn parse_event_entry(input: ParseStream) -> syn::Result<EventEntry> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let mut entry = EventEntry {
+        name: input.parse()?,
+        channel: None,
+        passthrough: false,
+        timestamped: false,
+        actor_ordered: false,
+        actor_scoped: false,
+    };
+    for attr in attrs.iter() {
+        if attr.path.is_ident("channel") {
+            entry.channel = Some(attr.parse_args::<LitInt>()?);
+        } else if attr.path.is_ident("passthrough") {
+            entry.passthrough = true;
+        } else if attr.path.is_ident("timestamped") {
+            entry.timestamped = true;
+        } else if attr.path.is_ident("actor_ordered") {
+            entry.actor_ordered = true;
+        } else if attr.path.is_ident("actor_scoped") {
+            entry.actor_scoped = true;
+        } else {
+            return Err(syn::Error::new(
+                attr.path.span(),
+                "expected `channel(..)`, `passthrough`, `timestamped`, `actor_ordered` or \
+                 `actor_scoped`",
+            ));
+        }
+    }
+    if entry.channel.is_some() && entry.passthrough {
+        return Err(syn::Error::new(
+            entry.name.span(),
+            "a passthrough Event can't be registered on a channel",
+        ));
+    }
+    return Ok(entry);
+}
+
+fn parse_actor_entry(input: ParseStream) -> syn::Result<ActorEntry> {
+    if let Some(attr) = input.call(Attribute::parse_outer)?.first() {
+        return Err(syn::Error::new(
+            attr.path.span(),
+            "Actors listed in protocol! take no attributes",
+        ));
+    }
+    return Ok(ActorEntry {
+        name: input.parse()?,
+    });
+}
+
+fn get_event_enum(events: &Section<EventEntry>) -> TokenStream {
+    let Section {
+        attrs, vis, name, ..
+    } = events;
+    let variants = events.entries.iter().map(|entry| {
+        let event_name = &entry.name;
+        return quote! { #event_name(#event_name) };
+    });
+    return quote! {
+        #(#attrs)*
+        #[derive(Clone)]
+        #vis enum #name {
+            #(#variants,)*
+        }
+    };
+}
+
+fn get_actor_enum(actors: &Section<ActorEntry>) -> TokenStream {
+    let Section {
+        attrs, vis, name, ..
+    } = actors;
+    let variants = actors.entries.iter().map(|entry| {
+        let actor_name = &entry.name;
+        return quote! {
+            #actor_name(std::rc::Rc<std::cell::RefCell<#actor_name>>)
+        };
+    });
+    return quote! {
+        #(#attrs)*
+        #[derive(Clone)]
+        #vis enum #name {
+            #(#variants,)*
+        }
+    };
+}
+
+// Checks that every listed type implements the trait it's listed as, with the
+// error pointing at the type in the list if it doesn't
+fn get_trait_checks(protocol: &Protocol) -> TokenStream {
+    let event_type = &protocol.events.name;
+    let actor_type = &protocol.actors.name;
+    let event_checks = protocol.events.entries.iter().map(|entry| {
+        let event_name = &entry.name;
+        return quote_spanned! {event_name.span()=>
+            listed_as_event::<#event_name>();
+        };
+    });
+    let actor_checks = protocol.actors.entries.iter().map(|entry| {
+        let actor_name = &entry.name;
+        return quote_spanned! {actor_name.span()=>
+            listed_as_actor::<#actor_name>();
+        };
+    });
+    return quote! {
+        const _: () = {
+            fn listed_as_event<E: naia_shared::Event<#event_type> + Clone + 'static>() {}
+            fn listed_as_actor<A: naia_shared::Actor<#actor_type> + 'static>() {}
+            #[allow(dead_code)]
+            fn check_listed_types() {
+                #(#event_checks)*
+                #(#actor_checks)*
+            }
+        };
+    };
+}
+
+fn get_manifest_fn(protocol: &Protocol) -> TokenStream {
+    let (attrs, vis, fn_name) = &protocol.manifest_fn;
+    let event_type = &protocol.events.name;
+    let actor_type = &protocol.actors.name;
+
+    let event_registrations = protocol.events.entries.iter().map(|entry| {
+        let event_name = &entry.name;
+        let register = if entry.passthrough {
+            quote! { manifest.register_passthrough_event(#event_name::get_builder()); }
+        } else if let Some(channel) = &entry.channel {
+            quote! { manifest.register_event_on_channel(#event_name::get_builder(), #channel); }
+        } else {
+            quote! { manifest.register_event(#event_name::get_builder()); }
+        };
+        let mut settings = Vec::new();
+        if entry.timestamped {
+            settings.push(quote! { set_event_timestamped });
+        }
+        if entry.actor_ordered {
+            settings.push(quote! { set_event_actor_ordered });
+        }
+        if entry.actor_scoped {
+            settings.push(quote! { set_event_actor_scoped });
+        }
+        return quote! {
+            #register
+            #(
+                manifest
+                    .#settings::<#event_name>()
+                    .expect("the Event type was just registered");
+            )*
+        };
+    });
+    let actor_registrations = protocol.actors.entries.iter().map(|entry| {
+        let actor_name = &entry.name;
+        return quote! { manifest.register_actor(#actor_name::get_builder()); };
+    });
+
+    return quote! {
+        #(#attrs)*
+        #vis fn #fn_name() -> naia_shared::Manifest<#event_type, #actor_type> {
+            let mut manifest = naia_shared::Manifest::<#event_type, #actor_type>::new();
+            #(#event_registrations)*
+            #(#actor_registrations)*
+            return manifest;
+        }
+    };
+}
@@ -1,6 +1,16 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Data, DeriveInput, Fields, GenericArgument, Ident, Lit, Meta, PathArguments, Type};
+use syn::{
+    parse::ParseStream, Attribute, Data, DeriveInput, Expr, Fields, GenericArgument, Ident, Lit,
+    LitInt, Meta, PathArguments, Token, Type,
+};
+
+/// The schema version a Property was added to its type in, and the value it
+/// is given when read from a host with an older version of the type
+pub struct PropertyVersion {
+    pub version: u16,
+    pub default: Option<Expr>,
+}
 
 pub fn get_properties(input: &DeriveInput) -> Vec<(Ident, Type)> {
     let mut fields = Vec::new();
@@ -28,6 +38,149 @@ pub fn get_properties(input: &DeriveInput) -> Vec<(Ident, Type)> {
     fields
 }
 
+// Gets the schema version each Property was added in, from its
+// `#[added(version = 2, default = ...)]` attribute, in the same order as
+// `get_properties()`. Properties without the attribute were there from the
+// first version, and Properties of later versions must come after those of
+// earlier ones, so that a host with an older version reads them in order
+pub fn get_property_versions(input: &DeriveInput) -> Vec<PropertyVersion> {
+    let properties = get_properties(input);
+    let mut versions: Vec<PropertyVersion> = Vec::new();
+
+    if let Data::Struct(data_struct) = &input.data {
+        if let Fields::Named(fields_named) = &data_struct.fields {
+            for field in fields_named.named.iter() {
+                if !properties
+                    .iter()
+                    .any(|(property_name, _)| Some(property_name) == field.ident.as_ref())
+                {
+                    continue;
+                }
+                let mut property_version = PropertyVersion {
+                    version: 1,
+                    default: None,
+                };
+                for attr in field.attrs.iter() {
+                    if attr.path.is_ident("added") {
+                        property_version = parse_added_attribute(attr);
+                    }
+                }
+                if let Some(previous) = versions.last() {
+                    if property_version.version < previous.version {
+                        panic!(
+                            "the Property `{}` was added in version {}, so must come before \
+                             every Property added in a later version",
+                            field.ident.as_ref().unwrap(),
+                            property_version.version
+                        );
+                    }
+                }
+                versions.push(property_version);
+            }
+        }
+    }
+
+    versions
+}
+
+// parses `#[added(version = 2, default = ...)]`, where the default is optional
+fn parse_added_attribute(attr: &Attribute) -> PropertyVersion {
+    let mut version: Option<u16> = None;
+    let mut default: Option<Expr> = None;
+    attr.parse_args_with(|input: ParseStream| {
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if key == "version" {
+                version = Some(input.parse::<LitInt>()?.base10_parse::<u16>()?);
+            } else if key == "default" {
+                default = Some(input.parse::<Expr>()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `version` or `default`",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(())
+    })
+    .unwrap_or_else(|error| panic!("malformed #[added] attribute: {}", error));
+
+    let version =
+        version.expect("#[added] requires the version the Property was added in, as `version = 2`");
+    if version < 2 {
+        panic!("#[added] requires a version after the first, as `version = 2`");
+    }
+    return PropertyVersion { version, default };
+}
+
+// Gets the schema version of the type, which is the latest version any of its
+// Properties were added in
+pub fn get_schema_version(versions: &Vec<PropertyVersion>) -> u16 {
+    return versions
+        .iter()
+        .map(|property_version| property_version.version)
+        .max()
+        .unwrap_or(1);
+}
+
+// Gets the name of the version argument of generated methods, which is only
+// used by types with Properties added after the first version
+pub fn get_version_arg(versions: &Vec<PropertyVersion>) -> Ident {
+    match get_schema_version(versions) {
+        1 => return Ident::new("_version", Span::call_site()),
+        _ => return Ident::new("version", Span::call_site()),
+    }
+}
+
+// Gets the method which collects the ActorRefs held by an Actor's or Event's
+// Properties
+pub fn get_get_actor_refs_method(properties: &Vec<(Ident, Type)>) -> TokenStream {
+    let mut output = quote! {};
+
+    for (field_name, field_type) in properties.iter() {
+        if !is_actor_ref_type(field_type) {
+            continue;
+        }
+        let new_output_right = quote! {
+            output.push(*self.#field_name.get());
+        };
+        let new_output_result = quote! {
+            #output
+            #new_output_right
+        };
+        output = new_output_result;
+    }
+
+    if output.is_empty() {
+        return quote! {
+            fn get_actor_refs(&self) -> Vec<naia_shared::ActorRef> {
+                return Vec::new();
+            }
+        };
+    }
+
+    return quote! {
+        fn get_actor_refs(&self) -> Vec<naia_shared::ActorRef> {
+            let mut output = Vec::new();
+            #output
+            return output;
+        }
+    };
+}
+
+pub fn is_actor_ref_type(property_type: &Type) -> bool {
+    if let Type::Path(type_path) = property_type {
+        if let Some(segment) = type_path.path.segments.last() {
+            return segment.ident == "ActorRef";
+        }
+    }
+    return false;
+}
+
 pub fn get_type_name(input: &DeriveInput, type_type: &str) -> Ident {
     let mut type_name_option: Option<Ident> = None;
 
@@ -80,3 +233,100 @@ pub fn get_write_method(properties: &Vec<(Ident, Type)>) -> TokenStream {
         }
     };
 }
+
+pub fn get_write_versioned_method(
+    properties: &Vec<(Ident, Type)>,
+    versions: &Vec<PropertyVersion>,
+) -> TokenStream {
+    let mut output = quote! {};
+
+    for ((field_name, _), property_version) in properties.iter().zip(versions.iter()) {
+        let new_output_right = match property_version.version {
+            1 => quote! {
+                Property::write(&self.#field_name, buffer);
+            },
+            added_version => quote! {
+                if version >= #added_version {
+                    Property::write(&self.#field_name, buffer);
+                }
+            },
+        };
+        let new_output_result = quote! {
+            #output
+            #new_output_right
+        };
+        output = new_output_result;
+    }
+
+    let version_arg = get_version_arg(versions);
+
+    return quote! {
+        fn write_versioned(&self, #version_arg: u16, buffer: &mut Vec<u8>) {
+            #output
+        }
+    };
+}
+
+// Gets the value a Property starts out with when it's read, which a Property
+// not written by a host with an older version of the type is left with
+pub fn get_property_default(property_version: &PropertyVersion) -> TokenStream {
+    match &property_version.default {
+        Some(default) => return quote! { #default },
+        None => return quote! { Default::default() },
+    }
+}
+
+// Gets the `describe()` method of a derived type, which lists its Properties
+// for `Manifest::export_schema()`, along with whether Events of the type are
+// guaranteed, given as an expression
+pub fn get_describe_method(
+    type_name: &Ident,
+    properties: &Vec<(Ident, Type)>,
+    versions: &Vec<PropertyVersion>,
+    guaranteed: TokenStream,
+    interpolated: &Vec<(Ident, Type)>,
+    predicted: &Vec<(Ident, Type)>,
+) -> TokenStream {
+    let name = type_name.to_string();
+
+    let mut property_schemas = quote! {};
+    for ((field_name, field_type), version) in properties.iter().zip(versions.iter()) {
+        let property_name = field_name.to_string();
+        let value_type = quote! { #field_type }.to_string().replace(' ', "");
+        let added_version = version.version;
+        let default = match &version.default {
+            Some(default) => {
+                let default = quote! { #default }.to_string();
+                quote! { Some(#default.to_string()) }
+            }
+            None => quote! { None },
+        };
+        let is_interpolated = interpolated.iter().any(|(name, _)| name == field_name);
+        let is_predicted = predicted.iter().any(|(name, _)| name == field_name);
+        let new_output_right = quote! {
+            naia_shared::PropertySchema {
+                name: #property_name.to_string(),
+                value_type: #value_type.to_string(),
+                added_version: #added_version,
+                default: #default,
+                interpolated: #is_interpolated,
+                predicted: #is_predicted,
+            },
+        };
+        let new_output_result = quote! {
+            #property_schemas
+            #new_output_right
+        };
+        property_schemas = new_output_result;
+    }
+
+    return quote! {
+        pub fn describe() -> naia_shared::TypeDescription {
+            return naia_shared::TypeDescription {
+                name: #name.to_string(),
+                guaranteed: #guaranteed,
+                properties: vec![#property_schemas],
+            };
+        }
+    };
+}
@@ -0,0 +1,148 @@
+//! Benchmark of a whole tick of the Server's replication work, run with
+//! `cargo bench -p naia-server --features bench,use-udp`. See
+//! `benches/README.md` for comparing against the committed baseline
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use naia_server::TickHarness;
+use naia_shared::Manifest;
+
+mod bench_event {
+    use naia_derive::EventType;
+
+    use super::ping_event::PingEvent;
+
+    #[derive(EventType, Clone)]
+    pub enum BenchEvent {
+        PingEvent(PingEvent),
+    }
+}
+
+mod ping_event {
+    use naia_derive::Event;
+    use naia_shared::{Event, Property};
+
+    use super::bench_event::BenchEvent;
+
+    #[derive(Event, Clone)]
+    #[type_name = "BenchEvent"]
+    pub struct PingEvent {
+        pub value: Property<u16>,
+    }
+
+    impl PingEvent {
+        fn is_guaranteed() -> bool {
+            false
+        }
+    }
+}
+
+mod bench_actor {
+    use std::{cell::RefCell, rc::Rc};
+
+    use naia_derive::ActorType;
+
+    use super::position::Position;
+
+    #[derive(ActorType, Clone)]
+    pub enum BenchActor {
+        Position(Rc<RefCell<Position>>),
+    }
+}
+
+mod position {
+    use naia_derive::Actor;
+    use naia_shared::{Actor, Property};
+
+    use super::bench_actor::BenchActor;
+
+    #[derive(Actor)]
+    #[type_name = "BenchActor"]
+    pub struct Position {
+        pub x: Property<u16>,
+        pub y: Property<u16>,
+    }
+}
+
+use bench_actor::BenchActor;
+use bench_event::BenchEvent;
+use position::Position;
+
+// The numbers of connections & of Actors in scope for each, that a tick is
+// benchmarked at, from a few connections with many Actors each to many
+// connections with few
+const HARNESS_SIZES: [(u16, u16); 2] = [(100, 1000), (1000, 100)];
+
+// The numbers of connections & of unchanging Actors in scope for each, that a
+// tick is benchmarked at with the Actors registered as static & as regular
+// Actors
+const STATIC_HARNESS_SIZE: (u16, u16) = (10, 10000);
+
+fn new_actors(actor_count: u16) -> (Manifest<BenchEvent, BenchActor>, Vec<BenchActor>) {
+    let mut manifest = Manifest::<BenchEvent, BenchActor>::new();
+    manifest.register_actor(Position::get_builder());
+    let actors = (0..actor_count)
+        .map(|index| BenchActor::Position(Position::new_complete(index, index).wrap()))
+        .collect();
+    return (manifest, actors);
+}
+
+fn new_harness(connection_count: u16, actor_count: u16) -> TickHarness<BenchEvent, BenchActor> {
+    let (manifest, actors) = new_actors(actor_count);
+    return TickHarness::new(manifest, connection_count, actors);
+}
+
+fn harness_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("harness");
+    group.sample_size(10);
+    for (connection_count, actor_count) in HARNESS_SIZES.iter() {
+        let mut harness = new_harness(*connection_count, *actor_count);
+        // every Actor moves along one axis each tick
+        let name = format!(
+            "tick_{}_connections_{}_actors",
+            connection_count, actor_count
+        );
+        group.bench_function(name.as_str(), |b| {
+            b.iter(|| {
+                for (_, actor) in harness.actors_iter() {
+                    let BenchActor::Position(position) = actor;
+                    let mut position = position.borrow_mut();
+                    let x = *position.x.get();
+                    position.x.set(x.wrapping_add(1));
+                }
+                return harness.tick().bytes_written;
+            });
+        });
+    }
+    group.finish();
+}
+
+// a tick where no Actor has changed, which for static Actors should cost next
+// to nothing, as they're left out of the scan for changes
+fn static_actors_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("static_actors");
+    group.sample_size(10);
+    let (connection_count, actor_count) = STATIC_HARNESS_SIZE;
+    for is_static in [false, true].iter() {
+        let (manifest, actors) = new_actors(actor_count);
+        let mut harness = match is_static {
+            true => TickHarness::new_static(manifest, connection_count, actors),
+            false => TickHarness::new(manifest, connection_count, actors),
+        };
+        let name = format!(
+            "tick_{}_connections_{}_{}_actors",
+            connection_count,
+            actor_count,
+            if *is_static { "static" } else { "dynamic" }
+        );
+        group.bench_function(name.as_str(), |b| {
+            b.iter(|| {
+                return harness.tick().actors_dirty;
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, harness_tick, static_actors_tick);
+criterion_main!(benches);
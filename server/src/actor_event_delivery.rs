@@ -0,0 +1,11 @@
+/// Decides what happens to an Event sent with `NaiaServer::queue_actor_event()`
+/// for a User whose Client has the Actor it's about in scope, but has yet to
+/// acknowledge creating it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ActorEventDelivery {
+    /// The Event isn't sent to the User
+    SkipUnspawned,
+    /// The Event is held until the Client has acknowledged creating the Actor,
+    /// and then queued, or dropped if the Actor leaves scope first
+    HoldUntilSpawned,
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use naia_shared::SequenceBuffer;
+
+use super::actor_key::actor_key::ActorKey;
+
+/// Keeps the state of each Actor flagged to have its history tracked, as it
+/// was replicated on each of the most recent ticks, so that the Server can
+/// look back at what a Client saw when it acted. Memory is bounded by the
+/// number of ticks kept & the number of Actors which can be tracked
+#[derive(Debug)]
+pub struct ActorHistory<S: Clone> {
+    ticks: u16,
+    max_actors: usize,
+    states: HashMap<ActorKey, SequenceBuffer<S>>,
+}
+
+impl<S: Clone> ActorHistory<S> {
+    /// Create a new ActorHistory, given the number of ticks of state to keep,
+    /// and the number of Actors which can be tracked at once
+    pub fn new(ticks: u16, max_actors: usize) -> Self {
+        ActorHistory {
+            ticks,
+            max_actors,
+            states: HashMap::new(),
+        }
+    }
+
+    /// Starts tracking the history of an Actor, from the next recorded tick
+    /// on. Returns false if it isn't already tracked and the maximum number of
+    /// tracked Actors has been reached
+    pub fn track(&mut self, key: &ActorKey) -> bool {
+        if self.is_tracked(key) {
+            return true;
+        }
+        if self.ticks == 0 || self.states.len() >= self.max_actors {
+            return false;
+        }
+        self.states
+            .insert(*key, SequenceBuffer::with_capacity(self.ticks));
+        return true;
+    }
+
+    /// Stops tracking the history of an Actor, dropping what was kept of it
+    pub fn untrack(&mut self, key: &ActorKey) {
+        self.states.remove(key);
+    }
+
+    /// Gets whether the history of an Actor is being tracked
+    pub fn is_tracked(&self, key: &ActorKey) -> bool {
+        return self.states.contains_key(key);
+    }
+
+    /// Records the state of every tracked Actor on the given tick, replacing
+    /// any recorded earlier on the same tick. Actors which `get_state` gives
+    /// no state for are left as they were
+    pub fn record<F: FnMut(&ActorKey) -> Option<S>>(&mut self, tick: u16, mut get_state: F) {
+        for (key, states) in self.states.iter_mut() {
+            if let Some(state) = get_state(key) {
+                states.insert(tick, state);
+            }
+        }
+    }
+
+    /// Gets the state of a tracked Actor as recorded on the given tick, if
+    /// that tick is still kept
+    pub fn get(&self, key: &ActorKey, tick: u16) -> Option<&S> {
+        return self.states.get(key)?.get(tick);
+    }
+}
+
+/// A view of the Actors with their history tracked, as they were replicated
+/// on a past tick
+#[derive(Debug)]
+pub struct HistoryView<'h, S: Clone> {
+    history: &'h ActorHistory<S>,
+    tick: u16,
+}
+
+impl<'h, S: Clone> HistoryView<'h, S> {
+    pub(crate) fn new(history: &'h ActorHistory<S>, tick: u16) -> Self {
+        HistoryView { history, tick }
+    }
+
+    /// Gets the tick the view is of
+    pub fn tick(&self) -> u16 {
+        return self.tick;
+    }
+
+    /// Gets the state of an Actor as it was replicated on the tick the view
+    /// is of, if its history is tracked & still kept that far back
+    pub fn get(&self, key: &ActorKey) -> Option<&'h S> {
+        return self.history.get(key, self.tick);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slotmap::DenseSlotMap;
+
+    use super::ActorHistory;
+    use crate::ActorKey;
+
+    #[test]
+    fn states_are_kept_for_the_most_recent_ticks() {
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let (tracked, untracked) = (actors.insert(()), actors.insert(()));
+
+        let mut history = ActorHistory::new(4, 8);
+        assert!(history.track(&tracked));
+        // ticks wrap around without losing the states kept before
+        for tick in 65530..65540u32 {
+            let tick = tick as u16;
+            history.record(tick, |_| Some(tick.wrapping_mul(10)));
+            // a later recording on the same tick replaces the earlier one
+            history.record(tick, |_| Some(tick.wrapping_mul(10) + 1));
+        }
+
+        for tick in 0..4u16 {
+            assert_eq!(history.get(&tracked, tick), Some(&(tick * 10 + 1)));
+        }
+        assert_eq!(history.get(&tracked, 65535), None);
+        assert_eq!(history.get(&tracked, 4), None);
+        assert_eq!(history.get(&untracked, 3), None);
+
+        history.untrack(&tracked);
+        assert_eq!(history.get(&tracked, 3), None);
+    }
+
+    #[test]
+    fn tracked_actors_are_bounded() {
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let (first, second) = (actors.insert(()), actors.insert(()));
+
+        let mut history: ActorHistory<u16> = ActorHistory::new(4, 1);
+        assert!(history.track(&first));
+        assert!(history.track(&first));
+        assert!(!history.track(&second));
+        assert!(!history.is_tracked(&second));
+
+        history.untrack(&first);
+        assert!(history.track(&second));
+    }
+}
@@ -1,6 +1,32 @@
+use slotmap::KeyData;
+
+use naia_shared::ActorRef;
+
 #[allow(missing_docs)]
 #[allow(unused_doc_comments)]
 pub mod actor_key {
     // The Global Key used to get a reference of an Actor
     new_key_type! { pub struct ActorKey; }
 }
+
+use actor_key::ActorKey;
+
+impl From<ActorKey> for ActorRef {
+    fn from(key: ActorKey) -> Self {
+        ActorRef::new(get_actor_net_id(&key))
+    }
+}
+
+/// Gets the network id of an Actor, which is stable for the lifetime of the
+/// Actor and is the same for every Client
+pub fn get_actor_net_id(key: &ActorKey) -> u64 {
+    return KeyData::from(*key).as_ffi();
+}
+
+/// Gets the ActorKey an ActorRef is pointing to, if any
+pub fn get_actor_ref_key(actor_ref: &ActorRef) -> Option<ActorKey> {
+    if let Some(net_id) = actor_ref.get_net_id() {
+        return Some(KeyData::from_ffi(net_id).into());
+    }
+    return None;
+}
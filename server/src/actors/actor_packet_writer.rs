@@ -1,105 +1,229 @@
-use byteorder::{BigEndian, WriteBytesExt};
+use std::any::TypeId;
 
-use naia_shared::{ActorType, EventType, Manifest, MTU_SIZE};
+use byteorder::WriteBytesExt;
 
-use super::server_actor_message::ServerActorMessage;
+use naia_shared::{
+    byte_order, varint, ActorGeneration, ActorType, EventType, Manifest, SchemaVersions, StateMask,
+};
+
+use super::{actor_key::get_actor_net_id, server_actor_message::ServerActorMessage};
 
 use crate::server_packet_writer::ServerPacketWriter;
 
+// The message type of a creation written against an Archetype, which no
+// ServerActorMessage is written as otherwise
+const ARCHETYPE_CREATE_MESSAGE_TYPE: u8 = 8;
+
 /// Writes into a packet with Actor data
 #[derive(Debug)]
 pub struct ActorPacketWriter {}
 
 impl ActorPacketWriter {
-    /// Given a general PacketWriter, the manifest, and a buffered
-    /// ActorMessage, actually write Actor data into the packet
+    /// Given a general PacketWriter, the manifest, the schema versions agreed
+    /// on for the connection, a buffered ActorMessage, the ActorGeneration of
+    /// the Actor it is for, and the mask of its Properties visible to the
+    /// Client if not all are, actually write Actor data into the packet. A
+    /// message for an Actor whose type has not been registered in the Manifest
+    /// is dropped
     pub fn write_actor_message<T: EventType, U: ActorType>(
         packet_writer: &mut ServerPacketWriter,
         manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
         message: &ServerActorMessage<U>,
+        generation: ActorGeneration,
+        visibility: Option<&StateMask>,
     ) -> bool {
+        // the number of Actor messages is written as a single byte
+        if packet_writer.actor_message_count == u8::MAX {
+            return false;
+        }
+
         let mut actor_total_bytes = Vec::<u8>::new();
 
         match message {
-            ServerActorMessage::CreateActor(_, local_key, actor) => {
-                //write actor payload
+            ServerActorMessage::CreateActor(
+                global_key,
+                local_key,
+                actor,
+                static_payload,
+                prediction_id,
+            ) => {
+                let type_id = actor.as_ref().borrow().get_type_id();
+                let naia_id = match manifest.get_actor_naia_id(&type_id) {
+                    Ok(naia_id) => naia_id,
+                    Err(_) => return true,
+                };
+
+                //write actor payload, which static Actors have serialized
+                // already, as the latest version of their type, with all of
+                // their Properties visible
                 let mut actor_payload_bytes = Vec::<u8>::new();
-                actor.as_ref().borrow().write(&mut actor_payload_bytes);
+                let actor_ref = actor.as_ref().borrow();
+                match (schema_versions.get_actor_version(naia_id), visibility) {
+                    (Some(version), Some(visibility)) => actor_ref.write_masked_versioned(
+                        visibility,
+                        version,
+                        &mut actor_payload_bytes,
+                    ),
+                    (Some(version), None) => {
+                        actor_ref.write_versioned(version, &mut actor_payload_bytes)
+                    }
+                    (None, Some(visibility)) => {
+                        actor_ref.write_masked(visibility, &mut actor_payload_bytes)
+                    }
+                    (None, None) => match static_payload
+                        .as_ref()
+                        .and_then(|payload| payload.borrow().clone())
+                    {
+                        Some(payload) => actor_payload_bytes.extend_from_slice(&payload),
+                        None => actor_ref.write(&mut actor_payload_bytes),
+                    },
+                }
+
+                // an Actor with all of its Properties visible, written as the
+                // latest version of its type, may instead be written as the
+                // Properties which differ from an Archetype agreed on for the
+                // connection, if that is smaller
+                let mut archetype = None;
+                if prediction_id.is_none()
+                    && visibility.is_none()
+                    && schema_versions.get_actor_version(naia_id).is_none()
+                {
+                    if let Some((archetype_id, mut diff_mask, diff_payload)) =
+                        manifest.get_closest_archetype(naia_id, &*actor_ref, schema_versions)
+                    {
+                        let archetype_size = varint::encoded_len(u64::from(archetype_id))
+                            + 1
+                            + usize::from(diff_mask.byte_number())
+                            + diff_payload.len();
+                        if archetype_size < 2 + actor_payload_bytes.len() {
+                            let mut archetype_bytes = Vec::new();
+                            varint::write_u16(archetype_id, &mut archetype_bytes); // write archetype id
+                            diff_mask.write(&mut archetype_bytes); // write diff mask
+                            archetype_bytes.extend_from_slice(&diff_payload); // write diff payload
+                            archetype = Some(archetype_bytes);
+                        }
+                    }
+                }
 
                 //Write actor "header"
-                actor_total_bytes
-                    .write_u8(message.write_message_type())
-                    .unwrap(); // write actor message type
+                match archetype {
+                    Some(mut archetype_bytes) => {
+                        actor_total_bytes
+                            .write_u8(ARCHETYPE_CREATE_MESSAGE_TYPE)
+                            .unwrap(); // write actor message type
+                        byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                        actor_total_bytes.write_u8(generation).unwrap(); //write generation
+                        byte_order::write_u64(&mut actor_total_bytes, get_actor_net_id(global_key)); //write net id
+                        actor_total_bytes.append(&mut archetype_bytes); // write archetype & diff
+                    }
+                    None => {
+                        actor_total_bytes
+                            .write_u8(message.write_message_type())
+                            .unwrap(); // write actor message type
 
-                let type_id = actor.as_ref().borrow().get_type_id();
-                let naia_id = manifest.get_actor_naia_id(&type_id); // get naia id
-                actor_total_bytes.write_u16::<BigEndian>(naia_id).unwrap(); // write naia id
-                actor_total_bytes
-                    .write_u16::<BigEndian>(*local_key)
-                    .unwrap(); //write local key
-                actor_total_bytes.append(&mut actor_payload_bytes); // write payload
+                        byte_order::write_u16(&mut actor_total_bytes, naia_id); // write naia id
+                        byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                        actor_total_bytes.write_u8(generation).unwrap(); //write generation
+                        byte_order::write_u64(&mut actor_total_bytes, get_actor_net_id(global_key)); //write net id
+                        if let Some(prediction_id) = prediction_id {
+                            byte_order::write_u16(&mut actor_total_bytes, *prediction_id);
+                            //write prediction id
+                        }
+                        actor_total_bytes.append(&mut actor_payload_bytes); // write payload
+                    }
+                }
             }
-            ServerActorMessage::DeleteActor(_, local_key) => {
+            ServerActorMessage::DeleteActor(_, local_key, reason) => {
                 actor_total_bytes
                     .write_u8(message.write_message_type())
                     .unwrap(); //Write actor message type
-                actor_total_bytes
-                    .write_u16::<BigEndian>(*local_key)
-                    .unwrap(); //write local key
+                byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                actor_total_bytes.write_u8(generation).unwrap(); //write generation
+                reason.write(&mut actor_total_bytes); //write despawn reason
             }
             ServerActorMessage::UpdateActor(_, local_key, state_mask, actor) => {
                 //write actor payload
                 let mut actor_payload_bytes = Vec::<u8>::new();
-                actor
-                    .as_ref()
-                    .borrow()
-                    .write_partial(&state_mask.as_ref().borrow(), &mut actor_payload_bytes);
+                let actor_ref = actor.as_ref().borrow();
+                match get_actor_version(manifest, schema_versions, &actor_ref.get_type_id()) {
+                    Some(version) => actor_ref.write_partial_versioned(
+                        &state_mask.as_ref().borrow(),
+                        version,
+                        &mut actor_payload_bytes,
+                    ),
+                    None => actor_ref
+                        .write_partial(&state_mask.as_ref().borrow(), &mut actor_payload_bytes),
+                }
 
                 //Write actor "header"
                 actor_total_bytes
                     .write_u8(message.write_message_type())
                     .unwrap(); // write actor message type
 
-                actor_total_bytes
-                    .write_u16::<BigEndian>(*local_key)
-                    .unwrap(); //write local key
+                byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                actor_total_bytes.write_u8(generation).unwrap(); //write generation
                 state_mask
                     .as_ref()
                     .borrow_mut()
                     .write(&mut actor_total_bytes); // write state mask
+                                                    // the size is written, so that the Client can skip over an
+                                                    // update for another generation of the Actor
+                varint::write_u16(actor_payload_bytes.len() as u16, &mut actor_total_bytes); // write payload size
                 actor_total_bytes.append(&mut actor_payload_bytes); // write payload
             }
             ServerActorMessage::AssignPawn(_, local_key) => {
                 actor_total_bytes
                     .write_u8(message.write_message_type())
                     .unwrap(); //Write actor message type
-                actor_total_bytes
-                    .write_u16::<BigEndian>(*local_key)
-                    .unwrap(); //write local key
+                byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                actor_total_bytes.write_u8(generation).unwrap(); //write generation
             }
             ServerActorMessage::UnassignPawn(_, local_key) => {
                 actor_total_bytes
                     .write_u8(message.write_message_type())
                     .unwrap(); //Write actor message type
-                actor_total_bytes
-                    .write_u16::<BigEndian>(*local_key)
-                    .unwrap(); //write local key
+                byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                actor_total_bytes.write_u8(generation).unwrap(); //write generation
             }
-            ServerActorMessage::UpdatePawn(_, local_key, _, actor) => {
+            ServerActorMessage::UpdatePawn(_, local_key, _, actor)
+            | ServerActorMessage::ResyncActor(_, local_key, actor) => {
                 //write actor payload
                 let mut actor_payload_bytes = Vec::<u8>::new();
-                actor.as_ref().borrow().write(&mut actor_payload_bytes);
+                let actor_ref = actor.as_ref().borrow();
+                match (
+                    get_actor_version(manifest, schema_versions, &actor_ref.get_type_id()),
+                    visibility,
+                ) {
+                    (Some(version), Some(visibility)) => actor_ref.write_masked_versioned(
+                        visibility,
+                        version,
+                        &mut actor_payload_bytes,
+                    ),
+                    (Some(version), None) => {
+                        actor_ref.write_versioned(version, &mut actor_payload_bytes)
+                    }
+                    (None, Some(visibility)) => {
+                        actor_ref.write_masked(visibility, &mut actor_payload_bytes)
+                    }
+                    (None, None) => actor_ref.write(&mut actor_payload_bytes),
+                }
 
                 //Write actor "header"
                 actor_total_bytes
                     .write_u8(message.write_message_type())
                     .unwrap(); // write actor message type
 
-                actor_total_bytes
-                    .write_u16::<BigEndian>(*local_key)
-                    .unwrap(); //write local key
+                byte_order::write_u16(&mut actor_total_bytes, *local_key); //write local key
+                actor_total_bytes.write_u8(generation).unwrap(); //write generation
+                varint::write_u16(actor_payload_bytes.len() as u16, &mut actor_total_bytes); // write payload size
                 actor_total_bytes.append(&mut actor_payload_bytes); // write payload
             }
+            ServerActorMessage::WorldSyncComplete => {
+                actor_total_bytes
+                    .write_u8(message.write_message_type())
+                    .unwrap(); //Write actor message type
+            }
         }
 
         let mut hypothetical_next_payload_size =
@@ -107,7 +231,7 @@ impl ActorPacketWriter {
         if packet_writer.actor_message_count == 0 {
             hypothetical_next_payload_size += 2;
         }
-        if hypothetical_next_payload_size < MTU_SIZE {
+        if hypothetical_next_payload_size < packet_writer.max_payload_size() {
             packet_writer.actor_message_count += 1;
             packet_writer
                 .actor_working_bytes
@@ -118,3 +242,16 @@ impl ActorPacketWriter {
         }
     }
 }
+
+// gets the schema version to write an Actor of the given type as, if the
+// Client has an older version of its type than the Server
+fn get_actor_version<T: EventType, U: ActorType>(
+    manifest: &Manifest<T, U>,
+    schema_versions: &SchemaVersions,
+    type_id: &TypeId,
+) -> Option<u16> {
+    return manifest
+        .get_actor_naia_id(type_id)
+        .ok()
+        .and_then(|naia_id| schema_versions.get_actor_version(naia_id));
+}
@@ -2,11 +2,27 @@ use std::{cell::RefCell, rc::Rc};
 
 use naia_shared::{LocalActorKey, StateMask};
 
+use super::update_rate::UpdateRate;
+
 #[derive(Debug)]
 pub struct ActorRecord {
     pub local_key: LocalActorKey,
     state_mask: Rc<RefCell<StateMask>>,
     pub status: LocalActorStatus,
+    // the Server tick of the last acknowledged packet containing the Actor
+    pub last_acked_tick: u16,
+    // the number of sent updates which have been neither acknowledged nor
+    // dropped
+    pub in_flight_updates: u16,
+    // overrides the rate set for the Actor, for this Client only
+    pub update_rate: Option<UpdateRate>,
+    // the Server tick the last update of the Actor was queued on
+    pub last_update_tick: Option<u16>,
+    // whether the creation of the Actor has been written into a packet, which
+    // it always is before its deletion
+    pub creation_sent: bool,
+    // whether the deletion of the Actor has been written into a packet
+    pub deletion_sent: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -22,6 +38,12 @@ impl ActorRecord {
             local_key,
             state_mask: Rc::new(RefCell::new(StateMask::new(state_mask_size))),
             status: LocalActorStatus::Creating,
+            last_acked_tick: 0,
+            in_flight_updates: 0,
+            update_rate: None,
+            last_update_tick: None,
+            creation_sent: false,
+            deletion_sent: false,
         }
     }
 
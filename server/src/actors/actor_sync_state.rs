@@ -0,0 +1,25 @@
+/// How far along a Client is in receiving the state of an Actor
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ActorSyncState {
+    /// The Actor is not in scope for the User, or the Client has already
+    /// deleted it
+    NotInScope,
+    /// The Actor has come into scope, but the Client has not yet acknowledged
+    /// creating it
+    SpawnPending,
+    /// The Client has the current state of the Actor, as of the Server tick of
+    /// the last acknowledged packet containing it
+    Synced {
+        /// The Server tick of the last acknowledged packet containing the Actor
+        last_acked_tick: u16,
+    },
+    /// The Client has created the Actor, but changes to it have not yet been
+    /// acknowledged
+    UpdatePending {
+        /// The Server tick of the last acknowledged packet containing the Actor
+        last_acked_tick: u16,
+    },
+    /// The Actor has gone out of scope, but the Client has not yet
+    /// acknowledged deleting it
+    DespawnPending,
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use naia_shared::{
+    sequence_greater_than, utils::duration_between, ActorGeneration, Instant, LocalActorKey,
+    SequenceNumber,
+};
+
+use super::actor_key::actor_key::ActorKey;
+
+/// Assigns each Actor in scope for a Client a compact LocalActorKey, which is
+/// what identifies the Actor on the wire, instead of its global ActorKey.
+/// Keys are only reused once the Client has acknowledged the deletion of the
+/// Actor they belonged to, and every packet which may still have carried a
+/// message for that Actor has been acknowledged or dropped, so that a late
+/// message can never be applied to another Actor. Each reuse of a key also
+/// increments its ActorGeneration, which the Client checks every message
+/// against, in case a packet arrives even later than that
+#[derive(Debug)]
+pub struct LocalActorKeyMap {
+    local_to_global: HashMap<LocalActorKey, ActorKey>,
+    generations: HashMap<LocalActorKey, ActorGeneration>,
+    free_keys: VecDeque<LocalActorKey>,
+    // released keys, with the index of the last packet sent before the
+    // deletion of their Actor was acknowledged, and the time they were
+    // released at
+    quarantined_keys: VecDeque<(LocalActorKey, SequenceNumber, Instant)>,
+    next_new_key: LocalActorKey,
+}
+
+impl LocalActorKeyMap {
+    pub fn new() -> Self {
+        LocalActorKeyMap {
+            local_to_global: HashMap::new(),
+            generations: HashMap::new(),
+            free_keys: VecDeque::new(),
+            quarantined_keys: VecDeque::new(),
+            next_new_key: 0,
+        }
+    }
+
+    /// Assigns a LocalActorKey to an Actor which has come into scope. Freed
+    /// keys are reused in the order they were freed, so that a key stays
+    /// unused for as long as possible
+    pub fn assign(&mut self, global_key: &ActorKey) -> LocalActorKey {
+        let local_key = match self.free_keys.pop_front() {
+            Some(local_key) => {
+                let generation = self.generations.entry(local_key).or_insert(0);
+                *generation = generation.wrapping_add(1);
+                local_key
+            }
+            None => {
+                let local_key = self.next_new_key;
+                self.next_new_key = self.next_new_key.wrapping_add(1);
+                local_key
+            }
+        };
+        self.local_to_global.insert(local_key, *global_key);
+        return local_key;
+    }
+
+    /// Gets the Actor a LocalActorKey is currently assigned to
+    pub fn get_global_key(&self, local_key: LocalActorKey) -> Option<&ActorKey> {
+        return self.local_to_global.get(&local_key);
+    }
+
+    /// Gets the ActorGeneration of the Actor a LocalActorKey is assigned to,
+    /// or was last assigned to
+    pub fn get_generation(&self, local_key: LocalActorKey) -> ActorGeneration {
+        return self.generations.get(&local_key).copied().unwrap_or(0);
+    }
+
+    /// Returns whether the LocalActorKey is still assigned to the given Actor
+    pub fn is_assigned(&self, local_key: LocalActorKey, global_key: &ActorKey) -> bool {
+        return self.local_to_global.get(&local_key) == Some(global_key);
+    }
+
+    /// Releases the LocalActorKey of an Actor whose deletion has been
+    /// acknowledged, or has gone unacknowledged for too long, given the index
+    /// of the last packet sent so far & the current time. The key isn't reused
+    /// until every packet up to that one has been resolved, or until it has
+    /// been held back for too long, as told by `reclaim_expired()`
+    pub fn release(
+        &mut self,
+        local_key: LocalActorKey,
+        last_sent_packet_index: SequenceNumber,
+        now: &Instant,
+    ) {
+        if self.local_to_global.remove(&local_key).is_some() {
+            self.quarantined_keys
+                .push_back((local_key, last_sent_packet_index, now.clone()));
+        }
+    }
+
+    /// Frees quarantined keys, given the index of the oldest packet which has
+    /// been neither acknowledged nor dropped, if there is one
+    pub fn reclaim(&mut self, oldest_in_flight: Option<SequenceNumber>) {
+        while let Some((local_key, packet_index, _)) = self.quarantined_keys.front() {
+            if let Some(oldest_in_flight) = oldest_in_flight {
+                if !sequence_greater_than(oldest_in_flight, *packet_index) {
+                    return;
+                }
+            }
+            self.free_keys.push_back(*local_key);
+            self.quarantined_keys.pop_front();
+        }
+    }
+
+    /// Frees quarantined keys which were released at least the given retention
+    /// ago, whether or not the packets they wait on have been resolved, as a
+    /// Client which never acknowledges them would otherwise hold them forever.
+    /// The ActorGeneration of a reused key still keeps a late message from
+    /// being applied to the new Actor
+    pub fn reclaim_expired(&mut self, now: &Instant, retention: Duration) {
+        while let Some((local_key, _, released_at)) = self.quarantined_keys.front() {
+            if duration_between(released_at, now) < retention {
+                return;
+            }
+            self.free_keys.push_back(*local_key);
+            self.quarantined_keys.pop_front();
+        }
+    }
+
+    /// Gets the number of keys which are assigned or quarantined, and so are
+    /// held on to
+    pub fn get_held_count(&self) -> usize {
+        return self.local_to_global.len() + self.quarantined_keys.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slotmap::DenseSlotMap;
+
+    use std::time::Duration;
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::LocalActorKeyMap;
+    use crate::ActorKey;
+
+    #[test]
+    fn keys_are_reused_after_quarantine_in_order() {
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let first = actors.insert(());
+        let second = actors.insert(());
+        let third = actors.insert(());
+        let mut key_map = LocalActorKeyMap::new();
+        let now = SystemClock.now();
+
+        assert_eq!(key_map.assign(&first), 0);
+        assert_eq!(key_map.assign(&second), 1);
+        key_map.release(0, 10, &now);
+        key_map.release(1, 12, &now);
+        assert_eq!(key_map.get_global_key(0), None);
+
+        // packet 10 is still in flight
+        key_map.reclaim(Some(10));
+        assert_eq!(key_map.assign(&third), 2);
+
+        // packets up to 11 are resolved, only the first key is freed
+        key_map.reclaim(Some(12));
+        let fourth = actors.insert(());
+        assert_eq!(key_map.assign(&fourth), 0);
+        assert!(key_map.is_assigned(0, &fourth));
+        assert!(!key_map.is_assigned(0, &first));
+
+        key_map.reclaim(None);
+        let fifth = actors.insert(());
+        assert_eq!(key_map.assign(&fifth), 1);
+
+        // every reuse of a key is a new generation of it
+        assert_eq!(key_map.get_generation(0), 1);
+        assert_eq!(key_map.get_generation(1), 1);
+        assert_eq!(key_map.get_generation(2), 0);
+        key_map.release(0, 20, &now);
+        key_map.reclaim(None);
+        let sixth = actors.insert(());
+        assert_eq!(key_map.assign(&sixth), 0);
+        assert_eq!(key_map.get_generation(0), 2);
+    }
+
+    #[test]
+    fn quarantine_is_bounded_in_time() {
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut key_map = LocalActorKeyMap::new();
+        let mut now = SystemClock.now();
+        let first = actors.insert(());
+        assert_eq!(key_map.assign(&first), 0);
+        key_map.release(0, 10, &now);
+        assert_eq!(key_map.get_held_count(), 1);
+
+        // packet 10 is never resolved, but the key is freed once it has been
+        // held back for long enough
+        now.add_millis(900);
+        key_map.reclaim_expired(&now, Duration::from_secs(1));
+        let second = actors.insert(());
+        assert_eq!(key_map.assign(&second), 1);
+        now.add_millis(200);
+        key_map.reclaim_expired(&now, Duration::from_secs(1));
+        let third = actors.insert(());
+        assert_eq!(key_map.assign(&third), 0);
+        assert_eq!(key_map.get_generation(0), 1);
+        assert_eq!(key_map.get_held_count(), 2);
+    }
+}
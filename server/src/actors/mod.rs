@@ -1,7 +1,12 @@
+pub(crate) mod actor_history;
 pub(crate) mod actor_key;
 pub(crate) mod actor_packet_writer;
 pub(crate) mod actor_record;
+pub(crate) mod actor_sync_state;
+pub(crate) mod local_actor_key_map;
 pub(crate) mod mut_handler;
 pub(crate) mod server_actor_manager;
 pub(crate) mod server_actor_message;
 pub(crate) mod server_actor_mutator;
+pub(crate) mod update_rate;
+pub(crate) mod world_sync_state;
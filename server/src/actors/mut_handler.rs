@@ -2,23 +2,40 @@ use std::{cell::RefCell, collections::HashMap, net::SocketAddr, rc::Rc};
 
 use naia_shared::StateMask;
 
-use crate::actors::actor_key::actor_key::ActorKey;
+use crate::actors::{actor_key::actor_key::ActorKey, update_rate::UpdateRate};
 
 use indexmap::IndexMap;
 
+/// The serialized state of a static Actor, which is written into the creation
+/// message sent to each Client. Emptied if the Actor is ever mutated, so that
+/// creation messages still waiting to be sent fall back to writing the Actor's
+/// current state
+pub type StaticPayload = Rc<RefCell<Option<Box<[u8]>>>>;
+
 #[derive(Debug)]
 pub struct MutHandler {
     actor_state_mask_list_map: HashMap<ActorKey, IndexMap<SocketAddr, Rc<RefCell<StateMask>>>>,
+    static_actors: HashMap<ActorKey, StaticPayload>,
+    upgraded_actors: Vec<ActorKey>,
+    update_rates: HashMap<ActorKey, UpdateRate>,
 }
 
 impl MutHandler {
     pub fn new() -> Rc<RefCell<MutHandler>> {
         Rc::new(RefCell::new(MutHandler {
             actor_state_mask_list_map: HashMap::new(),
+            static_actors: HashMap::new(),
+            upgraded_actors: Vec::new(),
+            update_rates: HashMap::new(),
         }))
     }
 
     pub fn mutate(&mut self, actor_key: &ActorKey, property_index: u8) {
+        if let Some(payload) = self.static_actors.remove(actor_key) {
+            warn!("static actor was mutated, it will be synced as a regular actor from now on");
+            payload.borrow_mut().take();
+            self.upgraded_actors.push(*actor_key);
+        }
         if let Some(state_mask_list) = self.actor_state_mask_list_map.get_mut(actor_key) {
             for (_, mask_ref) in state_mask_list.iter_mut() {
                 mask_ref.borrow_mut().set_bit(property_index, true);
@@ -57,6 +74,46 @@ impl MutHandler {
 
     pub fn deregister_actor(&mut self, actor_key: &ActorKey) {
         self.actor_state_mask_list_map.remove(actor_key);
+        self.static_actors.remove(actor_key);
+        self.update_rates.remove(actor_key);
+    }
+
+    /// Marks a registered Actor as static, given its serialized state
+    pub fn set_static(&mut self, actor_key: &ActorKey, payload: Box<[u8]>) {
+        self.static_actors
+            .insert(*actor_key, Rc::new(RefCell::new(Some(payload))));
+    }
+
+    pub fn is_static(&self, actor_key: &ActorKey) -> bool {
+        return self.static_actors.contains_key(actor_key);
+    }
+
+    pub fn get_static_payload(&self, actor_key: &ActorKey) -> Option<StaticPayload> {
+        return self.static_actors.get(actor_key).cloned();
+    }
+
+    /// Takes the static Actors which have been mutated since this was last
+    /// called, and so must now be checked for changes like any other Actor
+    pub fn take_upgraded_actors(&mut self) -> Vec<ActorKey> {
+        return std::mem::take(&mut self.upgraded_actors);
+    }
+
+    /// Sets how often changes to an Actor are sent, for every Client which
+    /// doesn't have its own rate set for the Actor
+    pub fn set_update_rate(&mut self, actor_key: &ActorKey, update_rate: UpdateRate) {
+        if update_rate == UpdateRate::EveryTick {
+            self.update_rates.remove(actor_key);
+        } else {
+            self.update_rates.insert(*actor_key, update_rate);
+        }
+    }
+
+    pub fn get_update_rate(&self, actor_key: &ActorKey) -> UpdateRate {
+        return self
+            .update_rates
+            .get(actor_key)
+            .copied()
+            .unwrap_or(UpdateRate::EveryTick);
     }
 
     pub fn register_mask(
@@ -75,4 +132,51 @@ impl MutHandler {
             state_mask_list.remove(address);
         }
     }
+
+    /// Moves every State Mask registered for a Client from its old address to
+    /// its new one, after its connection has resumed from another address
+    pub fn move_masks(&mut self, old_address: &SocketAddr, new_address: &SocketAddr) {
+        if old_address == new_address {
+            return;
+        }
+        for state_mask_list in self.actor_state_mask_list_map.values_mut() {
+            if let Some(mask_ref) = state_mask_list.remove(old_address) {
+                state_mask_list.insert(*new_address, mask_ref);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slotmap::DenseSlotMap;
+
+    use super::MutHandler;
+    use crate::ActorKey;
+
+    #[test]
+    fn mutating_static_actor_upgrades_it() {
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let static_key = actors.insert(());
+        let dynamic_key = actors.insert(());
+
+        let mut_handler = MutHandler::new();
+        let mut mut_handler = mut_handler.borrow_mut();
+        mut_handler.register_actor(&static_key);
+        mut_handler.register_actor(&dynamic_key);
+        mut_handler.set_static(&static_key, vec![1, 2, 3].into_boxed_slice());
+
+        let payload = mut_handler.get_static_payload(&static_key).unwrap();
+        assert!(mut_handler.is_static(&static_key));
+        assert!(!mut_handler.is_static(&dynamic_key));
+
+        mut_handler.mutate(&dynamic_key, 0);
+        assert_eq!(mut_handler.take_upgraded_actors().len(), 0);
+
+        mut_handler.mutate(&static_key, 0);
+        assert!(!mut_handler.is_static(&static_key));
+        assert!(payload.borrow().is_none());
+        assert_eq!(mut_handler.take_upgraded_actors(), vec![static_key]);
+        assert_eq!(mut_handler.take_upgraded_actors().len(), 0);
+    }
 }
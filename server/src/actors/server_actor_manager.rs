@@ -5,17 +5,28 @@ use std::{
     collections::{HashMap, HashSet, VecDeque},
     net::SocketAddr,
     rc::Rc,
+    time::Duration,
 };
 
+use indexmap::IndexSet;
 use slotmap::SparseSecondaryMap;
 
 use super::{
     actor_key::actor_key::ActorKey,
     actor_record::{ActorRecord, LocalActorStatus},
+    actor_sync_state::ActorSyncState,
+    local_actor_key_map::LocalActorKeyMap,
     mut_handler::MutHandler,
     server_actor_message::ServerActorMessage,
+    update_rate::UpdateRate,
+    world_sync_state::WorldSyncState,
+};
+use crate::tick_summary::TickSummary;
+use naia_shared::{
+    sequence_greater_than, strict_assert, utils::duration_between, wrapping_diff, Actor,
+    ActorGeneration, ActorNotifiable, ActorType, DespawnReason, Instant, LocalActorKey,
+    PredictionId, SequenceNumber, StateMask,
 };
-use naia_shared::{Actor, ActorNotifiable, ActorType, LocalActorKey, StateMask};
 
 /// Manages Actors for a given Client connection and keeps them in sync on the
 /// Client
@@ -23,52 +34,121 @@ use naia_shared::{Actor, ActorNotifiable, ActorType, LocalActorKey, StateMask};
 pub struct ServerActorManager<T: ActorType> {
     address: SocketAddr,
     local_actor_store: SparseSecondaryMap<ActorKey, Rc<RefCell<dyn Actor<T>>>>,
-    local_to_global_key_map: HashMap<LocalActorKey, ActorKey>,
-    recycled_local_keys: Vec<LocalActorKey>,
-    next_new_local_key: LocalActorKey,
+    local_key_map: LocalActorKeyMap,
     actor_records: SparseSecondaryMap<ActorKey, ActorRecord>,
+    // Actors which are checked for changes every tick, leaving out static ones,
+    // in the order they came into scope, so that their updates are always
+    // queued in the same order
+    dynamic_actors: IndexSet<ActorKey>,
     queued_messages: VecDeque<ServerActorMessage<T>>,
     sent_messages: HashMap<u16, Vec<ServerActorMessage<T>>>,
     sent_updates: HashMap<u16, HashMap<ActorKey, Rc<RefCell<StateMask>>>>,
+    sent_packet_ticks: HashMap<u16, u16>,
     last_update_packet_index: u16,
     last_last_update_packet_index: u16,
+    last_sent_packet_index: u16,
     mut_handler: Rc<RefCell<MutHandler>>,
     last_popped_state_mask: StateMask,
     pawn_store: HashSet<ActorKey>,
+    // the PredictionId each Actor spawned for a request of the Client is to be
+    // created with, until it is
+    spawn_predictions: HashMap<ActorKey, PredictionId>,
+    // the Properties of each Actor which are sent to the Client, for Actors
+    // which don't have all of them sent. Kept while Actors leave & come back
+    // into scope
+    property_visibility: HashMap<ActorKey, StateMask>,
+    world_sync_state: WorldSyncState,
+    world_sync_queue: VecDeque<ServerActorMessage<T>>,
+    world_sync_pending: HashSet<ActorKey>,
+    world_sync_bytes_per_tick: usize,
+    world_sync_completed: bool,
+    // Actors whose whole state is being resent, as asked for by the Client
+    // after it missed a long run of packets, until the Client has it
+    resyncing: HashSet<ActorKey>,
+    // the index of each packet Actor messages were sent in, with the time it
+    // was sent at, oldest first
+    sent_packet_times: VecDeque<(u16, Instant)>,
+    // each Actor whose deletion has been sent, with its LocalActorKey & the
+    // time the deletion was first sent at, oldest first
+    sent_deletions: VecDeque<(ActorKey, LocalActorKey, Instant)>,
+    // the time of the last call to expire_despawns(), which messages sent
+    // since are stamped with
+    now: Instant,
+    paused: bool,
 }
 
 impl<T: ActorType> ServerActorManager<T> {
-    /// Create a new ServerActorManager, given the client's address and a
-    /// reference to a MutHandler associated with the Client
-    pub fn new(address: SocketAddr, mut_handler: &Rc<RefCell<MutHandler>>) -> Self {
+    /// Create a new ServerActorManager, given the client's address, a
+    /// reference to a MutHandler associated with the Client, and the number of
+    /// bytes of Actor creation messages which may be released each tick while
+    /// the initial world sync is in progress, as of the given current time
+    pub fn new(
+        address: SocketAddr,
+        mut_handler: &Rc<RefCell<MutHandler>>,
+        world_sync_bytes_per_tick: usize,
+        now: &Instant,
+    ) -> Self {
         ServerActorManager {
             address,
             local_actor_store: SparseSecondaryMap::new(),
-            local_to_global_key_map: HashMap::new(),
-            recycled_local_keys: Vec::new(),
-            next_new_local_key: 0,
+            local_key_map: LocalActorKeyMap::new(),
             actor_records: SparseSecondaryMap::new(),
+            dynamic_actors: IndexSet::new(),
             queued_messages: VecDeque::new(),
             sent_messages: HashMap::new(),
             sent_updates: HashMap::<u16, HashMap<ActorKey, Rc<RefCell<StateMask>>>>::new(),
+            sent_packet_ticks: HashMap::new(),
             last_update_packet_index: 0,
             last_last_update_packet_index: 0,
+            last_sent_packet_index: 0,
             mut_handler: mut_handler.clone(),
             last_popped_state_mask: StateMask::new(0),
             pawn_store: HashSet::new(),
+            spawn_predictions: HashMap::new(),
+            property_visibility: HashMap::new(),
+            world_sync_state: WorldSyncState::Idle,
+            world_sync_queue: VecDeque::new(),
+            world_sync_pending: HashSet::new(),
+            world_sync_bytes_per_tick,
+            world_sync_completed: false,
+            resyncing: HashSet::new(),
+            sent_packet_times: VecDeque::new(),
+            sent_deletions: VecDeque::new(),
+            now: now.clone(),
+            paused: false,
         }
     }
 
     pub fn has_outgoing_messages(&self) -> bool {
-        return self.queued_messages.len() != 0;
+        return !self.paused && self.queued_messages.len() != 0;
+    }
+
+    /// Returns the number of messages either waiting to be transmitted, or
+    /// waiting to be acknowledged by the Client
+    pub fn get_outgoing_message_count(&self) -> usize {
+        let in_flight: usize = self.sent_messages.values().map(|list| list.len()).sum();
+        return self.queued_messages.len() + self.world_sync_queue.len() + in_flight;
     }
 
-    pub fn pop_outgoing_message(&mut self, packet_index: u16) -> Option<ServerActorMessage<T>> {
+    pub fn pop_outgoing_message(
+        &mut self,
+        packet_index: u16,
+        host_tick: u16,
+    ) -> Option<ServerActorMessage<T>> {
+        if self.paused {
+            return None;
+        }
+
         match self.queued_messages.pop_front() {
             Some(message) => {
+                self.sent_packet_ticks.insert(packet_index, host_tick);
+                self.last_sent_packet_index = packet_index;
+
                 if !self.sent_messages.contains_key(&packet_index) {
                     let sent_messages_list: Vec<ServerActorMessage<T>> = Vec::new();
                     self.sent_messages.insert(packet_index, sent_messages_list);
+                    self.sent_packet_times
+                        .push_back((packet_index, self.now.clone()));
                 }
 
                 if let Some(sent_messages_list) = self.sent_messages.get_mut(&packet_index) {
@@ -77,19 +157,48 @@ impl<T: ActorType> ServerActorManager<T> {
 
                 //clear state mask of actor if need be
                 match &message {
-                    ServerActorMessage::CreateActor(global_key, _, _) => {
-                        if let Some(record) = self.actor_records.get(*global_key) {
+                    ServerActorMessage::CreateActor(global_key, _, _, _, _) => {
+                        if let Some(record) = self.actor_records.get_mut(*global_key) {
                             self.last_popped_state_mask =
                                 record.get_state_mask().as_ref().borrow().clone();
+                            record.creation_sent = true;
                         }
                         self.mut_handler
                             .as_ref()
                             .borrow_mut()
                             .clear_state(&self.address, global_key);
                     }
+                    ServerActorMessage::DeleteActor(global_key, local_key, _) => {
+                        if let Some(record) = self.actor_records.get_mut(*global_key) {
+                            strict_assert!(
+                                record.creation_sent,
+                                "Actor {} is being deleted on {} before its creation was sent, \
+                                 in packet {}",
+                                local_key,
+                                self.address,
+                                packet_index
+                            );
+                            if !record.deletion_sent {
+                                record.deletion_sent = true;
+                                self.sent_deletions.push_back((
+                                    *global_key,
+                                    *local_key,
+                                    self.now.clone(),
+                                ));
+                            }
+                        }
+                    }
                     ServerActorMessage::UpdateActor(global_key, local_key, state_mask, actor) => {
+                        // every Property still in flight is written again, so
+                        // that this update holds all an older one did, & the
+                        // Client can discard whichever arrives after it
+                        let in_flight_state_mask = self.get_in_flight_state_mask(global_key);
                         let locked_state_mask =
                             self.process_actor_update(packet_index, global_key, state_mask);
+                        locked_state_mask
+                            .as_ref()
+                            .borrow_mut()
+                            .or(&in_flight_state_mask);
                         // return new Update message to be written
                         return Some(ServerActorMessage::UpdateActor(
                             *global_key,
@@ -120,6 +229,26 @@ impl<T: ActorType> ServerActorManager<T> {
         }
     }
 
+    // the Properties written in the updates to an Actor which are still in
+    // flight, other than those since hidden from the Client
+    fn get_in_flight_state_mask(&self, global_key: &ActorKey) -> StateMask {
+        let mut in_flight_state_mask = StateMask::new(0);
+        for sent_updates_map in self.sent_updates.values() {
+            if let Some(state_mask) = sent_updates_map.get(global_key) {
+                let state_mask = state_mask.as_ref().borrow();
+                if in_flight_state_mask.byte_number() < state_mask.byte_number() {
+                    in_flight_state_mask = StateMask::new(state_mask.byte_number());
+                }
+                in_flight_state_mask.or(&state_mask);
+            }
+        }
+        hide_properties(
+            self.property_visibility.get(global_key),
+            &mut in_flight_state_mask,
+        );
+        return in_flight_state_mask;
+    }
+
     fn process_actor_update(
         &mut self,
         packet_index: u16,
@@ -141,6 +270,9 @@ impl<T: ActorType> ServerActorManager<T> {
         if let Some(sent_updates_map) = self.sent_updates.get_mut(&packet_index) {
             sent_updates_map.insert(*global_key, locked_state_mask.clone());
         }
+        if let Some(record) = self.actor_records.get_mut(*global_key) {
+            record.in_flight_updates += 1;
+        }
 
         // having copied the state mask for this update, clear the state
         self.last_popped_state_mask = state_mask.as_ref().borrow().clone();
@@ -158,11 +290,16 @@ impl<T: ActorType> ServerActorManager<T> {
             sent_messages_list.pop();
             if sent_messages_list.len() == 0 {
                 self.sent_messages.remove(&packet_index);
+                if let Some((last_packet_index, _)) = self.sent_packet_times.back() {
+                    if *last_packet_index == packet_index {
+                        self.sent_packet_times.pop_back();
+                    }
+                }
             }
         }
 
         match &message {
-            ServerActorMessage::CreateActor(global_key, _, _) => {
+            ServerActorMessage::CreateActor(global_key, _, _, _, _) => {
                 self.mut_handler.as_ref().borrow_mut().set_state(
                     &self.address,
                     global_key,
@@ -210,6 +347,9 @@ impl<T: ActorType> ServerActorManager<T> {
         }
 
         self.last_update_packet_index = self.last_last_update_packet_index;
+        if let Some(record) = self.actor_records.get_mut(*global_key) {
+            record.in_flight_updates = record.in_flight_updates.saturating_sub(1);
+        }
         self.mut_handler.as_ref().borrow_mut().set_state(
             &self.address,
             global_key,
@@ -230,8 +370,7 @@ impl<T: ActorType> ServerActorManager<T> {
     pub fn add_actor(&mut self, key: &ActorKey, actor: &Rc<RefCell<dyn Actor<T>>>) {
         if !self.local_actor_store.contains_key(*key) {
             self.local_actor_store.insert(*key, actor.clone());
-            let local_key = self.get_new_local_key();
-            self.local_to_global_key_map.insert(local_key, *key);
+            let local_key = self.local_key_map.assign(key);
             let state_mask_size = actor.as_ref().borrow().get_state_mask_size();
             let actor_record = ActorRecord::new(local_key, state_mask_size);
             self.mut_handler.as_ref().borrow_mut().register_mask(
@@ -240,44 +379,104 @@ impl<T: ActorType> ServerActorManager<T> {
                 actor_record.get_state_mask(),
             );
             self.actor_records.insert(*key, actor_record);
-            self.queued_messages
-                .push_back(ServerActorMessage::CreateActor(
-                    *key,
-                    local_key,
-                    actor.clone(),
-                ));
+            let static_payload = self.mut_handler.as_ref().borrow().get_static_payload(key);
+            if static_payload.is_none() {
+                self.dynamic_actors.insert(*key);
+            }
+            if self.world_sync_state == WorldSyncState::Syncing {
+                self.world_sync_pending.insert(*key);
+            }
+            let prediction_id = self.spawn_predictions.remove(key);
+            self.queue_message(ServerActorMessage::CreateActor(
+                *key,
+                local_key,
+                actor.clone(),
+                static_payload,
+                prediction_id,
+            ));
 
             // if this is a pawn, send a "assign pawn" follow-up message
             if self.pawn_store.contains(key) {
-                self.queued_messages
-                    .push_back(ServerActorMessage::AssignPawn(*key, local_key));
+                self.queue_message(ServerActorMessage::AssignPawn(*key, local_key));
             }
         }
     }
 
-    pub fn remove_actor(&mut self, key: &ActorKey) {
+    pub fn remove_actor(&mut self, key: &ActorKey, reason: DespawnReason) {
+        self.spawn_predictions.remove(key);
         if let Some(actor_record) = self.actor_records.get_mut(*key) {
             if actor_record.status != LocalActorStatus::Deleting {
                 actor_record.status = LocalActorStatus::Deleting;
+                let local_key = actor_record.local_key;
+
+                // the Client never heard of an Actor whose creation is still
+                // queued, as while replication is paused, so it's dropped
+                // along with the creation, rather than created & deleted
+                if !actor_record.creation_sent {
+                    self.queued_messages
+                        .retain(|message| message.get_global_key() != Some(*key));
+                    self.world_sync_queue
+                        .retain(|message| message.get_global_key() != Some(*key));
+                    self.forget_actor(*key, local_key);
+                    return;
+                }
 
                 // if this is a pawn, send an "unassign pawn" message first
                 if self.pawn_store.contains(key) {
-                    self.queued_messages
-                        .push_back(ServerActorMessage::UnassignPawn(
-                            *key,
-                            actor_record.local_key,
-                        ));
+                    self.queue_message(ServerActorMessage::UnassignPawn(*key, local_key));
                 }
 
-                self.queued_messages
-                    .push_back(ServerActorMessage::DeleteActor(
-                        *key,
-                        actor_record.local_key,
-                    ));
+                self.queue_message(ServerActorMessage::DeleteActor(*key, local_key, reason));
             }
         }
     }
 
+    /// Tags the creation of an Actor with the PredictionId the Client
+    /// predicted its spawn with. Only the first creation of the Actor is
+    /// tagged, so it should be set before the Actor comes into scope
+    pub fn set_spawn_prediction(&mut self, key: &ActorKey, prediction_id: PredictionId) {
+        if !self.local_actor_store.contains_key(*key) {
+            self.spawn_predictions.insert(*key, prediction_id);
+        }
+    }
+
+    /// Sets which Properties of an Actor are sent to the Client, as the bits
+    /// of a StateMask indexed like the Actor's own. Changes to hidden
+    /// Properties are never sent, and they're written with their default
+    /// values whenever the whole Actor is. Properties which become visible
+    /// have their current values sent. Passing None makes every Property
+    /// visible again
+    pub fn set_property_visibility(&mut self, key: &ActorKey, visibility: Option<StateMask>) {
+        let old_visibility = match visibility {
+            Some(visibility) => self.property_visibility.insert(*key, visibility),
+            None => self.property_visibility.remove(key),
+        };
+        if let Some(record) = self.actor_records.get(*key) {
+            let new_visibility = self.property_visibility.get(key);
+            let mut state_mask = record.get_state_mask().as_ref().borrow_mut();
+            let bit_count = u16::from(state_mask.byte_number()) * 8;
+            let mut revealed = false;
+            for index in 0..bit_count.min(256) {
+                let index = index as u8;
+                if is_visible(new_visibility, index) && !is_visible(old_visibility.as_ref(), index)
+                {
+                    state_mask.set_bit(index, true);
+                    revealed = true;
+                }
+            }
+            // a static Actor is only checked for changes once it's dynamic
+            if revealed {
+                self.dynamic_actors.insert(*key);
+            }
+        }
+    }
+
+    /// Gets which Properties of an Actor are sent to the Client, if not all
+    /// of them are
+    pub fn get_property_visibility(&self, key: &ActorKey) -> Option<&StateMask> {
+        return self.property_visibility.get(key);
+    }
+
     pub fn has_pawn(&self, key: &ActorKey) -> bool {
         return self.pawn_store.contains(key);
     }
@@ -285,9 +484,9 @@ impl<T: ActorType> ServerActorManager<T> {
     pub fn add_pawn(&mut self, key: &ActorKey) {
         if !self.pawn_store.contains(key) {
             self.pawn_store.insert(*key);
-            if let Some(actor_record) = self.actor_records.get_mut(*key) {
-                self.queued_messages
-                    .push_back(ServerActorMessage::AssignPawn(*key, actor_record.local_key));
+            if let Some(actor_record) = self.actor_records.get(*key) {
+                let local_key = actor_record.local_key;
+                self.queue_message(ServerActorMessage::AssignPawn(*key, local_key));
             }
         }
     }
@@ -295,37 +494,381 @@ impl<T: ActorType> ServerActorManager<T> {
     pub fn remove_pawn(&mut self, key: &ActorKey) {
         if self.pawn_store.contains(key) {
             self.pawn_store.remove(key);
-            if let Some(actor_record) = self.actor_records.get_mut(*key) {
+            if let Some(actor_record) = self.actor_records.get(*key) {
+                let local_key = actor_record.local_key;
+                self.queue_message(ServerActorMessage::UnassignPawn(*key, local_key));
+            }
+        }
+    }
+
+    /// Marks every Property of a Pawn as changed, so that its full state is
+    /// sent to the Client with the next updates, correcting its prediction
+    pub fn force_pawn_update(&mut self, key: &ActorKey) {
+        if !self.pawn_store.contains(key) {
+            return;
+        }
+        if let Some(actor_record) = self.actor_records.get(*key) {
+            let mut state_mask = actor_record.get_state_mask().as_ref().borrow_mut();
+            let bit_count = u16::from(state_mask.byte_number()) * 8;
+            for index in 0..bit_count.min(256) {
+                state_mask.set_bit(index as u8, true);
+            }
+        }
+    }
+
+    /// Stops sending any Actor messages to the Client. Queued messages are held
+    /// and State Masks keep accumulating, so that only the net changes are sent
+    /// on resume
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes sending Actor messages to the Client
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.paused;
+    }
+
+    /// Prepares for the Client's connection to be resumed, from the given
+    /// address, with packet indices starting over. Every packet still in
+    /// flight is treated as dropped, so that what it carried is sent again,
+    /// while everything the Client has acknowledged is kept
+    pub fn reset_connection(&mut self, address: SocketAddr) {
+        let last_sent_packet_index = self.last_sent_packet_index;
+        let mut in_flight: Vec<u16> = self.sent_messages.keys().copied().collect();
+        // oldest first, so that each dropped update only restores the state
+        // which wasn't sent again in a later packet
+        in_flight.sort_by_key(|packet_index| {
+            std::cmp::Reverse(wrapping_diff(*packet_index, last_sent_packet_index))
+        });
+        for packet_index in in_flight {
+            self.notify_packet_dropped(packet_index);
+        }
+        self.sent_updates.clear();
+        self.sent_packet_ticks.clear();
+        self.sent_packet_times.clear();
+        self.local_key_map.reclaim(None);
+        self.last_update_packet_index = 0;
+        self.last_last_update_packet_index = 0;
+        self.last_sent_packet_index = 0;
+
+        self.mut_handler
+            .as_ref()
+            .borrow_mut()
+            .move_masks(&self.address, &address);
+        self.address = address;
+    }
+
+    /// Bounds the bookkeeping held for a Client which stops acknowledging
+    /// packets, given the current time & how long to wait for acks. Packets
+    /// which have gone unacknowledged for that long are treated as dropped,
+    /// and Actors whose deletion was first sent that long ago are forgotten as
+    /// if it had been acknowledged, their LocalActorKeys being reused after
+    /// waiting as long again. A late ack or update for a forgotten Actor is
+    /// ignored. Returns the number of Actors forgotten
+    pub fn expire_despawns(&mut self, now: &Instant, retention: Duration) -> u64 {
+        self.now = now.clone();
+
+        while let Some((packet_index, sent_at)) = self.sent_packet_times.front() {
+            if duration_between(sent_at, now) < retention {
+                break;
+            }
+            let packet_index = *packet_index;
+            self.sent_packet_times.pop_front();
+            if self.sent_messages.contains_key(&packet_index) {
+                self.notify_packet_dropped(packet_index);
+            }
+        }
+
+        let mut expired_count = 0;
+        while let Some((global_key, local_key, sent_at)) = self.sent_deletions.front() {
+            if duration_between(sent_at, now) < retention {
+                break;
+            }
+            let (global_key, local_key) = (*global_key, *local_key);
+            self.sent_deletions.pop_front();
+            if self.forget_actor(global_key, local_key) {
+                // the deletion may have been queued to be sent again
                 self.queued_messages
-                    .push_back(ServerActorMessage::UnassignPawn(
-                        *key,
-                        actor_record.local_key,
-                    ));
+                    .retain(|message| message.get_global_key() != Some(global_key));
+                self.world_sync_queue
+                    .retain(|message| message.get_global_key() != Some(global_key));
+                expired_count += 1;
+            }
+        }
+        self.local_key_map.reclaim_expired(now, retention);
+        return expired_count;
+    }
+
+    // Drops every record of an Actor which is being deleted, once the deletion
+    // has been acknowledged or has gone unacknowledged for too long, releasing
+    // its LocalActorKey. Returns false if the Actor has already been forgotten,
+    // or has been added again under another key since
+    fn forget_actor(&mut self, global_key: ActorKey, local_key: LocalActorKey) -> bool {
+        match self.actor_records.get(global_key) {
+            Some(record) => {
+                if record.local_key != local_key || record.status != LocalActorStatus::Deleting {
+                    return false;
+                }
+            }
+            None => return false,
+        }
+        self.mut_handler
+            .as_ref()
+            .borrow_mut()
+            .deregister_mask(&self.address, &global_key);
+        self.local_actor_store.remove(global_key);
+        self.local_key_map
+            .release(local_key, self.last_sent_packet_index, &self.now);
+        self.actor_records.remove(global_key);
+        self.dynamic_actors.shift_remove(&global_key);
+        self.pawn_store.remove(&global_key);
+        self.resyncing.remove(&global_key);
+        self.world_sync_pending.remove(&global_key);
+        return true;
+    }
+
+    /// Returns true once, after the Client has been notified that the initial
+    /// world sync has completed
+    pub fn take_world_sync_complete(&mut self) -> bool {
+        if self.world_sync_completed {
+            self.world_sync_completed = false;
+            return true;
+        }
+        return false;
+    }
+
+    /// Starts the initial world sync, once the Client has scope. Until then
+    /// there's nothing to sync, so the Client isn't told the sync is complete
+    pub fn begin_world_sync(&mut self) {
+        if self.world_sync_state == WorldSyncState::Idle {
+            self.world_sync_state = WorldSyncState::Syncing;
+        }
+    }
+
+    // While the initial world sync is in progress, creation/deletion/pawn
+    // messages are held back in a separate queue so that they can be released
+    // a bit at a time, and still arrive in order
+    fn queue_message(&mut self, message: ServerActorMessage<T>) {
+        if self.world_sync_state == WorldSyncState::Syncing {
+            self.world_sync_queue.push_back(message);
+        } else {
+            self.queued_messages.push_back(message);
+        }
+    }
+
+    fn release_world_sync_messages(&mut self) {
+        if self.world_sync_state != WorldSyncState::Syncing {
+            return;
+        }
+
+        // release messages ahead of any updates, up to the byte budget. At least
+        // one message is released each tick so that the sync always progresses
+        let mut released_bytes: usize = 0;
+        while let Some(message) = self.world_sync_queue.front() {
+            let message_size = get_world_sync_message_size(message);
+            if released_bytes != 0 && released_bytes + message_size > self.world_sync_bytes_per_tick
+            {
+                break;
             }
+            released_bytes += message_size;
+            let message = self.world_sync_queue.pop_front().unwrap();
+            self.queued_messages.push_back(message);
+        }
+
+        if self.world_sync_queue.len() == 0 && self.world_sync_pending.len() == 0 {
+            self.world_sync_state = WorldSyncState::Completing;
+            self.queued_messages
+                .push_back(ServerActorMessage::WorldSyncComplete);
         }
     }
 
     pub fn get_global_key_from_local(&self, local_key: LocalActorKey) -> Option<&ActorKey> {
-        return self.local_to_global_key_map.get(&local_key);
+        return self.local_key_map.get_global_key(local_key);
+    }
+
+    // Gets the index of the oldest packet with Actor messages which has been
+    // neither acknowledged nor dropped
+    fn get_oldest_in_flight_packet(&self) -> Option<SequenceNumber> {
+        let last_sent_packet_index = self.last_sent_packet_index;
+        return self
+            .sent_messages
+            .keys()
+            .max_by_key(|packet_index| wrapping_diff(**packet_index, last_sent_packet_index))
+            .copied();
+    }
+
+    // Whether a dropped message still applies to the Actor it was sent for, and
+    // so should be sent again. Messages for Actors which have since been
+    // deleted, or whose LocalActorKey has been released, are not resent, so
+    // that they can't arrive after the deletion, or be applied to another Actor
+    /// Queues the whole state of the Actor the Client holds with the given
+    /// LocalActorKey & ActorGeneration to be resent, through the same reliable
+    /// path as its creation, as the Client asks for after missing a long run of
+    /// packets. Requests for an Actor the key no longer belongs to, which is
+    /// being created or deleted, which is a Pawn, or whose state is already
+    /// being resent, are ignored. Returns whether the state was queued
+    pub fn resync_actor(&mut self, local_key: LocalActorKey, generation: ActorGeneration) -> bool {
+        let global_key = match self.local_key_map.get_global_key(local_key) {
+            Some(global_key) => *global_key,
+            None => return false,
+        };
+        if self.local_key_map.get_generation(local_key) != generation
+            || self.get_status(&global_key) != Some(&LocalActorStatus::Created)
+            || self.pawn_store.contains(&global_key)
+            || self.resyncing.contains(&global_key)
+        {
+            return false;
+        }
+        let actor = match self.local_actor_store.get(global_key) {
+            Some(actor) => actor.clone(),
+            None => return false,
+        };
+        self.resyncing.insert(global_key);
+        self.queue_message(ServerActorMessage::ResyncActor(
+            global_key, local_key, actor,
+        ));
+        return true;
+    }
+
+    fn should_resend(&self, message: &ServerActorMessage<T>) -> bool {
+        match message {
+            ServerActorMessage::CreateActor(global_key, local_key, _, _, _) => {
+                return self.local_key_map.is_assigned(*local_key, global_key)
+                    && self.get_status(global_key) == Some(&LocalActorStatus::Creating);
+            }
+            ServerActorMessage::AssignPawn(global_key, local_key)
+            | ServerActorMessage::UnassignPawn(global_key, local_key) => {
+                return self.local_key_map.is_assigned(*local_key, global_key)
+                    && self.get_status(global_key) != Some(&LocalActorStatus::Deleting);
+            }
+            ServerActorMessage::DeleteActor(global_key, local_key, _) => {
+                return self.local_key_map.is_assigned(*local_key, global_key);
+            }
+            ServerActorMessage::ResyncActor(global_key, local_key, _) => {
+                return self.local_key_map.is_assigned(*local_key, global_key)
+                    && self.get_status(global_key) == Some(&LocalActorStatus::Created);
+            }
+            _ => {
+                return true;
+            }
+        }
+    }
+
+    fn get_status(&self, key: &ActorKey) -> Option<&LocalActorStatus> {
+        return self.actor_records.get(*key).map(|record| &record.status);
+    }
+
+    /// Start checking a static Actor for changes every tick, after it has been
+    /// mutated
+    pub fn make_dynamic(&mut self, key: &ActorKey) {
+        if self.actor_records.contains_key(*key) {
+            self.dynamic_actors.insert(*key);
+        }
+    }
+
+    /// Gets the LocalActorKey the Client knows an Actor in scope by
+    pub fn get_local_key(&self, key: &ActorKey) -> Option<LocalActorKey> {
+        return self.actor_records.get(*key).map(|record| record.local_key);
+    }
+
+    /// Get how far along the Client is in receiving the state of an Actor
+    pub fn get_sync_state(&self, key: &ActorKey) -> ActorSyncState {
+        match self.actor_records.get(*key) {
+            None => return ActorSyncState::NotInScope,
+            Some(record) => match record.status {
+                LocalActorStatus::Creating => return ActorSyncState::SpawnPending,
+                LocalActorStatus::Deleting => return ActorSyncState::DespawnPending,
+                LocalActorStatus::Created => {
+                    let last_acked_tick = record.last_acked_tick;
+                    if record.in_flight_updates > 0
+                        || !record.get_state_mask().as_ref().borrow().is_clear()
+                    {
+                        return ActorSyncState::UpdatePending { last_acked_tick };
+                    }
+                    return ActorSyncState::Synced { last_acked_tick };
+                }
+            },
+        }
     }
 
-    fn get_new_local_key(&mut self) -> u16 {
-        if let Some(local_key) = self.recycled_local_keys.pop() {
-            return local_key;
+    /// Sets how often changes to an Actor are sent to this Client, overriding
+    /// the rate set for the Actor. Passing None goes back to that rate
+    pub fn set_update_rate(&mut self, key: &ActorKey, update_rate: Option<UpdateRate>) {
+        if let Some(record) = self.actor_records.get_mut(*key) {
+            record.update_rate = update_rate;
         }
+    }
 
-        let output = self.next_new_local_key;
-        self.next_new_local_key += 1;
-        return output;
+    /// Gets the ActorGeneration of the Actor a LocalActorKey is assigned to,
+    /// which every message for the Actor is tagged with
+    pub fn get_generation(&self, local_key: LocalActorKey) -> ActorGeneration {
+        return self.local_key_map.get_generation(local_key);
     }
 
-    pub fn collect_actor_updates(&mut self) {
-        for (key, record) in self.actor_records.iter() {
+    /// Gets the number of LocalActorKeys which are assigned or quarantined,
+    /// and so can't be given to an Actor coming into scope
+    pub fn get_held_local_key_count(&self) -> usize {
+        return self.local_key_map.get_held_count();
+    }
+
+    /// Queues updates for Actors which have changed, given the current Server
+    /// tick. Actors with a reduced update rate are skipped until enough ticks
+    /// have passed since their last update, while their changes accumulate.
+    /// The Actors found changed & the updates queued are counted into the
+    /// given TickSummary
+    pub fn collect_actor_updates(&mut self, server_tick: u16, summary: &mut TickSummary) {
+        if self.paused {
+            return;
+        }
+
+        self.release_world_sync_messages();
+
+        let mut_handler = self.mut_handler.as_ref().borrow();
+        for key in self.dynamic_actors.iter() {
+            let key = *key;
+            let record = match self.actor_records.get_mut(key) {
+                Some(record) => record,
+                None => continue,
+            };
+            hide_properties(
+                self.property_visibility.get(&key),
+                &mut record.get_state_mask().as_ref().borrow_mut(),
+            );
             if record.status == LocalActorStatus::Created
                 && !record.get_state_mask().as_ref().borrow().is_clear()
             {
+                summary.actors_dirty += 1;
                 if let Some(actor_ref) = self.local_actor_store.get(key) {
-                    if self.pawn_store.contains(&key) {
+                    let is_pawn = self.pawn_store.contains(&key);
+
+                    // pawns are always updated right away, so the Client's
+                    // prediction is corrected as soon as possible, and Actors
+                    // updated on every tick are updated as often as updates
+                    // are sent, even more than once in a tick
+                    let update_interval = if is_pawn {
+                        1
+                    } else {
+                        record
+                            .update_rate
+                            .unwrap_or_else(|| mut_handler.get_update_rate(&key))
+                            .get_interval()
+                    };
+                    if update_interval > 1 {
+                        if let Some(last_update_tick) = record.last_update_tick {
+                            let elapsed = i32::from(wrapping_diff(last_update_tick, server_tick));
+                            if elapsed >= 0 && elapsed < i32::from(update_interval) {
+                                continue;
+                            }
+                        }
+                    }
+                    record.last_update_tick = Some(server_tick);
+                    summary.updates_emitted += 1;
+
+                    if is_pawn {
                         // handle as a pawn
                         self.queued_messages
                             .push_back(ServerActorMessage::UpdatePawn(
@@ -352,52 +895,76 @@ impl<T: ActorType> ServerActorManager<T> {
 
 impl<T: ActorType> ActorNotifiable for ServerActorManager<T> {
     fn notify_packet_delivered(&mut self, packet_index: u16) {
-        if let Some(delivered_messages_list) = self.sent_messages.get(&packet_index) {
-            for delivered_message in delivered_messages_list.into_iter() {
+        let packet_tick = self.sent_packet_ticks.remove(&packet_index);
+        if let Some(delivered_messages_list) = self.sent_messages.remove(&packet_index) {
+            for delivered_message in delivered_messages_list.iter() {
                 match delivered_message {
-                    ServerActorMessage::CreateActor(global_key, _, _) => {
+                    ServerActorMessage::CreateActor(global_key, _, _, _, _) => {
                         if let Some(actor_record) = self.actor_records.get_mut(*global_key) {
                             // update actor record status
                             actor_record.status = LocalActorStatus::Created;
+                            if let Some(tick) = packet_tick {
+                                actor_record.last_acked_tick = tick;
+                            }
                         }
+                        self.world_sync_pending.remove(global_key);
                     }
-                    ServerActorMessage::DeleteActor(global_key_ref, local_key) => {
-                        let global_key = *global_key_ref;
-                        if let Some(_) = self.actor_records.get(global_key) {
-                            // actually delete the actor from local records
-                            self.mut_handler
-                                .as_ref()
-                                .borrow_mut()
-                                .deregister_mask(&self.address, global_key_ref);
-                            self.local_actor_store.remove(global_key);
-                            self.local_to_global_key_map.remove(local_key);
-                            self.recycled_local_keys.push(*local_key);
-                            self.actor_records.remove(global_key);
-                            self.pawn_store.remove(&global_key);
-                        }
+                    ServerActorMessage::DeleteActor(global_key, local_key, _) => {
+                        // actually delete the actor from local records
+                        self.forget_actor(*global_key, *local_key);
                     }
-                    ServerActorMessage::UpdateActor(_, _, _, _)
-                    | ServerActorMessage::UpdatePawn(_, _, _, _) => {
+                    ServerActorMessage::UpdateActor(global_key, _, _, _)
+                    | ServerActorMessage::UpdatePawn(global_key, _, _, _) => {
                         self.sent_updates.remove(&packet_index);
+                        if let Some(actor_record) = self.actor_records.get_mut(*global_key) {
+                            actor_record.in_flight_updates =
+                                actor_record.in_flight_updates.saturating_sub(1);
+                            if let Some(tick) = packet_tick {
+                                if sequence_greater_than(tick, actor_record.last_acked_tick) {
+                                    actor_record.last_acked_tick = tick;
+                                }
+                            }
+                        }
                     }
                     ServerActorMessage::AssignPawn(_, _) => {}
                     ServerActorMessage::UnassignPawn(_, _) => {}
+                    ServerActorMessage::ResyncActor(global_key, _, _) => {
+                        self.resyncing.remove(global_key);
+                    }
+                    ServerActorMessage::WorldSyncComplete => {
+                        if self.world_sync_state == WorldSyncState::Completing {
+                            self.world_sync_state = WorldSyncState::Complete;
+                            self.world_sync_completed = true;
+                        }
+                    }
                 }
             }
 
-            self.sent_messages.remove(&packet_index);
+            self.local_key_map
+                .reclaim(self.get_oldest_in_flight_packet());
         }
     }
 
     fn notify_packet_dropped(&mut self, dropped_packet_index: u16) {
+        self.sent_packet_ticks.remove(&dropped_packet_index);
         if let Some(dropped_messages_list) = self.sent_messages.get(&dropped_packet_index) {
             for dropped_message in dropped_messages_list.into_iter() {
                 match dropped_message {
-                    ServerActorMessage::CreateActor(_, _, _)
-                    | ServerActorMessage::DeleteActor(_, _)
+                    ServerActorMessage::CreateActor(_, _, _, _, _)
+                    | ServerActorMessage::DeleteActor(_, _, _)
                     | ServerActorMessage::AssignPawn(_, _)
-                    | ServerActorMessage::UnassignPawn(_, _) => {
-                        self.queued_messages.push_back(dropped_message.clone());
+                    | ServerActorMessage::UnassignPawn(_, _)
+                    | ServerActorMessage::WorldSyncComplete => {
+                        if self.should_resend(dropped_message) {
+                            self.queued_messages.push_back(dropped_message.clone());
+                        }
+                    }
+                    ServerActorMessage::ResyncActor(global_key, _, _) => {
+                        if self.should_resend(dropped_message) {
+                            self.queued_messages.push_back(dropped_message.clone());
+                        } else {
+                            self.resyncing.remove(global_key);
+                        }
                     }
                     ServerActorMessage::UpdateActor(global_key, _, _, _)
                     | ServerActorMessage::UpdatePawn(global_key, _, _, _) => {
@@ -430,12 +997,1172 @@ impl<T: ActorType> ActorNotifiable for ServerActorManager<T> {
                                 }
                             }
                         }
+                        if let Some(record) = self.actor_records.get_mut(*global_key) {
+                            record.in_flight_updates = record.in_flight_updates.saturating_sub(1);
+                        }
                     }
                 }
             }
 
             self.sent_updates.remove(&dropped_packet_index);
             self.sent_messages.remove(&dropped_packet_index);
+            self.local_key_map
+                .reclaim(self.get_oldest_in_flight_packet());
+        }
+    }
+}
+
+// whether a Property is visible to the Client, given the visibility set for
+// its Actor, if any
+fn is_visible(visibility: Option<&StateMask>, index: u8) -> bool {
+    match visibility {
+        Some(visibility) => return visibility.get_bit(index) == Some(true),
+        None => return true,
+    }
+}
+
+// clears the bits of a StateMask for Properties hidden from the Client
+fn hide_properties(visibility: Option<&StateMask>, state_mask: &mut StateMask) {
+    if visibility.is_none() {
+        return;
+    }
+    let bit_count = u16::from(state_mask.byte_number()) * 8;
+    for index in 0..bit_count.min(256) {
+        let index = index as u8;
+        if !is_visible(visibility, index) {
+            state_mask.set_bit(index, false);
+        }
+    }
+}
+
+// Gets the approximate number of bytes a message will take up in an outgoing
+// packet, used to rate-limit the initial world sync
+
+fn get_world_sync_message_size<T: ActorType>(message: &ServerActorMessage<T>) -> usize {
+    match message {
+        ServerActorMessage::CreateActor(_, _, actor, static_payload, _) => {
+            if let Some(payload) = static_payload {
+                if let Some(payload_bytes) = payload.as_ref().borrow().as_ref() {
+                    // message type + naia id + local key + net id
+                    return 13 + payload_bytes.len();
+                }
+            }
+            let mut payload_bytes = Vec::<u8>::new();
+            actor.as_ref().borrow().write(&mut payload_bytes);
+            // message type + naia id + local key + net id
+            return 13 + payload_bytes.len();
+        }
+        _ => {
+            // message type + local key
+            return 3;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, net::SocketAddr, rc::Rc, time::Duration};
+
+    use naia_shared::{
+        sequence_less_than,
+        test_support::{Gauge, TestActors},
+        Actor, ActorNotifiable, ActorType, Clock, DespawnReason, PacketReader, StateMask,
+        SystemClock,
+    };
+    use slotmap::DenseSlotMap;
+
+    use super::ServerActorManager;
+    use crate::{
+        actors::{
+            actor_sync_state::ActorSyncState, mut_handler::MutHandler,
+            server_actor_message::ServerActorMessage, update_rate::UpdateRate,
+        },
+        tick_summary::TickSummary,
+        ActorKey,
+    };
+
+    // sends everything queued in a single packet, returning the number of
+    // updates sent
+    fn send_packet(
+        manager: &mut ServerActorManager<TestActors>,
+        packet_index: u16,
+        tick: u16,
+    ) -> usize {
+        manager.collect_actor_updates(tick, &mut TickSummary::default());
+        let mut update_count = 0;
+        while let Some(message) = manager.pop_outgoing_message(packet_index, tick) {
+            if let ServerActorMessage::UpdateActor(_, _, _, _) = message {
+                update_count += 1;
+            }
+        }
+        return update_count;
+    }
+
+    fn get_keys(message: &ServerActorMessage<TestActors>) -> Option<(ActorKey, u16)> {
+        match message {
+            ServerActorMessage::CreateActor(global_key, local_key, _, _, _)
+            | ServerActorMessage::UpdateActor(global_key, local_key, _, _)
+            | ServerActorMessage::UpdatePawn(global_key, local_key, _, _)
+            | ServerActorMessage::ResyncActor(global_key, local_key, _) => {
+                return Some((*global_key, *local_key))
+            }
+            ServerActorMessage::DeleteActor(global_key, local_key, _)
+            | ServerActorMessage::AssignPawn(global_key, local_key)
+            | ServerActorMessage::UnassignPawn(global_key, local_key) => {
+                return Some((*global_key, *local_key))
+            }
+            ServerActorMessage::WorldSyncComplete => return None,
+        }
+    }
+
+    #[test]
+    fn local_keys_never_alias_under_churn_and_loss() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut_handler = MutHandler::new();
+        let keys: Vec<ActorKey> = (0..8).map(|_| actors.insert(())).collect();
+        for key in keys.iter() {
+            mut_handler.borrow_mut().register_actor(key);
+        }
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+
+        // a deterministic pseudo-random sequence
+        let mut seed: u32 = 0x2545_f491;
+        let mut random = move |range: u32| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            return seed % range;
+        };
+
+        // packets which have been neither acknowledged nor dropped, with the
+        // keys of the messages they carry
+        let mut in_flight: Vec<(u16, Vec<(ActorKey, u16)>)> = Vec::new();
+        let mut largest_local_key = 0;
+        for packet_index in 0..5000u16 {
+            // actors come in & out of scope, change, and become pawns
+            for key in keys.iter() {
+                match random(8) {
+                    0 => {
+                        let prop = TestActors::new_prop();
+                        manager.add_actor(key, &prop.inner_ref());
+                    }
+                    1 => manager.remove_actor(key, DespawnReason::OutOfScope),
+                    2 => manager.add_pawn(key),
+                    _ => {}
+                }
+                if manager.has_actor(key) {
+                    mut_handler.borrow_mut().mutate(key, 0);
+                }
+            }
+
+            manager.collect_actor_updates(packet_index, &mut TickSummary::default());
+            let mut sent_keys = Vec::new();
+            let mut sent_any = false;
+            while let Some(message) = manager.pop_outgoing_message(packet_index, packet_index) {
+                sent_any = true;
+                if let Some((global_key, local_key)) = get_keys(&message) {
+                    // no message still in flight uses the key for another Actor
+                    for (_, packet_keys) in in_flight.iter() {
+                        for (other_global_key, other_local_key) in packet_keys.iter() {
+                            assert!(
+                                *other_local_key != local_key || *other_global_key == global_key,
+                                "local key {} aliased in packet {}",
+                                local_key,
+                                packet_index
+                            );
+                        }
+                    }
+                    largest_local_key = largest_local_key.max(local_key);
+                    sent_keys.push((global_key, local_key));
+                }
+            }
+            if sent_any {
+                in_flight.push((packet_index, sent_keys));
+            }
+
+            // packets are resolved out of order, a quarter of them are lost,
+            // and any still unacknowledged after 32 packets are dropped
+            while in_flight.len() > 0 && random(3) != 0 {
+                let (index, _) = in_flight.remove(random(in_flight.len() as u32) as usize);
+                match random(4) {
+                    0 => manager.notify_packet_dropped(index),
+                    _ => manager.notify_packet_delivered(index),
+                }
+            }
+            while in_flight.len() > 0 && packet_index - in_flight[0].0 > 32 {
+                let (index, _) = in_flight.remove(0);
+                manager.notify_packet_dropped(index);
+            }
+        }
+
+        // keys stay compact, as they are reused
+        assert!(largest_local_key < 64, "largest key {}", largest_local_key);
+    }
+
+    // the number of entries the manager keeps for Actors & the packets they
+    // were sent in
+    fn get_bookkeeping_size(manager: &ServerActorManager<TestActors>) -> usize {
+        return manager.actor_records.len()
+            + manager.local_actor_store.len()
+            + manager.sent_messages.len()
+            + manager.sent_packet_times.len()
+            + manager.sent_deletions.len()
+            + manager.local_key_map.get_held_count();
+    }
+
+    #[test]
+    fn despawn_bookkeeping_plateaus_without_acks() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut_handler = MutHandler::new();
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let retention = Duration::from_secs(1);
+
+        // every 50ms, 4 Actors are spawned & those spawned before despawned,
+        // for a Client which never acknowledges a packet
+        let mut now = SystemClock.now();
+        let mut spawned: Vec<ActorKey> = Vec::new();
+        let mut expired_count = 0;
+        let mut midpoint_size = 0;
+        for packet_index in 0..1000u16 {
+            for key in spawned.drain(..) {
+                manager.remove_actor(&key, DespawnReason::OutOfScope);
+            }
+            for _ in 0..4 {
+                let key = actors.insert(());
+                mut_handler.borrow_mut().register_actor(&key);
+                let prop = TestActors::new_prop();
+                manager.add_actor(&key, &prop.inner_ref());
+                spawned.push(key);
+            }
+            expired_count += manager.expire_despawns(&now, retention);
+            send_packet(&mut manager, packet_index, packet_index);
+            now.add_millis(50);
+            if packet_index == 500 {
+                midpoint_size = get_bookkeeping_size(&manager);
+            }
+        }
+
+        let final_size = get_bookkeeping_size(&manager);
+        assert!(
+            final_size <= midpoint_size + 16,
+            "bookkeeping grew from {} to {}",
+            midpoint_size,
+            final_size
+        );
+        assert!(final_size < 1000, "bookkeeping of {} entries", final_size);
+        assert!(expired_count > 3800, "{} despawns expired", expired_count);
+
+        // a late ack of a packet given up on changes nothing
+        manager.notify_packet_delivered(0);
+        assert_eq!(get_bookkeeping_size(&manager), final_size);
+    }
+
+    #[test]
+    fn reset_connection_resends_only_unacknowledged_state() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let new_address: SocketAddr = "127.0.0.1:14192".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let synced_key = actors.insert(());
+        let spawning_key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&synced_key);
+        mut_handler.borrow_mut().register_actor(&spawning_key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+
+        let prop = TestActors::new_prop();
+        manager.add_actor(&synced_key, &prop.inner_ref());
+        send_packet(&mut manager, 0, 10);
+        manager.notify_packet_delivered(0);
+
+        // an update & a creation are in flight when the connection drops
+        mut_handler.borrow_mut().mutate(&synced_key, 0);
+        let prop = TestActors::new_prop();
+        manager.add_actor(&spawning_key, &prop.inner_ref());
+        send_packet(&mut manager, 1, 11);
+        send_packet(&mut manager, 2, 12);
+
+        manager.reset_connection(new_address);
+
+        // packet indices start over, and only what wasn't acknowledged is sent
+        let mut created = Vec::new();
+        let mut updated = Vec::new();
+        manager.collect_actor_updates(13, &mut TickSummary::default());
+        while let Some(message) = manager.pop_outgoing_message(0, 13) {
+            match message {
+                ServerActorMessage::CreateActor(global_key, _, _, _, _) => created.push(global_key),
+                ServerActorMessage::UpdateActor(global_key, _, _, _) => updated.push(global_key),
+                _ => {}
+            }
+        }
+        assert_eq!(created, vec![spawning_key]);
+        assert_eq!(updated, vec![synced_key]);
+        manager.notify_packet_delivered(0);
+        assert_eq!(
+            manager.get_sync_state(&synced_key),
+            ActorSyncState::Synced {
+                last_acked_tick: 13
+            }
+        );
+
+        // changes are still tracked for the Client at its new address
+        mut_handler.borrow_mut().mutate(&synced_key, 0);
+        assert_eq!(send_packet(&mut manager, 1, 14), 1);
+    }
+
+    #[test]
+    fn sync_state_through_lifecycle_with_loss() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+
+        assert_eq!(manager.get_sync_state(&key), ActorSyncState::NotInScope);
+
+        let prop = TestActors::new_prop();
+        manager.add_actor(&key, &prop.inner_ref());
+        assert_eq!(manager.get_sync_state(&key), ActorSyncState::SpawnPending);
+
+        // creation is lost, then resent
+        send_packet(&mut manager, 0, 10);
+        manager.notify_packet_dropped(0);
+        assert_eq!(manager.get_sync_state(&key), ActorSyncState::SpawnPending);
+        send_packet(&mut manager, 1, 11);
+        manager.notify_packet_delivered(1);
+        assert_eq!(
+            manager.get_sync_state(&key),
+            ActorSyncState::Synced {
+                last_acked_tick: 11
+            }
+        );
+
+        // an update is lost, then resent
+        mut_handler.borrow_mut().mutate(&key, 0);
+        let pending = ActorSyncState::UpdatePending {
+            last_acked_tick: 11,
+        };
+        assert_eq!(manager.get_sync_state(&key), pending);
+        send_packet(&mut manager, 2, 12);
+        assert_eq!(manager.get_sync_state(&key), pending);
+        manager.notify_packet_dropped(2);
+        assert_eq!(manager.get_sync_state(&key), pending);
+        send_packet(&mut manager, 3, 13);
+        assert_eq!(manager.get_sync_state(&key), pending);
+        manager.notify_packet_delivered(3);
+        assert_eq!(
+            manager.get_sync_state(&key),
+            ActorSyncState::Synced {
+                last_acked_tick: 13
+            }
+        );
+
+        // deletion, which keeps its reason when resent
+        manager.remove_actor(&key, DespawnReason::Custom(7));
+        assert_eq!(manager.get_sync_state(&key), ActorSyncState::DespawnPending);
+        send_packet(&mut manager, 4, 14);
+        manager.notify_packet_dropped(4);
+        assert_eq!(manager.get_sync_state(&key), ActorSyncState::DespawnPending);
+        match manager.pop_outgoing_message(5, 15) {
+            Some(ServerActorMessage::DeleteActor(_, _, reason)) => {
+                assert_eq!(reason, DespawnReason::Custom(7));
+            }
+            _ => panic!("expected a resent deletion"),
+        }
+        manager.notify_packet_delivered(5);
+        assert_eq!(manager.get_sync_state(&key), ActorSyncState::NotInScope);
+    }
+
+    #[test]
+    fn reduced_update_rates_throttle_and_converge() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+
+        let rates = [
+            (UpdateRate::EveryTick, 100),
+            (UpdateRate::EveryNTicks(2), 50),
+            (UpdateRate::EveryNTicks(5), 20),
+            (UpdateRate::EveryNTicks(10), 10),
+        ];
+        for (update_rate, expected_count) in rates.iter() {
+            let key = actors.insert(());
+            let mut_handler = MutHandler::new();
+            mut_handler.borrow_mut().register_actor(&key);
+            let mut manager =
+                ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+            let prop = TestActors::new_prop();
+            manager.add_actor(&key, &prop.inner_ref());
+            send_packet(&mut manager, 0, 0);
+            manager.notify_packet_delivered(0);
+
+            mut_handler.borrow_mut().set_update_rate(&key, *update_rate);
+
+            // changes every tick, across the tick wrapping around
+            let mut update_count = 0;
+            let mut tick: u16 = 65500;
+            for index in 1..=100 {
+                mut_handler.borrow_mut().mutate(&key, 0);
+                update_count += send_packet(&mut manager, index, tick);
+                manager.notify_packet_delivered(index);
+                tick = tick.wrapping_add(1);
+            }
+            assert_eq!(update_count, *expected_count, "rate {:?}", update_rate);
+
+            // the last changes are still sent once the interval has passed
+            mut_handler.borrow_mut().mutate(&key, 0);
+            let mut index = 101;
+            while let ActorSyncState::UpdatePending { .. } = manager.get_sync_state(&key) {
+                assert!(index - 101 < update_rate.get_interval());
+                send_packet(&mut manager, index, tick);
+                manager.notify_packet_delivered(index);
+                tick = tick.wrapping_add(1);
+                index += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn every_tick_rate_updates_each_time_updates_are_sent() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let prop = TestActors::new_prop();
+        manager.add_actor(&key, &prop.inner_ref());
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+
+        // updates are sent twice in the same tick
+        mut_handler.borrow_mut().mutate(&key, 0);
+        assert_eq!(send_packet(&mut manager, 1, 1), 1);
+        mut_handler.borrow_mut().mutate(&key, 0);
+        assert_eq!(send_packet(&mut manager, 2, 1), 1);
+    }
+
+    #[test]
+    fn connection_update_rate_overrides_actor_rate() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let prop = TestActors::new_prop();
+        manager.add_actor(&key, &prop.inner_ref());
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+
+        mut_handler
+            .borrow_mut()
+            .set_update_rate(&key, UpdateRate::EveryNTicks(10));
+        manager.set_update_rate(&key, Some(UpdateRate::EveryNTicks(4)));
+
+        let mut update_count = 0;
+        for tick in 1..=40 {
+            mut_handler.borrow_mut().mutate(&key, 0);
+            update_count += send_packet(&mut manager, tick, tick);
+            manager.notify_packet_delivered(tick);
+        }
+        assert_eq!(update_count, 10);
+
+        manager.set_update_rate(&key, None);
+        update_count = 0;
+        for tick in 41..=80 {
+            mut_handler.borrow_mut().mutate(&key, 0);
+            update_count += send_packet(&mut manager, tick, tick);
+            manager.notify_packet_delivered(tick);
+        }
+        assert_eq!(update_count, 4);
+    }
+
+    #[test]
+    fn forced_pawn_update_resends_full_state() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let prop_key = actors.insert(());
+        let pawn_key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&prop_key);
+        mut_handler.borrow_mut().register_actor(&pawn_key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let prop = TestActors::new_prop();
+        let pawn = TestActors::new_prop();
+        manager.add_actor(&prop_key, &prop.inner_ref());
+        manager.add_actor(&pawn_key, &pawn.inner_ref());
+        manager.add_pawn(&pawn_key);
+        // finish the initial world sync
+        for index in 0..2 {
+            send_packet(&mut manager, index, index);
+            manager.notify_packet_delivered(index);
+        }
+
+        // only Pawns are corrected
+        manager.force_pawn_update(&prop_key);
+        assert!(!manager.has_outgoing_messages());
+        manager.collect_actor_updates(2, &mut TickSummary::default());
+        assert!(!manager.has_outgoing_messages());
+
+        // a rejected Command sends the Pawn's full state back to the Client
+        manager.force_pawn_update(&pawn_key);
+        manager.collect_actor_updates(2, &mut TickSummary::default());
+        match manager.pop_outgoing_message(2, 2) {
+            Some(ServerActorMessage::UpdatePawn(key, _, state_mask, _)) => {
+                assert_eq!(key, pawn_key);
+                assert_eq!(state_mask.borrow().get_bit(0), Some(true));
+            }
+            _ => panic!("expected a Pawn update"),
+        }
+        assert!(manager.pop_outgoing_message(2, 2).is_none());
+        assert_eq!(
+            manager.get_sync_state(&pawn_key),
+            ActorSyncState::UpdatePending { last_acked_tick: 0 }
+        );
+
+        // the correction is synced once delivered
+        manager.notify_packet_delivered(2);
+        assert_eq!(
+            manager.get_sync_state(&pawn_key),
+            ActorSyncState::Synced { last_acked_tick: 2 }
+        );
+    }
+
+    #[test]
+    fn resync_resends_whole_state_until_delivered() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let prop_key = actors.insert(());
+        let pawn_key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&prop_key);
+        mut_handler.borrow_mut().register_actor(&pawn_key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let prop = TestActors::new_prop();
+        let pawn = TestActors::new_prop();
+        manager.add_actor(&prop_key, &prop.inner_ref());
+        manager.add_actor(&pawn_key, &pawn.inner_ref());
+        manager.add_pawn(&pawn_key);
+        let mut local_keys = Vec::new();
+        for index in 0..2 {
+            manager.collect_actor_updates(index, &mut TickSummary::default());
+            while let Some(message) = manager.pop_outgoing_message(index, index) {
+                if let ServerActorMessage::CreateActor(global_key, local_key, _, _, _) = message {
+                    local_keys.push((global_key, local_key));
+                }
+            }
+            manager.notify_packet_delivered(index);
+        }
+        let get_local_key = |global_key: ActorKey| {
+            return local_keys
+                .iter()
+                .find(|(key, _)| *key == global_key)
+                .unwrap()
+                .1;
+        };
+        let prop_local_key = get_local_key(prop_key);
+        let generation = manager.local_key_map.get_generation(prop_local_key);
+
+        // Pawns, unknown keys & other generations are ignored
+        assert!(!manager.resync_actor(get_local_key(pawn_key), generation));
+        assert!(!manager.resync_actor(prop_local_key.wrapping_add(100), generation));
+        assert!(!manager.resync_actor(prop_local_key, generation.wrapping_add(1)));
+        assert!(!manager.has_outgoing_messages());
+
+        // the whole state is sent once, however many times it's asked for
+        // before it's delivered, and again if dropped
+        assert!(manager.resync_actor(prop_local_key, generation));
+        assert!(!manager.resync_actor(prop_local_key, generation));
+        match manager.pop_outgoing_message(2, 2) {
+            Some(ServerActorMessage::ResyncActor(key, _, _)) => assert_eq!(key, prop_key),
+            _ => panic!("expected a resync"),
+        }
+        assert!(manager.pop_outgoing_message(2, 2).is_none());
+        manager.notify_packet_dropped(2);
+        assert!(matches!(
+            manager.pop_outgoing_message(3, 3),
+            Some(ServerActorMessage::ResyncActor(_, _, _))
+        ));
+        manager.notify_packet_delivered(3);
+        assert!(manager.resync_actor(prop_local_key, generation));
+    }
+
+    #[test]
+    fn hidden_properties_are_masked_until_revealed() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let mut visibility = StateMask::new(1);
+        visibility.set_bit(0, true);
+        manager.set_property_visibility(&key, Some(visibility));
+        let prop = TestActors::new_prop();
+        manager.add_actor(&key, &prop.inner_ref());
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+        send_packet(&mut manager, 1, 1);
+        manager.notify_packet_delivered(1);
+
+        // changes to a hidden Property aren't sent
+        mut_handler.borrow_mut().mutate(&key, 1);
+        assert_eq!(send_packet(&mut manager, 2, 2), 0);
+        assert_eq!(
+            manager.get_sync_state(&key),
+            ActorSyncState::Synced { last_acked_tick: 0 }
+        );
+
+        // while those to a visible one are, without the hidden one
+        mut_handler.borrow_mut().mutate(&key, 0);
+        mut_handler.borrow_mut().mutate(&key, 1);
+        manager.collect_actor_updates(3, &mut TickSummary::default());
+        match manager.pop_outgoing_message(3, 3) {
+            Some(ServerActorMessage::UpdateActor(_, _, state_mask, _)) => {
+                assert_eq!(state_mask.borrow().get_bit(0), Some(true));
+                assert_eq!(state_mask.borrow().get_bit(1), Some(false));
+            }
+            _ => panic!("expected an update"),
+        }
+        manager.notify_packet_delivered(3);
+
+        // showing every Property sends the ones which were hidden
+        manager.set_property_visibility(&key, None);
+        assert!(manager.get_property_visibility(&key).is_none());
+        manager.collect_actor_updates(4, &mut TickSummary::default());
+        match manager.pop_outgoing_message(4, 4) {
+            Some(ServerActorMessage::UpdateActor(_, _, state_mask, _)) => {
+                assert_eq!(state_mask.borrow().get_bit(0), Some(false));
+                assert_eq!(state_mask.borrow().get_bit(1), Some(true));
+                assert_eq!(state_mask.borrow().get_bit(7), Some(true));
+            }
+            _ => panic!("expected an update"),
+        }
+    }
+
+    #[test]
+    fn tick_summary_counts_only_changed_actors() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut_handler = MutHandler::new();
+        let keys: Vec<ActorKey> = (0..10).map(|_| actors.insert(())).collect();
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        for key in keys.iter() {
+            mut_handler.borrow_mut().register_actor(key);
+            let prop = TestActors::new_prop();
+            manager.add_actor(key, &prop.inner_ref());
+        }
+        // finish the initial world sync, which sends no updates
+        for index in 0..2 {
+            assert_eq!(send_packet(&mut manager, index, index), 0);
+            manager.notify_packet_delivered(index);
+        }
+
+        // one of the changed Actors is held back by its update rate
+        mut_handler
+            .borrow_mut()
+            .set_update_rate(&keys[0], UpdateRate::EveryNTicks(10));
+        for key in keys[..4].iter() {
+            mut_handler.borrow_mut().mutate(key, 0);
+        }
+        let mut summary = TickSummary::new(2);
+        manager.collect_actor_updates(2, &mut summary);
+        assert_eq!(summary.actors_dirty, 4);
+        assert_eq!(summary.updates_emitted, 4);
+        while manager.pop_outgoing_message(2, 2).is_some() {}
+        manager.notify_packet_delivered(2);
+
+        mut_handler.borrow_mut().mutate(&keys[0], 0);
+        mut_handler.borrow_mut().mutate(&keys[1], 0);
+        let mut summary = TickSummary::new(3);
+        manager.collect_actor_updates(3, &mut summary);
+        assert_eq!(summary.actors_dirty, 2);
+        assert_eq!(summary.updates_emitted, 1);
+        while manager.pop_outgoing_message(3, 3).is_some() {}
+        manager.notify_packet_delivered(3);
+
+        // nothing changed since, so nothing but the held back Actor is visited
+        let mut summary = TickSummary::new(4);
+        manager.collect_actor_updates(4, &mut summary);
+        assert_eq!(summary.actors_dirty, 1);
+        assert_eq!(summary.updates_emitted, 0);
+        assert!(!manager.has_outgoing_messages());
+    }
+
+    #[test]
+    fn updates_are_queued_in_the_order_actors_came_into_scope() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut_handler = MutHandler::new();
+        let mut keys: Vec<ActorKey> = (0..64).map(|_| actors.insert(())).collect();
+        keys.reverse();
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        for key in keys.iter() {
+            mut_handler.borrow_mut().register_actor(key);
+            let prop = TestActors::new_prop();
+            manager.add_actor(key, &prop.inner_ref());
+        }
+        for index in 0..2 {
+            send_packet(&mut manager, index, index);
+            manager.notify_packet_delivered(index);
+        }
+        manager.remove_actor(&keys[10], DespawnReason::OutOfScope);
+        send_packet(&mut manager, 2, 2);
+        manager.notify_packet_delivered(2);
+        keys.remove(10);
+
+        for key in keys.iter() {
+            mut_handler.borrow_mut().mutate(key, 0);
+        }
+        manager.collect_actor_updates(3, &mut TickSummary::default());
+        let mut updated_keys = Vec::new();
+        while let Some(message) = manager.pop_outgoing_message(3, 3) {
+            if let ServerActorMessage::UpdateActor(global_key, _, _, _) = message {
+                updated_keys.push(global_key);
+            }
+        }
+        assert_eq!(updated_keys, keys);
+    }
+
+    // sends everything queued in a single packet, which is delivered, returning
+    // the number of creations sent & whether the world sync was completed
+    fn send_world_sync_packet(
+        manager: &mut ServerActorManager<TestActors>,
+        packet_index: u16,
+    ) -> (usize, bool) {
+        manager.collect_actor_updates(packet_index, &mut TickSummary::default());
+        let mut create_count = 0;
+        let mut completed = false;
+        while let Some(message) = manager.pop_outgoing_message(packet_index, packet_index) {
+            match message {
+                ServerActorMessage::CreateActor(_, _, _, _, _) => create_count += 1,
+                ServerActorMessage::WorldSyncComplete => completed = true,
+                _ => {}
+            }
+        }
+        manager.notify_packet_delivered(packet_index);
+        return (create_count, completed);
+    }
+
+    #[test]
+    fn world_sync_waits_for_scope() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+
+        // a Client without scope is never told the sync is complete
+        for packet_index in 0..4 {
+            assert_eq!(
+                send_world_sync_packet(&mut manager, packet_index),
+                (0, false)
+            );
+        }
+        assert!(!manager.take_world_sync_complete());
+
+        // once it has scope, the sync completes after its Actors are delivered
+        manager.begin_world_sync();
+        let prop = TestActors::new_prop();
+        manager.add_actor(&key, &prop.inner_ref());
+        assert_eq!(send_world_sync_packet(&mut manager, 4), (1, false));
+        assert!(!manager.take_world_sync_complete());
+        assert_eq!(send_world_sync_packet(&mut manager, 5), (0, true));
+        assert!(manager.take_world_sync_complete());
+
+        // and only completes once
+        manager.begin_world_sync();
+        assert_eq!(send_world_sync_packet(&mut manager, 6), (0, false));
+        assert!(!manager.take_world_sync_complete());
+    }
+
+    #[test]
+    fn world_sync_of_many_actors_is_spread_over_a_small_budget() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut_handler = MutHandler::new();
+        // room for 4 creations of a Prop a tick
+        let mut manager = ServerActorManager::new(address, &mut_handler, 64, &SystemClock.now());
+        manager.begin_world_sync();
+        for _ in 0..1000 {
+            let key = actors.insert(());
+            mut_handler.borrow_mut().register_actor(&key);
+            let prop = TestActors::new_prop();
+            manager.add_actor(&key, &prop.inner_ref());
+        }
+
+        let mut created_count = 0;
+        let mut packet_index = 0;
+        loop {
+            assert!(packet_index < 1000, "the sync never completed");
+            let (create_count, completed) = send_world_sync_packet(&mut manager, packet_index);
+            assert!(create_count <= 4, "{} creations in one tick", create_count);
+            created_count += create_count;
+            packet_index += 1;
+            if completed {
+                break;
+            }
+            assert!(!manager.take_world_sync_complete());
+        }
+
+        // every Actor is created before the Client is notified, which is only
+        // done once
+        assert_eq!(created_count, 1000);
+        assert_eq!(packet_index, 251);
+        assert!(manager.take_world_sync_complete());
+        for _ in 0..4 {
+            assert_eq!(
+                send_world_sync_packet(&mut manager, packet_index),
+                (0, false)
+            );
+            assert!(!manager.take_world_sync_complete());
+            packet_index += 1;
+        }
+    }
+
+    #[test]
+    fn spawn_prediction_tags_only_first_creation() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let get_prediction = |manager: &mut ServerActorManager<TestActors>, index: u16| {
+            manager.collect_actor_updates(index, &mut TickSummary::default());
+            let mut prediction = None;
+            while let Some(message) = manager.pop_outgoing_message(index, index) {
+                if let ServerActorMessage::CreateActor(_, _, _, _, prediction_id) = message {
+                    assert_eq!(message.write_message_type(), prediction_id.map_or(0, |_| 7));
+                    prediction = Some(prediction_id);
+                }
+            }
+            manager.notify_packet_delivered(index);
+            return prediction;
+        };
+
+        manager.set_spawn_prediction(&key, 42);
+        let prop = TestActors::new_prop();
+        manager.add_actor(&key, &prop.inner_ref());
+        assert_eq!(get_prediction(&mut manager, 0), Some(Some(42)));
+
+        // once spawned, the Actor coming back into scope is a plain creation
+        manager.remove_actor(&key, DespawnReason::OutOfScope);
+        get_prediction(&mut manager, 1);
+        manager.add_actor(&key, &prop.inner_ref());
+        assert_eq!(get_prediction(&mut manager, 2), Some(None));
+
+        // and a prediction set for an Actor already in scope is ignored
+        manager.set_spawn_prediction(&key, 43);
+        manager.remove_actor(&key, DespawnReason::OutOfScope);
+        get_prediction(&mut manager, 3);
+        manager.add_actor(&key, &prop.inner_ref());
+        assert_eq!(get_prediction(&mut manager, 4), Some(None));
+    }
+
+    // sends everything queued, returning the masks of the updates sent, as
+    // their single byte
+    fn send_update_masks(
+        manager: &mut ServerActorManager<TestActors>,
+        packet_index: u16,
+        tick: u16,
+    ) -> Vec<u8> {
+        manager.collect_actor_updates(tick, &mut TickSummary::default());
+        let mut masks = Vec::new();
+        while let Some(message) = manager.pop_outgoing_message(packet_index, tick) {
+            if let ServerActorMessage::UpdateActor(_, _, state_mask, _) = message {
+                masks.push(state_mask.as_ref().borrow().get_byte(0));
+            }
+        }
+        return masks;
+    }
+
+    fn mask_of(bits: &[u8]) -> StateMask {
+        let mut state_mask = StateMask::new(1);
+        for bit in bits.iter() {
+            state_mask.set_bit(*bit, true);
+        }
+        return state_mask;
+    }
+
+    #[test]
+    fn updates_carry_properties_still_in_flight() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let gauge = TestActors::new_gauge();
+        manager.add_actor(&key, &gauge.inner_ref());
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+
+        mut_handler.borrow_mut().mutate(&key, 0);
+        assert_eq!(
+            send_update_masks(&mut manager, 1, 1),
+            vec![mask_of(&[0]).get_byte(0)]
+        );
+
+        // the first update is still in flight, so is written again
+        mut_handler.borrow_mut().mutate(&key, 1);
+        assert_eq!(
+            send_update_masks(&mut manager, 2, 2),
+            vec![mask_of(&[0, 1]).get_byte(0)]
+        );
+
+        // once acknowledged, it isn't
+        manager.notify_packet_delivered(1);
+        manager.notify_packet_delivered(2);
+        mut_handler.borrow_mut().mutate(&key, 2);
+        assert_eq!(
+            send_update_masks(&mut manager, 3, 3),
+            vec![mask_of(&[2]).get_byte(0)]
+        );
+
+        // nor is a Property hidden from the Client since
+        manager.set_property_visibility(&key, Some(mask_of(&[0, 1, 3])));
+        mut_handler.borrow_mut().mutate(&key, 3);
+        assert_eq!(
+            send_update_masks(&mut manager, 4, 4),
+            vec![mask_of(&[3]).get_byte(0)]
+        );
+
+        // and nothing is sent for an Actor which hasn't changed again
+        manager.notify_packet_delivered(3);
+        manager.notify_packet_delivered(4);
+        assert!(send_update_masks(&mut manager, 5, 5).is_empty());
+    }
+
+    #[test]
+    fn paused_replication_sends_only_the_net_changes_on_resume() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let gauge_key = actors.insert(());
+        let prop_key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&gauge_key);
+        mut_handler.borrow_mut().register_actor(&prop_key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let gauge = TestActors::new_gauge();
+        manager.add_actor(&gauge_key, &gauge.inner_ref());
+        let prop = TestActors::new_prop();
+        manager.add_actor(&prop_key, &prop.inner_ref());
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+
+        // the world changes heavily, while nothing at all is sent
+        manager.pause();
+        manager.remove_actor(&prop_key, DespawnReason::Custom(7));
+        for tick in 1..=100 {
+            mut_handler
+                .borrow_mut()
+                .mutate(&gauge_key, (tick % 3) as u8);
+            manager.collect_actor_updates(tick, &mut TickSummary::default());
+            assert!(!manager.has_outgoing_messages());
+            assert!(manager.pop_outgoing_message(tick, tick).is_none());
+        }
+        // the changes accumulate in place, rather than as a message each
+        assert_eq!(manager.get_outgoing_message_count(), 1);
+        assert_eq!(
+            manager.get_sync_state(&gauge_key),
+            ActorSyncState::UpdatePending { last_acked_tick: 0 }
+        );
+
+        // resuming sends the deletion & a single update of every Property
+        // which changed
+        manager.resume();
+        manager.collect_actor_updates(101, &mut TickSummary::default());
+        let mut deleted = Vec::new();
+        let mut update_masks = Vec::new();
+        while let Some(message) = manager.pop_outgoing_message(101, 101) {
+            match message {
+                ServerActorMessage::DeleteActor(global_key, _, _) => deleted.push(global_key),
+                ServerActorMessage::UpdateActor(global_key, _, state_mask, _) => {
+                    assert_eq!(global_key, gauge_key);
+                    update_masks.push(state_mask.as_ref().borrow().get_byte(0));
+                }
+                _ => panic!("only the net changes should be sent"),
+            }
+        }
+        assert_eq!(deleted, [prop_key]);
+        assert_eq!(update_masks, [mask_of(&[0, 1, 2]).get_byte(0)]);
+        manager.notify_packet_delivered(101);
+        assert_eq!(
+            manager.get_sync_state(&gauge_key),
+            ActorSyncState::Synced {
+                last_acked_tick: 101
+            }
+        );
+        assert_eq!(
+            manager.get_sync_state(&prop_key),
+            ActorSyncState::NotInScope
+        );
+    }
+
+    #[test]
+    fn actor_created_and_deleted_while_paused_is_never_sent() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let gauge_key = actors.insert(());
+        let prop_key = actors.insert(());
+        let mut_handler = MutHandler::new();
+        mut_handler.borrow_mut().register_actor(&gauge_key);
+        mut_handler.borrow_mut().register_actor(&prop_key);
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+        let gauge = TestActors::new_gauge();
+        manager.add_actor(&gauge_key, &gauge.inner_ref());
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+
+        // the Prop comes & goes before its creation is ever sent
+        manager.pause();
+        let prop = TestActors::new_prop();
+        manager.add_actor(&prop_key, &prop.inner_ref());
+        assert_eq!(
+            manager.get_sync_state(&prop_key),
+            ActorSyncState::SpawnPending
+        );
+        manager.remove_actor(&prop_key, DespawnReason::Custom(7));
+        assert_eq!(
+            manager.get_sync_state(&prop_key),
+            ActorSyncState::NotInScope
+        );
+        assert!(!manager.has_actor(&prop_key));
+        manager.remove_actor(&gauge_key, DespawnReason::Custom(7));
+
+        // resuming sends only the deletion of the Actor the Client holds
+        manager.resume();
+        manager.collect_actor_updates(1, &mut TickSummary::default());
+        let mut deleted = Vec::new();
+        while let Some(message) = manager.pop_outgoing_message(1, 1) {
+            match message {
+                ServerActorMessage::DeleteActor(global_key, _, _) => deleted.push(global_key),
+                _ => panic!("the Prop should never be sent"),
+            }
+        }
+        assert_eq!(deleted, [gauge_key]);
+    }
+
+    // an update as the Client receives it
+    struct ReceivedUpdate {
+        key: ActorKey,
+        tick: u16,
+        packet_index: u16,
+        state_mask: StateMask,
+        payload: Vec<u8>,
+    }
+
+    #[test]
+    fn tiny_packets_never_leave_a_torn_actor() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let keys: Vec<ActorKey> = (0..3).map(|_| actors.insert(())).collect();
+        let mut_handler = MutHandler::new();
+        let mut manager = ServerActorManager::new(address, &mut_handler, 4096, &SystemClock.now());
+
+        // the Server's Actors, each's state as of every tick it changed on,
+        // & the Client's copy of it along with the tick of its newest update
+        let mut gauges = Vec::new();
+        let mut histories: Vec<Vec<Vec<u8>>> = Vec::new();
+        let mut replicas = Vec::new();
+        for key in keys.iter() {
+            mut_handler.borrow_mut().register_actor(key);
+            let gauge = Rc::new(RefCell::new(Gauge::new()));
+            manager.add_actor(key, &TestActors::Gauge(gauge.clone()).inner_ref());
+            histories.push(vec![gauge.borrow().get_values()]);
+            gauges.push(gauge);
+            replicas.push((Gauge::new(), None::<u16>));
+        }
+        send_packet(&mut manager, 0, 0);
+        manager.notify_packet_delivered(0);
+
+        // a deterministic pseudo-random sequence
+        let mut seed: u32 = 0x9e37_79b9;
+        let mut random = move |range: u32| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            return seed % range;
+        };
+
+        let mut packet_index: u16 = 1;
+        let mut in_flight: Vec<ReceivedUpdate> = Vec::new();
+        for tick in 1..1000u16 {
+            let lossy = tick < 900;
+            for (index, key) in keys.iter().enumerate() {
+                let mut gauge = gauges[index].borrow_mut();
+                let mut changed = false;
+                for bit in 0..4u8 {
+                    if random(3) == 0 {
+                        gauge.properties[bit as usize].set(tick as u8);
+                        mut_handler.borrow_mut().mutate(key, bit);
+                        changed = true;
+                    }
+                }
+                if changed {
+                    histories[index].push(gauge.get_values());
+                }
+            }
+
+            // each update is sent in a packet of its own, as if no more would
+            // fit, so the changes of a tick are spread across packets
+            manager.collect_actor_updates(tick, &mut TickSummary::default());
+            while let Some(message) = manager.pop_outgoing_message(packet_index, tick) {
+                if let ServerActorMessage::UpdateActor(key, _, state_mask, actor) = message {
+                    let state_mask = state_mask.as_ref().borrow().clone();
+                    let mut payload = Vec::new();
+                    actor
+                        .as_ref()
+                        .borrow()
+                        .write_partial(&state_mask, &mut payload);
+                    in_flight.push(ReceivedUpdate {
+                        key,
+                        tick,
+                        packet_index,
+                        state_mask,
+                        payload,
+                    });
+                }
+                packet_index = packet_index.wrapping_add(1);
+            }
+
+            // packets arrive out of order, & a third of them are lost
+            while in_flight.len() > 0 && (!lossy || random(2) == 0) {
+                let update = in_flight.remove(random(in_flight.len() as u32) as usize);
+                if lossy && random(3) == 0 {
+                    manager.notify_packet_dropped(update.packet_index);
+                    continue;
+                }
+                let index = keys.iter().position(|key| *key == update.key).unwrap();
+                let (replica, last_tick) = &mut replicas[index];
+                if !last_tick.is_some_and(|last_tick| sequence_less_than(update.tick, last_tick)) {
+                    *last_tick = Some(update.tick);
+                    replica.read_partial(
+                        &update.state_mask,
+                        &mut PacketReader::new(&update.payload),
+                        update.packet_index,
+                    );
+                    // the Client only ever holds a state the Server had
+                    let values = replica.get_values();
+                    assert!(
+                        histories[index].contains(&values),
+                        "Actor {} torn to {:?} on tick {}",
+                        index,
+                        values,
+                        tick
+                    );
+                }
+                manager.notify_packet_delivered(update.packet_index);
+            }
+        }
+
+        // once packets stop being lost, every Client copy catches up
+        for (index, (replica, _)) in replicas.iter().enumerate() {
+            assert_eq!(replica.get_values(), gauges[index].borrow().get_values());
         }
     }
 }
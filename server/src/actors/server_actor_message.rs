@@ -1,18 +1,24 @@
-use naia_shared::{Actor, ActorType, LocalActorKey, StateMask};
+use naia_shared::{Actor, ActorType, DespawnReason, LocalActorKey, PredictionId, StateMask};
 use std::{cell::RefCell, rc::Rc};
 
-use super::actor_key::actor_key::ActorKey;
+use super::{actor_key::actor_key::ActorKey, mut_handler::StaticPayload};
 
 #[derive(Debug)]
 pub enum ServerActorMessage<T: ActorType> {
-    CreateActor(ActorKey, LocalActorKey, Rc<RefCell<dyn Actor<T>>>),
+    CreateActor(
+        ActorKey,
+        LocalActorKey,
+        Rc<RefCell<dyn Actor<T>>>,
+        Option<StaticPayload>,
+        Option<PredictionId>,
+    ),
     UpdateActor(
         ActorKey,
         LocalActorKey,
         Rc<RefCell<StateMask>>,
         Rc<RefCell<dyn Actor<T>>>,
     ),
-    DeleteActor(ActorKey, LocalActorKey),
+    DeleteActor(ActorKey, LocalActorKey, DespawnReason),
     AssignPawn(ActorKey, LocalActorKey),
     UnassignPawn(ActorKey, LocalActorKey),
     UpdatePawn(
@@ -21,17 +27,48 @@ pub enum ServerActorMessage<T: ActorType> {
         Rc<RefCell<StateMask>>,
         Rc<RefCell<dyn Actor<T>>>,
     ),
+    WorldSyncComplete,
+    ResyncActor(ActorKey, LocalActorKey, Rc<RefCell<dyn Actor<T>>>),
 }
 
 impl<T: ActorType> ServerActorMessage<T> {
     pub fn write_message_type(&self) -> u8 {
         match self {
-            ServerActorMessage::CreateActor(_, _, _) => 0,
-            ServerActorMessage::DeleteActor(_, _) => 1,
+            ServerActorMessage::CreateActor(_, _, _, _, None) => 0,
+            ServerActorMessage::DeleteActor(_, _, _) => 1,
             ServerActorMessage::UpdateActor(_, _, _, _) => 2,
             ServerActorMessage::AssignPawn(_, _) => 3,
             ServerActorMessage::UnassignPawn(_, _) => 4,
             ServerActorMessage::UpdatePawn(_, _, _, _) => 5,
+            ServerActorMessage::WorldSyncComplete => 6,
+            ServerActorMessage::CreateActor(_, _, _, _, Some(_)) => 7,
+            ServerActorMessage::ResyncActor(_, _, _) => 9,
+        }
+    }
+
+    pub fn get_global_key(&self) -> Option<ActorKey> {
+        match self {
+            ServerActorMessage::CreateActor(global_key, _, _, _, _)
+            | ServerActorMessage::UpdateActor(global_key, _, _, _)
+            | ServerActorMessage::UpdatePawn(global_key, _, _, _)
+            | ServerActorMessage::DeleteActor(global_key, _, _)
+            | ServerActorMessage::AssignPawn(global_key, _)
+            | ServerActorMessage::UnassignPawn(global_key, _)
+            | ServerActorMessage::ResyncActor(global_key, _, _) => return Some(*global_key),
+            ServerActorMessage::WorldSyncComplete => return None,
+        }
+    }
+
+    pub fn get_local_key(&self) -> Option<LocalActorKey> {
+        match self {
+            ServerActorMessage::CreateActor(_, local_key, _, _, _)
+            | ServerActorMessage::UpdateActor(_, local_key, _, _)
+            | ServerActorMessage::UpdatePawn(_, local_key, _, _) => return Some(*local_key),
+            ServerActorMessage::DeleteActor(_, local_key, _)
+            | ServerActorMessage::AssignPawn(_, local_key)
+            | ServerActorMessage::UnassignPawn(_, local_key)
+            | ServerActorMessage::ResyncActor(_, local_key, _) => return Some(*local_key),
+            ServerActorMessage::WorldSyncComplete => return None,
         }
     }
 }
@@ -39,11 +76,11 @@ impl<T: ActorType> ServerActorMessage<T> {
 impl<T: ActorType> Clone for ServerActorMessage<T> {
     fn clone(&self) -> Self {
         match self {
-            ServerActorMessage::CreateActor(gk, lk, e) => {
-                ServerActorMessage::CreateActor(gk.clone(), lk.clone(), e.clone())
+            ServerActorMessage::CreateActor(gk, lk, e, sp, p) => {
+                ServerActorMessage::CreateActor(gk.clone(), lk.clone(), e.clone(), sp.clone(), *p)
             }
-            ServerActorMessage::DeleteActor(gk, lk) => {
-                ServerActorMessage::DeleteActor(gk.clone(), lk.clone())
+            ServerActorMessage::DeleteActor(gk, lk, r) => {
+                ServerActorMessage::DeleteActor(gk.clone(), lk.clone(), *r)
             }
             ServerActorMessage::UpdateActor(gk, lk, sm, e) => {
                 ServerActorMessage::UpdateActor(gk.clone(), lk.clone(), sm.clone(), e.clone())
@@ -57,6 +94,10 @@ impl<T: ActorType> Clone for ServerActorMessage<T> {
             ServerActorMessage::UpdatePawn(gk, lk, sm, e) => {
                 ServerActorMessage::UpdatePawn(gk.clone(), lk.clone(), sm.clone(), e.clone())
             }
+            ServerActorMessage::WorldSyncComplete => ServerActorMessage::WorldSyncComplete,
+            ServerActorMessage::ResyncActor(gk, lk, e) => {
+                ServerActorMessage::ResyncActor(gk.clone(), lk.clone(), e.clone())
+            }
         }
     }
 }
@@ -0,0 +1,20 @@
+/// How often changes to an Actor are sent to Clients. Changes made in between
+/// accumulate, so that only the net changes are sent. Creation & deletion of
+/// the Actor are always sent right away
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UpdateRate {
+    /// Changes are sent on every tick
+    EveryTick,
+    /// Changes are sent at most once every given number of ticks
+    EveryNTicks(u16),
+}
+
+impl UpdateRate {
+    /// Get the minimum number of ticks between updates
+    pub fn get_interval(&self) -> u16 {
+        match self {
+            UpdateRate::EveryTick => return 1,
+            UpdateRate::EveryNTicks(ticks) => return (*ticks).max(1),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+/// Tracks the progress of the initial sync of all in-scope Actors to a newly
+/// connected Client
+#[derive(Debug, PartialEq)]
+pub enum WorldSyncState {
+    /// The Client has no scope yet, so there is nothing to sync
+    Idle,
+    /// In-scope Actors are still being sent to the Client
+    Syncing,
+    /// All in-scope Actors have been delivered, and the Client is being
+    /// notified that the sync is complete
+    Completing,
+    /// The Client has been notified that the sync is complete
+    Complete,
+}
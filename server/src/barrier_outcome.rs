@@ -0,0 +1,23 @@
+/// How a barrier placed with `NaiaServer::reliable_barrier()` or
+/// `NaiaServer::barrier_all()` completed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BarrierOutcome {
+    /// Every reliable Event queued before the barrier has been acknowledged by
+    /// every connection it covered
+    Delivered,
+    /// A connection the barrier covered was closed before acknowledging every
+    /// reliable Event queued before the barrier. Any other connection it
+    /// covered has acknowledged them all
+    Disconnected,
+    /// The timeout the barrier was placed with passed before every reliable
+    /// Event queued before it was acknowledged
+    TimedOut,
+}
+
+impl BarrierOutcome {
+    /// Returns whether the barrier failed, so some of the reliable Events
+    /// queued before it may never have been received
+    pub fn is_failed(&self) -> bool {
+        return *self != BarrierOutcome::Delivered;
+    }
+}
@@ -1,25 +1,92 @@
-use std::{cell::RefCell, net::SocketAddr, rc::Rc};
+use std::{
+    any::TypeId, cell::RefCell, collections::HashSet, net::SocketAddr, rc::Rc, sync::Arc,
+    time::Duration,
+};
 
 use naia_shared::{
-    Actor, ActorType, Connection, ConnectionConfig, Event, EventType, ManagerType, Manifest,
-    PacketReader, PacketType, SequenceNumber, StandardHeader,
+    byte_order,
+    handshake::{EarlyData, SessionToken},
+    manifest_extension::read_manifest_extension_ack,
+    sequence_greater_than,
+    utils::duration_between,
+    Actor, ActorType, ChannelIndex, Clock, Connection, ConnectionConfig, ConnectionInfo,
+    DespawnReason, Event, EventType, Instant, ManagerType, Manifest, MessageReceipt, NaiaError,
+    PacketReader, PacketType, PingManager, PredictionId, RawEvent, SchemaVersions, SequenceNumber,
+    SkippedFrame, StandardHeader, StateMask, SubTick, Timer,
 };
 
+#[cfg(feature = "diagnostics")]
+use super::connection_histograms::ConnectionHistograms;
 use super::{
     actors::{
         actor_key::actor_key::ActorKey, actor_packet_writer::ActorPacketWriter,
-        mut_handler::MutHandler, server_actor_manager::ServerActorManager,
+        actor_sync_state::ActorSyncState, mut_handler::MutHandler,
+        server_actor_manager::ServerActorManager, server_actor_message::ServerActorMessage,
+        update_rate::UpdateRate,
     },
     command_receiver::CommandReceiver,
-    ping_manager::PingManager,
+    congestion_monitor::CongestionMonitor,
+    event_context::EventContext,
+    event_handling::EventHandling,
+    event_middleware::EventMiddleware,
+    late_event_types::LateEventTypes,
+    mtu_prober::{MtuProber, MIN_MTU},
+    outgoing_queue::OutgoingQueue,
+    reliable_buffer_diagnostics::ReliableBufferDiagnostics,
+    send_mode::SendMode,
+    server_config::ServerConfig,
     server_packet_writer::ServerPacketWriter,
+    tick_payload_sender::TickPayloadSender,
+    tick_summary::TickSummary,
+    user::user_key::UserKey,
 };
 
+// the interval between reports to the Client of how many of its Commands were
+// received in time for their tick
+const COMMAND_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// the interval between resends of a ManifestExtension the Client has yet to
+// answer
+const MANIFEST_EXTENSION_RESEND_INTERVAL: Duration = Duration::from_millis(200);
+
 pub struct ClientConnection<T: EventType, U: ActorType> {
     connection: Connection<T>,
     actor_manager: ServerActorManager<U>,
     ping_manager: PingManager,
     command_receiver: CommandReceiver<T>,
+    command_report_timer: Timer,
+    // the newest Client tick Commands have been received for, which the ticks
+    // of early data have to be past to be released
+    newest_command_tick: Option<u16>,
+    congestion_monitor: CongestionMonitor,
+    ordered_channel_count: u8,
+    reliable_buffer_max_messages: usize,
+    reliable_buffer_max_bytes: usize,
+    reliable_buffer_overflowed: bool,
+    despawn_retention_rtts: f32,
+    despawn_retention_grace: Duration,
+    expired_despawns: u64,
+    mtu_prober: Option<MtuProber>,
+    connected_at: Instant,
+    bytes_sent: u64,
+    bytes_received: u64,
+    rejected_commands: u64,
+    auth_event: Option<T>,
+    session_token: Option<SessionToken>,
+    resumed: bool,
+    last_flush_tick: Option<u16>,
+    // actor-scoped Events held until the Client has acknowledged creating the
+    // Actor they're about, in the order they were sent
+    held_actor_events: Vec<(ActorKey, Box<dyn Event<T>>, Option<ChannelIndex>)>,
+    tick_payload_sender: TickPayloadSender,
+    // the epoch of the latest ManifestExtension the Client has answered, and
+    // the NaiaIds of the Event types in those it answered that it has too
+    manifest_extension_epoch: u16,
+    late_event_type_ids: HashSet<u16>,
+    manifest_extension_timer: Timer,
+    #[cfg(feature = "diagnostics")]
+    histograms: ConnectionHistograms,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T: EventType, U: ActorType> ClientConnection<T, U> {
@@ -27,46 +94,202 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         address: SocketAddr,
         mut_handler: Option<&Rc<RefCell<MutHandler>>>,
         connection_config: &ConnectionConfig,
+        server_config: &ServerConfig,
     ) -> Self {
+        let clock = connection_config.clock.clone();
+        let now = clock.now();
+        let mtu_prober = match server_config.mtu_discovery {
+            true => Some(MtuProber::new(
+                server_config.mtu_probe_timeout,
+                server_config.mtu_probe_attempts,
+                server_config.mtu_reprobe_interval,
+                &now,
+            )),
+            false => None,
+        };
+        // the first ManifestExtension is sent straight away
+        let mut manifest_extension_timer = Timer::new(MANIFEST_EXTENSION_RESEND_INTERVAL, &now);
+        manifest_extension_timer.ring_manual();
         ClientConnection {
             connection: Connection::new(address, connection_config),
-            actor_manager: ServerActorManager::new(address, mut_handler.unwrap()),
-            ping_manager: PingManager::new(),
+            actor_manager: ServerActorManager::new(
+                address,
+                mut_handler.unwrap(),
+                server_config.world_sync_bytes_per_tick,
+                &now,
+            ),
+            ping_manager: PingManager::new(
+                connection_config.ping_interval,
+                connection_config.rtt_sample_size,
+                &now,
+            ),
             command_receiver: CommandReceiver::new(),
+            command_report_timer: Timer::new(COMMAND_REPORT_INTERVAL, &now),
+            newest_command_tick: None,
+            congestion_monitor: CongestionMonitor::from_config(server_config),
+            ordered_channel_count: connection_config.ordered_channel_count,
+            reliable_buffer_max_messages: server_config.reliable_buffer_max_messages,
+            reliable_buffer_max_bytes: server_config.reliable_buffer_max_bytes,
+            reliable_buffer_overflowed: false,
+            despawn_retention_rtts: server_config.despawn_retention_rtts,
+            despawn_retention_grace: server_config.despawn_retention_grace,
+            expired_despawns: 0,
+            mtu_prober,
+            connected_at: now.clone(),
+            bytes_sent: 0,
+            bytes_received: 0,
+            rejected_commands: 0,
+            auth_event: None,
+            session_token: None,
+            resumed: false,
+            last_flush_tick: None,
+            held_actor_events: Vec::new(),
+            tick_payload_sender: TickPayloadSender::new(),
+            manifest_extension_epoch: 0,
+            late_event_type_ids: HashSet::new(),
+            manifest_extension_timer,
+            #[cfg(feature = "diagnostics")]
+            histograms: ConnectionHistograms::new(),
+            clock,
         }
     }
 
+    /// Resumes the connection after it has dropped, from the given address.
+    /// Packet indices start over with a new underlying connection, while the
+    /// state of every Actor the Client has acknowledged is kept, so that only
+    /// what it hasn't received is sent again. Events queued before the
+    /// connection dropped are not carried over
+    pub fn resume(&mut self, address: SocketAddr, connection_config: &ConnectionConfig) {
+        self.connection = Connection::new(address, connection_config);
+        self.actor_manager.reset_connection(address);
+    }
+
+    /// Sets the payload of the given tick, to be carried by the packets sent
+    /// to the Client during the tick, as the schema version of its type agreed
+    /// on for the connection. The first payload set for a tick is kept
+    pub fn set_tick_payload(
+        &mut self,
+        tick: u16,
+        manifest: &Manifest<T, U>,
+        payload: &dyn Event<T>,
+    ) {
+        let naia_id = match manifest.get_event_naia_id(&payload.get_type_id()) {
+            Ok(naia_id) => naia_id,
+            Err(_) => return,
+        };
+        let mut payload_bytes = Vec::new();
+        match self
+            .connection
+            .get_schema_versions()
+            .get_event_version(naia_id)
+        {
+            Some(version) => payload.write_versioned(version, &mut payload_bytes),
+            None => payload.write(&mut payload_bytes),
+        }
+        self.tick_payload_sender.set(tick, naia_id, &payload_bytes);
+    }
+
     pub fn get_outgoing_packet(
         &mut self,
         host_tick: u16,
         manifest: &Manifest<T, U>,
     ) -> Option<Box<[u8]>> {
-        if self.connection.has_outgoing_events() || self.actor_manager.has_outgoing_messages() {
-            let mut writer = ServerPacketWriter::new();
+        return self
+            .get_outgoing_paced_packet(host_tick, manifest, &[])
+            .map(|(payload, _)| payload);
+    }
+
+    /// Gets the next packet to be sent to the Client, along with whether it
+    /// must be sent immediately rather than paced, because it holds an Event
+    /// on one of the given ordered channels, or an update to one of the
+    /// Client's Pawns
+    pub fn get_outgoing_paced_packet(
+        &mut self,
+        host_tick: u16,
+        manifest: &Manifest<T, U>,
+        immediate_channels: &[ChannelIndex],
+    ) -> Option<(Box<[u8]>, bool)> {
+        let now = self.clock.now();
+        self.connection.expire_outgoing_events(manifest, &now);
+        // the payload of the tick, sent on its own if nothing else is, then
+        // carried by every later packet of the tick with room for it
+        let tick_payload = self
+            .tick_payload_sender
+            .get_frame(host_tick)
+            .map(|(frame, written)| (frame.to_vec(), written));
+        let tick_payload_unwritten = matches!(tick_payload, Some((_, false)));
+        if self.connection.has_outgoing_events()
+            || self.actor_manager.has_outgoing_messages()
+            || tick_payload_unwritten
+        {
+            let max_payload_size = self.get_mtu() - StandardHeader::bytes_number();
+            let mut writer = match &tick_payload {
+                Some((frame, false)) => {
+                    ServerPacketWriter::with_tick_payload(max_payload_size, frame.clone())
+                }
+                _ => ServerPacketWriter::new(max_payload_size),
+            };
+            writer.set_writes_trace_ids(self.connection.is_message_tracing_enabled());
 
+            let mut immediate = false;
             let next_packet_index: u16 = self.get_next_packet_index();
             while let Some(popped_event) = self.connection.pop_outgoing_event(next_packet_index) {
-                if !writer.write_event(manifest, &popped_event) {
+                if !writer.write_event(
+                    manifest,
+                    self.connection.get_schema_versions(),
+                    &popped_event,
+                    &now,
+                ) {
                     self.connection
                         .unpop_outgoing_event(next_packet_index, &popped_event);
                     break;
                 }
+                if let Some((channel, _)) = popped_event.ordering {
+                    immediate |= immediate_channels.contains(&channel);
+                }
             }
-            while let Some(popped_actor_message) =
-                self.actor_manager.pop_outgoing_message(next_packet_index)
+            while let Some(popped_actor_message) = self
+                .actor_manager
+                .pop_outgoing_message(next_packet_index, host_tick)
             {
+                let generation = popped_actor_message
+                    .get_local_key()
+                    .map(|local_key| self.actor_manager.get_generation(local_key))
+                    .unwrap_or(0);
+                let visibility = popped_actor_message
+                    .get_global_key()
+                    .and_then(|global_key| self.actor_manager.get_property_visibility(&global_key));
                 if !ActorPacketWriter::write_actor_message(
                     &mut writer,
                     manifest,
+                    self.connection.get_schema_versions(),
                     &popped_actor_message,
+                    generation,
+                    visibility,
                 ) {
                     self.actor_manager
                         .unpop_outgoing_message(next_packet_index, &popped_actor_message);
                     break;
                 }
+                immediate |= matches!(
+                    popped_actor_message,
+                    ServerActorMessage::AssignPawn(_, _)
+                        | ServerActorMessage::UnassignPawn(_, _)
+                        | ServerActorMessage::UpdatePawn(_, _, _, _)
+                );
+            }
+
+            if let Some((frame, true)) = &tick_payload {
+                if writer.has_bytes() {
+                    writer.write_tick_payload(frame);
+                }
             }
 
             if writer.has_bytes() {
+                if writer.has_tick_payload() {
+                    self.tick_payload_sender.mark_written();
+                }
+
                 // Get bytes from writer
                 let out_bytes = writer.get_bytes();
 
@@ -77,7 +300,7 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
                     PacketType::Data,
                     &out_bytes,
                 );
-                return Some(payload);
+                return Some((payload, immediate));
             }
         }
 
@@ -88,8 +311,10 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         &mut self,
         server_tick: u16,
         client_tick: u16,
+        perceived_tick: u16,
         manifest: &Manifest<T, U>,
         data: &[u8],
+        received_at: &Instant,
     ) {
         let mut reader = PacketReader::new(data);
         while reader.has_more() {
@@ -99,18 +324,70 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
                     self.command_receiver.process_data(
                         server_tick,
                         client_tick,
+                        perceived_tick,
                         &mut reader,
                         manifest,
+                        self.connection.get_schema_versions(),
                     );
+                    self.mark_commands_received(client_tick);
                 }
                 ManagerType::Event => {
-                    self.connection.process_event_data(&mut reader, manifest);
+                    self.connection.process_event_data(
+                        &mut reader,
+                        manifest,
+                        client_tick,
+                        received_at,
+                    );
                 }
                 _ => {}
             }
         }
     }
 
+    /// Releases the Commands a Client resuming the connection attached to its
+    /// handshake as early data, as if each packet had just been received.
+    /// Packets for ticks no later than the newest Commands were received for
+    /// are replays, and are discarded, as is anything besides Commands.
+    /// Returns the number of packets released
+    pub fn release_early_data(
+        &mut self,
+        server_tick: u16,
+        manifest: &Manifest<T, U>,
+        early_data: &[EarlyData],
+    ) -> usize {
+        let mut released = 0;
+        for packet in early_data {
+            if let Some(newest_tick) = self.newest_command_tick {
+                if !sequence_greater_than(packet.client_tick, newest_tick) {
+                    continue;
+                }
+            }
+            let mut reader = PacketReader::new(&packet.payload);
+            if !reader.has_more() || ManagerType::from(reader.read_u8()) != ManagerType::Command {
+                continue;
+            }
+            self.command_receiver.process_data(
+                server_tick,
+                packet.client_tick,
+                packet.perceived_tick,
+                &mut reader,
+                manifest,
+                self.connection.get_schema_versions(),
+            );
+            self.mark_commands_received(packet.client_tick);
+            released += 1;
+        }
+        return released;
+    }
+
+    // keeps track of the newest Client tick Commands have been received for
+    fn mark_commands_received(&mut self, client_tick: u16) {
+        match self.newest_command_tick {
+            Some(newest_tick) if !sequence_greater_than(client_tick, newest_tick) => {}
+            _ => self.newest_command_tick = Some(client_tick),
+        }
+    }
+
     pub fn has_actor(&self, key: &ActorKey) -> bool {
         return self.actor_manager.has_actor(key);
     }
@@ -119,12 +396,64 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         self.actor_manager.add_actor(key, actor);
     }
 
-    pub fn remove_actor(&mut self, key: &ActorKey) {
-        self.actor_manager.remove_actor(key);
+    pub fn get_actor_sync_state(&self, key: &ActorKey) -> ActorSyncState {
+        return self.actor_manager.get_sync_state(key);
+    }
+
+    pub fn make_actor_dynamic(&mut self, key: &ActorKey) {
+        self.actor_manager.make_dynamic(key);
+    }
+
+    pub fn remove_actor(&mut self, key: &ActorKey, reason: DespawnReason) {
+        self.actor_manager.remove_actor(key, reason);
+    }
+
+    pub fn set_actor_update_rate(&mut self, key: &ActorKey, update_rate: Option<UpdateRate>) {
+        self.actor_manager.set_update_rate(key, update_rate);
+    }
+
+    pub fn set_property_visibility(&mut self, key: &ActorKey, visibility: Option<StateMask>) {
+        self.actor_manager.set_property_visibility(key, visibility);
+    }
+
+    pub fn collect_actor_updates(&mut self, server_tick: u16, summary: &mut TickSummary) {
+        // deletions the Client hasn't acknowledged in a number of round trips
+        // are given up on, but never before the grace period
+        let retention = Duration::from_secs_f32(
+            (self.get_rtt() * self.despawn_retention_rtts / 1000.0).max(0.0),
+        )
+        .max(self.despawn_retention_grace);
+        let expired_count = self
+            .actor_manager
+            .expire_despawns(&self.clock.now(), retention);
+        self.expired_despawns += expired_count;
+        summary.despawns_expired += expired_count as usize;
+        self.actor_manager
+            .collect_actor_updates(server_tick, summary);
+    }
+
+    pub fn pause_replication(&mut self) {
+        self.actor_manager.pause();
+    }
+
+    pub fn resume_replication(&mut self) {
+        self.actor_manager.resume();
+    }
+
+    pub fn is_replication_paused(&self) -> bool {
+        return self.actor_manager.is_paused();
+    }
+
+    pub fn begin_world_sync(&mut self) {
+        self.actor_manager.begin_world_sync();
+    }
+
+    pub fn take_world_sync_complete(&mut self) -> bool {
+        return self.actor_manager.take_world_sync_complete();
     }
 
-    pub fn collect_actor_updates(&mut self) {
-        self.actor_manager.collect_actor_updates();
+    pub fn set_spawn_prediction(&mut self, key: &ActorKey, prediction_id: PredictionId) {
+        self.actor_manager.set_spawn_prediction(key, prediction_id);
     }
 
     pub fn has_pawn(&self, key: &ActorKey) -> bool {
@@ -139,6 +468,10 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         self.actor_manager.remove_pawn(key);
     }
 
+    pub fn force_pawn_update(&mut self, key: &ActorKey) {
+        self.actor_manager.force_pawn_update(key);
+    }
+
     // Pass-through methods to underlying common connection
 
     pub fn mark_sent(&mut self) {
@@ -149,12 +482,32 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         return self.connection.should_send_heartbeat();
     }
 
-    pub fn mark_heard(&mut self) {
-        return self.connection.mark_heard();
+    pub fn mark_heard(&mut self, now: &Instant) {
+        return self.connection.mark_heard(now);
+    }
+
+    pub fn should_drop(&self, now: &Instant) -> bool {
+        return self.connection.should_drop(now);
+    }
+
+    pub fn set_compression_enabled(&mut self, enabled: bool) {
+        self.connection.set_compression_enabled(enabled);
+    }
+
+    pub fn set_message_tracing_enabled(&mut self, enabled: bool) {
+        self.connection.set_message_tracing_enabled(enabled);
+    }
+
+    pub fn set_schema_versions(&mut self, schema_versions: SchemaVersions) {
+        self.connection.set_schema_versions(schema_versions);
     }
 
-    pub fn should_drop(&self) -> bool {
-        return self.connection.should_drop();
+    pub fn is_compression_enabled(&self) -> bool {
+        return self.connection.is_compression_enabled();
+    }
+
+    pub fn is_message_tracing_enabled(&self) -> bool {
+        return self.connection.is_message_tracing_enabled();
     }
 
     pub fn process_incoming_header(&mut self, header: &StandardHeader) {
@@ -169,34 +522,251 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         packet_type: PacketType,
         payload: &[u8],
     ) -> Box<[u8]> {
-        return self.connection.process_outgoing_header(
+        let mtu = self.get_mtu();
+        self.connection.set_max_packet_size(mtu);
+        let packet = self.connection.process_outgoing_header(
             host_tick,
             last_received_tick,
             packet_type,
             payload,
         );
+        self.bytes_sent += packet.len() as u64;
+        #[cfg(feature = "diagnostics")]
+        self.histograms.record_outgoing_packet(packet.len());
+        return packet;
     }
 
     pub fn get_next_packet_index(&self) -> SequenceNumber {
         return self.connection.get_next_packet_index();
     }
 
-    pub fn queue_event(&mut self, event: &impl Event<T>) {
-        return self.connection.queue_event(event);
+    pub fn queue_event(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        return self.connection.queue_event(event, channel);
+    }
+
+    /// Queues an Event about an Actor, to be received along with the key the
+    /// Client knows the Actor by. Does nothing if the Actor isn't in scope.
+    /// Returns an Error if the channel hasn't been configured
+    pub fn queue_actor_event(
+        &mut self,
+        actor_key: &ActorKey,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        if let Some(local_key) = self.actor_manager.get_local_key(actor_key) {
+            return self.connection.queue_actor_event(event, local_key, channel);
+        }
+        return Ok(());
+    }
+
+    /// Holds an Event about an Actor the Client has yet to acknowledge
+    /// creating, until `queue_held_actor_events()` finds that it has. Returns
+    /// an Error if the channel hasn't been configured
+    pub fn hold_actor_event(
+        &mut self,
+        actor_key: &ActorKey,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        self.connection.check_channel(channel)?;
+        self.held_actor_events
+            .push((*actor_key, event.clone_box(), channel));
+        return Ok(());
+    }
+
+    /// Queues the held Events about Actors the Client has since acknowledged
+    /// creating, in the order they were held, and drops those about Actors
+    /// which have left scope before it did
+    pub fn queue_held_actor_events(&mut self) {
+        if self.held_actor_events.is_empty() {
+            return;
+        }
+        let held_actor_events = std::mem::take(&mut self.held_actor_events);
+        for (actor_key, event, channel) in held_actor_events {
+            match self.actor_manager.get_sync_state(&actor_key) {
+                ActorSyncState::SpawnPending => {
+                    self.held_actor_events.push((actor_key, event, channel));
+                }
+                ActorSyncState::Synced { .. } | ActorSyncState::UpdatePending { .. } => {
+                    // the channel was checked when the Event was held
+                    let _ = self.queue_actor_event(&actor_key, event.as_ref(), channel);
+                }
+                ActorSyncState::NotInScope | ActorSyncState::DespawnPending => {}
+            }
+        }
+    }
+
+    pub fn queue_event_group(&mut self, events: &[Box<dyn Event<T>>]) {
+        return self.connection.queue_event_group(events);
+    }
+
+    pub fn queue_raw_event(
+        &mut self,
+        raw_event: &RawEvent,
+        type_id: TypeId,
+        guaranteed: bool,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        return self
+            .connection
+            .queue_raw_event(raw_event, type_id, guaranteed, channel);
+    }
+
+    /// Gets the next received Event, along with its MessageReceipt if its type
+    /// is timestamped
+    pub fn get_incoming_event(&mut self) -> Option<(T, Option<MessageReceipt>)> {
+        // Clients don't send Events about Actors
+        let (event, stamp, _) = self.connection.get_incoming_event()?;
+        let receipt = stamp.map(|stamp| stamp.to_receipt(self.get_rtt(), &self.clock.now()));
+        return Some((event, receipt));
+    }
+
+    pub fn get_incoming_raw_event(&mut self) -> Option<RawEvent> {
+        return self.connection.get_incoming_raw_event();
+    }
+
+    pub fn get_skipped_frame(&mut self) -> Option<SkippedFrame> {
+        return self.connection.get_skipped_frame();
+    }
+
+    pub fn take_malformed_frame_limit_exceeded(&mut self) -> bool {
+        return self.connection.take_malformed_frame_limit_exceeded();
+    }
+
+    pub fn get_incoming_event_count(&self) -> usize {
+        return self.connection.get_incoming_event_count();
+    }
+
+    pub fn get_held_local_key_count(&self) -> usize {
+        return self.actor_manager.get_held_local_key_count();
+    }
+
+    /// Passes the Events received since the incoming queue held the given
+    /// number of Events through the middleware, queueing any replies. Returns
+    /// whether any replies were queued
+    pub fn apply_event_middleware(
+        &mut self,
+        user_key: UserKey,
+        first_new_event: usize,
+        manifest: &Manifest<T, U>,
+        middleware: &EventMiddleware<T>,
+    ) -> Option<usize> {
+        let first_reply = self.connection.get_queued_event_count();
+        let mut replied = false;
+        for (event, stamp, actor) in self.connection.take_incoming_events_from(first_new_event) {
+            let receipt = stamp
+                .as_ref()
+                .map(|stamp| stamp.to_receipt(self.get_rtt(), &self.clock.now()));
+            let mut context = EventContext::new(user_key, self.get_rtt(), receipt);
+            // received Events were built from the Manifest, so are always registered
+            let handling = match manifest.get_event_naia_id(&event.get_type_id()) {
+                Ok(naia_id) => middleware.process(naia_id, &mut context, &event),
+                Err(_) => EventHandling::PassThrough,
+            };
+
+            for reply in context.take_replies() {
+                let channel = manifest.get_event_channel(&reply.get_type_id());
+                if let Err(error) = self.connection.queue_event(reply.as_ref(), channel) {
+                    warn!("dropping Event middleware reply: {}", error);
+                    continue;
+                }
+                replied = true;
+            }
+
+            if handling == EventHandling::PassThrough {
+                self.connection.push_incoming_event(event, stamp, actor);
+            }
+        }
+        if !replied {
+            return None;
+        }
+        return Some(first_reply);
+    }
+
+    /// Gets a packet holding only Events queued at or after the given position
+    /// in the outgoing queue, as the replies of Event middleware are, so that
+    /// they can be sent straight away without anything queued before them
+    pub fn get_outgoing_reply_packet(
+        &mut self,
+        host_tick: u16,
+        manifest: &Manifest<T, U>,
+        first_reply: usize,
+    ) -> Option<Box<[u8]>> {
+        let now = self.clock.now();
+        let max_payload_size = self.get_mtu() - StandardHeader::bytes_number();
+        let mut writer = ServerPacketWriter::new(max_payload_size);
+        writer.set_writes_trace_ids(self.connection.is_message_tracing_enabled());
+
+        let next_packet_index: u16 = self.get_next_packet_index();
+        while let Some(popped_event) = self
+            .connection
+            .pop_outgoing_event_from(next_packet_index, first_reply)
+        {
+            if !writer.write_event(
+                manifest,
+                self.connection.get_schema_versions(),
+                &popped_event,
+                &now,
+            ) {
+                self.connection.unpop_outgoing_event_to(
+                    next_packet_index,
+                    &popped_event,
+                    first_reply,
+                );
+                break;
+            }
+        }
+
+        if !writer.has_bytes() {
+            return None;
+        }
+        let out_bytes = writer.get_bytes();
+        return Some(self.process_outgoing_header(
+            host_tick,
+            self.connection.get_last_received_tick(),
+            PacketType::Data,
+            &out_bytes,
+        ));
+    }
+
+    pub fn get_perceived_tick(&self, client_tick: u16) -> Option<u16> {
+        return self.command_receiver.get_perceived_tick(client_tick);
     }
 
-    pub fn get_incoming_event(&mut self) -> Option<T> {
-        return self.connection.get_incoming_event();
+    /// Gets the payload of a report of how many Commands were received in time
+    /// for their tick, and how many were missed, once per report interval, if
+    /// any have been
+    pub fn get_command_report_payload(&mut self) -> Option<Box<[u8]>> {
+        let now = self.clock.now();
+        if !self.command_report_timer.ringing(&now) {
+            return None;
+        }
+        self.command_report_timer.reset(&now);
+        let (received_count, missed_count) = self.command_receiver.take_miss_counts()?;
+        let mut payload = Vec::new();
+        byte_order::write_u16(&mut payload, received_count); // write received count
+        byte_order::write_u16(&mut payload, missed_count); // write missed count
+        return Some(payload.into_boxed_slice());
     }
 
-    pub fn get_incoming_command(&mut self, server_tick: u16) -> Option<(ActorKey, T)> {
-        if let Some((local_pawn_key, command)) =
+    pub fn get_incoming_command(
+        &mut self,
+        server_tick: u16,
+    ) -> Option<(ActorKey, T, Option<SubTick>)> {
+        if let Some((local_pawn_key, command, sub_tick)) =
             self.command_receiver.pop_incoming_command(server_tick)
         {
             if let Some(global_pawn_key) =
                 self.actor_manager.get_global_key_from_local(local_pawn_key)
             {
-                return Some((*global_pawn_key, command));
+                return Some((*global_pawn_key, command, sub_tick));
             }
         }
         return None;
@@ -206,11 +776,728 @@ impl<T: EventType, U: ActorType> ClientConnection<T, U> {
         return self.connection.get_address();
     }
 
-    pub fn process_ping(&self, ping_payload: &[u8]) -> Box<[u8]> {
-        return self.ping_manager.process_ping(ping_payload);
+    pub fn process_ping(&self, ping_payload: &[u8], received_at: &Instant) -> Box<[u8]> {
+        return self
+            .ping_manager
+            .process_ping(ping_payload, received_at, &self.clock.now());
+    }
+
+    pub fn force_ping(&mut self) {
+        self.ping_manager.force_ping();
+    }
+
+    pub fn should_send_ping(&self) -> bool {
+        return self.ping_manager.should_send_ping(&self.clock.now());
+    }
+
+    pub fn get_ping_payload(&mut self) -> Box<[u8]> {
+        return self.ping_manager.get_ping_payload(&self.clock.now());
+    }
+
+    pub fn process_pong(&mut self, pong_payload: &[u8]) {
+        self.ping_manager
+            .process_pong(pong_payload, &self.clock.now());
+    }
+
+    pub fn get_rtt(&self) -> f32 {
+        return self.ping_manager.get_rtt();
+    }
+
+    // mtu discovery
+
+    /// Gets the payload of the next MTU probe to send, if one is due. First
+    /// lowers the MTU, if large packets have started to be lost
+    pub fn get_mtu_probe_payload(&mut self) -> Option<Box<[u8]>> {
+        let mtu_prober = self.mtu_prober.as_mut()?;
+        let now = self.clock.now();
+        let delivery = self
+            .connection
+            .get_delivery_by_size(MIN_MTU, mtu_prober.get_raised_at_packet());
+        mtu_prober.process_delivery(&delivery, &now);
+        return mtu_prober.get_probe_payload(&now);
+    }
+
+    /// Queues the whole state of each Actor in a request from the Client to
+    /// be resent, the request holding the LocalActorKey & ActorGeneration of
+    /// each, after it missed a long run of packets
+    pub fn process_resync_request(&mut self, request_payload: &[u8]) {
+        let mut reader = PacketReader::new(request_payload);
+        while reader.get_buffer().len() - reader.get_cursor().position() as usize >= 3 {
+            let local_key = byte_order::read_u16(&mut reader);
+            let generation = reader.read_u8();
+            self.actor_manager.resync_actor(local_key, generation);
+        }
+    }
+
+    // manifest extension
+
+    /// Gets the payload of a ManifestExtension telling the Client of the Event
+    /// types registered since the Server was started, past those it has
+    /// answered for, if there are any, resent until it answers
+    pub fn get_manifest_extension_payload(
+        &mut self,
+        late_event_types: &LateEventTypes,
+    ) -> Option<Box<[u8]>> {
+        if self.manifest_extension_epoch >= late_event_types.get_epoch() {
+            return None;
+        }
+        let now = self.clock.now();
+        if !self.manifest_extension_timer.ringing(&now) {
+            return None;
+        }
+        self.manifest_extension_timer.reset(&now);
+        return late_event_types.get_extension_payload(self.manifest_extension_epoch);
+    }
+
+    /// Records the Client's answer to a ManifestExtension, unless it has
+    /// already answered a later one
+    pub fn process_manifest_extension_ack(&mut self, ack_payload: &[u8]) {
+        let ack = match read_manifest_extension_ack(&mut PacketReader::new(ack_payload)) {
+            Ok(ack) => ack,
+            Err(_) => return,
+        };
+        if ack.epoch <= self.manifest_extension_epoch {
+            return;
+        }
+        self.manifest_extension_epoch = ack.epoch;
+        self.late_event_type_ids.extend(ack.naia_ids);
+        // anything registered since is sent straight away
+        self.manifest_extension_timer.ring_manual();
+    }
+
+    /// Gets whether the Client can be sent Events of the type with the given
+    /// NaiaId, as it always can those registered before the Server was
+    /// started, and those since once it has answered that it has registered
+    /// them too. None if it has yet to answer for the type
+    pub fn has_event_type(&self, late_event_types: &LateEventTypes, naia_id: u16) -> Option<bool> {
+        match late_event_types.get_epoch_of(naia_id) {
+            Some(epoch) if epoch <= self.manifest_extension_epoch => {
+                return Some(self.late_event_type_ids.contains(&naia_id));
+            }
+            Some(_) => return None,
+            None => return Some(true),
+        }
+    }
+
+    pub fn process_mtu_probe_ack(&mut self, ack_payload: &[u8]) {
+        let next_packet_index = self.get_next_packet_index();
+        if let Some(mtu_prober) = &mut self.mtu_prober {
+            mtu_prober.process_probe_ack(ack_payload, &self.clock.now(), next_packet_index);
+        }
+    }
+
+    pub fn get_mtu(&self) -> usize {
+        match &self.mtu_prober {
+            Some(mtu_prober) => return mtu_prober.get_mtu(),
+            None => return MIN_MTU,
+        }
+    }
+
+    // congestion
+
+    /// Gets the number of messages on the given outgoing queue, either waiting
+    /// to be sent, or waiting to be acknowledged by the Client
+    pub fn get_outgoing_count(&self, queue: OutgoingQueue) -> usize {
+        match queue {
+            OutgoingQueue::Events => return self.connection.get_outgoing_event_count(),
+            OutgoingQueue::Actors => return self.actor_manager.get_outgoing_message_count(),
+        }
+    }
+
+    /// Gets the number of bytes on the given outgoing queue which are measured
+    /// for congestion: those of the reliable Events waiting to be
+    /// acknowledged. Actor messages are written from the current state of
+    /// their Actors as they're sent, so have no size until then, and aren't
+    /// measured
+    pub fn get_outgoing_bytes(&self, queue: OutgoingQueue) -> usize {
+        match queue {
+            OutgoingQueue::Events => {
+                let channels = (0..self.ordered_channel_count).map(Some);
+                return std::iter::once(None)
+                    .chain(channels)
+                    .map(|channel| self.connection.get_reliable_usage(channel).1)
+                    .sum();
+            }
+            OutgoingQueue::Actors => return 0,
+        }
+    }
+
+    /// Updates the congestion state of each outgoing queue, returning the
+    /// first queue to have changed state, and whether it is now congested
+    pub fn update_congestion(&mut self) -> Option<(OutgoingQueue, bool)> {
+        for queue in [OutgoingQueue::Events, OutgoingQueue::Actors].iter() {
+            let count = self.get_outgoing_count(*queue);
+            let bytes = self.get_outgoing_bytes(*queue);
+            if let Some(congested) = self.congestion_monitor.update(*queue, count, bytes) {
+                return Some((*queue, congested));
+            }
+        }
+        return None;
+    }
+
+    /// Returns whether queued messages should be written into packets & sent
+    /// now, given the send mode & the current tick. In PerTick mode, this is
+    /// only true for the first call in each tick
+    pub fn take_flush(&mut self, send_mode: SendMode, host_tick: u16) -> bool {
+        match send_mode {
+            SendMode::Immediate => return true,
+            SendMode::PerTick => {
+                if self.last_flush_tick == Some(host_tick) {
+                    return false;
+                }
+                self.last_flush_tick = Some(host_tick);
+                return true;
+            }
+        }
+    }
+
+    /// Returns whether there are any Events or Actor messages either waiting
+    /// to be sent, or waiting to be acknowledged by the Client
+    pub fn has_pending_messages(&self) -> bool {
+        return self.get_outgoing_count(OutgoingQueue::Events) != 0
+            || self.get_outgoing_count(OutgoingQueue::Actors) != 0;
+    }
+
+    /// Gets the number of ordered channels, which `get_reliable_usage()` can be
+    /// asked about along with the unordered channel
+    pub fn get_ordered_channel_count(&self) -> u8 {
+        return self.ordered_channel_count;
+    }
+
+    /// Gets the tail of the Events queued for the Client so far, which a
+    /// barrier placed now is set at
+    pub fn get_reliable_tail(&self) -> u64 {
+        return self.connection.get_reliable_tail();
+    }
+
+    /// Returns whether the Client has acknowledged every reliable Event queued
+    /// before the given tail
+    pub fn is_delivered_up_to(&self, tail: u64) -> bool {
+        return self.connection.is_delivered_up_to(tail);
+    }
+
+    /// Gets the number of reliable Events which have yet to be acknowledged,
+    /// and their total size in bytes, on either the given ordered channel, or
+    /// the unordered channel if None
+    pub fn get_reliable_usage(&self, channel: Option<ChannelIndex>) -> (usize, usize) {
+        return self.connection.get_reliable_usage(channel);
+    }
+
+    /// Returns whether one of the reliable Event buffers has overflowed, after
+    /// which the connection has failed and nothing more should be queued
+    pub fn is_reliable_buffer_overflowed(&self) -> bool {
+        return self.reliable_buffer_overflowed;
+    }
+
+    /// Checks whether any reliable Event buffer is over the configured limits,
+    /// returning the state of the connection the first time one is
+    pub fn check_reliable_buffers(&mut self) -> Option<ReliableBufferDiagnostics> {
+        if self.reliable_buffer_overflowed {
+            return None;
+        }
+
+        let channels = (0..self.ordered_channel_count).map(Some);
+        for channel in std::iter::once(None).chain(channels) {
+            let (unacked_messages, unacked_bytes) = self.connection.get_reliable_usage(channel);
+            if unacked_messages > self.reliable_buffer_max_messages
+                || unacked_bytes > self.reliable_buffer_max_bytes
+            {
+                self.reliable_buffer_overflowed = true;
+                let oldest_unacked_age = match self.connection.get_oldest_reliable_queued_at() {
+                    Some(queued_at) => duration_between(&queued_at, &self.clock.now()),
+                    None => Duration::from_secs(0),
+                };
+                return Some(ReliableBufferDiagnostics {
+                    channel,
+                    unacked_messages,
+                    unacked_bytes,
+                    oldest_unacked_age,
+                    packet_loss: self.connection.get_packet_loss(),
+                });
+            }
+        }
+        return None;
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn is_congested(&self) -> bool {
+        return self.congestion_monitor.is_congested();
+    }
+
+    pub fn is_over_hard_limit(&self, queue: OutgoingQueue) -> bool {
+        return self.congestion_monitor.is_over_hard_limit(
+            queue,
+            self.get_outgoing_count(queue),
+            self.get_outgoing_bytes(queue),
+        );
+    }
+
+    // connection metadata
+
+    pub fn get_uptime(&self) -> Duration {
+        return duration_between(&self.connected_at, &self.clock.now());
+    }
+
+    pub fn get_bytes_sent(&self) -> u64 {
+        return self.bytes_sent;
+    }
+
+    pub fn get_padding_bytes_sent(&self) -> u64 {
+        return self.connection.get_padding_bytes_sent();
+    }
+
+    pub fn get_cover_heartbeat_bytes_sent(&self) -> u64 {
+        return self.connection.get_cover_heartbeat_bytes_sent();
+    }
+
+    pub fn mark_bytes_received(&mut self, bytes: usize) {
+        self.bytes_received += bytes as u64;
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub fn get_histograms(&self) -> &ConnectionHistograms {
+        return &self.histograms;
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub fn get_histograms_mut(&mut self) -> &mut ConnectionHistograms {
+        return &mut self.histograms;
+    }
+
+    pub fn get_bytes_received(&self) -> u64 {
+        return self.bytes_received;
+    }
+
+    pub fn get_packet_loss(&self) -> f32 {
+        return self.connection.get_packet_loss();
+    }
+
+    pub fn mark_command_rejected(&mut self) {
+        self.rejected_commands += 1;
+    }
+
+    pub fn get_rejected_command_count(&self) -> u64 {
+        return self.rejected_commands;
+    }
+
+    pub fn get_protocol_memory(&self) -> usize {
+        return self.connection.get_protocol_memory();
+    }
+
+    pub fn get_far_ahead_packet_count(&self) -> u64 {
+        return self.connection.get_far_ahead_packet_count();
+    }
+
+    pub fn get_expired_event_count(&self) -> u64 {
+        return self.connection.get_expired_event_count();
+    }
+
+    pub fn get_expired_despawn_count(&self) -> u64 {
+        return self.expired_despawns;
+    }
+
+    pub fn set_auth_event(&mut self, auth_event: Option<T>) {
+        self.auth_event = auth_event;
+    }
+
+    pub fn get_auth_event(&self) -> Option<&T> {
+        return self.auth_event.as_ref();
     }
 
     pub fn get_last_received_tick(&self) -> u16 {
         return self.connection.get_last_received_tick();
     }
+
+    // session resumption
+
+    /// Sets the session token the Client was issued, and whether the
+    /// connection was resumed, both of which are sent in the connect response
+    pub fn set_session(&mut self, session_token: Option<SessionToken>, resumed: bool) {
+        self.session_token = session_token;
+        self.resumed = resumed;
+    }
+
+    pub fn get_session_token(&self) -> Option<&SessionToken> {
+        return self.session_token.as_ref();
+    }
+
+    pub fn is_resumed(&self) -> bool {
+        return self.resumed;
+    }
+
+    pub fn get_connection_info(&self) -> ConnectionInfo {
+        return ConnectionInfo {
+            compression_enabled: self.is_compression_enabled(),
+            message_tracing_enabled: self.is_message_tracing_enabled(),
+            mtu: self.get_mtu(),
+            tick_interval: self.connection.get_tick_interval(),
+            resumable: self.session_token.is_some(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use std::time::Duration;
+
+    use naia_shared::{
+        test_support::{Blob, BlobBuilder, Block, BlockBuilder, NoActors, TestEvents},
+        Clock, ConnectionConfig, HostTickManager, ManagerType, Manifest, PacketReader, PacketType,
+        StandardHeader, SystemClock,
+    };
+
+    use naia_shared::{
+        handshake::EarlyData,
+        manifest_extension::{read_manifest_extension, write_manifest_extension_ack},
+    };
+
+    use super::ClientConnection;
+    use crate::{
+        actors::mut_handler::MutHandler, late_event_types::LateEventTypes, send_mode::SendMode,
+        server_config::ServerConfig, server_tick_manager::ServerTickManager,
+    };
+
+    // the reliable buffers are kept small, so that they overflow quickly
+    fn get_server_config() -> ServerConfig {
+        let mut server_config = ServerConfig::default();
+        server_config.reliable_buffer_max_messages = 100;
+        server_config.reliable_buffer_max_bytes = 1000;
+        return server_config;
+    }
+
+    fn new_connection() -> ClientConnection<TestEvents, NoActors> {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        return ClientConnection::new(
+            address,
+            Some(&MutHandler::new()),
+            &ConnectionConfig::default(),
+            &get_server_config(),
+        );
+    }
+
+    #[test]
+    fn reliable_buffer_overflows_under_total_loss() {
+        let mut connection = new_connection();
+
+        // every packet is lost, so nothing is ever acknowledged
+        let mut overflow = None;
+        for _ in 0..1000 {
+            connection.queue_event(&Blob { size: 16 }, None).unwrap();
+            connection
+                .queue_event(&Block { id: 0, size: 16 }, None)
+                .unwrap();
+            overflow = connection.check_reliable_buffers();
+            if overflow.is_some() {
+                break;
+            }
+
+            let packet_index = connection.get_next_packet_index();
+            while connection
+                .connection
+                .pop_outgoing_event(packet_index)
+                .is_some()
+            {}
+            connection.process_outgoing_header(0, 0, PacketType::Data, &[]);
+        }
+
+        // unreliable Events don't count towards the limit
+        let diagnostics = overflow.unwrap();
+        assert_eq!(diagnostics.channel, None);
+        assert_eq!(diagnostics.unacked_messages, 63);
+        assert_eq!(diagnostics.unacked_bytes, 63 * 16);
+        assert_eq!(diagnostics.packet_loss, 1.0);
+        assert!(connection.is_reliable_buffer_overflowed());
+
+        // only reported once
+        connection.queue_event(&Blob { size: 16 }, None).unwrap();
+        assert!(connection.check_reliable_buffers().is_none());
+    }
+
+    #[test]
+    fn reply_packets_hold_only_the_replies() {
+        let mut connection = new_connection();
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+        manifest.register_event(Box::new(BlockBuilder));
+
+        // Events queued before the replies, which wait for the next tick
+        for _ in 0..2 {
+            connection.queue_event(&Blob { size: 16 }, None).unwrap();
+        }
+        let first_reply = connection.connection.get_queued_event_count();
+        assert!(connection
+            .get_outgoing_reply_packet(0, &manifest, first_reply)
+            .is_none());
+
+        // more replies than fit in a packet are spread over several
+        for _ in 0..40 {
+            connection
+                .queue_event(&Block { id: 0, size: 16 }, None)
+                .unwrap();
+        }
+        let mut packet_count = 0;
+        while let Some(packet) = connection.get_outgoing_reply_packet(0, &manifest, first_reply) {
+            assert!(packet.len() <= connection.get_mtu());
+            packet_count += 1;
+        }
+        assert!(packet_count > 1);
+        assert_eq!(connection.connection.get_queued_event_count(), 2);
+    }
+
+    #[test]
+    fn late_event_types_wait_for_the_client_to_answer() {
+        let mut connection = new_connection();
+        let mut late_event_types = LateEventTypes::new();
+        assert!(connection
+            .get_manifest_extension_payload(&late_event_types)
+            .is_none());
+        assert_eq!(connection.has_event_type(&late_event_types, 0), Some(true));
+
+        late_event_types.add(1, "Reload".to_string());
+        late_event_types.add(2, "Patch".to_string());
+        let payload = connection
+            .get_manifest_extension_payload(&late_event_types)
+            .unwrap();
+        let extension = read_manifest_extension(&mut PacketReader::new(&payload)).unwrap();
+        assert_eq!(extension.epoch, 2);
+        assert_eq!(connection.has_event_type(&late_event_types, 1), None);
+        // not resent until the resend interval has passed
+        assert!(connection
+            .get_manifest_extension_payload(&late_event_types)
+            .is_none());
+
+        connection.process_manifest_extension_ack(&write_manifest_extension_ack(2, &[1]));
+        assert_eq!(connection.has_event_type(&late_event_types, 1), Some(true));
+        assert_eq!(connection.has_event_type(&late_event_types, 2), Some(false));
+        // an answer to an earlier ManifestExtension, arriving late, is ignored
+        connection.process_manifest_extension_ack(&write_manifest_extension_ack(1, &[]));
+        assert_eq!(connection.has_event_type(&late_event_types, 1), Some(true));
+
+        // a type registered since is sent on its own straight away
+        late_event_types.add(3, "Hotfix".to_string());
+        let payload = connection
+            .get_manifest_extension_payload(&late_event_types)
+            .unwrap();
+        let extension = read_manifest_extension(&mut PacketReader::new(&payload)).unwrap();
+        assert_eq!(extension.epoch, 3);
+        assert_eq!(extension.event_types, [(3, "Hotfix".to_string())]);
+    }
+
+    #[test]
+    fn commands_are_stamped_with_the_tick_the_client_saw() {
+        let mut connection = new_connection();
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+
+        // a Command for tick 110, along with the one issued 2 ticks before it,
+        // from a Client which had last received tick 95
+        let payload = [ManagerType::Command as u8, 1, 0, 7, 0, 0, 1, 2];
+        connection.process_incoming_data(100, 110, 95, &manifest, &payload, &SystemClock.now());
+
+        assert_eq!(connection.get_perceived_tick(110), Some(95));
+        assert_eq!(connection.get_perceived_tick(108), Some(93));
+        assert_eq!(connection.get_perceived_tick(109), None);
+    }
+
+    #[test]
+    fn early_data_is_only_released_past_the_newest_command_tick() {
+        let mut connection = new_connection();
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+
+        // Commands up to tick 110 were received before the connection dropped
+        let commands = vec![ManagerType::Command as u8, 1, 0, 7, 0, 0, 0];
+        connection.process_incoming_data(100, 110, 95, &manifest, &commands, &SystemClock.now());
+
+        let early_packet = |client_tick: u16, payload: &Vec<u8>| EarlyData {
+            client_tick,
+            perceived_tick: client_tick - 10,
+            payload: payload.clone(),
+        };
+        let events = vec![ManagerType::Event as u8, 0];
+        let early_data = [
+            early_packet(109, &commands),
+            early_packet(110, &commands),
+            early_packet(112, &commands),
+            early_packet(111, &commands),
+            early_packet(113, &events),
+            early_packet(114, &commands),
+        ];
+        assert_eq!(
+            connection.release_early_data(105, &manifest, &early_data),
+            2
+        );
+        assert_eq!(connection.get_perceived_tick(112), Some(102));
+        assert_eq!(connection.get_perceived_tick(114), Some(104));
+        for replayed_tick in [109, 111, 113].iter() {
+            assert_eq!(connection.get_perceived_tick(*replayed_tick), None);
+        }
+
+        // the same early data can't be released twice
+        assert_eq!(
+            connection.release_early_data(105, &manifest, &early_data),
+            0
+        );
+    }
+
+    #[test]
+    fn commands_missing_between_ticks_are_counted_as_missed() {
+        let mut connection = new_connection();
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+
+        // Commands for pawn 7 on ticks 101, 102 & 105, the one for 104 arriving
+        // too late for its tick, and for 130, after a pause, each along with
+        // the Server tick it arrives on
+        let arrivals = [(101, 100), (102, 100), (105, 104), (104, 105), (130, 110)];
+        for server_tick in 100..=130 {
+            for (tick, _) in arrivals.iter().filter(|(_, at)| *at == server_tick) {
+                let payload = [ManagerType::Command as u8, 1, 0, 7, 0, 0, 0];
+                connection.process_incoming_data(
+                    server_tick,
+                    *tick,
+                    server_tick,
+                    &manifest,
+                    &payload,
+                    &SystemClock.now(),
+                );
+            }
+            while connection.get_incoming_command(server_tick).is_some() {}
+        }
+
+        // ticks 103 & 104 are missed, while the pause isn't counted
+        assert_eq!(connection.command_receiver.take_miss_counts(), Some((4, 2)));
+        assert_eq!(connection.command_receiver.take_miss_counts(), None);
+    }
+
+    #[test]
+    fn command_sub_ticks_survive_redundancy() {
+        let mut connection = new_connection();
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+        manifest.set_command_sub_ticks(true);
+
+        // Commands for pawn 7 issued at steps 100, 200, 300 & 400 of ticks 108
+        // to 111, each sent along with the 2 before it. Only the packets for
+        // ticks 110 & 111 arrive, the one for 110 twice
+        let packets: [(u16, [u8; 14]); 3] = [
+            (110, [1, 0, 7, 0, 0, 2, 1, 44, 1, 0, 200, 2, 0, 100]),
+            (110, [1, 0, 7, 0, 0, 2, 1, 44, 1, 0, 200, 2, 0, 100]),
+            (111, [1, 0, 7, 0, 0, 2, 1, 144, 1, 1, 44, 2, 0, 200]),
+        ];
+        for (client_tick, commands) in packets.iter() {
+            let mut payload = vec![ManagerType::Command as u8];
+            payload.extend_from_slice(commands);
+            connection.process_incoming_data(
+                100,
+                *client_tick,
+                100,
+                &manifest,
+                &payload,
+                &SystemClock.now(),
+            );
+        }
+
+        // each Command is taken once, on its own tick, with its own SubTick
+        let steps = [(108, 100), (109, 200), (110, 300), (111, 400)];
+        for (tick, step) in steps.iter() {
+            match connection.command_receiver.pop_incoming_command(*tick) {
+                Some((7, _, Some(sub_tick))) => assert_eq!(sub_tick.get_step(), *step),
+                _ => panic!("expected a Command with a SubTick on tick {}", tick),
+            }
+            assert!(connection
+                .command_receiver
+                .pop_incoming_command(*tick)
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn stale_unreliable_events_expire_after_stall() {
+        let mut connection = new_connection();
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+        manifest.register_event(Box::new(BlockBuilder));
+        manifest
+            .set_event_expiry::<Block>(Duration::from_millis(100))
+            .unwrap();
+
+        // the connection stalls with Events queued
+        for _ in 0..3 {
+            connection
+                .queue_event(&Block { id: 0, size: 16 }, None)
+                .unwrap();
+        }
+        connection.queue_event(&Blob { size: 16 }, None).unwrap();
+        let mut later = SystemClock.now();
+        later.add_millis(150);
+        connection
+            .connection
+            .expire_outgoing_events(&manifest, &later);
+
+        // once it recovers, only the guaranteed & fresh Events are sent
+        connection
+            .queue_event(&Block { id: 0, size: 16 }, None)
+            .unwrap();
+        let packet = connection.get_outgoing_packet(0, &manifest).unwrap();
+        let (header, payload) = StandardHeader::read(&packet);
+        assert_eq!(header.packet_type(), PacketType::Data);
+        assert_eq!(payload[0], ManagerType::Event as u8);
+        assert_eq!(payload[1], 2);
+        assert_eq!(connection.get_expired_event_count(), 3);
+    }
+
+    #[test]
+    fn per_tick_send_mode_flushes_once_per_tick() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+        manifest.register_event(Box::new(BlockBuilder));
+
+        for send_mode in [SendMode::PerTick, SendMode::Immediate].iter() {
+            let mut connection = new_connection();
+            let mut now = SystemClock.now();
+            let mut tick_manager =
+                ServerTickManager::new(Duration::from_millis(33), 5, now.clone());
+
+            // the loop wakes up at irregular intervals, several times a tick
+            let mut seed: u32 = 0x2545_f491;
+            let mut call_count = 0;
+            let mut flush_ticks: Vec<u16> = Vec::new();
+            for _ in 0..1000 {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                now.add_millis(1 + seed % 20);
+                tick_manager.update(now.clone());
+                while tick_manager.take_tick() {}
+                let tick = tick_manager.get_tick();
+
+                call_count += 1;
+                connection
+                    .queue_event(&Block { id: 0, size: 16 }, None)
+                    .unwrap();
+                if connection.take_flush(*send_mode, tick) {
+                    flush_ticks.push(tick);
+                    while let Some(packet) = connection.get_outgoing_packet(tick, &manifest) {
+                        let (header, _) = StandardHeader::read(&packet);
+                        assert_eq!(header.host_tick(), tick);
+                    }
+                }
+            }
+
+            match send_mode {
+                SendMode::PerTick => {
+                    // exactly one flush in every tick
+                    let expected_ticks: Vec<u16> = (0..=tick_manager.get_tick()).collect();
+                    assert_eq!(flush_ticks, expected_ticks);
+                    assert!(call_count > flush_ticks.len() * 2);
+                }
+                SendMode::Immediate => {
+                    assert_eq!(flush_ticks.len(), call_count);
+                }
+            }
+        }
+    }
 }
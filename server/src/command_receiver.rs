@@ -1,16 +1,29 @@
 use std::collections::HashMap;
 
 use naia_shared::{
-    sequence_greater_than, ActorType, EventType, LocalActorKey, Manifest, PacketReader,
-    SequenceBuffer,
+    byte_order, sequence_greater_than, ActorType, EventType, LocalActorKey, Manifest, PacketReader,
+    SchemaVersions, SequenceBuffer, SubTick,
 };
 
 const COMMAND_BUFFER_MAX_SIZE: u16 = 64;
+// the most ticks in a row a pawn can go without a Command for them to be
+// counted as missed, rather than as the Client having stopped issuing them
+const MAX_MISSED_RUN: u16 = 8;
 
 /// Handles incoming commands, buffering them to be received on the correct tick
 #[derive(Debug)]
 pub struct CommandReceiver<T: EventType> {
-    queued_incoming_commands: SequenceBuffer<HashMap<LocalActorKey, T>>,
+    // the Commands for each tick, along with the SubTicks they were issued at,
+    // if the Manifest is set to send them
+    queued_incoming_commands: SequenceBuffer<HashMap<LocalActorKey, (T, Option<SubTick>)>>,
+    // the most recent Server tick the Client had received when it issued the
+    // commands of each tick
+    perceived_ticks: SequenceBuffer<u16>,
+    // the last tick a Command for each pawn was received in time for
+    last_received_ticks: HashMap<LocalActorKey, u16>,
+    // the number of Commands received in time & missed since the last report
+    received_count: u16,
+    missed_count: u16,
 }
 
 impl<T: EventType> CommandReceiver<T> {
@@ -18,49 +31,98 @@ impl<T: EventType> CommandReceiver<T> {
     pub fn new() -> Self {
         CommandReceiver {
             queued_incoming_commands: SequenceBuffer::with_capacity(COMMAND_BUFFER_MAX_SIZE),
+            perceived_ticks: SequenceBuffer::with_capacity(COMMAND_BUFFER_MAX_SIZE),
+            last_received_ticks: HashMap::new(),
+            received_count: 0,
+            missed_count: 0,
         }
     }
 
-    /// Get the most recently received Command
-    pub fn pop_incoming_command(&mut self, server_tick: u16) -> Option<(LocalActorKey, T)> {
+    /// Get the most recently received Command, along with the SubTick it was
+    /// issued at, if the Manifest is set to send them
+    pub fn pop_incoming_command(
+        &mut self,
+        server_tick: u16,
+    ) -> Option<(LocalActorKey, T, Option<SubTick>)> {
         if let Some(map) = self.queued_incoming_commands.get_mut(server_tick) {
             let mut any_key: Option<u16> = None;
             if let Some(any_key_ref) = map.keys().next() {
                 any_key = Some(*any_key_ref);
             }
             if let Some(any_key) = any_key {
-                if let Some(command) = map.remove(&any_key) {
-                    return Some((any_key, command));
+                if let Some((command, sub_tick)) = map.remove(&any_key) {
+                    self.count_command(any_key, server_tick);
+                    return Some((any_key, command, sub_tick));
                 }
             }
         }
         return None;
     }
 
+    /// Takes the number of Commands received in time for their tick, and the
+    /// number missed, since the last time they were taken, if there were any
+    pub fn take_miss_counts(&mut self) -> Option<(u16, u16)> {
+        if self.received_count == 0 && self.missed_count == 0 {
+            return None;
+        }
+        let counts = (self.received_count, self.missed_count);
+        self.received_count = 0;
+        self.missed_count = 0;
+        return Some(counts);
+    }
+
+    // counts a Command taken for its tick, along with the ticks since the last
+    // one for the same pawn, which were missed
+    fn count_command(&mut self, pawn_key: LocalActorKey, server_tick: u16) {
+        if let Some(last_tick) = self.last_received_ticks.insert(pawn_key, server_tick) {
+            let gap = server_tick.wrapping_sub(last_tick);
+            if gap > 1 && gap <= MAX_MISSED_RUN + 1 {
+                self.missed_count = self.missed_count.saturating_add(gap - 1);
+            }
+        }
+        self.received_count = self.received_count.saturating_add(1);
+    }
+
+    /// Gets the most recent Server tick the Client had received when it issued
+    /// the Commands of the given tick, if any of them are still buffered
+    pub fn get_perceived_tick(&self, client_tick: u16) -> Option<u16> {
+        return self.perceived_ticks.get(client_tick).copied();
+    }
+
     /// Given incoming packet data, read transmitted Command and store them to
-    /// be returned to the application
+    /// be returned to the application. Commands are read as the schema
+    /// versions of their types agreed on for the connection, and stamped with
+    /// the most recent Server tick the Client had received, which past
+    /// Commands are stamped with as many ticks back as they were issued. Each
+    /// is read along with the SubTick it was issued at, if the Manifest is set
+    /// to send them
     pub fn process_data<U: ActorType>(
         &mut self,
         server_tick: u16,
         client_tick: u16,
+        perceived_tick: u16,
         reader: &mut PacketReader,
         manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
     ) {
+        let reads_sub_ticks = manifest.has_command_sub_ticks();
         let command_count = reader.read_u8();
         for _x in 0..command_count {
-            let local_actor_key: LocalActorKey = reader.read_u16();
-            let naia_id: u16 = reader.read_u16();
+            let local_actor_key: LocalActorKey = byte_order::read_u16(reader);
+            let naia_id: u16 = byte_order::read_u16(reader);
             let past_commands_number: u8 = reader.read_u8();
+            let sub_tick = read_sub_tick(reads_sub_ticks, reader);
 
-            match manifest.create_event(naia_id, reader) {
+            match manifest.create_event(naia_id, reader, schema_versions) {
                 Some(new_command) => {
                     if !self.queued_incoming_commands.exists(client_tick) {
                         self.queued_incoming_commands
                             .insert(client_tick, HashMap::new());
                     }
                     if let Some(map) = self.queued_incoming_commands.get_mut(client_tick) {
-                        map.insert(local_actor_key, new_command);
+                        map.insert(local_actor_key, (new_command, sub_tick));
                     }
+                    self.perceived_ticks.insert(client_tick, perceived_tick);
                 }
                 _ => {}
             }
@@ -68,8 +130,9 @@ impl<T: EventType> CommandReceiver<T> {
             for _y in 0..past_commands_number {
                 let tick_diff = reader.read_u8();
                 let past_tick = client_tick.wrapping_sub(tick_diff.into());
+                let past_sub_tick = read_sub_tick(reads_sub_ticks, reader);
 
-                match manifest.create_event(naia_id, reader) {
+                match manifest.create_event(naia_id, reader, schema_versions) {
                     Some(new_command) => {
                         if sequence_greater_than(past_tick, server_tick) {
                             if !self.queued_incoming_commands.exists(past_tick) {
@@ -78,9 +141,15 @@ impl<T: EventType> CommandReceiver<T> {
                             }
                             if let Some(map) = self.queued_incoming_commands.get_mut(past_tick) {
                                 if !map.contains_key(&local_actor_key) {
-                                    map.insert(local_actor_key, new_command);
+                                    map.insert(local_actor_key, (new_command, past_sub_tick));
                                 }
                             }
+                            if !self.perceived_ticks.exists(past_tick) {
+                                self.perceived_ticks.insert(
+                                    past_tick,
+                                    perceived_tick.wrapping_sub(tick_diff.into()),
+                                );
+                            }
                         }
                     }
                     _ => {}
@@ -89,3 +158,12 @@ impl<T: EventType> CommandReceiver<T> {
         }
     }
 }
+
+// reads the SubTick a Command was issued at, if the Manifest is set to send
+// them
+fn read_sub_tick(reads_sub_ticks: bool, reader: &mut PacketReader) -> Option<SubTick> {
+    if reads_sub_ticks {
+        return Some(SubTick::read(reader));
+    }
+    return None;
+}
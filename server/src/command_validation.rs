@@ -0,0 +1,18 @@
+/// Returned from a Command validation function registered with
+/// `NaiaServer::on_validate_command()`, to decide what happens to a Command
+/// received from a Client before it is applied to the Pawn
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandValidation<T> {
+    /// The Command is returned from `NaiaServer::receive()` as it was sent
+    Accept,
+    /// The given, corrected Command is returned from `NaiaServer::receive()`
+    /// in place of the one which was sent
+    Clamp(T),
+    /// The Command is discarded, and counted against the User. If `correct`
+    /// is set, the full state of the Pawn is sent back to the Client, so that
+    /// its prediction is corrected straight away
+    Reject {
+        /// Whether to send the full state of the Pawn back to the Client
+        correct: bool,
+    },
+}
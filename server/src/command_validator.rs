@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use naia_shared::{ActorType, EventType};
+
+use super::{command_validation::CommandValidation, user::user_key::UserKey};
+
+type ValidatorFn<T, U> = Box<dyn Fn(&U, &T, &UserKey, u16) -> CommandValidation<T>>;
+
+/// Holds the validation function registered for each type of Command, keyed
+/// by NaiaId
+pub struct CommandValidator<T: EventType, U: ActorType> {
+    validators: HashMap<u16, ValidatorFn<T, U>>,
+}
+
+impl<T: EventType, U: ActorType> CommandValidator<T, U> {
+    /// Create a new, empty CommandValidator
+    pub fn new() -> Self {
+        CommandValidator {
+            validators: HashMap::new(),
+        }
+    }
+
+    /// Register the validation function for Commands with the given NaiaId,
+    /// replacing any function already registered for the same type
+    pub fn register(&mut self, naia_id: u16, validator: ValidatorFn<T, U>) {
+        self.validators.insert(naia_id, validator);
+    }
+
+    /// Returns whether any validation functions have been registered
+    pub fn is_empty(&self) -> bool {
+        return self.validators.len() == 0;
+    }
+
+    /// Passes a Command through the validation function registered for its
+    /// type, given the current state of the Pawn it was issued to, the User it
+    /// was received from, & the tick it is being applied on. Commands of types
+    /// without a validation function are accepted
+    pub fn validate(
+        &self,
+        naia_id: u16,
+        pawn: &U,
+        command: &T,
+        user_key: &UserKey,
+        server_tick: u16,
+    ) -> CommandValidation<T> {
+        match self.validators.get(&naia_id) {
+            Some(validator) => return validator(pawn, command, user_key, server_tick),
+            None => return CommandValidation::Accept,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::test_support::{TestActors, TestEvents};
+    use slotmap::DenseSlotMap;
+
+    use super::CommandValidator;
+    use crate::{command_validation::CommandValidation, UserKey};
+
+    const MOVE_ID: u16 = 0;
+    const JUMP_ID: u16 = 1;
+    const MAX_STEP: i16 = 2;
+
+    // accepts small steps, clamps larger ones, & rejects steps out of bounds
+    fn new_validator() -> CommandValidator<TestEvents, TestActors> {
+        let mut validator = CommandValidator::new();
+        validator.register(
+            MOVE_ID,
+            Box::new(|pawn, command, _, _| match (pawn, command) {
+                (TestActors::Position(position), TestEvents::Move(step)) => {
+                    if position.borrow().0 + step > 100 {
+                        return CommandValidation::Reject { correct: true };
+                    }
+                    if step.abs() > MAX_STEP {
+                        return CommandValidation::Clamp(TestEvents::Move(
+                            step.signum() * MAX_STEP,
+                        ));
+                    }
+                    return CommandValidation::Accept;
+                }
+                _ => panic!("only Moves of a Position are validated"),
+            }),
+        );
+        return validator;
+    }
+
+    fn new_user_key() -> UserKey {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        return users.insert(());
+    }
+
+    #[test]
+    fn accepts_clamps_and_rejects() {
+        let validator = new_validator();
+        let user_key = new_user_key();
+        let pawn = TestActors::new_position(90);
+
+        assert_eq!(
+            validator.validate(MOVE_ID, &pawn, &TestEvents::Move(1), &user_key, 0),
+            CommandValidation::Accept
+        );
+        assert_eq!(
+            validator.validate(MOVE_ID, &pawn, &TestEvents::Move(-5), &user_key, 0),
+            CommandValidation::Clamp(TestEvents::Move(-2))
+        );
+        assert_eq!(
+            validator.validate(MOVE_ID, &pawn, &TestEvents::Move(20), &user_key, 0),
+            CommandValidation::Reject { correct: true }
+        );
+    }
+
+    #[test]
+    fn unregistered_types_are_accepted() {
+        let validator = new_validator();
+        let user_key = new_user_key();
+        let pawn = TestActors::new_position(90);
+
+        assert_eq!(
+            validator.validate(JUMP_ID, &pawn, &TestEvents::Move(20), &user_key, 0),
+            CommandValidation::Accept
+        );
+    }
+
+    #[test]
+    fn registering_again_replaces_validator() {
+        let mut validator = new_validator();
+        let user_key = new_user_key();
+        let pawn = TestActors::new_position(0);
+
+        validator.register(
+            MOVE_ID,
+            Box::new(|_, _, _, _| CommandValidation::Reject { correct: false }),
+        );
+        assert_eq!(
+            validator.validate(MOVE_ID, &pawn, &TestEvents::Move(1), &user_key, 0),
+            CommandValidation::Reject { correct: false }
+        );
+    }
+}
@@ -0,0 +1,29 @@
+/// The limits on how much one of a connection's outgoing queues may hold
+/// before the connection is considered congested
+#[derive(Clone, Debug, PartialEq)]
+pub struct CongestionLimits {
+    /// The amount held past which the connection is reported as congested
+    pub soft_limit: usize,
+    /// The amount held at which the congestion policy is applied. Must be
+    /// greater than the soft limit
+    pub hard_limit: usize,
+}
+
+impl CongestionLimits {
+    /// Create new CongestionLimits, given the soft limit at which a queue is
+    /// considered congested, and the hard limit at which the congestion policy
+    /// is applied
+    pub fn new(soft_limit: usize, hard_limit: usize) -> Self {
+        CongestionLimits {
+            soft_limit,
+            hard_limit,
+        }
+    }
+
+    /// Returns whether the soft limit is below the hard limit, without which
+    /// the connection would never be reported as congested before the
+    /// congestion policy is applied
+    pub fn is_valid(&self) -> bool {
+        return self.soft_limit < self.hard_limit;
+    }
+}
@@ -0,0 +1,195 @@
+use super::{
+    congestion_limits::CongestionLimits, outgoing_queue::OutgoingQueue, server_config::ServerConfig,
+};
+
+/// Tracks whether the outgoing queues of a connection are congested, given the
+/// number of messages in each either waiting to be sent or waiting to be
+/// acknowledged, and for the Events queue, the number of bytes of reliable
+/// Events waiting to be acknowledged
+#[derive(Debug)]
+pub struct CongestionMonitor {
+    event_limits: CongestionLimits,
+    event_byte_limits: CongestionLimits,
+    actor_limits: CongestionLimits,
+    events_congested: bool,
+    actors_congested: bool,
+}
+
+impl CongestionMonitor {
+    /// Create a new CongestionMonitor, given the limits on the number of
+    /// messages on each queue, and on the number of bytes on the Events queue
+    pub fn new(
+        event_limits: CongestionLimits,
+        event_byte_limits: CongestionLimits,
+        actor_limits: CongestionLimits,
+    ) -> Self {
+        CongestionMonitor {
+            event_limits,
+            event_byte_limits,
+            actor_limits,
+            events_congested: false,
+            actors_congested: false,
+        }
+    }
+
+    /// Create a new CongestionMonitor with the limits in the given
+    /// ServerConfig
+    pub fn from_config(server_config: &ServerConfig) -> Self {
+        return CongestionMonitor::new(
+            server_config.event_congestion_limits.clone(),
+            server_config.event_congestion_byte_limits.clone(),
+            server_config.actor_congestion_limits.clone(),
+        );
+    }
+
+    /// Updates the congestion state of a queue given the number of messages
+    /// & bytes it holds. Returns Some(true) when the queue grows past either
+    /// soft limit, and Some(false) once it has drained back to half of both,
+    /// so that a queue hovering around a limit does not flap between states
+    pub fn update(
+        &mut self,
+        queue: OutgoingQueue,
+        queued_count: usize,
+        queued_bytes: usize,
+    ) -> Option<bool> {
+        let over_soft_limit = self.exceeds(queue, queued_count, queued_bytes, |limits| {
+            return limits.soft_limit + 1;
+        });
+        let drained = !self.exceeds(queue, queued_count, queued_bytes, |limits| {
+            return limits.soft_limit / 2 + 1;
+        });
+        let congested = match queue {
+            OutgoingQueue::Events => &mut self.events_congested,
+            OutgoingQueue::Actors => &mut self.actors_congested,
+        };
+        if !*congested && over_soft_limit {
+            *congested = true;
+            return Some(true);
+        }
+        if *congested && drained {
+            *congested = false;
+            return Some(false);
+        }
+        return None;
+    }
+
+    /// Returns whether either queue is congested
+    #[cfg(feature = "metrics")]
+    pub fn is_congested(&self) -> bool {
+        return self.events_congested || self.actors_congested;
+    }
+
+    /// Returns whether a queue holding the given number of messages & bytes
+    /// has reached either hard limit
+    pub fn is_over_hard_limit(
+        &self,
+        queue: OutgoingQueue,
+        queued_count: usize,
+        queued_bytes: usize,
+    ) -> bool {
+        return self.exceeds(queue, queued_count, queued_bytes, |limits| {
+            return limits.hard_limit;
+        });
+    }
+
+    // whether a queue holds at least the threshold picked from its limits,
+    // either in messages, or for the Events queue, in bytes
+    fn exceeds<F: Fn(&CongestionLimits) -> usize>(
+        &self,
+        queue: OutgoingQueue,
+        queued_count: usize,
+        queued_bytes: usize,
+        threshold: F,
+    ) -> bool {
+        match queue {
+            OutgoingQueue::Events => {
+                return queued_count >= threshold(&self.event_limits)
+                    || queued_bytes >= threshold(&self.event_byte_limits);
+            }
+            OutgoingQueue::Actors => return queued_count >= threshold(&self.actor_limits),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CongestionMonitor;
+    use crate::{congestion_limits::CongestionLimits, outgoing_queue::OutgoingQueue};
+
+    // a monitor with the given message limits on both queues, and byte limits
+    // too high to be reached
+    fn new_monitor(soft_limit: usize, hard_limit: usize) -> CongestionMonitor {
+        return CongestionMonitor::new(
+            CongestionLimits::new(soft_limit, hard_limit),
+            CongestionLimits::new(usize::MAX - 1, usize::MAX),
+            CongestionLimits::new(soft_limit, hard_limit),
+        );
+    }
+
+    #[test]
+    fn congests_and_clears() {
+        let mut monitor = new_monitor(100, 400);
+
+        assert_eq!(monitor.update(OutgoingQueue::Events, 100, 0), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 101, 0), Some(true));
+        assert_eq!(monitor.update(OutgoingQueue::Events, 300, 0), None);
+
+        // still congested until drained to half the soft limit
+        assert_eq!(monitor.update(OutgoingQueue::Events, 99, 0), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 50, 0), Some(false));
+        assert_eq!(monitor.update(OutgoingQueue::Events, 50, 0), None);
+    }
+
+    #[test]
+    fn queues_are_independent() {
+        let mut monitor = new_monitor(10, 40);
+
+        assert_eq!(monitor.update(OutgoingQueue::Actors, 11, 0), Some(true));
+        assert_eq!(monitor.update(OutgoingQueue::Events, 5, 0), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 11, 0), Some(true));
+        assert_eq!(monitor.update(OutgoingQueue::Actors, 0, 0), Some(false));
+    }
+
+    #[test]
+    fn queues_have_their_own_limits() {
+        let mut monitor = CongestionMonitor::new(
+            CongestionLimits::new(10, 40),
+            CongestionLimits::new(1000, 4000),
+            CongestionLimits::new(100, 400),
+        );
+
+        assert_eq!(monitor.update(OutgoingQueue::Actors, 50, 0), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 50, 0), Some(true));
+        assert!(monitor.is_over_hard_limit(OutgoingQueue::Events, 40, 0));
+        assert!(!monitor.is_over_hard_limit(OutgoingQueue::Actors, 399, 0));
+        assert!(monitor.is_over_hard_limit(OutgoingQueue::Actors, 400, 0));
+    }
+
+    #[test]
+    fn events_congest_by_bytes() {
+        let mut monitor = CongestionMonitor::new(
+            CongestionLimits::new(100, 400),
+            CongestionLimits::new(1000, 4000),
+            CongestionLimits::new(100, 400),
+        );
+
+        // a few large Events
+        assert_eq!(monitor.update(OutgoingQueue::Events, 2, 1000), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 3, 1001), Some(true));
+
+        // cleared once drained to half of both soft limits
+        assert_eq!(monitor.update(OutgoingQueue::Events, 3, 501), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 51, 500), None);
+        assert_eq!(monitor.update(OutgoingQueue::Events, 50, 500), Some(false));
+
+        assert!(!monitor.is_over_hard_limit(OutgoingQueue::Events, 1, 3999));
+        assert!(monitor.is_over_hard_limit(OutgoingQueue::Events, 1, 4000));
+    }
+
+    #[test]
+    fn hard_limit() {
+        let monitor = new_monitor(10, 40);
+        assert!(!monitor.is_over_hard_limit(OutgoingQueue::Events, 39, 0));
+        assert!(monitor.is_over_hard_limit(OutgoingQueue::Events, 40, 0));
+    }
+}
@@ -0,0 +1,11 @@
+/// Determines what the Server does when one of a connection's outgoing queues
+/// reaches the hard congestion limit
+#[derive(Clone, Debug, PartialEq)]
+pub enum CongestionPolicy {
+    /// Refuse to queue anything more for the connection until the queue has
+    /// drained: queueing an Event returns an Error, and no new Actors are
+    /// brought into scope for the User
+    RejectSends,
+    /// Disconnect the User
+    DropConnection,
+}
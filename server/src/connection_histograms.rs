@@ -0,0 +1,116 @@
+use naia_shared::{utils::duration_between, Histogram, Instant};
+
+// Upper bounds of the packet size buckets, in bytes. The minimum MTU of 508
+// bytes, and the largest probed MTU of 1472 bytes, each end a bucket
+const PACKET_SIZE_BOUNDS: [u32; 7] = [64, 128, 256, 508, 1024, 1280, 1472];
+
+// Upper bounds of the inter-arrival time buckets, in milliseconds
+const INTER_ARRIVAL_BOUNDS: [u32; 7] = [1, 5, 10, 20, 50, 100, 250];
+
+// Upper bounds of the processing time buckets, in microseconds
+const PROCESSING_TIME_BOUNDS: [u32; 7] = [10, 50, 100, 250, 500, 1000, 5000];
+
+/// Distributions of the traffic on a connection, which show problems that
+/// averages hide, such as bursts of packets arriving after being held in a
+/// queue somewhere along the way
+#[derive(Clone, Debug)]
+pub struct ConnectionHistograms {
+    outgoing_packet_sizes: Histogram,
+    incoming_inter_arrival_times: Histogram,
+    processing_times: Histogram,
+    last_arrival: Option<Instant>,
+}
+
+impl ConnectionHistograms {
+    pub(crate) fn new() -> Self {
+        ConnectionHistograms {
+            outgoing_packet_sizes: Histogram::new(PACKET_SIZE_BOUNDS),
+            incoming_inter_arrival_times: Histogram::new(INTER_ARRIVAL_BOUNDS),
+            processing_times: Histogram::new(PROCESSING_TIME_BOUNDS),
+            last_arrival: None,
+        }
+    }
+
+    pub(crate) fn record_outgoing_packet(&mut self, size: usize) {
+        self.outgoing_packet_sizes
+            .record(size.min(u32::MAX as usize) as u32);
+    }
+
+    pub(crate) fn record_arrival(&mut self, received_at: &Instant) {
+        if let Some(last_arrival) = &self.last_arrival {
+            let inter_arrival = duration_between(last_arrival, received_at);
+            self.incoming_inter_arrival_times
+                .record(inter_arrival.as_millis().min(u32::MAX as u128) as u32);
+        }
+        self.last_arrival = Some(received_at.clone());
+    }
+
+    pub(crate) fn record_processing(&mut self, received_at: &Instant, processed_at: &Instant) {
+        let processing_time = duration_between(received_at, processed_at);
+        self.processing_times
+            .record(processing_time.as_micros().min(u32::MAX as u128) as u32);
+    }
+
+    /// Gets the distribution of the sizes of packets sent, in bytes
+    pub fn outgoing_packet_sizes(&self) -> &Histogram {
+        return &self.outgoing_packet_sizes;
+    }
+
+    /// Gets the distribution of the time between packets being received, in
+    /// milliseconds
+    pub fn incoming_inter_arrival_times(&self) -> &Histogram {
+        return &self.incoming_inter_arrival_times;
+    }
+
+    /// Gets the distribution of the time taken to process each Data packet
+    /// received, from when it was received, in microseconds
+    pub fn processing_times(&self) -> &Histogram {
+        return &self.processing_times;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{Clock, SystemClock};
+
+    use super::ConnectionHistograms;
+
+    #[test]
+    fn synthetic_traffic_lands_in_expected_buckets() {
+        let mut histograms = ConnectionHistograms::new();
+
+        // mostly small packets, with a few at the minimum MTU & one above it
+        for size in [40, 60, 100, 300, 508, 508, 900].iter() {
+            histograms.record_outgoing_packet(*size);
+        }
+        assert_eq!(
+            histograms.outgoing_packet_sizes().get_counts(),
+            &[2, 1, 0, 3, 1, 0, 0, 0]
+        );
+
+        // packets arrive every 16ms, then a burst is released from a queue
+        let mut now = SystemClock.now();
+        for _ in 0..5 {
+            histograms.record_arrival(&now);
+            now.add_millis(16);
+        }
+        histograms.record_arrival(&now);
+        histograms.record_arrival(&now);
+        histograms.record_arrival(&now);
+        now.add_millis(300);
+        histograms.record_arrival(&now);
+        assert_eq!(
+            histograms.incoming_inter_arrival_times().get_counts(),
+            &[2, 0, 0, 5, 0, 0, 0, 1]
+        );
+
+        let received_at = SystemClock.now();
+        let mut processed_at = received_at.clone();
+        processed_at.add_millis(2);
+        histograms.record_processing(&received_at, &processed_at);
+        assert_eq!(
+            histograms.processing_times().get_counts(),
+            &[0, 0, 0, 0, 0, 0, 1, 0]
+        );
+    }
+}
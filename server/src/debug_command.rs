@@ -0,0 +1,56 @@
+use std::{fmt, net::SocketAddr};
+
+#[cfg(feature = "link-conditioner")]
+use naia_shared::LinkConditionerConfig;
+
+/// An operation for looking into or adjusting a running Server, carried out by
+/// `NaiaServer::debug_command()`, so that a game can wire diagnostics up to its
+/// own console or RCON. Connections are picked out by the address of their
+/// Client, as listed by `ListConnections`
+#[derive(Clone, Debug)]
+pub enum DebugCommand {
+    /// Lists every connected Client, one per line, by address, along with its
+    /// round trip time & packet loss, and whether its packets are traced
+    ListConnections,
+    /// Starts or stops tracing every packet sent to & received from the
+    /// Client at the given address. Each is logged as it goes, and the most
+    /// recent are kept for `DumpPacketTrace`
+    SetPacketTracing(SocketAddr, bool),
+    /// Takes the lines traced for the Client at the given address since they
+    /// were last taken, oldest first
+    DumpPacketTrace(SocketAddr),
+    /// Writes out the schema of the Server's Manifest, as JSON
+    DumpManifest,
+    /// Lists how many messages are waiting on each outgoing queue of the
+    /// connection to the Client at the given address, how many LocalActorKeys
+    /// it holds, and how many reliable Events on each channel are yet to be
+    /// acknowledged
+    DumpQueueDepths(SocketAddr),
+    /// Sets the network conditions simulated on the packets the Server
+    /// receives, or stops simulating any with None, from the next tick. Only
+    /// the UDP transport's conditions can be changed
+    #[cfg(feature = "link-conditioner")]
+    SetLinkConditioner(Option<LinkConditionerConfig>),
+}
+
+/// The outcome of a DebugCommand, with its output formatted as text, which is
+/// what the DebugResponse is displayed as
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DebugResponse {
+    /// The command was carried out, with its output
+    Done(String),
+    /// The command was accepted, and takes effect at the next tick
+    Scheduled(String),
+    /// The command couldn't be carried out, with the reason why
+    Failed(String),
+}
+
+impl fmt::Display for DebugResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            DebugResponse::Done(text)
+            | DebugResponse::Scheduled(text)
+            | DebugResponse::Failed(text) => write!(f, "{}", text),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+/// Determines how the Server handles a new connection request from an address
+/// which already has an established connection, for example when a Client has
+/// crashed and restarted before its previous connection has timed out
+#[derive(Clone, Debug, PartialEq)]
+pub enum DuplicateConnectionPolicy {
+    /// Disconnect the existing User and accept the new connection. The new
+    /// connection must still complete the handshake challenge, so that the
+    /// existing User cannot be evicted by a spoofed address
+    ReplaceExisting,
+    /// Reject the new connection until the existing connection has timed out
+    RejectNew,
+}
@@ -0,0 +1,51 @@
+use naia_shared::{Event, EventType, MessageReceipt};
+
+use super::user::user_key::UserKey;
+
+/// The context an Event middleware function is called with, giving limited
+/// access to the connection the Event was received on
+#[derive(Debug)]
+pub struct EventContext<T: EventType> {
+    user_key: UserKey,
+    rtt: f32,
+    receipt: Option<MessageReceipt>,
+    replies: Vec<Box<dyn Event<T>>>,
+}
+
+impl<T: EventType> EventContext<T> {
+    pub(crate) fn new(user_key: UserKey, rtt: f32, receipt: Option<MessageReceipt>) -> Self {
+        EventContext {
+            user_key,
+            rtt,
+            receipt,
+            replies: Vec::new(),
+        }
+    }
+
+    /// Get the key of the User the Event was received from
+    pub fn user_key(&self) -> UserKey {
+        return self.user_key;
+    }
+
+    /// Get the average Round Trip Time measured to the User, in milliseconds
+    pub fn rtt(&self) -> f32 {
+        return self.rtt;
+    }
+
+    /// Get an estimate of when the Event was sent, if its type is set to be
+    /// timestamped in the Manifest
+    pub fn receipt(&self) -> Option<MessageReceipt> {
+        return self.receipt;
+    }
+
+    /// Queues up an Event to be sent back to the User. Replies are sent as soon
+    /// as the received packet has been processed, without waiting for the next
+    /// call to `NaiaServer::send_all_updates()`
+    pub fn send_event(&mut self, event: &impl Event<T>) {
+        self.replies.push(event.clone_box());
+    }
+
+    pub(crate) fn take_replies(&mut self) -> Vec<Box<dyn Event<T>>> {
+        return std::mem::take(&mut self.replies);
+    }
+}
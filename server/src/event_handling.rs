@@ -0,0 +1,11 @@
+/// Returned from an Event middleware function registered with
+/// `NaiaServer::on_receive()`, to decide what happens to the received Event
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventHandling {
+    /// The Event has been dealt with, it will not be passed to any further
+    /// middleware, nor returned from `NaiaServer::receive()`
+    Handled,
+    /// The Event continues on to the next middleware registered for its type,
+    /// and then to the application
+    PassThrough,
+}
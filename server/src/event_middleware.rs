@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use naia_shared::EventType;
+
+use super::{event_context::EventContext, event_handling::EventHandling};
+
+type MiddlewareFn<T> = Box<dyn Fn(&mut EventContext<T>, &T) -> EventHandling>;
+
+/// Holds the middleware functions registered for each type of Event, keyed
+/// by NaiaId
+pub struct EventMiddleware<T: EventType> {
+    handlers: HashMap<u16, Vec<MiddlewareFn<T>>>,
+}
+
+impl<T: EventType> EventMiddleware<T> {
+    /// Create a new, empty EventMiddleware
+    pub fn new() -> Self {
+        EventMiddleware {
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Register a middleware function for Events with the given NaiaId. It will
+    /// be called after any functions already registered for the same type
+    pub fn register(&mut self, naia_id: u16, handler: MiddlewareFn<T>) {
+        self.handlers.entry(naia_id).or_default().push(handler);
+    }
+
+    /// Returns whether any middleware functions have been registered
+    pub fn is_empty(&self) -> bool {
+        return self.handlers.len() == 0;
+    }
+
+    /// Passes an Event through the middleware registered for its type, in
+    /// registration order, until one of them handles it
+    pub fn process(&self, naia_id: u16, context: &mut EventContext<T>, event: &T) -> EventHandling {
+        if let Some(handlers) = self.handlers.get(&naia_id) {
+            for handler in handlers {
+                if handler(context, event) == EventHandling::Handled {
+                    return EventHandling::Handled;
+                }
+            }
+        }
+        return EventHandling::PassThrough;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use naia_shared::test_support::{Pong, TestEvents};
+    use slotmap::DenseSlotMap;
+
+    use super::EventMiddleware;
+    use crate::{event_context::EventContext, event_handling::EventHandling, UserKey};
+
+    const PING_ID: u16 = 0;
+    const CHAT_ID: u16 = 1;
+
+    fn new_context() -> EventContext<TestEvents> {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        return EventContext::new(users.insert(()), 50.0, None);
+    }
+
+    #[test]
+    fn ping_responder_replies_in_layer() {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut middleware = EventMiddleware::new();
+
+        let first_calls = calls.clone();
+        middleware.register(
+            PING_ID,
+            Box::new(move |_, _| {
+                first_calls.borrow_mut().push("logger");
+                EventHandling::PassThrough
+            }),
+        );
+        let second_calls = calls.clone();
+        middleware.register(
+            PING_ID,
+            Box::new(move |context, event| {
+                second_calls.borrow_mut().push("responder");
+                if let TestEvents::Ping(sequence) = event {
+                    context.send_event(&Pong(*sequence));
+                }
+                EventHandling::Handled
+            }),
+        );
+        let third_calls = calls.clone();
+        middleware.register(
+            PING_ID,
+            Box::new(move |_, _| {
+                third_calls.borrow_mut().push("unreachable");
+                EventHandling::PassThrough
+            }),
+        );
+
+        let mut context = new_context();
+        let handling = middleware.process(PING_ID, &mut context, &TestEvents::Ping(7));
+
+        assert_eq!(handling, EventHandling::Handled);
+        assert_eq!(*calls.borrow(), vec!["logger", "responder"]);
+        let replies = context.take_replies();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].get_typed_copy(), TestEvents::Pong(7));
+    }
+
+    #[test]
+    fn unregistered_types_pass_through() {
+        let mut middleware = EventMiddleware::new();
+        middleware.register(PING_ID, Box::new(|_, _| EventHandling::Handled));
+
+        let mut context = new_context();
+        let handling = middleware.process(CHAT_ID, &mut context, &TestEvents::Ping(1));
+        assert_eq!(handling, EventHandling::PassThrough);
+        assert_eq!(context.take_replies().len(), 0);
+    }
+}
@@ -0,0 +1,93 @@
+use naia_shared::{manifest_extension::write_manifest_extension, MTU_SIZE};
+
+/// The Event types registered since the Server was started, each given a
+/// NaiaId past those of every type registered before it, so that no id a
+/// Client has been told of is ever reused. Every Client is told of them in a
+/// ManifestExtension, and can only be sent Events of those it answers that it
+/// has registered too
+pub struct LateEventTypes {
+    // the NaiaId & name of each, in the order they were registered
+    event_types: Vec<(u16, String)>,
+}
+
+impl LateEventTypes {
+    /// Creates a LateEventTypes, which holds no types yet
+    pub fn new() -> Self {
+        LateEventTypes {
+            event_types: Vec::new(),
+        }
+    }
+
+    /// Adds an Event type which has just been registered
+    pub fn add(&mut self, naia_id: u16, name: String) {
+        self.event_types.push((naia_id, name));
+    }
+
+    /// Gets the number of Event types registered since the Server was
+    /// started, which a Client's answer to a ManifestExtension is counted
+    /// against
+    pub fn get_epoch(&self) -> u16 {
+        return self.event_types.len() as u16;
+    }
+
+    /// Gets the epoch a Client has answered once it has answered for the Event
+    /// type with the given NaiaId, or None if the type was registered before
+    /// the Server was started
+    pub fn get_epoch_of(&self, naia_id: u16) -> Option<u16> {
+        return self
+            .event_types
+            .iter()
+            .position(|(late_naia_id, _)| *late_naia_id == naia_id)
+            .map(|index| index as u16 + 1);
+    }
+
+    /// Gets the payload of a ManifestExtension telling a Client of the Event
+    /// types registered past the given epoch, as many as fit into a packet, or
+    /// None if there are none
+    pub fn get_extension_payload(&self, from_epoch: u16) -> Option<Box<[u8]>> {
+        let pending = self.event_types.get(from_epoch as usize..)?;
+        if pending.is_empty() {
+            return None;
+        }
+        let mut count = pending.len();
+        let mut payload = write_manifest_extension(from_epoch + count as u16, pending);
+        // the first type always fits, as its name is cut short to 255 bytes
+        while payload.len() > MTU_SIZE && count > 1 {
+            count -= 1;
+            payload = write_manifest_extension(from_epoch + count as u16, &pending[..count]);
+        }
+        return Some(payload.into_boxed_slice());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{manifest_extension::read_manifest_extension, PacketReader, MTU_SIZE};
+
+    use super::LateEventTypes;
+
+    #[test]
+    fn extensions_hold_the_types_past_the_epoch_which_fit() {
+        let mut late_event_types = LateEventTypes::new();
+        assert!(late_event_types.get_extension_payload(0).is_none());
+        for naia_id in 4..14 {
+            late_event_types.add(naia_id, format!("{:0>100}", naia_id));
+        }
+        assert_eq!(late_event_types.get_epoch(), 10);
+        assert_eq!(late_event_types.get_epoch_of(6), Some(3));
+        assert_eq!(late_event_types.get_epoch_of(3), None);
+
+        let payload = late_event_types.get_extension_payload(2).unwrap();
+        assert!(payload.len() <= MTU_SIZE);
+        let extension = read_manifest_extension(&mut PacketReader::new(&payload)).unwrap();
+        // four names of 100 bytes fit into a packet, but not five
+        assert_eq!(extension.epoch, 6);
+        let naia_ids: Vec<u16> = extension
+            .event_types
+            .iter()
+            .map(|(naia_id, _)| *naia_id)
+            .collect();
+        assert_eq!(naia_ids, [6, 7, 8, 9]);
+        assert!(late_event_types.get_extension_payload(10).is_none());
+    }
+}
@@ -2,6 +2,13 @@
 //! A server that uses either UDP or WebRTC communication to send/receive events
 //! to/from connected clients, and syncs registered actors to clients to whom
 //! those actors are in-scope.
+//!
+//! Optional subsystems are behind features: "link-conditioner", on by
+//! default, for simulating network conditions, "compression" for payload
+//! compression, "diagnostics" for per-connection histograms, and "metrics"
+//! for exporting the Server's stats through the `metrics` facade. The "bench"
+//! feature exposes a TickHarness, which runs a tick's replication work without
+//! a socket, for the benchmarks in `benches/`.
 
 #![deny(
     missing_docs,
@@ -24,27 +31,107 @@ compile_error!("Naia Server can only use UDP or WebRTC, you must pick one");
 #[cfg(all(not(feature = "use-udp"), not(feature = "use-webrtc")))]
 compile_error!("Naia Server requires either the 'use-udp' or 'use-webrtc' feature to be enabled, you must pick one.");
 
+#[cfg(feature = "link-conditioner")]
+pub use naia_shared::LinkConditionerConfig;
 pub use naia_shared::{
-    find_my_ip_address, Actor, ActorType, LinkConditionerConfig, Random, SharedConfig,
+    find_my_ip_address, Actor, ActorRef, ActorType, ChannelIndex, Clock, ConnectionInfo,
+    DespawnReason, HookAction, ManifestError, ManualClock, MessageReceipt, NaiaError,
+    PacketPadding, PredictionId, Random, RawEvent, SharedConfig, SkippedFrame, StateMask, SubTick,
+    SystemClock,
 };
 
+mod actor_event_delivery;
 mod actors;
+mod barrier_outcome;
 mod client_connection;
 mod command_receiver;
-mod error;
+mod command_validation;
+mod command_validator;
+mod congestion_limits;
+mod congestion_monitor;
+mod congestion_policy;
+#[cfg(feature = "diagnostics")]
+mod connection_histograms;
+mod debug_command;
+mod duplicate_connection_policy;
+mod event_context;
+mod event_handling;
+mod event_middleware;
 mod interval;
+mod late_event_types;
+#[cfg(feature = "metrics")]
+mod metrics_config;
+#[cfg(feature = "metrics")]
+mod metrics_exporter;
+mod mtu_prober;
 mod naia_server;
-mod ping_manager;
+mod outgoing_queue;
+mod pacing_config;
+mod packet_pacer;
+mod packet_sender;
+mod packet_tracer;
+mod query_responder;
+mod rate_limiter;
+mod reliable_barriers;
+mod reliable_buffer_diagnostics;
 mod room;
+mod send_mode;
 mod server_config;
+mod server_context;
 mod server_event;
+mod server_event_handler;
 mod server_packet_writer;
+mod server_receiver;
+mod server_sender;
 mod server_tick_manager;
+mod session_store;
+mod socket_config;
+#[cfg(feature = "bench")]
+mod tick_harness;
+mod tick_payload_sender;
+mod tick_summary;
+#[cfg(feature = "use-udp")]
+mod udp_server_socket;
+mod unknown_address_policy;
 mod user;
+mod user_ref;
 
-pub use actors::actor_key::actor_key::ActorKey;
+pub use actor_event_delivery::ActorEventDelivery;
+pub use actors::{
+    actor_history::HistoryView, actor_key::actor_key::ActorKey, actor_sync_state::ActorSyncState,
+    update_rate::UpdateRate,
+};
+pub use barrier_outcome::BarrierOutcome;
+pub use command_validation::CommandValidation;
+pub use congestion_limits::CongestionLimits;
+pub use congestion_policy::CongestionPolicy;
+#[cfg(feature = "diagnostics")]
+pub use connection_histograms::ConnectionHistograms;
+pub use debug_command::{DebugCommand, DebugResponse};
+pub use duplicate_connection_policy::DuplicateConnectionPolicy;
+pub use event_context::EventContext;
+pub use event_handling::EventHandling;
+#[cfg(feature = "metrics")]
+pub use metrics_config::MetricsConfig;
 pub use naia_server::NaiaServer;
+pub use outgoing_queue::OutgoingQueue;
+pub use pacing_config::PacingConfig;
+pub use packet_pacer::PacingStats;
+pub use reliable_barriers::BarrierId;
+pub use reliable_buffer_diagnostics::ReliableBufferDiagnostics;
 pub use room::room_key::RoomKey;
+pub use send_mode::SendMode;
 pub use server_config::ServerConfig;
+pub use server_context::ServerContext;
 pub use server_event::ServerEvent;
-pub use user::user_key::UserKey;
+pub use server_event_handler::ServerEventHandler;
+pub use server_receiver::ServerReceiver;
+pub use server_sender::ServerSender;
+pub use socket_config::SocketConfig;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub use tick_harness::TickHarness;
+pub use tick_summary::TickSummary;
+pub use unknown_address_policy::UnknownAddressPolicy;
+pub use user::{user_key::UserKey, User};
+pub use user_ref::UserRef;
@@ -0,0 +1,23 @@
+/// How the Server's stats are exported through the `metrics` facade, with the
+/// "metrics" feature, for any `metrics`-compatible exporter to pick up, such
+/// as one scraped by Prometheus. They're updated once per tick
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricsConfig {
+    /// What the name of every metric starts with, followed by an underscore.
+    /// "naia" by default
+    pub prefix: String,
+    /// Whether each connection's RTT, packet loss & bytes sent & received are
+    /// also exported on their own, labelled with its connection id. Every
+    /// connection adds series which outlive it, so this is false by default,
+    /// exporting only stats aggregated across every connection
+    pub per_connection: bool,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "naia".to_string(),
+            per_connection: false,
+        }
+    }
+}
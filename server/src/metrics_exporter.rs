@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use metrics::{
+    counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit,
+};
+
+use naia_shared::{ActorType, EventType};
+
+use super::{
+    client_connection::ClientConnection, metrics_config::MetricsConfig, tick_summary::TickSummary,
+};
+
+/// Exports the Server's stats through the `metrics` facade, aggregated
+/// across every connection, and for each connection if configured to
+pub struct MetricsExporter {
+    per_connection: bool,
+    connections: String,
+    congested_connections: String,
+    bytes_sent: String,
+    bytes_received: String,
+    packet_loss: String,
+    rtt: String,
+    tick_duration: String,
+    connection_rtt: String,
+    connection_packet_loss: String,
+    connection_bytes_sent: String,
+    connection_bytes_received: String,
+    connection_protocol_memory: String,
+    described: bool,
+    // the bytes sent & received by each connection as of the last export, so
+    // that the aggregate counters only take what has been sent since
+    last_bytes: HashMap<u64, (u64, u64)>,
+    last_tick: Option<u16>,
+}
+
+impl MetricsExporter {
+    pub fn new(config: &MetricsConfig) -> Self {
+        let name = |suffix: &str| format!("{}_{}", config.prefix, suffix);
+        MetricsExporter {
+            per_connection: config.per_connection,
+            connections: name("connections"),
+            congested_connections: name("congested_connections"),
+            bytes_sent: name("bytes_sent_total"),
+            bytes_received: name("bytes_received_total"),
+            packet_loss: name("packet_loss_ratio"),
+            rtt: name("rtt_seconds"),
+            tick_duration: name("tick_duration_seconds"),
+            connection_rtt: name("connection_rtt_seconds"),
+            connection_packet_loss: name("connection_packet_loss_ratio"),
+            connection_bytes_sent: name("connection_bytes_sent_total"),
+            connection_bytes_received: name("connection_bytes_received_total"),
+            connection_protocol_memory: name("connection_protocol_memory_bytes"),
+            described: false,
+            last_bytes: HashMap::new(),
+            last_tick: None,
+        }
+    }
+
+    /// Exports the stats of every connection, given along with its connection
+    /// id, and the time spent sending updates in the given tick, if it hasn't
+    /// been already
+    pub fn export<'a, T: 'a + EventType, U: 'a + ActorType>(
+        &mut self,
+        summary: &TickSummary,
+        connections: impl Iterator<Item = (u64, &'a ClientConnection<T, U>)>,
+    ) {
+        if !self.described {
+            self.describe();
+            self.described = true;
+        }
+
+        if self.last_tick != Some(summary.tick) {
+            self.last_tick = Some(summary.tick);
+            histogram!(self.tick_duration.clone()).record(summary.replication_time.as_secs_f64());
+        }
+
+        let mut last_bytes = HashMap::with_capacity(self.last_bytes.len());
+        let mut connection_count: u32 = 0;
+        let mut bytes_sent = 0;
+        let mut bytes_received = 0;
+        let mut packet_loss = 0.0;
+        let mut congested_count: u32 = 0;
+        let rtt_histogram = histogram!(self.rtt.clone());
+        for (connection_id, connection) in connections {
+            connection_count += 1;
+            let connection_bytes_sent = connection.get_bytes_sent();
+            let connection_bytes_received = connection.get_bytes_received();
+            let rtt = f64::from(connection.get_rtt()) / 1000.0;
+            let connection_packet_loss = f64::from(connection.get_packet_loss());
+
+            // a counter can't go down, so a connection's bytes are only ever
+            // added to it, and stay counted once it's gone
+            let (last_sent, last_received) = self
+                .last_bytes
+                .get(&connection_id)
+                .cloned()
+                .unwrap_or((0, 0));
+            bytes_sent += connection_bytes_sent.saturating_sub(last_sent);
+            bytes_received += connection_bytes_received.saturating_sub(last_received);
+            last_bytes.insert(
+                connection_id,
+                (connection_bytes_sent, connection_bytes_received),
+            );
+            packet_loss += connection_packet_loss;
+            if connection.is_congested() {
+                congested_count += 1;
+            }
+            rtt_histogram.record(rtt);
+
+            if self.per_connection {
+                let labels = [("connection", connection_id.to_string())];
+                gauge!(self.connection_rtt.clone(), &labels).set(rtt);
+                gauge!(self.connection_packet_loss.clone(), &labels).set(connection_packet_loss);
+                counter!(self.connection_bytes_sent.clone(), &labels)
+                    .absolute(connection_bytes_sent);
+                counter!(self.connection_bytes_received.clone(), &labels)
+                    .absolute(connection_bytes_received);
+                gauge!(self.connection_protocol_memory.clone(), &labels)
+                    .set(connection.get_protocol_memory() as f64);
+            }
+        }
+        self.last_bytes = last_bytes;
+
+        gauge!(self.connections.clone()).set(f64::from(connection_count));
+        gauge!(self.congested_connections.clone()).set(f64::from(congested_count));
+        counter!(self.bytes_sent.clone()).increment(bytes_sent);
+        counter!(self.bytes_received.clone()).increment(bytes_received);
+        if connection_count == 0 {
+            gauge!(self.packet_loss.clone()).set(0.0);
+        } else {
+            gauge!(self.packet_loss.clone()).set(packet_loss / f64::from(connection_count));
+        }
+    }
+
+    fn describe(&self) {
+        describe_gauge!(self.connections.clone(), "The number of connected Users");
+        describe_gauge!(
+            self.congested_connections.clone(),
+            "The number of connections with a congested outgoing queue"
+        );
+        describe_counter!(
+            self.bytes_sent.clone(),
+            Unit::Bytes,
+            "The total number of bytes sent to every User"
+        );
+        describe_counter!(
+            self.bytes_received.clone(),
+            Unit::Bytes,
+            "The total number of bytes received from every User"
+        );
+        describe_gauge!(
+            self.packet_loss.clone(),
+            "The fraction of packets sent which were lost, averaged across every connection"
+        );
+        describe_histogram!(
+            self.rtt.clone(),
+            Unit::Seconds,
+            "The RTT of every connection, sampled once per tick"
+        );
+        describe_histogram!(
+            self.tick_duration.clone(),
+            Unit::Seconds,
+            "The time spent sending updates in each tick"
+        );
+        if self.per_connection {
+            describe_gauge!(
+                self.connection_rtt.clone(),
+                Unit::Seconds,
+                "The RTT of a connection"
+            );
+            describe_gauge!(
+                self.connection_packet_loss.clone(),
+                "The fraction of packets sent on a connection which were lost"
+            );
+            describe_counter!(
+                self.connection_bytes_sent.clone(),
+                Unit::Bytes,
+                "The total number of bytes sent on a connection"
+            );
+            describe_counter!(
+                self.connection_bytes_received.clone(),
+                Unit::Bytes,
+                "The total number of bytes received on a connection"
+            );
+            describe_gauge!(
+                self.connection_protocol_memory.clone(),
+                Unit::Bytes,
+                "The number of bytes allocated for the protocol buffers of a connection"
+            );
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use naia_shared::{
+    byte_order, utils::duration_between, DeliveryBySize, Instant, PacketReader, SequenceNumber,
+    StandardHeader, MAX_PACKET_SIZE, MTU_SIZE,
+};
+
+/// The size of the packets every connection starts out with, in bytes, which
+/// is small enough to never be fragmented
+pub const MIN_MTU: usize = MTU_SIZE + StandardHeader::bytes_number();
+
+// The packet sizes probed, in bytes, up to the largest packet ever sent, which
+// any peer can decompress a payload of
+const PROBE_SIZES: [usize; 6] = [576, 1024, 1200, 1280, 1400, MAX_PACKET_SIZE];
+
+// The least number of large packets which must have been dropped before the
+// MTU is lowered again
+const MIN_LARGE_DROPPED: usize = 4;
+
+#[derive(Debug)]
+struct SentProbe {
+    index: u16,
+    size: usize,
+    sent_at: Instant,
+    attempts: u8,
+}
+
+/// Discovers the largest packet which reaches the Client, by sending probes
+/// padded to increasing sizes after the connection is established. The MTU is
+/// raised to the size of each probe which is acknowledged, and dropped back
+/// down if large packets start to be lost while small ones still arrive
+#[derive(Debug)]
+pub struct MtuProber {
+    mtu: usize,
+    probe_index: u16,
+    sent_probe: Option<SentProbe>,
+    next_probe_at: Option<Instant>,
+    probe_timeout: Duration,
+    probe_attempts: u8,
+    reprobe_interval: Duration,
+    raised_at_packet: SequenceNumber,
+}
+
+impl MtuProber {
+    /// Create a new MtuProber, which sends its first probe straight away, as of
+    /// the given current time. Each probe is sent up to the given number of
+    /// times, waiting for the given timeout for an acknowledgement each time.
+    /// After a probe fails, or the MTU is lowered, probing starts again after
+    /// the given interval
+    pub fn new(
+        probe_timeout: Duration,
+        probe_attempts: u8,
+        reprobe_interval: Duration,
+        now: &Instant,
+    ) -> Self {
+        MtuProber {
+            mtu: MIN_MTU,
+            probe_index: 0,
+            sent_probe: None,
+            next_probe_at: Some(now.clone()),
+            probe_timeout,
+            probe_attempts: probe_attempts.max(1),
+            reprobe_interval,
+            raised_at_packet: 0,
+        }
+    }
+
+    /// Gets the size of the largest packet known to reach the Client, in bytes
+    pub fn get_mtu(&self) -> usize {
+        return self.mtu;
+    }
+
+    /// Get the payload of the next probe to send, if one is due, given the
+    /// current time. The payload is padded so that, with the StandardHeader,
+    /// the packet is the size being probed
+    pub fn get_probe_payload(&mut self, now: &Instant) -> Option<Box<[u8]>> {
+        if let Some(sent_probe) = &mut self.sent_probe {
+            if duration_between(&sent_probe.sent_at, now) < self.probe_timeout {
+                return None;
+            }
+            if sent_probe.attempts >= self.probe_attempts {
+                // no probe of this size got through, try again later
+                self.sent_probe = None;
+                self.next_probe_at = Some(instant_after(now, self.reprobe_interval));
+                return None;
+            }
+            sent_probe.attempts += 1;
+            sent_probe.sent_at = now.clone();
+            return Some(write_probe(sent_probe.index, sent_probe.size));
+        }
+
+        match &self.next_probe_at {
+            Some(next_probe_at) if next_probe_at <= now => {}
+            _ => return None,
+        }
+
+        let mtu = self.mtu;
+        let size = match PROBE_SIZES.iter().find(|size| **size > mtu) {
+            Some(size) => *size,
+            None => {
+                // already at the largest size
+                self.next_probe_at = None;
+                return None;
+            }
+        };
+
+        let index = self.probe_index;
+        self.probe_index = self.probe_index.wrapping_add(1);
+        self.sent_probe = Some(SentProbe {
+            index,
+            size,
+            sent_at: now.clone(),
+            attempts: 1,
+        });
+        return Some(write_probe(index, size));
+    }
+
+    /// Process the payload of an acknowledgement of a probe, given the current
+    /// time & the index of the next outgoing packet. Raises the MTU if the
+    /// probe arrived at its full size
+    pub fn process_probe_ack(
+        &mut self,
+        ack_payload: &[u8],
+        now: &Instant,
+        next_packet_index: SequenceNumber,
+    ) {
+        let mut reader = PacketReader::new(ack_payload);
+        let (index, received_size) = match (
+            byte_order::try_read_u16(&mut reader),
+            byte_order::try_read_u16(&mut reader),
+        ) {
+            (Some(index), Some(received_size)) => (index, received_size as usize),
+            _ => return,
+        };
+
+        if let Some(sent_probe) = &self.sent_probe {
+            if sent_probe.index == index && sent_probe.size == received_size {
+                self.mtu = sent_probe.size;
+                self.sent_probe = None;
+                self.next_probe_at = Some(now.clone());
+                self.raised_at_packet = next_packet_index;
+            }
+        }
+    }
+
+    /// Gets the packet index from which packets have been sent at the current
+    /// MTU, and so from which loss should be measured
+    pub fn get_raised_at_packet(&self) -> SequenceNumber {
+        return self.raised_at_packet;
+    }
+
+    /// Process the delivery of recently sent Data packets, split by whether
+    /// they are larger than the minimum MTU. If large packets are being lost
+    /// while small ones still arrive, the MTU is dropped back to the minimum
+    /// straight away, and probed again later
+    pub fn process_delivery(&mut self, delivery: &DeliveryBySize, now: &Instant) {
+        if self.mtu <= MIN_MTU || delivery.large_dropped < MIN_LARGE_DROPPED {
+            return;
+        }
+
+        let large_loss = delivery.large_dropped as f32 / delivery.large_count as f32;
+        let small_loss = match delivery.small_count {
+            0 => 0.0,
+            count => delivery.small_dropped as f32 / count as f32,
+        };
+        if large_loss >= 0.5 && small_loss < large_loss / 2.0 {
+            self.mtu = MIN_MTU;
+            self.sent_probe = None;
+            self.next_probe_at = Some(instant_after(now, self.reprobe_interval));
+        }
+    }
+}
+
+fn write_probe(index: u16, size: usize) -> Box<[u8]> {
+    let mut out_bytes = Vec::<u8>::new();
+    byte_order::write_u16(&mut out_bytes, index); // write probe index
+    out_bytes.resize(size - StandardHeader::bytes_number(), 0); // pad to the probed size
+    out_bytes.into_boxed_slice()
+}
+
+fn instant_after(now: &Instant, duration: Duration) -> Instant {
+    let mut instant = now.clone();
+    instant.add_millis(duration.as_millis() as u32);
+    return instant;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{byte_order, Clock, DeliveryBySize, Instant, StandardHeader, SystemClock};
+
+    use super::{MtuProber, MIN_MTU};
+
+    // a link which drops every packet larger than the given size, and acks
+    // every probe which gets through, as the Client would
+    fn run_probes(mtu_prober: &mut MtuProber, link_mtu: usize, now: &mut Instant) {
+        for _ in 0..100 {
+            if let Some(probe_payload) = mtu_prober.get_probe_payload(now) {
+                let packet_size = probe_payload.len() + StandardHeader::bytes_number();
+                if packet_size <= link_mtu {
+                    let index = byte_order::get_u16(&probe_payload);
+                    let mut ack_payload = Vec::new();
+                    byte_order::write_u16(&mut ack_payload, index);
+                    byte_order::write_u16(&mut ack_payload, packet_size as u16);
+                    mtu_prober.process_probe_ack(&ack_payload, now, 0);
+                }
+            }
+            now.add_millis(100);
+        }
+    }
+
+    fn new_prober() -> MtuProber {
+        return MtuProber::new(
+            Duration::from_millis(500),
+            3,
+            Duration::from_secs(30),
+            &SystemClock.now(),
+        );
+    }
+
+    #[test]
+    fn settles_on_largest_size_through_link() {
+        let mut mtu_prober = new_prober();
+        let mut now = SystemClock.now();
+        assert_eq!(mtu_prober.get_mtu(), MIN_MTU);
+
+        run_probes(&mut mtu_prober, 1300, &mut now);
+        assert_eq!(mtu_prober.get_mtu(), 1280);
+
+        // nothing more is probed until the reprobe interval has passed
+        assert!(mtu_prober.get_probe_payload(&now).is_none());
+    }
+
+    #[test]
+    fn stays_at_minimum_behind_small_link() {
+        let mut mtu_prober = new_prober();
+        let mut now = SystemClock.now();
+
+        run_probes(&mut mtu_prober, 540, &mut now);
+        assert_eq!(mtu_prober.get_mtu(), MIN_MTU);
+    }
+
+    #[test]
+    fn reaches_largest_size_on_open_link() {
+        let mut mtu_prober = new_prober();
+        let mut now = SystemClock.now();
+
+        run_probes(&mut mtu_prober, usize::MAX, &mut now);
+        assert_eq!(mtu_prober.get_mtu(), 1472);
+    }
+
+    #[test]
+    fn drops_down_when_large_packets_are_lost() {
+        let mut mtu_prober = new_prober();
+        let mut now = SystemClock.now();
+        run_probes(&mut mtu_prober, 1300, &mut now);
+        assert_eq!(mtu_prober.get_mtu(), 1280);
+
+        // some loss across all sizes is tolerated
+        mtu_prober.process_delivery(
+            &DeliveryBySize {
+                small_count: 10,
+                small_dropped: 4,
+                large_count: 10,
+                large_dropped: 5,
+            },
+            &now,
+        );
+        assert_eq!(mtu_prober.get_mtu(), 1280);
+
+        // large packets stop arriving, small ones still do
+        mtu_prober.process_delivery(
+            &DeliveryBySize {
+                small_count: 10,
+                small_dropped: 0,
+                large_count: 6,
+                large_dropped: 6,
+            },
+            &now,
+        );
+        assert_eq!(mtu_prober.get_mtu(), MIN_MTU);
+
+        // probing resumes after the reprobe interval
+        assert!(mtu_prober.get_probe_payload(&now).is_none());
+        now.add_millis(30_000);
+        run_probes(&mut mtu_prober, 1100, &mut now);
+        assert_eq!(mtu_prober.get_mtu(), 1024);
+    }
+}
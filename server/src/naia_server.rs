@@ -1,99 +1,288 @@
 use std::{
+    any::TypeId,
     cell::RefCell,
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     net::SocketAddr,
     panic,
     rc::Rc,
+    sync::Arc,
+    time::Duration,
 };
 
-use byteorder::{BigEndian, WriteBytesExt};
 use futures_util::{pin_mut, select, FutureExt, StreamExt};
 use log::info;
 use ring::{hmac, rand};
-use slotmap::DenseSlotMap;
+use slotmap::{DenseSlotMap, SecondaryMap};
 
-use naia_server_socket::{
-    MessageSender, NaiaServerSocketError, Packet, ServerSocket, ServerSocketTrait,
-};
+#[cfg(feature = "use-webrtc")]
+use naia_server_socket::ServerSocket;
+use naia_server_socket::{NaiaServerSocketError, Packet, ServerSocketTrait};
 pub use naia_shared::{
-    wrapping_diff, Actor, ActorMutator, ActorType, Connection, ConnectionConfig, Event, EventType,
-    HostTickManager, Instant, ManagerType, Manifest, PacketReader, PacketType, SharedConfig, Timer,
-    Timestamp,
+    byte_order, wrapping_diff, Actor, ActorMutator, ActorType, ChannelIndex, Connection,
+    ConnectionConfig, DespawnReason, Event, EventBuilder, EventGroup, EventPacketWriter, EventType,
+    HostTickManager, Instant, ManagerType, Manifest, MessageReceipt, NaiaError, PacketReader,
+    PacketType, PredictionId, RawEvent, RejectReason, SharedConfig, SubTick, Timer, Timestamp,
 };
 
+use super::socket_config::SharedLinkCondition;
+#[cfg(feature = "use-webrtc")]
+use super::socket_config::SocketConfig;
+#[cfg(feature = "use-udp")]
+use super::udp_server_socket::UdpServerSocket;
 use super::{
+    actor_event_delivery::ActorEventDelivery,
     actors::{
-        actor_key::actor_key::ActorKey, mut_handler::MutHandler,
+        actor_history::{ActorHistory, HistoryView},
+        actor_key::{actor_key::ActorKey, get_actor_ref_key},
+        actor_sync_state::ActorSyncState,
+        mut_handler::MutHandler,
         server_actor_mutator::ServerActorMutator,
+        update_rate::UpdateRate,
     },
     client_connection::ClientConnection,
-    error::NaiaServerError,
+    command_validation::CommandValidation,
+    command_validator::CommandValidator,
+    congestion_policy::CongestionPolicy,
+    debug_command::{DebugCommand, DebugResponse},
+    duplicate_connection_policy::DuplicateConnectionPolicy,
+    event_context::EventContext,
+    event_handling::EventHandling,
+    event_middleware::EventMiddleware,
     interval::Interval,
+    late_event_types::LateEventTypes,
+    outgoing_queue::OutgoingQueue,
+    packet_pacer::{PacingStats, PacketPacer},
+    packet_sender::PacketSender,
+    query_responder::QueryResponder,
+    rate_limiter::RateLimiter,
+    reliable_barriers::{BarrierId, ReliableBarriers},
+    reliable_buffer_diagnostics::ReliableBufferDiagnostics,
     room::{room_key::RoomKey, Room},
+    send_mode::SendMode,
     server_config::ServerConfig,
+    server_context::ServerContext,
     server_event::ServerEvent,
+    server_event_handler::ServerEventHandler,
+    server_receiver::ServerReceiver,
+    server_sender::{ServerSender, SplitEventQueue},
     server_tick_manager::ServerTickManager,
-    user::{user_key::UserKey, User},
+    session_store::SessionStore,
+    tick_summary::TickSummary,
+    unknown_address_policy::UnknownAddressPolicy,
+    user::{get_connection_id_user_key, user_key::UserKey, User},
+    user_ref::UserRef,
+};
+#[cfg(feature = "metrics")]
+use super::{metrics_exporter::MetricsExporter, user::get_user_connection_id};
+use naia_shared::{
+    handshake::{self, ConnectRequest},
+    sequence_less_than,
+    utils::duration_between,
+    Clock, HookAction, ManifestError, PacketHook, SchemaVersions, StandardHeader, StateMask,
+    SystemClock, MTU_SIZE, TICK_PAYLOAD_MAX_OVERHEAD,
 };
-use naia_shared::StandardHeader;
+
+const SHUTDOWN_RESEND_INTERVAL: Duration = Duration::from_millis(50);
+const SHUTDOWN_NOTIFY_COUNT: u8 = 3;
+// the reason given to a Client which is disconnected for overflowing one of its
+// reliable Event buffers
+const RELIABLE_BUFFER_OVERFLOW_REASON: &str = "ReliableBufferOverflow";
+// the reason given to Clients kicked for sending too many malformed Events
+const MALFORMED_FRAME_LIMIT_REASON: &str = "MalformedFrameLimit";
 
 /// A server that uses either UDP or WebRTC communication to send/receive events
 /// to/from connected clients, and syncs registered actors to clients to whom
 /// those actors are in-scope
 pub struct NaiaServer<T: EventType, U: ActorType> {
+    server_config: ServerConfig,
     connection_config: ConnectionConfig,
     manifest: Manifest<T, U>,
     socket: Box<dyn ServerSocketTrait>,
-    sender: MessageSender,
+    sender: PacketSender,
+    incoming_hook: PacketHook,
+    local_address: SocketAddr,
     global_actor_store: DenseSlotMap<ActorKey, U>,
     scope_actor_func: Option<Rc<Box<dyn Fn(&RoomKey, &UserKey, &ActorKey, U) -> bool>>>,
     auth_func: Option<Rc<Box<dyn Fn(&UserKey, &T) -> bool>>>,
     mut_handler: Rc<RefCell<MutHandler>>,
     users: DenseSlotMap<UserKey, User>,
     rooms: DenseSlotMap<RoomKey, Room>,
+    // packets are matched to their User by address once, after which the
+    // connection is found at the slot of the UserKey, which a key held past
+    // its User's disconnection doesn't match, even once the slot is reused
     address_to_user_key_map: HashMap<SocketAddr, UserKey>,
-    client_connections: HashMap<UserKey, ClientConnection<T, U>>,
+    client_connections: SecondaryMap<UserKey, ClientConnection<T, U>>,
     outstanding_disconnects: VecDeque<UserKey>,
+    outstanding_events: VecDeque<ServerEvent<T>>,
+    kick_queue: RefCell<VecDeque<(UserKey, String)>>,
+    reliable_buffer_overflows: VecDeque<(UserKey, ReliableBufferDiagnostics)>,
+    reliable_barriers: ReliableBarriers,
+    event_middleware: EventMiddleware<T>,
+    command_validator: CommandValidator<T, U>,
+    rate_limiter: RateLimiter,
+    not_connected_limiter: RateLimiter,
+    query_responder: QueryResponder,
+    unknown_address_packet_count: u64,
+    released_early_data_count: u64,
+    discarded_early_data_count: u64,
     heartbeat_timer: Timer,
     connection_hash_key: hmac::Key,
+    session_store: Option<SessionStore>,
     tick_manager: ServerTickManager,
     tick_timer: Interval,
+    tick_summary: TickSummary,
+    pacer: PacketPacer,
+    // the payloads set for ticks which have yet to pass
+    tick_payloads: HashMap<u16, Box<dyn Event<T>>>,
+    late_event_types: LateEventTypes,
+    #[cfg(feature = "metrics")]
+    metrics_exporter: MetricsExporter,
+    actor_history: ActorHistory<U>,
+    last_event_receipt: Option<MessageReceipt>,
+    last_command_sub_tick: Option<SubTick>,
+    event_handler: Option<Box<dyn ServerEventHandler<T, U>>>,
+    // set while the event handler is being called
+    handling_event: bool,
+    split_events: SplitEventQueue<T>,
+    // the network conditions simulated on incoming packets, if they can be
+    // changed while the socket is in use
+    #[cfg(feature = "link-conditioner")]
+    link_condition: Option<SharedLinkCondition>,
+    // DebugCommands which take effect at the next tick
+    deferred_debug_commands: VecDeque<DebugCommand>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T: EventType, U: ActorType> NaiaServer<T, U> {
     /// Create a new Server, given an address to listen at, an Event/Actor
-    /// manifest, and an optional Config
+    /// manifest, and an optional Config. Either an IPv4 or IPv6 address can
+    /// be given; listening at the unspecified IPv6 address (`[::]`) accepts
+    /// Clients of both families, unless `SocketConfig::ipv6_only` is set.
+    /// Panics if the socket can't be bound, or the ServerConfig is invalid,
+    /// see `try_new()`
     pub async fn new(
         address: SocketAddr,
         manifest: Manifest<T, U>,
         server_config: Option<ServerConfig>,
         shared_config: SharedConfig,
     ) -> Self {
+        return Self::try_new(address, manifest, server_config, shared_config)
+            .await
+            .expect("the Server could not be started");
+    }
+
+    /// Create a new Server, as `new()` does, but returning an Error if the
+    /// socket can't be bound, or any of the options in `ServerConfig::socket`
+    /// can't be applied to it, or if any soft congestion limit isn't below its
+    /// hard limit
+    pub async fn try_new(
+        address: SocketAddr,
+        manifest: Manifest<T, U>,
+        server_config: Option<ServerConfig>,
+        shared_config: SharedConfig,
+    ) -> Result<Self, NaiaError> {
         let server_config = match server_config {
             Some(config) => config,
             None => ServerConfig::default(),
         };
+        let congestion_limits = [
+            (
+                "event_congestion_limits",
+                &server_config.event_congestion_limits,
+            ),
+            (
+                "event_congestion_byte_limits",
+                &server_config.event_congestion_byte_limits,
+            ),
+            (
+                "actor_congestion_limits",
+                &server_config.actor_congestion_limits,
+            ),
+        ];
+        for (name, limits) in congestion_limits.iter() {
+            if !limits.is_valid() {
+                return Err(NaiaError::InvalidConfig(format!(
+                    "the soft limit of {} isn't below its hard limit",
+                    name
+                )));
+            }
+        }
 
-        let connection_config = ConnectionConfig::new(
+        let mut connection_config = ConnectionConfig::new(
             server_config.disconnection_timeout_duration,
             server_config.heartbeat_interval,
             server_config.ping_interval,
             server_config.rtt_sample_size,
+            shared_config.ordered_channel_count,
+            shared_config.tick_interval,
+            server_config.packet_padding,
+            server_config.cover_heartbeat_interval,
+        );
+        connection_config.catch_decode_panics = server_config.catch_decode_panics;
+        connection_config.malformed_frame_limit = server_config.malformed_frame_limit;
+        connection_config.clock = server_config.clock.clone();
+        let clock = server_config.clock.clone();
+        let now = clock.now();
+
+        let (mut server_socket, local_address, link_condition) =
+            bind_socket(address, &server_config, &shared_config).await?;
+        #[cfg(not(feature = "link-conditioner"))]
+        let _ = link_condition;
+
+        let sender = PacketSender::new(server_socket.get_sender());
+        let pacer = PacketPacer::new(server_config.pacing.clone(), shared_config.tick_interval);
+        let clients_map = SecondaryMap::new();
+        // connections are checked for heartbeats to send often enough for
+        // cover heartbeats to be sent on time
+        let heartbeat_timer = Timer::new(
+            match connection_config.cover_heartbeat_interval {
+                Some(cover_interval) => cover_interval.min(connection_config.heartbeat_interval),
+                None => connection_config.heartbeat_interval,
+            },
+            &now,
         );
 
-        let mut server_socket = ServerSocket::listen(address).await;
-        if let Some(config) = &shared_config.link_condition_config {
-            server_socket = server_socket.with_link_conditioner(config);
-        }
+        let tick_manager = ServerTickManager::new(
+            shared_config.tick_interval,
+            server_config.max_catch_up_ticks,
+            now.clone(),
+        );
 
-        let sender = server_socket.get_sender();
-        let clients_map = HashMap::new();
-        let heartbeat_timer = Timer::new(connection_config.heartbeat_interval);
+        let rate_limiter = RateLimiter::new(
+            server_config.pre_connection_packets_per_second,
+            server_config.pre_connection_packet_burst,
+            server_config.pre_connection_total_packets_per_second,
+            server_config.pre_connection_tracked_addresses,
+            &now,
+        );
+        let not_connected_limiter = RateLimiter::new(
+            server_config.not_connected_replies_per_second,
+            1,
+            server_config.pre_connection_total_packets_per_second,
+            server_config.pre_connection_tracked_addresses,
+            &now,
+        );
+        let query_responder = QueryResponder::new(
+            server_config.max_query_bytes,
+            server_config.max_query_reply_bytes,
+        );
 
         let connection_hash_key =
             hmac::Key::generate(hmac::HMAC_SHA256, &rand::SystemRandom::new()).unwrap();
 
-        NaiaServer {
+        let session_store = server_config
+            .session_resumption_grace
+            .map(SessionStore::new);
+
+        let actor_history = ActorHistory::new(
+            server_config.history_ticks,
+            server_config.history_max_actors,
+        );
+        #[cfg(feature = "metrics")]
+        let metrics_exporter = MetricsExporter::new(&server_config.metrics);
+
+        Ok(NaiaServer {
+            server_config,
             manifest,
             global_actor_store: DenseSlotMap::with_key(),
             scope_actor_func: None,
@@ -101,31 +290,167 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
             mut_handler: MutHandler::new(),
             socket: server_socket,
             sender,
+            incoming_hook: PacketHook::default(),
+            local_address,
             connection_config,
             users: DenseSlotMap::with_key(),
             rooms: DenseSlotMap::with_key(),
             connection_hash_key,
+            session_store,
             client_connections: clients_map,
             address_to_user_key_map: HashMap::new(),
             outstanding_disconnects: VecDeque::new(),
+            outstanding_events: VecDeque::new(),
+            kick_queue: RefCell::new(VecDeque::new()),
+            reliable_buffer_overflows: VecDeque::new(),
+            reliable_barriers: ReliableBarriers::new(),
+            event_middleware: EventMiddleware::new(),
+            command_validator: CommandValidator::new(),
+            rate_limiter,
+            not_connected_limiter,
+            query_responder,
+            unknown_address_packet_count: 0,
+            released_early_data_count: 0,
+            discarded_early_data_count: 0,
             heartbeat_timer,
-            tick_manager: ServerTickManager::new(shared_config.tick_interval),
+            tick_manager,
             tick_timer: Interval::new(shared_config.tick_interval),
-        }
+            tick_summary: TickSummary::default(),
+            pacer,
+            tick_payloads: HashMap::new(),
+            late_event_types: LateEventTypes::new(),
+            #[cfg(feature = "metrics")]
+            metrics_exporter,
+            actor_history,
+            last_event_receipt: None,
+            last_command_sub_tick: None,
+            event_handler: None,
+            handling_event: false,
+            split_events: Rc::new(RefCell::new(VecDeque::new())),
+            #[cfg(feature = "link-conditioner")]
+            link_condition,
+            deferred_debug_commands: VecDeque::new(),
+            clock,
+        })
+    }
+
+    /// Gets the address the Server's socket is bound to. When bound to port 0,
+    /// this has the port the OS picked, for Clients to connect to
+    pub fn local_addr(&self) -> SocketAddr {
+        return self.local_address;
     }
 
     /// Must be called regularly, maintains connection to and receives messages
-    /// from all Clients
-    pub async fn receive(&mut self) -> Result<ServerEvent<T>, NaiaServerError> {
+    /// from all Clients. Events are returned in order, so for each connection
+    /// the Connection event comes before its first Event, and the
+    /// Disconnection event after its last. Returns a WrongEventMode Error if
+    /// an event handler has been set with `set_event_handler()`, as events
+    /// are then only handed to it, by `process()`
+    pub async fn receive(&mut self) -> Result<ServerEvent<T>, NaiaError> {
+        if self.event_handler.is_some() || self.handling_event {
+            return Err(NaiaError::WrongEventMode);
+        }
+        return self.next_event().await;
+    }
+
+    /// Sets the handler to be called with every event, in place of them being
+    /// returned from `receive()`, for the Server to be driven by `process()`
+    /// instead. Replaces any handler set before
+    pub fn set_event_handler(&mut self, handler: impl ServerEventHandler<T, U> + 'static) {
+        self.event_handler = Some(Box::new(handler));
+    }
+
+    /// Must be called regularly once an event handler has been set with
+    /// `set_event_handler()`, in place of `receive()`. Maintains connection to
+    /// all Clients, calling the handler with each event as it is received, in
+    /// the same order `receive()` would return them, until the next Tick.
+    /// Updates are sent with `send_all_updates()` right after the handler's
+    /// `on_tick()` returns, and then this returns. Returns a WrongEventMode
+    /// Error if no event handler has been set
+    pub async fn process(&mut self) -> Result<(), NaiaError> {
+        loop {
+            let mut handler = match self.event_handler.take() {
+                Some(handler) => handler,
+                None => return Err(NaiaError::WrongEventMode),
+            };
+            let result = self.next_event().await;
+            let is_tick = match result {
+                Ok(ServerEvent::Tick) => true,
+                _ => false,
+            };
+
+            self.handling_event = true;
+            let mut context = ServerContext::new(self);
+            match result {
+                Ok(ServerEvent::Connection(user_key, connection_info)) => {
+                    handler.on_connect(&mut context, user_key, connection_info);
+                }
+                Ok(ServerEvent::Disconnection(user_key, user)) => {
+                    handler.on_disconnect(&mut context, user_key, user);
+                }
+                Ok(ServerEvent::Event(user_key, event)) => {
+                    handler.on_message(&mut context, user_key, event);
+                }
+                Ok(ServerEvent::Tick) => {
+                    handler.on_tick(&mut context);
+                }
+                Ok(event) => {
+                    handler.on_other(&mut context, event);
+                }
+                Err(error) => {
+                    handler.on_error(&mut context, error);
+                }
+            }
+            self.handling_event = false;
+            // the handler may have set another in its place
+            if self.event_handler.is_none() {
+                self.event_handler = Some(handler);
+            }
+
+            if is_tick {
+                self.send_all_updates().await;
+                return Ok(());
+            }
+        }
+    }
+
+    // maintains connection to all Clients, until the next event
+    async fn next_event(&mut self) -> Result<ServerEvent<T>, NaiaError> {
+        // queue the Events sent through ServerSenders
+        self.queue_split_events()?;
+
         loop {
+            self.release_paced_packets().await;
+
             // heartbeats
-            if self.heartbeat_timer.ringing() {
-                self.heartbeat_timer.reset();
+            let now = self.clock.now();
+            if self.heartbeat_timer.ringing(&now) {
+                self.heartbeat_timer.reset(&now);
 
                 for (user_key, connection) in self.client_connections.iter_mut() {
-                    if let Some(user) = self.users.get(*user_key) {
-                        if connection.should_drop() {
-                            self.outstanding_disconnects.push_back(*user_key);
+                    if let Some(user) = self.users.get(user_key) {
+                        if is_suspended(&self.session_store, &user_key) {
+                            continue;
+                        }
+                        if connection.should_drop(&now) {
+                            match &mut self.session_store {
+                                Some(session_store) if session_store.has_token(&user_key) => {
+                                    // keep the connection around, for the Client to resume
+                                    session_store.suspend(&user_key, &now);
+                                    self.outstanding_events
+                                        .push_back(ServerEvent::ConnectionSuspended(user_key));
+                                }
+                                _ => {
+                                    self.outstanding_disconnects.push_back(user_key);
+                                }
+                            }
+                        } else if self.server_config.congestion_policy
+                            == CongestionPolicy::DropConnection
+                            && (connection.is_over_hard_limit(OutgoingQueue::Events)
+                                || connection.is_over_hard_limit(OutgoingQueue::Actors))
+                        {
+                            warn!("dropping congested connection: {}", user.address);
+                            self.outstanding_disconnects.push_back(user_key);
                         } else {
                             if connection.should_send_heartbeat() {
                                 // Don't try to refactor this to self.internal_send, doesn't seem to
@@ -147,18 +472,183 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                 }
             }
 
+            // pings
+            for (user_key, connection) in self.client_connections.iter_mut() {
+                if let Some(user) = self.users.get(user_key) {
+                    if is_suspended(&self.session_store, &user_key) {
+                        continue;
+                    }
+                    if connection.should_send_ping() {
+                        let ping_payload = connection.get_ping_payload();
+                        let payload = connection.process_outgoing_header(
+                            self.tick_manager.get_tick(),
+                            connection.get_last_received_tick(),
+                            PacketType::Ping,
+                            &ping_payload,
+                        );
+                        self.sender
+                            .send(Packet::new_raw(user.address, payload))
+                            .await
+                            .expect("send failed!");
+                        connection.mark_sent();
+                    }
+                }
+            }
+
+            // mtu probes
+            for (user_key, connection) in self.client_connections.iter_mut() {
+                if let Some(user) = self.users.get(user_key) {
+                    if is_suspended(&self.session_store, &user_key) {
+                        continue;
+                    }
+                    if let Some(probe_payload) = connection.get_mtu_probe_payload() {
+                        let payload = connection.process_outgoing_header(
+                            self.tick_manager.get_tick(),
+                            connection.get_last_received_tick(),
+                            PacketType::MtuProbe,
+                            &probe_payload,
+                        );
+                        self.sender
+                            .send(Packet::new_raw(user.address, payload))
+                            .await
+                            .expect("send failed!");
+                        connection.mark_sent();
+                    }
+                }
+            }
+
+            // command reports
+            for (user_key, connection) in self.client_connections.iter_mut() {
+                if let Some(user) = self.users.get(user_key) {
+                    if is_suspended(&self.session_store, &user_key) {
+                        continue;
+                    }
+                    if let Some(report_payload) = connection.get_command_report_payload() {
+                        let payload = connection.process_outgoing_header(
+                            self.tick_manager.get_tick(),
+                            connection.get_last_received_tick(),
+                            PacketType::CommandReport,
+                            &report_payload,
+                        );
+                        self.sender
+                            .send(Packet::new_raw(user.address, payload))
+                            .await
+                            .expect("send failed!");
+                        connection.mark_sent();
+                    }
+                }
+            }
+
+            // manifest extensions
+            for (user_key, connection) in self.client_connections.iter_mut() {
+                if let Some(user) = self.users.get(user_key) {
+                    if is_suspended(&self.session_store, &user_key) {
+                        continue;
+                    }
+                    if let Some(extension_payload) =
+                        connection.get_manifest_extension_payload(&self.late_event_types)
+                    {
+                        let payload = connection.process_outgoing_header(
+                            self.tick_manager.get_tick(),
+                            connection.get_last_received_tick(),
+                            PacketType::ManifestExtension,
+                            &extension_payload,
+                        );
+                        self.sender
+                            .send(Packet::new_raw(user.address, payload))
+                            .await
+                            .expect("send failed!");
+                        connection.mark_sent();
+                    }
+                }
+            }
+
+            // suspended connections which weren't resumed within the grace period
+            if let Some(session_store) = &mut self.session_store {
+                for user_key in session_store.take_expired(&self.clock.now()) {
+                    self.outstanding_disconnects.push_back(user_key);
+                }
+            }
+
             // timeouts
             if let Some(user_key) = self.outstanding_disconnects.pop_front() {
-                for (_, room) in self.rooms.iter_mut() {
-                    room.unsubscribe_user(&user_key);
+                if let Some(user) = self.remove_user(&user_key) {
+                    return Ok(ServerEvent::Disconnection(user_key, user));
+                }
+            }
+
+            // kicks
+            let kicked_user = self.kick_queue.borrow_mut().pop_front();
+            if let Some((user_key, reason)) = kicked_user {
+                if let Some(connection) = self.client_connections.get_mut(user_key) {
+                    // let the Client know why it's being disconnected
+                    NaiaServer::<T, U>::send_disconnect_message(
+                        self.tick_manager.get_tick(),
+                        connection,
+                        &mut self.sender,
+                        &reason,
+                    )
+                    .await;
+                }
+                if let Some(user) = self.remove_user(&user_key) {
+                    info!("kicked user at {}: {}", user.address, reason);
+                    return Ok(ServerEvent::Disconnection(user_key, user));
+                }
+            }
+
+            // connections which have overflowed a reliable buffer
+            if let Some((user_key, diagnostics)) = self.reliable_buffer_overflows.pop_front() {
+                if let Some(connection) = self.client_connections.get_mut(user_key) {
+                    NaiaServer::<T, U>::send_disconnect_message(
+                        self.tick_manager.get_tick(),
+                        connection,
+                        &mut self.sender,
+                        RELIABLE_BUFFER_OVERFLOW_REASON,
+                    )
+                    .await;
                 }
+                if let Some(user) = self.remove_user(&user_key) {
+                    warn!(
+                        "dropping connection which overflowed a reliable buffer: {}, {:?}",
+                        user.address, diagnostics
+                    );
+                    self.outstanding_events
+                        .push_front(ServerEvent::Disconnection(user_key, user));
+                    return Ok(ServerEvent::ReliableBufferOverflow(user_key, diagnostics));
+                }
+            }
+
+            // events which could not be returned immediately
+            if let Some(event) = self.outstanding_events.pop_front() {
+                return Ok(event);
+            }
 
-                let address = self.users.get(user_key).unwrap().address;
-                self.address_to_user_key_map.remove(&address);
-                let user_clone = self.users.get(user_key).unwrap().clone();
-                self.users.remove(user_key);
-                self.client_connections.remove(&user_key);
-                return Ok(ServerEvent::Disconnection(user_key, user_clone));
+            // barriers which every connection they cover has reached
+            let client_connections = &self.client_connections;
+            let completed_barrier = self.reliable_barriers.take_completed(
+                |user_key, tail| match client_connections.get(*user_key) {
+                    Some(connection) => connection.is_delivered_up_to(tail),
+                    None => false,
+                },
+                &self.clock.now(),
+            );
+            if let Some((barrier_id, outcome)) = completed_barrier {
+                return Ok(ServerEvent::BarrierCompleted(barrier_id, outcome));
+            }
+
+            // ticks
+            if self.tick_manager.take_tick() {
+                self.apply_deferred_debug_commands();
+                #[cfg(feature = "metrics")]
+                self.metrics_exporter.export(
+                    &self.tick_summary,
+                    self.client_connections
+                        .iter()
+                        .map(|(user_key, connection)| {
+                            return (get_user_connection_id(&user_key), connection);
+                        }),
+                );
+                return Ok(ServerEvent::Tick);
             }
 
             // TODO: have 1 single queue for commands/events from all users, as it's
@@ -166,14 +656,70 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
             // self.client_connections
             for (user_key, connection) in self.client_connections.iter_mut() {
                 //receive commands from anyone
-                if let Some((pawn_key, command)) =
-                    connection.get_incoming_command(self.tick_manager.get_tick())
+                let server_tick = self.tick_manager.get_tick();
+                while let Some((pawn_key, command, sub_tick)) =
+                    connection.get_incoming_command(server_tick)
                 {
-                    return Ok(ServerEvent::Command(*user_key, pawn_key, command));
+                    self.last_command_sub_tick = sub_tick;
+                    if self.command_validator.is_empty() {
+                        return Ok(ServerEvent::Command(user_key, pawn_key, command));
+                    }
+                    let pawn = match self.global_actor_store.get(pawn_key) {
+                        Some(pawn) => pawn,
+                        None => continue,
+                    };
+                    // received Commands were built from the Manifest, so are always registered
+                    let validation = match self.manifest.get_event_naia_id(&command.get_type_id()) {
+                        Ok(naia_id) => self.command_validator.validate(
+                            naia_id,
+                            pawn,
+                            &command,
+                            &user_key,
+                            server_tick,
+                        ),
+                        Err(_) => CommandValidation::Accept,
+                    };
+                    match validation {
+                        CommandValidation::Accept => {
+                            return Ok(ServerEvent::Command(user_key, pawn_key, command));
+                        }
+                        CommandValidation::Clamp(clamped_command) => {
+                            return Ok(ServerEvent::Command(user_key, pawn_key, clamped_command));
+                        }
+                        CommandValidation::Reject { correct } => {
+                            connection.mark_command_rejected();
+                            if correct {
+                                connection.force_pawn_update(&pawn_key);
+                            }
+                        }
+                    }
                 }
                 //receive events from anyone
-                if let Some(event) = connection.get_incoming_event() {
-                    return Ok(ServerEvent::Event(*user_key, event));
+                if let Some((event, receipt)) = connection.get_incoming_event() {
+                    self.last_event_receipt = receipt;
+                    return Ok(ServerEvent::Event(user_key, event));
+                }
+                if let Some(raw_event) = connection.get_incoming_raw_event() {
+                    return Ok(ServerEvent::RawEvent(user_key, raw_event));
+                }
+                if let Some(skipped_frame) = connection.get_skipped_frame() {
+                    if connection.take_malformed_frame_limit_exceeded() {
+                        self.kick_queue
+                            .borrow_mut()
+                            .push_back((user_key, MALFORMED_FRAME_LIMIT_REASON.to_string()));
+                    }
+                    return Ok(ServerEvent::SkippedFrame(user_key, skipped_frame));
+                }
+                //notify of completed world syncs
+                if connection.take_world_sync_complete() {
+                    return Ok(ServerEvent::WorldSyncComplete(user_key));
+                }
+                //notify of congestion changes
+                if let Some((queue, congested)) = connection.update_congestion() {
+                    if congested {
+                        return Ok(ServerEvent::ConnectionCongested(user_key, queue));
+                    }
+                    return Ok(ServerEvent::ConnectionCongestionCleared(user_key, queue));
                 }
             }
 
@@ -181,8 +727,15 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
             enum Next {
                 SocketResult(Result<Packet, NaiaServerSocketError>),
                 Tick,
+                Pacing,
             }
 
+            // wakes up when the next paced packet is due to be sent
+            let pacing_delay = self
+                .pacer
+                .next_release()
+                .map(|release_at| duration_between(&self.clock.now(), release_at));
+
             let next = {
                 let timer_next = self.tick_timer.next().fuse();
                 pin_mut!(timer_next);
@@ -190,6 +743,17 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                 let socket_next = self.socket.receive().fuse();
                 pin_mut!(socket_next);
 
+                let pacing_next = async move {
+                    match pacing_delay {
+                        Some(delay) => {
+                            async_io::Timer::after(delay).await;
+                        }
+                        None => futures_util::future::pending::<()>().await,
+                    }
+                }
+                .fuse();
+                pin_mut!(pacing_next);
+
                 select! {
                     socket_result = socket_next => {
                         Next::SocketResult(socket_result)
@@ -197,6 +761,9 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                     _ = timer_next => {
                         Next::Tick
                     }
+                    _ = pacing_next => {
+                        Next::Pacing
+                    }
                 }
             };
 
@@ -204,14 +771,92 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                 Next::SocketResult(result) => {
                     match result {
                         Ok(packet) => {
+                            let received_at = self.clock.now();
                             let address = packet.address();
+                            if !self.incoming_hook.allows(&address, packet.payload()) {
+                                continue;
+                            }
+                            self.sender
+                                .get_tracer_mut()
+                                .trace_received(&address, packet.payload());
+
+                            // out-of-band queries are answered without a connection, from
+                            // any address
+                            if StandardHeader::read_packet_type(packet.payload())
+                                == PacketType::Query
+                            {
+                                if self.query_responder.has_handler()
+                                    && self.rate_limiter.allow(&address, &self.clock.now())
+                                {
+                                    if let Some(reply) =
+                                        self.query_responder.respond(packet.payload())
+                                    {
+                                        match self
+                                            .sender
+                                            .send(Packet::new_raw(address, reply))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(err) => {
+                                                info!("send error! {}", err);
+                                            }
+                                        }
+                                    }
+                                }
+                                continue;
+                            }
+
                             if let Some(user_key) = self.address_to_user_key_map.get(&address) {
-                                match self.client_connections.get_mut(&user_key) {
+                                match self.client_connections.get_mut(*user_key) {
                                     Some(connection) => {
-                                        connection.mark_heard();
+                                        // a handshake from the address is likely a restarted
+                                        // Client, which mustn't keep the connection it left
+                                        // behind from timing out
+                                        if !matches!(
+                                            StandardHeader::read_packet_type(packet.payload()),
+                                            PacketType::ClientChallengeRequest
+                                                | PacketType::ClientConnectRequest
+                                        ) {
+                                            connection.mark_heard(&received_at);
+                                        }
+                                        connection.mark_bytes_received(packet.payload().len());
+                                        #[cfg(feature = "diagnostics")]
+                                        connection
+                                            .get_histograms_mut()
+                                            .record_arrival(&received_at);
                                     }
                                     None => {} //not yet established connection
                                 }
+                            } else {
+                                if !self.rate_limiter.allow(&address, &self.clock.now()) {
+                                    // drop packets from unconnected addresses which are
+                                    // sending too many, before doing any work on them
+                                    continue;
+                                }
+
+                                // only handshake requests are accepted from addresses with no
+                                // connection
+                                match StandardHeader::read_packet_type(packet.payload()) {
+                                    PacketType::ClientChallengeRequest
+                                    | PacketType::ClientConnectRequest => {}
+                                    _ => {
+                                        self.unknown_address_packet_count += 1;
+                                        if self.server_config.unknown_address_policy
+                                            == UnknownAddressPolicy::NotifyNotConnected
+                                            && self
+                                                .not_connected_limiter
+                                                .allow(&address, &self.clock.now())
+                                        {
+                                            NaiaServer::<T, U>::internal_send_connectionless(
+                                                &mut self.sender,
+                                                PacketType::ServerNotConnected,
+                                                Packet::new(address, Vec::new()),
+                                            )
+                                            .await;
+                                        }
+                                        continue;
+                                    }
+                                }
                             }
 
                             let (header, payload) = StandardHeader::read(packet.payload());
@@ -219,27 +864,27 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                             match header.packet_type() {
                                 PacketType::ClientChallengeRequest => {
                                     let mut reader = PacketReader::new(&payload);
-                                    let timestamp = Timestamp::read(&mut reader);
+                                    let timestamp =
+                                        match handshake::read_challenge_request(&mut reader) {
+                                            Some(timestamp) => timestamp,
+                                            None => continue,
+                                        };
 
                                     let mut timestamp_bytes = Vec::new();
-                                    timestamp.write(&mut timestamp_bytes);
-                                    let timestamp_hash: hmac::Tag =
-                                        hmac::sign(&self.connection_hash_key, &timestamp_bytes);
-
-                                    let mut payload_bytes = Vec::new();
-                                    // write current tick
-                                    payload_bytes
-                                        .write_u16::<BigEndian>(self.tick_manager.get_tick())
-                                        .unwrap();
-
-                                    //write timestamp
-                                    payload_bytes.append(&mut timestamp_bytes);
-
-                                    //write timestamp digest
-                                    let hash_bytes: &[u8] = timestamp_hash.as_ref();
-                                    for hash_byte in hash_bytes {
-                                        payload_bytes.push(*hash_byte);
-                                    }
+                                    byte_order::write_timestamp(&mut timestamp_bytes, &timestamp);
+                                    let timestamp_hash: hmac::Tag = hmac::sign(
+                                        &self.connection_hash_key,
+                                        &NaiaServer::<T, U>::get_challenge_bytes(
+                                            &timestamp_bytes,
+                                            &address,
+                                        ),
+                                    );
+
+                                    let payload_bytes = handshake::write_challenge_response(
+                                        self.tick_manager.get_tick(),
+                                        &timestamp,
+                                        timestamp_hash.as_ref(),
+                                    );
 
                                     NaiaServer::<T, U>::internal_send_connectionless(
                                         &mut self.sender,
@@ -252,106 +897,307 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                                 }
                                 PacketType::ClientConnectRequest => {
                                     let mut reader = PacketReader::new(&payload);
-                                    let timestamp = Timestamp::read(&mut reader);
-
-                                    if let Some(user_key) =
-                                        self.address_to_user_key_map.get(&address)
-                                    {
+                                    let connect_request =
+                                        match handshake::read_connect_request(&mut reader) {
+                                            Some(connect_request) => connect_request,
+                                            None => continue,
+                                        };
+                                    let timestamp = connect_request.timestamp;
+
+                                    // the User already connected from this address, if any
+                                    let existing_user_key: Option<UserKey> =
+                                        self.address_to_user_key_map.get(&address).copied();
+
+                                    if let Some(user_key) = existing_user_key {
                                         if self.client_connections.contains_key(user_key) {
-                                            let user = self.users.get(*user_key).unwrap();
+                                            let user = self.users.get(user_key).unwrap();
                                             if user.timestamp == timestamp {
                                                 let mut connection = self
                                                     .client_connections
                                                     .get_mut(user_key)
                                                     .unwrap();
-                                                connection.process_incoming_header(&header);
                                                 NaiaServer::<T, U>::send_connect_accept_message(
                                                     &mut connection,
+                                                    &self.manifest,
                                                     &mut self.sender,
                                                 )
                                                 .await;
                                                 continue;
-                                            } else {
-                                                self.outstanding_disconnects.push_back(*user_key);
-                                                continue;
                                             }
                                         } else {
                                             error!("if there's a user key associated with the address, should also have a client connection initiated");
                                             continue;
                                         }
-                                    } else {
-                                        //Verify that timestamp hash has been written by this
-                                        // server instance
-                                        let mut timestamp_bytes: Vec<u8> = Vec::new();
-                                        timestamp.write(&mut timestamp_bytes);
-                                        let mut digest_bytes: Vec<u8> = Vec::new();
-                                        for _ in 0..32 {
-                                            digest_bytes.push(reader.read_u8());
-                                        }
-                                        if !hmac::verify(
-                                            &self.connection_hash_key,
+                                    }
+
+                                    //Verify that timestamp hash has been written by this
+                                    // server instance, for this address
+                                    let mut timestamp_bytes: Vec<u8> = Vec::new();
+                                    byte_order::write_timestamp(&mut timestamp_bytes, &timestamp);
+                                    if !hmac::verify(
+                                        &self.connection_hash_key,
+                                        &NaiaServer::<T, U>::get_challenge_bytes(
                                             &timestamp_bytes,
-                                            &digest_bytes,
+                                            &address,
+                                        ),
+                                        &connect_request.timestamp_digest,
+                                    )
+                                    .is_ok()
+                                    {
+                                        continue;
+                                    }
+
+                                    // resume the connection the session token was issued for,
+                                    // if it can still be resumed, otherwise connect afresh
+                                    let session_token = connect_request.session_token;
+                                    // the versions of each type both hosts can read & write,
+                                    // which Events & Actors are sent as on the connection
+                                    let schema_versions =
+                                        match SchemaVersions::read(&self.manifest, &mut reader) {
+                                            Ok(schema_versions) => schema_versions,
+                                            Err(_) => continue,
+                                        };
+                                    let resumed_user_key =
+                                        match (&mut self.session_store, session_token) {
+                                            (Some(session_store), Some(session_token)) => {
+                                                // another User's connection at this address is
+                                                // handled as a duplicate connection instead, and
+                                                // the token is left for its own User to resume with
+                                                match session_store.get_user(&session_token) {
+                                                    Some(user_key)
+                                                        if existing_user_key.is_none()
+                                                            || existing_user_key
+                                                                == Some(user_key) =>
+                                                    {
+                                                        session_store
+                                                            .resume(&session_token, &received_at)
+                                                    }
+                                                    _ => None,
+                                                }
+                                            }
+                                            _ => None,
+                                        };
+                                    if let Some(user_key) = resumed_user_key {
+                                        self.resume_user(
+                                            &user_key,
+                                            address,
+                                            &connect_request,
+                                            schema_versions,
                                         )
-                                        .is_ok()
-                                        {
-                                            continue;
+                                        .await;
+                                        return Ok(ServerEvent::ConnectionResumed(user_key));
+                                    }
+                                    // early data is only released into a resumed connection
+                                    self.discarded_early_data_count +=
+                                        connect_request.early_data.len() as u64;
+
+                                    // the existing User to be replaced by this connection, if any
+                                    let mut replaced_user_key: Option<UserKey> = None;
+
+                                    // a new handshake from an already connected address,
+                                    // usually because the Client has restarted
+                                    if let Some(user_key) = existing_user_key {
+                                        match self.server_config.duplicate_connection_policy {
+                                            DuplicateConnectionPolicy::RejectNew => {
+                                                NaiaServer::<T, U>::internal_send_connectionless(
+                                                    &mut self.sender,
+                                                    PacketType::ServerRejectResponse,
+                                                    Packet::new(
+                                                        address,
+                                                        handshake::write_reject_response(
+                                                            RejectReason::DuplicateConnection,
+                                                        ),
+                                                    ),
+                                                )
+                                                .await;
+                                                continue;
+                                            }
+                                            DuplicateConnectionPolicy::ReplaceExisting => {
+                                                replaced_user_key = Some(user_key);
+                                            }
                                         }
+                                    }
 
-                                        let user = User::new(address, timestamp);
-                                        let user_key = self.users.insert(user);
+                                    // a connection replacing another takes its place, so
+                                    // never fills the Server
+                                    let is_full =
+                                        self.server_config.max_clients.is_some_and(|max_clients| {
+                                            self.client_connections.len() >= max_clients
+                                        });
+                                    if replaced_user_key.is_none() && is_full {
+                                        NaiaServer::<T, U>::internal_send_connectionless(
+                                            &mut self.sender,
+                                            PacketType::ServerRejectResponse,
+                                            Packet::new(
+                                                address,
+                                                handshake::write_reject_response(
+                                                    RejectReason::ServerFull,
+                                                ),
+                                            ),
+                                        )
+                                        .await;
+                                        continue;
+                                    }
 
-                                        // Call auth function if there is one
-                                        if let Some(auth_func) = &self.auth_func {
-                                            let naia_id = reader.read_u16();
+                                    let user = User::new(address, timestamp);
+                                    let user_key = self.users.insert(user);
 
-                                            match self.manifest.create_event(naia_id, &mut reader) {
-                                                Some(new_actor) => {
-                                                    if !(auth_func.as_ref().as_ref())(
-                                                        &user_key, &new_actor,
-                                                    ) {
-                                                        self.users.remove(user_key);
-                                                        continue;
-                                                    }
+                                    // Call auth function if there is one
+                                    let mut accepted_auth_event: Option<T> = None;
+                                    if let Some(auth_func) = &self.auth_func {
+                                        let (naia_id, event_bytes) =
+                                            match handshake::read_auth_event(&mut reader) {
+                                                Some(auth_event) => auth_event,
+                                                None => {
+                                                    self.users.remove(user_key);
+                                                    continue;
                                                 }
-                                                _ => {
+                                            };
+                                        let mut event_reader = PacketReader::new(event_bytes);
+
+                                        match self.manifest.create_event(
+                                            naia_id,
+                                            &mut event_reader,
+                                            &schema_versions,
+                                        ) {
+                                            Some(new_actor) => {
+                                                if !(auth_func.as_ref().as_ref())(
+                                                    &user_key, &new_actor,
+                                                ) {
                                                     self.users.remove(user_key);
                                                     continue;
                                                 }
+                                                accepted_auth_event = Some(new_actor);
+                                            }
+                                            _ => {
+                                                self.users.remove(user_key);
+                                                continue;
                                             }
                                         }
+                                    }
+
+                                    // The handshake is legitimate, tear down the existing
+                                    // connection
+                                    let mut replaced_user: Option<(UserKey, User)> = None;
+                                    if let Some(old_user_key) = replaced_user_key {
+                                        if let Some(old_user) = self.remove_user(&old_user_key) {
+                                            replaced_user = Some((old_user_key, old_user));
+                                        }
+                                    }
 
-                                        self.address_to_user_key_map.insert(address, user_key);
+                                    self.address_to_user_key_map.insert(address, user_key);
 
-                                        // Success! Create new connection
-                                        let mut new_connection = ClientConnection::new(
-                                            address,
-                                            Some(&self.mut_handler),
-                                            &self.connection_config,
-                                        );
-                                        new_connection.process_incoming_header(&header);
-                                        NaiaServer::<T, U>::send_connect_accept_message(
-                                            &mut new_connection,
-                                            &mut self.sender,
-                                        )
-                                        .await;
-                                        self.client_connections.insert(user_key, new_connection);
-                                        return Ok(ServerEvent::Connection(user_key));
+                                    // Success! Create new connection
+                                    let mut new_connection = ClientConnection::new(
+                                        address,
+                                        Some(&self.mut_handler),
+                                        &self.connection_config,
+                                        &self.server_config,
+                                    );
+                                    new_connection.set_compression_enabled(
+                                        connect_request.supports_compression,
+                                    );
+                                    new_connection.set_message_tracing_enabled(
+                                        connect_request.supports_message_tracing,
+                                    );
+                                    new_connection.set_schema_versions(schema_versions);
+                                    new_connection.set_auth_event(accepted_auth_event);
+                                    let session_token = match &mut self.session_store {
+                                        Some(session_store) => Some(session_store.issue(&user_key)),
+                                        None => None,
+                                    };
+                                    new_connection.set_session(session_token, false);
+                                    NaiaServer::<T, U>::send_connect_accept_message(
+                                        &mut new_connection,
+                                        &self.manifest,
+                                        &mut self.sender,
+                                    )
+                                    .await;
+                                    let connection_info = new_connection.get_connection_info();
+                                    self.client_connections.insert(user_key, new_connection);
+
+                                    if let Some((old_user_key, old_user)) = replaced_user {
+                                        self.outstanding_events.push_back(ServerEvent::Connection(
+                                            user_key,
+                                            connection_info,
+                                        ));
+                                        return Ok(ServerEvent::Disconnection(
+                                            old_user_key,
+                                            old_user,
+                                        ));
                                     }
+                                    return Ok(ServerEvent::Connection(user_key, connection_info));
                                 }
                                 PacketType::Data => {
                                     if let Some(user_key) =
                                         self.address_to_user_key_map.get(&address)
                                     {
-                                        match self.client_connections.get_mut(user_key) {
+                                        match self.client_connections.get_mut(*user_key) {
                                             Some(connection) => {
                                                 connection.process_incoming_header(&header);
+                                                let first_new_event =
+                                                    connection.get_incoming_event_count();
                                                 connection.process_incoming_data(
                                                     self.tick_manager.get_tick(),
                                                     header.host_tick(),
+                                                    header.last_received_tick(),
                                                     &self.manifest,
                                                     &payload,
+                                                    &received_at,
                                                 );
+                                                #[cfg(feature = "diagnostics")]
+                                                connection.get_histograms_mut().record_processing(
+                                                    &received_at,
+                                                    &self.clock.now(),
+                                                );
+
+                                                let first_reply =
+                                                    if self.event_middleware.is_empty() {
+                                                        None
+                                                    } else {
+                                                        connection.apply_event_middleware(
+                                                            *user_key,
+                                                            first_new_event,
+                                                            &self.manifest,
+                                                            &self.event_middleware,
+                                                        )
+                                                    };
+                                                if let Some(first_reply) = first_reply {
+                                                    // send the replies alone right away,
+                                                    // unless they're to wait for the next
+                                                    // tick, leaving everything queued
+                                                    // before them for then
+                                                    while self.server_config.send_mode
+                                                        == SendMode::Immediate
+                                                    {
+                                                        let payload = match connection
+                                                            .get_outgoing_reply_packet(
+                                                                self.tick_manager.get_tick(),
+                                                                &self.manifest,
+                                                                first_reply,
+                                                            ) {
+                                                            Some(payload) => payload,
+                                                            None => break,
+                                                        };
+                                                        match self
+                                                            .sender
+                                                            .send(Packet::new_raw(address, payload))
+                                                            .await
+                                                        {
+                                                            Ok(_) => {}
+                                                            Err(err) => {
+                                                                info!("send error! {}", err);
+                                                            }
+                                                        }
+                                                        connection.mark_sent();
+                                                    }
+                                                    if let Some(diagnostics) =
+                                                        connection.check_reliable_buffers()
+                                                    {
+                                                        self.reliable_buffer_overflows
+                                                            .push_back((*user_key, diagnostics));
+                                                    }
+                                                }
                                                 continue;
                                             }
                                             None => {
@@ -363,11 +1209,25 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                                         }
                                     }
                                 }
+                                PacketType::ClientDisconnect => {
+                                    if let Some(user_key) =
+                                        self.address_to_user_key_map.get(&address)
+                                    {
+                                        if self.client_connections.contains_key(*user_key) {
+                                            self.outstanding_disconnects.push_back(*user_key);
+                                            continue;
+                                        }
+                                        warn!(
+                                            "received disconnect from unauthenticated client: {}",
+                                            address
+                                        );
+                                    }
+                                }
                                 PacketType::Heartbeat => {
                                     if let Some(user_key) =
                                         self.address_to_user_key_map.get(&address)
                                     {
-                                        match self.client_connections.get_mut(user_key) {
+                                        match self.client_connections.get_mut(*user_key) {
                                             Some(connection) => {
                                                 // Still need to do this so that proper notify
                                                 // events fire based on the heartbeat header
@@ -383,111 +1243,755 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                                         }
                                     }
                                 }
-                                PacketType::Ping => {
+                                PacketType::Pong => {
                                     if let Some(user_key) =
                                         self.address_to_user_key_map.get(&address)
                                     {
-                                        match self.client_connections.get_mut(user_key) {
+                                        match self.client_connections.get_mut(*user_key) {
                                             Some(connection) => {
                                                 connection.process_incoming_header(&header);
-                                                let ping_payload =
-                                                    connection.process_ping(&payload);
-                                                let payload_with_header = connection
-                                                    .process_outgoing_header(
-                                                        self.tick_manager.get_tick(),
-                                                        connection.get_last_received_tick(),
-                                                        PacketType::Pong,
-                                                        &ping_payload,
-                                                    );
-                                                self.sender
-                                                    .send(Packet::new_raw(
-                                                        connection.get_address(),
-                                                        payload_with_header,
-                                                    ))
-                                                    .await
-                                                    .expect("send failed!");
-                                                connection.mark_sent();
+                                                connection.process_pong(&payload);
                                                 continue;
                                             }
                                             None => {
                                                 warn!(
-                                                    "received ping from unauthenticated client: {}",
+                                                    "received pong from unauthenticated client: {}",
                                                     address
                                                 );
                                             }
                                         }
                                     }
                                 }
-                                _ => {}
-                            }
-                        }
-                        Err(error) => {
-                            //TODO: Determine if disconnecting a user based on a send error is the
-                            // right thing to do
-                            //
-                            // if let
-                            // NaiaServerSocketError::SendError(address) = error {
-                            //                        if let Some(user_key) =
-                            // self.address_to_user_key_map.get(&address).copied() {
-                            //                            self.client_connections.remove(&user_key);
-                            //                            output =
-                            // Some(Ok(ServerEvent::Disconnection(user_key)));
-                            //                            continue;
-                            //                        }
-                            //                    }
-
-                            return Err(NaiaServerError::Wrapped(Box::new(error)));
-                        }
-                    }
-                }
-                Next::Tick => {
-                    self.tick_manager.increment_tick();
-                    return Ok(ServerEvent::Tick);
-                }
-            }
-        }
-    }
-
-    async fn send_connect_accept_message(
-        connection: &mut ClientConnection<T, U>,
-        sender: &mut MessageSender,
-    ) {
-        let payload =
-            connection.process_outgoing_header(0, 0, PacketType::ServerConnectResponse, &[]);
-        match sender
-            .send(Packet::new_raw(connection.get_address(), payload))
-            .await
-        {
-            Ok(_) => {}
-            Err(err) => {
-                info!("send error! {}", err);
+                                PacketType::ResyncRequest => {
+                                    if let Some(user_key) =
+                                        self.address_to_user_key_map.get(&address)
+                                    {
+                                        match self.client_connections.get_mut(*user_key) {
+                                            Some(connection) => {
+                                                connection.process_incoming_header(&header);
+                                                connection.process_resync_request(&payload);
+                                                continue;
+                                            }
+                                            None => {
+                                                warn!(
+                                                    "received resync request from unauthenticated client: {}",
+                                                    address
+                                                );
+                                            }
+                                        }
+                                    }
+                                }
+                                PacketType::ManifestExtensionAck => {
+                                    if let Some(user_key) =
+                                        self.address_to_user_key_map.get(&address)
+                                    {
+                                        match self.client_connections.get_mut(*user_key) {
+                                            Some(connection) => {
+                                                connection.process_incoming_header(&header);
+                                                connection.process_manifest_extension_ack(&payload);
+                                                continue;
+                                            }
+                                            None => {
+                                                warn!(
+                                                    "received manifest extension ack from unauthenticated client: {}",
+                                                    address
+                                                );
+                                            }
+                                        }
+                                    }
+                                }
+                                PacketType::MtuProbeAck => {
+                                    if let Some(user_key) =
+                                        self.address_to_user_key_map.get(&address)
+                                    {
+                                        match self.client_connections.get_mut(*user_key) {
+                                            Some(connection) => {
+                                                connection.process_incoming_header(&header);
+                                                connection.process_mtu_probe_ack(&payload);
+                                                continue;
+                                            }
+                                            None => {
+                                                warn!(
+                                                    "received mtu probe ack from unauthenticated client: {}",
+                                                    address
+                                                );
+                                            }
+                                        }
+                                    }
+                                }
+                                PacketType::Ping => {
+                                    if let Some(user_key) =
+                                        self.address_to_user_key_map.get(&address)
+                                    {
+                                        match self.client_connections.get_mut(*user_key) {
+                                            Some(connection) => {
+                                                connection.process_incoming_header(&header);
+                                                let ping_payload =
+                                                    connection.process_ping(&payload, &received_at);
+                                                let payload_with_header = connection
+                                                    .process_outgoing_header(
+                                                        self.tick_manager.get_tick(),
+                                                        connection.get_last_received_tick(),
+                                                        PacketType::Pong,
+                                                        &ping_payload,
+                                                    );
+                                                self.sender
+                                                    .send(Packet::new_raw(
+                                                        connection.get_address(),
+                                                        payload_with_header,
+                                                    ))
+                                                    .await
+                                                    .expect("send failed!");
+                                                connection.mark_sent();
+                                                continue;
+                                            }
+                                            None => {
+                                                warn!(
+                                                    "received ping from unauthenticated client: {}",
+                                                    address
+                                                );
+                                            }
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        Err(error) => {
+                            //TODO: Determine if disconnecting a user based on a send error is the
+                            // right thing to do
+                            //
+                            // if let
+                            // NaiaServerSocketError::SendError(address) = error {
+                            //                        if let Some(user_key) =
+                            // self.address_to_user_key_map.get(&address).copied() {
+                            //                            self.client_connections.remove(&user_key);
+                            //                            output =
+                            // Some(Ok(ServerEvent::Disconnection(user_key)));
+                            //                            continue;
+                            //                        }
+                            //                    }
+
+                            // the socket's errors can't be sent between
+                            // threads, so only their message is kept
+                            return Err(NaiaError::Transport(error.to_string().into()));
+                        }
+                    }
+                }
+                Next::Tick => {
+                    self.tick_manager.update(self.clock.now());
+                    continue;
+                }
+                Next::Pacing => {
+                    continue;
+                }
+            }
+        }
+    }
+
+    fn remove_user(&mut self, user_key: &UserKey) -> Option<User> {
+        if let Some(user) = self.users.remove(*user_key) {
+            for (_, room) in self.rooms.iter_mut() {
+                room.unsubscribe_user(user_key);
+            }
+            if self.address_to_user_key_map.get(&user.address) == Some(user_key) {
+                self.address_to_user_key_map.remove(&user.address);
+                self.sender
+                    .get_tracer_mut()
+                    .set_enabled(&user.address, false);
+            }
+            self.client_connections.remove(*user_key);
+            self.reliable_barriers.notify_disconnected(user_key);
+            self.pacer.discard(&user.address);
+            if let Some(session_store) = &mut self.session_store {
+                session_store.remove(user_key);
+            }
+            return Some(user);
+        }
+        return None;
+    }
+
+    // Resumes the suspended connection of a User, from the given address,
+    // as asked for by the given ConnectRequest, releasing its early data, and
+    // lets the Client know with a new session token
+    async fn resume_user(
+        &mut self,
+        user_key: &UserKey,
+        address: SocketAddr,
+        connect_request: &ConnectRequest,
+        schema_versions: SchemaVersions,
+    ) {
+        let early_data = &connect_request.early_data;
+        if let Some(user) = self.users.get_mut(*user_key) {
+            if self.address_to_user_key_map.get(&user.address) == Some(user_key) {
+                self.address_to_user_key_map.remove(&user.address);
+            }
+            user.address = address;
+            user.timestamp = connect_request.timestamp;
+        }
+        self.address_to_user_key_map.insert(address, *user_key);
+
+        let session_token = match &mut self.session_store {
+            Some(session_store) => Some(session_store.issue(user_key)),
+            None => None,
+        };
+        if let Some(connection) = self.client_connections.get_mut(*user_key) {
+            connection.resume(address, &self.connection_config);
+            connection.set_compression_enabled(connect_request.supports_compression);
+            connection.set_message_tracing_enabled(connect_request.supports_message_tracing);
+            connection.set_schema_versions(schema_versions);
+            connection.set_session(session_token, true);
+
+            // early data over the limits is discarded whole
+            let early_data_bytes: usize =
+                early_data.iter().map(|packet| packet.payload.len()).sum();
+            let mut released = 0;
+            if early_data.len() <= self.server_config.early_data_max_packets
+                && early_data_bytes <= self.server_config.early_data_max_bytes
+            {
+                released = connection.release_early_data(
+                    self.tick_manager.get_tick(),
+                    &self.manifest,
+                    early_data,
+                );
+            } else {
+                warn!(
+                    "discarding {} packets of early data, {} bytes, over the limits: {}",
+                    early_data.len(),
+                    early_data_bytes,
+                    address
+                );
+            }
+            self.released_early_data_count += released as u64;
+            self.discarded_early_data_count += (early_data.len() - released) as u64;
+
+            NaiaServer::<T, U>::send_connect_accept_message(
+                connection,
+                &self.manifest,
+                &mut self.sender,
+            )
+            .await;
+        }
+    }
+
+    // The challenge digest covers the full address of the Client (including
+    // the IPv6 scope id), so that it can't be replayed from another address
+    fn get_challenge_bytes(timestamp_bytes: &[u8], address: &SocketAddr) -> Vec<u8> {
+        let mut challenge_bytes = timestamp_bytes.to_vec();
+        naia_shared::utils::write_socket_address(address, &mut challenge_bytes);
+        return challenge_bytes;
+    }
+
+    async fn send_disconnect_message(
+        host_tick: u16,
+        connection: &mut ClientConnection<T, U>,
+        sender: &mut PacketSender,
+        reason: &str,
+    ) {
+        let payload_bytes = handshake::write_disconnect(reason);
+
+        let payload = connection.process_outgoing_header(
+            host_tick,
+            connection.get_last_received_tick(),
+            PacketType::ServerDisconnect,
+            &payload_bytes,
+        );
+        match sender
+            .send(Packet::new_raw(connection.get_address(), payload))
+            .await
+        {
+            Ok(_) => {}
+            Err(err) => {
+                info!("send error! {}", err);
+            }
+        }
+    }
+
+    async fn send_connect_accept_message(
+        connection: &mut ClientConnection<T, U>,
+        manifest: &Manifest<T, U>,
+        sender: &mut PacketSender,
+    ) {
+        // let the Client know whether Data packets will be compressed, whether
+        // Events will be traced, whether its previous connection was resumed,
+        // and which versions of each type the Server has
+        let mut schema_versions = Vec::new();
+        manifest.write_schema_versions(&mut schema_versions);
+        let payload_bytes = handshake::write_connect_response(
+            connection.is_compression_enabled(),
+            connection.is_message_tracing_enabled(),
+            connection.is_resumed(),
+            connection.get_session_token(),
+            &schema_versions,
+        );
+        let payload = connection.process_outgoing_header(
+            0,
+            0,
+            PacketType::ServerConnectResponse,
+            &payload_bytes,
+        );
+        match sender
+            .send(Packet::new_raw(connection.get_address(), payload))
+            .await
+        {
+            Ok(_) => {}
+            Err(err) => {
+                info!("send error! {}", err);
             }
         }
         connection.mark_sent();
     }
 
     /// Queues up an Event to be sent to the Client associated with a given
-    /// UserKey
-    pub fn queue_event(&mut self, user_key: &UserKey, event: &impl Event<T>) {
-        if let Some(connection) = self.client_connections.get_mut(user_key) {
-            connection.queue_event(event);
+    /// UserKey. If the Event's type has been registered on an ordered channel
+    /// in the Manifest, it will be sent on that channel. Returns an Error if
+    /// the User isn't connected, if the Event's type isn't registered in the
+    /// Manifest or the Event is too large to fit into a packet, if the User's
+    /// connection is congested and the congestion policy is to reject new
+    /// sends, or if the User's reliable Event buffer has overflowed and the
+    /// User is being disconnected
+    pub fn queue_event(
+        &mut self,
+        user_key: &UserKey,
+        event: &impl Event<T>,
+    ) -> Result<(), NaiaError> {
+        let channel = self.manifest.get_event_channel(&Event::get_type_id(event));
+        return self.internal_queue_event(user_key, event, channel);
+    }
+
+    /// Queues up an Event to be sent to the Client associated with a given
+    /// UserKey, on a specific ordered channel. Events sent on the same channel
+    /// are received in the order they were sent. Returns an Error in the same
+    /// cases as `queue_event()`, or if the channel isn't one of the
+    /// `ordered_channel_count` configured in the SharedConfig
+    pub fn queue_event_on_channel(
+        &mut self,
+        user_key: &UserKey,
+        channel_index: ChannelIndex,
+        event: &impl Event<T>,
+    ) -> Result<(), NaiaError> {
+        return self.internal_queue_event(user_key, event, Some(channel_index));
+    }
+
+    /// Queues up an Event about an Actor to be sent to every Client which has
+    /// the Actor in scope, which receives it as a `ClientEvent::ActorEvent`,
+    /// along with the key it knows the Actor by. Clients the Actor is out of
+    /// scope for, as of the last call to `send_all_updates()`, are skipped, as
+    /// are Clients which have yet to acknowledge creating it, unless the given
+    /// ActorEventDelivery holds the Event for them until they have. Users
+    /// whose connection can't take the Event, for which `queue_event()` would
+    /// return an Error, are skipped too. Returns the number of Users the Event
+    /// was queued or held for, or an Error if the Event's type hasn't been set
+    /// to be actor-scoped in the Manifest, the Event is too large to fit into
+    /// a packet, or its type is registered on a channel which isn't configured
+    pub fn queue_actor_event(
+        &mut self,
+        actor_key: &ActorKey,
+        event: &impl Event<T>,
+        delivery: ActorEventDelivery,
+    ) -> Result<usize, NaiaError> {
+        EventPacketWriter::validate_event(&self.manifest, event)?;
+        let type_id = Event::get_type_id(event);
+        if !self
+            .manifest
+            .is_event_actor_scoped(self.manifest.get_event_naia_id(&type_id)?)
+        {
+            return Err(NaiaError::Manifest(ManifestError::EventNotActorScoped));
+        }
+        let naia_id = self.manifest.get_event_naia_id(&type_id)?;
+        let channel = self.manifest.get_event_channel(&type_id);
+
+        let user_keys: Vec<UserKey> = self.client_connections.keys().collect();
+        let mut recipient_count = 0;
+        for user_key in user_keys {
+            let connection = match self.client_connections.get_mut(user_key) {
+                Some(connection) => connection,
+                None => continue,
+            };
+            if connection.has_event_type(&self.late_event_types, naia_id) != Some(true) {
+                continue;
+            }
+            // Events held for the Actor go first, if it has been created since
+            connection.queue_held_actor_events();
+            match connection.get_actor_sync_state(actor_key) {
+                ActorSyncState::Synced { .. } | ActorSyncState::UpdatePending { .. } => {
+                    let queued = self.queue_on_connection(&user_key, |connection| {
+                        connection.queue_actor_event(actor_key, event, channel)
+                    });
+                    match queued {
+                        Ok(()) => recipient_count += 1,
+                        Err(error @ NaiaError::NoSuchChannel { .. }) => return Err(error),
+                        Err(_) => {}
+                    }
+                }
+                ActorSyncState::SpawnPending => {
+                    if delivery == ActorEventDelivery::HoldUntilSpawned {
+                        connection.hold_actor_event(actor_key, event, channel)?;
+                        recipient_count += 1;
+                    }
+                }
+                ActorSyncState::NotInScope | ActorSyncState::DespawnPending => {}
+            }
         }
+        return Ok(recipient_count);
+    }
+
+    /// Sets the payload of a Server tick, an Event carried by the first packet
+    /// sent to each Client during the tick, sent for the payload alone if
+    /// nothing else is, and by every later packet of the tick with room left
+    /// for it, so that it's likely to arrive even if some are lost. Each
+    /// Client receives it once, as a `ClientEvent::TickPayload`, in tick
+    /// order, along with a `ClientEvent::TickPayloadGap` for the ticks whose
+    /// payloads never arrived. A payload set for a tick which has passed, or
+    /// for which a Client isn't sent updates, is never sent. Returns an Error
+    /// if the Event's type isn't registered in the Manifest, or the Event is
+    /// too large to fit into a packet
+    pub fn set_tick_payload(
+        &mut self,
+        tick: u16,
+        payload: &impl Event<T>,
+    ) -> Result<(), NaiaError> {
+        self.manifest
+            .get_event_naia_id(&Event::get_type_id(payload))?;
+        let mut payload_bytes = Vec::<u8>::new();
+        payload.write(&mut payload_bytes);
+        // the payload must stay under the packet size by a byte
+        let max = MTU_SIZE - TICK_PAYLOAD_MAX_OVERHEAD - 1;
+        if payload_bytes.len() > max {
+            return Err(NaiaError::MessageTooLarge {
+                size: payload_bytes.len(),
+                max,
+            });
+        }
+        self.tick_payloads.insert(tick, payload.clone_box());
+        return Ok(());
+    }
+
+    /// Splits the Server into a ServerReceiver, which receives events & sends
+    /// updates as `receive()` & `send_all_updates()` do, and a ServerSender,
+    /// which queues Events to be sent to Clients, so that Events can be sent
+    /// in reply to each event as it's received, without holding on to the
+    /// events first
+    pub fn split(&mut self) -> (ServerReceiver<'_, T, U>, ServerSender<T>) {
+        let sender = ServerSender::new(self.split_events.clone());
+        return (ServerReceiver::new(self), sender);
+    }
+
+    /// Queues up a group of Events, added to the group by the given closure, to
+    /// be sent to the Client associated with a given UserKey. Every Event in
+    /// the group is written into the same packet, so that they are either all
+    /// lost or all delivered, and the Client receives them one after another,
+    /// in the order they were added. Returns an Error if the Events together
+    /// are too large to fit into a single packet, or in the same cases as
+    /// `queue_event()`
+    pub fn queue_event_group<F: FnOnce(&mut EventGroup<T>)>(
+        &mut self,
+        user_key: &UserKey,
+        build: F,
+    ) -> Result<(), NaiaError> {
+        let mut group = EventGroup::new();
+        build(&mut group);
+        EventPacketWriter::validate_event_group(&self.manifest, group.get_events())?;
+        for event in group.get_events() {
+            let naia_id = self.manifest.get_event_naia_id(&event.get_type_id())?;
+            self.check_peer_has_type(user_key, naia_id)?;
+        }
+        return self.queue_on_connection(user_key, |connection| {
+            connection.queue_event_group(group.get_events());
+            return Ok(());
+        });
+    }
+
+    /// Queues up a RawEvent to be sent to the Client associated with a given
+    /// UserKey, such as one received from another Client. It is written
+    /// exactly as an Event of the type with the RawEvent's NaiaId would be,
+    /// on the channel registered for that type, if any, and is re-transmitted
+    /// until delivered if `guaranteed` is set. Returns an Error in the same
+    /// cases as `queue_event()`
+    pub fn queue_raw_event(
+        &mut self,
+        user_key: &UserKey,
+        raw_event: &RawEvent,
+        guaranteed: bool,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        let type_id = EventPacketWriter::validate_raw_event(&self.manifest, raw_event)?;
+        self.check_peer_has_type(user_key, raw_event.naia_id)?;
+        let channel = self.manifest.get_event_channel(&type_id);
+        return self.queue_on_connection(user_key, |connection| {
+            connection.queue_raw_event(raw_event, type_id, guaranteed, channel)
+        });
+    }
+
+    /// Places a barrier behind every reliable Event queued so far for the
+    /// Client associated with a given UserKey, on every channel, returning
+    /// its id. Once the Client has acknowledged all of them,
+    /// `ServerEvent::BarrierCompleted` is emitted with
+    /// `BarrierOutcome::Delivered`, however many reliable Events have been
+    /// queued since, so that nothing needs to wait for them to stop. It fails
+    /// with `BarrierOutcome::Disconnected` if the connection is closed first,
+    /// or with `BarrierOutcome::TimedOut` if a timeout is given and passes
+    /// first. Events about Actors held until the Client has created them are
+    /// only covered once they have been queued. Returns an Error if the User
+    /// isn't connected
+    pub fn reliable_barrier(
+        &mut self,
+        user_key: &UserKey,
+        timeout: Option<Duration>,
+    ) -> Result<BarrierId, NaiaError> {
+        let connection = match self.client_connections.get(*user_key) {
+            Some(connection) => connection,
+            None => return Err(NaiaError::NotConnected),
+        };
+        let tails = vec![(*user_key, connection.get_reliable_tail())];
+        let timeout_at = self.get_barrier_timeout_at(timeout);
+        return Ok(self.reliable_barriers.place(tails, timeout_at));
+    }
+
+    /// Places a barrier as `reliable_barrier()` does, behind the reliable
+    /// Events queued so far for every connected Client at once, returning its
+    /// id. `ServerEvent::BarrierCompleted` is emitted once every one of those
+    /// Clients has acknowledged them, or has had its connection closed, in
+    /// which case it fails with `BarrierOutcome::Disconnected`, or once a
+    /// timeout given has passed
+    pub fn barrier_all(&mut self, timeout: Option<Duration>) -> BarrierId {
+        let tails = self
+            .client_connections
+            .iter()
+            .map(|(user_key, connection)| (user_key, connection.get_reliable_tail()))
+            .collect();
+        let timeout_at = self.get_barrier_timeout_at(timeout);
+        return self.reliable_barriers.place(tails, timeout_at);
+    }
+
+    // gets the time a barrier placed now with the given timeout times out at
+    fn get_barrier_timeout_at(&self, timeout: Option<Duration>) -> Option<Instant> {
+        return timeout.map(|timeout| {
+            let mut timeout_at = self.clock.now();
+            timeout_at.add_millis(timeout.as_millis() as u32);
+            return timeout_at;
+        });
+    }
+
+    /// Sets Events of the given type to be returned from `receive()` as
+    /// RawEvents, holding the bytes they were written as, rather than being
+    /// read into the EventType. Every Event is written with its size, so they
+    /// are never read at all. RawEvents aren't
+    /// passed through the middleware registered with `on_receive()`, and are
+    /// received in order only with the other RawEvents on their channel.
+    /// Returns an Error if the Event's type has not been registered in the
+    /// Manifest
+    pub fn receive_raw<E: Event<T> + 'static>(&mut self) -> Result<(), NaiaError> {
+        return self.manifest.set_event_receive_raw::<E>();
+    }
+
+    /// Registers an Event type while the Server is running, such as one added
+    /// by a module which has been reloaded, returning the NaiaId it was given.
+    /// Each type is given a NaiaId past those of every type registered before
+    /// it, so that no id a Client has been told of is ever reused. Every
+    /// Client, whether connected already or connecting later, is sent the
+    /// NaiaId & name of the type, and answers whether it has registered a type
+    /// of the same name under the same NaiaId, as a Client whose Manifest
+    /// registers the same types in the same order does. Until a Client has
+    /// answered that it has, sending it an Event of the type returns
+    /// `NaiaError::PeerLacksType`, and it is skipped by `queue_actor_event()`
+    /// & `set_tick_payload()`, so that it's never sent an Event it can't read.
+    /// The type must be described by its builder, as derived types are, for
+    /// any Client to answer that it has it. Panics if every NaiaId an Event
+    /// can be given is already in use
+    pub fn register_event(&mut self, event_builder: Box<dyn EventBuilder<T>>) -> u16 {
+        let type_id = event_builder.get_type_id();
+        let name = event_builder
+            .describe()
+            .map(|description| description.name)
+            .unwrap_or_default();
+        self.manifest.register_event(event_builder);
+        let naia_id = self
+            .manifest
+            .get_event_naia_id(&type_id)
+            .expect("the Event type was just registered");
+        self.late_event_types.add(naia_id, name);
+        return naia_id;
+    }
+
+    /// Gets whether the Client associated with a given UserKey can be sent
+    /// Events of the given type, as it always can those registered in the
+    /// Manifest the Server was started with, and those registered since with
+    /// `register_event()` once it has answered that it has registered them
+    /// too. Returns None if the User isn't connected, or its Client has yet to
+    /// answer for the type, or an Error if the type isn't registered
+    pub fn peer_has_event_type<E: Event<T> + 'static>(
+        &self,
+        user_key: &UserKey,
+    ) -> Result<Option<bool>, NaiaError> {
+        let naia_id = self.manifest.get_event_naia_id(&TypeId::of::<E>())?;
+        match self.client_connections.get(*user_key) {
+            Some(connection) => {
+                return Ok(connection.has_event_type(&self.late_event_types, naia_id));
+            }
+            None => return Ok(None),
+        }
+    }
+
+    fn internal_queue_event(
+        &mut self,
+        user_key: &UserKey,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        EventPacketWriter::validate_event(&self.manifest, event)?;
+        let naia_id = self.manifest.get_event_naia_id(&event.get_type_id())?;
+        self.check_peer_has_type(user_key, naia_id)?;
+        return self.queue_on_connection(user_key, |connection| {
+            connection.queue_event(event, channel)
+        });
+    }
+
+    // Returns an Error if the Client of the given User can't be sent Events of
+    // the type with the given NaiaId, as it hasn't answered that it has
+    // registered the type, which was registered since the Server was started
+    fn check_peer_has_type(&self, user_key: &UserKey, naia_id: u16) -> Result<(), NaiaError> {
+        if let Some(connection) = self.client_connections.get(*user_key) {
+            if connection.has_event_type(&self.late_event_types, naia_id) != Some(true) {
+                return Err(NaiaError::PeerLacksType { naia_id });
+            }
+        }
+        return Ok(());
+    }
+
+    // Queues the Events sent through ServerSenders, in order, stopping at the
+    // first which can't be
+    fn queue_split_events(&mut self) -> Result<(), NaiaError> {
+        loop {
+            let split_event = self.split_events.borrow_mut().pop_front();
+            let (user_key, event, channel) = match split_event {
+                Some(split_event) => split_event,
+                None => return Ok(()),
+            };
+            let channel = channel.or_else(|| {
+                self.manifest
+                    .get_event_channel(&event.as_ref().get_type_id())
+            });
+            self.internal_queue_event(&user_key, event.as_ref(), channel)?;
+        }
+    }
+
+    fn queue_on_connection<F: FnOnce(&mut ClientConnection<T, U>) -> Result<(), NaiaError>>(
+        &mut self,
+        user_key: &UserKey,
+        queue: F,
+    ) -> Result<(), NaiaError> {
+        let connection = match self.client_connections.get_mut(*user_key) {
+            Some(connection) => connection,
+            None => return Err(NaiaError::NotConnected),
+        };
+        if connection.is_reliable_buffer_overflowed() {
+            return Err(NaiaError::ReliableBufferOverflow);
+        }
+        if self.server_config.congestion_policy == CongestionPolicy::RejectSends
+            && connection.is_over_hard_limit(OutgoingQueue::Events)
+        {
+            return Err(NaiaError::ChannelFull);
+        }
+        queue(connection)?;
+        if let Some(diagnostics) = connection.check_reliable_buffers() {
+            self.reliable_buffer_overflows
+                .push_back((*user_key, diagnostics));
+            return Err(NaiaError::ReliableBufferOverflow);
+        }
+        return Ok(());
     }
 
     /// Sends all Actor/Event messages to all Clients. If you don't call this
     /// method, the Server will never communicate with it's connected
-    /// Clients
+    /// Clients. With `SendMode::PerTick`, each Client is only sent packets on
+    /// the first call in each tick, and further calls in the same tick do
+    /// nothing for it
     pub async fn send_all_updates(&mut self) {
+        let start = self.clock.now();
+        // Events sent through ServerSenders which can't be queued are left to
+        // return their Error from `receive()`
+        let _ = self.queue_split_events();
+        let server_tick = self.tick_manager.get_tick();
+        if self.tick_summary.tick != server_tick {
+            self.tick_summary = TickSummary::new(server_tick);
+        }
+
         // update actor scopes
         self.update_actor_scopes();
 
+        // actor-scoped Events are queued once their Actor has been created
+        for connection in self.client_connections.values_mut() {
+            connection.queue_held_actor_events();
+        }
+
+        // static actors which have been mutated need checking for changes
+        let upgraded_actors = self.mut_handler.borrow_mut().take_upgraded_actors();
+        for actor_key in upgraded_actors.iter() {
+            for connection in self.client_connections.values_mut() {
+                connection.make_actor_dynamic(actor_key);
+            }
+        }
+
+        // keep the state of Actors with their history tracked, as it is sent
+        let global_actor_store = &self.global_actor_store;
+        self.actor_history.record(server_tick, |actor_key| {
+            return global_actor_store
+                .get(*actor_key)
+                .map(|actor| actor.inner_ref().as_ref().borrow().get_typed_copy());
+        });
+
+        // payloads set for ticks which have passed are never sent
+        self.tick_payloads
+            .retain(|tick, _| !sequence_less_than(*tick, server_tick));
+        let tick_payload = self
+            .tick_payloads
+            .get(&server_tick)
+            .and_then(|tick_payload| {
+                let type_id = tick_payload.as_ref().get_type_id();
+                let naia_id = self.manifest.get_event_naia_id(&type_id).ok()?;
+                return Some((naia_id, tick_payload.clone()));
+            });
+
         // loop through all connections, send packet
         for (user_key, connection) in self.client_connections.iter_mut() {
-            if let Some(user) = self.users.get(*user_key) {
-                connection.collect_actor_updates();
-                while let Some(payload) =
-                    connection.get_outgoing_packet(self.tick_manager.get_tick(), &self.manifest)
+            if let Some(user) = self.users.get(user_key) {
+                if let Some((naia_id, tick_payload)) = &tick_payload {
+                    // Clients which lack the payload's type go without it
+                    if connection.has_event_type(&self.late_event_types, *naia_id) == Some(true) {
+                        connection.set_tick_payload(
+                            server_tick,
+                            &self.manifest,
+                            tick_payload.as_ref(),
+                        );
+                    }
+                }
+                if !connection
+                    .take_flush(self.server_config.send_mode, self.tick_manager.get_tick())
                 {
+                    continue;
+                }
+                connection.collect_actor_updates(server_tick, &mut self.tick_summary);
+                // changes accumulate until the connection is resumed
+                if is_suspended(&self.session_store, &user_key) {
+                    continue;
+                }
+                self.tick_summary.connections_serviced += 1;
+                let expired_event_count = connection.get_expired_event_count();
+                let mut packets = Vec::new();
+                while let Some((payload, immediate)) = connection.get_outgoing_paced_packet(
+                    server_tick,
+                    &self.manifest,
+                    self.pacer.get_immediate_channels(),
+                ) {
+                    self.tick_summary.packets_sent += 1;
+                    self.tick_summary.bytes_written += payload.len();
+                    packets.push((payload, immediate));
+                    connection.mark_sent();
+                }
+                for payload in self.pacer.pace(user.address, packets, &self.clock.now()) {
                     match self
                         .sender
                         .send(Packet::new_raw(user.address, payload))
@@ -498,7 +2002,196 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                             info!("send error! {}", err);
                         }
                     }
-                    connection.mark_sent();
+                }
+                self.tick_summary.events_expired +=
+                    (connection.get_expired_event_count() - expired_event_count) as usize;
+            }
+        }
+
+        self.tick_summary.replication_time += duration_between(&start, &self.clock.now());
+    }
+
+    /// Gets a summary of the replication work done in the most recent tick in
+    /// which `send_all_updates()` was called, including every call made during
+    /// the tick so far
+    pub fn last_tick_summary(&self) -> &TickSummary {
+        return &self.tick_summary;
+    }
+
+    /// Gets the running totals of the packets sent to Clients with updates,
+    /// in bursts as they were written & as they were sent once paced, as set
+    /// out in `ServerConfig::pacing`
+    pub fn get_pacing_stats(&self) -> &PacingStats {
+        return self.pacer.get_stats();
+    }
+
+    // sends every paced packet which is due
+    async fn release_paced_packets(&mut self) {
+        if self.pacer.next_release().is_none() {
+            return;
+        }
+        for (address, payloads) in self.pacer.take_due(&self.clock.now()) {
+            for payload in payloads {
+                match self.sender.send(Packet::new_raw(address, payload)).await {
+                    Ok(_) => {}
+                    Err(err) => {
+                        info!("send error! {}", err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets an estimate of when the Event most recently returned from
+    /// `receive()` was sent, if its type is set to be timestamped in the
+    /// Manifest
+    pub fn last_event_receipt(&self) -> Option<MessageReceipt> {
+        return self.last_event_receipt;
+    }
+
+    /// Gets the SubTick the Command most recently returned from `receive()`
+    /// was issued at, the fraction of its tick which had passed on the Client,
+    /// if the Manifest is set to send them with
+    /// `Manifest::set_command_sub_ticks()`
+    pub fn last_command_sub_tick(&self) -> Option<SubTick> {
+        return self.last_command_sub_tick;
+    }
+
+    /// Shuts down the Server, notifying every connected Client with the given
+    /// reason. No new connections are accepted once this is called. For the
+    /// first half of the drain duration, queued Events & Actor messages are
+    /// flushed to Clients on a best-effort basis; the shutdown notification is
+    /// then sent a few times over the rest of the window, to tolerate packet
+    /// loss. Returns within the drain duration, even if Clients are
+    /// unresponsive
+    pub async fn shutdown(mut self, reason: &str, drain: Duration) {
+        // the drain waits on the socket in real time, so is always timed by
+        // the SystemClock, whatever Clock the Server runs on
+        let start = SystemClock.now();
+        let flush_deadline = instant_after(&start, drain / 2);
+        let deadline = instant_after(&start, drain);
+
+        // paced packets aren't held back any longer
+        for (address, payloads) in self.pacer.take_all() {
+            for payload in payloads {
+                match self.sender.send(Packet::new_raw(address, payload)).await {
+                    Ok(_) => {}
+                    Err(err) => {
+                        info!("send error! {}", err);
+                    }
+                }
+            }
+        }
+
+        // flush pending messages, until acknowledged or out of time
+        loop {
+            let now = SystemClock.now();
+            if now >= flush_deadline {
+                break;
+            }
+            let has_pending_messages = self
+                .client_connections
+                .values()
+                .any(|connection| connection.has_pending_messages());
+            if !has_pending_messages {
+                break;
+            }
+
+            for (user_key, connection) in self.client_connections.iter_mut() {
+                if let Some(user) = self.users.get(user_key) {
+                    while let Some(payload) =
+                        connection.get_outgoing_packet(self.tick_manager.get_tick(), &self.manifest)
+                    {
+                        match self
+                            .sender
+                            .send(Packet::new_raw(user.address, payload))
+                            .await
+                        {
+                            Ok(_) => {}
+                            Err(err) => {
+                                info!("send error! {}", err);
+                            }
+                        }
+                        connection.mark_sent();
+                    }
+                }
+            }
+
+            self.receive_acks_until(
+                instant_after(&now, SHUTDOWN_RESEND_INTERVAL).min(flush_deadline.clone()),
+            )
+            .await;
+        }
+
+        // notify Clients
+        for _ in 0..SHUTDOWN_NOTIFY_COUNT {
+            let now = SystemClock.now();
+            if now >= deadline {
+                break;
+            }
+            for (_, connection) in self.client_connections.iter_mut() {
+                NaiaServer::<T, U>::send_disconnect_message(
+                    self.tick_manager.get_tick(),
+                    connection,
+                    &mut self.sender,
+                    reason,
+                )
+                .await;
+            }
+            self.receive_acks_until(
+                instant_after(&now, SHUTDOWN_RESEND_INTERVAL).min(deadline.clone()),
+            )
+            .await;
+        }
+
+        info!("server shut down: {}", reason);
+    }
+
+    // Processes incoming packets from connected Clients (only to receive acks)
+    // until the given deadline, ignoring everything else
+    async fn receive_acks_until(&mut self, deadline: Instant) {
+        loop {
+            let now = SystemClock.now();
+            if now >= deadline {
+                return;
+            }
+
+            let next = {
+                let timer_next =
+                    FutureExt::fuse(async_io::Timer::after(duration_between(&now, &deadline)));
+                pin_mut!(timer_next);
+
+                let socket_next = self.socket.receive().fuse();
+                pin_mut!(socket_next);
+
+                select! {
+                    socket_result = socket_next => {
+                        Some(socket_result)
+                    }
+                    _ = timer_next => {
+                        None
+                    }
+                }
+            };
+
+            match next {
+                Some(Ok(packet)) => {
+                    if !self
+                        .incoming_hook
+                        .allows(&packet.address(), packet.payload())
+                    {
+                        continue;
+                    }
+                    if let Some(user_key) = self.address_to_user_key_map.get(&packet.address()) {
+                        if let Some(connection) = self.client_connections.get_mut(*user_key) {
+                            let (header, _) = StandardHeader::read(packet.payload());
+                            connection.process_incoming_header(&header);
+                        }
+                    }
+                }
+                Some(Err(_)) => {}
+                None => {
+                    return;
                 }
             }
         }
@@ -507,8 +2200,12 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
     /// Register an Actor with the Server, whereby the Server will sync the
     /// state of the Actor to all connected Clients for which the Actor is
     /// in scope. Gives back an ActorKey which can be used to get the reference
-    /// to the Actor from the Server once again
-    pub fn register_actor(&mut self, actor: U) -> ActorKey {
+    /// to the Actor from the Server once again. Returns an Error if the
+    /// Actor's type has not been registered in the Manifest
+    pub fn register_actor(&mut self, actor: U) -> Result<ActorKey, NaiaError> {
+        self.manifest
+            .get_actor_naia_id(&actor.inner_ref().as_ref().borrow().get_type_id())?;
+
         let new_mutator_ref: Rc<RefCell<ServerActorMutator>> =
             Rc::new(RefCell::new(ServerActorMutator::new(&self.mut_handler)));
         actor
@@ -522,23 +2219,173 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
             .borrow_mut()
             .set_actor_key(actor_key);
         self.mut_handler.borrow_mut().register_actor(&actor_key);
-        return actor_key;
+        return Ok(actor_key);
+    }
+
+    /// Register an Actor spawned for a User's request, which the User's Client
+    /// has predicted the spawn of with the given PredictionId, usually sent
+    /// along with the request. When the Actor comes into scope for the User,
+    /// its creation is tagged with the PredictionId, so that the Client can
+    /// match it up with its prediction. Returns an Error in the same cases as
+    /// `register_actor()`
+    pub fn register_predicted_actor(
+        &mut self,
+        actor: U,
+        user_key: &UserKey,
+        prediction_id: PredictionId,
+    ) -> Result<ActorKey, NaiaError> {
+        let actor_key = self.register_actor(actor)?;
+        if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
+            user_connection.set_spawn_prediction(&actor_key, prediction_id);
+        }
+        return Ok(actor_key);
+    }
+
+    /// Register an Actor which will not change after it has been registered,
+    /// such as level geometry. Its state is serialized once, sent to each
+    /// Client as the Actor comes into scope, and the Actor is never checked for
+    /// changes afterwards. If a static Actor is mutated anyway, it is
+    /// transparently turned into a regular Actor, and the change is synced.
+    /// Returns an Error if the Actor's type has not been registered in the
+    /// Manifest
+    pub fn register_static_actor(&mut self, actor: U) -> Result<ActorKey, NaiaError> {
+        let mut payload = Vec::new();
+        actor.inner_ref().as_ref().borrow().write(&mut payload);
+        let actor_key = self.register_actor(actor)?;
+        self.mut_handler
+            .borrow_mut()
+            .set_static(&actor_key, payload.into_boxed_slice());
+        return Ok(actor_key);
+    }
+
+    /// Get how far along the Client of the given User is in receiving the
+    /// state of an Actor, such as whether it has been created on the Client
+    /// yet. Changes are acknowledged by the Client after a round trip
+    pub fn get_actor_sync_state(&self, user_key: &UserKey, actor_key: &ActorKey) -> ActorSyncState {
+        match self.client_connections.get(*user_key) {
+            Some(connection) => return connection.get_actor_sync_state(actor_key),
+            None => return ActorSyncState::NotInScope,
+        }
+    }
+
+    /// Sets how often changes to an Actor are sent to Clients, such as every
+    /// few ticks for distant or unimportant Actors. Changes made in between
+    /// accumulate and are sent together, while creation & deletion of the Actor
+    /// are never delayed. Pawns are always updated every tick
+    pub fn set_update_rate(&mut self, actor_key: &ActorKey, update_rate: UpdateRate) {
+        self.mut_handler
+            .borrow_mut()
+            .set_update_rate(actor_key, update_rate);
+    }
+
+    /// Sets how often changes to an Actor are sent to the Client of a given
+    /// User, overriding the rate set with `NaiaServer::set_update_rate()`.
+    /// Passing None goes back to that rate. This is reset if the Actor goes out
+    /// of scope for the User
+    pub fn set_user_update_rate(
+        &mut self,
+        user_key: &UserKey,
+        actor_key: &ActorKey,
+        update_rate: Option<UpdateRate>,
+    ) {
+        if let Some(connection) = self.client_connections.get_mut(*user_key) {
+            connection.set_actor_update_rate(actor_key, update_rate);
+        }
+    }
+
+    /// Sets which Properties of an Actor are sent to the Client of a given
+    /// User, as the bits of a StateMask indexed by the Actor's Property enum,
+    /// which the Actor derive generates as `{Actor}Prop`. Hidden Properties
+    /// are never written toward the Client: they're left out of updates, and
+    /// written with their default values in the Actor's creation & in Pawn
+    /// updates. Properties which become visible have their current values
+    /// sent, while those which become hidden keep the last value sent to the
+    /// Client. Passing None makes every Property visible again. This is kept
+    /// while the Actor goes out of & back into scope for the User
+    pub fn set_property_visibility(
+        &mut self,
+        user_key: &UserKey,
+        actor_key: &ActorKey,
+        visibility: Option<StateMask>,
+    ) {
+        if let Some(connection) = self.client_connections.get_mut(*user_key) {
+            connection.set_property_visibility(actor_key, visibility);
+        }
+    }
+
+    /// Returns whether the Actor was registered as static, and has not been
+    /// mutated since
+    pub fn is_static_actor(&self, key: &ActorKey) -> bool {
+        return self.mut_handler.borrow().is_static(key);
     }
 
     /// Deregisters an Actor with the Server, deleting local copies of the
-    /// Actor on each Client
+    /// Actor on each Client, which are told it has been Destroyed
     pub fn deregister_actor(&mut self, key: ActorKey) {
+        self.deregister_actor_with_reason(key, DespawnReason::Destroyed);
+    }
+
+    /// Deregisters an Actor with the Server, deleting local copies of the
+    /// Actor on each Client, which are given the reason
+    pub fn deregister_actor_with_reason(&mut self, key: ActorKey, reason: DespawnReason) {
         for (user_key, _) in self.users.iter() {
-            if let Some(user_connection) = self.client_connections.get_mut(&user_key) {
+            if let Some(user_connection) = self.client_connections.get_mut(user_key) {
                 user_connection.remove_pawn(&key);
-                user_connection.remove_actor(&key);
+                user_connection.remove_actor(&key, reason);
+                user_connection.set_property_visibility(&key, None);
             }
         }
 
         self.mut_handler.borrow_mut().deregister_actor(&key);
+        self.actor_history.untrack(&key);
         self.global_actor_store.remove(key);
     }
 
+    /// Starts keeping the state of an Actor as it is replicated on each tick,
+    /// for the number of ticks set in the ServerConfig, so that it can be
+    /// looked up with `state_at_tick()` or `rewind_scope()`. Returns false if
+    /// the Actor isn't registered, or the maximum number of Actors with their
+    /// history tracked has been reached
+    pub fn track_history(&mut self, actor_key: &ActorKey) -> bool {
+        if !self.global_actor_store.contains_key(*actor_key) {
+            return false;
+        }
+        return self.actor_history.track(actor_key);
+    }
+
+    /// Stops keeping the state of an Actor on each tick, dropping what has
+    /// been kept of it
+    pub fn untrack_history(&mut self, actor_key: &ActorKey) {
+        self.actor_history.untrack(actor_key);
+    }
+
+    /// Gets the state of an Actor with its history tracked, as it was
+    /// replicated on the given tick, if that tick is still kept
+    pub fn state_at_tick(&self, actor_key: &ActorKey, tick: u16) -> Option<&U> {
+        return self.actor_history.get(actor_key, tick);
+    }
+
+    /// Calls the given function with a view of the Actors with their history
+    /// tracked, as they were on the most recent tick the Client of a given
+    /// User had received when it issued its Commands for the given tick, such
+    /// as the tick a Command was received on. Returns None if no Commands
+    /// for the tick are buffered, and so the tick the Client saw is unknown
+    pub fn rewind_scope<R, F: FnOnce(&HistoryView<'_, U>) -> R>(
+        &self,
+        user_key: &UserKey,
+        tick: u16,
+        function: F,
+    ) -> Option<R> {
+        let perceived_tick = self
+            .client_connections
+            .get(*user_key)?
+            .get_perceived_tick(tick)?;
+        return Some(function(&HistoryView::new(
+            &self.actor_history,
+            perceived_tick,
+        )));
+    }
+
     /// Given an ActorKey, get a reference to a registered Actor being tracked
     /// by the Server
     pub fn get_actor(&mut self, key: ActorKey) -> Option<&U> {
@@ -596,10 +2443,22 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
         }
     }
 
-    /// Remove an Actor from a Room, given the appropriate RoomKey & ActorKey
+    /// Remove an Actor from a Room, given the appropriate RoomKey & ActorKey.
+    /// Users in the Room are told the Actor has gone OutOfScope
     pub fn room_remove_actor(&mut self, room_key: &RoomKey, actor_key: &ActorKey) {
+        self.room_remove_actor_with_reason(room_key, actor_key, DespawnReason::OutOfScope);
+    }
+
+    /// Remove an Actor from a Room, given the appropriate RoomKey & ActorKey,
+    /// and the reason Users in the Room are given for the Actor leaving
+    pub fn room_remove_actor_with_reason(
+        &mut self,
+        room_key: &RoomKey,
+        actor_key: &ActorKey,
+        reason: DespawnReason,
+    ) {
         if let Some(room) = self.rooms.get_mut(*room_key) {
-            room.remove_actor(actor_key);
+            room.remove_actor_with_reason(actor_key, reason);
         }
     }
 
@@ -644,6 +2503,79 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
         self.auth_func = Some(auth_func);
     }
 
+    /// Registers a closure which answers out-of-band queries, such as those
+    /// sent by server browsers with `NaiaClient::query()`. The closure is given
+    /// the bytes of the query, and returns the bytes to reply with, if any.
+    /// Queries don't need a connection, or take up a connection slot, and are
+    /// rate limited along with every other packet from an address without a
+    /// connection. Queries & replies larger than the limits in the
+    /// ServerConfig are dropped
+    pub fn on_query<F: 'static + Fn(&[u8]) -> Option<Vec<u8>>>(&mut self, query_func: F) {
+        self.query_responder.set_handler(Box::new(query_func));
+    }
+
+    /// Registers a hook which is shown every packet sent, with the address it
+    /// is sent to, and decides whether it goes out. The hook sees the packet
+    /// as it is on the wire, after padding & compression, and can't change
+    /// it. A dropped packet is lost just as if the network had dropped it, so
+    /// anything reliable in it is re-sent
+    pub fn set_outgoing_hook<F: 'static + FnMut(&SocketAddr, &[u8]) -> HookAction>(
+        &mut self,
+        hook: F,
+    ) {
+        self.sender.set_hook(Box::new(hook));
+    }
+
+    /// Registers a hook which is shown every packet received, with the address
+    /// it came from, before the Server reads anything from it, and decides
+    /// whether it is let through. The hook sees the packet as it is on the
+    /// wire, and can't change it. A dropped packet is ignored entirely, just
+    /// as if the network had dropped it
+    pub fn set_incoming_hook<F: 'static + FnMut(&SocketAddr, &[u8]) -> HookAction>(
+        &mut self,
+        hook: F,
+    ) {
+        self.incoming_hook.set(Box::new(hook));
+    }
+
+    /// Register a middleware function for Events of the given type, which is
+    /// called as soon as such an Event is received, before it would be
+    /// returned from `receive()`. The function can reply to the User straight
+    /// away through the EventContext, and returns whether the Event has been
+    /// handled, in which case it goes no further. Functions registered for the
+    /// same type are called in the order they were registered. Returns an
+    /// Error if the Event's type has not been registered in the Manifest
+    pub fn on_receive<E: Event<T> + 'static, F>(&mut self, handler: F) -> Result<(), NaiaError>
+    where
+        F: Fn(&mut EventContext<T>, &T) -> EventHandling + 'static,
+    {
+        let naia_id = self.manifest.get_event_naia_id(&TypeId::of::<E>())?;
+        self.event_middleware.register(naia_id, Box::new(handler));
+        return Ok(());
+    }
+
+    /// Register the validation function for Commands of the given type, which
+    /// is called before each such Command would be returned from `receive()`.
+    /// The function is given the current state of the Pawn the Command was
+    /// issued to, the Command, the User who sent it, & the current tick, and
+    /// decides whether the Command is accepted as it is, replaced with a
+    /// corrected Command, or rejected. Rejections are counted in
+    /// `UserRef::rejected_commands()`. Registering a function for a type which
+    /// already has one replaces it. Returns an Error if the Command's type has
+    /// not been registered in the Manifest
+    pub fn on_validate_command<E: Event<T> + 'static, F>(
+        &mut self,
+        validator: F,
+    ) -> Result<(), NaiaError>
+    where
+        F: Fn(&U, &T, &UserKey, u16) -> CommandValidation<T> + 'static,
+    {
+        let naia_id = self.manifest.get_event_naia_id(&TypeId::of::<E>())?;
+        self.command_validator
+            .register(naia_id, Box::new(validator));
+        return Ok(());
+    }
+
     /// Iterate through all currently connected Users
     pub fn users_iter(&self) -> slotmap::dense::Iter<UserKey, User> {
         return self.users.iter();
@@ -659,19 +2591,236 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
         return self.users.len();
     }
 
+    /// Iterate through all currently connected Users, along with the metadata
+    /// of their connections
+    pub fn users(&self) -> impl Iterator<Item = UserRef<'_, T, U>> {
+        return self
+            .users
+            .iter()
+            .filter_map(move |(user_key, _)| self.get_user_ref(&user_key));
+    }
+
+    /// Get a User along with the metadata of its connection, given the
+    /// associated UserKey
+    pub fn get_user_ref(&self, user_key: &UserKey) -> Option<UserRef<'_, T, U>> {
+        let user = self.users.get(*user_key)?;
+        let connection = self.client_connections.get(*user_key)?;
+        return Some(UserRef::new(*user_key, user, connection, &self.kick_queue));
+    }
+
+    /// Get a User along with the metadata of its connection, given the
+    /// connection id of the User
+    pub fn get_user_ref_by_connection_id(&self, connection_id: u64) -> Option<UserRef<'_, T, U>> {
+        return self.get_user_ref(&get_connection_id_user_key(connection_id));
+    }
+
+    /// Attaches data of any type to a User, such as an identity established
+    /// during authentication, replacing any data attached before. The data is
+    /// dropped along with the User, after it has been handed out with the
+    /// Disconnection event, so that it can still be read then
+    pub fn set_user_data<D: 'static>(&mut self, user_key: &UserKey, user_data: D) {
+        if let Some(user) = self.users.get_mut(*user_key) {
+            user.set_data(user_data);
+        }
+    }
+
+    /// Gets the data attached to a User with `set_user_data()`, if it is of
+    /// the given type
+    pub fn user_data<D: 'static>(&self, user_key: &UserKey) -> Option<&D> {
+        return self.users.get(*user_key)?.data::<D>();
+    }
+
+    /// Gets the data attached to a User with `set_user_data()` mutably, if it
+    /// is of the given type
+    pub fn user_data_mut<D: 'static>(&mut self, user_key: &UserKey) -> Option<&mut D> {
+        return self.users.get_mut(*user_key)?.data_mut::<D>();
+    }
+
+    /// Sends a ping to the Client of a given User right away, rather than
+    /// waiting for the ping interval to elapse, so that a fresh Round Trip
+    /// Time sample is taken
+    pub fn ping_user(&mut self, user_key: &UserKey) {
+        if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
+            user_connection.force_ping();
+        }
+    }
+
     /// Gets the last received tick from the Client
     pub fn get_client_tick(&self, user_key: &UserKey) -> Option<u16> {
-        if let Some(user_connection) = self.client_connections.get(user_key) {
+        if let Some(user_connection) = self.client_connections.get(*user_key) {
             return Some(user_connection.get_last_received_tick());
         }
         return None;
     }
 
+    /// Gets the number of packets from addresses without an established
+    /// connection which have been dropped by the rate limiter
+    pub fn get_rate_limited_packet_count(&self) -> u64 {
+        return self.rate_limiter.get_dropped_count();
+    }
+
+    /// Get the number of packets received from addresses which had no
+    /// connection, which were not handshake requests
+    pub fn get_unknown_address_packet_count(&self) -> u64 {
+        return self.unknown_address_packet_count;
+    }
+
+    /// Gets the number of packets of early data which have been released into
+    /// the connections they were sent along with the handshake to resume
+    pub fn get_released_early_data_count(&self) -> u64 {
+        return self.released_early_data_count;
+    }
+
+    /// Gets the number of packets of early data which have been discarded,
+    /// because the connection they were sent to resume couldn't be, they were
+    /// over the limits in the ServerConfig, or they were for ticks Commands had
+    /// already been received for
+    pub fn get_discarded_early_data_count(&self) -> u64 {
+        return self.discarded_early_data_count;
+    }
+
     /// Gets the current tick of the Server
     pub fn get_server_tick(&self) -> u16 {
         self.tick_manager.get_tick()
     }
 
+    /// Carries out a DebugCommand, to look into or adjust the Server while it
+    /// runs, returning its output as text. Commands which change how packets
+    /// are received take effect at the next tick, so as not to change them
+    /// part of the way through one, and are answered with
+    /// `DebugResponse::Scheduled`. Commands about a connection fail if no
+    /// Client is connected from the given address
+    pub fn debug_command(&mut self, command: DebugCommand) -> DebugResponse {
+        match command {
+            DebugCommand::ListConnections => {
+                let mut lines = Vec::new();
+                for (_, connection) in self.client_connections.iter() {
+                    let address = connection.get_address();
+                    let traced = if self.sender.get_tracer_mut().is_enabled(&address) {
+                        ", traced"
+                    } else {
+                        ""
+                    };
+                    lines.push(format!(
+                        "{}: rtt {:.1}ms, loss {:.1}%{}",
+                        address,
+                        connection.get_rtt(),
+                        connection.get_packet_loss() * 100.0,
+                        traced
+                    ));
+                }
+                return DebugResponse::Done(lines.join("\n"));
+            }
+            DebugCommand::SetPacketTracing(address, enabled) => {
+                if self.get_connection_at(&address).is_none() {
+                    return DebugResponse::Failed(format!(
+                        "no Client is connected from {}",
+                        address
+                    ));
+                }
+                self.sender.get_tracer_mut().set_enabled(&address, enabled);
+                let state = if enabled { "enabled" } else { "disabled" };
+                return DebugResponse::Done(format!("packet tracing {} for {}", state, address));
+            }
+            DebugCommand::DumpPacketTrace(address) => {
+                match self.sender.get_tracer_mut().take_lines(&address) {
+                    Some(lines) => return DebugResponse::Done(lines.join("\n")),
+                    None => {
+                        return DebugResponse::Failed(format!(
+                            "packets to & from {} aren't traced",
+                            address
+                        ));
+                    }
+                }
+            }
+            DebugCommand::DumpManifest => {
+                return DebugResponse::Done(self.manifest.export_schema().to_json());
+            }
+            DebugCommand::DumpQueueDepths(address) => {
+                let connection = match self.get_connection_at(&address) {
+                    Some(connection) => connection,
+                    None => {
+                        return DebugResponse::Failed(format!(
+                            "no Client is connected from {}",
+                            address
+                        ));
+                    }
+                };
+                let mut lines = vec![
+                    format!(
+                        "outgoing events: {}",
+                        connection.get_outgoing_count(OutgoingQueue::Events)
+                    ),
+                    format!(
+                        "outgoing actor messages: {}",
+                        connection.get_outgoing_count(OutgoingQueue::Actors)
+                    ),
+                    format!("incoming events: {}", connection.get_incoming_event_count()),
+                    format!(
+                        "local actor keys held: {}",
+                        connection.get_held_local_key_count()
+                    ),
+                ];
+                let channels = (0..connection.get_ordered_channel_count()).map(Some);
+                for channel in std::iter::once(None).chain(channels) {
+                    let (unacked_messages, unacked_bytes) = connection.get_reliable_usage(channel);
+                    let name = match channel {
+                        Some(channel_index) => format!("channel {}", channel_index),
+                        None => "unordered".to_string(),
+                    };
+                    lines.push(format!(
+                        "{} unacknowledged: {} ({} bytes)",
+                        name, unacked_messages, unacked_bytes
+                    ));
+                }
+                return DebugResponse::Done(lines.join("\n"));
+            }
+            #[cfg(feature = "link-conditioner")]
+            DebugCommand::SetLinkConditioner(config) => {
+                if self.link_condition.is_none() {
+                    return DebugResponse::Failed(
+                        "the link conditioner can only be changed on the UDP transport".to_string(),
+                    );
+                }
+                let response = match &config {
+                    Some(config) => format!(
+                        "the link conditioner will be set to {}ms latency, {}ms jitter & {:.1}% \
+                         loss at the next tick",
+                        config.incoming_latency,
+                        config.incoming_jitter,
+                        config.incoming_loss * 100.0
+                    ),
+                    None => "the link conditioner will be cleared at the next tick".to_string(),
+                };
+                self.deferred_debug_commands
+                    .push_back(DebugCommand::SetLinkConditioner(config));
+                return DebugResponse::Scheduled(response);
+            }
+        }
+    }
+
+    // gets the connection of the Client at the given address, if it's connected
+    fn get_connection_at(&self, address: &SocketAddr) -> Option<&ClientConnection<T, U>> {
+        let user_key = self.address_to_user_key_map.get(address)?;
+        return self.client_connections.get(*user_key);
+    }
+
+    // carries out the DebugCommands which were to take effect at this tick
+    fn apply_deferred_debug_commands(&mut self) {
+        while let Some(command) = self.deferred_debug_commands.pop_front() {
+            #[cfg(feature = "link-conditioner")]
+            {
+                if let DebugCommand::SetLinkConditioner(config) = command {
+                    if let Some(link_condition) = &self.link_condition {
+                        *link_condition.lock().unwrap() = config;
+                    }
+                }
+            }
+            #[cfg(not(feature = "link-conditioner"))]
+            let _ = command;
+        }
+    }
+
     /// Assigns an Actor to a specific User, making it a Pawn for that User
     /// (meaning that the User will be able to issue Commands to that Pawn)
     pub fn assign_pawn(&mut self, user_key: &UserKey, actor_key: &ActorKey) {
@@ -682,7 +2831,7 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                           property to be allowed to become a Pawn. In order to do this, add the attribute: '#[predict]'\n\
                           before you define an Actor's property, like so: '#[predict] pub my_u16: Property<u16>'\n");
             }
-            if let Some(user_connection) = self.client_connections.get_mut(user_key) {
+            if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
                 user_connection.add_pawn(actor_key);
             }
         }
@@ -692,27 +2841,62 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
     /// unable to issue Commands to that Pawn)
     pub fn unassign_pawn(&mut self, user_key: &UserKey, actor_key: &ActorKey) {
         if self.global_actor_store.contains_key(*actor_key) {
-            if let Some(user_connection) = self.client_connections.get_mut(user_key) {
+            if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
                 user_connection.remove_pawn(actor_key);
             }
         }
     }
 
+    /// Pauses the replication of Actors to a specific User. While paused, no
+    /// Actor creation/deletion/update messages are sent to the User, but
+    /// Events & heartbeats still are. Changes made while paused are
+    /// accumulated, and only the net result is sent when replication is
+    /// resumed
+    pub fn pause_replication(&mut self, user_key: &UserKey) {
+        if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
+            user_connection.pause_replication();
+        }
+    }
+
+    /// Resumes the replication of Actors to a specific User, after a call to
+    /// `pause_replication()`
+    pub fn resume_replication(&mut self, user_key: &UserKey) {
+        if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
+            user_connection.resume_replication();
+        }
+    }
+
+    /// Returns whether the replication of Actors to a specific User is
+    /// currently paused
+    pub fn is_replication_paused(&self, user_key: &UserKey) -> bool {
+        if let Some(user_connection) = self.client_connections.get(*user_key) {
+            return user_connection.is_replication_paused();
+        }
+        return false;
+    }
+
     fn update_actor_scopes(&mut self) {
         for (room_key, room) in self.rooms.iter_mut() {
-            while let Some((removed_user, removed_actor)) = room.pop_removal_queue() {
-                if let Some(user_connection) = self.client_connections.get_mut(&removed_user) {
-                    user_connection.remove_actor(&removed_actor);
+            while let Some((removed_user, removed_actor, reason)) = room.pop_removal_queue() {
+                if let Some(user_connection) = self.client_connections.get_mut(removed_user) {
+                    user_connection.remove_actor(&removed_actor, reason);
                 }
             }
 
             if let Some(scope_func) = &self.scope_actor_func {
                 for user_key in room.users_iter() {
-                    for actor_key in room.actors_iter() {
-                        if let Some(actor) = self.global_actor_store.get(*actor_key) {
-                            if let Some(user_connection) = self.client_connections.get_mut(user_key)
-                            {
-                                let currently_in_scope = user_connection.has_actor(actor_key);
+                    if let Some(user_connection) = self.client_connections.get_mut(*user_key) {
+                        // scope is re-evaluated once replication is resumed
+                        if user_connection.is_replication_paused() {
+                            continue;
+                        }
+                        user_connection.begin_world_sync();
+
+                        let mut scope_results: Vec<(ActorKey, bool)> = Vec::new();
+                        let mut referenced_actors: HashSet<ActorKey> = HashSet::new();
+
+                        for actor_key in room.actors_iter() {
+                            if let Some(actor) = self.global_actor_store.get(*actor_key) {
                                 let should_be_in_scope = user_connection.has_pawn(actor_key)
                                     || (scope_func.as_ref().as_ref())(
                                         &room_key,
@@ -720,21 +2904,52 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
                                         actor_key,
                                         (*actor).clone(),
                                     );
-                                if should_be_in_scope {
-                                    if !currently_in_scope {
-                                        // add actor to the connections local scope
-                                        if let Some(actor) = self.global_actor_store.get(*actor_key)
-                                        {
-                                            user_connection
-                                                .add_actor(actor_key, &actor.inner_ref());
+                                if should_be_in_scope && self.server_config.scope_actor_refs {
+                                    for actor_ref in
+                                        actor.inner_ref().as_ref().borrow().get_actor_refs()
+                                    {
+                                        if let Some(ref_key) = get_actor_ref_key(&actor_ref) {
+                                            if self.global_actor_store.contains_key(ref_key) {
+                                                referenced_actors.insert(ref_key);
+                                            }
                                         }
                                     }
-                                } else {
-                                    if currently_in_scope {
-                                        // remove actor from the connections local scope
-                                        user_connection.remove_actor(actor_key);
+                                }
+                                scope_results.push((*actor_key, should_be_in_scope));
+                            }
+                        }
+
+                        // referenced actors are kept in scope, even if the scope function
+                        // would otherwise exclude them
+                        for (actor_key, should_be_in_scope) in scope_results.iter_mut() {
+                            if referenced_actors.remove(actor_key) {
+                                *should_be_in_scope = true;
+                            }
+                        }
+                        for actor_key in referenced_actors.drain() {
+                            scope_results.push((actor_key, true));
+                        }
+
+                        // no new Actors are brought into scope for a congested connection
+                        let reject_new_actors = self.server_config.congestion_policy
+                            == CongestionPolicy::RejectSends
+                            && user_connection.is_over_hard_limit(OutgoingQueue::Actors);
+
+                        for (actor_key, should_be_in_scope) in scope_results.iter() {
+                            let currently_in_scope = user_connection.has_actor(actor_key);
+                            if *should_be_in_scope {
+                                if !currently_in_scope && !reject_new_actors {
+                                    // add actor to the connections local scope
+                                    if let Some(actor) = self.global_actor_store.get(*actor_key) {
+                                        user_connection.add_actor(actor_key, &actor.inner_ref());
                                     }
                                 }
+                            } else {
+                                if currently_in_scope {
+                                    // remove actor from the connections local scope
+                                    user_connection
+                                        .remove_actor(actor_key, DespawnReason::OutOfScope);
+                                }
                             }
                         }
                     }
@@ -744,7 +2959,7 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
     }
 
     async fn internal_send_connectionless(
-        sender: &mut MessageSender,
+        sender: &mut PacketSender,
         packet_type: PacketType,
         packet: Packet,
     ) {
@@ -757,6 +2972,100 @@ impl<T: EventType, U: ActorType> NaiaServer<T, U> {
     }
 }
 
+// Whether a User's connection has timed out, and is waiting to be resumed
+fn is_suspended(session_store: &Option<SessionStore>, user_key: &UserKey) -> bool {
+    match session_store {
+        Some(session_store) => return session_store.is_suspended(user_key),
+        None => return false,
+    }
+}
+
+// The moment the given Duration after an Instant, to the millisecond
+fn instant_after(instant: &Instant, duration: Duration) -> Instant {
+    let mut later = instant.clone();
+    later.add_millis(duration.as_millis().min(u128::from(u32::MAX)) as u32);
+    return later;
+}
+
 fn to_actor_mutator(eref: &Rc<RefCell<ServerActorMutator>>) -> Rc<RefCell<dyn ActorMutator>> {
     eref.clone()
 }
+
+// a socket bound for the Server, along with the address it's bound to, and the
+// network conditions simulated on it, if they can be changed while it's in use
+type BoundSocket = (
+    Box<dyn ServerSocketTrait>,
+    SocketAddr,
+    Option<SharedLinkCondition>,
+);
+
+// Binds the UDP socket to the address in the SocketConfig if there is one, or
+// else the one the Server was created with, returning it along with the
+// address it was bound to. With the "link-conditioner" feature, it's wrapped in
+// a link conditioner which can be changed while it's in use
+#[cfg(feature = "use-udp")]
+async fn bind_socket(
+    address: SocketAddr,
+    server_config: &ServerConfig,
+    shared_config: &SharedConfig,
+) -> Result<BoundSocket, NaiaError> {
+    let bind_address = server_config.socket.bind_address.unwrap_or(address);
+    let dscp = match server_config.dscp {
+        Some(dscp) if bind_address.is_ipv6() => {
+            warn!(
+                "DSCP marking ({}) isn't supported on IPv6 sockets, packets will be sent unmarked",
+                dscp
+            );
+            None
+        }
+        dscp => dscp,
+    };
+    let socket = UdpServerSocket::bind(bind_address, &server_config.socket, dscp)
+        .map_err(|error| NaiaError::Transport(Box::new(error)))?;
+    let local_address = socket
+        .local_addr()
+        .map_err(|error| NaiaError::Transport(Box::new(error)))?;
+    #[cfg(feature = "link-conditioner")]
+    {
+        let (socket, link_condition) =
+            socket.with_live_link_conditioner(shared_config.link_condition_config.clone());
+        return Ok((socket, local_address, Some(link_condition)));
+    }
+    #[cfg(not(feature = "link-conditioner"))]
+    {
+        let _ = shared_config;
+        return Ok((Box::new(socket), local_address, None));
+    }
+}
+
+// The WebRTC socket binds itself, so none of the options in the SocketConfig
+// can be applied to it, nor can its link conditioner be changed once set
+#[cfg(feature = "use-webrtc")]
+async fn bind_socket(
+    address: SocketAddr,
+    server_config: &ServerConfig,
+    shared_config: &SharedConfig,
+) -> Result<BoundSocket, NaiaError> {
+    if server_config.socket != SocketConfig::default() {
+        return Err(NaiaError::Transport(
+            "socket options can only be applied to the UDP transport".into(),
+        ));
+    }
+    if let Some(dscp) = server_config.dscp {
+        warn!(
+            "DSCP marking ({}) isn't supported by the WebRTC socket, packets will be sent \
+             unmarked",
+            dscp
+        );
+    }
+    let mut socket = ServerSocket::listen(address).await;
+    #[cfg(feature = "link-conditioner")]
+    {
+        if let Some(config) = &shared_config.link_condition_config {
+            socket = socket.with_link_conditioner(config);
+        }
+    }
+    #[cfg(not(feature = "link-conditioner"))]
+    let _ = shared_config;
+    return Ok((socket, address, None));
+}
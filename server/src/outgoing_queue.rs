@@ -0,0 +1,11 @@
+/// One of the outgoing queues of a connection to a Client, which is monitored
+/// for congestion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutgoingQueue {
+    /// Events queued up to be sent to the Client, including those on ordered
+    /// channels
+    Events,
+    /// Actor creation/deletion/update messages queued up to be sent to the
+    /// Client
+    Actors,
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use naia_shared::ChannelIndex;
+
+/// How the packets sent to each Client in a tick are paced, spread across the
+/// tick interval rather than sent back to back, so that a Client with a lot to
+/// be sent doesn't swamp the queues of the routers along the way
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacingConfig {
+    /// The least time between the packets sent to a Client, which the tick
+    /// interval is divided into, rounded down to whole milliseconds. Packets
+    /// are bunched up to fit, if there are more than the tick interval has
+    /// room for. 1ms by default
+    pub granularity: Duration,
+    /// The ordered channels whose Events are sent as soon as they're written
+    /// into a packet, along with everything else in it. Packets holding Pawn
+    /// updates, which answer the Client's Commands, are never held back either,
+    /// nor are heartbeats, pings or handshake packets, which aren't paced at
+    /// all. Empty by default
+    pub immediate_channels: Vec<ChannelIndex>,
+}
+
+impl Default for PacingConfig {
+    fn default() -> Self {
+        Self {
+            granularity: Duration::from_millis(1),
+            immediate_channels: Vec::new(),
+        }
+    }
+}
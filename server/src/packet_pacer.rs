@@ -0,0 +1,311 @@
+use std::{collections::VecDeque, net::SocketAddr, time::Duration};
+
+use naia_shared::{ChannelIndex, Instant};
+
+use super::pacing_config::PacingConfig;
+
+/// Running totals of the packets sent to Clients, as they were written, in a
+/// burst for each Client every time it was sent updates, and as they were
+/// actually sent, once paced. Without pacing, the two are the same
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PacingStats {
+    /// The number of times packets were written for a Client at once
+    pub bursts_written: u64,
+    /// The number of packets written
+    pub packets_written: u64,
+    /// The number of times packets were sent to a Client back to back
+    pub bursts_sent: u64,
+    /// The number of packets sent
+    pub packets_sent: u64,
+}
+
+impl PacingStats {
+    /// Gets the average number of packets written for a Client at once, which
+    /// would all have been sent back to back without pacing
+    pub fn average_burst_before_pacing(&self) -> f32 {
+        return average(self.packets_written, self.bursts_written);
+    }
+
+    /// Gets the average number of packets sent to a Client back to back
+    pub fn average_burst_after_pacing(&self) -> f32 {
+        return average(self.packets_sent, self.bursts_sent);
+    }
+}
+
+fn average(packets: u64, bursts: u64) -> f32 {
+    if bursts == 0 {
+        return 0.0;
+    }
+    return packets as f32 / bursts as f32;
+}
+
+// A packet held back until it's due to be sent
+struct PacedPacket {
+    release_at: Instant,
+    address: SocketAddr,
+    payload: Box<[u8]>,
+}
+
+/// Holds back the packets written for each Client, releasing them spread
+/// across the tick interval, as set out in a PacingConfig
+pub struct PacketPacer {
+    config: Option<PacingConfig>,
+    tick_interval: Duration,
+    // in the order they're due to be sent
+    queue: VecDeque<PacedPacket>,
+    stats: PacingStats,
+}
+
+impl PacketPacer {
+    /// Creates a PacketPacer, spreading packets across the given tick
+    /// interval, or sending them all straight away if the config is None
+    pub fn new(config: Option<PacingConfig>, tick_interval: Duration) -> Self {
+        PacketPacer {
+            config,
+            tick_interval,
+            queue: VecDeque::new(),
+            stats: PacingStats::default(),
+        }
+    }
+
+    /// Gets the ordered channels whose Events are never held back
+    pub fn get_immediate_channels(&self) -> &[ChannelIndex] {
+        match &self.config {
+            Some(config) => return &config.immediate_channels,
+            None => return &[],
+        }
+    }
+
+    /// Paces the packets written for a Client at once, each along with whether
+    /// it must be sent immediately, returning those to be sent now, in order.
+    /// The rest are held back, spread across the tick interval after any still
+    /// held back for the Client, so that they're sent in the order written
+    pub fn pace(
+        &mut self,
+        address: SocketAddr,
+        packets: Vec<(Box<[u8]>, bool)>,
+        now: &Instant,
+    ) -> Vec<Box<[u8]>> {
+        if packets.is_empty() {
+            return Vec::new();
+        }
+        self.stats.bursts_written += 1;
+        self.stats.packets_written += packets.len() as u64;
+
+        let granularity_millis = match &self.config {
+            Some(config) => config.granularity.as_millis().max(1) as u32,
+            None => {
+                let payloads: Vec<Box<[u8]>> =
+                    packets.into_iter().map(|(payload, _)| payload).collect();
+                self.count_sent(payloads.len());
+                return payloads;
+            }
+        };
+        let slots = (self.tick_interval.as_millis() as u32 / granularity_millis).max(1);
+        let paced_count = packets.iter().filter(|(_, immediate)| !immediate).count() as u32;
+        let start = self
+            .queue
+            .iter()
+            .filter(|packet| packet.address == address)
+            .map(|packet| &packet.release_at)
+            .max()
+            .filter(|release_at| *release_at > now)
+            .unwrap_or(now)
+            .clone();
+
+        let mut immediate_payloads = Vec::new();
+        let mut paced_index: u32 = 0;
+        for (payload, immediate) in packets {
+            if immediate {
+                immediate_payloads.push(payload);
+                continue;
+            }
+            let slot = (u64::from(paced_index) * u64::from(slots) / u64::from(paced_count)) as u32;
+            paced_index += 1;
+            let mut release_at = start.clone();
+            release_at.add_millis(slot * granularity_millis);
+            if release_at <= *now {
+                immediate_payloads.push(payload);
+                continue;
+            }
+            // after any due at the same time, so that each Client's packets
+            // stay in the order they were written
+            let position = self
+                .queue
+                .partition_point(|packet| packet.release_at <= release_at);
+            self.queue.insert(
+                position,
+                PacedPacket {
+                    release_at,
+                    address,
+                    payload,
+                },
+            );
+        }
+        self.count_sent(immediate_payloads.len());
+        return immediate_payloads;
+    }
+
+    /// Gets when the next packet held back is due to be sent, if any are
+    pub fn next_release(&self) -> Option<&Instant> {
+        return self.queue.front().map(|packet| &packet.release_at);
+    }
+
+    /// Takes every packet held back which is due to be sent by the given time,
+    /// in the order they're due, grouped by the Client they're for
+    pub fn take_due(&mut self, now: &Instant) -> Vec<(SocketAddr, Vec<Box<[u8]>>)> {
+        let mut due: Vec<(SocketAddr, Vec<Box<[u8]>>)> = Vec::new();
+        while let Some(packet) = self.queue.front() {
+            if packet.release_at > *now {
+                break;
+            }
+            let packet = self.queue.pop_front().unwrap();
+            match due
+                .iter_mut()
+                .find(|(address, _)| *address == packet.address)
+            {
+                Some((_, payloads)) => payloads.push(packet.payload),
+                None => due.push((packet.address, vec![packet.payload])),
+            }
+        }
+        for (_, payloads) in due.iter() {
+            self.count_sent(payloads.len());
+        }
+        return due;
+    }
+
+    /// Takes every packet held back, whenever it's due
+    pub fn take_all(&mut self) -> Vec<(SocketAddr, Vec<Box<[u8]>>)> {
+        let last = match self.queue.back() {
+            Some(packet) => packet.release_at.clone(),
+            None => return Vec::new(),
+        };
+        return self.take_due(&last);
+    }
+
+    /// Discards every packet held back for the given address, once the Client
+    /// there has gone
+    pub fn discard(&mut self, address: &SocketAddr) {
+        self.queue.retain(|packet| packet.address != *address);
+    }
+
+    /// Gets the running totals of the packets written & sent
+    pub fn get_stats(&self) -> &PacingStats {
+        return &self.stats;
+    }
+
+    fn count_sent(&mut self, packet_count: usize) {
+        if packet_count == 0 {
+            return;
+        }
+        self.stats.bursts_sent += 1;
+        self.stats.packets_sent += packet_count as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, time::Duration};
+
+    use naia_shared::{Clock, ManualClock};
+
+    use super::PacketPacer;
+    use crate::pacing_config::PacingConfig;
+
+    fn address(port: u16) -> SocketAddr {
+        return SocketAddr::new("127.0.0.1".parse().unwrap(), port);
+    }
+
+    // packets whose payload is their index, along with whether each is
+    // immediate
+    fn packets(count: u8, immediate: &[u8]) -> Vec<(Box<[u8]>, bool)> {
+        return (0..count)
+            .map(|index| (vec![index].into_boxed_slice(), immediate.contains(&index)))
+            .collect();
+    }
+
+    fn indices(payloads: &[Box<[u8]>]) -> Vec<u8> {
+        return payloads.iter().map(|payload| payload[0]).collect();
+    }
+
+    fn paced_pacer(granularity_millis: u64) -> PacketPacer {
+        let config = PacingConfig {
+            granularity: Duration::from_millis(granularity_millis),
+            ..Default::default()
+        };
+        return PacketPacer::new(Some(config), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn without_pacing_every_packet_is_sent_at_once() {
+        let clock = ManualClock::new();
+        let mut pacer = PacketPacer::new(None, Duration::from_millis(50));
+        let sent = pacer.pace(address(1), packets(20, &[]), &clock.now());
+        assert_eq!(sent.len(), 20);
+        assert!(pacer.next_release().is_none());
+        assert_eq!(pacer.get_stats().average_burst_before_pacing(), 20.0);
+        assert_eq!(pacer.get_stats().average_burst_after_pacing(), 20.0);
+    }
+
+    #[test]
+    fn packets_are_spread_across_the_tick_interval() {
+        let clock = ManualClock::new();
+        let mut pacer = paced_pacer(5);
+        // 10 slots of 5ms, for 20 packets
+        let sent = pacer.pace(address(1), packets(20, &[]), &clock.now());
+        assert_eq!(indices(&sent), [0, 1]);
+
+        let mut released = Vec::new();
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(5));
+            for (to, payloads) in pacer.take_due(&clock.now()) {
+                assert_eq!(to, address(1));
+                assert_eq!(payloads.len(), 2);
+                released.extend(indices(&payloads));
+            }
+        }
+        assert_eq!(released, (2..20).collect::<Vec<u8>>());
+        assert!(pacer.next_release().is_none());
+
+        let stats = pacer.get_stats();
+        assert_eq!(stats.packets_written, 20);
+        assert_eq!(stats.packets_sent, 20);
+        assert_eq!(stats.average_burst_before_pacing(), 20.0);
+        assert_eq!(stats.average_burst_after_pacing(), 2.0);
+    }
+
+    #[test]
+    fn immediate_packets_skip_the_queue() {
+        let clock = ManualClock::new();
+        let mut pacer = paced_pacer(1);
+        let sent = pacer.pace(address(1), packets(5, &[3, 4]), &clock.now());
+        assert_eq!(indices(&sent), [0, 3, 4]);
+
+        clock.advance(Duration::from_millis(50));
+        let due = pacer.take_due(&clock.now());
+        assert_eq!(due.len(), 1);
+        assert_eq!(indices(&due[0].1), [1, 2]);
+    }
+
+    #[test]
+    fn each_clients_packets_stay_in_order() {
+        let clock = ManualClock::new();
+        let mut pacer = paced_pacer(10);
+        pacer.pace(address(1), packets(5, &[]), &clock.now());
+        pacer.pace(address(2), packets(5, &[]), &clock.now());
+        // written again before the first packets have all been sent
+        clock.advance(Duration::from_millis(10));
+        pacer.take_due(&clock.now());
+        let sent = pacer.pace(address(1), packets(2, &[]), &clock.now());
+        assert!(sent.is_empty());
+
+        let mut released = Vec::new();
+        for (to, payloads) in pacer.take_all() {
+            if to == address(1) {
+                released.extend(indices(&payloads));
+            }
+        }
+        assert_eq!(released, [2, 3, 4, 0, 1]);
+        assert!(pacer.next_release().is_none());
+    }
+}
@@ -0,0 +1,46 @@
+use std::{error::Error, net::SocketAddr};
+
+use naia_server_socket::{MessageSender, Packet};
+use naia_shared::{HookAction, PacketHook};
+
+use super::packet_tracer::PacketTracer;
+
+/// Sends packets to Clients through the Server's socket, showing each to the
+/// outgoing packet hook first, and tracing those it lets through
+#[derive(Debug)]
+pub struct PacketSender {
+    sender: MessageSender,
+    hook: PacketHook,
+    tracer: PacketTracer,
+}
+
+impl PacketSender {
+    /// Create a new PacketSender, sending through the given MessageSender
+    pub fn new(sender: MessageSender) -> Self {
+        PacketSender {
+            sender,
+            hook: PacketHook::default(),
+            tracer: PacketTracer::new(),
+        }
+    }
+
+    /// Sets the hook shown each outgoing packet
+    pub fn set_hook(&mut self, hook: Box<dyn FnMut(&SocketAddr, &[u8]) -> HookAction>) {
+        self.hook.set(hook);
+    }
+
+    /// Gets the PacketTracer which traces the packets sent, which incoming
+    /// packets are traced with too
+    pub fn get_tracer_mut(&mut self) -> &mut PacketTracer {
+        return &mut self.tracer;
+    }
+
+    /// Send a Packet to a Client, unless the hook drops it
+    pub async fn send(&mut self, packet: Packet) -> Result<(), Box<dyn Error + Send>> {
+        if !self.hook.allows(&packet.address(), packet.payload()) {
+            return Ok(());
+        }
+        self.tracer.trace_sent(&packet.address(), packet.payload());
+        return self.sender.send(packet).await;
+    }
+}
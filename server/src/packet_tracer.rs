@@ -0,0 +1,117 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+};
+
+use naia_shared::StandardHeader;
+
+// the most lines kept for each traced address, before the oldest are dropped
+const MAX_TRACE_LINES: usize = 256;
+
+/// Logs a line about every packet sent to or received from the addresses
+/// tracing has been enabled for, keeping the most recent lines for each, so
+/// that a single connection can be looked into on a live Server
+#[derive(Debug, Default)]
+pub struct PacketTracer {
+    traces: HashMap<SocketAddr, VecDeque<String>>,
+}
+
+impl PacketTracer {
+    /// Create a new PacketTracer, tracing no addresses
+    pub fn new() -> Self {
+        return PacketTracer::default();
+    }
+
+    /// Starts or stops tracing the packets sent to & received from the given
+    /// address. The lines kept for an address are discarded when tracing stops
+    pub fn set_enabled(&mut self, address: &SocketAddr, enabled: bool) {
+        if enabled {
+            self.traces.entry(*address).or_default();
+        } else {
+            self.traces.remove(address);
+        }
+    }
+
+    /// Returns whether the packets sent to & received from the given address
+    /// are traced
+    pub fn is_enabled(&self, address: &SocketAddr) -> bool {
+        return self.traces.contains_key(address);
+    }
+
+    /// Traces a packet sent to the given address, if it's traced
+    pub fn trace_sent(&mut self, address: &SocketAddr, payload: &[u8]) {
+        self.trace(address, "sent", payload);
+    }
+
+    /// Traces a packet received from the given address, if it's traced
+    pub fn trace_received(&mut self, address: &SocketAddr, payload: &[u8]) {
+        self.trace(address, "received", payload);
+    }
+
+    /// Takes the lines kept for the given address, oldest first, or None if
+    /// it isn't traced
+    pub fn take_lines(&mut self, address: &SocketAddr) -> Option<Vec<String>> {
+        return self
+            .traces
+            .get_mut(address)
+            .map(|lines| lines.drain(..).collect());
+    }
+
+    fn trace(&mut self, address: &SocketAddr, direction: &str, payload: &[u8]) {
+        if let Some(lines) = self.traces.get_mut(address) {
+            let line = format!(
+                "{} {:?} packet, {} bytes",
+                direction,
+                StandardHeader::read_packet_type(payload),
+                payload.len()
+            );
+            info!("{}: {}", address, line);
+            if lines.len() == MAX_TRACE_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use naia_shared::PacketType;
+
+    use super::{PacketTracer, MAX_TRACE_LINES};
+
+    #[test]
+    fn only_traced_addresses_are_kept() {
+        let traced: SocketAddr = "127.0.0.1:14201".parse().unwrap();
+        let other: SocketAddr = "127.0.0.1:14202".parse().unwrap();
+        let mut tracer = PacketTracer::new();
+        tracer.set_enabled(&traced, true);
+
+        let heartbeat = [PacketType::Heartbeat as u8];
+        tracer.trace_sent(&traced, &heartbeat);
+        tracer.trace_received(&other, &heartbeat);
+        tracer.trace_received(&traced, &[]);
+        assert_eq!(
+            tracer.take_lines(&traced).unwrap(),
+            [
+                "sent Heartbeat packet, 1 bytes",
+                "received Unknown packet, 0 bytes"
+            ]
+        );
+        assert!(tracer.take_lines(&traced).unwrap().is_empty());
+        assert_eq!(tracer.take_lines(&other), None);
+
+        // only the most recent lines are kept
+        for _ in 0..MAX_TRACE_LINES + 1 {
+            tracer.trace_sent(&traced, &heartbeat);
+        }
+        assert_eq!(tracer.take_lines(&traced).unwrap().len(), MAX_TRACE_LINES);
+
+        tracer.trace_sent(&traced, &heartbeat);
+        tracer.set_enabled(&traced, false);
+        assert!(!tracer.is_enabled(&traced));
+        assert_eq!(tracer.take_lines(&traced), None);
+    }
+}
@@ -0,0 +1,125 @@
+use log::warn;
+
+use naia_shared::{utils::write_connectionless_payload, PacketType, StandardHeader};
+
+/// Answers out-of-band queries, which may come from any address without a
+/// connection, using the handler registered by the application. Queries & their
+/// replies are bounded in size, and no reply is larger than the query packet it
+/// answers, so that the Server can't be used to amplify traffic. Queries are
+/// padded by the Client to leave room for a reply
+pub struct QueryResponder {
+    query_func: Option<Box<dyn Fn(&[u8]) -> Option<Vec<u8>>>>,
+    max_query_bytes: usize,
+    max_reply_bytes: usize,
+}
+
+impl QueryResponder {
+    /// Create a new QueryResponder, given the largest query & reply allowed,
+    /// in bytes
+    pub fn new(max_query_bytes: usize, max_reply_bytes: usize) -> Self {
+        QueryResponder {
+            query_func: None,
+            max_query_bytes,
+            max_reply_bytes,
+        }
+    }
+
+    /// Sets the handler which is given the bytes of each query, and returns the
+    /// bytes to reply with, if any
+    pub fn set_handler(&mut self, query_func: Box<dyn Fn(&[u8]) -> Option<Vec<u8>>>) {
+        self.query_func = Some(query_func);
+    }
+
+    /// Returns whether a handler has been registered
+    pub fn has_handler(&self) -> bool {
+        return self.query_func.is_some();
+    }
+
+    /// Get the packet to reply to a query with, given the query packet. Returns
+    /// None if there is no handler, the handler declines to reply, the query
+    /// or the reply is too large, or the reply packet would be larger than the
+    /// query packet
+    pub fn respond(&self, query_packet: &[u8]) -> Option<Box<[u8]>> {
+        let query_func = self.query_func.as_ref()?;
+        let (_, query) = StandardHeader::read(query_packet);
+        if query.len() > self.max_query_bytes {
+            return None;
+        }
+
+        let reply = (query_func)(&query)?;
+        if reply.len() > self.max_reply_bytes {
+            warn!(
+                "query reply of {} bytes is over the limit of {} bytes, not sending it",
+                reply.len(),
+                self.max_reply_bytes
+            );
+            return None;
+        }
+        let reply_packet = write_connectionless_payload(PacketType::QueryResponse, &reply);
+        if reply_packet.len() > query_packet.len() {
+            warn!(
+                "query reply of {} bytes is larger than the {} byte query, not sending it",
+                reply_packet.len(),
+                query_packet.len()
+            );
+            return None;
+        }
+        return Some(reply_packet);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{
+        utils::{write_connectionless_payload, write_padded_connectionless_payload},
+        PacketType, StandardHeader,
+    };
+
+    use super::QueryResponder;
+
+    // a query packet, padded to leave room for replies
+    fn query(payload: &[u8]) -> Box<[u8]> {
+        return write_padded_connectionless_payload(PacketType::Query, payload, 64);
+    }
+
+    #[test]
+    fn answers_within_size_limits() {
+        let mut responder = QueryResponder::new(8, 16);
+        assert!(responder.respond(&query(b"info")).is_none());
+
+        responder.set_handler(Box::new(|query: &[u8]| {
+            if query == b"info" {
+                return Some(b"players=64/64".to_vec());
+            }
+            if query == b"big" {
+                return Some(vec![0; 17]);
+            }
+            return None;
+        }));
+
+        let reply = responder.respond(&query(b"info")).unwrap();
+        let (header, payload) = StandardHeader::read(&reply);
+        assert_eq!(header.packet_type(), PacketType::QueryResponse);
+        assert_eq!(payload.as_ref(), b"players=64/64");
+
+        assert!(responder.respond(&query(b"unknown")).is_none());
+        assert!(responder.respond(&query(b"big")).is_none());
+        assert!(responder.respond(&query(b"too long query")).is_none());
+    }
+
+    #[test]
+    fn replies_are_never_larger_than_the_query() {
+        let mut responder = QueryResponder::new(8, 400);
+        responder.set_handler(Box::new(|_: &[u8]| {
+            return Some(b"players=64/64".to_vec());
+        }));
+
+        // an unpadded query is too small to be answered
+        let unpadded = write_connectionless_payload(PacketType::Query, b"i");
+        assert!(responder.respond(&unpadded).is_none());
+
+        let padded = query(b"i");
+        let reply = responder.respond(&padded).unwrap();
+        assert!(reply.len() <= padded.len());
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+};
+
+use naia_shared::Instant;
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f32,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(tokens: f32, now: &Instant) -> Self {
+        TokenBucket {
+            tokens,
+            last_refill: now.clone(),
+        }
+    }
+
+    fn try_take(&mut self, now: &Instant, rate: f32, burst: f32) -> bool {
+        let elapsed = now
+            .get_inner()
+            .saturating_duration_since(self.last_refill.get_inner());
+        self.last_refill = now.clone();
+        self.tokens = (self.tokens + (elapsed.as_secs_f32() * rate)).min(burst);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return true;
+        }
+        return false;
+    }
+}
+
+#[derive(Debug)]
+struct AddressBucket {
+    bucket: TokenBucket,
+    last_used: u64,
+}
+
+/// Limits the rate of packets accepted from addresses which do not have an
+/// established connection, both per address and in total. The number of
+/// addresses tracked is bounded, evicting the least recently used address
+/// when full, so that the limiter can't be used to exhaust memory
+#[derive(Debug)]
+pub struct RateLimiter {
+    packets_per_second: f32,
+    burst: f32,
+    total_packets_per_second: f32,
+    capacity: usize,
+    total_bucket: TokenBucket,
+    address_buckets: HashMap<SocketAddr, AddressBucket>,
+    // may hold stale entries, which are skipped when evicting
+    usage_order: VecDeque<(SocketAddr, u64)>,
+    use_count: u64,
+    dropped_count: u64,
+}
+
+impl RateLimiter {
+    /// Create a new RateLimiter, given the rate & burst size allowed per
+    /// address, the total rate allowed across all addresses, and the maximum
+    /// number of addresses to track
+    pub fn new(
+        packets_per_second: f32,
+        burst: u16,
+        total_packets_per_second: f32,
+        capacity: usize,
+        now: &Instant,
+    ) -> Self {
+        RateLimiter {
+            packets_per_second,
+            burst: f32::from(burst),
+            total_packets_per_second,
+            capacity,
+            total_bucket: TokenBucket::new(total_packets_per_second, now),
+            address_buckets: HashMap::new(),
+            usage_order: VecDeque::new(),
+            use_count: 0,
+            dropped_count: 0,
+        }
+    }
+
+    /// Returns whether a packet from the given address should be processed
+    pub fn allow(&mut self, address: &SocketAddr, now: &Instant) -> bool {
+        if !self.address_buckets.contains_key(address) {
+            if self.address_buckets.len() >= self.capacity {
+                self.evict_least_recently_used();
+            }
+            self.address_buckets.insert(
+                *address,
+                AddressBucket {
+                    bucket: TokenBucket::new(self.burst, now),
+                    last_used: 0,
+                },
+            );
+        }
+
+        self.use_count += 1;
+        let address_bucket = self.address_buckets.get_mut(address).unwrap();
+        address_bucket.last_used = self.use_count;
+        let address_allowed =
+            address_bucket
+                .bucket
+                .try_take(now, self.packets_per_second, self.burst);
+
+        self.usage_order.push_back((*address, self.use_count));
+        if self.usage_order.len() > self.capacity * 4 {
+            self.compact_usage_order();
+        }
+
+        let allowed = address_allowed
+            && self.total_bucket.try_take(
+                now,
+                self.total_packets_per_second,
+                self.total_packets_per_second,
+            );
+        if !allowed {
+            self.dropped_count += 1;
+        }
+        return allowed;
+    }
+
+    /// Get the number of packets which have been dropped by the limiter
+    pub fn get_dropped_count(&self) -> u64 {
+        return self.dropped_count;
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        while let Some((address, last_used)) = self.usage_order.pop_front() {
+            let is_current = match self.address_buckets.get(&address) {
+                Some(address_bucket) => address_bucket.last_used == last_used,
+                None => false,
+            };
+            if is_current {
+                self.address_buckets.remove(&address);
+                return;
+            }
+        }
+    }
+
+    fn compact_usage_order(&mut self) {
+        let address_buckets = &self.address_buckets;
+        self.usage_order
+            .retain(|(address, last_used)| match address_buckets.get(address) {
+                Some(address_bucket) => address_bucket.last_used == *last_used,
+                None => false,
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use naia_shared::{Clock, SystemClock};
+
+    use super::RateLimiter;
+
+    fn synthetic_address(index: u32) -> SocketAddr {
+        return (Ipv4Addr::from(0x0a00_0000 + index), 14191).into();
+    }
+
+    #[test]
+    fn limits_single_address() {
+        let mut now = SystemClock.now();
+        let mut limiter = RateLimiter::new(10.0, 5, 1000.0, 16, &now);
+        let address = synthetic_address(1);
+
+        let mut processed = 0;
+        for _ in 0..100 {
+            if limiter.allow(&address, &now) {
+                processed += 1;
+            }
+        }
+        assert_eq!(processed, 5);
+        assert_eq!(limiter.get_dropped_count(), 95);
+
+        // refills at the configured rate
+        now.add_millis(200);
+        assert!(limiter.allow(&address, &now));
+        assert!(limiter.allow(&address, &now));
+        assert!(!limiter.allow(&address, &now));
+    }
+
+    #[test]
+    fn limits_many_addresses() {
+        let now = SystemClock.now();
+        let mut limiter = RateLimiter::new(10.0, 5, 100.0, 64, &now);
+
+        let mut processed = 0;
+        for index in 0..10_000 {
+            if limiter.allow(&synthetic_address(index), &now) {
+                processed += 1;
+            }
+        }
+        assert_eq!(processed, 100);
+        assert!(limiter.address_buckets.len() <= 64);
+        assert!(limiter.usage_order.len() <= 64 * 4);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let now = SystemClock.now();
+        let mut limiter = RateLimiter::new(10.0, 5, 1000.0, 2, &now);
+        let first = synthetic_address(1);
+        let second = synthetic_address(2);
+        let third = synthetic_address(3);
+
+        limiter.allow(&first, &now);
+        limiter.allow(&second, &now);
+        limiter.allow(&first, &now);
+        limiter.allow(&third, &now);
+
+        assert!(limiter.address_buckets.contains_key(&first));
+        assert!(!limiter.address_buckets.contains_key(&second));
+        assert!(limiter.address_buckets.contains_key(&third));
+    }
+}
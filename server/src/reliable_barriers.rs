@@ -0,0 +1,177 @@
+use naia_shared::Instant;
+
+use super::{barrier_outcome::BarrierOutcome, user::user_key::UserKey};
+
+/// The id of a barrier placed with `NaiaServer::reliable_barrier()` or
+/// `NaiaServer::barrier_all()`, unique among the barriers of a Server
+pub type BarrierId = u32;
+
+/// Keeps track of the barriers placed across the connections to Clients, each
+/// waiting for the reliable Events queued on its connections before it to be
+/// acknowledged
+pub struct ReliableBarriers {
+    next_id: BarrierId,
+    // in the order they were placed
+    pending: Vec<PendingBarrier>,
+}
+
+struct PendingBarrier {
+    id: BarrierId,
+    // the connections which have yet to acknowledge every reliable Event
+    // queued before the barrier, along with the tail each had when it was
+    // placed
+    tails: Vec<(UserKey, u64)>,
+    // whether a connection was closed before it did
+    disconnected: bool,
+    timeout_at: Option<Instant>,
+}
+
+impl ReliableBarriers {
+    /// Create a new ReliableBarriers, with no barriers placed
+    pub fn new() -> Self {
+        ReliableBarriers {
+            next_id: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Places a barrier, given the tail of each connection it covers, and the
+    /// time it times out at, if any. Returns the id of the barrier
+    pub fn place(&mut self, tails: Vec<(UserKey, u64)>, timeout_at: Option<Instant>) -> BarrierId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending.push(PendingBarrier {
+            id,
+            tails,
+            disconnected: false,
+            timeout_at,
+        });
+        return id;
+    }
+
+    /// Fails every barrier waiting on the connection of a User, which has been
+    /// closed. Those covering other connections still wait for them
+    pub fn notify_disconnected(&mut self, user_key: &UserKey) {
+        for barrier in self.pending.iter_mut() {
+            let tail_count = barrier.tails.len();
+            barrier.tails.retain(|(key, _)| key != user_key);
+            if barrier.tails.len() != tail_count {
+                barrier.disconnected = true;
+            }
+        }
+    }
+
+    /// Takes the first barrier, in the order they were placed, which has
+    /// completed, along with how, given whether the connection of a User has
+    /// acknowledged every reliable Event queued before a tail, and the current
+    /// time
+    pub fn take_completed<F: Fn(&UserKey, u64) -> bool>(
+        &mut self,
+        is_delivered_up_to: F,
+        now: &Instant,
+    ) -> Option<(BarrierId, BarrierOutcome)> {
+        for index in 0..self.pending.len() {
+            let barrier = &mut self.pending[index];
+            barrier
+                .tails
+                .retain(|(user_key, tail)| !is_delivered_up_to(user_key, *tail));
+            let outcome = if barrier.tails.is_empty() {
+                if barrier.disconnected {
+                    BarrierOutcome::Disconnected
+                } else {
+                    BarrierOutcome::Delivered
+                }
+            } else {
+                match &barrier.timeout_at {
+                    Some(timeout_at) if *timeout_at <= *now => BarrierOutcome::TimedOut,
+                    _ => continue,
+                }
+            };
+            let barrier = self.pending.remove(index);
+            return Some((barrier.id, outcome));
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slotmap::DenseSlotMap;
+
+    use naia_shared::{Clock, Instant, SystemClock};
+
+    use super::ReliableBarriers;
+    use crate::{BarrierOutcome, UserKey};
+
+    fn after(now: &Instant, millis: u32) -> Instant {
+        let mut later = now.clone();
+        later.add_millis(millis);
+        return later;
+    }
+
+    #[test]
+    fn completes_once_each_connection_reaches_its_tail() {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let alice = users.insert(());
+        let bob = users.insert(());
+        let mut barriers = ReliableBarriers::new();
+        let now = SystemClock.now();
+
+        let first = barriers.place(vec![(alice, 3), (bob, 5)], None);
+        let second = barriers.place(vec![(alice, 4)], None);
+        assert_ne!(first, second);
+
+        // Alice has delivered up to 4, Bob up to 2
+        let delivered = |user_key: &UserKey, tail: u64| {
+            return tail <= if *user_key == alice { 4 } else { 2 };
+        };
+        assert_eq!(
+            barriers.take_completed(delivered, &now),
+            Some((second, BarrierOutcome::Delivered))
+        );
+        assert_eq!(barriers.take_completed(delivered, &now), None);
+
+        // a connection which has reached its tail isn't checked again
+        let bob_delivered = |user_key: &UserKey, _: u64| {
+            assert_eq!(*user_key, bob);
+            return true;
+        };
+        assert_eq!(
+            barriers.take_completed(bob_delivered, &now),
+            Some((first, BarrierOutcome::Delivered))
+        );
+    }
+
+    #[test]
+    fn fails_on_disconnection_or_timeout() {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let alice = users.insert(());
+        let bob = users.insert(());
+        let mut barriers = ReliableBarriers::new();
+        let now = SystemClock.now();
+        let nothing_delivered = |_: &UserKey, _: u64| false;
+
+        let alone = barriers.place(vec![(alice, 1)], None);
+        let both = barriers.place(vec![(alice, 1), (bob, 1)], None);
+        let timed = barriers.place(vec![(bob, 1)], Some(after(&now, 1_000)));
+
+        barriers.notify_disconnected(&alice);
+        assert_eq!(
+            barriers.take_completed(nothing_delivered, &now),
+            Some((alone, BarrierOutcome::Disconnected))
+        );
+        // still waits for Bob, who is still connected
+        assert_eq!(barriers.take_completed(nothing_delivered, &now), None);
+
+        assert_eq!(
+            barriers.take_completed(nothing_delivered, &after(&now, 1_000)),
+            Some((timed, BarrierOutcome::TimedOut))
+        );
+        assert_eq!(
+            barriers.take_completed(|_, _| true, &now),
+            Some((both, BarrierOutcome::Disconnected))
+        );
+        assert!(BarrierOutcome::Disconnected.is_failed());
+        assert!(!BarrierOutcome::Delivered.is_failed());
+    }
+}
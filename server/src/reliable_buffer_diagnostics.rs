@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use naia_shared::ChannelIndex;
+
+/// The state of a connection at the moment one of its reliable Event buffers
+/// overflowed, recorded so that the cause can be looked into afterwards
+#[derive(Clone, Debug)]
+pub struct ReliableBufferDiagnostics {
+    /// The ordered channel which overflowed, or None for reliable Events sent
+    /// without a channel
+    pub channel: Option<ChannelIndex>,
+    /// The number of Events on the channel which had not been acknowledged
+    pub unacked_messages: usize,
+    /// The total size in bytes of the Events on the channel which had not been
+    /// acknowledged
+    pub unacked_bytes: usize,
+    /// How long the oldest reliable Event which had not been acknowledged had
+    /// been waiting for, across every channel
+    pub oldest_unacked_age: Duration,
+    /// The fraction of recently sent packets which had not been acknowledged
+    pub packet_loss: f32,
+}
@@ -1,5 +1,7 @@
 use std::collections::{hash_set::Iter, HashSet, VecDeque};
 
+use naia_shared::DespawnReason;
+
 use super::{actors::actor_key::actor_key::ActorKey, user::user_key::UserKey};
 
 #[allow(missing_docs)]
@@ -12,7 +14,7 @@ pub mod room_key {
 pub struct Room {
     users: HashSet<UserKey>,
     actors: HashSet<ActorKey>,
-    removal_queue: VecDeque<(UserKey, ActorKey)>,
+    removal_queue: VecDeque<(UserKey, ActorKey, DespawnReason)>,
 }
 
 impl Room {
@@ -29,9 +31,14 @@ impl Room {
     }
 
     pub fn remove_actor(&mut self, actor_key: &ActorKey) {
+        self.remove_actor_with_reason(actor_key, DespawnReason::OutOfScope);
+    }
+
+    pub fn remove_actor_with_reason(&mut self, actor_key: &ActorKey, reason: DespawnReason) {
         self.actors.remove(actor_key);
         for user_key in self.users.iter() {
-            self.removal_queue.push_back((*user_key, *actor_key));
+            self.removal_queue
+                .push_back((*user_key, *actor_key, reason));
         }
     }
 
@@ -46,7 +53,8 @@ impl Room {
     pub fn unsubscribe_user(&mut self, user_key: &UserKey) {
         self.users.remove(user_key);
         for actor_key in self.actors.iter() {
-            self.removal_queue.push_back((*user_key, *actor_key));
+            self.removal_queue
+                .push_back((*user_key, *actor_key, DespawnReason::OutOfScope));
         }
     }
 
@@ -54,7 +62,49 @@ impl Room {
         return self.users.iter();
     }
 
-    pub fn pop_removal_queue(&mut self) -> Option<(UserKey, ActorKey)> {
+    pub fn pop_removal_queue(&mut self) -> Option<(UserKey, ActorKey, DespawnReason)> {
         return self.removal_queue.pop_front();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::DespawnReason;
+    use slotmap::DenseSlotMap;
+
+    use super::Room;
+    use crate::{ActorKey, UserKey};
+
+    #[test]
+    fn removals_carry_their_reason() {
+        let mut actors: DenseSlotMap<ActorKey, ()> = DenseSlotMap::with_key();
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let (actor_a, actor_b) = (actors.insert(()), actors.insert(()));
+        let user = users.insert(());
+
+        let mut room = Room::new();
+        room.subscribe_user(&user);
+        room.add_actor(&actor_a);
+        room.add_actor(&actor_b);
+
+        room.remove_actor(&actor_a);
+        assert_eq!(
+            room.pop_removal_queue(),
+            Some((user, actor_a, DespawnReason::OutOfScope))
+        );
+
+        room.remove_actor_with_reason(&actor_b, DespawnReason::Custom(3));
+        assert_eq!(
+            room.pop_removal_queue(),
+            Some((user, actor_b, DespawnReason::Custom(3)))
+        );
+
+        room.add_actor(&actor_a);
+        room.unsubscribe_user(&user);
+        assert_eq!(
+            room.pop_removal_queue(),
+            Some((user, actor_a, DespawnReason::OutOfScope))
+        );
+        assert_eq!(room.pop_removal_queue(), None);
+    }
+}
@@ -0,0 +1,14 @@
+/// Decides when queued Events & Actor messages are written into packets and
+/// sent to each Client
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SendMode {
+    /// Packets are sent on every call to `NaiaServer::send_all_updates()`, and
+    /// replies from Event middleware are sent as soon as they are queued
+    Immediate,
+    /// Packets are sent to each Client at most once per Server tick, on the
+    /// first call to `NaiaServer::send_all_updates()` in that tick, so that
+    /// they are evenly spaced however often it is called. Replies from Event
+    /// middleware wait for the next tick's packets. Heartbeats, pings & other
+    /// connection upkeep are still sent whenever they are due
+    PerTick,
+}
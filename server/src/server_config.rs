@@ -1,4 +1,15 @@
-use std::{default::Default, time::Duration};
+use std::{default::Default, sync::Arc, time::Duration};
+
+use naia_shared::{Clock, PacketPadding, SystemClock};
+
+#[cfg(feature = "metrics")]
+use super::metrics_config::MetricsConfig;
+
+use super::{
+    congestion_limits::CongestionLimits, congestion_policy::CongestionPolicy,
+    duplicate_connection_policy::DuplicateConnectionPolicy, pacing_config::PacingConfig,
+    send_mode::SendMode, socket_config::SocketConfig, unknown_address_policy::UnknownAddressPolicy,
+};
 
 /// Contains Config properties which will be used by a Server or Client
 #[derive(Clone, Debug)]
@@ -17,6 +28,163 @@ pub struct ServerConfig {
     /// Number of samples to measure RTT & Jitter by. A higher number will
     /// smooth out RTT measurements, but at the cost of responsiveness.
     pub rtt_sample_size: u16,
+    /// Whether Actors referenced by an in-scope Actor (through an ActorRef
+    /// Property) should be brought into scope for the same User, so that
+    /// references can be resolved on the Client
+    pub scope_actor_refs: bool,
+    /// The maximum number of bytes of Actor creation messages to send to a
+    /// newly connected Client each tick, while the initial sync of all
+    /// in-scope Actors is in progress. These messages are sent ahead of any
+    /// Actor updates
+    pub world_sync_bytes_per_tick: usize,
+    /// The maximum number of Tick events which will be queued up when the
+    /// Server falls behind (for example, after a long frame). Any further
+    /// elapsed ticks are skipped, rather than compounding the delay. A value
+    /// of 0 is treated as 1
+    pub max_catch_up_ticks: u16,
+    /// How to handle a connection request from an address which already has an
+    /// established connection
+    pub duplicate_connection_policy: DuplicateConnectionPolicy,
+    /// The largest number of Clients which may be connected at once, if any.
+    /// Further connection requests are rejected with `RejectReason::ServerFull`,
+    /// though out-of-band queries are still answered
+    pub max_clients: Option<usize>,
+    /// The limits on the number of Events in a connection's outgoing Events
+    /// queue, either waiting to be sent, or waiting to be acknowledged. Past
+    /// the soft limit the connection is reported as congested, and at the hard
+    /// limit the congestion policy is applied
+    pub event_congestion_limits: CongestionLimits,
+    /// The limits on the number of bytes of reliable Events in a connection's
+    /// outgoing Events queue which are waiting to be acknowledged, applied
+    /// along with `event_congestion_limits`
+    pub event_congestion_byte_limits: CongestionLimits,
+    /// The limits on the number of messages in a connection's outgoing Actors
+    /// queue, either waiting to be sent, or waiting to be acknowledged
+    pub actor_congestion_limits: CongestionLimits,
+    /// What to do with a connection which has reached the hard congestion
+    /// limit
+    pub congestion_policy: CongestionPolicy,
+    /// The number of packets per second accepted from an address which does
+    /// not have an established connection. Further packets are dropped before
+    /// any handshake processing happens
+    pub pre_connection_packets_per_second: f32,
+    /// The number of packets an address without an established connection can
+    /// send in a burst, before being limited to
+    /// `pre_connection_packets_per_second`
+    pub pre_connection_packet_burst: u16,
+    /// The total number of packets per second accepted across all addresses
+    /// which do not have an established connection
+    pub pre_connection_total_packets_per_second: f32,
+    /// The maximum number of addresses without an established connection for
+    /// which rate limiting state is kept
+    pub pre_connection_tracked_addresses: usize,
+    /// Determines what happens to packets, other than handshake requests, from
+    /// addresses which have no connection
+    pub unknown_address_policy: UnknownAddressPolicy,
+    /// The number of "not connected" replies per second sent to any one
+    /// address, when notifying unknown addresses
+    pub not_connected_replies_per_second: f32,
+    /// The maximum number of reliable Events on any one channel of a
+    /// connection which may be waiting to be acknowledged. Past this, the
+    /// connection is considered failed and the User is disconnected
+    pub reliable_buffer_max_messages: usize,
+    /// The maximum total size in bytes of reliable Events on any one channel
+    /// of a connection which may be waiting to be acknowledged. Past this, the
+    /// connection is considered failed and the User is disconnected
+    pub reliable_buffer_max_bytes: usize,
+    /// The largest out-of-band query accepted, in bytes. Larger queries are
+    /// dropped without being handed to the query handler
+    pub max_query_bytes: usize,
+    /// The largest reply to an out-of-band query which will be sent, in bytes.
+    /// Larger replies are dropped, as are replies which would be larger than
+    /// the query packet they answer, so that the Server can't be used to
+    /// amplify traffic. `NaiaClient::query()` pads each query to leave room for
+    /// a reply of up to 495 bytes
+    pub max_query_reply_bytes: usize,
+    /// Whether to discover the largest packet which reaches each Client, by
+    /// probing with padded packets after the connection is established. Until
+    /// a larger size has been confirmed, or if this is disabled, packets are
+    /// kept to 508 bytes
+    pub mtu_discovery: bool,
+    /// The duration to wait for an MTU probe to be acknowledged before it is
+    /// sent again, or given up on
+    pub mtu_probe_timeout: Duration,
+    /// The number of times an MTU probe of a given size is sent before that
+    /// size is given up on
+    pub mtu_probe_attempts: u8,
+    /// The duration to wait before probing for a larger MTU again, after a
+    /// probe has failed or the MTU has been lowered due to packet loss
+    pub mtu_reprobe_interval: Duration,
+    /// How long the replication state of a Client whose connection has timed
+    /// out is kept, so that the Client can resume the connection with the
+    /// session token it was issued, and only be sent what has changed since.
+    /// None, the default, disables resumption, and timed out Clients are
+    /// disconnected straight away
+    pub session_resumption_grace: Option<Duration>,
+    /// The most packets of Commands a Client resuming its connection can
+    /// attach to the handshake as early data, which are released as soon as
+    /// the connection is resumed, rather than waiting a round trip for it.
+    /// Early data over this, or over `early_data_max_bytes`, is discarded
+    /// whole, as is any sent without a valid session token. 0 refuses early
+    /// data altogether
+    pub early_data_max_packets: usize,
+    /// The most bytes of Commands, across every packet, a Client resuming its
+    /// connection can attach to the handshake as early data
+    pub early_data_max_bytes: usize,
+    /// How packets sent to the Client are padded, to hide their sizes from
+    /// anyone observing the traffic. None, the default, sends packets unpadded
+    pub packet_padding: Option<PacketPadding>,
+    /// The duration between cover heartbeats, which are sent to the Client
+    /// whether or not anything else has been sent. None, the default, only
+    /// sends heartbeats when the connection is otherwise idle
+    pub cover_heartbeat_interval: Option<Duration>,
+    /// Decides when queued Events & Actor messages are sent to each Client.
+    /// Defaults to sending them on every call to
+    /// `NaiaServer::send_all_updates()`
+    pub send_mode: SendMode,
+    /// How the packets sent to each Client in a tick are spread across the
+    /// tick interval. None, the default, sends them all back to back
+    pub pacing: Option<PacingConfig>,
+    /// The DSCP value to mark packets sent to the Client with, so that networks
+    /// which honour it can prioritize them. Only IPv4 UDP sockets can be
+    /// marked, through IP_TOS: over IPv6 or WebRTC, packets are sent unmarked
+    /// and a warning is logged if this is set. None, the default, leaves
+    /// packets unmarked
+    pub dscp: Option<u8>,
+    /// Options applied to the socket when it is bound
+    pub socket: SocketConfig,
+    /// The number of ticks of replicated state kept for each Actor which has
+    /// its history tracked, with `NaiaServer::track_history()`, to validate
+    /// Client actions against what the Client saw at the time
+    pub history_ticks: u16,
+    /// The maximum number of Actors which can have their history tracked at
+    /// once
+    pub history_max_actors: usize,
+    /// Whether a panic while reading an Event from a Client, such as over a
+    /// payload shorter than its type expects, is caught & the Event skipped
+    /// over as a SkippedFrame, rather than taking the Server down. True by
+    /// default
+    pub catch_decode_panics: bool,
+    /// How many malformed Events a Client can send before it's kicked for
+    /// it. None, the default, never kicks Clients over malformed Events
+    pub malformed_frame_limit: Option<u32>,
+    /// How long the bookkeeping of an Actor which has left a Client's scope is
+    /// kept once its deletion has been sent, if the Client never acknowledges
+    /// it, as a multiple of the connection's RTT. Whichever is longer of this &
+    /// `despawn_retention_grace` is used, which also bounds how long a packet
+    /// may go unacknowledged before it's treated as dropped, and how long the
+    /// LocalActorKey of a deleted Actor waits before it's reused
+    pub despawn_retention_rtts: f32,
+    /// The least time the bookkeeping of an Actor is kept once its deletion
+    /// has been sent, however short the connection's RTT
+    pub despawn_retention_grace: Duration,
+    /// The Clock every time on the Server & its connections is read from,
+    /// including when ticks fall due. The SystemClock by default, while a
+    /// ManualClock only moves when it's advanced, for tests & replays
+    pub clock: Arc<dyn Clock>,
+    /// How the Server's stats are exported through the `metrics` facade
+    #[cfg(feature = "metrics")]
+    pub metrics: MetricsConfig,
 }
 
 impl Default for ServerConfig {
@@ -27,6 +195,47 @@ impl Default for ServerConfig {
             send_handshake_interval: Duration::from_secs(1),
             ping_interval: Duration::from_secs(1),
             rtt_sample_size: 20,
+            scope_actor_refs: false,
+            world_sync_bytes_per_tick: 4096,
+            max_catch_up_ticks: 5,
+            duplicate_connection_policy: DuplicateConnectionPolicy::ReplaceExisting,
+            max_clients: None,
+            event_congestion_limits: CongestionLimits::new(256, 1024),
+            event_congestion_byte_limits: CongestionLimits::new(256 * 1024, 1024 * 1024),
+            actor_congestion_limits: CongestionLimits::new(256, 1024),
+            congestion_policy: CongestionPolicy::RejectSends,
+            pre_connection_packets_per_second: 10.0,
+            pre_connection_packet_burst: 20,
+            pre_connection_total_packets_per_second: 1000.0,
+            pre_connection_tracked_addresses: 4096,
+            unknown_address_policy: UnknownAddressPolicy::NotifyNotConnected,
+            not_connected_replies_per_second: 1.0,
+            reliable_buffer_max_messages: 2048,
+            reliable_buffer_max_bytes: 1024 * 1024,
+            max_query_bytes: 128,
+            max_query_reply_bytes: 400,
+            mtu_discovery: false,
+            mtu_probe_timeout: Duration::from_millis(500),
+            mtu_probe_attempts: 3,
+            mtu_reprobe_interval: Duration::from_secs(30),
+            session_resumption_grace: None,
+            early_data_max_packets: 4,
+            early_data_max_bytes: 512,
+            packet_padding: None,
+            cover_heartbeat_interval: None,
+            send_mode: SendMode::Immediate,
+            pacing: None,
+            dscp: None,
+            socket: SocketConfig::default(),
+            history_ticks: 32,
+            history_max_actors: 256,
+            catch_decode_panics: true,
+            malformed_frame_limit: None,
+            despawn_retention_rtts: 8.0,
+            despawn_retention_grace: Duration::from_secs(5),
+            clock: Arc::new(SystemClock),
+            #[cfg(feature = "metrics")]
+            metrics: MetricsConfig::default(),
         }
     }
 }
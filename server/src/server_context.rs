@@ -0,0 +1,33 @@
+use std::ops::{Deref, DerefMut};
+
+use naia_shared::{ActorType, EventType};
+
+use super::naia_server::NaiaServer;
+
+/// The context a ServerEventHandler is called with, from within
+/// `NaiaServer::process()`. It gives access to the Server, to send replies,
+/// kick Users or update Actors, but not to receive events, which are only
+/// handed to the ServerEventHandler
+pub struct ServerContext<'s, T: EventType, U: ActorType> {
+    server: &'s mut NaiaServer<T, U>,
+}
+
+impl<'s, T: EventType, U: ActorType> ServerContext<'s, T, U> {
+    pub(crate) fn new(server: &'s mut NaiaServer<T, U>) -> Self {
+        ServerContext { server }
+    }
+}
+
+impl<'s, T: EventType, U: ActorType> Deref for ServerContext<'s, T, U> {
+    type Target = NaiaServer<T, U>;
+
+    fn deref(&self) -> &NaiaServer<T, U> {
+        return self.server;
+    }
+}
+
+impl<'s, T: EventType, U: ActorType> DerefMut for ServerContext<'s, T, U> {
+    fn deref_mut(&mut self) -> &mut NaiaServer<T, U> {
+        return self.server;
+    }
+}
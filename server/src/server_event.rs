@@ -1,22 +1,68 @@
+use naia_shared::{ConnectionInfo, RawEvent, SkippedFrame};
+
 use super::{
     actors::actor_key::actor_key::ActorKey,
+    barrier_outcome::BarrierOutcome,
+    outgoing_queue::OutgoingQueue,
+    reliable_barriers::BarrierId,
+    reliable_buffer_diagnostics::ReliableBufferDiagnostics,
     user::{user_key::UserKey, User},
 };
 
 /// An Event that is emitted as a result of some communication with a Client, or
-/// a Tick event
+/// a Tick event. For each connection, the Connection event always comes before
+/// the first event from it, and the Disconnection event after the last
 pub enum ServerEvent<T> {
     /// Occurs when a new Client has successfully established a connection with
-    /// the Server
-    Connection(UserKey),
+    /// the Server, given the parameters agreed for the connection. These can
+    /// be read again later with `UserRef::connection_info()`, as the MTU may
+    /// grow once discovered
+    Connection(UserKey, ConnectionInfo),
     /// Occurs when the Server has lost connection to a Client, usually as the
     /// result of a timeout
     Disconnection(UserKey, User),
+    /// Occurs when the connection to a Client has timed out, but may still be
+    /// resumed by the Client within the session resumption grace period. The
+    /// User stays in its Rooms, and changes to its Actors keep being tracked.
+    /// Either a ConnectionResumed or a Disconnection event follows
+    ConnectionSuspended(UserKey),
+    /// Occurs when a Client has resumed its suspended connection, possibly
+    /// from a new address. Only what changed while the connection was down is
+    /// sent to it
+    ConnectionResumed(UserKey),
     /// An Event emitted to the Server from a Client
     Event(UserKey, T),
+    /// An Event emitted to the Server from a Client, of a type set to be
+    /// received raw with `NaiaServer::receive_raw()`
+    RawEvent(UserKey, RawEvent),
+    /// Occurs when an Event from a Client was skipped over instead of
+    /// received, because it couldn't be read. The Events sent alongside it are
+    /// still received
+    SkippedFrame(UserKey, SkippedFrame),
     /// An Command emitted to the Server from a Client
     Command(UserKey, ActorKey, T),
+    /// Occurs when every Actor that was in scope for a newly connected Client
+    /// has been delivered to it, and the Client has been notified. The sync
+    /// only starts once the Client is in a Room its scope is evaluated for
+    WorldSyncComplete(UserKey),
+    /// Occurs when one of a User's outgoing queues has grown past the soft
+    /// congestion limit, usually because the connection has degraded
+    ConnectionCongested(UserKey, OutgoingQueue),
+    /// Occurs when a congested outgoing queue of a User has drained
+    ConnectionCongestionCleared(UserKey, OutgoingQueue),
+    /// Occurs when more reliable Events are waiting to be acknowledged by a
+    /// User than the configured limits allow. Dropping them would break the
+    /// guarantee of delivery, so the User is disconnected instead, and a
+    /// Disconnection event follows right after
+    ReliableBufferOverflow(UserKey, ReliableBufferDiagnostics),
+    /// Occurs when a barrier placed with `NaiaServer::reliable_barrier()` or
+    /// `NaiaServer::barrier_all()` has completed, given how. Reliable Events
+    /// queued after the barrier aren't waited for. A barrier failed by a
+    /// closed connection completes after that connection's Disconnection
+    BarrierCompleted(BarrierId, BarrierOutcome),
     /// A Tick Event, the duration between Tick events is defined in the Config
-    /// object passed to the Server on initialization
+    /// object passed to the Server on initialization. If the Server falls
+    /// behind, several Tick events will be emitted in a row to catch up, so
+    /// game logic should be run exactly once per Tick event
     Tick,
 }
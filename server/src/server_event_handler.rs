@@ -0,0 +1,49 @@
+use naia_shared::{ActorType, ConnectionInfo, EventType, NaiaError};
+
+use super::{
+    server_context::ServerContext,
+    server_event::ServerEvent,
+    user::{user_key::UserKey, User},
+};
+
+/// Handles the events of a Server which is driven by `NaiaServer::process()`,
+/// as an alternative to receiving them one at a time from
+/// `NaiaServer::receive()`. Methods are called synchronously, one event at a
+/// time, in the same order `receive()` would return them, so for each
+/// connection `on_connect()` comes before its first `on_message()`, and
+/// `on_disconnect()` after its last. Every method does nothing unless
+/// implemented
+pub trait ServerEventHandler<T: EventType, U: ActorType> {
+    /// Called when a new Client has established a connection with the Server
+    fn on_connect(
+        &mut self,
+        _context: &mut ServerContext<T, U>,
+        _user_key: UserKey,
+        _connection_info: ConnectionInfo,
+    ) {
+    }
+
+    /// Called when the Server has lost connection to a Client. Nothing more is
+    /// received from it afterwards
+    fn on_disconnect(
+        &mut self,
+        _context: &mut ServerContext<T, U>,
+        _user_key: UserKey,
+        _user: User,
+    ) {
+    }
+
+    /// Called with an Event sent to the Server by a Client
+    fn on_message(&mut self, _context: &mut ServerContext<T, U>, _user_key: UserKey, _event: T) {}
+
+    /// Called once per Tick, for game logic to be run in. Updates made to
+    /// Actors here are sent right after it returns
+    fn on_tick(&mut self, _context: &mut ServerContext<T, U>) {}
+
+    /// Called with an Error which occurred while receiving
+    fn on_error(&mut self, _context: &mut ServerContext<T, U>, _error: NaiaError) {}
+
+    /// Called with every other kind of event, such as Commands or changes in
+    /// a connection's congestion
+    fn on_other(&mut self, _context: &mut ServerContext<T, U>, _event: ServerEvent<T>) {}
+}
@@ -1,6 +1,9 @@
 use byteorder::WriteBytesExt;
 
-use naia_shared::{ActorType, Event, EventPacketWriter, EventType, ManagerType, Manifest};
+use naia_shared::{
+    ActorType, EventPacketWriter, EventType, Instant, ManagerType, Manifest, OutgoingEvent,
+    SchemaVersions,
+};
 
 /// Handles writing of Event & Actor data into an outgoing packet
 pub struct ServerPacketWriter {
@@ -9,22 +12,66 @@ pub struct ServerPacketWriter {
     pub actor_working_bytes: Vec<u8>,
     /// number of Actor messages to be written
     pub actor_message_count: u8,
+    // the frame of the tick payload to be written after everything else, if
+    // the packet carries one
+    tick_payload_frame: Option<Vec<u8>>,
 }
 
 impl ServerPacketWriter {
-    /// Construct a new instance of `PacketReader`, the given `buffer` will be
-    /// used to read information from.
-    pub fn new() -> ServerPacketWriter {
+    /// Construct a new instance of `ServerPacketWriter`, which writes packet
+    /// payloads of up to the given number of bytes
+    pub fn new(max_payload_size: usize) -> ServerPacketWriter {
         ServerPacketWriter {
-            event_writer: EventPacketWriter::new(),
+            event_writer: EventPacketWriter::with_max_payload_size(max_payload_size),
             actor_working_bytes: Vec::<u8>::new(),
             actor_message_count: 0,
+            tick_payload_frame: None,
         }
     }
 
+    /// Construct a new instance of `ServerPacketWriter`, like `new()`, which
+    /// carries the given frame of a tick payload, leaving the rest of the
+    /// packet for Events & Actor messages
+    pub fn with_tick_payload(
+        max_payload_size: usize,
+        tick_payload_frame: Vec<u8>,
+    ) -> ServerPacketWriter {
+        // the manager type is written ahead of the frame
+        let reserved = 1 + tick_payload_frame.len();
+        let mut writer = ServerPacketWriter::new(max_payload_size.saturating_sub(reserved));
+        writer.tick_payload_frame = Some(tick_payload_frame);
+        return writer;
+    }
+
+    /// Writes the frame of a tick payload into the packet, if it has room left
+    /// for it once everything else has been written. Returns whether it was
+    /// written
+    pub fn write_tick_payload(&mut self, tick_payload_frame: &[u8]) -> bool {
+        if self.tick_payload_frame.is_some()
+            || self.bytes_number() + 1 + tick_payload_frame.len() >= self.max_payload_size()
+        {
+            return false;
+        }
+        self.tick_payload_frame = Some(tick_payload_frame.to_vec());
+        return true;
+    }
+
+    /// Returns whether the packet carries the frame of a tick payload
+    pub fn has_tick_payload(&self) -> bool {
+        return self.tick_payload_frame.is_some();
+    }
+
+    /// Sets whether the trace id of each Event is written ahead of its frame,
+    /// once both hosts have agreed to message tracing
+    pub fn set_writes_trace_ids(&mut self, enabled: bool) {
+        self.event_writer.set_writes_trace_ids(enabled);
+    }
+
     /// Returns whether the writer has bytes to write into the outgoing packet
     pub fn has_bytes(&self) -> bool {
-        return self.event_writer.has_bytes() || self.actor_message_count != 0;
+        return self.event_writer.has_bytes()
+            || self.actor_message_count != 0
+            || self.tick_payload_frame.is_some();
     }
 
     /// Gets the bytes to write into an outgoing packet
@@ -44,22 +91,37 @@ impl ServerPacketWriter {
             self.actor_message_count = 0;
         }
 
+        if let Some(mut tick_payload_frame) = self.tick_payload_frame.take() {
+            out_bytes.write_u8(ManagerType::TickPayload as u8).unwrap(); // write manager type
+            out_bytes.append(&mut tick_payload_frame); // write tick payload frame
+        }
+
         out_bytes.into_boxed_slice()
     }
 
     /// Get the number of bytes which is ready to be written into an outgoing
-    /// packet
+    /// packet, leaving out any tick payload
     pub fn bytes_number(&self) -> usize {
         return self.event_writer.bytes_number() + self.actor_working_bytes.len();
     }
 
+    /// Get the largest packet payload the writer will write, in bytes
+    pub fn max_payload_size(&self) -> usize {
+        return self.event_writer.max_payload_size();
+    }
+
     /// Writes an Event into the Writer's internal buffer, which will eventually
-    /// be put into the outgoing packet
+    /// be put into the outgoing packet, as the schema version of its type
+    /// agreed on for the connection, given the current time
     pub fn write_event<T: EventType, U: ActorType>(
         &mut self,
         manifest: &Manifest<T, U>,
-        event: &Box<dyn Event<T>>,
+        schema_versions: &SchemaVersions,
+        event: &OutgoingEvent<T>,
+        now: &Instant,
     ) -> bool {
-        return self.event_writer.write_event(manifest, event);
+        return self
+            .event_writer
+            .write_event(manifest, schema_versions, event, now);
     }
 }
@@ -0,0 +1,28 @@
+use naia_shared::{ActorType, EventType, NaiaError};
+
+use super::{naia_server::NaiaServer, server_event::ServerEvent};
+
+/// Receives events from Clients, as `NaiaServer::receive()` does, while the
+/// ServerSender it was split from the NaiaServer with, by
+/// `NaiaServer::split()`, queues Events in reply
+pub struct ServerReceiver<'s, T: EventType, U: ActorType> {
+    server: &'s mut NaiaServer<T, U>,
+}
+
+impl<'s, T: EventType, U: ActorType> ServerReceiver<'s, T, U> {
+    pub(crate) fn new(server: &'s mut NaiaServer<T, U>) -> Self {
+        ServerReceiver { server }
+    }
+
+    /// Receives the next event, exactly as `NaiaServer::receive()` does
+    pub async fn receive(&mut self) -> Result<ServerEvent<T>, NaiaError> {
+        return self.server.receive().await;
+    }
+
+    /// Sends all Actor/Event messages to all Clients, exactly as
+    /// `NaiaServer::send_all_updates()` does, including the Events queued
+    /// through ServerSenders
+    pub async fn send_all_updates(&mut self) {
+        self.server.send_all_updates().await;
+    }
+}
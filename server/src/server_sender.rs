@@ -0,0 +1,49 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use naia_shared::{ChannelIndex, Event, EventType};
+
+use super::user::user_key::UserKey;
+
+// Events queued through ServerSenders, along with the User they're sent to &
+// the channel they were sent on, if not that registered for their type
+pub(crate) type SplitEventQueue<T> =
+    Rc<RefCell<VecDeque<(UserKey, Box<dyn Event<T>>, Option<ChannelIndex>)>>>;
+
+/// Queues Events to be sent to Clients, while the ServerReceiver it was split
+/// from the NaiaServer with, by `NaiaServer::split()`, is in use. The Events
+/// are handed to the NaiaServer on its next call to `receive()` or
+/// `send_all_updates()`, in the order they were queued, and `receive()`
+/// returns any Error `queue_event()` would have instead. Can be cloned, and
+/// kept after the ServerReceiver is dropped
+#[derive(Clone, Debug)]
+pub struct ServerSender<T: EventType> {
+    queue: SplitEventQueue<T>,
+}
+
+impl<T: EventType> ServerSender<T> {
+    pub(crate) fn new(queue: SplitEventQueue<T>) -> Self {
+        ServerSender { queue }
+    }
+
+    /// Queues up an Event to be sent to the Client associated with a given
+    /// UserKey, on the ordered channel registered for its type in the
+    /// Manifest, if any
+    pub fn queue_event(&self, user_key: &UserKey, event: &impl Event<T>) {
+        self.queue
+            .borrow_mut()
+            .push_back((*user_key, event.clone_box(), None));
+    }
+
+    /// Queues up an Event to be sent to the Client associated with a given
+    /// UserKey, on a specific ordered channel
+    pub fn queue_event_on_channel(
+        &self,
+        user_key: &UserKey,
+        channel_index: ChannelIndex,
+        event: &impl Event<T>,
+    ) {
+        self.queue
+            .borrow_mut()
+            .push_back((*user_key, event.clone_box(), Some(channel_index)));
+    }
+}
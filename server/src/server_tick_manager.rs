@@ -1,23 +1,74 @@
 use std::time::Duration;
 
-use naia_shared::HostTickManager;
+use naia_shared::{HostTickManager, Instant};
 
 /// Manages the current tick for the host
 #[derive(Debug)]
 pub struct ServerTickManager {
     tick_interval: Duration,
     current_tick: u16,
+    last_instant: Instant,
+    accumulator: Duration,
+    pending_ticks: u16,
+    max_catch_up_ticks: u16,
 }
 
 impl ServerTickManager {
-    /// Create a new HostTickManager with a given tick interval duration
-    pub fn new(tick_interval: Duration) -> Self {
+    /// Create a new HostTickManager with a given tick interval duration, and
+    /// the maximum number of ticks which can be queued up at once when the
+    /// host falls behind, which is at least 1, so that the host keeps ticking
+    pub fn new(tick_interval: Duration, max_catch_up_ticks: u16, now: Instant) -> Self {
         ServerTickManager {
             tick_interval,
             current_tick: 0,
+            last_instant: now,
+            accumulator: Duration::from_secs(0),
+            pending_ticks: 0,
+            max_catch_up_ticks: max_catch_up_ticks.max(1),
         }
     }
 
+    /// Accumulates the time elapsed since the last update, queueing up a tick
+    /// for every tick interval that has passed. If the number of queued ticks
+    /// would exceed the catch-up cap, the extra time is discarded, so that a
+    /// host which cannot keep up does not fall further and further behind
+    pub fn update(&mut self, now: Instant) {
+        let elapsed = now
+            .get_inner()
+            .saturating_duration_since(self.last_instant.get_inner());
+        self.last_instant = now;
+        self.accumulator += elapsed;
+
+        if self.tick_interval.as_nanos() == 0 {
+            return;
+        }
+
+        let elapsed_ticks = self.accumulator.as_nanos() / self.tick_interval.as_nanos();
+        let remainder = self.accumulator.as_nanos() % self.tick_interval.as_nanos();
+        self.accumulator = Duration::from_nanos(remainder as u64);
+
+        let max_new_ticks = (self.max_catch_up_ticks - self.pending_ticks) as u128;
+        if elapsed_ticks > max_new_ticks {
+            warn!(
+                "server is running behind, skipping {} ticks",
+                elapsed_ticks - max_new_ticks
+            );
+            self.pending_ticks = self.max_catch_up_ticks;
+        } else {
+            self.pending_ticks += elapsed_ticks as u16;
+        }
+    }
+
+    /// If a tick is queued up, increments the current tick and returns true
+    pub fn take_tick(&mut self) -> bool {
+        if self.pending_ticks > 0 {
+            self.pending_ticks -= 1;
+            self.increment_tick();
+            return true;
+        }
+        return false;
+    }
+
     /// Increments the current tick
     pub fn increment_tick(&mut self) {
         self.current_tick = self.current_tick.wrapping_add(1);
@@ -29,3 +80,81 @@ impl HostTickManager for ServerTickManager {
         self.current_tick
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use naia_shared::{Clock, HostTickManager, SystemClock};
+
+    use super::ServerTickManager;
+
+    fn take_all_ticks(tick_manager: &mut ServerTickManager) -> u16 {
+        let mut ticks = 0;
+        while tick_manager.take_tick() {
+            ticks += 1;
+        }
+        return ticks;
+    }
+
+    #[test]
+    fn ticks_once_per_interval() {
+        let mut now = SystemClock.now();
+        let mut tick_manager = ServerTickManager::new(Duration::from_millis(50), 5, now.clone());
+
+        now.add_millis(40);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 0);
+
+        now.add_millis(10);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 1);
+        assert_eq!(tick_manager.get_tick(), 1);
+    }
+
+    #[test]
+    fn catches_up_after_long_frame() {
+        let mut now = SystemClock.now();
+        let mut tick_manager = ServerTickManager::new(Duration::from_millis(50), 5, now.clone());
+
+        now.add_millis(175);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 3);
+
+        // remainder of the long frame is carried over
+        now.add_millis(25);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 1);
+        assert_eq!(tick_manager.get_tick(), 4);
+    }
+
+    #[test]
+    fn caps_catch_up_ticks() {
+        let mut now = SystemClock.now();
+        let mut tick_manager = ServerTickManager::new(Duration::from_millis(50), 5, now.clone());
+
+        now.add_millis(10_000);
+        tick_manager.update(now.clone());
+        now.add_millis(100);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 5);
+
+        // skipped time is not made up for later
+        now.add_millis(50);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 1);
+    }
+
+    #[test]
+    fn ticks_with_a_catch_up_cap_of_zero() {
+        let mut now = SystemClock.now();
+        let mut tick_manager = ServerTickManager::new(Duration::from_millis(50), 0, now.clone());
+
+        now.add_millis(500);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 1);
+        now.add_millis(50);
+        tick_manager.update(now.clone());
+        assert_eq!(take_all_ticks(&mut tick_manager), 1);
+    }
+}
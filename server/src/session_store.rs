@@ -0,0 +1,213 @@
+use std::{collections::HashMap, time::Duration};
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+use naia_shared::{
+    handshake::{SessionToken, SESSION_TOKEN_BYTES},
+    Instant,
+};
+
+use super::user::user_key::UserKey;
+
+/// Issues the session tokens Clients can resume their connection with, and
+/// keeps track of the connections which have dropped and are waiting to be
+/// resumed. Each token can only be used once, and a new one is issued on
+/// every resumption, so that a token which has been used can't be used again
+pub struct SessionStore {
+    grace_period: Duration,
+    random: SystemRandom,
+    token_to_user: HashMap<SessionToken, UserKey>,
+    user_to_token: HashMap<UserKey, SessionToken>,
+    suspended_until: HashMap<UserKey, Instant>,
+}
+
+impl SessionStore {
+    /// Create a new SessionStore, given how long a dropped connection can be
+    /// resumed for
+    pub fn new(grace_period: Duration) -> Self {
+        SessionStore {
+            grace_period,
+            random: SystemRandom::new(),
+            token_to_user: HashMap::new(),
+            user_to_token: HashMap::new(),
+            suspended_until: HashMap::new(),
+        }
+    }
+
+    /// Issues a new session token for a User, replacing any previous one
+    pub fn issue(&mut self, user_key: &UserKey) -> SessionToken {
+        let mut session_token: SessionToken = [0; SESSION_TOKEN_BYTES];
+        self.random.fill(&mut session_token).unwrap();
+        if let Some(old_token) = self.user_to_token.insert(*user_key, session_token) {
+            self.token_to_user.remove(&old_token);
+        }
+        self.token_to_user.insert(session_token, *user_key);
+        return session_token;
+    }
+
+    /// Returns whether a session token has been issued to the User
+    pub fn has_token(&self, user_key: &UserKey) -> bool {
+        return self.user_to_token.contains_key(user_key);
+    }
+
+    /// Marks the connection of a User as dropped, given the current time. It
+    /// can be resumed until the grace period has passed
+    pub fn suspend(&mut self, user_key: &UserKey, now: &Instant) {
+        let mut expires_at = now.clone();
+        expires_at.add_millis(self.grace_period.as_millis() as u32);
+        self.suspended_until.insert(*user_key, expires_at);
+    }
+
+    /// Returns whether the connection of a User has dropped, and is waiting to
+    /// be resumed
+    pub fn is_suspended(&self, user_key: &UserKey) -> bool {
+        return self.suspended_until.contains_key(user_key);
+    }
+
+    /// Gets the User a session token was issued to, if any, without using the
+    /// token up
+    pub fn get_user(&self, session_token: &SessionToken) -> Option<UserKey> {
+        return self.token_to_user.get(session_token).copied();
+    }
+
+    /// Uses a session token to resume a connection, given the current time.
+    /// Returns the User the token was issued to, unless the token is unknown,
+    /// has already been used, or the grace period of the User's dropped
+    /// connection has passed. The token can't be used again
+    pub fn resume(&mut self, session_token: &SessionToken, now: &Instant) -> Option<UserKey> {
+        let user_key = self.token_to_user.remove(session_token)?;
+        self.user_to_token.remove(&user_key);
+        if let Some(expires_at) = self.suspended_until.remove(&user_key) {
+            if expires_at <= *now {
+                // the connection will be cleaned up along with other expired ones
+                self.suspended_until.insert(user_key, expires_at);
+                return None;
+            }
+        }
+        return Some(user_key);
+    }
+
+    /// Takes every User whose dropped connection can no longer be resumed,
+    /// given the current time
+    pub fn take_expired(&mut self, now: &Instant) -> Vec<UserKey> {
+        let expired: Vec<UserKey> = self
+            .suspended_until
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= *now)
+            .map(|(user_key, _)| *user_key)
+            .collect();
+        for user_key in expired.iter() {
+            self.remove(user_key);
+        }
+        return expired;
+    }
+
+    /// Forgets a User, whose connection has been closed
+    pub fn remove(&mut self, user_key: &UserKey) {
+        if let Some(session_token) = self.user_to_token.remove(user_key) {
+            self.token_to_user.remove(&session_token);
+        }
+        self.suspended_until.remove(user_key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use slotmap::DenseSlotMap;
+
+    use naia_shared::{Clock, Instant, SystemClock};
+
+    use super::SessionStore;
+    use crate::UserKey;
+
+    fn after(now: &Instant, millis: u32) -> Instant {
+        let mut later = now.clone();
+        later.add_millis(millis);
+        return later;
+    }
+
+    #[test]
+    fn resumes_within_grace_period() {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let user_key = users.insert(());
+        let mut session_store = SessionStore::new(Duration::from_secs(10));
+        let now = SystemClock.now();
+
+        let session_token = session_store.issue(&user_key);
+        session_store.suspend(&user_key, &now);
+        assert!(session_store.is_suspended(&user_key));
+
+        let later = after(&now, 5_000);
+        assert!(session_store.take_expired(&later).is_empty());
+        assert_eq!(session_store.resume(&session_token, &later), Some(user_key));
+        assert!(!session_store.is_suspended(&user_key));
+        assert!(session_store.take_expired(&after(&now, 20_000)).is_empty());
+    }
+
+    #[test]
+    fn expires_after_grace_period() {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let user_key = users.insert(());
+        let mut session_store = SessionStore::new(Duration::from_secs(10));
+        let now = SystemClock.now();
+
+        let session_token = session_store.issue(&user_key);
+        session_store.suspend(&user_key, &now);
+
+        let later = after(&now, 10_000);
+        assert_eq!(session_store.resume(&session_token, &later), None);
+        assert_eq!(session_store.take_expired(&later), vec![user_key]);
+        assert!(!session_store.is_suspended(&user_key));
+        assert!(!session_store.has_token(&user_key));
+    }
+
+    #[test]
+    fn looking_up_a_token_keeps_it() {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let user_key = users.insert(());
+        let mut session_store = SessionStore::new(Duration::from_secs(10));
+        let now = SystemClock.now();
+
+        let session_token = session_store.issue(&user_key);
+        session_store.suspend(&user_key, &now);
+        assert_eq!(session_store.get_user(&session_token), Some(user_key));
+        assert_eq!(session_store.get_user(&session_token), Some(user_key));
+        assert!(session_store.is_suspended(&user_key));
+
+        // the token is still there to resume with
+        assert_eq!(session_store.resume(&session_token, &now), Some(user_key));
+        assert_eq!(session_store.get_user(&session_token), None);
+    }
+
+    #[test]
+    fn rejects_invalid_and_reused_tokens() {
+        let mut users: DenseSlotMap<UserKey, ()> = DenseSlotMap::with_key();
+        let user_key = users.insert(());
+        let other_user_key = users.insert(());
+        let mut session_store = SessionStore::new(Duration::from_secs(10));
+        let now = SystemClock.now();
+
+        let session_token = session_store.issue(&user_key);
+        let other_token = session_store.issue(&other_user_key);
+        assert_ne!(session_token, other_token);
+        session_store.suspend(&user_key, &now);
+
+        // a token which was never issued
+        let mut forged_token = session_token;
+        forged_token[0] ^= 0xff;
+        assert_eq!(session_store.resume(&forged_token, &now), None);
+        assert!(session_store.is_suspended(&user_key));
+
+        // the token is rotated on resumption, so a stolen copy is useless
+        assert_eq!(session_store.resume(&session_token, &now), Some(user_key));
+        let new_token = session_store.issue(&user_key);
+        assert_eq!(session_store.resume(&session_token, &now), None);
+
+        // a replaced token can't be used either
+        session_store.issue(&user_key);
+        assert_eq!(session_store.resume(&new_token, &now), None);
+        assert!(session_store.has_token(&other_user_key));
+    }
+}
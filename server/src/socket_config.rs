@@ -0,0 +1,42 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use naia_server_socket::LinkConditionerConfig;
+
+/// The network conditions simulated on the packets the Server receives, shared
+/// with the socket's link conditioner so that they can be changed while the
+/// socket is in use. None lets packets through as they arrive
+pub type SharedLinkCondition = Arc<Mutex<Option<LinkConditionerConfig>>>;
+
+/// Options applied to the Server's socket when it is bound. Only the UDP
+/// transport can apply them: with WebRTC, creating the Server fails if any
+/// differ from the defaults
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SocketConfig {
+    /// Whether to set SO_REUSEADDR, so that a restarted Server can bind its
+    /// address straight away, rather than waiting for the previous socket to
+    /// be released
+    pub reuse_address: bool,
+    /// The size to request for the socket's receive buffer (SO_RCVBUF), in
+    /// bytes. The OS may round it, or cap it to its own limit. None, the
+    /// default, keeps the OS's default size
+    pub receive_buffer_size: Option<usize>,
+    /// The size to request for the socket's send buffer (SO_SNDBUF), in
+    /// bytes. The OS may round it, or cap it to its own limit. None, the
+    /// default, keeps the OS's default size
+    pub send_buffer_size: Option<usize>,
+    /// The local address to bind to, when it differs from the address Clients
+    /// reach the Server at, such as behind NAT or to listen on a single
+    /// interface. Binding to port 0 picks an unused port, which
+    /// `NaiaServer::local_addr()` gives. None, the default, binds to the
+    /// address the Server is created with
+    pub bind_address: Option<SocketAddr>,
+    /// Whether a socket bound to an IPv6 address only accepts IPv6 Clients
+    /// (IPV6_V6ONLY). By default, false, it is set up as dual-stack whatever
+    /// the platform's own default, so that a Server listening at `[::]`
+    /// accepts Clients of both families, IPv4 Clients being seen at
+    /// IPv4-mapped addresses. Ignored for IPv4 addresses
+    pub ipv6_only: bool,
+}
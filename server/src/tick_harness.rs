@@ -0,0 +1,184 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    rc::Rc,
+};
+
+use slotmap::{DenseSlotMap, SecondaryMap};
+
+use naia_shared::{
+    ActorMutator, ActorType, Clock, ConnectionConfig, EventType, Manifest, PacketType,
+    StandardHeader,
+};
+
+use super::{
+    actors::{
+        actor_key::actor_key::ActorKey, mut_handler::MutHandler,
+        server_actor_mutator::ServerActorMutator,
+    },
+    client_connection::ClientConnection,
+    server_config::ServerConfig,
+    tick_summary::TickSummary,
+    user::{user_key::UserKey, User},
+};
+
+/// Runs the replication work of a NaiaServer's tick, as done by
+/// `NaiaServer::send_all_updates()`, for a number of connections which every
+/// Actor is in scope for, without a socket or a handshake. The packets sent
+/// are acknowledged straight away, as a Client with a perfect connection
+/// would, and are matched to their connection by address, as incoming packets
+/// are. Only built with the "bench" feature, for the benchmarks
+pub struct TickHarness<T: EventType, U: ActorType> {
+    manifest: Manifest<T, U>,
+    actors: DenseSlotMap<ActorKey, U>,
+    users: DenseSlotMap<UserKey, User>,
+    address_to_user_key_map: HashMap<SocketAddr, UserKey>,
+    connections: SecondaryMap<UserKey, ClientConnection<T, U>>,
+    server_tick: u16,
+    client_packet_index: u16,
+    summary: TickSummary,
+}
+
+impl<T: EventType, U: ActorType> TickHarness<T, U> {
+    /// Creates a new TickHarness, with the given number of connections, which
+    /// every one of the given Actors is in scope for, configured as by the
+    /// default ServerConfig. Ticks until every connection has been sent the
+    /// whole world
+    pub fn new(manifest: Manifest<T, U>, connection_count: u16, actors: Vec<U>) -> Self {
+        return TickHarness::build(manifest, connection_count, actors, false);
+    }
+
+    /// Creates a new TickHarness like `new()`, with every Actor registered as
+    /// static, as by `NaiaServer::register_static_actor()`
+    pub fn new_static(manifest: Manifest<T, U>, connection_count: u16, actors: Vec<U>) -> Self {
+        return TickHarness::build(manifest, connection_count, actors, true);
+    }
+
+    fn build(
+        manifest: Manifest<T, U>,
+        connection_count: u16,
+        actors: Vec<U>,
+        is_static: bool,
+    ) -> Self {
+        let server_config = ServerConfig::default();
+        let connection_config = ConnectionConfig::default();
+        let mut_handler = MutHandler::new();
+
+        let mut actor_store = DenseSlotMap::with_key();
+        for actor in actors {
+            let mutator_ref = Rc::new(RefCell::new(ServerActorMutator::new(&mut_handler)));
+            let actor_mutator: Rc<RefCell<dyn ActorMutator>> = mutator_ref.clone();
+            actor
+                .inner_ref()
+                .as_ref()
+                .borrow_mut()
+                .set_mutator(&actor_mutator);
+            let mut payload = Vec::new();
+            if is_static {
+                actor.inner_ref().as_ref().borrow().write(&mut payload);
+            }
+            let actor_key = actor_store.insert(actor);
+            mutator_ref.as_ref().borrow_mut().set_actor_key(actor_key);
+            mut_handler.borrow_mut().register_actor(&actor_key);
+            if is_static {
+                mut_handler
+                    .borrow_mut()
+                    .set_static(&actor_key, payload.into_boxed_slice());
+            }
+        }
+
+        let mut users = DenseSlotMap::with_key();
+        let mut address_to_user_key_map = HashMap::new();
+        let mut connections = SecondaryMap::new();
+        for index in 0..connection_count {
+            let address = SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                10000u16.wrapping_add(index),
+            );
+            let mut connection = ClientConnection::new(
+                address,
+                Some(&mut_handler),
+                &connection_config,
+                &server_config,
+            );
+            connection.begin_world_sync();
+            for (actor_key, actor) in actor_store.iter() {
+                connection.add_actor(&actor_key, &actor.inner_ref());
+            }
+            let user_key = users.insert(User::new(address, connection_config.clock.timestamp()));
+            address_to_user_key_map.insert(address, user_key);
+            connections.insert(user_key, connection);
+        }
+
+        let mut harness = TickHarness {
+            manifest,
+            actors: actor_store,
+            users,
+            address_to_user_key_map,
+            connections,
+            server_tick: 0,
+            client_packet_index: 0,
+            summary: TickSummary::default(),
+        };
+        let mut synced_count = 0;
+        while synced_count < harness.connections.len() {
+            harness.tick();
+            for connection in harness.connections.values_mut() {
+                if connection.take_world_sync_complete() {
+                    synced_count += 1;
+                }
+            }
+        }
+        return harness;
+    }
+
+    /// Gets an iterator over every Actor, to make changes to before a tick
+    pub fn actors_iter(&self) -> slotmap::dense::Iter<'_, ActorKey, U> {
+        return self.actors.iter();
+    }
+
+    /// Runs a tick, collecting the changes made to the Actors & writing them
+    /// into packets for every connection, returning a summary of the work
+    pub fn tick(&mut self) -> &TickSummary {
+        self.server_tick = self.server_tick.wrapping_add(1);
+        self.summary = TickSummary::default();
+        self.summary.tick = self.server_tick;
+        for (user_key, user) in self.users.iter() {
+            let connection = match self.connections.get_mut(user_key) {
+                Some(connection) => connection,
+                None => continue,
+            };
+            connection.collect_actor_updates(self.server_tick, &mut self.summary);
+            self.summary.connections_serviced += 1;
+            let mut last_packet_index = None;
+            while let Some(payload) =
+                connection.get_outgoing_packet(self.server_tick, &self.manifest)
+            {
+                self.summary.packets_sent += 1;
+                self.summary.bytes_written += payload.len();
+                let (header, _) = StandardHeader::read(&payload);
+                last_packet_index = Some(header.local_packet_index());
+                connection.mark_sent();
+            }
+            // acknowledge every packet sent, from the connection's address
+            if let Some(last_packet_index) = last_packet_index {
+                let ack_header = StandardHeader::new(
+                    PacketType::Heartbeat,
+                    self.client_packet_index,
+                    last_packet_index,
+                    u32::MAX,
+                    0,
+                    self.server_tick,
+                );
+                if let Some(user_key) = self.address_to_user_key_map.get(&user.address) {
+                    if let Some(connection) = self.connections.get_mut(*user_key) {
+                        connection.process_incoming_header(&ack_header);
+                    }
+                }
+            }
+        }
+        self.client_packet_index = self.client_packet_index.wrapping_add(1);
+        return &self.summary;
+    }
+}
@@ -0,0 +1,109 @@
+use naia_shared::write_tick_payload_frame;
+
+/// Holds the frame of the payload set for the current tick, to be carried by
+/// each packet sent to a Client during the tick, the first of which is sent
+/// for the payload alone if need be
+pub struct TickPayloadSender {
+    // the tick the payload was set for, and its frame
+    current: Option<(u16, Vec<u8>)>,
+    // whether the frame has been written into a packet yet
+    written: bool,
+    // the tick of the last payload written into a packet, which each frame
+    // points back to, so the Client can tell which ticks it has missed
+    last_written_tick: Option<u16>,
+}
+
+impl TickPayloadSender {
+    /// Creates a TickPayloadSender, which has sent no payloads yet
+    pub fn new() -> Self {
+        TickPayloadSender {
+            current: None,
+            written: false,
+            last_written_tick: None,
+        }
+    }
+
+    /// Sets the payload of a tick, given the NaiaId of its Event type & its
+    /// bytes, unless one has been set for the tick already
+    pub fn set(&mut self, tick: u16, naia_id: u16, payload: &[u8]) {
+        if let Some((current_tick, _)) = &self.current {
+            if *current_tick == tick {
+                return;
+            }
+            if self.written {
+                self.last_written_tick = Some(*current_tick);
+            }
+        }
+        let mut frame = Vec::new();
+        write_tick_payload_frame(tick, self.last_written_tick, naia_id, payload, &mut frame);
+        self.current = Some((tick, frame));
+        self.written = false;
+    }
+
+    /// Gets the frame of the payload set for the given tick, along with whether
+    /// it has been written into a packet yet
+    pub fn get_frame(&self, tick: u16) -> Option<(&[u8], bool)> {
+        match &self.current {
+            Some((current_tick, frame)) if *current_tick == tick => {
+                return Some((frame, self.written));
+            }
+            _ => return None,
+        }
+    }
+
+    /// Records that the frame of the current payload has been written into a
+    /// packet
+    pub fn mark_written(&mut self) {
+        self.written = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{read_tick_payload_frame, PacketReader};
+
+    use super::TickPayloadSender;
+
+    fn previous_tick(sender: &TickPayloadSender, tick: u16) -> Option<u16> {
+        let (frame, _) = sender.get_frame(tick).unwrap();
+        return read_tick_payload_frame(&mut PacketReader::new(frame))
+            .unwrap()
+            .previous_tick;
+    }
+
+    #[test]
+    fn frames_point_back_to_the_last_payload_written() {
+        let mut sender = TickPayloadSender::new();
+        sender.set(10, 1, &[10]);
+        assert_eq!(previous_tick(&sender, 10), None);
+        assert_eq!(
+            sender.get_frame(10).map(|(_, written)| written),
+            Some(false)
+        );
+        sender.mark_written();
+        assert_eq!(sender.get_frame(10).map(|(_, written)| written), Some(true));
+
+        // never written, so the next frame still points back to tick 10
+        sender.set(11, 1, &[11]);
+        assert_eq!(previous_tick(&sender, 11), Some(10));
+        sender.set(12, 1, &[12]);
+        assert_eq!(previous_tick(&sender, 12), Some(10));
+        assert!(sender.get_frame(11).is_none());
+    }
+
+    #[test]
+    fn a_tick_keeps_the_first_payload_set() {
+        let mut sender = TickPayloadSender::new();
+        sender.set(10, 1, &[1]);
+        sender.mark_written();
+        sender.set(10, 1, &[2]);
+        let (frame, written) = sender.get_frame(10).unwrap();
+        assert!(written);
+        assert_eq!(
+            read_tick_payload_frame(&mut PacketReader::new(frame))
+                .unwrap()
+                .payload,
+            &[1]
+        );
+    }
+}
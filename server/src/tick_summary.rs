@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+/// A summary of the replication work done in a single server tick, across
+/// every call to `NaiaServer::send_all_updates()` made during it, meant to be
+/// cheap enough to log or export every tick
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TickSummary {
+    /// The server tick the summary is of
+    pub tick: u16,
+    /// The number of Actors with changes not yet sent, counted once for every
+    /// Client they are in scope for, including those held back by their
+    /// update rate
+    pub actors_dirty: usize,
+    /// The number of Actor & Pawn updates queued to be sent
+    pub updates_emitted: usize,
+    /// The number of packets sent
+    pub packets_sent: usize,
+    /// The total size in bytes of the packets sent
+    pub bytes_written: usize,
+    /// The number of connections which were sent updates
+    pub connections_serviced: usize,
+    /// The number of queued Events dropped after waiting longer than their
+    /// expiry
+    pub events_expired: usize,
+    /// The number of despawned Actors forgotten after their deletion went
+    /// unacknowledged for too long
+    pub despawns_expired: usize,
+    /// The total time spent sending updates
+    pub replication_time: Duration,
+}
+
+impl TickSummary {
+    pub(crate) fn new(tick: u16) -> Self {
+        TickSummary {
+            tick,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    io::{Error as IoError, Result as IoResult},
+    net::{SocketAddr, UdpSocket},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_io::{Async, Timer};
+use async_trait::async_trait;
+use futures_channel::mpsc;
+use futures_util::{pin_mut, select, FutureExt, StreamExt};
+use socket2::{Domain, Socket, Type};
+
+use naia_server_socket::{
+    LinkConditionerConfig, MessageSender, NaiaServerSocketError, Packet, ServerSocketTrait,
+};
+use naia_socket_shared::{link_condition_logic, TimeQueue};
+
+use super::socket_config::{SharedLinkCondition, SocketConfig};
+
+const CLIENT_CHANNEL_SIZE: usize = 8;
+
+/// A UDP socket which the Server sends & receives through, bound with the
+/// options in a SocketConfig applied
+#[derive(Debug)]
+pub struct UdpServerSocket {
+    socket: Async<UdpSocket>,
+    to_client_sender: mpsc::Sender<Packet>,
+    to_client_receiver: mpsc::Receiver<Packet>,
+    receive_buffer: Vec<u8>,
+}
+
+impl UdpServerSocket {
+    /// Binds a new UdpServerSocket to the given address, applying the options
+    /// in the SocketConfig first, along with a DSCP value to mark outgoing
+    /// packets with. Returns an Error if any of them can't be applied
+    pub fn bind(address: SocketAddr, config: &SocketConfig, dscp: Option<u8>) -> IoResult<Self> {
+        let socket = Socket::new(Domain::for_address(address), Type::DGRAM, None)?;
+        if address.is_ipv6() {
+            socket.set_only_v6(config.ipv6_only)?;
+        }
+        if config.reuse_address {
+            socket.set_reuse_address(true)?;
+        }
+        if let Some(size) = config.receive_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = config.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(dscp) = dscp {
+            // the DSCP is the upper six bits of the IPv4 type of service
+            socket.set_tos((dscp as u32) << 2)?;
+        }
+        socket.bind(&address.into())?;
+
+        let (to_client_sender, to_client_receiver) = mpsc::channel(CLIENT_CHANNEL_SIZE);
+        return Ok(UdpServerSocket {
+            socket: Async::new(UdpSocket::from(socket))?,
+            to_client_sender,
+            to_client_receiver,
+            receive_buffer: vec![0; 0x10000],
+        });
+    }
+
+    /// Gets the address the socket is bound to, with the port the OS picked
+    /// if it was bound to port 0
+    pub fn local_addr(&self) -> IoResult<SocketAddr> {
+        return self.socket.get_ref().local_addr();
+    }
+
+    /// Wraps the socket in a link conditioner simulating the given network
+    /// conditions, if any, which can be changed through the returned
+    /// SharedLinkCondition while the socket is in use
+    #[cfg(feature = "link-conditioner")]
+    pub fn with_live_link_conditioner(
+        self,
+        config: Option<LinkConditionerConfig>,
+    ) -> (Box<dyn ServerSocketTrait>, SharedLinkCondition) {
+        let link_condition = Arc::new(Mutex::new(config));
+        let socket = Box::new(LinkConditioner {
+            config: link_condition.clone(),
+            inner_socket: Box::new(self),
+            time_queue: TimeQueue::new(),
+        });
+        return (socket, link_condition);
+    }
+}
+
+#[async_trait]
+impl ServerSocketTrait for UdpServerSocket {
+    async fn receive(&mut self) -> Result<Packet, NaiaServerSocketError> {
+        enum Next {
+            FromClientMessage(Result<(usize, SocketAddr), IoError>),
+            ToClientMessage(Packet),
+        }
+
+        loop {
+            let next = {
+                let to_client_receiver_next = self.to_client_receiver.next().fuse();
+                pin_mut!(to_client_receiver_next);
+
+                let from_client_message_next =
+                    self.socket.recv_from(&mut self.receive_buffer).fuse();
+                pin_mut!(from_client_message_next);
+
+                select! {
+                    from_client_result = from_client_message_next => {
+                        Next::FromClientMessage(from_client_result)
+                    }
+                    to_client_message = to_client_receiver_next => {
+                        Next::ToClientMessage(
+                            to_client_message.expect("to client message receiver closed")
+                        )
+                    }
+                }
+            };
+
+            match next {
+                Next::FromClientMessage(Ok((message_len, message_address))) => {
+                    let payload: Box<[u8]> = self.receive_buffer[0..message_len].into();
+                    return Ok(Packet::new_raw(message_address, payload));
+                }
+                Next::FromClientMessage(Err(error)) => {
+                    return Err(NaiaServerSocketError::Wrapped(Box::new(error)));
+                }
+                Next::ToClientMessage(packet) => {
+                    let address = packet.address();
+                    if self
+                        .socket
+                        .send_to(packet.payload(), address)
+                        .await
+                        .is_err()
+                    {
+                        return Err(NaiaServerSocketError::SendError(address));
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_sender(&mut self) -> MessageSender {
+        return MessageSender::new(self.to_client_sender.clone());
+    }
+
+    fn with_link_conditioner(
+        self: Box<Self>,
+        config: &LinkConditionerConfig,
+    ) -> Box<dyn ServerSocketTrait> {
+        return Box::new(LinkConditioner {
+            config: Arc::new(Mutex::new(Some(config.clone()))),
+            inner_socket: self,
+            time_queue: TimeQueue::new(),
+        });
+    }
+}
+
+// holds back incoming packets to simulate the configured network conditions,
+// as naia-server-socket's own link conditioner does for its sockets. Packets
+// received while no conditions are set are let through as they arrive, while
+// those already held back are still let through once due
+struct LinkConditioner {
+    config: SharedLinkCondition,
+    inner_socket: Box<dyn ServerSocketTrait>,
+    time_queue: TimeQueue<Packet>,
+}
+
+#[async_trait]
+impl ServerSocketTrait for LinkConditioner {
+    async fn receive(&mut self) -> Result<Packet, NaiaServerSocketError> {
+        loop {
+            let next_due = match self.time_queue.peek_entry() {
+                Some(entry) => Timer::at(entry.instant.get_inner()),
+                // nothing is held back, so only the socket is waited on
+                None => Timer::after(Duration::from_secs(60 * 60 * 24)),
+            };
+            let buffered_next = FutureExt::fuse(next_due);
+            pin_mut!(buffered_next);
+
+            let socket_next = self.inner_socket.receive().fuse();
+            pin_mut!(socket_next);
+
+            select! {
+                socket_result = socket_next => {
+                    let packet = socket_result?;
+                    let config = self.config.lock().unwrap().clone();
+                    match config {
+                        Some(config) => {
+                            link_condition_logic::process_packet(
+                                &config,
+                                &mut self.time_queue,
+                                packet,
+                            );
+                        }
+                        None => return Ok(packet),
+                    }
+                }
+                _ = buffered_next => {
+                    if let Some(packet) = self.time_queue.pop_item() {
+                        return Ok(packet);
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_sender(&mut self) -> MessageSender {
+        return self.inner_socket.get_sender();
+    }
+
+    fn with_link_conditioner(
+        self: Box<Self>,
+        config: &LinkConditionerConfig,
+    ) -> Box<dyn ServerSocketTrait> {
+        return Box::new(LinkConditioner {
+            config: Arc::new(Mutex::new(Some(config.clone()))),
+            inner_socket: self,
+            time_queue: TimeQueue::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+
+    use async_io::block_on;
+    use naia_server_socket::ServerSocketTrait;
+    use socket2::SockRef;
+
+    use super::UdpServerSocket;
+    use crate::SocketConfig;
+
+    #[test]
+    fn options_are_applied_at_bind() {
+        let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let default_socket =
+            UdpServerSocket::bind(address, &SocketConfig::default(), None).unwrap();
+        let default_ref = SockRef::from(default_socket.socket.get_ref());
+        let config = SocketConfig {
+            reuse_address: true,
+            receive_buffer_size: Some(8192),
+            send_buffer_size: Some(8192),
+            bind_address: None,
+            ipv6_only: false,
+        };
+        let socket = UdpServerSocket::bind(address, &config, None).unwrap();
+        let socket_ref = SockRef::from(socket.socket.get_ref());
+
+        // an unused port was picked
+        assert_ne!(socket.local_addr().unwrap().port(), 0);
+        assert!(socket_ref.reuse_address().unwrap());
+        assert!(!default_ref.reuse_address().unwrap());
+        // the OS may grant more than was asked for, as Linux does to leave
+        // room for its own bookkeeping, but not the default size
+        let receive_buffer_size = socket_ref.recv_buffer_size().unwrap();
+        assert!(receive_buffer_size >= 8192);
+        assert_ne!(receive_buffer_size, default_ref.recv_buffer_size().unwrap());
+        let send_buffer_size = socket_ref.send_buffer_size().unwrap();
+        assert!(send_buffer_size >= 8192);
+        assert_ne!(send_buffer_size, default_ref.send_buffer_size().unwrap());
+    }
+
+    // whether an IPv6 socket can be bound to the loopback address, which it
+    // can't on platforms without IPv6 support
+    fn ipv6_supported() -> bool {
+        return std::net::UdpSocket::bind("[::1]:0").is_ok();
+    }
+
+    // sends a packet from a socket bound to the given address to the server
+    // socket, returning the address the server saw it come from
+    fn receive_from(socket: &mut UdpServerSocket, client_address: &str) -> SocketAddr {
+        let client = std::net::UdpSocket::bind(client_address).unwrap();
+        let mut server_address = socket.local_addr().unwrap();
+        server_address.set_ip(client.local_addr().unwrap().ip());
+        client.send_to(&[1, 2, 3], server_address).unwrap();
+        let packet = block_on(socket.receive()).unwrap();
+        assert_eq!(packet.payload(), &[1, 2, 3]);
+        return packet.address();
+    }
+
+    #[test]
+    fn unspecified_ipv6_address_is_dual_stack() {
+        if !ipv6_supported() {
+            return;
+        }
+        let address: SocketAddr = "[::]:0".parse().unwrap();
+        let mut socket = UdpServerSocket::bind(address, &SocketConfig::default(), None).unwrap();
+        assert!(!SockRef::from(socket.socket.get_ref()).only_v6().unwrap());
+
+        let from_v6 = receive_from(&mut socket, "[::1]:0");
+        assert_eq!(from_v6.ip(), "::1".parse::<IpAddr>().unwrap());
+        // IPv4 Clients are seen at IPv4-mapped addresses
+        let from_v4 = receive_from(&mut socket, "127.0.0.1:0");
+        assert_eq!(from_v4.ip(), "::ffff:127.0.0.1".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn ipv6_only_is_applied_at_bind() {
+        if !ipv6_supported() {
+            return;
+        }
+        let address: SocketAddr = "[::]:0".parse().unwrap();
+        let config = SocketConfig {
+            ipv6_only: true,
+            ..SocketConfig::default()
+        };
+        let socket = UdpServerSocket::bind(address, &config, None).unwrap();
+        assert!(SockRef::from(socket.socket.get_ref()).only_v6().unwrap());
+    }
+
+    #[test]
+    fn dscp_is_applied_at_bind() {
+        let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let unmarked = UdpServerSocket::bind(address, &SocketConfig::default(), None).unwrap();
+        assert_eq!(SockRef::from(unmarked.socket.get_ref()).tos().unwrap(), 0);
+        // expedited forwarding
+        let mut marked =
+            UdpServerSocket::bind(address, &SocketConfig::default(), Some(46)).unwrap();
+        assert_eq!(
+            SockRef::from(marked.socket.get_ref()).tos().unwrap(),
+            46 << 2
+        );
+
+        // marked sockets still receive over loopback
+        receive_from(&mut marked, "127.0.0.1:0");
+    }
+
+    #[test]
+    fn bind_failure_is_returned() {
+        let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let first = UdpServerSocket::bind(address, &SocketConfig::default(), None).unwrap();
+        let taken = first.local_addr().unwrap();
+        assert!(UdpServerSocket::bind(taken, &SocketConfig::default(), None).is_err());
+    }
+}
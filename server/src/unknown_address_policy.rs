@@ -0,0 +1,11 @@
+/// Determines what the Server does with packets, other than handshake
+/// requests, from addresses which have no connection
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnknownAddressPolicy {
+    /// Drop the packet silently
+    Drop,
+    /// Drop the packet, replying with a "not connected" message at a limited
+    /// rate, so that a Client whose connection has been dropped by the Server
+    /// can start a new handshake without waiting to time out
+    NotifyNotConnected,
+}
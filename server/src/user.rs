@@ -1,5 +1,7 @@
 use crate::naia_server::Timestamp;
-use std::net::SocketAddr;
+use std::{any::Any, net::SocketAddr};
+
+use slotmap::KeyData;
 
 #[allow(missing_docs)]
 #[allow(unused_doc_comments)]
@@ -8,14 +10,130 @@ pub mod user_key {
     new_key_type! { pub struct UserKey; }
 }
 
-#[derive(Clone)]
+use user_key::UserKey;
+
+/// Gets the connection id of a User, a number which can be used to refer to
+/// the User outside of the Server, for example in admin tooling
+pub fn get_user_connection_id(key: &UserKey) -> u64 {
+    return KeyData::from(*key).as_ffi();
+}
+
+/// Gets the UserKey a connection id refers to. The User may no longer exist
+pub fn get_connection_id_user_key(connection_id: u64) -> UserKey {
+    return KeyData::from_ffi(connection_id).into();
+}
+
+/// A Client which has connected to the Server
 pub struct User {
+    /// The address the Client is connected from
     pub address: SocketAddr,
+    /// The Timestamp the Client sent along with its connection request
     pub timestamp: Timestamp,
+    data: Option<Box<dyn Any>>,
 }
 
 impl User {
-    pub fn new(address: SocketAddr, timestamp: Timestamp) -> User {
-        User { address, timestamp }
+    pub(crate) fn new(address: SocketAddr, timestamp: Timestamp) -> User {
+        User {
+            address,
+            timestamp,
+            data: None,
+        }
+    }
+
+    /// Gets the data attached to the User with `NaiaServer::set_user_data()`,
+    /// if it is of the given type. The data is kept until the User is
+    /// dropped, so it can still be read from the User handed out with a
+    /// Disconnection event
+    pub fn data<D: 'static>(&self) -> Option<&D> {
+        return self.data.as_ref()?.downcast_ref::<D>();
+    }
+
+    /// Gets the data attached to the User mutably, if it is of the given type
+    pub fn data_mut<D: 'static>(&mut self) -> Option<&mut D> {
+        return self.data.as_mut()?.downcast_mut::<D>();
+    }
+
+    pub(crate) fn set_data<D: 'static>(&mut self, data: D) {
+        self.data = Some(Box::new(data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, net::SocketAddr, rc::Rc};
+
+    use slotmap::{DenseSlotMap, SecondaryMap};
+
+    use super::{get_connection_id_user_key, get_user_connection_id, user_key::UserKey, User};
+    use naia_shared::{Clock, SystemClock};
+
+    // counts how many times it has been dropped
+    struct Tracked(Rc<Cell<u32>>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    fn new_user() -> User {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        return User::new(address, SystemClock.timestamp());
+    }
+
+    #[test]
+    fn data_is_dropped_exactly_once() {
+        let replaced_drops = Rc::new(Cell::new(0));
+        let drops = Rc::new(Cell::new(0));
+        let mut users: DenseSlotMap<UserKey, User> = DenseSlotMap::with_key();
+        let user_key = users.insert(new_user());
+
+        let user = users.get_mut(user_key).unwrap();
+        user.set_data(Tracked(replaced_drops.clone()));
+        user.set_data(Tracked(drops.clone()));
+        assert_eq!(replaced_drops.get(), 1);
+
+        // only readable as the type it was set as
+        let user = users.get_mut(user_key).unwrap();
+        assert!(user.data::<u32>().is_none());
+        assert!(user.data_mut::<Tracked>().is_some());
+
+        // removing the User hands it out, data and all, as every disconnect
+        // path does with the Disconnection event
+        let removed_user = users.remove(user_key).unwrap();
+        assert_eq!(drops.get(), 0);
+        assert!(removed_user.data::<Tracked>().is_some());
+        drop(removed_user);
+        assert_eq!(drops.get(), 1);
+        drop(users);
+        assert_eq!(replaced_drops.get(), 1);
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn stale_keys_miss_reused_slots() {
+        let mut users: DenseSlotMap<UserKey, User> = DenseSlotMap::with_key();
+        let mut connections: SecondaryMap<UserKey, u32> = SecondaryMap::new();
+        let stale_key = users.insert(new_user());
+        connections.insert(stale_key, 1);
+        let stale_connection_id = get_user_connection_id(&stale_key);
+
+        // the User disconnects, & another connects into the same slot, as
+        // stored by the Server
+        users.remove(stale_key);
+        connections.remove(stale_key);
+        let user_key = users.insert(new_user());
+        connections.insert(user_key, 2);
+        assert_ne!(user_key, stale_key);
+
+        // the stale key, or the connection id given out for it, finds neither
+        // the new User nor its connection
+        let stale_key = get_connection_id_user_key(stale_connection_id);
+        assert!(users.get(stale_key).is_none());
+        assert!(connections.get(stale_key).is_none());
+        assert_eq!(connections.insert(stale_key, 3), None);
+        assert_eq!(connections.get(user_key), Some(&2));
+        assert_eq!(connections.len(), 1);
     }
 }
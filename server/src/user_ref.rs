@@ -0,0 +1,158 @@
+use std::{cell::RefCell, collections::VecDeque, net::SocketAddr, time::Duration};
+
+use naia_shared::{ActorType, ConnectionInfo, EventType};
+
+#[cfg(feature = "diagnostics")]
+use super::connection_histograms::ConnectionHistograms;
+use super::{
+    client_connection::ClientConnection,
+    user::{get_user_connection_id, user_key::UserKey, User},
+};
+
+/// A read-only view of a connected User and the metadata of its connection,
+/// intended for admin & monitoring tooling
+pub struct UserRef<'s, T: EventType, U: ActorType> {
+    key: UserKey,
+    user: &'s User,
+    connection: &'s ClientConnection<T, U>,
+    kick_queue: &'s RefCell<VecDeque<(UserKey, String)>>,
+}
+
+impl<'s, T: EventType, U: ActorType> UserRef<'s, T, U> {
+    pub(crate) fn new(
+        key: UserKey,
+        user: &'s User,
+        connection: &'s ClientConnection<T, U>,
+        kick_queue: &'s RefCell<VecDeque<(UserKey, String)>>,
+    ) -> Self {
+        UserRef {
+            key,
+            user,
+            connection,
+            kick_queue,
+        }
+    }
+
+    /// Get the UserKey of the User
+    pub fn key(&self) -> UserKey {
+        return self.key;
+    }
+
+    /// Get the connection id of the User, which can be passed back to
+    /// `NaiaServer::get_user_ref_by_connection_id()`
+    pub fn connection_id(&self) -> u64 {
+        return get_user_connection_id(&self.key);
+    }
+
+    /// Get the address of the User
+    pub fn address(&self) -> SocketAddr {
+        return self.user.address;
+    }
+
+    /// Get the time elapsed since the connection was established
+    pub fn uptime(&self) -> Duration {
+        return self.connection.get_uptime();
+    }
+
+    /// Get the average Round Trip Time measured to the User, in milliseconds
+    pub fn rtt(&self) -> f32 {
+        return self.connection.get_rtt();
+    }
+
+    /// Get the size of the largest packet which will be sent to the User, in
+    /// bytes. This starts at 508 bytes, and is raised as larger packets are
+    /// confirmed to reach the User if `ServerConfig::mtu_discovery` is enabled
+    pub fn mtu(&self) -> usize {
+        return self.connection.get_mtu();
+    }
+
+    /// Get the parameters the User's connection is running with
+    pub fn connection_info(&self) -> ConnectionInfo {
+        return self.connection.get_connection_info();
+    }
+
+    /// Get the total number of bytes sent to the User
+    pub fn bytes_sent(&self) -> u64 {
+        return self.connection.get_bytes_sent();
+    }
+
+    /// Get how many of the bytes sent to the User were padding, added as
+    /// configured by `ServerConfig::packet_padding`
+    pub fn padding_bytes_sent(&self) -> u64 {
+        return self.connection.get_padding_bytes_sent();
+    }
+
+    /// Get how many of the bytes sent to the User were in cover heartbeats,
+    /// sent as configured by `ServerConfig::cover_heartbeat_interval`
+    pub fn cover_heartbeat_bytes_sent(&self) -> u64 {
+        return self.connection.get_cover_heartbeat_bytes_sent();
+    }
+
+    /// Get the total number of bytes received from the User
+    pub fn bytes_received(&self) -> u64 {
+        return self.connection.get_bytes_received();
+    }
+
+    /// Get the number of Commands from the User which have been rejected by a
+    /// validation function registered with `NaiaServer::on_validate_command()`
+    pub fn rejected_commands(&self) -> u64 {
+        return self.connection.get_rejected_command_count();
+    }
+
+    /// Get the number of packets from the User whose index was too far ahead
+    /// of the last one received to be recorded, which may indicate that
+    /// packets are being forged or replayed
+    pub fn far_ahead_packets(&self) -> u64 {
+        return self.connection.get_far_ahead_packet_count();
+    }
+
+    /// Get the number of bytes allocated for the protocol buffers of the
+    /// User's connection: the record of packets received from it, and the
+    /// buffers of each ordered channel, which are only allocated once the
+    /// channel is first used, so that idle connections stay small
+    pub fn protocol_memory(&self) -> usize {
+        return self.connection.get_protocol_memory();
+    }
+
+    /// Get the number of Events queued for the User which were dropped instead
+    /// of sent, after waiting longer than the expiry set for their type with
+    /// `Manifest::set_event_expiry()`
+    pub fn expired_events(&self) -> u64 {
+        return self.connection.get_expired_event_count();
+    }
+
+    /// Get the number of despawned Actors the User never acknowledged the
+    /// deletion of, which were forgotten after waiting for as long as set by
+    /// `ServerConfig::despawn_retention_rtts` & `despawn_retention_grace`
+    pub fn expired_despawns(&self) -> u64 {
+        return self.connection.get_expired_despawn_count();
+    }
+
+    /// Get the distributions of packet sizes, inter-arrival times & processing
+    /// times on the User's connection. Log them compactly with
+    /// `Histogram::sparkline()`
+    #[cfg(feature = "diagnostics")]
+    pub fn histograms(&self) -> &ConnectionHistograms {
+        return self.connection.get_histograms();
+    }
+
+    /// Get the auth Event the User was accepted with, if the Server has an
+    /// auth function registered
+    pub fn auth_event(&self) -> Option<&T> {
+        return self.connection.get_auth_event();
+    }
+
+    /// Get the data attached to the User with `NaiaServer::set_user_data()`,
+    /// if it is of the given type
+    pub fn user_data<D: 'static>(&self) -> Option<&D> {
+        return self.user.data::<D>();
+    }
+
+    /// Disconnects the User, letting the Client know the reason why. The
+    /// Disconnection event will be returned from `NaiaServer::receive()`
+    pub fn kick(&self, reason: &str) {
+        self.kick_queue
+            .borrow_mut()
+            .push_back((self.key, reason.to_string()));
+    }
+}
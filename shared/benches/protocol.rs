@@ -0,0 +1,274 @@
+//! Benchmarks of the protocol's per-packet hot paths, run with
+//! `cargo bench -p naia-shared`. See `benches/README.md` for comparing against
+//! the committed baseline
+
+use std::rc::Rc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use naia_shared::{
+    varint, Actor, Clock, Event, EventPacketWriter, Manifest, OutgoingEvent, PacketReader,
+    PacketType, SchemaVersions, SequenceBuffer, StandardHeader, StateMask, SystemClock,
+};
+
+mod bench_event {
+    use naia_derive::EventType;
+
+    use super::small_event::SmallEvent;
+
+    #[derive(EventType, Clone)]
+    pub enum BenchEvent {
+        SmallEvent(SmallEvent),
+    }
+}
+
+mod small_event {
+    use naia_derive::Event;
+    use naia_shared::{Event, Property};
+
+    use super::bench_event::BenchEvent;
+
+    #[derive(Event, Clone)]
+    #[type_name = "BenchEvent"]
+    pub struct SmallEvent {
+        pub value: Property<u8>,
+    }
+
+    impl SmallEvent {
+        fn is_guaranteed() -> bool {
+            false
+        }
+    }
+}
+
+mod bench_actor {
+    use std::{cell::RefCell, rc::Rc};
+
+    use naia_derive::ActorType;
+
+    use super::wide_actor::WideActor;
+
+    #[derive(ActorType, Clone)]
+    pub enum BenchActor {
+        WideActor(Rc<RefCell<WideActor>>),
+    }
+}
+
+mod wide_actor {
+    use naia_derive::Actor;
+    use naia_shared::{Actor, Property};
+
+    use super::bench_actor::BenchActor;
+
+    #[derive(Actor)]
+    #[type_name = "BenchActor"]
+    pub struct WideActor {
+        pub p00: Property<u16>,
+        pub p01: Property<u16>,
+        pub p02: Property<u16>,
+        pub p03: Property<u16>,
+        pub p04: Property<u16>,
+        pub p05: Property<u16>,
+        pub p06: Property<u16>,
+        pub p07: Property<u16>,
+        pub p08: Property<u16>,
+        pub p09: Property<u16>,
+        pub p10: Property<u16>,
+        pub p11: Property<u16>,
+        pub p12: Property<u16>,
+        pub p13: Property<u16>,
+        pub p14: Property<u16>,
+        pub p15: Property<u16>,
+        pub p16: Property<u16>,
+        pub p17: Property<u16>,
+        pub p18: Property<u16>,
+        pub p19: Property<u16>,
+    }
+
+    impl WideActor {
+        pub fn new() -> WideActor {
+            return WideActor::new_complete(
+                0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+            );
+        }
+    }
+}
+
+use bench_actor::BenchActor;
+use bench_event::BenchEvent;
+use small_event::SmallEvent;
+use wide_actor::WideActor;
+
+// The capacities SequenceBuffers are benchmarked at, from that of an ordered
+// channel's window to that of the packet records
+const SEQUENCE_BUFFER_CAPACITIES: [u16; 3] = [64, 256, 1024];
+
+fn sequence_buffer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sequence_buffer");
+    for capacity in SEQUENCE_BUFFER_CAPACITIES.iter() {
+        let capacity = *capacity;
+        group.throughput(Throughput::Elements(u64::from(capacity)));
+        group.bench_with_input(
+            BenchmarkId::new("insert", capacity),
+            &capacity,
+            |b, &capacity| {
+                let mut buffer = SequenceBuffer::<u32>::with_capacity(capacity);
+                let mut sequence: u16 = 0;
+                b.iter(|| {
+                    for _ in 0..capacity {
+                        buffer.insert(sequence, u32::from(sequence));
+                        sequence = sequence.wrapping_add(1);
+                    }
+                });
+            },
+        );
+
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(capacity);
+        for sequence in 0..capacity {
+            buffer.insert(sequence, u32::from(sequence));
+        }
+        group.bench_with_input(BenchmarkId::new("get", capacity), &buffer, |b, buffer| {
+            b.iter(|| {
+                let mut sum: u32 = 0;
+                for sequence in 0..capacity {
+                    if let Some(value) = buffer.get(black_box(sequence)) {
+                        sum = sum.wrapping_add(*value);
+                    }
+                }
+                return sum;
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("iter", capacity), &buffer, |b, buffer| {
+            b.iter(|| {
+                return buffer
+                    .iter(false)
+                    .fold(0u32, |sum, (_, value)| sum.wrapping_add(*value));
+            });
+        });
+    }
+    group.finish();
+}
+
+fn standard_header(c: &mut Criterion) {
+    let mut group = c.benchmark_group("standard_header");
+    let header = StandardHeader::new(PacketType::Data, 4321, 1234, 0xdead_beef, 77, 76);
+    group.bench_function("write", |b| {
+        let mut buffer = Vec::with_capacity(StandardHeader::bytes_number());
+        b.iter(|| {
+            buffer.clear();
+            black_box(&header).write(&mut buffer);
+        });
+    });
+    let mut packet = Vec::new();
+    header.write(&mut packet);
+    packet.extend_from_slice(&[0; 64]);
+    group.bench_function("read", |b| {
+        b.iter(|| StandardHeader::read(black_box(&packet)));
+    });
+    group.finish();
+}
+
+// This tree packs integers with varints, rather than a bit-packer
+fn varint(c: &mut Criterion) {
+    let mut group = c.benchmark_group("varint");
+    let values: Vec<u32> = (0..1024u32)
+        .map(|index| index.wrapping_mul(2_654_435_761) >> (index % 32))
+        .collect();
+    let mut bytes = Vec::new();
+    for value in values.iter() {
+        varint::write_u32(*value, &mut bytes);
+    }
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("write_u32", |b| {
+        let mut out_bytes = Vec::with_capacity(bytes.len());
+        b.iter(|| {
+            out_bytes.clear();
+            for value in values.iter() {
+                varint::write_u32(black_box(*value), &mut out_bytes);
+            }
+        });
+    });
+    group.bench_function("read_u32", |b| {
+        b.iter(|| {
+            let mut reader = PacketReader::new(&bytes);
+            let mut sum: u32 = 0;
+            while reader.has_more() {
+                let value = varint::read_from(&mut reader, varint::read_u32).unwrap();
+                sum = sum.wrapping_add(value);
+            }
+            return sum;
+        });
+    });
+    group.finish();
+}
+
+fn event_frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("event_frames");
+    let mut manifest = Manifest::<BenchEvent, BenchActor>::new();
+    manifest.register_event(SmallEvent::get_builder());
+    let schema_versions = SchemaVersions::new();
+    let now = SystemClock.now();
+    let events: Vec<OutgoingEvent<BenchEvent>> = (0..100u8)
+        .map(|value| {
+            let event: Box<dyn Event<BenchEvent>> = Box::new(SmallEvent::new_complete(value));
+            return OutgoingEvent {
+                event: Rc::new(event),
+                ordering: None,
+                size: 0,
+                queued_at: now.clone(),
+                grouped: Vec::new(),
+                trace_id: None,
+                actor: None,
+                queue_index: 0,
+            };
+        })
+        .collect();
+    group.throughput(Throughput::Elements(events.len() as u64));
+    group.bench_function("aggregate_100_small", |b| {
+        let mut payload = Vec::new();
+        b.iter(|| {
+            let mut writer = EventPacketWriter::new();
+            for event in events.iter() {
+                assert!(writer.write_event(&manifest, &schema_versions, event, &now));
+            }
+            payload.clear();
+            writer.get_bytes(&mut payload);
+        });
+    });
+    group.finish();
+}
+
+fn delta_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delta_encoding");
+    let actor = WideActor::new();
+    let mut state_mask = StateMask::new(3);
+    state_mask.set_bit(3, true);
+    state_mask.set_bit(17, true);
+    group.bench_function("write_20_fields_2_dirty", |b| {
+        let mut bytes = Vec::new();
+        b.iter(|| {
+            bytes.clear();
+            actor.write_partial(black_box(&state_mask), &mut bytes);
+        });
+    });
+    let mut bytes = Vec::new();
+    actor.write_partial(&state_mask, &mut bytes);
+    group.bench_function("read_20_fields_2_dirty", |b| {
+        let mut receiver = WideActor::new();
+        b.iter(|| {
+            let mut reader = PacketReader::new(&bytes);
+            receiver.read_partial(black_box(&state_mask), &mut reader, 1);
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    sequence_buffer,
+    standard_header,
+    varint,
+    event_frames,
+    delta_encoding
+);
+criterion_main!(benches);
@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+};
 
 use super::{
-    sequence_buffer::{SequenceBuffer, SequenceNumber},
+    sequence_buffer::{SequenceBuffer, SequenceInsertError, SequenceNumber},
     standard_header::StandardHeader,
     wrapping_number::sequence_greater_than,
 };
@@ -13,7 +16,13 @@ use super::{
 };
 
 const REDUNDANT_PACKET_ACKS_SIZE: u16 = 32;
+// number of most recently received packet indices remembered. Incoming packet
+// indices may advance by at most half of this at once, so that a single forged
+// packet index can't flush the record of received packets
+const RECEIVED_PACKETS_SIZE: u16 = 1024;
 const DEFAULT_SEND_PACKETS_SIZE: usize = 256;
+// number of most recently sent Data packets which packet loss is measured over
+const PACKET_LOSS_WINDOW: usize = 64;
 
 /// Keeps track of sent & received packets, and contains ack information that is
 /// copied into the standard header on each outgoing packet
@@ -29,6 +38,11 @@ pub struct AckManager {
     // However, we can only reasonably ack up to `REDUNDANT_PACKET_ACKS_SIZE + 1` packets on each
     // message we send so this should be that large.
     received_packets: SequenceBuffer<ReceivedPacket>,
+    // the number of incoming packets whose index was too far ahead of the most recent one
+    far_ahead_packet_count: u64,
+    // the most recently sent Data packets, their sizes in bytes, and whether each has been
+    // delivered or dropped, if known yet
+    recent_data_packets: VecDeque<(SequenceNumber, usize, Option<bool>)>,
 }
 
 impl AckManager {
@@ -38,7 +52,12 @@ impl AckManager {
             sequence_number: 0,
             remote_ack_sequence_num: u16::max_value(),
             sent_packets: HashMap::with_capacity(DEFAULT_SEND_PACKETS_SIZE),
-            received_packets: SequenceBuffer::with_capacity(REDUNDANT_PACKET_ACKS_SIZE + 1),
+            received_packets: SequenceBuffer::with_max_advance(
+                RECEIVED_PACKETS_SIZE,
+                RECEIVED_PACKETS_SIZE / 2,
+            ),
+            far_ahead_packet_count: 0,
+            recent_data_packets: VecDeque::with_capacity(PACKET_LOSS_WINDOW),
         }
     }
 
@@ -47,20 +66,26 @@ impl AckManager {
         self.sequence_number
     }
 
-    /// Process an incoming packet, handle notifications of delivered / dropped
-    /// packets
+    /// Process an incoming packet from the given address, handle notifications
+    /// of delivered / dropped packets. Returns an Error if the packet's index
+    /// could not be recorded, though the acks it carries are still processed
     pub fn process_incoming<T: EventType>(
         &mut self,
+        address: &SocketAddr,
         header: &StandardHeader,
         event_manager: &mut EventManager<T>,
         actor_notifiable: &mut Option<&mut dyn ActorNotifiable>,
-    ) {
+    ) -> Result<(), SequenceInsertError> {
         let remote_seq_num = header.local_packet_index();
         let remote_ack_seq = header.last_remote_packet_index();
-        let mut remote_ack_field = header.ack_field();
+        let remote_ack_field = header.ack_field();
 
-        self.received_packets
-            .insert(remote_seq_num, ReceivedPacket {});
+        let insert_result = self
+            .received_packets
+            .try_insert(remote_seq_num, ReceivedPacket {});
+        if insert_result == Err(SequenceInsertError::TooFarAhead) {
+            self.far_ahead_packet_count += 1;
+        }
 
         // ensure that `self.remote_ack_sequence_num` is always increasing (with
         // wrapping)
@@ -71,7 +96,12 @@ impl AckManager {
         // the current `remote_ack_seq` was (clearly) received so we should remove it
         if let Some(sent_packet) = self.sent_packets.get(&remote_ack_seq) {
             if sent_packet.packet_type == PacketType::Data {
-                self.notify_packet_delivered(remote_ack_seq, event_manager, actor_notifiable);
+                self.notify_packet_delivered(
+                    address,
+                    remote_ack_seq,
+                    event_manager,
+                    actor_notifiable,
+                );
             }
 
             self.sent_packets.remove(&remote_ack_seq);
@@ -83,26 +113,60 @@ impl AckManager {
         for i in 1..=REDUNDANT_PACKET_ACKS_SIZE {
             let ack_sequence = remote_ack_seq.wrapping_sub(i);
             if let Some(sent_packet) = self.sent_packets.get(&ack_sequence) {
-                if remote_ack_field & 1 == 1 {
+                if is_acked_in_field(remote_ack_field, i) {
                     if sent_packet.packet_type == PacketType::Data {
-                        self.notify_packet_delivered(ack_sequence, event_manager, actor_notifiable);
+                        self.notify_packet_delivered(
+                            address,
+                            ack_sequence,
+                            event_manager,
+                            actor_notifiable,
+                        );
                     }
 
                     self.sent_packets.remove(&ack_sequence);
                 } else {
                     if sent_packet.packet_type == PacketType::Data {
-                        self.notify_packet_dropped(ack_sequence, event_manager, actor_notifiable);
+                        self.notify_packet_dropped(
+                            address,
+                            ack_sequence,
+                            event_manager,
+                            actor_notifiable,
+                        );
                     }
                     self.sent_packets.remove(&ack_sequence);
                 }
             }
-
-            remote_ack_field >>= 1;
         }
+
+        return insert_result;
+    }
+
+    /// Gets the number of bytes allocated for the record of received packets
+    pub fn get_allocated_bytes(&self) -> usize {
+        return self.received_packets.allocated_bytes();
     }
 
-    /// Records the packet with the given packet index
-    pub fn track_packet(&mut self, packet_type: PacketType, sequence_number: SequenceNumber) {
+    /// Gets the number of incoming packets whose index was too far ahead of
+    /// the most recently received one to be recorded
+    pub fn get_far_ahead_packet_count(&self) -> u64 {
+        return self.far_ahead_packet_count;
+    }
+
+    /// Records the packet with the given packet index, and its size in bytes
+    pub fn track_packet(
+        &mut self,
+        packet_type: PacketType,
+        sequence_number: SequenceNumber,
+        size: usize,
+    ) {
+        if packet_type == PacketType::Data {
+            if self.recent_data_packets.len() >= PACKET_LOSS_WINDOW {
+                self.recent_data_packets.pop_front();
+            }
+            self.recent_data_packets
+                .push_back((sequence_number, size, None));
+        }
+
         self.sent_packets.insert(
             sequence_number,
             SentPacket {
@@ -117,30 +181,123 @@ impl AckManager {
         self.sequence_number = self.sequence_number.wrapping_add(1);
     }
 
-    fn notify_packet_delivered<T: EventType>(
+    /// Gets the fraction of recently sent Data packets which have not been
+    /// acknowledged, including those still in flight
+    pub fn get_packet_loss(&self) -> f32 {
+        if self.recent_data_packets.len() == 0 {
+            return 0.0;
+        }
+        let lost_count = self
+            .recent_data_packets
+            .iter()
+            .filter(|(_, _, outcome)| *outcome != Some(true))
+            .count();
+        return lost_count as f32 / self.recent_data_packets.len() as f32;
+    }
+
+    /// Gets the fraction of recently sent Data packets known to have been
+    /// dropped, out of those known to have been either delivered or dropped
+    pub fn get_settled_packet_loss(&self) -> f32 {
+        let mut settled_count = 0;
+        let mut dropped_count = 0;
+        for (_, _, outcome) in self.recent_data_packets.iter() {
+            match outcome {
+                Some(true) => settled_count += 1,
+                Some(false) => {
+                    settled_count += 1;
+                    dropped_count += 1;
+                }
+                None => {}
+            }
+        }
+        if settled_count == 0 {
+            return 0.0;
+        }
+        return dropped_count as f32 / settled_count as f32;
+    }
+
+    /// Gets the delivery of recently sent Data packets which are known to have
+    /// been either delivered or dropped, split into those no larger than the
+    /// given size in bytes and those larger. Only packets sent from the given
+    /// packet index onwards are counted
+    pub fn get_delivery_by_size(
         &self,
+        size_threshold: usize,
+        since: SequenceNumber,
+    ) -> DeliveryBySize {
+        let mut delivery = DeliveryBySize::default();
+        for (sequence_number, size, outcome) in self.recent_data_packets.iter() {
+            if sequence_greater_than(since, *sequence_number) {
+                continue;
+            }
+            let (count, dropped) = if *size > size_threshold {
+                (&mut delivery.large_count, &mut delivery.large_dropped)
+            } else {
+                (&mut delivery.small_count, &mut delivery.small_dropped)
+            };
+            match outcome {
+                Some(true) => *count += 1,
+                Some(false) => {
+                    *count += 1;
+                    *dropped += 1;
+                }
+                None => {}
+            }
+        }
+        return delivery;
+    }
+
+    fn notify_packet_delivered<T: EventType>(
+        &mut self,
+        address: &SocketAddr,
         packet_sequence_number: u16,
         event_manager: &mut EventManager<T>,
         actor_notifiable: &mut Option<&mut dyn ActorNotifiable>,
     ) {
-        event_manager.notify_packet_delivered(packet_sequence_number);
+        self.record_outcome(address, packet_sequence_number, true);
+        event_manager.notify_packet_delivered(address, packet_sequence_number);
         if let Some(notifiable) = actor_notifiable {
             notifiable.notify_packet_delivered(packet_sequence_number);
         }
     }
 
     fn notify_packet_dropped<T: EventType>(
-        &self,
+        &mut self,
+        address: &SocketAddr,
         packet_sequence_number: u16,
         event_manager: &mut EventManager<T>,
         actor_notifiable: &mut Option<&mut dyn ActorNotifiable>,
     ) {
+        self.record_outcome(address, packet_sequence_number, false);
         event_manager.notify_packet_dropped(packet_sequence_number);
         if let Some(notifiable) = actor_notifiable {
             notifiable.notify_packet_dropped(packet_sequence_number);
         }
     }
 
+    // records whether a recently sent Data packet was delivered, which is only
+    // ever found out once
+    fn record_outcome(
+        &mut self,
+        address: &SocketAddr,
+        packet_sequence_number: u16,
+        delivered: bool,
+    ) {
+        for (sequence_number, _, outcome) in self.recent_data_packets.iter_mut() {
+            if *sequence_number == packet_sequence_number {
+                crate::strict_assert!(
+                    outcome.is_none(),
+                    "packet {} to {} was notified as {} after already being {}",
+                    packet_sequence_number,
+                    address,
+                    get_outcome_name(Some(delivered)),
+                    get_outcome_name(*outcome)
+                );
+                *outcome = Some(delivered);
+            }
+        }
+    }
+
     pub(crate) fn get_last_remote_packet_index(&self) -> SequenceNumber {
         self.received_packets.sequence_num().wrapping_sub(1)
     }
@@ -164,6 +321,37 @@ impl AckManager {
     }
 }
 
+/// Gets the indices of the packets a header acknowledges, given its last
+/// remote packet index & ack field: the last remote packet index itself, then
+/// each of the 32 packets before it whose bit is set, the lowest bit standing
+/// for the packet just before it
+pub fn get_acked_packet_indices(
+    last_remote_packet_index: SequenceNumber,
+    ack_field: u32,
+) -> Vec<SequenceNumber> {
+    let mut acked = vec![last_remote_packet_index];
+    for i in 1..=REDUNDANT_PACKET_ACKS_SIZE {
+        if is_acked_in_field(ack_field, i) {
+            acked.push(last_remote_packet_index.wrapping_sub(i));
+        }
+    }
+    return acked;
+}
+
+// whether the packet the given distance before the last remote packet index
+// has its bit set in the ack field
+fn is_acked_in_field(ack_field: u32, distance: u16) -> bool {
+    return (ack_field >> (distance - 1)) & 1 == 1;
+}
+
+fn get_outcome_name(outcome: Option<bool>) -> &'static str {
+    match outcome {
+        Some(true) => return "delivered",
+        Some(false) => return "dropped",
+        None => return "in flight",
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct SentPacket {
     pub id: u32,
@@ -172,3 +360,18 @@ pub struct SentPacket {
 
 #[derive(Clone, Debug, Default)]
 pub struct ReceivedPacket;
+
+/// The number of recently sent Data packets known to have been delivered or
+/// dropped, and how many of those were dropped, split by packet size
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeliveryBySize {
+    /// The number of packets no larger than the size threshold
+    pub small_count: usize,
+    /// The number of packets no larger than the size threshold which were
+    /// dropped
+    pub small_dropped: usize,
+    /// The number of packets larger than the size threshold
+    pub large_count: usize,
+    /// The number of packets larger than the size threshold which were dropped
+    pub large_dropped: usize,
+}
@@ -5,7 +5,9 @@ use std::{
     rc::Rc,
 };
 
-use super::{actor_mutator::ActorMutator, actor_type::ActorType, state_mask::StateMask};
+use super::{
+    actor_mutator::ActorMutator, actor_ref::ActorRef, actor_type::ActorType, state_mask::StateMask,
+};
 
 use crate::PacketReader;
 
@@ -26,6 +28,44 @@ pub trait Actor<T: ActorType> {
     /// Write data into an outgoing byte stream, sufficient only to update the
     /// mutated Properties of the Actor on the client
     fn write_partial(&self, state_mask: &StateMask, out_bytes: &mut Vec<u8>);
+    /// Writes data into an outgoing byte stream, sufficient to completely
+    /// recreate the given schema version of the Actor on the client, leaving
+    /// out any Properties added in later versions
+    fn write_versioned(&self, _version: u16, out_bytes: &mut Vec<u8>) {
+        self.write(out_bytes);
+    }
+    /// Write data into an outgoing byte stream, sufficient only to update the
+    /// mutated Properties of the given schema version of the Actor on the
+    /// client, leaving out any Properties added in later versions
+    fn write_partial_versioned(
+        &self,
+        state_mask: &StateMask,
+        _version: u16,
+        out_bytes: &mut Vec<u8>,
+    ) {
+        self.write_partial(state_mask, out_bytes);
+    }
+    /// Writes data into an outgoing byte stream, sufficient to completely
+    /// recreate the Actor on the client, with only the Properties set in the
+    /// given visibility mask holding their values. The others are written as
+    /// their default values, so that the client reads the same layout. Actors
+    /// not using the derive macro don't hide any Properties unless they
+    /// override this
+    fn write_masked(&self, _visibility: &StateMask, out_bytes: &mut Vec<u8>) {
+        self.write(out_bytes);
+    }
+    /// Writes data into an outgoing byte stream, sufficient to completely
+    /// recreate the given schema version of the Actor on the client, with
+    /// only the Properties set in the given visibility mask holding their
+    /// values, as `write_masked()` does
+    fn write_masked_versioned(
+        &self,
+        _visibility: &StateMask,
+        version: u16,
+        out_bytes: &mut Vec<u8>,
+    ) {
+        self.write_versioned(version, out_bytes);
+    }
     /// Reads data from an incoming packet, sufficient to sync the in-memory
     /// Actor with it's state on the Server
     fn read_full(&mut self, reader: &mut PacketReader, packet_index: u16);
@@ -45,6 +85,9 @@ pub trait Actor<T: ActorType> {
     fn is_interpolated(&self) -> bool;
     /// Returns whether or not the Actor has any predicted properties
     fn is_predicted(&self) -> bool;
+    /// Gets all the ActorRefs held by the Actor's Properties, used to keep
+    /// referenced Actors in scope alongside the Actor
+    fn get_actor_refs(&self) -> Vec<ActorRef>;
 }
 
 //TODO: do we really need another trait here?
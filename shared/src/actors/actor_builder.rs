@@ -1,6 +1,6 @@
 use super::actor_type::ActorType;
 
-use crate::PacketReader;
+use crate::{protocol_schema::TypeDescription, PacketReader};
 
 use std::{
     any::TypeId,
@@ -11,8 +11,24 @@ use std::{
 pub trait ActorBuilder<T: ActorType> {
     /// Create a new Actor instance
     fn build(&self, reader: &mut PacketReader) -> T;
+    /// Gets the schema version of the Actor the builder is able to build,
+    /// which goes up as Properties are added to it
+    fn get_schema_version(&self) -> u16 {
+        return 1;
+    }
+    /// Create a new Actor instance, written as the given schema version of its
+    /// type, giving any Properties added in later versions their defaults
+    fn build_versioned(&self, reader: &mut PacketReader, _version: u16) -> T {
+        return self.build(reader);
+    }
     /// Gets the TypeId of the Actor the builder is able to build
     fn get_type_id(&self) -> TypeId;
+    /// Describes the name & Properties of the Actor the builder is able to
+    /// build, for `Manifest::export_schema()`. Derived Actors describe
+    /// themselves, other builders describe nothing by default
+    fn describe(&self) -> Option<TypeDescription> {
+        return None;
+    }
 }
 
 impl<T: ActorType> Debug for Box<dyn ActorBuilder<T>> {
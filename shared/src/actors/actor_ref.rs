@@ -0,0 +1,68 @@
+use nanoserde::{DeBin, SerBin};
+
+/// A reference to another Actor, which can be stored inside of a Property
+/// (i.e. `Property<ActorRef>`) and synced to the Client.
+///
+/// On the Server, an ActorRef is created from an ActorKey. On the Client, an
+/// ActorRef can be resolved into the LocalActorKey of the referenced Actor,
+/// which will only succeed while the referenced Actor is in scope for the
+/// Client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, SerBin, DeBin)]
+pub struct ActorRef {
+    net_id: Option<u64>,
+}
+
+impl ActorRef {
+    /// Create a new ActorRef pointing to the Actor with the given network id
+    pub fn new(net_id: u64) -> Self {
+        ActorRef {
+            net_id: Some(net_id),
+        }
+    }
+
+    /// Create a new ActorRef which does not point to any Actor
+    pub fn none() -> Self {
+        ActorRef { net_id: None }
+    }
+
+    /// Gets the network id of the referenced Actor, if there is one
+    pub fn get_net_id(&self) -> Option<u64> {
+        return self.net_id;
+    }
+
+    /// Returns whether the ActorRef points to an Actor
+    pub fn is_some(&self) -> bool {
+        return self.net_id.is_some();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ActorRef, PacketReader, Property};
+
+    #[test]
+    fn read_write_some() {
+        let property = Property::new(ActorRef::new(1234567890123), 0);
+        let mut bytes = Vec::new();
+        property.write(&mut bytes);
+
+        let mut other = Property::new(ActorRef::none(), 0);
+        let mut reader = PacketReader::new(&bytes);
+        other.read(&mut reader, 1);
+
+        assert_eq!(other.get().get_net_id(), Some(1234567890123));
+    }
+
+    #[test]
+    fn read_write_none() {
+        let property = Property::new(ActorRef::none(), 0);
+        let mut bytes = Vec::new();
+        property.write(&mut bytes);
+
+        let mut other = Property::new(ActorRef::new(7), 0);
+        let mut reader = PacketReader::new(&bytes);
+        other.read(&mut reader, 1);
+
+        assert!(!other.get().is_some());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    actors::{actor::Actor, actor_type::ActorType, state_mask::StateMask},
+    byte_order, PacketReader,
+};
+
+/// The id an Archetype is written as, given to each in the order they are
+/// registered in the Manifest
+pub type ArchetypeId = u16;
+
+// The FNV-1a offset basis & prime, for 32 bit hashes
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// A named baseline state of a registered Actor type. An Actor spawned for a
+/// Client which has registered the same Archetype is written as the Archetype's
+/// id, along with only the Properties which differ from the baseline, and the
+/// Client rebuilds it from its own copy of the baseline
+#[derive(Debug)]
+pub struct Archetype<U: ActorType> {
+    name: String,
+    naia_id: u16,
+    baseline: U,
+    digest: u32,
+}
+
+impl<U: ActorType> Archetype<U> {
+    /// Create a new Archetype, given its name, and the NaiaId & schema version
+    /// of the Actor type its baseline is of
+    pub fn new(name: &str, naia_id: u16, schema_version: u16, baseline: U) -> Self {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(name.as_bytes());
+        byte_order::write_u16(&mut bytes, naia_id);
+        byte_order::write_u16(&mut bytes, schema_version);
+        baseline.inner_ref().as_ref().borrow().write(&mut bytes);
+        return Archetype {
+            name: name.to_string(),
+            naia_id,
+            baseline,
+            digest: fnv1a(&bytes),
+        };
+    }
+
+    /// Gives the Archetype's Actor type a new NaiaId, as when the Manifest it
+    /// was registered in is merged into another, hashing it afresh
+    pub(crate) fn renumbered(self, naia_id: u16, schema_version: u16) -> Self {
+        return Archetype::new(&self.name, naia_id, schema_version, self.baseline);
+    }
+
+    /// Gets the name the Archetype was registered with
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Gets the NaiaId of the Actor type the Archetype's baseline is of
+    pub fn get_naia_id(&self) -> u16 {
+        return self.naia_id;
+    }
+
+    /// Gets a hash of the Archetype's name, type & baseline, which the
+    /// Archetypes of both hosts must share to be used on a connection
+    pub fn get_digest(&self) -> u32 {
+        return self.digest;
+    }
+
+    /// Gets a mask of the Properties of the given Actor, of the Archetype's
+    /// type, which differ from the baseline, as told by the bytes they are
+    /// written as
+    pub fn diff(&self, actor: &dyn Actor<U>) -> StateMask {
+        let baseline = self.baseline.inner_ref();
+        let baseline = baseline.as_ref().borrow();
+        let mask_size = actor.get_state_mask_size();
+        let mut diff_mask = StateMask::new(mask_size);
+        let mut property_mask = StateMask::new(mask_size);
+        for index in 0..(u16::from(mask_size) * 8) {
+            property_mask.clear();
+            property_mask.set_bit(index as u8, true);
+            let mut actor_bytes = Vec::new();
+            actor.write_partial(&property_mask, &mut actor_bytes);
+            let mut baseline_bytes = Vec::new();
+            baseline.write_partial(&property_mask, &mut baseline_bytes);
+            if actor_bytes != baseline_bytes {
+                diff_mask.set_bit(index as u8, true);
+            }
+        }
+        return diff_mask;
+    }
+
+    /// Creates an Actor instance from a copy of the baseline, with the
+    /// Properties set in the given mask read from the payload, typically from
+    /// an incoming packet
+    pub fn build(&self, diff_mask: &StateMask, reader: &mut PacketReader) -> U {
+        let mut actor = self.baseline.inner_ref().as_ref().borrow().get_typed_copy();
+        // read as the Properties of an Actor built whole are, so that they
+        // take later updates alike
+        actor.read_partial(diff_mask, reader, 1);
+        return actor;
+    }
+}
+
+// a 32 bit FNV-1a hash of the given bytes
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in bytes.iter() {
+        hash ^= u32::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    return hash;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fnv1a;
+
+    #[test]
+    fn digest_is_fnv1a() {
+        assert_eq!(fnv1a(b""), 0x811c_9dc5);
+        assert_eq!(fnv1a(b"a"), 0xe40c_292c);
+        assert_eq!(fnv1a(b"foobar"), 0xbf9c_f968);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::PacketReader;
+
+/// Why an Actor has been deleted from a Client, sent along with the deletion
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DespawnReason {
+    /// The Actor has left the Client's scope, but still exists on the Server
+    OutOfScope,
+    /// The Actor has been deregistered from the Server
+    Destroyed,
+    /// A reason specific to the application, such as the Actor having moved
+    /// to another Server
+    Custom(u8),
+}
+
+impl DespawnReason {
+    /// Writes the reason into an outgoing byte stream
+    pub fn write(&self, buffer: &mut Vec<u8>) {
+        match self {
+            DespawnReason::OutOfScope => buffer.push(0),
+            DespawnReason::Destroyed => buffer.push(1),
+            DespawnReason::Custom(code) => {
+                buffer.push(2);
+                buffer.push(*code);
+            }
+        }
+    }
+
+    /// Reads a reason from an incoming byte stream. An unknown reason is read
+    /// as Destroyed
+    pub fn read(reader: &mut PacketReader) -> DespawnReason {
+        match reader.read_u8() {
+            0 => return DespawnReason::OutOfScope,
+            2 => return DespawnReason::Custom(reader.read_u8()),
+            _ => return DespawnReason::Destroyed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DespawnReason;
+    use crate::PacketReader;
+
+    #[test]
+    fn write_read_roundtrip() {
+        let reasons = [
+            DespawnReason::OutOfScope,
+            DespawnReason::Destroyed,
+            DespawnReason::Custom(0),
+            DespawnReason::Custom(255),
+        ];
+        let mut bytes = Vec::new();
+        for reason in reasons.iter() {
+            reason.write(&mut bytes);
+        }
+        assert_eq!(bytes, [0, 1, 2, 0, 2, 255]);
+
+        let mut reader = PacketReader::new(&bytes);
+        for reason in reasons.iter() {
+            assert_eq!(DespawnReason::read(&mut reader), *reason);
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use std::ops::{Add, Sub};
 
+/// A Property type which can be interpolated, or have the difference between
+/// two of its values blended out, by converting it to & from an f32
 pub trait InterpLerpable: Sized + Sub + Add + Clone + Copy + PartialEq {
+    /// Converts the value to an f32
     fn to_f32(&self) -> f32;
+    /// Converts an f32 back to the value
     fn from_f32(input: f32) -> Self;
 }
 
@@ -29,3 +33,13 @@ impl InterpLerpable for u16 {
         input as Self
     }
 }
+
+impl InterpLerpable for f32 {
+    fn to_f32(&self) -> f32 {
+        *self
+    }
+
+    fn from_f32(input: f32) -> Self {
+        input
+    }
+}
@@ -1,3 +1,31 @@
 /// The key that represents an Actor in the Client's scope, that is being
 /// synced to the Client
 pub type LocalActorKey = u16;
+
+/// The incarnation of the Actor using a LocalActorKey. Every time a key is
+/// reused for another Actor its generation is incremented, wrapping around,
+/// and every message for an Actor is tagged with it, so that a late message
+/// for the Actor which used the key before is never applied to the one using
+/// it now
+pub type ActorGeneration = u8;
+
+/// Returns whether ActorGeneration `a` is more recent than `b`, taking the
+/// shortest way around the wrap
+pub fn generation_greater_than(a: ActorGeneration, b: ActorGeneration) -> bool {
+    return (a.wrapping_sub(b) as i8) > 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generation_greater_than;
+
+    #[test]
+    fn generations_compare_across_wrap() {
+        assert!(generation_greater_than(1, 0));
+        assert!(!generation_greater_than(0, 1));
+        assert!(!generation_greater_than(7, 7));
+        assert!(generation_greater_than(0, 255));
+        assert!(generation_greater_than(3, 250));
+        assert!(!generation_greater_than(250, 3));
+    }
+}
@@ -1,9 +1,25 @@
+#[cfg(feature = "std")]
 pub(crate) mod actor;
+#[cfg(feature = "std")]
 pub(crate) mod actor_builder;
+#[cfg(feature = "std")]
 pub(crate) mod actor_mutator;
+#[cfg(feature = "std")]
 pub(crate) mod actor_notifiable;
+#[cfg(feature = "std")]
+pub(crate) mod actor_ref;
+#[cfg(feature = "std")]
 pub(crate) mod actor_type;
+#[cfg(feature = "std")]
+pub(crate) mod archetype;
+#[cfg(feature = "std")]
+pub(crate) mod despawn_reason;
+#[cfg(feature = "std")]
 pub(crate) mod interp_lerp;
+#[cfg(feature = "std")]
 pub(crate) mod local_actor_key;
+#[cfg(feature = "std")]
+pub(crate) mod prediction_id;
+#[cfg(feature = "std")]
 pub(crate) mod property;
 pub(crate) mod state_mask;
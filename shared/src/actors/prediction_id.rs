@@ -0,0 +1,5 @@
+/// The id a Client picks for an Actor it predicts the spawn of, before the
+/// Server has spawned it. The Client sends it along with its request for the
+/// spawn, and the Server tags the Actor it spawns for the request with it, so
+/// that the Client can match the Actor up with its prediction
+pub type PredictionId = u16;
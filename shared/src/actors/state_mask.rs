@@ -1,6 +1,7 @@
-use byteorder::WriteBytesExt;
-use std::fmt;
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
 
+#[cfg(feature = "std")]
 use crate::PacketReader;
 
 /// The State Mask is a variable-length byte array, where each bit represents
@@ -102,13 +103,14 @@ impl StateMask {
 
     /// Writes the StateMask into an outgoing byte stream
     pub fn write(&mut self, out_bytes: &mut Vec<u8>) {
-        out_bytes.write_u8(self.bytes).unwrap();
+        out_bytes.push(self.bytes);
         for x in 0..self.bytes {
-            out_bytes.write_u8(self.mask[x as usize]).unwrap();
+            out_bytes.push(self.mask[x as usize]);
         }
     }
 
     /// Reads the StateMask from an incoming packet
+    #[cfg(feature = "std")]
     pub fn read(reader: &mut PacketReader) -> StateMask {
         let bytes: u8 = reader.read_u8();
         let mut mask: Vec<u8> = Vec::new();
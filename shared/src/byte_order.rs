@@ -0,0 +1,204 @@
+use alloc::vec::Vec;
+
+use byteorder::ByteOrder;
+
+#[cfg(feature = "std")]
+use naia_socket_shared::{PacketReader, Timestamp};
+
+/// The byte order of every fixed-width integer in the wire format
+#[cfg(not(feature = "wire-little-endian"))]
+pub type WireOrder = byteorder::BigEndian;
+
+/// The byte order of every fixed-width integer in the wire format
+#[cfg(feature = "wire-little-endian")]
+pub type WireOrder = byteorder::LittleEndian;
+
+/// The name of the byte order of the wire format, as exported in the protocol
+/// schema
+#[cfg(not(feature = "wire-little-endian"))]
+pub const WIRE_ORDER_NAME: &str = "big-endian";
+
+/// The name of the byte order of the wire format, as exported in the protocol
+/// schema
+#[cfg(feature = "wire-little-endian")]
+pub const WIRE_ORDER_NAME: &str = "little-endian";
+
+/// Gets the u16 at the start of the given bytes, which must be at least 2
+/// bytes long
+pub fn get_u16(bytes: &[u8]) -> u16 {
+    return WireOrder::read_u16(bytes);
+}
+
+/// Gets the u32 at the start of the given bytes, which must be at least 4
+/// bytes long
+pub fn get_u32(bytes: &[u8]) -> u32 {
+    return WireOrder::read_u32(bytes);
+}
+
+/// Gets the u64 at the start of the given bytes, which must be at least 8
+/// bytes long
+pub fn get_u64(bytes: &[u8]) -> u64 {
+    return WireOrder::read_u64(bytes);
+}
+
+/// Puts a u16 at the start of the given bytes, which must be at least 2 bytes
+/// long
+pub fn put_u16(bytes: &mut [u8], value: u16) {
+    WireOrder::write_u16(bytes, value);
+}
+
+/// Puts a u32 at the start of the given bytes, which must be at least 4 bytes
+/// long
+pub fn put_u32(bytes: &mut [u8], value: u32) {
+    WireOrder::write_u32(bytes, value);
+}
+
+/// Puts a u64 at the start of the given bytes, which must be at least 8 bytes
+/// long
+pub fn put_u64(bytes: &mut [u8], value: u64) {
+    WireOrder::write_u64(bytes, value);
+}
+
+/// Writes a u16 onto the end of the given bytes
+pub fn write_u16(bytes: &mut Vec<u8>, value: u16) {
+    let mut value_bytes = [0; 2];
+    put_u16(&mut value_bytes, value);
+    bytes.extend_from_slice(&value_bytes);
+}
+
+/// Writes a u32 onto the end of the given bytes
+pub fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    let mut value_bytes = [0; 4];
+    put_u32(&mut value_bytes, value);
+    bytes.extend_from_slice(&value_bytes);
+}
+
+/// Writes a u64 onto the end of the given bytes
+pub fn write_u64(bytes: &mut Vec<u8>, value: u64) {
+    let mut value_bytes = [0; 8];
+    put_u64(&mut value_bytes, value);
+    bytes.extend_from_slice(&value_bytes);
+}
+
+/// Reads a u16 from the reader, or None if there are fewer than 2 bytes left,
+/// in which case the reader is left where it was
+#[cfg(feature = "std")]
+pub fn try_read_u16(reader: &mut PacketReader) -> Option<u16> {
+    return try_read(reader, 2).map(|bytes| get_u16(&bytes));
+}
+
+/// Reads a u32 from the reader, or None if there are fewer than 4 bytes left,
+/// in which case the reader is left where it was
+#[cfg(feature = "std")]
+pub fn try_read_u32(reader: &mut PacketReader) -> Option<u32> {
+    return try_read(reader, 4).map(|bytes| get_u32(&bytes));
+}
+
+/// Reads a u64 from the reader, or None if there are fewer than 8 bytes left,
+/// in which case the reader is left where it was
+#[cfg(feature = "std")]
+pub fn try_read_u64(reader: &mut PacketReader) -> Option<u64> {
+    return try_read(reader, 8).map(|bytes| get_u64(&bytes));
+}
+
+/// Reads a u16 from the reader, panicking if there are fewer than 2 bytes left
+#[cfg(feature = "std")]
+pub fn read_u16(reader: &mut PacketReader) -> u16 {
+    return try_read_u16(reader).expect("a u16 was cut short");
+}
+
+/// Reads a u32 from the reader, panicking if there are fewer than 4 bytes left
+#[cfg(feature = "std")]
+pub fn read_u32(reader: &mut PacketReader) -> u32 {
+    return try_read_u32(reader).expect("a u32 was cut short");
+}
+
+/// Reads a u64 from the reader, panicking if there are fewer than 8 bytes left
+#[cfg(feature = "std")]
+pub fn read_u64(reader: &mut PacketReader) -> u64 {
+    return try_read_u64(reader).expect("a u64 was cut short");
+}
+
+/// Writes a Timestamp onto the end of the given bytes, as a u64
+#[cfg(feature = "std")]
+pub fn write_timestamp(bytes: &mut Vec<u8>, timestamp: &Timestamp) {
+    // a Timestamp only writes itself big endian
+    let mut timestamp_bytes = Vec::new();
+    timestamp.write(&mut timestamp_bytes);
+    write_u64(bytes, byteorder::BigEndian::read_u64(&timestamp_bytes));
+}
+
+/// Reads a Timestamp from the reader, as a u64, panicking if there are fewer
+/// than 8 bytes left
+#[cfg(feature = "std")]
+pub fn read_timestamp(reader: &mut PacketReader) -> Timestamp {
+    return timestamp_from_u64(read_u64(reader));
+}
+
+/// Gets the Timestamp holding the given time, in whatever unit the Clock it's
+/// from counts
+#[cfg(feature = "std")]
+pub fn timestamp_from_u64(time: u64) -> Timestamp {
+    return Timestamp::read(&mut PacketReader::new(&time.to_be_bytes()));
+}
+
+// takes the next bytes from the reader, if there are enough left
+#[cfg(feature = "std")]
+fn try_read(reader: &mut PacketReader, length: usize) -> Option<Vec<u8>> {
+    let position = reader.get_cursor().position() as usize;
+    let buffer = reader.get_buffer();
+    if buffer.len() < position + length {
+        return None;
+    }
+    let bytes = buffer[position..position + length].to_vec();
+    reader.get_cursor().set_position((position + length) as u64);
+    return Some(bytes);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{
+        get_u32, put_u16, read_timestamp, read_u16, timestamp_from_u64, try_read_u32,
+        write_timestamp, write_u16, write_u32, write_u64, WIRE_ORDER_NAME,
+    };
+    use naia_socket_shared::PacketReader;
+
+    #[test]
+    fn integers_are_written_in_the_wire_order() {
+        let mut bytes = Vec::new();
+        write_u16(&mut bytes, 0x0102);
+        write_u32(&mut bytes, 0x0304_0506);
+        write_u64(&mut bytes, 0x0708_090a_0b0c_0d0e);
+        write_timestamp(&mut bytes, &timestamp_from_u64(0x0f10));
+
+        let mut expected: Vec<u8> = (0x01..=0x0e).collect();
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0x0f, 0x10]);
+        if WIRE_ORDER_NAME == "little-endian" {
+            expected[0..2].reverse();
+            expected[2..6].reverse();
+            expected[6..14].reverse();
+            expected[14..22].reverse();
+        }
+        assert_eq!(bytes, expected);
+
+        let mut slice = [0; 3];
+        put_u16(&mut slice[1..], 0x0102);
+        assert_eq!(&slice[1..], &bytes[0..2]);
+        assert_eq!(get_u32(&bytes[2..6]), 0x0304_0506);
+    }
+
+    #[test]
+    fn cut_short_integers_are_not_read() {
+        let mut bytes = Vec::new();
+        write_u16(&mut bytes, 7);
+        write_timestamp(&mut bytes, &timestamp_from_u64(9));
+        bytes.push(0xff);
+
+        let mut reader = PacketReader::new(&bytes);
+        assert_eq!(read_u16(&mut reader), 7);
+        assert_eq!(read_timestamp(&mut reader), timestamp_from_u64(9));
+        assert_eq!(try_read_u32(&mut reader), None);
+        assert_eq!(reader.read_u8(), 0xff);
+        assert!(!reader.has_more());
+    }
+}
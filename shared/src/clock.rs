@@ -0,0 +1,136 @@
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{byte_order, Instant, Timestamp};
+
+/// A source of the current time. Every read of the time by a Client, a Server
+/// or their connections goes through the Clock they were configured with, so
+/// that the time can be driven by something other than the system, such as a
+/// ManualClock which only moves when told to, to replay a session
+/// deterministically
+pub trait Clock: Debug + Send + Sync {
+    /// Gets the current moment
+    fn now(&self) -> Instant;
+
+    /// Gets a Timestamp of the current moment, as a Client starts the
+    /// handshake with
+    fn timestamp(&self) -> Timestamp;
+}
+
+/// The Clock which reads the system's monotonic clock, used by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        return Instant::now();
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        // to the microsecond, rather than the second, so that a Client which
+        // restarts straight away doesn't start its handshake with the same
+        // Timestamp as before, and have the Server take it for the old one
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let since_epoch = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("timing error!");
+            return byte_order::timestamp_from_u64(since_epoch.as_micros() as u64);
+        }
+        #[cfg(target_arch = "wasm32")]
+        return Timestamp::now();
+    }
+}
+
+/// A Clock which stands still until it's advanced, for tests & replays. Two
+/// ManualClocks advanced by the same steps read the same times relative to
+/// where they started, and the same Timestamps, which count the whole seconds
+/// they've been advanced by
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed_nanos: AtomicU64,
+}
+
+impl ManualClock {
+    /// Creates a new ManualClock, standing at the moment it's created
+    pub fn new() -> Self {
+        return ManualClock {
+            start: SystemClock.now(),
+            elapsed_nanos: AtomicU64::new(0),
+        };
+    }
+
+    /// Moves the ManualClock forward by the given Duration
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// Gets the time the ManualClock has been advanced by since it was created
+    pub fn elapsed(&self) -> Duration {
+        return Duration::from_nanos(self.elapsed_nanos.load(Ordering::SeqCst));
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        return ManualClock::new();
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        // Instants only move by whole milliseconds, so the ManualClock reads
+        // the milliseconds it's been advanced by
+        let mut now = self.start.clone();
+        let mut millis = self.elapsed().as_millis();
+        while millis > 0 {
+            let step = millis.min(u128::from(u32::MAX));
+            now.add_millis(step as u32);
+            millis -= step;
+        }
+        return now;
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        return byte_order::timestamp_from_u64(self.elapsed().as_secs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Clock, ManualClock};
+    use crate::utils::duration_between;
+
+    #[test]
+    fn manual_clock_only_moves_when_advanced() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_micros(1500));
+        assert_eq!(
+            duration_between(&start, &clock.now()),
+            Duration::from_millis(1)
+        );
+        clock.advance(Duration::from_micros(500));
+        assert_eq!(
+            duration_between(&start, &clock.now()),
+            Duration::from_millis(2)
+        );
+
+        let other = ManualClock::new();
+        assert_eq!(clock.timestamp(), other.timestamp());
+        other.advance(Duration::from_secs(1));
+        assert_ne!(clock.timestamp(), other.timestamp());
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(feature = "compression")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "compression")]
+use crate::{StandardHeader, MAX_PACKET_SIZE};
+
+/// Bit set on the packet type byte of the StandardHeader when the payload of
+/// the packet has been compressed
+pub const COMPRESSED_FLAG: u8 = 0x80;
+
+// No legitimate payload can be larger than the largest packet, once a larger
+// MTU has been discovered, so anything that would decompress past this is
+// rejected
+#[cfg(feature = "compression")]
+const MAX_DECOMPRESSED_SIZE: usize = MAX_PACKET_SIZE - StandardHeader::bytes_number();
+
+/// Returns whether this host was built with support for payload compression
+pub fn is_supported() -> bool {
+    return cfg!(feature = "compression");
+}
+
+/// Compresses a packet payload, returning None if compression would not make
+/// the payload any smaller
+#[cfg(feature = "compression")]
+pub fn compress(payload: &[u8]) -> Option<Vec<u8>> {
+    let compressed = miniz_oxide::deflate::compress_to_vec(payload, 1);
+    if compressed.len() < payload.len() {
+        return Some(compressed);
+    }
+    return None;
+}
+
+/// Decompresses a packet payload, returning None if the payload is invalid or
+/// would decompress to more than the maximum payload size
+#[cfg(feature = "compression")]
+pub fn decompress(payload: &[u8]) -> Option<Vec<u8>> {
+    return miniz_oxide::inflate::decompress_to_vec_with_limit(payload, MAX_DECOMPRESSED_SIZE).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{compression, PacketType, StandardHeader};
+
+    fn write_packet(payload: &[u8], compressed: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        StandardHeader::new(PacketType::Data, 0, 0, 0, 0, 0).write(&mut bytes);
+        if compressed {
+            bytes[0] |= compression::COMPRESSED_FLAG;
+        }
+        bytes.extend_from_slice(payload);
+        return bytes;
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn incompressible_payload_is_not_compressed() {
+        let payload: Vec<u8> = (0..64u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        assert!(compression::compress(&payload).is_none());
+    }
+
+    #[test]
+    fn uncompressed_payload_reads_unchanged() {
+        let payload = [7u8; 100];
+        let (header, read_payload) = StandardHeader::read(&write_packet(&payload, false));
+        assert_eq!(header.packet_type(), PacketType::Data);
+        assert_eq!(read_payload.as_ref(), &payload[..]);
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn compressed_payload_rejected_without_support() {
+        assert!(!compression::is_supported());
+
+        let (header, read_payload) = StandardHeader::read(&write_packet(&[0u8; 16], true));
+        assert_eq!(header.packet_type(), PacketType::Unknown);
+        assert_eq!(read_payload.len(), 0);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_payload_reads_back() {
+        let payload = [3u8; 300];
+        let compressed = compression::compress(&payload).unwrap();
+        assert!(compressed.len() < payload.len());
+
+        let (header, read_payload) = StandardHeader::read(&write_packet(&compressed, true));
+        assert_eq!(header.packet_type(), PacketType::Data);
+        assert_eq!(read_payload.as_ref(), &payload[..]);
+    }
+
+    #[cfg(all(feature = "compression", feature = "std"))]
+    #[test]
+    fn compressed_payload_reads_back_padded() {
+        let payload = [3u8; 300];
+        let compressed = compression::compress(&payload).unwrap();
+        let packet = write_packet(&compressed, true);
+        let padded = crate::packet_padding::pad_packet(&packet, packet.len() + 40);
+
+        let (header, read_payload) = StandardHeader::read(&padded);
+        assert_eq!(header.packet_type(), PacketType::Data);
+        assert_eq!(read_payload.as_ref(), &payload[..]);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn payload_of_largest_packet_reads_back() {
+        let payload_size = crate::MAX_PACKET_SIZE - StandardHeader::bytes_number();
+        let payload: Vec<u8> = (0..payload_size).map(|i| (i % 7) as u8).collect();
+        let compressed = compression::compress(&payload).unwrap();
+        assert_eq!(compression::decompress(&compressed), Some(payload));
+
+        let too_large = [0u8; crate::MAX_PACKET_SIZE];
+        let compressed = compression::compress(&too_large).unwrap();
+        assert!(compression::decompress(&compressed).is_none());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decompression_bomb_rejected() {
+        let bomb = miniz_oxide::deflate::compress_to_vec(&[0u8; 100_000], 10);
+        assert!(bomb.len() < 508);
+        assert!(compression::decompress(&bomb).is_none());
+
+        let (header, read_payload) = StandardHeader::read(&write_packet(&bomb, true));
+        assert_eq!(header.packet_type(), PacketType::Unknown);
+        assert_eq!(read_payload.len(), 0);
+    }
+}
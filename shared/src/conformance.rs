@@ -0,0 +1,1555 @@
+use std::{
+    fs,
+    io::Result as IoResult,
+    path::{Path, PathBuf},
+};
+
+use byteorder::WriteBytesExt;
+use nanoserde::{DeJson, SerJson};
+
+use crate::{
+    ack_manager::get_acked_packet_indices,
+    byte_order,
+    events::event_frame::{read_event_frame, write_event_frame},
+    handshake::{self, EarlyData, SessionToken, SESSION_TOKEN_BYTES, TIMESTAMP_DIGEST_BYTES},
+    packet_padding::pad_packet,
+    varint, DespawnReason, PacketReader, PacketType, RejectReason, SchemaVersions, StandardHeader,
+    StateMask, Timestamp, PROTOCOL_VERSION,
+};
+
+/// Every construct fixtures are generated for, each written to a file of the
+/// same name, with a ".json" extension
+pub const CONSTRUCTS: [&str; 17] = [
+    "varint_u16",
+    "varint_u32",
+    "varint_u64",
+    "varint_i16",
+    "varint_i32",
+    "varint_i64",
+    "standard_header",
+    "ack_field",
+    "challenge_request",
+    "challenge_response",
+    "connect_request",
+    "connect_response",
+    "reject_response",
+    "disconnect",
+    "event_frames",
+    "state_mask",
+    "despawn_reason",
+];
+
+// the start of every fixture file, which is enough to tell which construct
+// its cases are for
+#[derive(DeJson)]
+struct FixtureHeader {
+    construct: String,
+    protocol_version: u16,
+}
+
+#[derive(DeJson)]
+struct FixtureFile<C> {
+    cases: Vec<C>,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct VarintCase<V> {
+    name: String,
+    // absent if the bytes are rejected, with the error they are rejected with
+    value: Option<V>,
+    error: Option<String>,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct StandardHeaderCase {
+    name: String,
+    packet_type: u8,
+    local_packet_index: u16,
+    last_remote_packet_index: u16,
+    ack_field: u32,
+    host_tick: u16,
+    last_received_tick: u16,
+    // the payload after the header, once any padding is stripped
+    payload_hex: String,
+    // the size the packet is padded up to, if it is padded
+    padded_size: Option<u16>,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct AckFieldCase {
+    name: String,
+    last_remote_packet_index: u16,
+    ack_field: u32,
+    acked: Vec<u16>,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct ChallengeRequestCase {
+    name: String,
+    timestamp: u64,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct ChallengeResponseCase {
+    name: String,
+    server_tick: u16,
+    timestamp: u64,
+    digest_hex: String,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct TypeVersion {
+    naia_id: u16,
+    version: u16,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct ArchetypeDigest {
+    archetype_id: u16,
+    digest: u32,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct EarlyDataPacket {
+    client_tick: u16,
+    perceived_tick: u16,
+    payload_hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct ConnectRequestCase {
+    name: String,
+    timestamp: u64,
+    digest_hex: String,
+    supports_compression: bool,
+    supports_message_tracing: bool,
+    session_token_hex: Option<String>,
+    early_data: Vec<EarlyDataPacket>,
+    event_versions: Vec<TypeVersion>,
+    actor_versions: Vec<TypeVersion>,
+    archetype_digests: Vec<ArchetypeDigest>,
+    auth_naia_id: Option<u16>,
+    auth_payload_hex: Option<String>,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct ConnectResponseCase {
+    name: String,
+    compression_enabled: bool,
+    message_tracing_enabled: bool,
+    resumed: bool,
+    session_token_hex: Option<String>,
+    event_versions: Vec<TypeVersion>,
+    actor_versions: Vec<TypeVersion>,
+    archetype_digests: Vec<ArchetypeDigest>,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct RejectResponseCase {
+    name: String,
+    reason: u8,
+    // false if the bytes are only ever read as the reason, but a reason is
+    // never written as them
+    encode: bool,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct DisconnectCase {
+    name: String,
+    reason: String,
+    encode: bool,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct EventFrameCase {
+    // written ahead of the frame, once both hosts have agreed to message
+    // tracing
+    trace_id: Option<u32>,
+    naia_id: u16,
+    channel_index: Option<u8>,
+    sequence: Option<u16>,
+    payload_hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct EventFramesCase {
+    name: String,
+    frames: Vec<EventFrameCase>,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct StateMaskCase {
+    name: String,
+    byte_count: u8,
+    set_bits: Vec<u8>,
+    hex: String,
+}
+
+#[derive(Clone, DeJson, SerJson)]
+struct DespawnReasonCase {
+    name: String,
+    reason: String,
+    custom_code: Option<u8>,
+    encode: bool,
+    hex: String,
+}
+
+/// Generates the fixtures for every construct, as the name of the file each
+/// is written to, and its JSON. Each file holds the construct's name, the
+/// PROTOCOL_VERSION, a description of the layout, and its cases, each of which
+/// pairs the decoded fields with the bytes they are written as, in hex
+pub fn generate() -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    for construct in CONSTRUCTS.iter() {
+        let (description, cases) = generate_cases(construct);
+        files.push((
+            format!("{}.json", construct),
+            to_file(construct, description, &cases),
+        ));
+    }
+    return files;
+}
+
+/// Checks the cases in a fixture file, which may have been written by another
+/// implementation of the protocol, against this crate: every case's bytes must
+/// be read as its fields, and its fields must be written as its bytes, unless
+/// the case is only ever read. Returns the number of cases checked, or a
+/// description of the first which failed
+pub fn verify(json: &str) -> Result<usize, String> {
+    let header = FixtureHeader::deserialize_json(json)
+        .map_err(|error| format!("not a fixture file: {}", error))?;
+    if header.protocol_version != PROTOCOL_VERSION {
+        return Err(format!(
+            "{}: written for protocol version {}, not {}",
+            header.construct, header.protocol_version, PROTOCOL_VERSION
+        ));
+    }
+    let construct = header.construct.as_str();
+    let result = match construct {
+        "varint_u16" => verify_cases(json, |case| {
+            verify_varint(case, varint::read_u16, varint::write_u16)
+        }),
+        "varint_u32" => verify_cases(json, |case| {
+            verify_varint(case, varint::read_u32, varint::write_u32)
+        }),
+        "varint_u64" => verify_cases(json, |case| {
+            verify_varint(case, varint::read_u64, varint::write_u64)
+        }),
+        "varint_i16" => verify_cases(json, |case| {
+            verify_varint(case, varint::read_i16, varint::write_i16)
+        }),
+        "varint_i32" => verify_cases(json, |case| {
+            verify_varint(case, varint::read_i32, varint::write_i32)
+        }),
+        "varint_i64" => verify_cases(json, |case| {
+            verify_varint(case, varint::read_i64, varint::write_i64)
+        }),
+        "standard_header" => verify_cases(json, verify_standard_header),
+        "ack_field" => verify_cases(json, verify_ack_field),
+        "challenge_request" => verify_cases(json, verify_challenge_request),
+        "challenge_response" => verify_cases(json, verify_challenge_response),
+        "connect_request" => verify_cases(json, verify_connect_request),
+        "connect_response" => verify_cases(json, verify_connect_response),
+        "reject_response" => verify_cases(json, verify_reject_response),
+        "disconnect" => verify_cases(json, verify_disconnect),
+        "event_frames" => verify_cases(json, verify_event_frames),
+        "state_mask" => verify_cases(json, verify_state_mask),
+        "despawn_reason" => verify_cases(json, verify_despawn_reason),
+        _ => return Err(format!("unknown construct '{}'", construct)),
+    };
+    return result.map_err(|error| format!("{}: {}", construct, error));
+}
+
+/// Writes the fixtures for every construct into the given directory, creating
+/// it if needed
+pub fn write_fixtures(directory: &Path) -> IoResult<()> {
+    fs::create_dir_all(directory)?;
+    for (file_name, json) in generate() {
+        fs::write(directory.join(file_name), json)?;
+    }
+    return Ok(());
+}
+
+/// Checks every ".json" fixture file in the given directory with `verify()`,
+/// returning the total number of cases checked, or a description of the first
+/// which failed
+pub fn verify_fixtures(directory: &Path) -> Result<usize, String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(directory)
+        .map_err(|error| format!("can't read {}: {}", directory.display(), error))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    let mut case_count = 0;
+    for path in paths {
+        let json = fs::read_to_string(&path)
+            .map_err(|error| format!("can't read {}: {}", path.display(), error))?;
+        case_count += verify(&json).map_err(|error| format!("{}: {}", path.display(), error))?;
+    }
+    return Ok(case_count);
+}
+
+// writes a fixture file with each case on its own line, so that a change to
+// the wire format shows up as a change to the cases it affects
+fn to_file(construct: &str, description: &str, cases: &[String]) -> String {
+    let case_lines: Vec<String> = cases.iter().map(|case| format!("    {}", case)).collect();
+    return format!(
+        "{{\n  \"construct\": {},\n  \"protocol_version\": {},\n  \"description\": {},\n  \
+         \"cases\": [\n{}\n  ]\n}}\n",
+        construct.to_string().serialize_json(),
+        PROTOCOL_VERSION,
+        description.to_string().serialize_json(),
+        case_lines.join(",\n")
+    );
+}
+
+fn verify_cases<C: DeJson>(
+    json: &str,
+    verify_case: impl Fn(&C) -> Result<(), String>,
+) -> Result<usize, String> {
+    let file = FixtureFile::<C>::deserialize_json(json)
+        .map_err(|error| format!("cases can't be read: {}", error))?;
+    for (index, case) in file.cases.iter().enumerate() {
+        verify_case(case).map_err(|error| format!("case {}: {}", index, error))?;
+    }
+    return Ok(file.cases.len());
+}
+
+fn generate_cases(construct: &str) -> (&'static str, Vec<String>) {
+    // each construct's cases are serialized here, as their types differ
+    fn serialized<C: SerJson>(cases: Vec<C>) -> Vec<String> {
+        return cases.iter().map(|case| case.serialize_json()).collect();
+    }
+    match construct {
+        "varint_u16" => {
+            let values = [0, 1, 127, 128, 300, 16383, 16384, u16::MAX];
+            let rejected = [vec![0x80], vec![0xff, 0xff, 0x7f], vec![0x81, 0x00]];
+            return (
+                VARINT_DESCRIPTION,
+                serialized(varint_cases(
+                    &values,
+                    &rejected,
+                    varint::read_u16,
+                    varint::write_u16,
+                )),
+            );
+        }
+        "varint_u32" => {
+            let values = [0, 127, 128, 2_097_151, 2_097_152, u32::MAX];
+            let rejected = [vec![0xff, 0xff, 0xff, 0xff, 0x7f], vec![0x80, 0x80, 0x00]];
+            return (
+                VARINT_DESCRIPTION,
+                serialized(varint_cases(
+                    &values,
+                    &rejected,
+                    varint::read_u32,
+                    varint::write_u32,
+                )),
+            );
+        }
+        "varint_u64" => {
+            let values = [0, 127, 128, u64::from(u32::MAX) + 1, u64::MAX];
+            let rejected = [
+                vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02],
+                vec![0xff],
+            ];
+            return (
+                VARINT_DESCRIPTION,
+                serialized(varint_cases(
+                    &values,
+                    &rejected,
+                    varint::read_u64,
+                    varint::write_u64,
+                )),
+            );
+        }
+        "varint_i16" => {
+            let values = [0, -1, 1, -64, 64, i16::MIN, i16::MAX];
+            let rejected = [vec![0x80, 0x80], vec![0x80, 0x00]];
+            return (
+                SIGNED_VARINT_DESCRIPTION,
+                serialized(varint_cases(
+                    &values,
+                    &rejected,
+                    varint::read_i16,
+                    varint::write_i16,
+                )),
+            );
+        }
+        "varint_i32" => {
+            let values = [0, -1, 1, -8193, 8192, i32::MIN, i32::MAX];
+            let rejected = [vec![0x80, 0x80, 0x80, 0x80, 0x80, 0x01]];
+            return (
+                SIGNED_VARINT_DESCRIPTION,
+                serialized(varint_cases(
+                    &values,
+                    &rejected,
+                    varint::read_i32,
+                    varint::write_i32,
+                )),
+            );
+        }
+        "varint_i64" => {
+            let values = [0, -1, 1, -65, 65, i64::MIN, i64::MAX];
+            let rejected = [vec![0xfe, 0x80]];
+            return (
+                SIGNED_VARINT_DESCRIPTION,
+                serialized(varint_cases(
+                    &values,
+                    &rejected,
+                    varint::read_i64,
+                    varint::write_i64,
+                )),
+            );
+        }
+        "standard_header" => {
+            return (
+                STANDARD_HEADER_DESCRIPTION,
+                serialized(standard_header_cases()),
+            )
+        }
+        "ack_field" => return (ACK_FIELD_DESCRIPTION, serialized(ack_field_cases())),
+        "challenge_request" => {
+            return (
+                CHALLENGE_REQUEST_DESCRIPTION,
+                serialized(challenge_request_cases()),
+            )
+        }
+        "challenge_response" => {
+            return (
+                CHALLENGE_RESPONSE_DESCRIPTION,
+                serialized(challenge_response_cases()),
+            )
+        }
+        "connect_request" => {
+            return (
+                CONNECT_REQUEST_DESCRIPTION,
+                serialized(connect_request_cases()),
+            )
+        }
+        "connect_response" => {
+            return (
+                CONNECT_RESPONSE_DESCRIPTION,
+                serialized(connect_response_cases()),
+            )
+        }
+        "reject_response" => {
+            return (
+                REJECT_RESPONSE_DESCRIPTION,
+                serialized(reject_response_cases()),
+            )
+        }
+        "disconnect" => return (DISCONNECT_DESCRIPTION, serialized(disconnect_cases())),
+        "event_frames" => return (EVENT_FRAMES_DESCRIPTION, serialized(event_frames_cases())),
+        "state_mask" => return (STATE_MASK_DESCRIPTION, serialized(state_mask_cases())),
+        "despawn_reason" => {
+            return (
+                DESPAWN_REASON_DESCRIPTION,
+                serialized(despawn_reason_cases()),
+            )
+        }
+        _ => panic!("no cases for construct '{}'", construct),
+    }
+}
+
+const VARINT_DESCRIPTION: &str = "An unsigned integer written seven bits at a time, least \
+significant first, with the high bit set on every byte but the last. Only the shortest encoding \
+is read, and encodings of values too large for the type are rejected, as are encodings cut short. \
+A rejected case has the error it is rejected with, and no value";
+
+const SIGNED_VARINT_DESCRIPTION: &str = "A signed integer zig-zag encoded, so that 0, -1, 1, -2, \
+2 become 0, 1, 2, 3, 4, then written as an unsigned varint of the same width: seven bits at a \
+time, least significant first, with the high bit set on every byte but the last. Only the \
+shortest encoding is read. A rejected case has the error it is rejected with, and no value";
+
+const STANDARD_HEADER_DESCRIPTION: &str =
+    "The 13 byte header every packet starts with: the packet \
+type as a u8, then as big endian integers the u16 local packet index, the u16 last remote packet \
+index, the u32 ack field, the u16 host tick and the u16 last received tick, followed by the \
+payload. If the packet type has the padded flag (0x40) set, the header is followed by a big \
+endian u16 count of zero bytes at the end of the packet, which aren't part of the payload";
+
+const ACK_FIELD_DESCRIPTION: &str = "The packets a header acknowledges: the last remote packet \
+index, then for each bit i of the ack field which is set, counting from 0 at the least \
+significant bit, the packet i + 1 before it, wrapping around below 0";
+
+const CHALLENGE_REQUEST_DESCRIPTION: &str = "The payload of a ClientChallengeRequest (packet type \
+3): the Timestamp the Client started the handshake at, as a big endian u64";
+
+const CHALLENGE_RESPONSE_DESCRIPTION: &str = "The payload of a ServerChallengeResponse (packet \
+type 4): the Server's tick as a big endian u16, the Client's Timestamp as a big endian u64, then \
+the Server's 32 byte digest of it";
+
+const CONNECT_REQUEST_DESCRIPTION: &str = "The payload of a ClientConnectRequest (packet type 5): \
+the Timestamp as a big endian u64, the 32 byte digest, a u8 of flags with 0x01 set if the Client \
+supports compression, 0x02 set if it supports message tracing & 0x04 set if early data follows \
+the session token, a u8 which is 1 if a 16 byte session token follows, then the early data, if \
+any, as a u8 count of packets each written as its big endian u16 client tick, u16 perceived tick & \
+u16 length followed by its payload, then the schema versions, then the auth Event, if any, as its big endian u16 \
+NaiaId followed by its payload up to the end of the packet. The schema versions are a varint u16 \
+count of Event types followed by the varint u16 NaiaId & varint u16 version of each, then the same \
+for Actor types";
+
+const CONNECT_RESPONSE_DESCRIPTION: &str = "The payload of a ServerConnectResponse (packet type \
+6): a u8 of flags with 0x01 set if Data packets will be compressed & 0x02 set if Events will be \
+traced, a u8 which is 1 if the previous connection was resumed, a u8 which is 1 if a 16 byte \
+session token follows, then the schema versions: a varint u16 count of Event types followed by the \
+varint u16 NaiaId & varint u16 version of each, then the same for Actor types";
+
+const REJECT_RESPONSE_DESCRIPTION: &str = "The payload of a ServerRejectResponse (packet type 9): \
+the reason as a u8. An empty payload, or a reason which isn't known, is read as Unknown (255). \
+Cases which aren't encoded are only ever read";
+
+const DISCONNECT_DESCRIPTION: &str = "The payload of a ServerDisconnect (packet type 10): the \
+length of the reason as a u8, then the reason in UTF-8, truncated to 255 bytes when written. A \
+reason cut short is read as far as it goes, and invalid UTF-8 is replaced with U+FFFD. Cases \
+which aren't encoded are only ever read";
+
+const EVENT_FRAMES_DESCRIPTION: &str = "The Events in a Data packet, after the u8 manager type: a \
+u8 count of Events, then the frame of each. A frame is the varint u16 NaiaId shifted up one bit, \
+with the lowest bit set if the Event is ordered, in which case the u8 channel index & big endian \
+u16 sequence number follow, then the varint u16 size of the payload & the payload. Once both hosts \
+have agreed to message tracing, every frame is preceded by the varint u32 trace id of its Event";
+
+const STATE_MASK_DESCRIPTION: &str = "A StateMask, written ahead of an Actor's updated \
+Properties: the number of bytes as a u8, then the bytes. Property i is updated if bit i % 8 of \
+byte i / 8 is set, counting from 0 at the least significant bit";
+
+const DESPAWN_REASON_DESCRIPTION: &str = "The reason sent along with an Actor's deletion: a u8 \
+which is 0 for OutOfScope, 1 for Destroyed or 2 for Custom, in which case the u8 custom code \
+follows. Any other value is read as Destroyed. Cases which aren't encoded are only ever read";
+
+// the pinned inputs the handshake cases are written from
+const TIMESTAMPS: [u64; 3] = [0, 1_700_000_000_123, u64::MAX];
+
+fn digest(seed: u8) -> Vec<u8> {
+    return (0..TIMESTAMP_DIGEST_BYTES as u8)
+        .map(|index| seed.wrapping_add(index))
+        .collect();
+}
+
+fn session_token() -> SessionToken {
+    let mut session_token: SessionToken = [0; SESSION_TOKEN_BYTES];
+    for (index, byte) in session_token.iter_mut().enumerate() {
+        *byte = 0xc0 + index as u8;
+    }
+    return session_token;
+}
+
+fn type_versions(versions: &[(u16, u16)]) -> Vec<TypeVersion> {
+    return versions
+        .iter()
+        .map(|(naia_id, version)| TypeVersion {
+            naia_id: *naia_id,
+            version: *version,
+        })
+        .collect();
+}
+
+fn archetype_digests(digests: &[(u16, u32)]) -> Vec<ArchetypeDigest> {
+    return digests
+        .iter()
+        .map(|(archetype_id, digest)| ArchetypeDigest {
+            archetype_id: *archetype_id,
+            digest: *digest,
+        })
+        .collect();
+}
+
+fn schema_versions_bytes(
+    event_versions: &[TypeVersion],
+    actor_versions: &[TypeVersion],
+    digests: &[ArchetypeDigest],
+) -> Vec<u8> {
+    fn pairs(versions: &[TypeVersion]) -> Vec<(u16, u16)> {
+        return versions
+            .iter()
+            .map(|version| (version.naia_id, version.version))
+            .collect();
+    }
+    let mut bytes = Vec::new();
+    SchemaVersions::write_advertised(
+        &pairs(event_versions),
+        &pairs(actor_versions),
+        &digest_pairs(digests),
+        &mut bytes,
+    );
+    return bytes;
+}
+
+fn digest_pairs(digests: &[ArchetypeDigest]) -> Vec<(u16, u32)> {
+    return digests
+        .iter()
+        .map(|digest| (digest.archetype_id, digest.digest))
+        .collect();
+}
+
+fn varint_cases<V: Copy + ToString>(
+    values: &[V],
+    rejected: &[Vec<u8>],
+    read: fn(&[u8]) -> Result<(V, usize), varint::VarintError>,
+    write: fn(V, &mut Vec<u8>),
+) -> Vec<VarintCase<V>> {
+    let mut cases = Vec::new();
+    for value in values.iter() {
+        let mut bytes = Vec::new();
+        write(*value, &mut bytes);
+        cases.push(VarintCase {
+            name: value.to_string(),
+            value: Some(*value),
+            error: None,
+            hex: to_hex(&bytes),
+        });
+    }
+    for bytes in rejected.iter() {
+        let error = match read(bytes) {
+            Ok(_) => panic!("varint {} isn't rejected", to_hex(bytes)),
+            Err(error) => format!("{:?}", error),
+        };
+        cases.push(VarintCase {
+            name: format!("rejected {}", to_hex(bytes)),
+            value: None,
+            error: Some(error),
+            hex: to_hex(bytes),
+        });
+    }
+    return cases;
+}
+
+fn standard_header_cases() -> Vec<StandardHeaderCase> {
+    let headers = [
+        (
+            "heartbeat",
+            PacketType::Heartbeat,
+            1,
+            0,
+            0,
+            0,
+            0,
+            vec![],
+            None,
+        ),
+        (
+            "data",
+            PacketType::Data,
+            513,
+            512,
+            0xdead_beef,
+            1000,
+            999,
+            vec![0x03, 0x01, 0x02],
+            None,
+        ),
+        (
+            "indices at wrap around",
+            PacketType::Data,
+            u16::MAX,
+            u16::MAX - 1,
+            u32::MAX,
+            u16::MAX,
+            0,
+            vec![0x00],
+            None,
+        ),
+        (
+            "padded",
+            PacketType::Data,
+            7,
+            6,
+            0b101,
+            42,
+            41,
+            vec![0x03, 0x01, 0x02],
+            Some(32),
+        ),
+    ];
+    let mut cases = Vec::new();
+    for (name, packet_type, local, remote, ack_field, host_tick, received_tick, payload, padded) in
+        headers.iter()
+    {
+        let header = StandardHeader::new(
+            *packet_type,
+            *local,
+            *remote,
+            *ack_field,
+            *host_tick,
+            *received_tick,
+        );
+        let bytes = write_packet(&header, payload, *padded);
+        cases.push(StandardHeaderCase {
+            name: name.to_string(),
+            packet_type: *packet_type as u8,
+            local_packet_index: *local,
+            last_remote_packet_index: *remote,
+            ack_field: *ack_field,
+            host_tick: *host_tick,
+            last_received_tick: *received_tick,
+            payload_hex: to_hex(payload),
+            padded_size: *padded,
+            hex: to_hex(&bytes),
+        });
+    }
+    return cases;
+}
+
+fn write_packet(header: &StandardHeader, payload: &[u8], padded_size: Option<u16>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    header.write(&mut bytes);
+    bytes.extend_from_slice(payload);
+    match padded_size {
+        Some(padded_size) => return pad_packet(&bytes, padded_size as usize).to_vec(),
+        None => return bytes,
+    }
+}
+
+fn ack_field_cases() -> Vec<AckFieldCase> {
+    let fields = [
+        ("none before", 100, 0),
+        ("one before", 100, 0b1),
+        ("first & last", 100, 0x8000_0001),
+        ("wraps around", 1, 0b110),
+        ("all", u16::MAX, u32::MAX),
+    ];
+    return fields
+        .iter()
+        .map(|(name, last_remote_packet_index, ack_field)| AckFieldCase {
+            name: name.to_string(),
+            last_remote_packet_index: *last_remote_packet_index,
+            ack_field: *ack_field,
+            acked: get_acked_packet_indices(*last_remote_packet_index, *ack_field),
+        })
+        .collect();
+}
+
+fn challenge_request_cases() -> Vec<ChallengeRequestCase> {
+    return TIMESTAMPS
+        .iter()
+        .map(|timestamp| ChallengeRequestCase {
+            name: timestamp.to_string(),
+            timestamp: *timestamp,
+            hex: to_hex(&handshake::write_challenge_request(
+                &byte_order::timestamp_from_u64(*timestamp),
+            )),
+        })
+        .collect();
+}
+
+fn challenge_response_cases() -> Vec<ChallengeResponseCase> {
+    let responses = [(0, TIMESTAMPS[0], 0x00), (12345, TIMESTAMPS[1], 0xa0)];
+    return responses
+        .iter()
+        .map(|(server_tick, timestamp, digest_seed)| {
+            let digest = digest(*digest_seed);
+            let bytes = handshake::write_challenge_response(
+                *server_tick,
+                &byte_order::timestamp_from_u64(*timestamp),
+                &digest,
+            );
+            return ChallengeResponseCase {
+                name: format!("tick {}", server_tick),
+                server_tick: *server_tick,
+                timestamp: *timestamp,
+                digest_hex: to_hex(&digest),
+                hex: to_hex(&bytes),
+            };
+        })
+        .collect();
+}
+
+fn connect_request_cases() -> Vec<ConnectRequestCase> {
+    let mut cases = vec![
+        ConnectRequestCase {
+            name: "minimal".to_string(),
+            timestamp: TIMESTAMPS[1],
+            digest_hex: to_hex(&digest(0xa0)),
+            supports_compression: false,
+            supports_message_tracing: false,
+            session_token_hex: None,
+            early_data: Vec::new(),
+            event_versions: Vec::new(),
+            actor_versions: Vec::new(),
+            archetype_digests: Vec::new(),
+            auth_naia_id: None,
+            auth_payload_hex: None,
+            hex: String::new(),
+        },
+        ConnectRequestCase {
+            name: "resuming with versions & auth".to_string(),
+            timestamp: TIMESTAMPS[2],
+            digest_hex: to_hex(&digest(0x10)),
+            supports_compression: true,
+            supports_message_tracing: true,
+            session_token_hex: Some(to_hex(&session_token())),
+            early_data: Vec::new(),
+            event_versions: type_versions(&[(1, 2), (5, 3)]),
+            actor_versions: type_versions(&[(0, 2)]),
+            archetype_digests: archetype_digests(&[(0, 0x811c_9dc5), (1, 0x0bad_cafe)]),
+            auth_naia_id: Some(3),
+            auth_payload_hex: Some(to_hex(b"\x05alice")),
+            hex: String::new(),
+        },
+        ConnectRequestCase {
+            name: "auth with empty payload".to_string(),
+            timestamp: TIMESTAMPS[0],
+            digest_hex: to_hex(&digest(0xff)),
+            supports_compression: true,
+            supports_message_tracing: false,
+            session_token_hex: None,
+            early_data: Vec::new(),
+            event_versions: Vec::new(),
+            actor_versions: Vec::new(),
+            archetype_digests: Vec::new(),
+            auth_naia_id: Some(0),
+            auth_payload_hex: Some(String::new()),
+            hex: String::new(),
+        },
+        ConnectRequestCase {
+            name: "resuming with early data".to_string(),
+            timestamp: TIMESTAMPS[1],
+            digest_hex: to_hex(&digest(0x42)),
+            supports_compression: false,
+            supports_message_tracing: false,
+            session_token_hex: Some(to_hex(&session_token())),
+            early_data: vec![
+                EarlyDataPacket {
+                    client_tick: 1000,
+                    perceived_tick: 990,
+                    payload_hex: "0101000000070003".to_string(),
+                },
+                EarlyDataPacket {
+                    client_tick: 65535,
+                    perceived_tick: 65530,
+                    payload_hex: String::new(),
+                },
+            ],
+            event_versions: Vec::new(),
+            actor_versions: Vec::new(),
+            archetype_digests: Vec::new(),
+            auth_naia_id: None,
+            auth_payload_hex: None,
+            hex: String::new(),
+        },
+    ];
+    for case in cases.iter_mut() {
+        case.hex = to_hex(&encode_connect_request(case).unwrap());
+    }
+    return cases;
+}
+
+fn connect_response_cases() -> Vec<ConnectResponseCase> {
+    let mut cases = vec![
+        ConnectResponseCase {
+            name: "minimal".to_string(),
+            compression_enabled: false,
+            message_tracing_enabled: false,
+            resumed: false,
+            session_token_hex: None,
+            event_versions: Vec::new(),
+            actor_versions: Vec::new(),
+            archetype_digests: Vec::new(),
+            hex: String::new(),
+        },
+        ConnectResponseCase {
+            name: "resumed with versions".to_string(),
+            compression_enabled: true,
+            message_tracing_enabled: true,
+            resumed: true,
+            session_token_hex: Some(to_hex(&session_token())),
+            event_versions: type_versions(&[(2, 2)]),
+            actor_versions: type_versions(&[(0, 3), (200, 2)]),
+            archetype_digests: archetype_digests(&[(0, 0xdead_beef)]),
+            hex: String::new(),
+        },
+        ConnectResponseCase {
+            name: "compressed".to_string(),
+            compression_enabled: true,
+            message_tracing_enabled: false,
+            resumed: false,
+            session_token_hex: None,
+            event_versions: Vec::new(),
+            actor_versions: type_versions(&[(1, 2)]),
+            archetype_digests: Vec::new(),
+            hex: String::new(),
+        },
+    ];
+    for case in cases.iter_mut() {
+        case.hex = to_hex(&encode_connect_response(case).unwrap());
+    }
+    return cases;
+}
+
+fn reject_response_cases() -> Vec<RejectResponseCase> {
+    let mut cases: Vec<RejectResponseCase> = [
+        RejectReason::DuplicateConnection,
+        RejectReason::ServerFull,
+        RejectReason::Unknown,
+    ]
+    .iter()
+    .map(|reason| RejectResponseCase {
+        name: format!("{:?}", reason),
+        reason: *reason as u8,
+        encode: true,
+        hex: to_hex(&handshake::write_reject_response(*reason)),
+    })
+    .collect();
+    for (name, bytes) in [("empty", vec![]), ("unknown reason", vec![42])].iter() {
+        cases.push(RejectResponseCase {
+            name: name.to_string(),
+            reason: handshake::read_reject_response(&mut PacketReader::new(bytes)) as u8,
+            encode: false,
+            hex: to_hex(bytes),
+        });
+    }
+    return cases;
+}
+
+fn disconnect_cases() -> Vec<DisconnectCase> {
+    let reasons = [
+        ("empty", String::new()),
+        ("ascii", "server shutting down".to_string()),
+        ("multi-byte", "redémarrage 再起動".to_string()),
+        ("longest", "x".repeat(u8::MAX as usize)),
+    ];
+    let mut cases: Vec<DisconnectCase> = reasons
+        .iter()
+        .map(|(name, reason)| DisconnectCase {
+            name: name.to_string(),
+            reason: reason.clone(),
+            encode: true,
+            hex: to_hex(&handshake::write_disconnect(reason)),
+        })
+        .collect();
+    let read_only = [
+        ("no payload", vec![]),
+        ("cut short", vec![0x05, b'a', b'b']),
+        ("invalid utf-8", vec![0x02, 0xff, b'a']),
+    ];
+    for (name, bytes) in read_only.iter() {
+        cases.push(DisconnectCase {
+            name: name.to_string(),
+            reason: handshake::read_disconnect(&mut PacketReader::new(bytes)),
+            encode: false,
+            hex: to_hex(bytes),
+        });
+    }
+    return cases;
+}
+
+fn event_frames_cases() -> Vec<EventFramesCase> {
+    let frame = |naia_id: u16, ordering: Option<(u8, u16)>, payload: &[u8]| EventFrameCase {
+        trace_id: None,
+        naia_id,
+        channel_index: ordering.map(|(channel_index, _)| channel_index),
+        sequence: ordering.map(|(_, sequence)| sequence),
+        payload_hex: to_hex(payload),
+    };
+    let mut cases = vec![
+        EventFramesCase {
+            name: "none".to_string(),
+            frames: Vec::new(),
+            hex: String::new(),
+        },
+        EventFramesCase {
+            name: "unordered".to_string(),
+            frames: vec![frame(1, None, &[]), frame(300, None, &[1, 2, 3])],
+            hex: String::new(),
+        },
+        EventFramesCase {
+            name: "ordered".to_string(),
+            frames: vec![
+                frame(2, Some((0, 0)), &[0x2a]),
+                frame(2, Some((1, u16::MAX)), &[]),
+            ],
+            hex: String::new(),
+        },
+        EventFramesCase {
+            name: "large payload".to_string(),
+            frames: vec![frame(5, None, &[0x5a; 200])],
+            hex: String::new(),
+        },
+        EventFramesCase {
+            name: "traced".to_string(),
+            frames: vec![
+                EventFrameCase {
+                    trace_id: Some(1),
+                    ..frame(1, None, &[7])
+                },
+                EventFrameCase {
+                    trace_id: Some(u32::MAX),
+                    ..frame(2, Some((0, 9)), &[])
+                },
+            ],
+            hex: String::new(),
+        },
+    ];
+    for case in cases.iter_mut() {
+        case.hex = to_hex(&encode_event_frames(case).unwrap());
+    }
+    return cases;
+}
+
+fn state_mask_cases() -> Vec<StateMaskCase> {
+    let masks: [(u8, &[u8]); 4] = [(1, &[]), (1, &[0, 7]), (2, &[0, 8, 15]), (4, &[3, 17, 31])];
+    return masks
+        .iter()
+        .map(|(byte_count, set_bits)| {
+            let case = StateMaskCase {
+                name: format!("{} bytes, bits {:?}", byte_count, set_bits),
+                byte_count: *byte_count,
+                set_bits: set_bits.to_vec(),
+                hex: String::new(),
+            };
+            return StateMaskCase {
+                hex: to_hex(&encode_state_mask(&case)),
+                ..case
+            };
+        })
+        .collect();
+}
+
+fn despawn_reason_cases() -> Vec<DespawnReasonCase> {
+    let reasons = [
+        DespawnReason::OutOfScope,
+        DespawnReason::Destroyed,
+        DespawnReason::Custom(0),
+        DespawnReason::Custom(u8::MAX),
+    ];
+    let mut cases: Vec<DespawnReasonCase> = reasons
+        .iter()
+        .map(|reason| {
+            let mut bytes = Vec::new();
+            reason.write(&mut bytes);
+            return despawn_reason_case(format!("{:?}", reason), *reason, true, &bytes);
+        })
+        .collect();
+    let bytes = [7];
+    let reason = DespawnReason::read(&mut PacketReader::new(&bytes));
+    cases.push(despawn_reason_case(
+        "unknown".to_string(),
+        reason,
+        false,
+        &bytes,
+    ));
+    return cases;
+}
+
+fn despawn_reason_case(
+    name: String,
+    reason: DespawnReason,
+    encode: bool,
+    bytes: &[u8],
+) -> DespawnReasonCase {
+    let (reason_name, custom_code) = match reason {
+        DespawnReason::OutOfScope => ("OutOfScope", None),
+        DespawnReason::Destroyed => ("Destroyed", None),
+        DespawnReason::Custom(code) => ("Custom", Some(code)),
+    };
+    return DespawnReasonCase {
+        name,
+        reason: reason_name.to_string(),
+        custom_code,
+        encode,
+        hex: to_hex(bytes),
+    };
+}
+
+fn verify_varint<V: Copy + PartialEq + core::fmt::Debug>(
+    case: &VarintCase<V>,
+    read: fn(&[u8]) -> Result<(V, usize), varint::VarintError>,
+    write: fn(V, &mut Vec<u8>),
+) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    match (read(&bytes), case.value, &case.error) {
+        (Ok((value, length)), Some(expected), None) => {
+            check("value", &expected, &value)?;
+            check("length", &bytes.len(), &length)?;
+            let mut written = Vec::new();
+            write(expected, &mut written);
+            return check_encoded(&bytes, &written);
+        }
+        (Err(error), None, Some(expected)) => {
+            return check("error", expected, &format!("{:?}", error));
+        }
+        (result, _, _) => {
+            return Err(format!(
+                "'{}' is read as {:?}, not as value {:?} / error {:?}",
+                case.name, result, case.value, case.error
+            ));
+        }
+    }
+}
+
+fn verify_standard_header(case: &StandardHeaderCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    if bytes.len() < StandardHeader::bytes_number() {
+        return Err(format!("'{}' is shorter than a header", case.name));
+    }
+    let (header, payload) = StandardHeader::read(&bytes);
+    check(
+        "packet_type",
+        &case.packet_type,
+        &(header.packet_type() as u8),
+    )?;
+    check(
+        "local_packet_index",
+        &case.local_packet_index,
+        &header.local_packet_index(),
+    )?;
+    check(
+        "last_remote_packet_index",
+        &case.last_remote_packet_index,
+        &header.last_remote_packet_index(),
+    )?;
+    check("ack_field", &case.ack_field, &header.ack_field())?;
+    check("host_tick", &case.host_tick, &header.host_tick())?;
+    check(
+        "last_received_tick",
+        &case.last_received_tick,
+        &header.last_received_tick(),
+    )?;
+    check("payload", &from_hex(&case.payload_hex)?, &payload.to_vec())?;
+
+    let header = StandardHeader::new(
+        case.packet_type.into(),
+        case.local_packet_index,
+        case.last_remote_packet_index,
+        case.ack_field,
+        case.host_tick,
+        case.last_received_tick,
+    );
+    let written = write_packet(&header, &from_hex(&case.payload_hex)?, case.padded_size);
+    return check_encoded(&bytes, &written);
+}
+
+fn verify_ack_field(case: &AckFieldCase) -> Result<(), String> {
+    return check(
+        "acked",
+        &case.acked,
+        &get_acked_packet_indices(case.last_remote_packet_index, case.ack_field),
+    );
+}
+
+fn verify_challenge_request(case: &ChallengeRequestCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    let timestamp = handshake::read_challenge_request(&mut reader)
+        .ok_or_else(|| format!("'{}' is cut short", case.name))?;
+    check("timestamp", &case.timestamp, &timestamp_to_u64(&timestamp))?;
+    check_consumed(&mut reader)?;
+    let written =
+        handshake::write_challenge_request(&byte_order::timestamp_from_u64(case.timestamp));
+    return check_encoded(&bytes, &written);
+}
+
+fn verify_challenge_response(case: &ChallengeResponseCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    let response = handshake::read_challenge_response(&mut reader)
+        .ok_or_else(|| format!("'{}' is cut short", case.name))?;
+    check("server_tick", &case.server_tick, &response.server_tick)?;
+    check(
+        "timestamp",
+        &case.timestamp,
+        &timestamp_to_u64(&response.timestamp),
+    )?;
+    check(
+        "digest",
+        &from_hex(&case.digest_hex)?,
+        &response.timestamp_digest,
+    )?;
+    check_consumed(&mut reader)?;
+    let written = handshake::write_challenge_response(
+        case.server_tick,
+        &byte_order::timestamp_from_u64(case.timestamp),
+        &from_hex(&case.digest_hex)?,
+    );
+    return check_encoded(&bytes, &written);
+}
+
+fn verify_connect_request(case: &ConnectRequestCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    let request = handshake::read_connect_request(&mut reader)
+        .ok_or_else(|| format!("'{}' is cut short", case.name))?;
+    check(
+        "timestamp",
+        &case.timestamp,
+        &timestamp_to_u64(&request.timestamp),
+    )?;
+    check(
+        "digest",
+        &from_hex(&case.digest_hex)?,
+        &request.timestamp_digest,
+    )?;
+    check(
+        "supports_compression",
+        &case.supports_compression,
+        &request.supports_compression,
+    )?;
+    check(
+        "supports_message_tracing",
+        &case.supports_message_tracing,
+        &request.supports_message_tracing,
+    )?;
+    check(
+        "session_token",
+        &session_token_from_hex(&case.session_token_hex)?,
+        &request.session_token,
+    )?;
+    check(
+        "early_data",
+        &early_data_from_case(&case.early_data)?,
+        &request.early_data,
+    )?;
+    check_schema_versions(
+        &mut reader,
+        &case.event_versions,
+        &case.actor_versions,
+        &case.archetype_digests,
+    )?;
+    let auth_event = handshake::read_auth_event(&mut reader)
+        .map(|(naia_id, payload)| (naia_id, to_hex(payload)));
+    let expected_auth_event = match (case.auth_naia_id, &case.auth_payload_hex) {
+        (Some(naia_id), Some(payload_hex)) => Some((naia_id, payload_hex.clone())),
+        (None, None) => None,
+        _ => return Err("auth_naia_id & auth_payload_hex must be given together".to_string()),
+    };
+    check("auth_event", &expected_auth_event, &auth_event)?;
+    check_consumed(&mut reader)?;
+    return check_encoded(&bytes, &encode_connect_request(case)?);
+}
+
+fn encode_connect_request(case: &ConnectRequestCase) -> Result<Vec<u8>, String> {
+    let session_token = session_token_from_hex(&case.session_token_hex)?;
+    let auth_payload = match &case.auth_payload_hex {
+        Some(payload_hex) => Some(from_hex(payload_hex)?),
+        None => None,
+    };
+    let auth_event = case
+        .auth_naia_id
+        .zip(auth_payload.as_ref())
+        .map(|(naia_id, payload)| (naia_id, payload.as_slice()));
+    let early_data = early_data_from_case(&case.early_data)?;
+    return Ok(handshake::write_connect_request(
+        &byte_order::timestamp_from_u64(case.timestamp),
+        &from_hex(&case.digest_hex)?,
+        case.supports_compression,
+        case.supports_message_tracing,
+        session_token
+            .as_ref()
+            .map(|session_token| (session_token, early_data.as_slice())),
+        &schema_versions_bytes(
+            &case.event_versions,
+            &case.actor_versions,
+            &case.archetype_digests,
+        ),
+        auth_event,
+    ));
+}
+
+fn early_data_from_case(packets: &[EarlyDataPacket]) -> Result<Vec<EarlyData>, String> {
+    let mut early_data = Vec::new();
+    for packet in packets {
+        early_data.push(EarlyData {
+            client_tick: packet.client_tick,
+            perceived_tick: packet.perceived_tick,
+            payload: from_hex(&packet.payload_hex)?,
+        });
+    }
+    return Ok(early_data);
+}
+
+fn verify_connect_response(case: &ConnectResponseCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    let response = handshake::read_connect_response(&mut reader);
+    check(
+        "compression_enabled",
+        &case.compression_enabled,
+        &response.compression_enabled,
+    )?;
+    check(
+        "message_tracing_enabled",
+        &case.message_tracing_enabled,
+        &response.message_tracing_enabled,
+    )?;
+    check("resumed", &case.resumed, &response.resumed)?;
+    check(
+        "session_token",
+        &session_token_from_hex(&case.session_token_hex)?,
+        &response.session_token,
+    )?;
+    check_schema_versions(
+        &mut reader,
+        &case.event_versions,
+        &case.actor_versions,
+        &case.archetype_digests,
+    )?;
+    check_consumed(&mut reader)?;
+    return check_encoded(&bytes, &encode_connect_response(case)?);
+}
+
+fn encode_connect_response(case: &ConnectResponseCase) -> Result<Vec<u8>, String> {
+    let session_token = session_token_from_hex(&case.session_token_hex)?;
+    return Ok(handshake::write_connect_response(
+        case.compression_enabled,
+        case.message_tracing_enabled,
+        case.resumed,
+        session_token.as_ref(),
+        &schema_versions_bytes(
+            &case.event_versions,
+            &case.actor_versions,
+            &case.archetype_digests,
+        ),
+    ));
+}
+
+fn verify_reject_response(case: &RejectResponseCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    let reason = handshake::read_reject_response(&mut reader);
+    check("reason", &case.reason, &(reason as u8))?;
+    if !case.encode {
+        return Ok(());
+    }
+    check_consumed(&mut reader)?;
+    let written = handshake::write_reject_response(case.reason.into());
+    return check_encoded(&bytes, &written);
+}
+
+fn verify_disconnect(case: &DisconnectCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    check(
+        "reason",
+        &case.reason,
+        &handshake::read_disconnect(&mut reader),
+    )?;
+    if !case.encode {
+        return Ok(());
+    }
+    check_consumed(&mut reader)?;
+    return check_encoded(&bytes, &handshake::write_disconnect(&case.reason));
+}
+
+fn verify_event_frames(case: &EventFramesCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    let mut reader = PacketReader::new(&bytes);
+    if !reader.has_more() {
+        return Err(format!("'{}' has no count of Events", case.name));
+    }
+    check("count", &case.frames.len(), &(reader.read_u8() as usize))?;
+    for (index, frame) in case.frames.iter().enumerate() {
+        if frame.trace_id.is_some() {
+            let trace_id = varint::read_from(&mut reader, varint::read_u32)
+                .map_err(|error| format!("frame {} trace id can't be read: {:?}", index, error))?;
+            check("trace_id", &frame.trace_id, &Some(trace_id))?;
+        }
+        let (naia_id, ordering, payload) = read_event_frame(&mut reader)
+            .map_err(|error| format!("frame {} can't be read: {:?}", index, error))?;
+        check("naia_id", &frame.naia_id, &naia_id)?;
+        check("ordering", &frame_ordering(frame)?, &ordering)?;
+        check("payload", &from_hex(&frame.payload_hex)?, &payload.to_vec())?;
+    }
+    check_consumed(&mut reader)?;
+    return check_encoded(&bytes, &encode_event_frames(case)?);
+}
+
+fn encode_event_frames(case: &EventFramesCase) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    bytes.write_u8(case.frames.len() as u8).unwrap(); // write event count
+    for frame in case.frames.iter() {
+        if let Some(trace_id) = frame.trace_id {
+            varint::write_u32(trace_id, &mut bytes); // write trace id
+        }
+        write_event_frame(
+            frame.naia_id,
+            frame_ordering(frame)?,
+            &from_hex(&frame.payload_hex)?,
+            &mut bytes,
+        );
+    }
+    return Ok(bytes);
+}
+
+fn frame_ordering(frame: &EventFrameCase) -> Result<Option<(u8, u16)>, String> {
+    match (frame.channel_index, frame.sequence) {
+        (Some(channel_index), Some(sequence)) => return Ok(Some((channel_index, sequence))),
+        (None, None) => return Ok(None),
+        _ => return Err("channel_index & sequence must be given together".to_string()),
+    }
+}
+
+fn verify_state_mask(case: &StateMaskCase) -> Result<(), String> {
+    let bytes = from_hex(&case.hex)?;
+    if bytes
+        .first()
+        .map_or(true, |count| bytes.len() < 1 + *count as usize)
+    {
+        return Err(format!("'{}' is cut short", case.name));
+    }
+    let mut reader = PacketReader::new(&bytes);
+    let state_mask = StateMask::read(&mut reader);
+    check("byte_count", &case.byte_count, &state_mask.byte_number())?;
+    for index in 0..(case.byte_count as u16 * 8) {
+        let index = index as u8;
+        check(
+            &format!("bit {}", index),
+            &Some(case.set_bits.contains(&index)),
+            &state_mask.get_bit(index),
+        )?;
+    }
+    check_consumed(&mut reader)?;
+    return check_encoded(&bytes, &encode_state_mask(case));
+}
+
+fn encode_state_mask(case: &StateMaskCase) -> Vec<u8> {
+    let mut state_mask = StateMask::new(case.byte_count);
+    for index in case.set_bits.iter() {
+        state_mask.set_bit(*index, true);
+    }
+    let mut bytes = Vec::new();
+    state_mask.write(&mut bytes);
+    return bytes;
+}
+
+fn verify_despawn_reason(case: &DespawnReasonCase) -> Result<(), String> {
+    let expected = match (case.reason.as_str(), case.custom_code) {
+        ("OutOfScope", None) => DespawnReason::OutOfScope,
+        ("Destroyed", None) => DespawnReason::Destroyed,
+        ("Custom", Some(code)) => DespawnReason::Custom(code),
+        _ => return Err(format!("'{}' isn't a valid reason", case.name)),
+    };
+    let bytes = from_hex(&case.hex)?;
+    if bytes.is_empty() || (bytes[0] == 2 && bytes.len() < 2) {
+        return Err(format!("'{}' is cut short", case.name));
+    }
+    let mut reader = PacketReader::new(&bytes);
+    check("reason", &expected, &DespawnReason::read(&mut reader))?;
+    if !case.encode {
+        return Ok(());
+    }
+    check_consumed(&mut reader)?;
+    let mut written = Vec::new();
+    expected.write(&mut written);
+    return check_encoded(&bytes, &written);
+}
+
+fn check_schema_versions(
+    reader: &mut PacketReader,
+    event_versions: &[TypeVersion],
+    actor_versions: &[TypeVersion],
+    digests: &[ArchetypeDigest],
+) -> Result<(), String> {
+    let (read_event_versions, read_actor_versions, read_digests) =
+        SchemaVersions::read_advertised(reader)
+            .map_err(|error| format!("schema versions can't be read: {:?}", error))?;
+    let pairs = |versions: &[TypeVersion]| -> Vec<(u16, u16)> {
+        return versions
+            .iter()
+            .map(|version| (version.naia_id, version.version))
+            .collect();
+    };
+    check(
+        "event_versions",
+        &pairs(event_versions),
+        &read_event_versions,
+    )?;
+    check(
+        "actor_versions",
+        &pairs(actor_versions),
+        &read_actor_versions,
+    )?;
+    return check("archetype_digests", &digest_pairs(digests), &read_digests);
+}
+
+fn check<T: PartialEq + core::fmt::Debug + ?Sized>(
+    field: &str,
+    expected: &T,
+    actual: &T,
+) -> Result<(), String> {
+    if expected != actual {
+        return Err(format!(
+            "{} is read as {:?}, not {:?}",
+            field, actual, expected
+        ));
+    }
+    return Ok(());
+}
+
+fn check_encoded(expected: &[u8], written: &[u8]) -> Result<(), String> {
+    if expected != written {
+        return Err(format!(
+            "fields are written as {}, not {}",
+            to_hex(written),
+            to_hex(expected)
+        ));
+    }
+    return Ok(());
+}
+
+fn check_consumed(reader: &mut PacketReader) -> Result<(), String> {
+    if reader.has_more() {
+        let position = reader.get_cursor().position() as usize;
+        return Err(format!(
+            "{} bytes are left over",
+            reader.get_buffer().len() - position
+        ));
+    }
+    return Ok(());
+}
+
+fn session_token_from_hex(hex: &Option<String>) -> Result<Option<SessionToken>, String> {
+    let hex = match hex {
+        Some(hex) => hex,
+        None => return Ok(None),
+    };
+    let bytes = from_hex(hex)?;
+    if bytes.len() != SESSION_TOKEN_BYTES {
+        return Err(format!(
+            "a session token is {} bytes, not {}",
+            SESSION_TOKEN_BYTES,
+            bytes.len()
+        ));
+    }
+    let mut session_token: SessionToken = [0; SESSION_TOKEN_BYTES];
+    session_token.copy_from_slice(&bytes);
+    return Ok(Some(session_token));
+}
+
+// Timestamps have no accessor for their time, so are converted through the
+// bytes they are written as
+fn timestamp_to_u64(timestamp: &Timestamp) -> u64 {
+    let mut bytes = Vec::new();
+    byte_order::write_timestamp(&mut bytes, timestamp);
+    return byte_order::get_u64(&bytes);
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.is_ascii() || hex.len() % 2 != 0 {
+        return Err(format!("'{}' isn't an even number of hex digits", hex));
+    }
+    return (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            return u8::from_str_radix(&hex[index..index + 2], 16)
+                .map_err(|_| format!("'{}' isn't hex", hex));
+        })
+        .collect();
+}
@@ -1,15 +1,28 @@
-use std::{net::SocketAddr, rc::Rc};
+use std::{any::TypeId, collections::VecDeque, net::SocketAddr, sync::Arc, time::Duration};
 
-use crate::{wrapping_diff, Timer};
+use log::warn;
+
+use crate::{
+    compression, message_tracing, utils::duration_between, wrapping_diff, Clock, Instant,
+    NaiaError, PacketPadding, Timer, MTU_SIZE,
+};
 
 use super::{
-    ack_manager::AckManager,
-    actors::{actor_notifiable::ActorNotifiable, actor_type::ActorType},
+    ack_manager::{AckManager, DeliveryBySize},
+    actors::{
+        actor_notifiable::ActorNotifiable, actor_type::ActorType, local_actor_key::LocalActorKey,
+    },
     connection_config::ConnectionConfig,
-    events::{event::Event, event_manager::EventManager, event_type::EventType},
+    events::{
+        event::Event, event_manager::EventManager, event_stamp::EventStamp, event_type::EventType,
+        ordered_channel::ChannelIndex, outgoing_event::OutgoingEvent, raw_event::RawEvent,
+        skipped_frame::SkippedFrame,
+    },
     manifest::Manifest,
+    packet_padding,
     packet_type::PacketType,
-    sequence_buffer::SequenceNumber,
+    schema_versions::SchemaVersions,
+    sequence_buffer::{SequenceInsertError, SequenceNumber},
     standard_header::StandardHeader,
     PacketReader,
 };
@@ -19,47 +32,129 @@ use super::{
 #[derive(Debug)]
 pub struct Connection<T: EventType> {
     address: SocketAddr,
+    clock: Arc<dyn Clock>,
     heartbeat_timer: Timer,
-    timeout_timer: Timer,
+    disconnection_timeout: Duration,
+    last_heard_at: Instant,
+    clock_gap_threshold: Duration,
+    last_update_at: Instant,
+    clock_gap_count: u64,
     ack_manager: AckManager,
     event_manager: EventManager<T>,
     last_received_tick: u16,
+    compression_enabled: bool,
+    message_tracing_enabled: bool,
+    schema_versions: SchemaVersions,
+    expired_event_count: u64,
+    packet_padding: Option<PacketPadding>,
+    max_packet_size: usize,
+    tick_interval: Duration,
+    cover_heartbeat_timer: Option<Timer>,
+    padding_bytes_sent: u64,
+    cover_heartbeat_bytes_sent: u64,
+    malformed_frame_count: u64,
+    malformed_frame_limit: Option<u32>,
+    malformed_frame_limit_exceeded: bool,
 }
 
 impl<T: EventType> Connection<T> {
     /// Create a new Connection, given the appropriate underlying managers
     pub fn new(address: SocketAddr, config: &ConnectionConfig) -> Self {
+        let mut event_manager = EventManager::new(config.ordered_channel_count);
+        event_manager.set_catch_decode_panics(config.catch_decode_panics);
+        if message_tracing::is_supported() {
+            event_manager.start_tracing(address);
+        }
+        let now = config.clock.now();
         return Connection {
             address,
-            heartbeat_timer: Timer::new(config.heartbeat_interval),
-            timeout_timer: Timer::new(config.disconnection_timeout_duration),
+            clock: config.clock.clone(),
+            heartbeat_timer: Timer::new(config.heartbeat_interval, &now),
+            disconnection_timeout: config.disconnection_timeout_duration,
+            last_heard_at: now.clone(),
+            clock_gap_threshold: config.clock_gap_threshold,
+            last_update_at: now.clone(),
+            clock_gap_count: 0,
             ack_manager: AckManager::new(),
-            event_manager: EventManager::new(),
+            event_manager,
             last_received_tick: 0,
+            compression_enabled: false,
+            message_tracing_enabled: false,
+            schema_versions: SchemaVersions::new(),
+            expired_event_count: 0,
+            packet_padding: config.packet_padding,
+            max_packet_size: MTU_SIZE + StandardHeader::bytes_number(),
+            tick_interval: config.tick_interval,
+            cover_heartbeat_timer: config
+                .cover_heartbeat_interval
+                .map(|interval| Timer::new(interval, &now)),
+            padding_bytes_sent: 0,
+            cover_heartbeat_bytes_sent: 0,
+            malformed_frame_count: 0,
+            malformed_frame_limit: config.malformed_frame_limit,
+            malformed_frame_limit_exceeded: false,
         };
     }
 
     /// Record that a message has been sent (to prevent needing to send a
     /// heartbeat)
     pub fn mark_sent(&mut self) {
-        return self.heartbeat_timer.reset();
+        return self.heartbeat_timer.reset(&self.clock.now());
     }
 
-    /// Returns whether a heartbeat message should be sent
+    /// Returns whether a heartbeat message should be sent, either because
+    /// nothing else has been sent for a while, or because a cover heartbeat
+    /// is due
     pub fn should_send_heartbeat(&self) -> bool {
-        return self.heartbeat_timer.ringing();
+        let now = self.clock.now();
+        return self.heartbeat_timer.ringing(&now) || self.is_cover_heartbeat_due(&now);
+    }
+
+    fn is_cover_heartbeat_due(&self, now: &Instant) -> bool {
+        match &self.cover_heartbeat_timer {
+            Some(timer) => return timer.ringing(now),
+            None => return false,
+        }
     }
 
     /// Record that a message has been received from a remote host (to prevent
-    /// disconnecting from the remote host)
-    pub fn mark_heard(&mut self) {
-        return self.timeout_timer.reset();
+    /// disconnecting from the remote host), given the time it was received
+    pub fn mark_heard(&mut self, now: &Instant) {
+        self.last_heard_at = now.clone();
     }
 
     /// Returns whether this connection should be dropped as a result of a
-    /// timeout
-    pub fn should_drop(&self) -> bool {
-        return self.timeout_timer.ringing();
+    /// timeout, given the current time. Clock gaps found by
+    /// `detect_clock_gap()` don't count towards the timeout
+    pub fn should_drop(&self, now: &Instant) -> bool {
+        return duration_between(&self.last_heard_at, now) > self.disconnection_timeout;
+    }
+
+    /// Checks the time since the connection was last updated, given the
+    /// current time, and returns its length if it was long enough for the
+    /// host to have been frozen, going by `ConnectionConfig::clock_gap_threshold`.
+    /// Meant to be called once each update, before anything else on the
+    /// connection. The gap is left out of the disconnection timeout, as
+    /// nothing could have been heard from the remote host while frozen, but
+    /// any silence before the gap still counts
+    pub fn detect_clock_gap(&mut self, now: &Instant) -> Option<Duration> {
+        let elapsed = duration_between(&self.last_update_at, now);
+        self.last_update_at = now.clone();
+        if elapsed <= self.clock_gap_threshold {
+            return None;
+        }
+
+        // the silence up to the last update still counts, the gap doesn't
+        let gap_millis = elapsed.as_millis().min(u128::from(u32::MAX)) as u32;
+        self.last_heard_at.add_millis(gap_millis);
+        self.clock_gap_count += 1;
+        return Some(elapsed);
+    }
+
+    /// Gets the number of clock gaps found by `detect_clock_gap()` over the
+    /// life of the connection
+    pub fn get_clock_gap_count(&self) -> u64 {
+        return self.clock_gap_count;
     }
 
     /// Process an incoming packet, pulling out the packet index number to keep
@@ -73,8 +168,47 @@ impl<T: EventType> Connection<T> {
         if wrapping_diff(self.last_received_tick, header.host_tick()) > 0 {
             self.last_received_tick = header.host_tick();
         }
-        self.ack_manager
-            .process_incoming(&header, &mut self.event_manager, actor_notifiable);
+        let result = self.ack_manager.process_incoming(
+            &self.address,
+            &header,
+            &mut self.event_manager,
+            actor_notifiable,
+        );
+        if result == Err(SequenceInsertError::TooFarAhead) {
+            warn!(
+                "packet index {} from {} is too far ahead of the last received, possible replay attack ({} so far)",
+                header.local_packet_index(),
+                self.address,
+                self.ack_manager.get_far_ahead_packet_count()
+            );
+        }
+    }
+
+    /// Drops queued Events which are not re-transmitted if lost, and have waited
+    /// longer than the expiry set for their type in the Manifest, given the
+    /// current time. Meant to be called right before Events are written into
+    /// a packet, so that a stalled connection doesn't send stale Events once
+    /// it recovers
+    pub fn expire_outgoing_events<U: ActorType>(
+        &mut self,
+        manifest: &Manifest<T, U>,
+        now: &Instant,
+    ) {
+        let expired_count = self.event_manager.expire_outgoing_events(manifest, now);
+        self.expired_event_count += expired_count as u64;
+    }
+
+    /// Gets the number of queued Events which have been dropped after waiting
+    /// longer than their expiry
+    pub fn get_expired_event_count(&self) -> u64 {
+        return self.expired_event_count;
+    }
+
+    /// Gets the number of incoming packets whose index was too far ahead of
+    /// the most recently received one to be recorded, which may indicate that
+    /// packets are being forged
+    pub fn get_far_ahead_packet_count(&self) -> u64 {
+        return self.ack_manager.get_far_ahead_packet_count();
     }
 
     /// Given a packet payload, start tracking the packet via it's index, attach
@@ -104,15 +238,133 @@ impl<T: EventType> Connection<T> {
         );
         header.write(&mut header_bytes);
 
+        #[cfg(feature = "compression")]
+        let compressed_payload = self.compress_payload(packet_type, payload, &mut header_bytes);
+        #[cfg(not(feature = "compression"))]
+        let compressed_payload: Option<Vec<u8>> = None;
+        let mut packet_bytes = [
+            header_bytes.as_slice(),
+            compressed_payload.as_deref().unwrap_or(payload),
+        ]
+        .concat()
+        .into_boxed_slice();
+
+        let now = self.clock.now();
+        if packet_type == PacketType::Heartbeat && self.is_cover_heartbeat_due(&now) {
+            // only count heartbeats which wouldn't otherwise have been sent
+            if !self.heartbeat_timer.ringing(&now) {
+                self.cover_heartbeat_bytes_sent += packet_bytes.len() as u64;
+            }
+            if let Some(timer) = &mut self.cover_heartbeat_timer {
+                timer.reset(&now);
+            }
+        }
+
+        // MTU probes are already padded to the size being probed
+        if packet_type != PacketType::MtuProbe {
+            if let Some(padded_size) = self.packet_padding.and_then(|padding| {
+                padding.get_padded_size(packet_bytes.len(), self.max_packet_size)
+            }) {
+                self.padding_bytes_sent += (padded_size - packet_bytes.len()) as u64;
+                packet_bytes = packet_padding::pad_packet(&packet_bytes, padded_size);
+            }
+        }
+
+        if packet_type == PacketType::Data {
+            self.event_manager.trace_packed(local_packet_index);
+        }
+
         // Ack stuff //
         self.ack_manager
-            .track_packet(packet_type, local_packet_index);
+            .track_packet(packet_type, local_packet_index, packet_bytes.len());
         self.ack_manager.increment_local_packet_index();
         ///////////////
 
-        [header_bytes.as_slice(), &payload]
-            .concat()
-            .into_boxed_slice()
+        return packet_bytes;
+    }
+
+    // compresses the payload of a Data packet if compression has been agreed
+    // to & makes it any smaller, flagging it as such in the header
+    #[cfg(feature = "compression")]
+    fn compress_payload(
+        &self,
+        packet_type: PacketType,
+        payload: &[u8],
+        header_bytes: &mut Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        if !self.compression_enabled || packet_type != PacketType::Data {
+            return None;
+        }
+        let compressed_payload = compression::compress(payload)?;
+        header_bytes[0] |= compression::COMPRESSED_FLAG;
+        return Some(compressed_payload);
+    }
+
+    /// Sets whether outgoing Data packets should be compressed, which should
+    /// only be enabled once both hosts have agreed to it during the handshake
+    pub fn set_compression_enabled(&mut self, enabled: bool) {
+        self.compression_enabled = enabled && compression::is_supported();
+    }
+
+    /// Returns whether outgoing Data packets are being compressed
+    pub fn is_compression_enabled(&self) -> bool {
+        return self.compression_enabled;
+    }
+
+    /// Sets whether trace ids are written ahead of & read from Event frames,
+    /// which should only be enabled once both hosts have agreed to message
+    /// tracing during the handshake
+    pub fn set_message_tracing_enabled(&mut self, enabled: bool) {
+        self.message_tracing_enabled = enabled && message_tracing::is_supported();
+        self.event_manager
+            .set_trace_ids_in_frames(self.message_tracing_enabled);
+    }
+
+    /// Returns whether trace ids are written ahead of & read from Event
+    /// frames
+    pub fn is_message_tracing_enabled(&self) -> bool {
+        return self.message_tracing_enabled;
+    }
+
+    /// Sets the schema versions to write & read Events and Actors as, agreed
+    /// on by both hosts during the handshake
+    pub fn set_schema_versions(&mut self, schema_versions: SchemaVersions) {
+        self.schema_versions = schema_versions;
+    }
+
+    /// Gets the schema versions to write & read Events and Actors as
+    pub fn get_schema_versions(&self) -> &SchemaVersions {
+        return &self.schema_versions;
+    }
+
+    /// Sets the size of the largest packet which can be sent on the
+    /// connection, in bytes, which packets are never padded past
+    pub fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.max_packet_size = max_packet_size;
+    }
+
+    /// Gets the size of the largest packet which can be sent on the
+    /// connection, in bytes
+    pub fn get_max_packet_size(&self) -> usize {
+        return self.max_packet_size;
+    }
+
+    /// Gets the duration between each tick on the connection
+    pub fn get_tick_interval(&self) -> Duration {
+        return self.tick_interval;
+    }
+
+    /// Gets the total number of padding bytes added to outgoing packets,
+    /// including the padding length written into each padded packet
+    pub fn get_padding_bytes_sent(&self) -> u64 {
+        return self.padding_bytes_sent;
+    }
+
+    /// Gets the total number of bytes sent in cover heartbeats, which would
+    /// not have been sent without `ConnectionConfig::cover_heartbeat_interval`,
+    /// not counting any padding they were sent with
+    pub fn get_cover_heartbeat_bytes_sent(&self) -> u64 {
+        return self.cover_heartbeat_bytes_sent;
     }
 
     /// Get the next outgoing packet's index
@@ -120,9 +372,66 @@ impl<T: EventType> Connection<T> {
         return self.ack_manager.get_local_packet_index();
     }
 
-    /// Queue up an event to be sent to the remote host
-    pub fn queue_event(&mut self, event: &impl Event<T>) {
-        return self.event_manager.queue_outgoing_event(event);
+    /// Queue up an event to be sent to the remote host, optionally on an
+    /// ordered channel. Returns an Error if the channel hasn't been configured
+    pub fn queue_event(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        return self
+            .event_manager
+            .queue_outgoing_event(event, channel, &self.clock.now());
+    }
+
+    /// Queue up an Event about the Actor with the given LocalActorKey to be
+    /// sent to the remote host, which receives the key along with it. Returns
+    /// an Error if the channel hasn't been configured
+    pub fn queue_actor_event(
+        &mut self,
+        event: &dyn Event<T>,
+        actor: LocalActorKey,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError> {
+        return self.event_manager.queue_outgoing_actor_event(
+            event,
+            actor,
+            channel,
+            &self.clock.now(),
+        );
+    }
+
+    /// Returns an Error if the given channel hasn't been configured, so that
+    /// an Event can't be sent on it
+    pub fn check_channel(&self, channel: Option<ChannelIndex>) -> Result<(), NaiaError> {
+        return self.event_manager.check_channel(channel);
+    }
+
+    /// Queue up a group of Events to be sent to the remote host together, in
+    /// the same packet
+    pub fn queue_event_group(&mut self, events: &[Box<dyn Event<T>>]) {
+        return self
+            .event_manager
+            .queue_outgoing_event_group(events, &self.clock.now());
+    }
+
+    /// Queue up the bytes of a RawEvent to be sent to the remote host, exactly
+    /// as an Event of the type with the given TypeId would be. Returns an
+    /// Error if the channel hasn't been configured
+    pub fn queue_raw_event(
+        &mut self,
+        raw_event: &RawEvent,
+        type_id: TypeId,
+        guaranteed: bool,
+        channel: Option<ChannelIndex>,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        let now = self.clock.now();
+        return self
+            .event_manager
+            .queue_outgoing_raw_event(raw_event, type_id, guaranteed, channel, &now);
     }
 
     /// Returns whether there are events to be sent to the remote host
@@ -130,32 +439,216 @@ impl<T: EventType> Connection<T> {
         return self.event_manager.has_outgoing_events();
     }
 
+    /// Returns the number of Events either waiting to be transmitted, or
+    /// waiting to be acknowledged by the remote host
+    pub fn get_outgoing_event_count(&self) -> usize {
+        return self.event_manager.get_outgoing_event_count();
+    }
+
     /// Pop the next outgoing event from the queue
-    pub fn pop_outgoing_event(&mut self, next_packet_index: u16) -> Option<Rc<Box<dyn Event<T>>>> {
+    pub fn pop_outgoing_event(&mut self, next_packet_index: u16) -> Option<OutgoingEvent<T>> {
         return self.event_manager.pop_outgoing_event(next_packet_index);
     }
 
     /// If for some reason the next outgoing event could not be written into a
     /// message and sent, place it back into the front of the queue
-    pub fn unpop_outgoing_event(&mut self, next_packet_index: u16, event: &Rc<Box<dyn Event<T>>>) {
+    pub fn unpop_outgoing_event(&mut self, next_packet_index: u16, event: &OutgoingEvent<T>) {
         return self
             .event_manager
             .unpop_outgoing_event(next_packet_index, event);
     }
 
+    /// Returns the number & total size in bytes of the events re-queued to be
+    /// re-transmitted since this was last called
+    pub fn take_retransmitted_events(&mut self) -> (usize, usize) {
+        return self.event_manager.take_retransmitted();
+    }
+
+    /// Returns the number of events waiting to be transmitted, which is the
+    /// position in the queue the next event queued will take
+    pub fn get_queued_event_count(&self) -> usize {
+        return self.event_manager.get_queued_event_count();
+    }
+
+    /// Pop the next outgoing event from those queued at or after the given
+    /// position in the queue
+    pub fn pop_outgoing_event_from(
+        &mut self,
+        next_packet_index: u16,
+        queue_position: usize,
+    ) -> Option<OutgoingEvent<T>> {
+        return self
+            .event_manager
+            .pop_outgoing_event_from(next_packet_index, queue_position);
+    }
+
+    /// If an event popped from the given position in the queue could not be
+    /// written into a message and sent, place it back where it was
+    pub fn unpop_outgoing_event_to(
+        &mut self,
+        next_packet_index: u16,
+        event: &OutgoingEvent<T>,
+        queue_position: usize,
+    ) {
+        return self.event_manager.unpop_outgoing_event_to(
+            next_packet_index,
+            event,
+            queue_position,
+        );
+    }
+
     /// Given an incoming packet which has been identified as an event, send the
-    /// data to the EventManager for processing
+    /// data to the EventManager for processing, along with the remote host's
+    /// tick when it sent the packet, and the time the packet arrived at
     pub fn process_event_data<U: ActorType>(
         &mut self,
         reader: &mut PacketReader,
         manifest: &Manifest<T, U>,
+        packet_tick: u16,
+        received_at: &Instant,
+    ) {
+        return self.event_manager.process_data(
+            reader,
+            manifest,
+            &self.schema_versions,
+            packet_tick,
+            received_at,
+        );
+    }
+
+    /// Get the most recent event that has been received from a remote host,
+    /// along with its EventStamp if its type is timestamped, and the Actor it's
+    /// about if its type is actor-scoped
+    pub fn get_incoming_event(&mut self) -> Option<(T, Option<EventStamp>, Option<LocalActorKey>)> {
+        return self.event_manager.pop_incoming_stamped_event();
+    }
+
+    /// Get the most recent RawEvent that has been received from a remote host
+    pub fn get_incoming_raw_event(&mut self) -> Option<RawEvent> {
+        return self.event_manager.pop_incoming_raw_event();
+    }
+
+    /// Get the next Event from the remote host which was skipped over, rather
+    /// than received, because it couldn't be read. Those skipped for being
+    /// malformed, rather than of an unknown type, are counted as they're
+    /// taken
+    pub fn get_skipped_frame(&mut self) -> Option<SkippedFrame> {
+        let skipped_frame = self.event_manager.pop_skipped_frame()?;
+        if !matches!(skipped_frame, SkippedFrame::UnknownType(_)) {
+            self.malformed_frame_count += 1;
+        }
+        return Some(skipped_frame);
+    }
+
+    /// Get how many malformed Events from the remote host have been taken
+    /// with `get_skipped_frame()`
+    pub fn get_malformed_frame_count(&self) -> u64 {
+        return self.malformed_frame_count;
+    }
+
+    /// Returns whether the remote host has sent more malformed Events than
+    /// the limit set in the ConnectionConfig, only the first time it's called
+    /// after the limit has been exceeded, so that the connection is only
+    /// dropped over it once
+    pub fn take_malformed_frame_limit_exceeded(&mut self) -> bool {
+        if self.malformed_frame_limit_exceeded {
+            return false;
+        }
+        if let Some(limit) = self.malformed_frame_limit {
+            if self.malformed_frame_count > u64::from(limit) {
+                self.malformed_frame_limit_exceeded = true;
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Get the number of received Events waiting to be handed to the
+    /// application
+    pub fn get_incoming_event_count(&self) -> usize {
+        return self.event_manager.get_incoming_event_count();
+    }
+
+    /// Removes & returns the received Events from the given position in the
+    /// queue onwards, along with their EventStamps & the Actors they're about
+    pub fn take_incoming_events_from(
+        &mut self,
+        start: usize,
+    ) -> VecDeque<(T, Option<EventStamp>, Option<LocalActorKey>)> {
+        return self.event_manager.take_incoming_events_from(start);
+    }
+
+    /// Adds a received Event to the back of the queue to be handed to the
+    /// application, along with its EventStamp & the Actor it's about
+    pub fn push_incoming_event(
+        &mut self,
+        event: T,
+        stamp: Option<EventStamp>,
+        actor: Option<LocalActorKey>,
     ) {
-        return self.event_manager.process_data(reader, manifest);
+        self.event_manager.push_incoming_event(event, stamp, actor);
+    }
+
+    /// Gets the number of Events, and their total size in bytes, which must be
+    /// re-transmitted until delivered and have not been yet, on either the
+    /// given ordered channel, or the unordered channel if None
+    pub fn get_reliable_usage(&self, channel: Option<ChannelIndex>) -> (usize, usize) {
+        return self.event_manager.get_reliable_usage(channel);
+    }
+
+    /// Gets the time at which the oldest Event which must be re-transmitted
+    /// until delivered, and has not been yet, was queued
+    pub fn get_oldest_reliable_queued_at(&self) -> Option<Instant> {
+        return self.event_manager.get_oldest_reliable_queued_at();
+    }
+
+    /// Gets the tail of the Events queued so far, which a barrier placed now
+    /// is set at
+    pub fn get_reliable_tail(&self) -> u64 {
+        return self.event_manager.get_reliable_tail();
+    }
+
+    /// Returns whether every Event which must be re-transmitted until
+    /// delivered, and was queued before the given tail, has been delivered
+    pub fn is_delivered_up_to(&self, tail: u64) -> bool {
+        return self.event_manager.is_delivered_up_to(tail);
+    }
+
+    /// Gets the number of bytes the connection has allocated for its protocol
+    /// buffers: the record of received packets, and the buffers of each
+    /// ordered channel, which are only allocated once the channel is used
+    pub fn get_protocol_memory(&self) -> usize {
+        return self.ack_manager.get_allocated_bytes()
+            + self.event_manager.get_channel_allocated_bytes();
     }
 
-    /// Get the most recent event that has been received from a remote host
-    pub fn get_incoming_event(&mut self) -> Option<T> {
-        return self.event_manager.pop_incoming_event();
+    /// Gets the number of bytes allocated for the buffers of the ordered
+    /// channels alone
+    pub fn get_channel_memory(&self) -> usize {
+        return self.event_manager.get_channel_allocated_bytes();
+    }
+
+    /// Gets the fraction of recently sent Data packets which have not been
+    /// acknowledged by the remote host, including those still in flight
+    pub fn get_packet_loss(&self) -> f32 {
+        return self.ack_manager.get_packet_loss();
+    }
+
+    /// Gets the fraction of recently sent Data packets known to have been
+    /// dropped, leaving out those still in flight
+    pub fn get_settled_packet_loss(&self) -> f32 {
+        return self.ack_manager.get_settled_packet_loss();
+    }
+
+    /// Gets the delivery of recently sent Data packets, split into those no
+    /// larger than the given size in bytes and those larger, counting only
+    /// packets sent from the given packet index onwards
+    pub fn get_delivery_by_size(
+        &self,
+        size_threshold: usize,
+        since: SequenceNumber,
+    ) -> DeliveryBySize {
+        return self.ack_manager.get_delivery_by_size(size_threshold, since);
     }
 
     /// Get the address of the remote host
@@ -168,3 +661,201 @@ impl<T: EventType> Connection<T> {
         return self.last_received_tick;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{any::TypeId, net::SocketAddr, sync::Arc, time::Duration};
+
+    use super::Connection;
+    use crate::{
+        Clock, ConnectionConfig, EventType, ManualClock, PacketPadding, PacketType, StandardHeader,
+        SystemClock,
+    };
+
+    #[derive(Clone)]
+    struct NoEvents;
+
+    impl EventType for NoEvents {
+        fn write(&self, _buffer: &mut Vec<u8>) {}
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<NoEvents>();
+        }
+    }
+
+    fn new_connection(config: ConnectionConfig) -> Connection<NoEvents> {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        return Connection::new(address, &config);
+    }
+
+    #[test]
+    fn padded_packets_follow_quantization_and_read_back() {
+        let mut connection = new_connection(ConnectionConfig {
+            packet_padding: Some(PacketPadding::ToMultipleOf(64)),
+            ..Default::default()
+        });
+
+        let mut padding_bytes_sent = 0;
+        for payload_size in [0, 10, 49, 50, 200, 440, 493].iter() {
+            let payload: Vec<u8> = (0..*payload_size).map(|i| i as u8).collect();
+            let packet = connection.process_outgoing_header(1, 2, PacketType::Data, &payload);
+
+            let unpadded_size = StandardHeader::bytes_number() + payload.len();
+            if unpadded_size + 2 <= 448 {
+                assert_eq!(packet.len() % 64, 0, "{} byte payload", payload_size);
+                assert!(packet.len() >= unpadded_size + 2);
+                padding_bytes_sent += (packet.len() - unpadded_size) as u64;
+            } else {
+                // padding up to the next multiple would go past the MTU
+                assert_eq!(packet.len(), unpadded_size);
+            }
+
+            let (header, read_payload) = StandardHeader::read(&packet);
+            assert_eq!(header.packet_type(), PacketType::Data);
+            assert_eq!(header.host_tick(), 1);
+            assert_eq!(read_payload.as_ref(), payload.as_slice());
+        }
+        assert_eq!(connection.get_padding_bytes_sent(), padding_bytes_sent);
+
+        // MTU probes are sent at exactly the size being probed
+        let probe = connection.process_outgoing_header(1, 2, PacketType::MtuProbe, &[0; 100]);
+        assert_eq!(probe.len(), StandardHeader::bytes_number() + 100);
+
+        // a larger MTU allows larger packets to be padded
+        connection.set_max_packet_size(1472);
+        let packet = connection.process_outgoing_header(1, 2, PacketType::Data, &[0; 493]);
+        assert_eq!(packet.len(), 512);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_packets_as_large_as_the_mtu_read_back() {
+        let mut connection = new_connection(ConnectionConfig::default());
+        connection.set_compression_enabled(true);
+        connection.set_max_packet_size(1472);
+
+        let payload: Vec<u8> = (0..1400).map(|i| (i % 13) as u8).collect();
+        let packet = connection.process_outgoing_header(1, 2, PacketType::Data, &payload);
+        assert!(packet.len() < StandardHeader::bytes_number() + payload.len());
+
+        let (header, read_payload) = StandardHeader::read(&packet);
+        assert_eq!(header.packet_type(), PacketType::Data);
+        assert_eq!(read_payload.as_ref(), payload.as_slice());
+    }
+
+    #[test]
+    fn cover_heartbeats_are_due_regardless_of_activity() {
+        let clock = Arc::new(ManualClock::new());
+        let mut connection = new_connection(ConnectionConfig {
+            heartbeat_interval: Duration::from_secs(60),
+            cover_heartbeat_interval: Some(Duration::from_millis(100)),
+            packet_padding: Some(PacketPadding::ToSize(100)),
+            clock: clock.clone(),
+            ..Default::default()
+        });
+
+        connection.mark_sent();
+        assert!(!connection.should_send_heartbeat());
+        clock.advance(Duration::from_millis(101));
+        connection.mark_sent();
+        assert!(connection.should_send_heartbeat());
+        let heartbeat = connection.process_outgoing_header(0, 0, PacketType::Heartbeat, &[]);
+        assert_eq!(heartbeat.len(), 100);
+        assert_eq!(
+            connection.get_cover_heartbeat_bytes_sent(),
+            StandardHeader::bytes_number() as u64
+        );
+        assert_eq!(
+            connection.get_padding_bytes_sent(),
+            (100 - StandardHeader::bytes_number()) as u64
+        );
+
+        let without_cover = new_connection(ConnectionConfig {
+            heartbeat_interval: Duration::from_secs(60),
+            ..Default::default()
+        });
+        assert!(!without_cover.should_send_heartbeat());
+    }
+
+    #[test]
+    fn heartbeats_alone_allocate_no_channel_buffers() {
+        let config = || ConnectionConfig {
+            ordered_channel_count: 8,
+            ..Default::default()
+        };
+        let mut client = new_connection(config());
+        let mut server = new_connection(config());
+        for tick in 0..200 {
+            let heartbeat = client.process_outgoing_header(tick, tick, PacketType::Heartbeat, &[]);
+            let (header, _) = StandardHeader::read(&heartbeat);
+            server.process_incoming_header(&header, &mut None);
+            let heartbeat = server.process_outgoing_header(tick, tick, PacketType::Heartbeat, &[]);
+            let (header, _) = StandardHeader::read(&heartbeat);
+            client.process_incoming_header(&header, &mut None);
+        }
+
+        for connection in [&client, &server].iter() {
+            assert_eq!(connection.get_channel_memory(), 0);
+            // the record of received packets is all the memory held
+            assert_eq!(
+                connection.get_protocol_memory(),
+                connection.ack_manager.get_allocated_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn acks_resume_after_a_gap_past_max_advance() {
+        let mut client = new_connection(ConnectionConfig::default());
+        let mut server = new_connection(ConnectionConfig::default());
+        let mut send = |client: &mut Connection<NoEvents>, delivered: bool| {
+            let packet = client.process_outgoing_header(0, 0, PacketType::Heartbeat, &[]);
+            let (header, _) = StandardHeader::read(&packet);
+            if delivered {
+                server.process_incoming_header(&header, &mut None);
+            }
+            return header.local_packet_index();
+        };
+
+        for _ in 0..10 {
+            send(&mut client, true);
+        }
+        // a burst of loss longer than the window of received packets can skip
+        for _ in 0..600 {
+            send(&mut client, false);
+        }
+
+        let mut last_sent = 0;
+        for _ in 0..5 {
+            last_sent = send(&mut client, true);
+        }
+        assert_eq!(server.ack_manager.get_last_remote_packet_index(), last_sent);
+        assert_eq!(server.get_far_ahead_packet_count(), 2);
+    }
+
+    #[test]
+    fn clock_gap_is_left_out_of_timeout() {
+        let mut connection = new_connection(ConnectionConfig {
+            disconnection_timeout_duration: Duration::from_secs(10),
+            ..Default::default()
+        });
+        let mut now = SystemClock.now();
+        connection.mark_heard(&now);
+
+        // updated every second while nothing is heard, then frozen for 30
+        // seconds
+        for _ in 0..8 {
+            now.add_millis(1000);
+            assert_eq!(connection.detect_clock_gap(&now), None);
+        }
+        now.add_millis(30_000);
+        let gap = connection.detect_clock_gap(&now).unwrap();
+        assert_eq!(gap.as_secs(), 30);
+        assert!(!connection.should_drop(&now));
+        assert_eq!(connection.get_clock_gap_count(), 1);
+
+        // the silence before the gap still counts
+        now.add_millis(2001);
+        assert_eq!(connection.detect_clock_gap(&now), None);
+        assert!(connection.should_drop(&now));
+    }
+}
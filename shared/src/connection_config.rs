@@ -1,4 +1,10 @@
-use std::{default::Default, time::Duration};
+use std::{default::Default, sync::Arc, time::Duration};
+
+use crate::{ChannelIndex, Clock, NaiaError, PacketPadding, SystemClock};
+
+// a gap between updates this long is far past any frame hitch, but short of
+// the disconnection timeout
+const DEFAULT_CLOCK_GAP_THRESHOLD: Duration = Duration::from_secs(3);
 
 /// Contains Config properties which will be used by a Server or Client
 #[derive(Clone, Debug)]
@@ -15,6 +21,40 @@ pub struct ConnectionConfig {
     /// Number of samples to measure RTT & Jitter by. A higher number will
     /// smooth out RTT measurements, but at the cost of responsiveness.
     pub rtt_sample_size: u16,
+    /// Number of ordered Event channels available on the connection
+    pub ordered_channel_count: u8,
+    /// The duration between each tick, as set in the SharedConfig
+    pub tick_interval: Duration,
+    /// How outgoing packets are padded, to hide their sizes from anyone
+    /// observing the traffic. None, the default, sends packets unpadded
+    pub packet_padding: Option<PacketPadding>,
+    /// The duration between cover heartbeats, which are sent to the remote
+    /// host whether or not anything else has been sent, so that the rate of
+    /// packets gives away less about activity on the connection. None, the
+    /// default, only sends heartbeats when the connection is otherwise idle
+    pub cover_heartbeat_interval: Option<Duration>,
+    /// The longest the connection can go between updates before the host is
+    /// taken to have been frozen, such as by a laptop suspending or a phone
+    /// app being backgrounded. A frozen host couldn't hear the remote host,
+    /// so the gap doesn't count towards the disconnection timeout, and the
+    /// estimates of time on the connection are restarted afterwards. Only
+    /// checked by hosts which call `Connection::detect_clock_gap()` on every
+    /// update
+    pub clock_gap_threshold: Duration,
+    /// Whether a panic while reading a received Event, such as a Property
+    /// indexing past the end of a payload shorter than its type expects, is
+    /// caught & the Event skipped over as a SkippedFrame, rather than taking
+    /// the host down with it. True by default. Only has an effect when built
+    /// to unwind on panic
+    pub catch_decode_panics: bool,
+    /// How many malformed Events, skipped over as `SkippedFrame::DecodeFailed`
+    /// or `SkippedFrame::Malformed`, the remote host can send before the
+    /// connection is dropped for it. None, the default, never drops the
+    /// connection over malformed Events
+    pub malformed_frame_limit: Option<u32>,
+    /// The Clock every time on the connection is read from. The SystemClock
+    /// by default
+    pub clock: Arc<dyn Clock>,
 }
 
 impl ConnectionConfig {
@@ -24,13 +64,39 @@ impl ConnectionConfig {
         heartbeat_interval: Duration,
         ping_interval: Duration,
         rtt_sample_size: u16,
+        ordered_channel_count: u8,
+        tick_interval: Duration,
+        packet_padding: Option<PacketPadding>,
+        cover_heartbeat_interval: Option<Duration>,
     ) -> Self {
         ConnectionConfig {
             disconnection_timeout_duration,
             heartbeat_interval,
             ping_interval,
             rtt_sample_size,
+            ordered_channel_count,
+            tick_interval,
+            packet_padding,
+            cover_heartbeat_interval,
+            clock_gap_threshold: DEFAULT_CLOCK_GAP_THRESHOLD,
+            catch_decode_panics: true,
+            malformed_frame_limit: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Returns an Error if the given channel isn't one of the ordered channels
+    /// configured, so that an Event can't be sent on it
+    pub fn check_channel(&self, channel: Option<ChannelIndex>) -> Result<(), NaiaError> {
+        if let Some(channel_index) = channel {
+            if channel_index >= self.ordered_channel_count {
+                return Err(NaiaError::NoSuchChannel {
+                    channel_index,
+                    channel_count: self.ordered_channel_count as usize,
+                });
+            }
         }
+        return Ok(());
     }
 }
 
@@ -41,6 +107,14 @@ impl Default for ConnectionConfig {
             heartbeat_interval: Duration::from_secs(4),
             ping_interval: Duration::from_secs(1),
             rtt_sample_size: 20,
+            ordered_channel_count: 1,
+            tick_interval: Duration::from_secs(1),
+            packet_padding: None,
+            cover_heartbeat_interval: None,
+            clock_gap_threshold: DEFAULT_CLOCK_GAP_THRESHOLD,
+            catch_decode_panics: true,
+            malformed_frame_limit: None,
+            clock: Arc::new(SystemClock),
         }
     }
 }
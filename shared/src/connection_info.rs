@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+/// The parameters a connection is running with, as agreed by both hosts while
+/// establishing it, or discovered since
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionInfo {
+    /// Whether Data packets are compressed, which requires both hosts to
+    /// support it
+    pub compression_enabled: bool,
+    /// Whether Events are traced across both hosts, which requires both to
+    /// be built with message tracing
+    pub message_tracing_enabled: bool,
+    /// The size of the largest packet which is sent on the connection, in
+    /// bytes. On the Server, this grows as MTU discovery completes
+    pub mtu: usize,
+    /// The duration between each tick
+    pub tick_interval: Duration,
+    /// Whether the Server issued a session token, with which the connection
+    /// can be resumed after dropping
+    pub resumable: bool,
+}
@@ -0,0 +1,239 @@
+use std::{error::Error, fmt};
+
+/// An Error that can be returned by the public API of the Server or Client
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum NaiaError {
+    /// An Event or Actor could not be written into, or read from, a packet
+    Serialization(String),
+    /// A type was used which has not been registered in the Manifest
+    Manifest(ManifestError),
+    /// Returned when trying to queue an Event for a connection whose outgoing
+    /// Event queue has reached the hard congestion limit
+    ChannelFull,
+    /// Returned when trying to queue an Event for a connection which is being
+    /// closed, after overflowing one of its reliable Event buffers
+    ReliableBufferOverflow,
+    /// Returned when trying to send before a connection has been established,
+    /// something which isn't held until it is, or to a User who isn't
+    /// connected
+    NotConnected,
+    /// Returned when trying to send a guaranteed Event before a connection has
+    /// been established, once as many are held to be sent as the Client is
+    /// configured to hold
+    PreConnectionQueueFull {
+        /// The most Events & EventGroups held until connected
+        max_messages: usize,
+    },
+    /// Returned when trying to send an Event which is too large to ever fit
+    /// into a single packet
+    MessageTooLarge {
+        /// The size of the Event's payload, in bytes
+        size: usize,
+        /// The largest payload an Event can have, in bytes
+        max: usize,
+    },
+    /// Returned when trying to send an Event on an ordered channel which
+    /// hasn't been configured, either given when sending it or registered for
+    /// its type in the Manifest
+    NoSuchChannel {
+        /// The index of the channel
+        channel_index: u8,
+        /// The number of ordered channels configured in the SharedConfig
+        channel_count: usize,
+    },
+    /// An Error produced by the underlying socket
+    Transport(Box<dyn Error + Send + Sync>),
+    /// Returned when receiving events one at a time while an event handler is
+    /// set to be called with them instead, or when driving the event handler
+    /// while none is set, so that no event is delivered twice
+    WrongEventMode,
+    /// Returned when trying to send an Event of a type registered since the
+    /// Server was started to a Client which hasn't confirmed that it has
+    /// registered the type too, under the same NaiaId
+    PeerLacksType {
+        /// The NaiaId of the Event's type
+        naia_id: u16,
+    },
+    /// Returned when starting a host with a configuration which can't be
+    /// used, describing what's wrong with it
+    InvalidConfig(String),
+}
+
+impl fmt::Display for NaiaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            NaiaError::Serialization(msg) => write!(f, "Naia Error: serialization failed: {}", msg),
+            NaiaError::Manifest(err) => fmt::Display::fmt(err, f),
+            NaiaError::ChannelFull => write!(f, "Naia Error: connection congested"),
+            NaiaError::ReliableBufferOverflow => write!(f, "Naia Error: reliable buffer overflow"),
+            NaiaError::NotConnected => write!(f, "Naia Error: not connected"),
+            NaiaError::PreConnectionQueueFull { max_messages } => write!(
+                f,
+                "Naia Error: already holding {} messages until connected",
+                max_messages
+            ),
+            NaiaError::MessageTooLarge { size, max } => write!(
+                f,
+                "Naia Error: message of {} bytes is larger than the maximum of {} bytes",
+                size, max
+            ),
+            NaiaError::NoSuchChannel {
+                channel_index,
+                channel_count,
+            } => write!(
+                f,
+                "Naia Error: no ordered channel {}, only {} are configured",
+                channel_index, channel_count
+            ),
+            NaiaError::Transport(boxed_err) => fmt::Display::fmt(boxed_err.as_ref(), f),
+            NaiaError::WrongEventMode => {
+                write!(f, "Naia Error: events are consumed through the other API")
+            }
+            NaiaError::PeerLacksType { naia_id } => write!(
+                f,
+                "Naia Error: peer has not registered the event type with naia id {}",
+                naia_id
+            ),
+            NaiaError::InvalidConfig(msg) => write!(f, "Naia Error: invalid config: {}", msg),
+        }
+    }
+}
+
+impl Error for NaiaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NaiaError::Manifest(err) => Some(err),
+            NaiaError::Transport(boxed_err) => Some(boxed_err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// An Error returned when looking up a type which has not been registered in
+/// the Manifest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ManifestError {
+    /// The Event type has not been registered
+    UnregisteredEvent,
+    /// The Actor type has not been registered
+    UnregisteredActor,
+    /// The Event type has not been set to be sent about an Actor
+    EventNotActorScoped,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ManifestError::UnregisteredEvent => {
+                write!(
+                    f,
+                    "Naia Error: Event type is not registered in the Manifest"
+                )
+            }
+            ManifestError::UnregisteredActor => {
+                write!(
+                    f,
+                    "Naia Error: Actor type is not registered in the Manifest"
+                )
+            }
+            ManifestError::EventNotActorScoped => {
+                write!(
+                    f,
+                    "Naia Error: Event type is not set to be actor-scoped in the Manifest"
+                )
+            }
+        }
+    }
+}
+
+impl Error for ManifestError {}
+
+/// An Error returned when merging one Manifest into another, or adding a
+/// ProtocolPlugin to one, which would leave the two in conflict. The Manifest
+/// merged into is left as it was
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// The Event type is registered in both Manifests. Holds its name, if its
+    /// builder describes it
+    DuplicateEvent(Option<String>),
+    /// The Actor type is registered in both Manifests. Holds its name, if its
+    /// builder describes it
+    DuplicateActor(Option<String>),
+    /// An Archetype of the same name is registered in both Manifests
+    DuplicateArchetype(String),
+    /// With deterministic ids, two different types of the same kind, or two
+    /// Archetypes, share the name their ids are given in order of
+    DuplicateName(String),
+    /// With deterministic ids, a type's builder doesn't describe its name, as
+    /// those of derived types do
+    UnnamedType,
+    /// There would be more Event types, Actor types, or Archetypes than can be
+    /// told apart on the wire
+    TooManyTypes,
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            MergeError::DuplicateEvent(name) => write!(
+                f,
+                "Naia Error: Event type {} is registered in both Manifests",
+                name.as_deref().unwrap_or("<unnamed>")
+            ),
+            MergeError::DuplicateActor(name) => write!(
+                f,
+                "Naia Error: Actor type {} is registered in both Manifests",
+                name.as_deref().unwrap_or("<unnamed>")
+            ),
+            MergeError::DuplicateArchetype(name) => write!(
+                f,
+                "Naia Error: Archetype '{}' is registered in both Manifests",
+                name
+            ),
+            MergeError::DuplicateName(name) => write!(
+                f,
+                "Naia Error: more than one type or Archetype is named {}, so ids can't be given \
+                 in order of name",
+                name
+            ),
+            MergeError::UnnamedType => write!(
+                f,
+                "Naia Error: a type's builder doesn't describe its name, so ids can't be given \
+                 in order of name"
+            ),
+            MergeError::TooManyTypes => write!(
+                f,
+                "Naia Error: the merged Manifest would have more types than can be told apart \
+                 on the wire"
+            ),
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+impl From<ManifestError> for NaiaError {
+    fn from(err: ManifestError) -> Self {
+        NaiaError::Manifest(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, thread};
+
+    use super::NaiaError;
+
+    #[test]
+    fn transport_errors_cross_threads() {
+        let error = NaiaError::Transport(Box::new(io::Error::from(io::ErrorKind::TimedOut)));
+        let message = thread::spawn(move || return error.to_string())
+            .join()
+            .unwrap();
+        assert_eq!(
+            message,
+            io::Error::from(io::ErrorKind::TimedOut).to_string()
+        );
+    }
+}
@@ -4,6 +4,7 @@ use std::{
 };
 
 use super::event_type::EventType;
+use crate::actors::actor_ref::ActorRef;
 
 /// An Event is a struct of data that can be sent and recreated on the connected
 /// remote host
@@ -13,10 +14,21 @@ pub trait Event<T: EventType>: EventClone<T> {
     fn is_guaranteed(&self) -> bool;
     /// Writes the current Event into an outgoing packet's byte stream
     fn write(&self, out_bytes: &mut Vec<u8>);
+    /// Writes the current Event into an outgoing packet's byte stream, as the
+    /// given schema version of its type, leaving out any Properties added in
+    /// later versions. Types without versions are always written whole
+    fn write_versioned(&self, _version: u16, out_bytes: &mut Vec<u8>) {
+        self.write(out_bytes);
+    }
     /// Gets a copy of the Event, encapsulated within an EventType enum
     fn get_typed_copy(&self) -> T;
     /// Gets the TypeId of the Event
     fn get_type_id(&self) -> TypeId;
+    /// Gets all the ActorRefs held by the Event's Properties, which the
+    /// receiver can wait on to resolve before handing the Event over
+    fn get_actor_refs(&self) -> Vec<ActorRef> {
+        return Vec::new();
+    }
 }
 
 /// A Boxed Event must be able to clone itself
@@ -3,7 +3,7 @@ use std::{
     fmt::{Debug, Formatter, Result},
 };
 
-use crate::PacketReader;
+use crate::{protocol_schema::TypeDescription, PacketReader};
 
 use super::event_type::EventType;
 
@@ -13,6 +13,22 @@ pub trait EventBuilder<T: EventType> {
     fn get_type_id(&self) -> TypeId;
     /// Creates a new Event
     fn build(&self, reader: &mut PacketReader) -> T;
+    /// Gets the schema version of the Event it is able to build, which goes up
+    /// as Properties are added to it
+    fn get_schema_version(&self) -> u16 {
+        return 1;
+    }
+    /// Creates a new Event, written as the given schema version of its type,
+    /// giving any Properties added in later versions their defaults
+    fn build_versioned(&self, reader: &mut PacketReader, _version: u16) -> T {
+        return self.build(reader);
+    }
+    /// Describes the name & Properties of the Event it is able to build, for
+    /// `Manifest::export_schema()`. Derived Events describe themselves, other
+    /// builders describe nothing by default
+    fn describe(&self) -> Option<TypeDescription> {
+        return None;
+    }
 }
 
 impl<T: EventType> Debug for Box<dyn EventBuilder<T>> {
@@ -0,0 +1,94 @@
+use byteorder::WriteBytesExt;
+
+use crate::{
+    byte_order,
+    events::ordered_channel::{ChannelIndex, ORDERED_EVENT_FLAG},
+    sequence_buffer::SequenceNumber,
+    varint::{self, VarintError},
+    PacketReader,
+};
+
+/// Writes an Event's frame: its NaiaId, flagged if the channel index &
+/// sequence number of an ordered Event follow, then the size of its payload &
+/// the payload itself
+pub fn write_event_frame(
+    naia_id: u16,
+    ordering: Option<(ChannelIndex, SequenceNumber)>,
+    payload: &[u8],
+    out_bytes: &mut Vec<u8>,
+) {
+    match ordering {
+        Some((channel_index, sequence)) => {
+            varint::write_u16((naia_id << 1) | ORDERED_EVENT_FLAG, out_bytes); // write naia id, flagged as ordered
+            out_bytes.write_u8(channel_index).unwrap(); // write channel index
+            byte_order::write_u16(out_bytes, sequence); // write sequence number
+        }
+        None => {
+            varint::write_u16(naia_id << 1, out_bytes); // write naia id
+        }
+    }
+    // the size is always written, so that the receiver can skip over an
+    // Event it can't read, and still read the Events after it
+    varint::write_u16(payload.len() as u16, out_bytes); // write payload size
+    out_bytes.extend_from_slice(payload); // write payload
+}
+
+/// Reads an Event's frame, as written by `write_event_frame()`, returning its
+/// NaiaId & ordering, along with the bytes of its payload. A payload cut short
+/// is returned as far as it goes, while a frame cut short before its payload
+/// is an Error, as there's no telling where the next frame starts
+pub fn read_event_frame<'s>(
+    reader: &mut PacketReader<'s>,
+) -> Result<(u16, Option<(ChannelIndex, SequenceNumber)>, &'s [u8]), VarintError> {
+    let flagged_naia_id = varint::read_from(reader, varint::read_u16)?;
+    let naia_id = flagged_naia_id >> 1;
+    let mut ordering: Option<(ChannelIndex, SequenceNumber)> = None;
+    if flagged_naia_id & ORDERED_EVENT_FLAG != 0 {
+        let ordering_bytes = read_bytes(reader, 3);
+        if ordering_bytes.len() < 3 {
+            return Err(VarintError::Truncated);
+        }
+        let channel_index = ordering_bytes[0];
+        let sequence = byte_order::get_u16(&ordering_bytes[1..3]);
+        ordering = Some((channel_index, sequence));
+    }
+    let size = varint::read_from(reader, varint::read_u16)? as usize;
+    return Ok((naia_id, ordering, read_bytes(reader, size)));
+}
+
+// reads the given number of bytes, or as many as are left in the packet
+fn read_bytes<'s>(reader: &mut PacketReader<'s>, size: usize) -> &'s [u8] {
+    let buffer = reader.get_buffer();
+    let start = (reader.get_cursor().position() as usize).min(buffer.len());
+    let end = (start + size).min(buffer.len());
+    reader.get_cursor().set_position(end as u64);
+    return &buffer[start..end];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_event_frame, write_event_frame};
+    use crate::{varint::VarintError, PacketReader};
+
+    #[test]
+    fn frames_read_back_as_written() {
+        let mut bytes = Vec::new();
+        write_event_frame(3, None, &[1, 2], &mut bytes);
+        write_event_frame(200, Some((1, 0xfffe)), &[], &mut bytes);
+        let mut reader = PacketReader::new(&bytes);
+        assert_eq!(read_event_frame(&mut reader), Ok((3, None, &[1u8, 2][..])));
+        assert_eq!(
+            read_event_frame(&mut reader),
+            Ok((200, Some((1, 0xfffe)), &[][..]))
+        );
+        assert!(!reader.has_more());
+    }
+
+    #[test]
+    fn ordering_cut_short_is_an_error() {
+        let mut bytes = Vec::new();
+        write_event_frame(3, Some((0, 7)), &[1], &mut bytes);
+        let mut reader = PacketReader::new(&bytes[..2]);
+        assert_eq!(read_event_frame(&mut reader), Err(VarintError::Truncated));
+    }
+}
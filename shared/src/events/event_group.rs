@@ -0,0 +1,28 @@
+use super::{
+    event::{Event, EventClone},
+    event_type::EventType,
+};
+
+/// A group of Events to be sent together. Every Event in the group is written
+/// into the same outgoing packet, so that if the packet is lost, all of them
+/// are, and they are received by the remote host one after another
+pub struct EventGroup<T: EventType> {
+    events: Vec<Box<dyn Event<T>>>,
+}
+
+impl<T: EventType> EventGroup<T> {
+    /// Create a new, empty EventGroup
+    pub fn new() -> Self {
+        EventGroup { events: Vec::new() }
+    }
+
+    /// Adds an Event to the group, after every Event already in it
+    pub fn queue(&mut self, event: &impl Event<T>) {
+        self.events.push(EventClone::clone_box(event));
+    }
+
+    /// Gets the Events in the group, in the order they were added
+    pub fn get_events(&self) -> &[Box<dyn Event<T>>] {
+        return &self.events;
+    }
+}
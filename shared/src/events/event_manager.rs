@@ -1,53 +1,169 @@
 use std::{
+    any::{Any, TypeId},
     collections::{HashMap, VecDeque},
+    convert::TryFrom,
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
     rc::Rc,
+    time::Duration,
     vec::Vec,
 };
 
+use log::warn;
+
 use crate::{
-    actors::actor_type::ActorType,
+    actors::{actor_type::ActorType, local_actor_key::LocalActorKey},
     events::{
         event::{Event, EventClone},
+        event_frame::read_event_frame,
+        event_stamp::EventStamp,
         event_type::EventType,
+        ordered_channel::{ChannelIndex, OrderedChannel},
+        outgoing_event::OutgoingEvent,
+        raw_event::{OutgoingRawEvent, RawEvent},
+        skipped_frame::SkippedFrame,
     },
     manifest::Manifest,
-    PacketReader,
+    message_tracing::{MessageTracer, TraceId, TraceStage},
+    schema_versions::SchemaVersions,
+    utils::duration_between,
+    varint, Instant, NaiaError, PacketReader,
 };
 
 /// Handles incoming/outgoing events, tracks the delivery status of Events so
 /// that guaranteed Events can be re-transmitted to the remote host
 #[derive(Debug)]
 pub struct EventManager<T: EventType> {
-    queued_outgoing_events: VecDeque<Rc<Box<dyn Event<T>>>>,
-    queued_incoming_events: VecDeque<T>,
-    sent_events: HashMap<u16, Vec<Rc<Box<dyn Event<T>>>>>,
+    queued_outgoing_events: VecDeque<OutgoingEvent<T>>,
+    // along with their EventStamp, if their type is timestamped, and the key of
+    // the Actor they're about, if their type is actor-scoped
+    queued_incoming_events: VecDeque<(T, Option<EventStamp>, Option<LocalActorKey>)>,
+    queued_incoming_raw_events: VecDeque<RawEvent>,
+    skipped_frames: VecDeque<SkippedFrame>,
+    catch_decode_panics: bool,
+    sent_events: HashMap<u16, Vec<OutgoingEvent<T>>>,
+    // along with their trace ids, if the remote host wrote them
+    ordered_channels: Vec<OrderedChannel<(IncomingEvent<T>, Option<TraceId>)>>,
+    // the number & total size of Events which must be re-transmitted until
+    // delivered, and have not been yet. The unordered channel comes first,
+    // followed by each ordered channel
+    reliable_usage: Vec<(usize, usize)>,
+    tracer: Option<MessageTracer>,
+    trace_ids_in_frames: bool,
+    // the trace ids of the Events popped to be written into the next packet,
+    // which are traced as packed once it is sent
+    packed_trace_ids: Vec<TraceId>,
+    // the number of Events queued so far, which is the queue index of the next
+    queued_count: u64,
+    // the number & total size of the Events re-queued after their packets
+    // were dropped, since last taken
+    retransmitted: (usize, usize),
 }
 
 impl<T: EventType> EventManager<T> {
-    /// Creates a new EventManager
-    pub fn new() -> Self {
+    /// Creates a new EventManager, with the given number of ordered channels
+    pub fn new(ordered_channel_count: u8) -> Self {
+        let mut ordered_channels = Vec::with_capacity(ordered_channel_count as usize);
+        for _ in 0..ordered_channel_count {
+            ordered_channels.push(OrderedChannel::new());
+        }
+
         EventManager {
             queued_outgoing_events: VecDeque::new(),
             queued_incoming_events: VecDeque::new(),
+            queued_incoming_raw_events: VecDeque::new(),
+            skipped_frames: VecDeque::new(),
+            catch_decode_panics: false,
             sent_events: HashMap::new(),
+            ordered_channels,
+            reliable_usage: vec![(0, 0); ordered_channel_count as usize + 1],
+            tracer: None,
+            trace_ids_in_frames: false,
+            packed_trace_ids: Vec::new(),
+            queued_count: 0,
+            retransmitted: (0, 0),
         }
     }
 
-    /// Occurs when a packet has been notified as delivered. Stops tracking the
-    /// status of Events in that packet.
-    pub fn notify_packet_delivered(&mut self, packet_index: u16) {
-        self.sent_events.remove(&packet_index);
+    /// Starts tracing the Events sent to & received from the remote host at
+    /// the given address, giving each Event queued from now on a trace id
+    pub fn start_tracing(&mut self, peer: SocketAddr) {
+        self.tracer = Some(MessageTracer::new(peer));
+    }
+
+    /// Sets whether a panic while reading a received Event is caught, & the
+    /// Event skipped over as failing to decode
+    pub fn set_catch_decode_panics(&mut self, enabled: bool) {
+        self.catch_decode_panics = enabled;
+    }
+
+    /// Sets whether a trace id is read ahead of each incoming Event frame,
+    /// which should only be enabled once both hosts have agreed to message
+    /// tracing during the handshake
+    pub fn set_trace_ids_in_frames(&mut self, enabled: bool) {
+        self.trace_ids_in_frames = enabled;
+    }
+
+    /// Traces the Events popped since the last packet was sent as packed into
+    /// the packet with the given index, which has just been sent
+    pub fn trace_packed(&mut self, packet_index: u16) {
+        if let Some(tracer) = &self.tracer {
+            for trace_id in self.packed_trace_ids.drain(..) {
+                tracer.trace(trace_id, TraceStage::Packed, Some(packet_index));
+            }
+        }
+    }
+
+    fn trace(&self, trace_id: Option<TraceId>, stage: TraceStage, packet_index: Option<u16>) {
+        if let (Some(tracer), Some(trace_id)) = (&self.tracer, trace_id) {
+            tracer.trace(trace_id, stage, packet_index);
+        }
+    }
+
+    /// Occurs when a packet sent to the given address has been notified as
+    /// delivered. Stops tracking the status of Events in that packet.
+    pub fn notify_packet_delivered(&mut self, address: &SocketAddr, packet_index: u16) {
+        if let Some(delivered_events_list) = self.sent_events.remove(&packet_index) {
+            for delivered_event in delivered_events_list.iter() {
+                if let Some((channel_index, sequence)) = delivered_event.ordering {
+                    let in_flight =
+                        self.ordered_channels[channel_index as usize].notify_delivered(sequence);
+                    crate::strict_assert!(
+                        in_flight,
+                        "Event {} on channel {} to {} was acknowledged while not in flight, in \
+                         packet {}",
+                        sequence,
+                        channel_index,
+                        address,
+                        packet_index
+                    );
+                }
+                let usage = &mut self.reliable_usage[Self::get_usage_index(delivered_event)];
+                usage.0 = usage.0.saturating_sub(1);
+                usage.1 = usage.1.saturating_sub(delivered_event.size);
+                self.trace(
+                    delivered_event.trace_id,
+                    TraceStage::Acked,
+                    Some(packet_index),
+                );
+            }
+        }
     }
 
     /// Occurs when a packet has been notified as having been dropped. Queues up
     /// any guaranteed Events that were lost in the packet for retransmission.
     pub fn notify_packet_dropped(&mut self, packet_index: u16) {
-        if let Some(dropped_events_list) = self.sent_events.get(&packet_index) {
+        if let Some(dropped_events_list) = self.sent_events.remove(&packet_index) {
             for dropped_event in dropped_events_list.into_iter() {
-                self.queued_outgoing_events.push_back(dropped_event.clone());
+                self.trace(
+                    dropped_event.trace_id,
+                    TraceStage::Retransmitted,
+                    Some(packet_index),
+                );
+                self.retransmitted.0 += 1 + dropped_event.grouped.len();
+                self.retransmitted.1 += dropped_event.size;
+                self.queued_outgoing_events.push_back(dropped_event);
             }
-
-            self.sent_events.remove(&packet_index);
         }
     }
 
@@ -57,23 +173,77 @@ impl<T: EventType> EventManager<T> {
         return self.queued_outgoing_events.len() != 0;
     }
 
-    /// Gets the next queued Event to be transmitted
-    pub fn pop_outgoing_event(&mut self, packet_index: u16) -> Option<Rc<Box<dyn Event<T>>>> {
-        match self.queued_outgoing_events.pop_front() {
-            Some(event) => {
+    /// Returns the number & total size in bytes of the Events re-queued to be
+    /// re-transmitted since this was last called, counting each Event of a
+    /// group
+    pub fn take_retransmitted(&mut self) -> (usize, usize) {
+        return std::mem::take(&mut self.retransmitted);
+    }
+
+    /// Returns the number of Events waiting to be transmitted
+    pub fn get_queued_event_count(&self) -> usize {
+        return self.queued_outgoing_events.len();
+    }
+
+    /// Returns the number of Events either waiting to be transmitted, or
+    /// waiting to be acknowledged by the remote host
+    pub fn get_outgoing_event_count(&self) -> usize {
+        let in_flight: usize = self.sent_events.values().map(|list| list.len()).sum();
+        return self.queued_outgoing_events.len() + in_flight;
+    }
+
+    /// Gets the next queued Event to be transmitted. Events on an ordered
+    /// channel which has too many Events in flight are skipped until earlier
+    /// Events have been delivered
+    pub fn pop_outgoing_event(&mut self, packet_index: u16) -> Option<OutgoingEvent<T>> {
+        return self.pop_outgoing_event_from(packet_index, 0);
+    }
+
+    /// Gets the next queued Event to be transmitted, like
+    /// `pop_outgoing_event()`, from those queued at or after the given
+    /// position in the queue only
+    pub fn pop_outgoing_event_from(
+        &mut self,
+        packet_index: u16,
+        queue_position: usize,
+    ) -> Option<OutgoingEvent<T>> {
+        let mut next_index = None;
+        for (index, outgoing_event) in self
+            .queued_outgoing_events
+            .iter()
+            .enumerate()
+            .skip(queue_position)
+        {
+            let can_send = match outgoing_event.ordering {
+                Some((channel_index, sequence)) => {
+                    self.ordered_channels[channel_index as usize].can_send(sequence)
+                }
+                None => true,
+            };
+            if can_send {
+                next_index = Some(index);
+                break;
+            }
+        }
+
+        match next_index.and_then(|index| self.queued_outgoing_events.remove(index)) {
+            Some(outgoing_event) => {
                 //place in transmission record if this is a gauranteed event
-                if Event::is_guaranteed(event.as_ref().as_ref()) {
+                if Self::is_tracked(&outgoing_event) {
                     if !self.sent_events.contains_key(&packet_index) {
-                        let sent_events_list: Vec<Rc<Box<dyn Event<T>>>> = Vec::new();
+                        let sent_events_list: Vec<OutgoingEvent<T>> = Vec::new();
                         self.sent_events.insert(packet_index, sent_events_list);
                     }
 
                     if let Some(sent_events_list) = self.sent_events.get_mut(&packet_index) {
-                        sent_events_list.push(event.clone());
+                        sent_events_list.push(outgoing_event.clone());
                     }
                 }
+                if let Some(trace_id) = outgoing_event.trace_id {
+                    self.packed_trace_ids.push(trace_id);
+                }
 
-                Some(event)
+                Some(outgoing_event)
             }
             None => None,
         }
@@ -81,10 +251,22 @@ impl<T: EventType> EventManager<T> {
 
     /// If  the last popped Event from the queue somehow wasn't able to be
     /// written into a packet, put the Event back into the front of the queue
-    pub fn unpop_outgoing_event(&mut self, packet_index: u16, event: &Rc<Box<dyn Event<T>>>) {
-        let cloned_event = event.clone();
+    pub fn unpop_outgoing_event(&mut self, packet_index: u16, outgoing_event: &OutgoingEvent<T>) {
+        self.unpop_outgoing_event_to(packet_index, outgoing_event, 0);
+    }
+
+    /// If the last Event popped with `pop_outgoing_event_from()` wasn't able
+    /// to be written into a packet, put it back into the queue at the position
+    /// it was popped from
+    pub fn unpop_outgoing_event_to(
+        &mut self,
+        packet_index: u16,
+        outgoing_event: &OutgoingEvent<T>,
+        queue_position: usize,
+    ) {
+        let cloned_event = outgoing_event.clone();
 
-        if Event::is_guaranteed(event.as_ref().as_ref()) {
+        if Self::is_tracked(outgoing_event) {
             if let Some(sent_events_list) = self.sent_events.get_mut(&packet_index) {
                 sent_events_list.pop();
                 if sent_events_list.len() == 0 {
@@ -92,14 +274,258 @@ impl<T: EventType> EventManager<T> {
                 }
             }
         }
+        if outgoing_event.trace_id.is_some() {
+            self.packed_trace_ids.pop();
+        }
+
+        let queue_position = queue_position.min(self.queued_outgoing_events.len());
+        self.queued_outgoing_events
+            .insert(queue_position, cloned_event);
+    }
 
-        self.queued_outgoing_events.push_front(cloned_event);
+    // Events on an ordered channel must always be re-transmitted if lost, or
+    // the channel would be blocked forever
+    // the Events in a group are all re-transmitted if any one of them must be
+    fn is_tracked(outgoing_event: &OutgoingEvent<T>) -> bool {
+        return outgoing_event.ordering.is_some()
+            || Event::is_guaranteed(outgoing_event.event.as_ref().as_ref())
+            || outgoing_event
+                .grouped
+                .iter()
+                .any(|event| Event::is_guaranteed(event.as_ref().as_ref()));
+    }
+
+    fn get_usage_index(outgoing_event: &OutgoingEvent<T>) -> usize {
+        match outgoing_event.ordering {
+            Some((channel_index, _)) => return channel_index as usize + 1,
+            None => return 0,
+        }
+    }
+
+    /// Drops queued Events which are not re-transmitted if lost, and have waited
+    /// longer than the expiry set for their type in the Manifest, given the
+    /// current time. Returns the number of Events dropped
+    pub fn expire_outgoing_events<U: ActorType>(
+        &mut self,
+        manifest: &Manifest<T, U>,
+        now: &Instant,
+    ) -> usize {
+        let queued_count = self.queued_outgoing_events.len();
+        self.queued_outgoing_events.retain(|outgoing_event| {
+            if Self::is_tracked(outgoing_event) {
+                return true;
+            }
+            let type_id = Event::get_type_id(outgoing_event.event.as_ref().as_ref());
+            match manifest.get_event_expiry(&type_id) {
+                Some(expiry) => return duration_between(&outgoing_event.queued_at, now) <= expiry,
+                None => return true,
+            }
+        });
+        return queued_count - self.queued_outgoing_events.len();
+    }
+
+    /// Gets the number of Events, and their total size in bytes, which must be
+    /// re-transmitted until delivered and have not been yet, on either the
+    /// given ordered channel, or the unordered channel if None
+    pub fn get_reliable_usage(&self, channel: Option<ChannelIndex>) -> (usize, usize) {
+        let index = match channel {
+            Some(channel_index) => channel_index as usize + 1,
+            None => 0,
+        };
+        return self.reliable_usage.get(index).copied().unwrap_or((0, 0));
+    }
+
+    /// Gets the number of bytes allocated for the buffers of the ordered
+    /// channels, which are only allocated once a channel is first used
+    pub fn get_channel_allocated_bytes(&self) -> usize {
+        return self
+            .ordered_channels
+            .iter()
+            .map(|channel| channel.allocated_bytes())
+            .sum();
+    }
+
+    /// Gets the time at which the oldest Event which must be re-transmitted
+    /// until delivered, and has not been yet, was queued
+    pub fn get_oldest_reliable_queued_at(&self) -> Option<Instant> {
+        let mut oldest: Option<&Instant> = None;
+        let sent_events = self.sent_events.values().flat_map(|list| list.iter());
+        for outgoing_event in self.queued_outgoing_events.iter().chain(sent_events) {
+            if !Self::is_tracked(outgoing_event) {
+                continue;
+            }
+            let is_older = match oldest {
+                Some(oldest_queued_at) => outgoing_event.queued_at < *oldest_queued_at,
+                None => true,
+            };
+            if is_older {
+                oldest = Some(&outgoing_event.queued_at);
+            }
+        }
+        return oldest.cloned();
+    }
+
+    /// Gets the tail of the Events queued so far, which a barrier placed now
+    /// is set at. Every Event queued before it has a lower queue index, and
+    /// every Event queued after it a higher one
+    pub fn get_reliable_tail(&self) -> u64 {
+        return self.queued_count;
+    }
+
+    /// Returns whether every Event which must be re-transmitted until
+    /// delivered, and was queued before the given tail, has been delivered.
+    /// Events queued after the tail aren't waited for
+    pub fn is_delivered_up_to(&self, tail: u64) -> bool {
+        let sent_events = self.sent_events.values().flat_map(|list| list.iter());
+        return !self
+            .queued_outgoing_events
+            .iter()
+            .chain(sent_events)
+            .any(|outgoing_event| {
+                return outgoing_event.queue_index < tail && Self::is_tracked(outgoing_event);
+            });
+    }
+
+    /// Queues an Event to be transmitted to the remote host, given the current
+    /// time. If a channel is given, the Event will be received in order with
+    /// every other Event sent on that channel. Returns an Error if the channel
+    /// hasn't been configured
+    pub fn queue_outgoing_event(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+        now: &Instant,
+    ) -> Result<(), NaiaError> {
+        return self.queue_outgoing(event, channel, None, now);
     }
 
-    /// Queues an Event to be transmitted to the remote host
-    pub fn queue_outgoing_event(&mut self, event: &impl Event<T>) {
+    /// Queues an Event about the Actor with the given LocalActorKey to be
+    /// transmitted to the remote host, as `queue_outgoing_event()` does. The
+    /// key is written along with it if its type is set to be actor-scoped
+    pub fn queue_outgoing_actor_event(
+        &mut self,
+        event: &dyn Event<T>,
+        actor: LocalActorKey,
+        channel: Option<ChannelIndex>,
+        now: &Instant,
+    ) -> Result<(), NaiaError> {
+        return self.queue_outgoing(event, channel, Some(actor), now);
+    }
+
+    /// Returns an Error if the given channel hasn't been configured, so that
+    /// an Event can't be sent on it
+    pub fn check_channel(&self, channel: Option<ChannelIndex>) -> Result<(), NaiaError> {
+        if let Some(channel_index) = channel {
+            if channel_index as usize >= self.ordered_channels.len() {
+                return Err(NaiaError::NoSuchChannel {
+                    channel_index,
+                    channel_count: self.ordered_channels.len(),
+                });
+            }
+        }
+        return Ok(());
+    }
+
+    fn queue_outgoing(
+        &mut self,
+        event: &dyn Event<T>,
+        channel: Option<ChannelIndex>,
+        actor: Option<LocalActorKey>,
+        now: &Instant,
+    ) -> Result<(), NaiaError> {
+        self.check_channel(channel)?;
+        let ordering = channel.map(|channel_index| {
+            let ordered_channel = &mut self.ordered_channels[channel_index as usize];
+            return (channel_index, ordered_channel.next_send_sequence());
+        });
         let clone = Rc::new(EventClone::clone_box(event));
-        self.queued_outgoing_events.push_back(clone);
+        let mut outgoing_event = OutgoingEvent {
+            event: clone,
+            ordering,
+            size: 0,
+            queued_at: now.clone(),
+            grouped: Vec::new(),
+            trace_id: self.next_trace_id(),
+            actor,
+            queue_index: self.next_queue_index(),
+        };
+        if Self::is_tracked(&outgoing_event) {
+            let mut event_bytes = Vec::new();
+            event.write(&mut event_bytes);
+            outgoing_event.size = event_bytes.len();
+
+            let usage = &mut self.reliable_usage[Self::get_usage_index(&outgoing_event)];
+            usage.0 += 1;
+            usage.1 += outgoing_event.size;
+        }
+        self.trace(outgoing_event.trace_id, TraceStage::Queued, None);
+        self.queued_outgoing_events.push_back(outgoing_event);
+        return Ok(());
+    }
+
+    fn next_trace_id(&self) -> Option<TraceId> {
+        return self.tracer.as_ref().map(|tracer| tracer.next_trace_id());
+    }
+
+    fn next_queue_index(&mut self) -> u64 {
+        let queue_index = self.queued_count;
+        self.queued_count += 1;
+        return queue_index;
+    }
+
+    /// Queues a group of Events to be transmitted to the remote host together,
+    /// in the same packet, given the current time. They are received one after
+    /// another, in the order given, but not in order with any other Events
+    pub fn queue_outgoing_event_group(&mut self, events: &[Box<dyn Event<T>>], now: &Instant) {
+        let (first, rest) = match events.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        let mut outgoing_event = OutgoingEvent {
+            event: Rc::new(EventClone::clone_box(first.as_ref())),
+            ordering: None,
+            size: 0,
+            queued_at: now.clone(),
+            grouped: rest
+                .iter()
+                .map(|event| Rc::new(EventClone::clone_box(event.as_ref())))
+                .collect(),
+            trace_id: self.next_trace_id(),
+            actor: None,
+            queue_index: self.next_queue_index(),
+        };
+        if Self::is_tracked(&outgoing_event) {
+            let mut event_bytes = Vec::new();
+            for event in events.iter() {
+                event.write(&mut event_bytes);
+            }
+            outgoing_event.size = event_bytes.len();
+
+            let usage = &mut self.reliable_usage[Self::get_usage_index(&outgoing_event)];
+            usage.0 += 1;
+            usage.1 += outgoing_event.size;
+        }
+        self.trace(outgoing_event.trace_id, TraceStage::Queued, None);
+        self.queued_outgoing_events.push_back(outgoing_event);
+    }
+
+    /// Queues the bytes of a RawEvent of the Event type with the given TypeId
+    /// to be transmitted to the remote host, exactly as an Event of that type
+    /// would be, given the current time. Returns an Error if the channel
+    /// hasn't been configured
+    pub fn queue_outgoing_raw_event(
+        &mut self,
+        raw_event: &RawEvent,
+        type_id: TypeId,
+        guaranteed: bool,
+        channel: Option<ChannelIndex>,
+        now: &Instant,
+    ) -> Result<(), NaiaError>
+    where
+        T: 'static,
+    {
+        let event = OutgoingRawEvent::<T>::new(type_id, guaranteed, raw_event.bytes.clone());
+        return self.queue_outgoing_event(&event, channel, now);
     }
 
     /// Returns whether any Events have been received that must be handed to the
@@ -108,28 +534,233 @@ impl<T: EventType> EventManager<T> {
         return self.queued_incoming_events.len() != 0;
     }
 
+    /// Get the most recently received RawEvent
+    pub fn pop_incoming_raw_event(&mut self) -> Option<RawEvent> {
+        return self.queued_incoming_raw_events.pop_front();
+    }
+
     /// Get the most recently received Event
     pub fn pop_incoming_event(&mut self) -> Option<T> {
+        return self.pop_incoming_stamped_event().map(|(event, _, _)| event);
+    }
+
+    /// Get the most recently received Event, along with its EventStamp if its
+    /// type is set to be timestamped in the Manifest, and the LocalActorKey of
+    /// the Actor it's about if its type is set to be actor-scoped
+    pub fn pop_incoming_stamped_event(
+        &mut self,
+    ) -> Option<(T, Option<EventStamp>, Option<LocalActorKey>)> {
         return self.queued_incoming_events.pop_front();
     }
 
+    /// Get the number of received Events waiting to be handed to the
+    /// application
+    pub fn get_incoming_event_count(&self) -> usize {
+        return self.queued_incoming_events.len();
+    }
+
+    /// Removes & returns the received Events from the given position in the
+    /// queue onwards, along with their EventStamps & the Actors they're about
+    pub fn take_incoming_events_from(
+        &mut self,
+        start: usize,
+    ) -> VecDeque<(T, Option<EventStamp>, Option<LocalActorKey>)> {
+        if start >= self.queued_incoming_events.len() {
+            return VecDeque::new();
+        }
+        return self.queued_incoming_events.split_off(start);
+    }
+
+    /// Adds a received Event to the back of the queue to be handed to the
+    /// application, along with its EventStamp & the Actor it's about
+    pub fn push_incoming_event(
+        &mut self,
+        event: T,
+        stamp: Option<EventStamp>,
+        actor: Option<LocalActorKey>,
+    ) {
+        self.queued_incoming_events.push_back((event, stamp, actor));
+    }
+
     /// Given incoming packet data, read transmitted Events and store them to be
-    /// returned to the application
+    /// returned to the application. Events of types set to be received raw in
+    /// the Manifest are stored as RawEvents. Events which can't be read are
+    /// skipped over, and recorded as SkippedFrames. Events of timestamped types
+    /// are stored with an EventStamp, given the remote host's tick when it sent
+    /// the packet, and the time the packet arrived at, and Events of
+    /// actor-scoped types with the key of the Actor they're about, if they
+    /// were sent about one. Events are read as the schema versions of their
+    /// types agreed on for the connection. Once both hosts have agreed to
+    /// message tracing, each Event frame is read along with the trace id
+    /// written ahead of it
     pub fn process_data<U: ActorType>(
         &mut self,
         reader: &mut PacketReader,
         manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
+        packet_tick: u16,
+        received_at: &Instant,
     ) {
         let event_count = reader.read_u8();
         for _x in 0..event_count {
-            let naia_id: u16 = reader.read_u16();
+            let frame = if self.trace_ids_in_frames {
+                varint::read_from(reader, varint::read_u32).and_then(|trace_id| {
+                    read_event_frame(reader).map(|frame| (Some(trace_id), frame))
+                })
+            } else {
+                read_event_frame(reader).map(|frame| (None, frame))
+            };
+            let (trace_id, (naia_id, ordering, bytes)) = match frame {
+                Ok(frame) => frame,
+                Err(_) => {
+                    // without the size of the Event, there's no telling where
+                    // the next one starts, so the rest of the packet is dropped
+                    let end = reader.get_buffer().len();
+                    reader.get_cursor().set_position(end as u64);
+                    self.skipped_frames.push_back(SkippedFrame::Malformed);
+                    return;
+                }
+            };
+            self.trace(trace_id, TraceStage::Received, None);
+
+            let new_event = match Self::read_event(
+                naia_id,
+                bytes,
+                manifest,
+                schema_versions,
+                packet_tick,
+                received_at,
+                self.catch_decode_panics,
+            ) {
+                Ok(new_event) => new_event,
+                Err(skipped_frame) => {
+                    self.skipped_frames.push_back(skipped_frame);
+                    IncomingEvent::Skipped
+                }
+            };
 
-            match manifest.create_event(naia_id, reader) {
-                Some(new_event) => {
-                    self.queued_incoming_events.push_back(new_event);
+            match ordering {
+                Some((channel_index, sequence)) => {
+                    // a skipped Event still takes its place in the channel, so
+                    // the Events after it aren't held back forever
+                    if let Some(ordered_channel) =
+                        self.ordered_channels.get_mut(channel_index as usize)
+                    {
+                        let mut output = VecDeque::new();
+                        ordered_channel.receive(sequence, (new_event, trace_id), &mut output);
+                        for (event, trace_id) in output {
+                            self.push_received_event(event, trace_id);
+                        }
+                    }
+                }
+                None => {
+                    self.push_received_event(new_event, trace_id);
                 }
-                _ => {}
             }
         }
     }
+
+    /// Get the next Event which was skipped over while reading a packet
+    pub fn pop_skipped_frame(&mut self) -> Option<SkippedFrame> {
+        return self.skipped_frames.pop_front();
+    }
+
+    // reads an Event from the bytes it was written as, which only needs
+    // reading into the EventType if it isn't received raw. The time a
+    // timestamped Event waited to be sent is read from ahead of its payload,
+    // and the key of the Actor an actor-scoped Event is about ahead of that.
+    // Reading a payload shorter than its type expects panics, which is caught
+    // if set to be
+    fn read_event<U: ActorType>(
+        naia_id: u16,
+        mut bytes: &[u8],
+        manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
+        packet_tick: u16,
+        received_at: &Instant,
+        catch_decode_panics: bool,
+    ) -> Result<IncomingEvent<T>, SkippedFrame> {
+        let mut actor = None;
+        if manifest.is_event_actor_scoped(naia_id) {
+            let (actor_id, length) =
+                varint::read_u32(bytes).map_err(|_| SkippedFrame::DecodeFailed(naia_id))?;
+            bytes = &bytes[length..];
+            if actor_id > 0 {
+                let actor_key = LocalActorKey::try_from(actor_id - 1)
+                    .map_err(|_| SkippedFrame::DecodeFailed(naia_id))?;
+                actor = Some(actor_key);
+            }
+        }
+        let mut stamp = None;
+        if manifest.is_event_timestamped(naia_id) {
+            let (delay_millis, length) =
+                varint::read_u16(bytes).map_err(|_| SkippedFrame::DecodeFailed(naia_id))?;
+            bytes = &bytes[length..];
+            stamp = Some(EventStamp {
+                queue_delay: Duration::from_millis(u64::from(delay_millis)),
+                sent_tick: packet_tick,
+                received_at: received_at.clone(),
+            });
+        }
+        if manifest.is_event_received_raw(naia_id) {
+            return Ok(IncomingEvent::Raw(RawEvent {
+                naia_id,
+                bytes: bytes.into(),
+            }));
+        }
+        let mut event_reader = PacketReader::new(bytes);
+        let event = if catch_decode_panics {
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                manifest.create_event(naia_id, &mut event_reader, schema_versions)
+            }))
+            .map_err(|payload| {
+                warn!(
+                    "failed to decode Event of type {}: {}",
+                    naia_id,
+                    get_panic_message(&*payload)
+                );
+                return SkippedFrame::DecodeFailed(naia_id);
+            })?
+        } else {
+            manifest.create_event(naia_id, &mut event_reader, schema_versions)
+        }
+        .ok_or(SkippedFrame::UnknownType(naia_id))?;
+        if event_reader.has_more() {
+            return Err(SkippedFrame::DecodeFailed(naia_id));
+        }
+        return Ok(IncomingEvent::Typed(event, stamp, actor));
+    }
+
+    fn push_received_event(&mut self, event: IncomingEvent<T>, trace_id: Option<TraceId>) {
+        if !matches!(event, IncomingEvent::Skipped) {
+            self.trace(trace_id, TraceStage::Delivered, None);
+        }
+        match event {
+            IncomingEvent::Typed(event, stamp, actor) => {
+                self.queued_incoming_events.push_back((event, stamp, actor))
+            }
+            IncomingEvent::Raw(raw_event) => self.queued_incoming_raw_events.push_back(raw_event),
+            IncomingEvent::Skipped => {}
+        }
+    }
+}
+
+// A received Event, either read into the EventType or held as its bytes, or
+// the place of one which was skipped over
+#[derive(Clone, Debug)]
+enum IncomingEvent<T: EventType> {
+    Typed(T, Option<EventStamp>, Option<LocalActorKey>),
+    Raw(RawEvent),
+    Skipped,
+}
+
+// the message a panic was raised with, if it was raised with one
+fn get_panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message;
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message;
+    }
+    return "unknown panic";
 }
@@ -1,32 +1,86 @@
-use byteorder::{BigEndian, WriteBytesExt};
+use std::{any::TypeId, time::Duration};
+
+use byteorder::WriteBytesExt;
 
 use crate::{
-    actors::actor_type::ActorType,
-    events::{event::Event, event_type::EventType},
+    actors::{actor_type::ActorType, local_actor_key::LocalActorKey},
+    error::NaiaError,
+    events::{
+        event::Event, event_frame::write_event_frame, event_type::EventType,
+        ordered_channel::ChannelIndex, outgoing_event::OutgoingEvent, raw_event::RawEvent,
+    },
     manager_type::ManagerType,
     manifest::Manifest,
+    message_tracing::TraceId,
+    schema_versions::SchemaVersions,
+    sequence_buffer::SequenceNumber,
     standard_header::StandardHeader,
+    utils::duration_between,
+    varint, Instant,
 };
 
 /// The maximum of bytes that can be used for the payload of a given packet. (See #38 of http://ithare.com/64-network-dos-and-donts-for-game-engines-part-v-udp/)
 pub const MTU_SIZE: usize = 508 - StandardHeader::bytes_number();
 
+/// The largest payload an Event can have and still fit into an otherwise empty
+/// packet, alongside the Event's header, at its largest
+pub const MAX_EVENT_PAYLOAD_SIZE: usize = MTU_SIZE - 11 - TRACE_ID_MAX_SIZE;
+
+// The most bytes the trace id written ahead of each Event frame takes up, once
+// both hosts have agreed to message tracing. Room is left for it whenever this
+// host is built with message tracing, whether or not the remote host agrees
+#[cfg(feature = "message-tracing")]
+const TRACE_ID_MAX_SIZE: usize = 5;
+#[cfg(not(feature = "message-tracing"))]
+const TRACE_ID_MAX_SIZE: usize = 0;
+
+// The most bytes the time an Event waited to be sent takes up, written as a
+// u16 varint of milliseconds ahead of the payload of timestamped Events
+const QUEUE_DELAY_MAX_SIZE: usize = 3;
+
+// The most bytes the key of the Actor an Event is about takes up, written as a
+// u32 varint of the key plus one ahead of the payload of actor-scoped Events
+const ACTOR_KEY_MAX_SIZE: usize = 3;
+
 /// Handles writing of Event & Actor data into an outgoing packet
 pub struct EventPacketWriter {
     event_working_bytes: Vec<u8>,
     event_count: u8,
+    max_payload_size: usize,
+    writes_trace_ids: bool,
 }
 
 impl EventPacketWriter {
     /// Construct a new instance of `EventPacketWriter`, the given `buffer` will
     /// be used to read information from.
     pub fn new() -> EventPacketWriter {
+        return EventPacketWriter::with_max_payload_size(MTU_SIZE);
+    }
+
+    /// Construct a new instance of `EventPacketWriter`, which writes packet
+    /// payloads of up to the given number of bytes, such as a connection's
+    /// discovered MTU allows
+    pub fn with_max_payload_size(max_payload_size: usize) -> EventPacketWriter {
         EventPacketWriter {
             event_working_bytes: Vec::<u8>::new(),
             event_count: 0,
+            max_payload_size,
+            writes_trace_ids: false,
         }
     }
 
+    /// Sets whether the trace id of each Event is written ahead of its frame,
+    /// which should only be enabled once both hosts have agreed to message
+    /// tracing during the handshake
+    pub fn set_writes_trace_ids(&mut self, enabled: bool) {
+        self.writes_trace_ids = enabled;
+    }
+
+    /// Get the largest packet payload the writer will write, in bytes
+    pub fn max_payload_size(&self) -> usize {
+        return self.max_payload_size;
+    }
+
     /// Returns whether the writer has bytes to write into the outgoing packet
     pub fn has_bytes(&self) -> bool {
         return self.event_count != 0;
@@ -43,6 +97,94 @@ impl EventPacketWriter {
         }
     }
 
+    /// Checks that an Event can be sent, before it is queued. Returns an Error
+    /// if the Event's type has not been registered in the Manifest, or if the
+    /// Event is too large to ever fit into a packet
+    pub fn validate_event<T: EventType, U: ActorType>(
+        manifest: &Manifest<T, U>,
+        event: &dyn Event<T>,
+    ) -> Result<(), NaiaError> {
+        let naia_id = manifest.get_event_naia_id(&event.get_type_id())?;
+
+        let mut payload_bytes = Vec::<u8>::new();
+        event.write(&mut payload_bytes);
+        return Self::validate_size(manifest, naia_id, payload_bytes.len());
+    }
+
+    /// Checks that a RawEvent can be sent, before it is queued, returning the
+    /// TypeId of the Event. Returns an Error if no Event type has been
+    /// registered in the Manifest with the RawEvent's NaiaId, or if the
+    /// RawEvent is too large to ever fit into a packet
+    pub fn validate_raw_event<T: EventType, U: ActorType>(
+        manifest: &Manifest<T, U>,
+        raw_event: &RawEvent,
+    ) -> Result<TypeId, NaiaError> {
+        let type_id = manifest.get_event_type_id(raw_event.naia_id)?;
+        Self::validate_size(manifest, raw_event.naia_id, raw_event.bytes.len())?;
+        return Ok(type_id);
+    }
+
+    /// Checks that a group of Events can be sent, before it is queued. Returns
+    /// an Error if any Event's type has not been registered in the Manifest,
+    /// or if the Events together are too large to ever fit into a single
+    /// packet
+    pub fn validate_event_group<T: EventType, U: ActorType>(
+        manifest: &Manifest<T, U>,
+        events: &[Box<dyn Event<T>>],
+    ) -> Result<(), NaiaError> {
+        let mut size = 0;
+        // the manager header, and the byte the payload must stay under the
+        // packet size by
+        let mut overhead = 3;
+        for event in events.iter() {
+            let naia_id = manifest.get_event_naia_id(&event.get_type_id())?;
+            let mut payload_bytes = Vec::<u8>::new();
+            event.write(&mut payload_bytes);
+            size += payload_bytes.len();
+            let mut frame_size = payload_bytes.len();
+            if manifest.is_event_timestamped(naia_id) {
+                overhead += QUEUE_DELAY_MAX_SIZE;
+                frame_size += QUEUE_DELAY_MAX_SIZE;
+            }
+            // grouped Events are never about an Actor, so 0 is written
+            if manifest.is_event_actor_scoped(naia_id) {
+                overhead += 1;
+                frame_size += 1;
+            }
+            // grouped Events are never ordered, so only their NaiaId & size
+            // are written ahead of them
+            overhead += varint::encoded_len(u64::from(naia_id << 1))
+                + varint::encoded_len(frame_size as u64)
+                + TRACE_ID_MAX_SIZE;
+        }
+        let max = MTU_SIZE.saturating_sub(overhead);
+        if size > max {
+            return Err(NaiaError::MessageTooLarge { size, max });
+        }
+        return Ok(());
+    }
+
+    // timestamped Events have the time they waited to be sent written ahead of
+    // their payload, and actor-scoped Events the key of their Actor, leaving
+    // less room for it
+    fn validate_size<T: EventType, U: ActorType>(
+        manifest: &Manifest<T, U>,
+        naia_id: u16,
+        size: usize,
+    ) -> Result<(), NaiaError> {
+        let mut max = MAX_EVENT_PAYLOAD_SIZE;
+        if manifest.is_event_timestamped(naia_id) {
+            max -= QUEUE_DELAY_MAX_SIZE;
+        }
+        if manifest.is_event_actor_scoped(naia_id) {
+            max -= ACTOR_KEY_MAX_SIZE;
+        }
+        if size > max {
+            return Err(NaiaError::MessageTooLarge { size, max });
+        }
+        return Ok(());
+    }
+
     /// Get the number of bytes which is ready to be written into an outgoing
     /// packet
     pub fn bytes_number(&self) -> usize {
@@ -50,34 +192,1039 @@ impl EventPacketWriter {
     }
 
     /// Writes an Event into the Writer's internal buffer, which will eventually
-    /// be put into the outgoing packet
+    /// be put into the outgoing packet, along with every Event grouped with it.
+    /// Either the whole group is written, or none of it is. Events are written
+    /// as the schema versions of their types agreed on for the connection, and
+    /// timestamped Events with how long they were queued for as of the given
+    /// current time. An Event whose type has not been registered in the
+    /// Manifest is dropped
     pub fn write_event<T: EventType, U: ActorType>(
         &mut self,
         manifest: &Manifest<T, U>,
-        event: &Box<dyn Event<T>>,
+        schema_versions: &SchemaVersions,
+        outgoing_event: &OutgoingEvent<T>,
+        now: &Instant,
     ) -> bool {
-        //Write event payload
-        let mut event_payload_bytes = Vec::<u8>::new();
-        event.as_ref().write(&mut event_payload_bytes);
-
-        //Write event "header"
         let mut event_total_bytes = Vec::<u8>::new();
+        let mut event_count: usize = 0;
+        let queue_delay = duration_between(&outgoing_event.queued_at, now);
+        // every Event in a group is written with the group's trace id, and an
+        // Event without one with 0, which is never given
+        let mut trace_id = None;
+        if self.writes_trace_ids {
+            trace_id = Some(outgoing_event.trace_id.unwrap_or(0));
+        }
+
+        if Self::write_frame(
+            manifest,
+            schema_versions,
+            outgoing_event.event.as_ref().as_ref(),
+            outgoing_event.ordering,
+            queue_delay,
+            outgoing_event.actor,
+            trace_id,
+            &mut event_total_bytes,
+        ) {
+            event_count += 1;
+        }
+        for grouped_event in outgoing_event.grouped.iter() {
+            if Self::write_frame(
+                manifest,
+                schema_versions,
+                grouped_event.as_ref().as_ref(),
+                None,
+                queue_delay,
+                None,
+                trace_id,
+                &mut event_total_bytes,
+            ) {
+                event_count += 1;
+            }
+        }
+        if event_count == 0 {
+            return true;
+        }
 
-        let type_id = event.as_ref().get_type_id();
-        let naia_id = manifest.get_event_naia_id(&type_id); // get naia id
-        event_total_bytes.write_u16::<BigEndian>(naia_id).unwrap(); // write naia id
-        event_total_bytes.append(&mut event_payload_bytes); // write payload
+        // the number of Events is written as a single byte
+        if self.event_count as usize + event_count > u8::MAX as usize {
+            return false;
+        }
 
         let mut hypothetical_next_payload_size = self.bytes_number() + event_total_bytes.len();
         if self.event_count == 0 {
             hypothetical_next_payload_size += 2;
         }
-        if hypothetical_next_payload_size < MTU_SIZE {
-            self.event_count += 1;
+        if hypothetical_next_payload_size < self.max_payload_size {
+            self.event_count += event_count as u8;
             self.event_working_bytes.append(&mut event_total_bytes);
             return true;
         } else {
             return false;
         }
     }
+
+    // writes an Event's header & payload, returning false if the Event's type
+    // has not been registered in the Manifest. Events of timestamped types
+    // have the time they waited to be sent written ahead of their payload,
+    // counted in its size, and Events of actor-scoped types the key of the
+    // Actor they're about ahead of that, plus one, or 0 if they aren't about
+    // one. A trace id, if given, is written ahead of the frame
+    fn write_frame<T: EventType, U: ActorType>(
+        manifest: &Manifest<T, U>,
+        schema_versions: &SchemaVersions,
+        event: &dyn Event<T>,
+        ordering: Option<(ChannelIndex, SequenceNumber)>,
+        queue_delay: Duration,
+        actor: Option<LocalActorKey>,
+        trace_id: Option<TraceId>,
+        out_bytes: &mut Vec<u8>,
+    ) -> bool {
+        let naia_id = match manifest.get_event_naia_id(&event.get_type_id()) {
+            Ok(naia_id) => naia_id,
+            Err(_) => return false,
+        };
+
+        //Write event payload
+        let mut event_payload_bytes = Vec::<u8>::new();
+        if manifest.is_event_actor_scoped(naia_id) {
+            let actor_id = actor.map_or(0, |actor| u32::from(actor) + 1);
+            varint::write_u32(actor_id, &mut event_payload_bytes); // write actor key
+        }
+        if manifest.is_event_timestamped(naia_id) {
+            let delay_millis = queue_delay.as_millis().min(u16::MAX as u128) as u16;
+            varint::write_u16(delay_millis, &mut event_payload_bytes); // write queue delay
+        }
+        match schema_versions.get_event_version(naia_id) {
+            Some(version) => event.write_versioned(version, &mut event_payload_bytes),
+            None => event.write(&mut event_payload_bytes),
+        }
+
+        if let Some(trace_id) = trace_id {
+            varint::write_u32(trace_id, out_bytes); // write trace id
+        }
+        write_event_frame(naia_id, ordering, &event_payload_bytes, out_bytes);
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::TypeId,
+        net::SocketAddr,
+        panic::{self, AssertUnwindSafe},
+        rc::Rc,
+    };
+
+    use super::TRACE_ID_MAX_SIZE;
+    use crate::{utils::duration_between, wire_format::assert_golden};
+
+    // the remote host Events are sent to
+    fn remote_address() -> SocketAddr {
+        return "127.0.0.1:14191".parse().unwrap();
+    }
+    use crate::{
+        test_support::{
+            Blob, BlobBuilder, Block, BlockBuilder, Chat, ChatBuilder, NoActors, TestEvents,
+            TruncatedChat,
+        },
+        Clock, Event, EventGroup, EventManager, EventPacketWriter, LocalActorKey, ManagerType,
+        Manifest, ManifestError, ManualClock, NaiaError, OutgoingEvent, PacketReader, RawEvent,
+        SchemaVersions, SkippedFrame, SystemClock, MAX_EVENT_PAYLOAD_SIZE, MTU_SIZE,
+    };
+
+    #[test]
+    fn unregistered_event_is_rejected() {
+        let manifest = Manifest::<TestEvents, NoActors>::new();
+        match EventPacketWriter::validate_event(&manifest, &Blob { size: 4 }) {
+            Err(NaiaError::Manifest(ManifestError::UnregisteredEvent)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn oversized_event_is_rejected() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+
+        let largest = Blob {
+            size: MAX_EVENT_PAYLOAD_SIZE,
+        };
+        assert!(EventPacketWriter::validate_event(&manifest, &largest).is_ok());
+
+        let too_large = Blob {
+            size: MAX_EVENT_PAYLOAD_SIZE + 1,
+        };
+        match EventPacketWriter::validate_event(&manifest, &too_large) {
+            Err(NaiaError::MessageTooLarge { size, max }) => {
+                assert_eq!(size, MAX_EVENT_PAYLOAD_SIZE + 1);
+                assert_eq!(max, MAX_EVENT_PAYLOAD_SIZE);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn largest_event_fits_into_a_packet() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+
+        let event: Box<dyn Event<TestEvents>> = Box::new(Blob {
+            size: MAX_EVENT_PAYLOAD_SIZE,
+        });
+        let outgoing_event = OutgoingEvent {
+            event: Rc::new(event),
+            ordering: Some((0, 0)),
+            size: 0,
+            queued_at: SystemClock.now(),
+            grouped: Vec::new(),
+            trace_id: None,
+            actor: None,
+            queue_index: 0,
+        };
+        let mut writer = EventPacketWriter::new();
+        assert!(writer.write_event(
+            &manifest,
+            &SchemaVersions::new(),
+            &outgoing_event,
+            &SystemClock.now()
+        ));
+    }
+
+    fn chat_manifest(passthrough: bool) -> Manifest<TestEvents, NoActors> {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+        match passthrough {
+            true => manifest.register_passthrough_event(Box::new(ChatBuilder)),
+            false => manifest.register_event(Box::new(ChatBuilder)),
+        }
+        return manifest;
+    }
+
+    // writes the Events into a packet, and reads them back out on the other end
+    fn send(
+        sending_manifest: &Manifest<TestEvents, NoActors>,
+        receiving_manifest: &Manifest<TestEvents, NoActors>,
+        events: Vec<Box<dyn Event<TestEvents>>>,
+    ) -> EventManager<TestEvents> {
+        let mut writer = EventPacketWriter::new();
+        for event in events {
+            let outgoing_event = OutgoingEvent {
+                event: Rc::new(event),
+                ordering: None,
+                size: 0,
+                queued_at: SystemClock.now(),
+                grouped: Vec::new(),
+                trace_id: None,
+                actor: None,
+                queue_index: 0,
+            };
+            assert!(writer.write_event(
+                sending_manifest,
+                &SchemaVersions::new(),
+                &outgoing_event,
+                &SystemClock.now()
+            ));
+        }
+        let mut payload = Vec::new();
+        writer.get_bytes(&mut payload);
+
+        let mut receiver = EventManager::new(0);
+        let mut reader = PacketReader::new(&payload);
+        assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+        receiver.process_data(
+            &mut reader,
+            receiving_manifest,
+            &SchemaVersions::new(),
+            0,
+            &SystemClock.now(),
+        );
+        assert!(!reader.has_more());
+        return receiver;
+    }
+
+    #[test]
+    fn passthrough_event_is_received_raw() {
+        let sender_manifest = chat_manifest(true);
+        let mut relay_manifest = chat_manifest(true);
+        relay_manifest.set_event_receive_raw::<Chat>().unwrap();
+
+        let mut relay = send(
+            &sender_manifest,
+            &relay_manifest,
+            vec![
+                Box::new(Chat("hello".to_string())),
+                Box::new(Blob { size: 0 }),
+            ],
+        );
+        let raw_event = relay.pop_incoming_raw_event().unwrap();
+        assert_eq!(raw_event.naia_id, 1);
+        assert_eq!(&*raw_event.bytes, b"\x05hello");
+        assert!(relay.pop_incoming_raw_event().is_none());
+        assert_eq!(
+            relay.pop_incoming_event(),
+            Some(TestEvents::Blob(Blob { size: 0 }))
+        );
+    }
+
+    #[test]
+    fn raw_event_is_forwarded_and_read() {
+        let manifest = chat_manifest(true);
+        let raw_event = RawEvent {
+            naia_id: 1,
+            bytes: b"\x02hi".to_vec().into_boxed_slice(),
+        };
+        let type_id = EventPacketWriter::validate_raw_event(&manifest, &raw_event).unwrap();
+        assert_eq!(type_id, TypeId::of::<Chat>());
+
+        let mut sender = EventManager::<TestEvents>::new(0);
+        sender
+            .queue_outgoing_raw_event(&raw_event, type_id, true, None, &SystemClock.now())
+            .unwrap();
+        let outgoing_event = sender.pop_outgoing_event(0).unwrap();
+
+        let mut receiver = send(&manifest, &manifest, vec![outgoing_event.event.clone_box()]);
+        assert_eq!(
+            receiver.pop_incoming_event(),
+            Some(TestEvents::Chat(Chat("hi".to_string())))
+        );
+    }
+
+    #[test]
+    fn event_is_received_raw_without_passthrough() {
+        // every Event is written with its size, so its bytes are taken as they
+        // were written, whether it was registered as passthrough or not
+        let sender_manifest = chat_manifest(false);
+        let mut relay_manifest = chat_manifest(false);
+        relay_manifest.set_event_receive_raw::<Chat>().unwrap();
+
+        let mut relay = send(
+            &sender_manifest,
+            &relay_manifest,
+            vec![
+                Box::new(Chat("hey".to_string())),
+                Box::new(Chat("you".to_string())),
+            ],
+        );
+        assert_eq!(&*relay.pop_incoming_raw_event().unwrap().bytes, b"\x03hey");
+        assert_eq!(&*relay.pop_incoming_raw_event().unwrap().bytes, b"\x03you");
+    }
+
+    fn write_frames(
+        manifest: &Manifest<TestEvents, NoActors>,
+        events: Vec<(Box<dyn Event<TestEvents>>, Option<(u8, u16)>)>,
+    ) -> Vec<u8> {
+        let mut writer = EventPacketWriter::new();
+        for (event, ordering) in events {
+            let outgoing_event = OutgoingEvent {
+                event: Rc::new(event),
+                ordering,
+                size: 0,
+                queued_at: SystemClock.now(),
+                grouped: Vec::new(),
+                trace_id: None,
+                actor: None,
+                queue_index: 0,
+            };
+            assert!(writer.write_event(
+                manifest,
+                &SchemaVersions::new(),
+                &outgoing_event,
+                &SystemClock.now()
+            ));
+        }
+        let mut payload = Vec::new();
+        writer.get_bytes(&mut payload);
+        return payload;
+    }
+
+    #[test]
+    fn large_and_small_events_received_in_order_under_loss() {
+        for passthrough in [false, true].iter() {
+            let mut manifest = Manifest::<TestEvents, NoActors>::new();
+            match passthrough {
+                true => manifest.register_passthrough_event(Box::new(BlockBuilder)),
+                false => manifest.register_event(Box::new(BlockBuilder)),
+            }
+            let mut sender = EventManager::<TestEvents>::new(1);
+            let mut receiver = EventManager::<TestEvents>::new(1);
+
+            // a deterministic pseudo-random sequence
+            let mut seed: u32 = 0x9e37_79b9;
+            let mut random = move |range: u32| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                return seed % range;
+            };
+
+            // large Events fill a packet on their own, while small ones are
+            // packed together behind them
+            let event_count: u16 = 300;
+            for id in 0..event_count {
+                let size = match random(4) {
+                    0 => 400,
+                    _ => 4 + random(20) as u16,
+                };
+                sender
+                    .queue_outgoing_event(&Block { id, size }, Some(0), &SystemClock.now())
+                    .unwrap();
+            }
+
+            // packets in flight, with the tick they arrive or are found lost on
+            let mut link: Vec<(u16, u16, Option<Vec<u8>>)> = Vec::new();
+            let mut received_ids = Vec::new();
+            let mut packet_index: u16 = 0;
+            for tick in 0..2000u16 {
+                let mut writer = EventPacketWriter::new();
+                while let Some(outgoing_event) = sender.pop_outgoing_event(packet_index) {
+                    if !writer.write_event(
+                        &manifest,
+                        &SchemaVersions::new(),
+                        &outgoing_event,
+                        &SystemClock.now(),
+                    ) {
+                        sender.unpop_outgoing_event(packet_index, &outgoing_event);
+                        break;
+                    }
+                }
+                if writer.has_bytes() {
+                    let mut payload = Vec::new();
+                    writer.get_bytes(&mut payload);
+                    // a fifth of packets are lost, the rest take from 1 to 5
+                    // ticks to arrive, so they are reordered
+                    match random(5) {
+                        0 => link.push((packet_index, tick + 8, None)),
+                        _ => link.push((packet_index, tick + 1 + random(5) as u16, Some(payload))),
+                    }
+                    packet_index = packet_index.wrapping_add(1);
+                }
+
+                let (due, in_flight): (Vec<_>, Vec<_>) =
+                    link.drain(..).partition(|(_, at, _)| *at <= tick);
+                link = in_flight;
+                for (index, _, payload) in due {
+                    match payload {
+                        Some(payload) => {
+                            let mut reader = PacketReader::new(&payload);
+                            assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+                            receiver.process_data(
+                                &mut reader,
+                                &manifest,
+                                &SchemaVersions::new(),
+                                0,
+                                &SystemClock.now(),
+                            );
+                            sender.notify_packet_delivered(&remote_address(), index);
+                        }
+                        None => sender.notify_packet_dropped(index),
+                    }
+                }
+                while let Some(TestEvents::Block(block)) = receiver.pop_incoming_event() {
+                    received_ids.push(block.id);
+                }
+            }
+
+            let expected_ids: Vec<u16> = (0..event_count).collect();
+            assert_eq!(received_ids, expected_ids);
+        }
+    }
+
+    // writes each queued Event into packets until none are left, returning the
+    // packets' payloads
+    fn write_packets(
+        manifest: &Manifest<TestEvents, NoActors>,
+        sender: &mut EventManager<TestEvents>,
+        packet_index: &mut u16,
+    ) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        while sender.has_outgoing_events() {
+            let mut writer = EventPacketWriter::new();
+            while let Some(outgoing_event) = sender.pop_outgoing_event(*packet_index) {
+                if !writer.write_event(
+                    manifest,
+                    &SchemaVersions::new(),
+                    &outgoing_event,
+                    &SystemClock.now(),
+                ) {
+                    sender.unpop_outgoing_event(*packet_index, &outgoing_event);
+                    break;
+                }
+            }
+            let mut payload = Vec::new();
+            writer.get_bytes(&mut payload);
+            packets.push(payload);
+            *packet_index = packet_index.wrapping_add(1);
+        }
+        return packets;
+    }
+
+    // reads the Events out of a packet, in the order they are received
+    fn receive(manifest: &Manifest<TestEvents, NoActors>, payload: &[u8]) -> Vec<TestEvents> {
+        let mut receiver = EventManager::new(0);
+        let mut reader = PacketReader::new(payload);
+        assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+        receiver.process_data(
+            &mut reader,
+            manifest,
+            &SchemaVersions::new(),
+            0,
+            &SystemClock.now(),
+        );
+        let mut events = Vec::new();
+        while let Some(event) = receiver.pop_incoming_event() {
+            events.push(event);
+        }
+        return events;
+    }
+
+    #[test]
+    fn event_group_is_written_into_one_packet() {
+        let mut manifest = chat_manifest(false);
+        manifest.register_event(Box::new(BlockBuilder));
+        let mut sender = EventManager::<TestEvents>::new(0);
+
+        let block = |id, size| TestEvents::Block(Block { id, size });
+        let chat = TestEvents::Chat(Chat("grouped".to_string()));
+
+        // the first member would fit behind the first Event, but the whole
+        // group doesn't, so it is moved into the next packet
+        sender
+            .queue_outgoing_event(&Block { id: 0, size: 300 }, None, &SystemClock.now())
+            .unwrap();
+        let mut group = EventGroup::new();
+        group.queue(&Block { id: 1, size: 100 });
+        group.queue(&Chat("grouped".to_string()));
+        group.queue(&Block { id: 2, size: 100 });
+        EventPacketWriter::validate_event_group(&manifest, group.get_events()).unwrap();
+        sender.queue_outgoing_event_group(group.get_events(), &SystemClock.now());
+        sender
+            .queue_outgoing_event(&Block { id: 3, size: 20 }, None, &SystemClock.now())
+            .unwrap();
+
+        let mut packet_index = 0;
+        let packets = write_packets(&manifest, &mut sender, &mut packet_index);
+        assert_eq!(packets.len(), 2);
+        assert_eq!(receive(&manifest, &packets[0]), vec![block(0, 300)]);
+        let grouped = vec![block(1, 100), chat.clone(), block(2, 100)];
+        let mut expected = grouped.clone();
+        expected.push(block(3, 20));
+        assert_eq!(receive(&manifest, &packets[1]), expected);
+
+        // the group holds a guaranteed Event, so if the packet is lost, the
+        // whole group is re-transmitted together
+        sender.notify_packet_dropped(1);
+        let packets = write_packets(&manifest, &mut sender, &mut packet_index);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(receive(&manifest, &packets[0]), grouped);
+        sender.notify_packet_delivered(&remote_address(), 2);
+        assert_eq!(sender.get_reliable_usage(None), (0, 0));
+    }
+
+    #[test]
+    fn event_on_unconfigured_channel_is_rejected() {
+        let mut sender = EventManager::<TestEvents>::new(2);
+        let block = Block { id: 0, size: 10 };
+        assert!(matches!(
+            sender.queue_outgoing_event(&block, Some(2), &SystemClock.now()),
+            Err(NaiaError::NoSuchChannel {
+                channel_index: 2,
+                channel_count: 2
+            })
+        ));
+        assert!(!sender.has_outgoing_events());
+        sender
+            .queue_outgoing_event(&block, Some(1), &SystemClock.now())
+            .unwrap();
+        assert!(sender.has_outgoing_events());
+    }
+
+    #[test]
+    fn reliable_tail_waits_for_events_queued_before_it() {
+        let mut manifest = chat_manifest(false);
+        manifest.register_event(Box::new(BlockBuilder));
+        let mut sender = EventManager::<TestEvents>::new(0);
+        let mut packet_index = 0;
+
+        sender
+            .queue_outgoing_event(&Chat("before".to_string()), None, &SystemClock.now())
+            .unwrap();
+        sender
+            .queue_outgoing_event(&Block { id: 0, size: 10 }, None, &SystemClock.now())
+            .unwrap();
+        assert_eq!(
+            write_packets(&manifest, &mut sender, &mut packet_index).len(),
+            1
+        );
+        let tail = sender.get_reliable_tail();
+        sender
+            .queue_outgoing_event(&Chat("after".to_string()), None, &SystemClock.now())
+            .unwrap();
+        assert_eq!(
+            write_packets(&manifest, &mut sender, &mut packet_index).len(),
+            1
+        );
+        assert!(!sender.is_delivered_up_to(tail));
+
+        // the Event queued after the tail arriving doesn't count, and the lost
+        // Block is never re-transmitted, so isn't waited for
+        sender.notify_packet_dropped(0);
+        sender.notify_packet_delivered(&remote_address(), 1);
+        assert!(!sender.is_delivered_up_to(tail));
+        assert_eq!(
+            write_packets(&manifest, &mut sender, &mut packet_index).len(),
+            1
+        );
+        sender.notify_packet_delivered(&remote_address(), 2);
+        assert!(sender.is_delivered_up_to(tail));
+
+        // a tail placed with nothing queued before it is reached straight away
+        sender
+            .queue_outgoing_event(&Chat("later".to_string()), None, &SystemClock.now())
+            .unwrap();
+        assert!(sender.is_delivered_up_to(0));
+        assert!(!sender.is_delivered_up_to(sender.get_reliable_tail()));
+    }
+
+    #[test]
+    fn oversized_event_group_is_rejected() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlockBuilder));
+
+        let mut group = EventGroup::new();
+        group.queue(&Block { id: 0, size: 200 });
+        group.queue(&Block { id: 1, size: 200 });
+        assert!(EventPacketWriter::validate_event_group(&manifest, group.get_events()).is_ok());
+
+        // each Event fits into a packet alone, but not together
+        group.queue(&Block { id: 2, size: 200 });
+        assert!(EventPacketWriter::validate_event(&manifest, &Block { id: 2, size: 200 }).is_ok());
+        match EventPacketWriter::validate_event_group(&manifest, group.get_events()) {
+            Err(NaiaError::MessageTooLarge { size, max }) => {
+                assert_eq!(size, 600);
+                assert_eq!(max, MTU_SIZE - 12 - 3 * TRACE_ID_MAX_SIZE);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // the largest group allowed fits into an empty packet
+        let mut group = EventGroup::new();
+        group.queue(&Block { id: 0, size: 200 });
+        group.queue(&Block {
+            id: 1,
+            size: (MTU_SIZE - 9 - 2 * TRACE_ID_MAX_SIZE - 200) as u16,
+        });
+        assert!(EventPacketWriter::validate_event_group(&manifest, group.get_events()).is_ok());
+        let mut sender = EventManager::<TestEvents>::new(0);
+        sender.queue_outgoing_event_group(group.get_events(), &SystemClock.now());
+        let packets = write_packets(&manifest, &mut sender, &mut 0);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(receive(&manifest, &packets[0]).len(), 2);
+    }
+
+    #[test]
+    fn event_frames_match_golden() {
+        let payload = write_frames(
+            &chat_manifest(false),
+            vec![
+                (Box::new(Chat("hi".to_string())), None),
+                (Box::new(Chat("hi".to_string())), Some((2, 0x0304))),
+            ],
+        );
+        assert_golden("event_frames", &payload);
+    }
+
+    #[test]
+    fn traced_event_frames_match_golden_and_read_back() {
+        let manifest = chat_manifest(false);
+        let event: Box<dyn Event<TestEvents>> = Box::new(Chat("hi".to_string()));
+        let outgoing_event = OutgoingEvent {
+            event: Rc::new(event),
+            ordering: None,
+            size: 0,
+            queued_at: SystemClock.now(),
+            grouped: Vec::new(),
+            trace_id: Some(300),
+            actor: None,
+            queue_index: 0,
+        };
+        let mut writer = EventPacketWriter::new();
+        writer.set_writes_trace_ids(true);
+        assert!(writer.write_event(
+            &manifest,
+            &SchemaVersions::new(),
+            &outgoing_event,
+            &SystemClock.now()
+        ));
+        let mut payload = Vec::new();
+        writer.get_bytes(&mut payload);
+        assert_golden("traced_event_frames", &payload);
+
+        let mut receiver = EventManager::new(0);
+        receiver.set_trace_ids_in_frames(true);
+        let mut reader = PacketReader::new(&payload[1..]);
+        receiver.process_data(
+            &mut reader,
+            &manifest,
+            &SchemaVersions::new(),
+            0,
+            &SystemClock.now(),
+        );
+        assert_eq!(
+            receiver.pop_incoming_event(),
+            Some(TestEvents::Chat(Chat("hi".to_string())))
+        );
+        assert_eq!(receiver.pop_skipped_frame(), None);
+    }
+
+    #[test]
+    fn timestamped_event_frames_match_golden() {
+        let mut manifest = chat_manifest(false);
+        manifest.set_event_timestamped::<Chat>().unwrap();
+        let event: Box<dyn Event<TestEvents>> = Box::new(Chat("hi".to_string()));
+        // written at the moment it was queued, so as having waited no time
+        let clock = ManualClock::new();
+        let outgoing_event = OutgoingEvent {
+            event: Rc::new(event),
+            ordering: None,
+            size: 0,
+            queued_at: clock.now(),
+            grouped: Vec::new(),
+            trace_id: None,
+            actor: None,
+            queue_index: 0,
+        };
+        let mut writer = EventPacketWriter::new();
+        assert!(writer.write_event(
+            &manifest,
+            &SchemaVersions::new(),
+            &outgoing_event,
+            &clock.now()
+        ));
+        let mut payload = Vec::new();
+        writer.get_bytes(&mut payload);
+        assert_golden("timestamped_event_frames", &payload);
+    }
+
+    #[test]
+    fn timestamped_event_is_received_with_stamp() {
+        let mut sender_manifest = chat_manifest(false);
+        sender_manifest.set_event_timestamped::<Chat>().unwrap();
+        let mut relay_manifest = chat_manifest(false);
+        relay_manifest.set_event_timestamped::<Chat>().unwrap();
+        relay_manifest.set_event_receive_raw::<Chat>().unwrap();
+
+        let clock = ManualClock::new();
+        let queued_at = clock.now();
+        clock.advance(std::time::Duration::from_millis(20));
+        let chat: Box<dyn Event<TestEvents>> = Box::new(Chat("hi".to_string()));
+        let blob: Box<dyn Event<TestEvents>> = Box::new(Blob { size: 0 });
+        let outgoing_event = OutgoingEvent {
+            event: Rc::new(chat),
+            ordering: None,
+            size: 0,
+            queued_at: queued_at.clone(),
+            grouped: vec![Rc::new(blob)],
+            trace_id: None,
+            actor: None,
+            queue_index: 0,
+        };
+        let mut writer = EventPacketWriter::new();
+        assert!(writer.write_event(
+            &sender_manifest,
+            &SchemaVersions::new(),
+            &outgoing_event,
+            &clock.now()
+        ));
+        let mut payload = Vec::new();
+        writer.get_bytes(&mut payload);
+
+        let received_at = clock.now();
+        let mut receiver = EventManager::new(0);
+        let mut reader = PacketReader::new(&payload);
+        assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+        receiver.process_data(
+            &mut reader,
+            &sender_manifest,
+            &SchemaVersions::new(),
+            77,
+            &received_at,
+        );
+        assert!(!reader.has_more());
+
+        let (event, stamp, _) = receiver.pop_incoming_stamped_event().unwrap();
+        assert_eq!(event, TestEvents::Chat(Chat("hi".to_string())));
+        let stamp = stamp.unwrap();
+        assert_eq!(stamp.sent_tick, 77);
+        assert_eq!(stamp.received_at, received_at);
+        assert!(stamp.queue_delay >= std::time::Duration::from_millis(20));
+        assert!(stamp.queue_delay <= duration_between(&queued_at, &received_at));
+        // the Blob type isn't timestamped, so is written as it always is
+        let (event, stamp, _) = receiver.pop_incoming_stamped_event().unwrap();
+        assert_eq!(event, TestEvents::Blob(Blob { size: 0 }));
+        assert!(stamp.is_none());
+
+        // a host receiving the timestamped type raw gets only its payload
+        let mut relay = EventManager::new(0);
+        let mut reader = PacketReader::new(&payload);
+        reader.read_u8();
+        relay.process_data(
+            &mut reader,
+            &relay_manifest,
+            &SchemaVersions::new(),
+            77,
+            &received_at,
+        );
+        assert_eq!(&*relay.pop_incoming_raw_event().unwrap().bytes, b"\x02hi");
+    }
+
+    #[test]
+    fn actor_scoped_event_is_received_with_actor_key() {
+        let mut manifest = chat_manifest(false);
+        manifest.set_event_actor_scoped::<Chat>().unwrap();
+
+        let clock = ManualClock::new();
+        let mut writer = EventPacketWriter::new();
+        let events: Vec<(Box<dyn Event<TestEvents>>, Option<LocalActorKey>)> = vec![
+            (Box::new(Chat("hi".to_string())), Some(300)),
+            (Box::new(Chat("yo".to_string())), None),
+            (Box::new(Blob { size: 0 }), None),
+        ];
+        for (event, actor) in events {
+            let outgoing_event = OutgoingEvent {
+                event: Rc::new(event),
+                ordering: None,
+                size: 0,
+                queued_at: clock.now(),
+                grouped: Vec::new(),
+                trace_id: None,
+                actor,
+                queue_index: 0,
+            };
+            assert!(writer.write_event(
+                &manifest,
+                &SchemaVersions::new(),
+                &outgoing_event,
+                &clock.now()
+            ));
+        }
+        let mut payload = Vec::new();
+        writer.get_bytes(&mut payload);
+
+        let mut receiver = EventManager::new(0);
+        let mut reader = PacketReader::new(&payload);
+        assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+        receiver.process_data(
+            &mut reader,
+            &manifest,
+            &SchemaVersions::new(),
+            0,
+            &clock.now(),
+        );
+        assert!(!reader.has_more());
+
+        let (event, _, actor) = receiver.pop_incoming_stamped_event().unwrap();
+        assert_eq!(event, TestEvents::Chat(Chat("hi".to_string())));
+        assert_eq!(actor, Some(300));
+        // an actor-scoped type may still be sent about no Actor at all
+        let (event, _, actor) = receiver.pop_incoming_stamped_event().unwrap();
+        assert_eq!(event, TestEvents::Chat(Chat("yo".to_string())));
+        assert_eq!(actor, None);
+        // the Blob type isn't actor-scoped, so is written as it always is
+        let (event, _, actor) = receiver.pop_incoming_stamped_event().unwrap();
+        assert_eq!(event, TestEvents::Blob(Blob { size: 0 }));
+        assert_eq!(actor, None);
+    }
+
+    #[test]
+    fn timestamped_event_has_less_room_for_payload() {
+        let mut manifest = Manifest::<TestEvents, NoActors>::new();
+        manifest.register_event(Box::new(BlobBuilder));
+        manifest.set_event_timestamped::<Blob>().unwrap();
+
+        let largest = Blob {
+            size: MAX_EVENT_PAYLOAD_SIZE - 3,
+        };
+        assert!(EventPacketWriter::validate_event(&manifest, &largest).is_ok());
+        match EventPacketWriter::validate_event(
+            &manifest,
+            &Blob {
+                size: largest.size + 1,
+            },
+        ) {
+            Err(NaiaError::MessageTooLarge { max, .. }) => assert_eq!(max, largest.size),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_raw_event_is_rejected() {
+        let manifest = chat_manifest(true);
+        let unregistered = RawEvent {
+            naia_id: 7,
+            bytes: Box::new([]),
+        };
+        match EventPacketWriter::validate_raw_event(&manifest, &unregistered) {
+            Err(NaiaError::Manifest(ManifestError::UnregisteredEvent)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let too_large = RawEvent {
+            naia_id: 1,
+            bytes: vec![0; MAX_EVENT_PAYLOAD_SIZE + 1].into_boxed_slice(),
+        };
+        match EventPacketWriter::validate_raw_event(&manifest, &too_large) {
+            Err(NaiaError::MessageTooLarge { max, .. }) => {
+                assert_eq!(max, MAX_EVENT_PAYLOAD_SIZE)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unreadable_event_is_skipped() {
+        let mut sender_manifest = chat_manifest(false);
+        sender_manifest.register_event(Box::new(BlockBuilder));
+        let chat = |text: &str| TestEvents::Chat(Chat(text.to_string()));
+
+        // a host which doesn't know the Block type yet, and one which reads it
+        // as a Blob, which is written differently
+        let older_manifest = chat_manifest(false);
+        let mut mismatched_manifest = chat_manifest(false);
+        mismatched_manifest.register_event(Box::new(BlobBuilder));
+
+        for (receiving_manifest, skipped_frame) in [
+            (older_manifest, SkippedFrame::UnknownType(2)),
+            (mismatched_manifest, SkippedFrame::DecodeFailed(2)),
+        ]
+        .iter()
+        {
+            // unordered, then on an ordered channel, which must not be held
+            // back by the skipped Event
+            for ordered in [false, true].iter() {
+                let ordering = |sequence| match ordered {
+                    true => Some((0, sequence)),
+                    false => None,
+                };
+                let payload = write_frames(
+                    &sender_manifest,
+                    vec![
+                        (Box::new(Chat("first".to_string())), ordering(0)),
+                        (Box::new(Block { id: 1, size: 12 }), ordering(1)),
+                        (Box::new(Chat("last".to_string())), ordering(2)),
+                    ],
+                );
+
+                let mut receiver = EventManager::new(1);
+                let mut reader = PacketReader::new(&payload);
+                assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+                receiver.process_data(
+                    &mut reader,
+                    receiving_manifest,
+                    &SchemaVersions::new(),
+                    0,
+                    &SystemClock.now(),
+                );
+                assert!(!reader.has_more());
+
+                assert_eq!(receiver.pop_incoming_event(), Some(chat("first")));
+                assert_eq!(receiver.pop_incoming_event(), Some(chat("last")));
+                assert_eq!(receiver.pop_incoming_event(), None);
+                assert_eq!(receiver.pop_skipped_frame(), Some(*skipped_frame));
+                assert_eq!(receiver.pop_skipped_frame(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_frame_drops_rest_of_packet() {
+        let manifest = chat_manifest(false);
+        let chat = |text: &str| TestEvents::Chat(Chat(text.to_string()));
+        let mut payload = write_frames(
+            &manifest,
+            vec![
+                (Box::new(Chat("first".to_string())), None),
+                (Box::new(Chat("last".to_string())), None),
+            ],
+        );
+        // re-write the second Event's NaiaId with a redundant trailing byte
+        let second_frame = 2 + 2 + "first".len() + 1;
+        assert_eq!(payload[second_frame], 0x02);
+        payload[second_frame] = 0x82;
+        payload.insert(second_frame + 1, 0x00);
+
+        let mut receiver = EventManager::new(0);
+        let mut reader = PacketReader::new(&payload);
+        assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+        receiver.process_data(
+            &mut reader,
+            &manifest,
+            &SchemaVersions::new(),
+            0,
+            &SystemClock.now(),
+        );
+        assert!(!reader.has_more());
+
+        assert_eq!(receiver.pop_incoming_event(), Some(chat("first")));
+        assert_eq!(receiver.pop_incoming_event(), None);
+        assert_eq!(receiver.pop_skipped_frame(), Some(SkippedFrame::Malformed));
+    }
+
+    #[test]
+    fn truncated_events_are_skipped_rather_than_panicking() {
+        let manifest = chat_manifest(false);
+        let chat = |text: &str| TestEvents::Chat(Chat(text.to_string()));
+        let mut full_payload = Vec::new();
+        Event::write(&Chat("hello".to_string()), &mut full_payload);
+        let read = |receiver: &mut EventManager<TestEvents>, payload: &[u8]| {
+            let mut reader = PacketReader::new(payload);
+            assert_eq!(reader.read_u8(), ManagerType::Event as u8);
+            receiver.process_data(
+                &mut reader,
+                &manifest,
+                &SchemaVersions::new(),
+                0,
+                &SystemClock.now(),
+            );
+            assert!(!reader.has_more());
+        };
+
+        // every length short of the whole payload makes the ChatBuilder read
+        // past its end
+        let mut receiver = EventManager::new(0);
+        receiver.set_catch_decode_panics(true);
+        for length in 0..=full_payload.len() {
+            let payload = write_frames(
+                &manifest,
+                vec![
+                    (
+                        Box::new(TruncatedChat(full_payload[..length].to_vec())),
+                        None,
+                    ),
+                    (Box::new(Chat("after".to_string())), None),
+                ],
+            );
+            read(&mut receiver, &payload);
+            if length < full_payload.len() {
+                assert_eq!(
+                    receiver.pop_skipped_frame(),
+                    Some(SkippedFrame::DecodeFailed(1))
+                );
+            } else {
+                assert_eq!(receiver.pop_incoming_event(), Some(chat("hello")));
+            }
+            assert_eq!(receiver.pop_incoming_event(), Some(chat("after")));
+            assert_eq!(receiver.pop_incoming_event(), None);
+            assert_eq!(receiver.pop_skipped_frame(), None);
+        }
+
+        // unless set to be caught, the panic escapes
+        let payload = write_frames(
+            &manifest,
+            vec![(Box::new(TruncatedChat(full_payload[..1].to_vec())), None)],
+        );
+        let mut receiver = EventManager::new(0);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| read(&mut receiver, &payload)));
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::{utils::duration_between, Instant};
+
+use super::message_receipt::MessageReceipt;
+
+/// Held alongside a received Event of a type set to be timestamped in the
+/// Manifest, until it is handed to the application, along with a
+/// MessageReceipt made from it
+#[derive(Clone, Debug)]
+pub struct EventStamp {
+    /// How long the Event waited in the remote host's outgoing queue, as
+    /// written along with it
+    pub queue_delay: Duration,
+    /// The remote host's tick when it sent the packet the Event arrived in
+    pub sent_tick: u16,
+    /// The time the packet the Event arrived in was read at
+    pub received_at: Instant,
+}
+
+impl EventStamp {
+    /// Gets the MessageReceipt of the Event, given the Round Trip Time
+    /// measured to the remote host in milliseconds, half of which is taken to
+    /// be the time the packet took to arrive, and the current time
+    pub fn to_receipt(&self, rtt: f32, now: &Instant) -> MessageReceipt {
+        let transit = Duration::from_secs_f32(rtt.max(0.0) / 2000.0);
+        return MessageReceipt {
+            age_estimate: self.queue_delay + transit + duration_between(&self.received_at, now),
+            sent_tick: self.sent_tick,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::EventStamp;
+    use crate::{Clock, SystemClock};
+
+    #[test]
+    fn receipt_adds_queue_delay_transit_and_wait() {
+        let received_at = SystemClock.now();
+        let stamp = EventStamp {
+            queue_delay: Duration::from_millis(30),
+            sent_tick: 12,
+            received_at: received_at.clone(),
+        };
+        let mut now = received_at;
+        now.add_millis(5);
+
+        let receipt = stamp.to_receipt(80.0, &now);
+        assert_eq!(receipt.sent_tick, 12);
+        // 30ms queued, 40ms in transit, & 5ms waiting to be handed over
+        let age_ms = receipt.age_estimate.as_secs_f32() * 1000.0;
+        assert!((age_ms - 75.0).abs() < 0.01, "age was {}ms", age_ms);
+    }
+}
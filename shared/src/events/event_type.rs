@@ -1,5 +1,7 @@
 use std::any::TypeId;
 
+use crate::actors::actor_ref::ActorRef;
+
 /// An Enum with a variant for every Event that can be sent to a remote host
 pub trait EventType: Clone {
     // write & get_type_id are ONLY currently used for reading/writing auth events..
@@ -8,4 +10,8 @@ pub trait EventType: Clone {
     fn write(&self, buffer: &mut Vec<u8>);
     /// Get the TypeId of the contained Event
     fn get_type_id(&self) -> TypeId;
+    /// Gets all the ActorRefs held by the contained Event's Properties
+    fn get_actor_refs(&self) -> Vec<ActorRef> {
+        return Vec::new();
+    }
 }
@@ -0,0 +1,13 @@
+use std::time::Duration;
+
+/// When an Event was sent, as estimated by the host which received it. Only
+/// Events of types set to be timestamped in the Manifest have one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MessageReceipt {
+    /// An estimate of how long ago the Event was queued by the remote host,
+    /// counting the time it waited to be sent, the time it took to arrive,
+    /// and the time it has waited to be handed to the application since
+    pub age_estimate: Duration,
+    /// The remote host's tick when it sent the packet the Event arrived in
+    pub sent_tick: u16,
+}
@@ -1,5 +1,14 @@
 pub(crate) mod event;
 pub(crate) mod event_builder;
+pub(crate) mod event_frame;
+pub(crate) mod event_group;
 pub(crate) mod event_manager;
 pub(crate) mod event_packet_writer;
+pub(crate) mod event_stamp;
 pub(crate) mod event_type;
+pub(crate) mod message_receipt;
+pub(crate) mod ordered_channel;
+pub(crate) mod outgoing_event;
+pub(crate) mod raw_event;
+pub(crate) mod skipped_frame;
+pub(crate) mod tick_payload_frame;
@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+
+use crate::{
+    sequence_buffer::{SequenceBuffer, SequenceNumber},
+    wrapping_number::{sequence_less_than, wrapping_diff},
+};
+
+/// The index of an ordered Event channel. Events sent on the same channel are
+/// received in the order they were sent, independently of other channels
+pub type ChannelIndex = u8;
+
+/// An Event's NaiaId is written into a packet shifted up by one bit, with this
+/// flag set on the lowest bit to indicate that the channel index & sequence
+/// number of the Event follow
+pub(crate) const ORDERED_EVENT_FLAG: u16 = 0x0001;
+
+// The maximum number of Events that can be in flight on a channel before the
+// oldest has been acknowledged, so that the receiver never has to buffer more
+// than this many Events
+const CHANNEL_WINDOW_SIZE: u16 = 64;
+
+/// Keeps track of the sequence of Events sent & received on a single ordered
+/// channel. Its buffers are only allocated once the channel is used, the one
+/// for sending when the first Event is sent on it, and the one for receiving
+/// when the first Event is received on it, so that channels a connection never
+/// uses cost next to nothing
+#[derive(Debug)]
+pub struct OrderedChannel<T: Clone> {
+    next_send_sequence: SequenceNumber,
+    oldest_unacked_sequence: SequenceNumber,
+    acked_sequences: Option<SequenceBuffer<bool>>,
+    next_receive_sequence: SequenceNumber,
+    received_events: Option<SequenceBuffer<T>>,
+}
+
+impl<T: Clone> OrderedChannel<T> {
+    /// Creates a new OrderedChannel, without allocating its buffers
+    pub fn new() -> Self {
+        OrderedChannel {
+            next_send_sequence: 0,
+            oldest_unacked_sequence: 0,
+            acked_sequences: None,
+            next_receive_sequence: 0,
+            received_events: None,
+        }
+    }
+
+    /// Gets the number of bytes allocated for the channel's buffers
+    pub fn allocated_bytes(&self) -> usize {
+        let acked_bytes = self
+            .acked_sequences
+            .as_ref()
+            .map_or(0, |buffer| buffer.allocated_bytes());
+        let received_bytes = self
+            .received_events
+            .as_ref()
+            .map_or(0, |buffer| buffer.allocated_bytes());
+        return acked_bytes + received_bytes;
+    }
+
+    /// Gets the sequence number to be used for the next outgoing Event
+    pub fn next_send_sequence(&mut self) -> SequenceNumber {
+        self.acked_sequences
+            .get_or_insert_with(|| SequenceBuffer::with_capacity(CHANNEL_WINDOW_SIZE));
+        let sequence = self.next_send_sequence;
+        self.next_send_sequence = self.next_send_sequence.wrapping_add(1);
+        return sequence;
+    }
+
+    /// Returns whether the Event with the given sequence number can be sent
+    /// without overflowing the receiver's buffer
+    pub fn can_send(&self, sequence: SequenceNumber) -> bool {
+        return wrapping_diff(self.oldest_unacked_sequence, sequence) < CHANNEL_WINDOW_SIZE as i16;
+    }
+
+    /// Records that the Event with the given sequence number has been
+    /// delivered to the remote host. Returns false if the Event isn't in
+    /// flight, having been acknowledged already, or never sent
+    pub fn notify_delivered(&mut self, sequence: SequenceNumber) -> bool {
+        // nothing has been sent on the channel
+        let acked_sequences = match &mut self.acked_sequences {
+            Some(acked_sequences) => acked_sequences,
+            None => return false,
+        };
+        let in_flight = sequence_less_than(sequence, self.next_send_sequence)
+            && !sequence_less_than(sequence, self.oldest_unacked_sequence)
+            && !acked_sequences.exists(sequence);
+        acked_sequences.insert(sequence, true);
+        while self.oldest_unacked_sequence != self.next_send_sequence
+            && acked_sequences.exists(self.oldest_unacked_sequence)
+        {
+            acked_sequences.remove(self.oldest_unacked_sequence);
+            self.oldest_unacked_sequence = self.oldest_unacked_sequence.wrapping_add(1);
+        }
+        return in_flight;
+    }
+
+    /// Receives an Event with the given sequence number, pushing it and any
+    /// buffered Events that were waiting on it into the output queue, in order.
+    /// Events which have already been received are discarded
+    pub fn receive(&mut self, sequence: SequenceNumber, event: T, output: &mut VecDeque<T>) {
+        let received_events = self
+            .received_events
+            .get_or_insert_with(|| SequenceBuffer::with_capacity(CHANNEL_WINDOW_SIZE));
+        if sequence == self.next_receive_sequence {
+            output.push_back(event);
+            self.next_receive_sequence = self.next_receive_sequence.wrapping_add(1);
+            while let Some(buffered_event) = received_events.remove(self.next_receive_sequence) {
+                output.push_back(buffered_event);
+                self.next_receive_sequence = self.next_receive_sequence.wrapping_add(1);
+            }
+        } else {
+            // the sequence number half the range ahead is taken as ahead by
+            // `sequence_greater_than()`, but as far behind by `wrapping_diff()`,
+            // so only the latter is used, lest it flush the buffered Events
+            let offset = wrapping_diff(self.next_receive_sequence, sequence);
+            if offset > 0 && offset < CHANNEL_WINDOW_SIZE as i16 {
+                received_events.insert(sequence, event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::OrderedChannel;
+
+    #[test]
+    fn receives_in_order() {
+        let mut channel = OrderedChannel::<u8>::new();
+        let mut output = VecDeque::new();
+
+        channel.receive(1, 11, &mut output);
+        channel.receive(2, 12, &mut output);
+        assert_eq!(output.len(), 0);
+
+        channel.receive(0, 10, &mut output);
+        assert_eq!(output, vec![10, 11, 12]);
+    }
+
+    #[test]
+    fn discards_duplicates() {
+        let mut channel = OrderedChannel::<u8>::new();
+        let mut output = VecDeque::new();
+
+        channel.receive(0, 10, &mut output);
+        channel.receive(0, 10, &mut output);
+        channel.receive(2, 12, &mut output);
+        channel.receive(2, 12, &mut output);
+        channel.receive(1, 11, &mut output);
+        assert_eq!(output, vec![10, 11, 12]);
+    }
+
+    #[test]
+    fn held_events_are_bounded_by_the_window() {
+        let mut channel = OrderedChannel::<Vec<u8>>::new();
+        let mut output = VecDeque::new();
+        let held_bytes = |channel: &OrderedChannel<Vec<u8>>| -> usize {
+            return (0..=u16::MAX)
+                .filter_map(|sequence| channel.received_events.as_ref()?.get(sequence))
+                .map(|event| event.len())
+                .sum();
+        };
+
+        // every sequence number but the next is sent, scrambled, as a peer
+        // holding back the next Event to make the receiver buffer the rest
+        for step in 1..=u16::MAX {
+            let sequence = step.wrapping_mul(7919);
+            channel.receive(sequence, vec![0; 400], &mut output);
+            if step % 4096 == 0 {
+                assert!(held_bytes(&channel) <= 63 * 400);
+            }
+        }
+        assert!(output.is_empty());
+        assert_eq!(held_bytes(&channel), 63 * 400);
+
+        channel.receive(0, Vec::new(), &mut output);
+        assert_eq!(output.len(), 64);
+        assert_eq!(held_bytes(&channel), 0);
+    }
+
+    #[test]
+    fn blocked_channel_does_not_block_others() {
+        let mut blocked_channel = OrderedChannel::<u8>::new();
+        let mut flowing_channel = OrderedChannel::<u8>::new();
+        let mut output = VecDeque::new();
+
+        // first event on the blocked channel is lost
+        for sequence in 1..10 {
+            blocked_channel.receive(sequence, 0, &mut output);
+        }
+        for sequence in 0..10 {
+            flowing_channel.receive(sequence, sequence as u8, &mut output);
+        }
+        assert_eq!(output, (0..10).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn buffers_are_allocated_on_first_use() {
+        let mut channel = OrderedChannel::<u8>::new();
+        let mut output = VecDeque::new();
+        assert_eq!(channel.allocated_bytes(), 0);
+        assert!(!channel.notify_delivered(0));
+        assert_eq!(channel.allocated_bytes(), 0);
+
+        channel.next_send_sequence();
+        let sending_bytes = channel.allocated_bytes();
+        assert!(sending_bytes > 0);
+        assert!(channel.notify_delivered(0));
+
+        channel.receive(0, 10, &mut output);
+        assert!(channel.allocated_bytes() > sending_bytes);
+        assert_eq!(output, vec![10]);
+    }
+
+    #[test]
+    fn window_limits_unacked_events() {
+        let mut channel = OrderedChannel::<u8>::new();
+
+        for _ in 0..64 {
+            let sequence = channel.next_send_sequence();
+            assert!(channel.can_send(sequence));
+        }
+        let sequence = channel.next_send_sequence();
+        assert!(!channel.can_send(sequence));
+
+        // acknowledging out of order doesn't open the window
+        assert!(channel.notify_delivered(1));
+        assert!(!channel.can_send(sequence));
+
+        assert!(channel.notify_delivered(0));
+        assert!(channel.can_send(sequence));
+
+        // Events are only ever acknowledged once, and only once sent
+        assert!(!channel.notify_delivered(1));
+        assert!(!channel.notify_delivered(0));
+        assert!(!channel.notify_delivered(65));
+    }
+}
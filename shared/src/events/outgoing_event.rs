@@ -0,0 +1,50 @@
+use std::rc::Rc;
+
+use crate::{
+    actors::local_actor_key::LocalActorKey, message_tracing::TraceId,
+    sequence_buffer::SequenceNumber, Instant,
+};
+
+use super::{event::Event, event_type::EventType, ordered_channel::ChannelIndex};
+
+/// An Event queued to be sent to the remote host
+#[derive(Debug)]
+pub struct OutgoingEvent<T: EventType> {
+    /// The Event to be sent
+    pub event: Rc<Box<dyn Event<T>>>,
+    /// The channel & sequence number of the Event, if it is to be received in
+    /// order
+    pub ordering: Option<(ChannelIndex, SequenceNumber)>,
+    /// The size of the Event in bytes, if it must be re-transmitted until
+    /// delivered, otherwise zero
+    pub size: usize,
+    /// The time the Event was first queued at
+    pub queued_at: Instant,
+    /// The Events queued in a group with this one, which must be written into
+    /// the same packet right after it. Empty unless the Event leads a group
+    pub grouped: Vec<Rc<Box<dyn Event<T>>>>,
+    /// The id the Event is traced by, shared by every Event in its group, if
+    /// this host was built with message tracing
+    pub trace_id: Option<TraceId>,
+    /// The key of the Actor the Event is about on the remote host, if it was
+    /// sent as an actor-scoped Event
+    pub actor: Option<LocalActorKey>,
+    /// The number of Events queued on the connection before this one, which
+    /// tells the Events queued before a barrier apart from those after it
+    pub queue_index: u64,
+}
+
+impl<T: EventType> Clone for OutgoingEvent<T> {
+    fn clone(&self) -> Self {
+        OutgoingEvent {
+            event: self.event.clone(),
+            ordering: self.ordering,
+            size: self.size,
+            queued_at: self.queued_at.clone(),
+            grouped: self.grouped.clone(),
+            trace_id: self.trace_id,
+            actor: self.actor,
+            queue_index: self.queue_index,
+        }
+    }
+}
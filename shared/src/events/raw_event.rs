@@ -0,0 +1,53 @@
+use std::{any::TypeId, marker::PhantomData};
+
+use super::{event::Event, event_type::EventType};
+
+/// An Event held as the bytes it was written as, along with the NaiaId of its
+/// type, so that it can be forwarded without being read into the EventType
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawEvent {
+    /// The NaiaId of the Event's type
+    pub naia_id: u16,
+    /// The Event's payload, as written by `Event::write()`
+    pub bytes: Box<[u8]>,
+}
+
+// Queues the bytes of a RawEvent alongside typed Events, to be written as they
+// are
+#[derive(Clone)]
+pub(crate) struct OutgoingRawEvent<T: EventType> {
+    type_id: TypeId,
+    guaranteed: bool,
+    bytes: Box<[u8]>,
+    phantom_t: PhantomData<T>,
+}
+
+impl<T: EventType> OutgoingRawEvent<T> {
+    pub fn new(type_id: TypeId, guaranteed: bool, bytes: Box<[u8]>) -> Self {
+        OutgoingRawEvent {
+            type_id,
+            guaranteed,
+            bytes,
+            phantom_t: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static + EventType> Event<T> for OutgoingRawEvent<T> {
+    fn is_guaranteed(&self) -> bool {
+        return self.guaranteed;
+    }
+
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.extend_from_slice(&self.bytes);
+    }
+
+    fn get_typed_copy(&self) -> T {
+        // only ever written into packets, never handed to the application
+        unreachable!("a RawEvent can't be read into an EventType");
+    }
+
+    fn get_type_id(&self) -> TypeId {
+        return self.type_id;
+    }
+}
@@ -0,0 +1,17 @@
+/// An Event which was skipped over while reading a packet, rather than being
+/// received. Every Event is written along with its size, so the Events after
+/// it in the packet are still received
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SkippedFrame {
+    /// No Event type has been registered in the Manifest with the given
+    /// NaiaId, such as when the remote host has been upgraded to a version
+    /// with Event types this host doesn't know yet
+    UnknownType(u16),
+    /// The Event type with the given NaiaId didn't read exactly the bytes the
+    /// Event was written as, or panicked reading them, so the two hosts
+    /// disagree on how it is written, or the payload was corrupted
+    DecodeFailed(u16),
+    /// The header of an Event couldn't be read, so neither it nor any Event
+    /// after it in the packet could be
+    Malformed,
+}
@@ -0,0 +1,123 @@
+use crate::{
+    byte_order,
+    varint::{self, VarintError},
+    PacketReader,
+};
+
+/// The most bytes a tick payload's frame adds to the payload itself: the
+/// manager type, the tick, the distance back to the previous tick payload,
+/// the NaiaId & the size of the payload
+pub const TICK_PAYLOAD_MAX_OVERHEAD: usize = 1 + 2 + 3 + 3 + 3;
+
+/// A tick payload, as read from its frame
+#[derive(Debug, PartialEq)]
+pub struct TickPayloadFrame<'s> {
+    /// The Server tick the payload was set for
+    pub tick: u16,
+    /// The tick of the previous payload sent on the connection, if any was
+    pub previous_tick: Option<u16>,
+    /// The NaiaId of the payload's Event type
+    pub naia_id: u16,
+    /// The bytes of the payload's Event
+    pub payload: &'s [u8],
+}
+
+/// Writes the frame of a tick payload: the tick it was set for, the distance
+/// back to the tick of the previous payload sent on the connection, or 0 if
+/// none was, then the NaiaId of its Event type, the size of its payload & the
+/// payload itself
+pub fn write_tick_payload_frame(
+    tick: u16,
+    previous_tick: Option<u16>,
+    naia_id: u16,
+    payload: &[u8],
+    out_bytes: &mut Vec<u8>,
+) {
+    byte_order::write_u16(out_bytes, tick); // write tick
+    let distance = previous_tick.map_or(0, |previous_tick| tick.wrapping_sub(previous_tick));
+    varint::write_u16(distance, out_bytes); // write distance back to previous tick
+    varint::write_u16(naia_id, out_bytes); // write naia id
+    varint::write_u16(payload.len() as u16, out_bytes); // write payload size
+    out_bytes.extend_from_slice(payload); // write payload
+}
+
+/// Reads the frame of a tick payload, as written by
+/// `write_tick_payload_frame()`. A frame cut short anywhere is an Error
+pub fn read_tick_payload_frame<'s>(
+    reader: &mut PacketReader<'s>,
+) -> Result<TickPayloadFrame<'s>, VarintError> {
+    let tick = byte_order::try_read_u16(reader).ok_or(VarintError::Truncated)?;
+    let distance = varint::read_from(reader, varint::read_u16)?;
+    let naia_id = varint::read_from(reader, varint::read_u16)?;
+    let size = varint::read_from(reader, varint::read_u16)? as usize;
+
+    let buffer = reader.get_buffer();
+    let start = reader.get_cursor().position() as usize;
+    if buffer.len() < start + size {
+        return Err(VarintError::Truncated);
+    }
+    reader.get_cursor().set_position((start + size) as u64);
+
+    let previous_tick = if distance == 0 {
+        None
+    } else {
+        Some(tick.wrapping_sub(distance))
+    };
+    return Ok(TickPayloadFrame {
+        tick,
+        previous_tick,
+        naia_id,
+        payload: &buffer[start..start + size],
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_tick_payload_frame, write_tick_payload_frame, TickPayloadFrame};
+    use crate::{varint::VarintError, wire_format::assert_golden, ManagerType, PacketReader};
+
+    #[test]
+    fn tick_payload_matches_golden() {
+        let mut bytes = vec![ManagerType::TickPayload as u8];
+        write_tick_payload_frame(0x0102, Some(0x0100), 3, b"hi", &mut bytes);
+        assert_golden("tick_payload", &bytes);
+    }
+
+    #[test]
+    fn frames_read_back_as_written() {
+        let mut bytes = Vec::new();
+        write_tick_payload_frame(3, Some(65534), 200, &[1, 2], &mut bytes);
+        write_tick_payload_frame(4, None, 1, &[], &mut bytes);
+        let mut reader = PacketReader::new(&bytes);
+        assert_eq!(
+            read_tick_payload_frame(&mut reader),
+            Ok(TickPayloadFrame {
+                tick: 3,
+                previous_tick: Some(65534),
+                naia_id: 200,
+                payload: &[1, 2],
+            })
+        );
+        assert_eq!(
+            read_tick_payload_frame(&mut reader),
+            Ok(TickPayloadFrame {
+                tick: 4,
+                previous_tick: None,
+                naia_id: 1,
+                payload: &[],
+            })
+        );
+        assert!(!reader.has_more());
+    }
+
+    #[test]
+    fn payload_cut_short_is_an_error() {
+        let mut bytes = Vec::new();
+        write_tick_payload_frame(3, None, 1, &[1, 2], &mut bytes);
+        let mut reader = PacketReader::new(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            read_tick_payload_frame(&mut reader),
+            Err(VarintError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use byteorder::WriteBytesExt;
+
+use crate::{byte_order, reject_reason::RejectReason, PacketReader, Timestamp};
+
+/// The number of bytes in a session token
+pub const SESSION_TOKEN_BYTES: usize = 16;
+
+/// The number of bytes in the digest the Server signs a Client's Timestamp
+/// with
+pub const TIMESTAMP_DIGEST_BYTES: usize = 32;
+
+// the number of bytes a Timestamp is written as
+const TIMESTAMP_BYTES: usize = 8;
+
+// the bits of the flags byte in connect requests & responses, for compression
+// & message tracing
+const COMPRESSION_FLAG: u8 = 0x01;
+const MESSAGE_TRACING_FLAG: u8 = 0x02;
+// the bit of a connect request's flags set when early data follows its
+// session token
+const EARLY_DATA_FLAG: u8 = 0x04;
+
+/// A token issued by the Server when a connection is accepted, with which the
+/// Client can resume the connection after it has dropped, within the Server's
+/// grace period
+pub type SessionToken = [u8; SESSION_TOKEN_BYTES];
+
+/// A packet of Commands a resuming Client attaches to its
+/// ClientConnectRequest, which the Server only releases once the connection
+/// has been resumed, so that they don't wait on the handshake
+#[derive(Clone, Debug, PartialEq)]
+pub struct EarlyData {
+    /// The Client tick the Commands were issued for
+    pub client_tick: u16,
+    /// The most recent Server tick the Client had received when they were
+    /// issued
+    pub perceived_tick: u16,
+    /// The payload of a Data packet holding nothing but the Commands
+    pub payload: Vec<u8>,
+}
+
+/// Writes the payload of a ClientChallengeRequest, which is the Timestamp the
+/// Client started the handshake at
+pub fn write_challenge_request(timestamp: &Timestamp) -> Vec<u8> {
+    let mut payload_bytes = Vec::new();
+    byte_order::write_timestamp(&mut payload_bytes, &timestamp);
+    return payload_bytes;
+}
+
+/// Reads the payload of a ClientChallengeRequest, as written by
+/// `write_challenge_request()`. Returns None if it has been cut short
+pub fn read_challenge_request(reader: &mut PacketReader) -> Option<Timestamp> {
+    if remaining(reader) < TIMESTAMP_BYTES {
+        return None;
+    }
+    return Some(byte_order::read_timestamp(reader));
+}
+
+/// Writes the payload of a ServerChallengeResponse: the current tick of the
+/// Server, followed by the Client's Timestamp and the Server's digest of it
+pub fn write_challenge_response(
+    server_tick: u16,
+    timestamp: &Timestamp,
+    timestamp_digest: &[u8],
+) -> Vec<u8> {
+    let mut payload_bytes = Vec::new();
+    byte_order::write_u16(&mut payload_bytes, server_tick); // write current tick
+    byte_order::write_timestamp(&mut payload_bytes, &timestamp); // write timestamp
+    payload_bytes.extend_from_slice(timestamp_digest); // write timestamp digest
+    return payload_bytes;
+}
+
+/// The payload of a ServerChallengeResponse, as read by
+/// `read_challenge_response()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChallengeResponse {
+    /// The current tick of the Server
+    pub server_tick: u16,
+    /// The Timestamp the Client started the handshake at
+    pub timestamp: Timestamp,
+    /// The Server's digest of the Timestamp
+    pub timestamp_digest: Vec<u8>,
+}
+
+/// Reads the payload of a ServerChallengeResponse, as written by
+/// `write_challenge_response()`. Returns None if it has been cut short
+pub fn read_challenge_response(reader: &mut PacketReader) -> Option<ChallengeResponse> {
+    if remaining(reader) < 2 + TIMESTAMP_BYTES + TIMESTAMP_DIGEST_BYTES {
+        return None;
+    }
+    let server_tick = byte_order::read_u16(reader);
+    let timestamp = byte_order::read_timestamp(reader);
+    let timestamp_digest = read_bytes(reader, TIMESTAMP_DIGEST_BYTES);
+    return Some(ChallengeResponse {
+        server_tick,
+        timestamp,
+        timestamp_digest,
+    });
+}
+
+/// Writes the payload of a ClientConnectRequest: the Client's Timestamp and
+/// the digest received from the Server, whether the Client supports
+/// compression & message tracing, the session token of the connection to
+/// resume, if any, followed by the early data to release once it's resumed,
+/// the schema versions of the Client's types, as written by
+/// `Manifest::write_schema_versions()`, and the NaiaId & payload of the auth
+/// Event, if there is one
+pub fn write_connect_request(
+    timestamp: &Timestamp,
+    timestamp_digest: &[u8],
+    supports_compression: bool,
+    supports_message_tracing: bool,
+    resumption: Option<(&SessionToken, &[EarlyData])>,
+    schema_versions: &[u8],
+    auth_event: Option<(u16, &[u8])>,
+) -> Vec<u8> {
+    let mut payload_bytes = Vec::new();
+    byte_order::write_timestamp(&mut payload_bytes, &timestamp); // write timestamp
+    payload_bytes.extend_from_slice(timestamp_digest); // write timestamp digest
+    let session_token = resumption.map(|(session_token, _)| session_token);
+    let early_data = resumption.map_or(&[][..], |(_, early_data)| early_data);
+    let writes_early_data = !early_data.is_empty();
+    let mut flags = write_flags(supports_compression, supports_message_tracing);
+    if writes_early_data {
+        flags |= EARLY_DATA_FLAG;
+    }
+    payload_bytes.write_u8(flags).unwrap(); // write compression, message tracing & early data
+    write_session_token(session_token, &mut payload_bytes);
+    if writes_early_data {
+        let early_data = &early_data[..early_data.len().min(u8::MAX as usize)];
+        payload_bytes.write_u8(early_data.len() as u8).unwrap(); // write early data count
+        for packet in early_data {
+            let payload_length = packet.payload.len().min(u16::MAX as usize);
+            byte_order::write_u16(&mut payload_bytes, packet.client_tick); // write client tick
+            byte_order::write_u16(&mut payload_bytes, packet.perceived_tick); // write perceived tick
+            byte_order::write_u16(&mut payload_bytes, payload_length as u16); // write payload length
+            payload_bytes.extend_from_slice(&packet.payload[..payload_length]); // write payload
+        }
+    }
+    payload_bytes.extend_from_slice(schema_versions); // write schema versions
+    if let Some((naia_id, event_bytes)) = auth_event {
+        byte_order::write_u16(&mut payload_bytes, naia_id); // write naia id
+        payload_bytes.extend_from_slice(event_bytes); // write auth event payload
+    }
+    return payload_bytes;
+}
+
+/// The start of a ClientConnectRequest's payload, as read by
+/// `read_connect_request()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectRequest {
+    /// The Timestamp the Client started the handshake at
+    pub timestamp: Timestamp,
+    /// The digest of the Timestamp received from the Server
+    pub timestamp_digest: Vec<u8>,
+    /// Whether the Client supports compression
+    pub supports_compression: bool,
+    /// Whether the Client was built with message tracing
+    pub supports_message_tracing: bool,
+    /// The session token of the connection to resume, if any
+    pub session_token: Option<SessionToken>,
+    /// The early data to release once the connection is resumed
+    pub early_data: Vec<EarlyData>,
+}
+
+/// Reads the start of the payload of a ClientConnectRequest, as written by
+/// `write_connect_request()`, leaving the reader at the schema versions,
+/// which are read with the Manifest, and the auth Event after them. Returns
+/// None if it has been cut short
+pub fn read_connect_request(reader: &mut PacketReader) -> Option<ConnectRequest> {
+    if remaining(reader) < TIMESTAMP_BYTES + TIMESTAMP_DIGEST_BYTES + 1 {
+        return None;
+    }
+    let timestamp = byte_order::read_timestamp(reader);
+    let timestamp_digest = read_bytes(reader, TIMESTAMP_DIGEST_BYTES);
+    let flags = reader.read_u8();
+    let (supports_compression, supports_message_tracing) = read_flags(flags);
+    let session_token = read_session_token(reader);
+    let mut early_data = Vec::new();
+    if session_token.is_some() && flags & EARLY_DATA_FLAG != 0 {
+        early_data = read_early_data(reader)?;
+    }
+    return Some(ConnectRequest {
+        timestamp,
+        timestamp_digest,
+        supports_compression,
+        supports_message_tracing,
+        session_token,
+        early_data,
+    });
+}
+
+// reads the early data following the session token of a connect request.
+// Returns None if it has been cut short
+fn read_early_data(reader: &mut PacketReader) -> Option<Vec<EarlyData>> {
+    if remaining(reader) < 1 {
+        return None;
+    }
+    let count = reader.read_u8();
+    let mut early_data = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        if remaining(reader) < 6 {
+            return None;
+        }
+        let client_tick = byte_order::read_u16(reader);
+        let perceived_tick = byte_order::read_u16(reader);
+        let payload_length = byte_order::read_u16(reader) as usize;
+        if remaining(reader) < payload_length {
+            return None;
+        }
+        early_data.push(EarlyData {
+            client_tick,
+            perceived_tick,
+            payload: read_bytes(reader, payload_length),
+        });
+    }
+    return Some(early_data);
+}
+
+/// Reads the NaiaId & payload of the auth Event at the end of a
+/// ClientConnectRequest, once the schema versions before it have been read.
+/// Returns None if there is no auth Event
+pub fn read_auth_event<'s>(reader: &mut PacketReader<'s>) -> Option<(u16, &'s [u8])> {
+    if remaining(reader) < 2 {
+        return None;
+    }
+    let naia_id = byte_order::read_u16(reader);
+    let buffer = reader.get_buffer();
+    let start = reader.get_cursor().position() as usize;
+    reader.get_cursor().set_position(buffer.len() as u64);
+    return Some((naia_id, &buffer[start..]));
+}
+
+/// Writes the payload of a ServerConnectResponse, which lets the Client know
+/// whether Data packets will be compressed, whether Events will be traced
+/// across both hosts, whether its previous connection
+/// was resumed, the session token to resume this connection with, if the
+/// Server allows resumption, and the schema versions of the Server's types,
+/// as written by `Manifest::write_schema_versions()`
+pub fn write_connect_response(
+    compression_enabled: bool,
+    message_tracing_enabled: bool,
+    resumed: bool,
+    session_token: Option<&SessionToken>,
+    schema_versions: &[u8],
+) -> Vec<u8> {
+    let mut payload_bytes = Vec::new();
+    let flags = write_flags(compression_enabled, message_tracing_enabled);
+    payload_bytes.write_u8(flags).unwrap(); // write compression & message tracing
+    payload_bytes.write_u8(resumed as u8).unwrap(); // write whether resumed
+    write_session_token(session_token, &mut payload_bytes);
+    payload_bytes.extend_from_slice(schema_versions); // write schema versions
+    return payload_bytes;
+}
+
+/// The start of a ServerConnectResponse's payload, as read by
+/// `read_connect_response()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectResponse {
+    /// Whether Data packets will be compressed
+    pub compression_enabled: bool,
+    /// Whether trace ids will be written ahead of Event frames
+    pub message_tracing_enabled: bool,
+    /// Whether the Client's previous connection was resumed
+    pub resumed: bool,
+    /// The session token to resume the connection with, if the Server allows
+    /// resumption
+    pub session_token: Option<SessionToken>,
+}
+
+/// Reads the start of the payload of a ServerConnectResponse, as written by
+/// `write_connect_response()`, leaving the reader at the schema versions,
+/// which are read with the Manifest. Anything cut short is read as false, or
+/// as no session token
+pub fn read_connect_response(reader: &mut PacketReader) -> ConnectResponse {
+    let mut flags = 0;
+    if reader.has_more() {
+        flags = reader.read_u8();
+    }
+    let (compression_enabled, message_tracing_enabled) = read_flags(flags);
+    let resumed = reader.has_more() && reader.read_u8() != 0;
+    let session_token = read_session_token(reader);
+    return ConnectResponse {
+        compression_enabled,
+        message_tracing_enabled,
+        resumed,
+        session_token,
+    };
+}
+
+/// Reads an optional session token, as written into connect requests &
+/// responses. Returns None if there is no token, or it has been cut short
+pub fn read_session_token(reader: &mut PacketReader) -> Option<SessionToken> {
+    if !reader.has_more() || reader.read_u8() == 0 {
+        return None;
+    }
+    let mut session_token: SessionToken = [0; SESSION_TOKEN_BYTES];
+    for byte in session_token.iter_mut() {
+        if !reader.has_more() {
+            return None;
+        }
+        *byte = reader.read_u8();
+    }
+    return Some(session_token);
+}
+
+fn write_flags(compression: bool, message_tracing: bool) -> u8 {
+    let mut flags = 0;
+    if compression {
+        flags |= COMPRESSION_FLAG;
+    }
+    if message_tracing {
+        flags |= MESSAGE_TRACING_FLAG;
+    }
+    return flags;
+}
+
+// unknown bits are left for capabilities added later, & ignored
+fn read_flags(flags: u8) -> (bool, bool) {
+    return (
+        flags & COMPRESSION_FLAG != 0,
+        flags & MESSAGE_TRACING_FLAG != 0,
+    );
+}
+
+fn write_session_token(session_token: Option<&SessionToken>, payload_bytes: &mut Vec<u8>) {
+    match session_token {
+        Some(session_token) => {
+            payload_bytes.write_u8(1).unwrap(); // write token flag
+            payload_bytes.extend_from_slice(session_token); // write token
+        }
+        None => {
+            payload_bytes.write_u8(0).unwrap(); // write token flag
+        }
+    }
+}
+
+/// Writes the payload of a ServerRejectResponse, which is the reason the
+/// connection was rejected
+pub fn write_reject_response(reason: RejectReason) -> Vec<u8> {
+    return vec![reason as u8];
+}
+
+/// Reads the payload of a ServerRejectResponse, as written by
+/// `write_reject_response()`. An empty payload, or an unrecognized reason, is
+/// read as `RejectReason::Unknown`
+pub fn read_reject_response(reader: &mut PacketReader) -> RejectReason {
+    if !reader.has_more() {
+        return RejectReason::Unknown;
+    }
+    return reader.read_u8().into();
+}
+
+/// Writes the payload of a ServerDisconnect, which is the reason given for
+/// closing the connection, truncated to 255 bytes
+pub fn write_disconnect(reason: &str) -> Vec<u8> {
+    let reason_bytes = reason.as_bytes();
+    let reason_length = reason_bytes.len().min(u8::MAX as usize);
+    let mut payload_bytes = Vec::new();
+    payload_bytes.write_u8(reason_length as u8).unwrap(); // write reason length
+    payload_bytes.extend_from_slice(&reason_bytes[..reason_length]); // write reason
+    return payload_bytes;
+}
+
+/// Reads the payload of a ServerDisconnect, as written by `write_disconnect()`.
+/// A reason cut short is read as far as it goes, and invalid UTF-8 is
+/// replaced
+pub fn read_disconnect(reader: &mut PacketReader) -> String {
+    if !reader.has_more() {
+        return String::new();
+    }
+    let reason_length = (reader.read_u8() as usize).min(remaining(reader));
+    let reason_bytes = read_bytes(reader, reason_length);
+    return String::from_utf8_lossy(&reason_bytes).to_string();
+}
+
+// the number of bytes left to read
+fn remaining(reader: &mut PacketReader) -> usize {
+    let length = reader.get_buffer().len();
+    return length.saturating_sub(reader.get_cursor().position() as usize);
+}
+
+// reads the given number of bytes, which the caller has checked are there
+fn read_bytes(reader: &mut PacketReader, count: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(count);
+    for _ in 0..count {
+        bytes.push(reader.read_u8());
+    }
+    return bytes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        read_challenge_request, read_challenge_response, read_connect_request, read_disconnect,
+        write_challenge_response, write_connect_request, write_disconnect, EarlyData, SessionToken,
+        SESSION_TOKEN_BYTES, TIMESTAMP_DIGEST_BYTES,
+    };
+    use crate::{Clock, PacketReader, SystemClock};
+
+    #[test]
+    fn cut_short_payloads_are_not_read() {
+        let timestamp = SystemClock.timestamp();
+        let digest = [7; TIMESTAMP_DIGEST_BYTES];
+        let challenge_response = write_challenge_response(3, &timestamp, &digest);
+        let connect_request =
+            write_connect_request(&timestamp, &digest, true, false, None, &[], None);
+
+        assert!(read_challenge_request(&mut PacketReader::new(&[0; 7])).is_none());
+        let short = &challenge_response[..challenge_response.len() - 1];
+        assert!(read_challenge_response(&mut PacketReader::new(short)).is_none());
+        let read = read_challenge_response(&mut PacketReader::new(&challenge_response)).unwrap();
+        assert_eq!(read.timestamp, timestamp);
+        assert_eq!(read.timestamp_digest, digest);
+        let short = &connect_request[..TIMESTAMP_DIGEST_BYTES + 8];
+        assert!(read_connect_request(&mut PacketReader::new(short)).is_none());
+
+        let disconnect = write_disconnect("restarting");
+        assert_eq!(
+            read_disconnect(&mut PacketReader::new(&disconnect[..4])),
+            "res"
+        );
+        assert_eq!(read_disconnect(&mut PacketReader::new(&[])), "");
+    }
+
+    #[test]
+    fn long_disconnect_reasons_are_truncated() {
+        let reason = "x".repeat(300);
+        let disconnect = write_disconnect(&reason);
+        assert_eq!(disconnect.len(), 256);
+        assert_eq!(
+            read_disconnect(&mut PacketReader::new(&disconnect)),
+            reason[..255]
+        );
+
+        // a character cut in two by the truncation is replaced
+        let reason = format!("{}é", "x".repeat(254));
+        let read = read_disconnect(&mut PacketReader::new(&write_disconnect(&reason)));
+        assert_eq!(read, format!("{}\u{fffd}", "x".repeat(254)));
+    }
+
+    #[test]
+    fn early_data_is_read_along_with_the_session_token() {
+        let timestamp = SystemClock.timestamp();
+        let digest = [7; TIMESTAMP_DIGEST_BYTES];
+        let session_token: SessionToken = [9; SESSION_TOKEN_BYTES];
+        let early_data = vec![
+            EarlyData {
+                client_tick: 40,
+                perceived_tick: 31,
+                payload: vec![1, 2, 3],
+            },
+            EarlyData {
+                client_tick: 41,
+                perceived_tick: 32,
+                payload: Vec::new(),
+            },
+        ];
+
+        let resuming = write_connect_request(
+            &timestamp,
+            &digest,
+            false,
+            false,
+            Some((&session_token, &early_data)),
+            &[0xaa],
+            None,
+        );
+        let mut reader = PacketReader::new(&resuming);
+        let request = read_connect_request(&mut reader).unwrap();
+        assert_eq!(request.session_token, Some(session_token));
+        assert_eq!(request.early_data, early_data);
+        // the reader is left at the schema versions
+        assert_eq!(reader.read_u8(), 0xaa);
+
+        // early data which has been cut short fails the whole request
+        let short = &resuming[..resuming.len() - 2];
+        assert!(read_connect_request(&mut PacketReader::new(short)).is_none());
+
+        // a request which resumes nothing has no early data to release
+        let fresh = write_connect_request(&timestamp, &digest, false, false, None, &[], None);
+        let request = read_connect_request(&mut PacketReader::new(&fresh)).unwrap();
+        assert!(request.early_data.is_empty());
+    }
+}
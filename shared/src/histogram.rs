@@ -0,0 +1,104 @@
+use alloc::string::String;
+
+/// The number of buckets in every Histogram
+pub const HISTOGRAM_BUCKETS: usize = 8;
+
+// The characters a sparkline is drawn with, from an empty bucket to the
+// fullest one
+const SPARKLINE_LEVELS: [char; 8] = [' ', '.', ':', '-', '=', '+', '*', '#'];
+
+/// Counts how many recorded values fall into each of a fixed set of buckets,
+/// without allocating. Each bucket holds the values up to & including its
+/// upper bound, and above the bound of the bucket before it. The last bucket
+/// holds every value above the last bound
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    upper_bounds: [u32; HISTOGRAM_BUCKETS - 1],
+    counts: [u64; HISTOGRAM_BUCKETS],
+}
+
+impl Histogram {
+    /// Create a new Histogram, given the upper bounds of every bucket but the
+    /// last, in ascending order
+    pub fn new(upper_bounds: [u32; HISTOGRAM_BUCKETS - 1]) -> Self {
+        Histogram {
+            upper_bounds,
+            counts: [0; HISTOGRAM_BUCKETS],
+        }
+    }
+
+    /// Records a value into the bucket it falls into
+    pub fn record(&mut self, value: u32) {
+        let index = self
+            .upper_bounds
+            .iter()
+            .position(|upper_bound| value <= *upper_bound)
+            .unwrap_or(HISTOGRAM_BUCKETS - 1);
+        self.counts[index] = self.counts[index].saturating_add(1);
+    }
+
+    /// Gets the upper bounds of every bucket but the last, which is unbounded
+    pub fn get_upper_bounds(&self) -> &[u32; HISTOGRAM_BUCKETS - 1] {
+        return &self.upper_bounds;
+    }
+
+    /// Gets the number of values recorded into each bucket
+    pub fn get_counts(&self) -> &[u64; HISTOGRAM_BUCKETS] {
+        return &self.counts;
+    }
+
+    /// Gets the total number of values recorded
+    pub fn get_total(&self) -> u64 {
+        return self.counts.iter().sum();
+    }
+
+    /// Formats the distribution as a compact line of characters, one per
+    /// bucket, for logging. Each bucket is drawn relative to the fullest one,
+    /// and only empty buckets are drawn blank
+    pub fn sparkline(&self) -> String {
+        let max_count = self.counts.iter().copied().max().unwrap_or(0);
+        let top_level = (SPARKLINE_LEVELS.len() - 1) as u64;
+        return self
+            .counts
+            .iter()
+            .map(|count| {
+                if *count == 0 {
+                    return SPARKLINE_LEVELS[0];
+                }
+                // round up, so that every non-empty bucket is visible
+                let level = (count * top_level + max_count - 1) / max_count;
+                return SPARKLINE_LEVELS[level as usize];
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Histogram;
+
+    #[test]
+    fn values_land_in_bucket_up_to_bound() {
+        let mut histogram = Histogram::new([10, 20, 30, 40, 50, 60, 70]);
+        for value in [0, 10, 11, 20, 35, 70, 71, 1000].iter() {
+            histogram.record(*value);
+        }
+        assert_eq!(histogram.get_counts(), &[2, 2, 0, 1, 0, 0, 1, 2]);
+        assert_eq!(histogram.get_total(), 8);
+    }
+
+    #[test]
+    fn sparkline_scales_to_fullest_bucket() {
+        let mut histogram = Histogram::new([10, 20, 30, 40, 50, 60, 70]);
+        assert_eq!(histogram.sparkline(), "        ");
+
+        for _ in 0..70 {
+            histogram.record(5);
+        }
+        for _ in 0..35 {
+            histogram.record(15);
+        }
+        histogram.record(100);
+        assert_eq!(histogram.sparkline(), "#=     .");
+    }
+}
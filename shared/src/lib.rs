@@ -1,6 +1,35 @@
 //! # Naia Shared
 //! Common functionality shared between naia-server & naia-client crates.
+//!
+//! With the default "std" feature disabled, and the "alloc" feature enabled,
+//! only the protocol primitives are built, with `#![no_std]`: the
+//! SequenceBuffer, wrapping sequence number comparisons & arithmetic, the
+//! PacketType & StandardHeader, the StateMask, varints, and payload
+//! compression.
+//!
+//! Heavier subsystems are optional, and their types & methods are left out
+//! entirely when disabled: "compression" for payload compression,
+//! "diagnostics" for per-connection histograms, and "link-conditioner", on by
+//! default, for simulating network conditions.
+//!
+//! Fixed-width integers are written big endian, through `byte_order`, unless
+//! the "wire-little-endian" feature is enabled, for tooling which expects
+//! little endian. Both hosts must be built with the same order.
+//!
+//! The "conformance" feature generates & verifies the fixtures in the
+//! repository's `conformance/` directory, which specify the wire format for
+//! other implementations of the protocol. The types registered in a Manifest
+//! can be exported alongside them, with `Manifest::export_schema()`.
+//!
+//! The "message-tracing" feature logs each Event's life under a trace id, on
+//! both hosts when both are built with it, as set out in `message_tracing`.
+//!
+//! The "strict-checks" feature, on by default, asserts invariants of the
+//! protocol at runtime in debug builds, such as Events never being
+//! acknowledged twice, so that a violation fails loudly where it happens.
+//! Release builds never check them.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(
     missing_docs,
     trivial_casts,
@@ -10,55 +39,202 @@
     unused_import_braces
 )]
 
+#[cfg(not(feature = "alloc"))]
+compile_error!("Naia Shared requires either the 'std' or 'alloc' feature to be enabled");
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 mod ack_manager;
 mod actors;
+#[cfg(feature = "std")]
+mod clock;
+#[cfg(feature = "std")]
 mod connection;
+#[cfg(feature = "std")]
 mod connection_config;
+#[cfg(feature = "std")]
+mod connection_info;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
 mod events;
+#[cfg(feature = "diagnostics")]
+mod histogram;
+#[cfg(feature = "std")]
 mod host_tick_manager;
+#[cfg(feature = "std")]
 mod host_type;
+#[cfg(feature = "std")]
 mod manager_type;
+#[cfg(feature = "std")]
 mod manifest;
+#[cfg(feature = "std")]
+mod packet_hook;
+mod packet_padding;
 mod packet_type;
+#[cfg(feature = "std")]
+mod ping_manager;
+#[cfg(feature = "std")]
+mod protocol_plugin;
+#[cfg(feature = "std")]
+mod protocol_schema;
+#[cfg(feature = "std")]
+mod reject_reason;
+#[cfg(feature = "std")]
+mod schema_versions;
 mod sequence_buffer;
+#[cfg(feature = "std")]
 mod shared_config;
 mod standard_header;
+mod strict_checks;
+#[cfg(feature = "std")]
+mod sub_tick;
+#[cfg(feature = "std")]
+mod timer;
+mod wire_format;
 mod wrapping_number;
 
 /// Commonly used utility methods to be used by naia-server & naia-client
+#[cfg(feature = "std")]
 pub mod utils;
 
-pub use naia_socket_shared::{
-    find_my_ip_address, Instant, LinkConditionerConfig, PacketReader, Random, Timer, Timestamp,
-};
+/// The byte order of every fixed-width integer in the wire format, from the
+/// fields of the StandardHeader to the timestamps & ticks in the handshake, &
+/// helpers which read & write them in it. It's big endian, unless the
+/// "wire-little-endian" feature is enabled, & both hosts must agree on it
+pub mod byte_order;
+
+/// Readers & writers for the payloads of the packets sent while a connection
+/// is being established or closed
+#[cfg(feature = "std")]
+pub mod handshake;
+
+/// Readers & writers for the payloads of the packets which tell a Client of
+/// the Event types a running Server has registered since it was started, and
+/// which of them the Client has registered too
+#[cfg(feature = "std")]
+pub mod manifest_extension;
+
+/// Conformance fixtures, which pin the bytes of each wire construct to the
+/// fields they decode to, so that another implementation of the protocol can
+/// be checked against this one, in either direction. Enabled with the
+/// "conformance" feature
+#[cfg(feature = "conformance")]
+pub mod conformance;
+
+/// Wrapping arithmetic on sequence numbers, which finds the distance between
+/// them, advances them, and orders them correctly across the point where they
+/// wrap around
+pub mod sequence;
 
-pub use ack_manager::AckManager;
+/// Optional compression of packet payloads, enabled with the "compression"
+/// feature
+pub mod compression;
+
+/// Tracing of Events from being queued to being delivered, across both hosts,
+/// enabled with the "message-tracing" feature
+#[cfg(feature = "std")]
+pub mod message_tracing;
+
+/// Variable length encoding of integers, in which small values take up fewer
+/// bytes, used for the lengths & ids written ahead of Events, and the lengths
+/// of Actor updates
+pub mod varint;
+
+/// Fixtures shared by the tests of naia-shared, naia-server & naia-client.
+/// Enabled with the "test-support" feature, which is only meant for their
+/// dev-dependencies, and needs the "std" feature, as the fixtures do
+#[cfg(all(feature = "std", any(test, feature = "test-support")))]
+pub mod test_support;
+
+#[cfg(feature = "link-conditioner")]
+pub use naia_socket_shared::LinkConditionerConfig;
+#[cfg(feature = "std")]
+pub use naia_socket_shared::{find_my_ip_address, Instant, PacketReader, Random, Timestamp};
+
+#[cfg(feature = "std")]
+pub use ack_manager::{get_acked_packet_indices, AckManager, DeliveryBySize};
+pub use actors::state_mask::StateMask;
+#[cfg(feature = "std")]
 pub use actors::{
     actor::{Actor, ActorEq},
     actor_builder::ActorBuilder,
     actor_mutator::ActorMutator,
     actor_notifiable::ActorNotifiable,
+    actor_ref::ActorRef,
     actor_type::ActorType,
-    interp_lerp::interp_lerp,
-    local_actor_key::LocalActorKey,
+    archetype::{Archetype, ArchetypeId},
+    despawn_reason::DespawnReason,
+    interp_lerp::{interp_lerp, InterpLerpable},
+    local_actor_key::{generation_greater_than, ActorGeneration, LocalActorKey},
+    prediction_id::PredictionId,
     property::Property,
-    state_mask::StateMask,
 };
+#[cfg(feature = "std")]
+pub use clock::{Clock, ManualClock, SystemClock};
+#[cfg(feature = "std")]
 pub use connection::Connection;
+#[cfg(feature = "std")]
 pub use connection_config::ConnectionConfig;
+#[cfg(feature = "std")]
+pub use connection_info::ConnectionInfo;
+#[cfg(feature = "std")]
+pub use error::{ManifestError, MergeError, NaiaError};
+#[cfg(feature = "std")]
 pub use events::{
     event::{Event, EventClone},
     event_builder::EventBuilder,
+    event_frame::{read_event_frame, write_event_frame},
+    event_group::EventGroup,
     event_manager::EventManager,
-    event_packet_writer::{EventPacketWriter, MTU_SIZE},
+    event_packet_writer::{EventPacketWriter, MAX_EVENT_PAYLOAD_SIZE, MTU_SIZE},
+    event_stamp::EventStamp,
     event_type::EventType,
+    message_receipt::MessageReceipt,
+    ordered_channel::ChannelIndex,
+    outgoing_event::OutgoingEvent,
+    raw_event::RawEvent,
+    skipped_frame::SkippedFrame,
+    tick_payload_frame::{
+        read_tick_payload_frame, write_tick_payload_frame, TickPayloadFrame,
+        TICK_PAYLOAD_MAX_OVERHEAD,
+    },
 };
+#[cfg(feature = "diagnostics")]
+pub use histogram::{Histogram, HISTOGRAM_BUCKETS};
+#[cfg(feature = "std")]
 pub use host_tick_manager::HostTickManager;
+#[cfg(feature = "std")]
 pub use host_type::HostType;
+#[cfg(feature = "std")]
 pub use manager_type::ManagerType;
+#[cfg(feature = "std")]
 pub use manifest::Manifest;
+#[cfg(feature = "std")]
+pub use packet_hook::{HookAction, PacketHook};
+pub use packet_padding::{PacketPadding, PADDED_FLAG};
 pub use packet_type::PacketType;
-pub use sequence_buffer::{SequenceBuffer, SequenceIterator, SequenceNumber};
+#[cfg(feature = "std")]
+pub use ping_manager::PingManager;
+#[cfg(feature = "std")]
+pub use protocol_plugin::ProtocolPlugin;
+#[cfg(feature = "std")]
+pub use protocol_schema::{
+    ActorSchema, ArchetypeSchema, EventSchema, PropertySchema, ProtocolSchema, TypeDescription,
+};
+#[cfg(feature = "std")]
+pub use reject_reason::RejectReason;
+#[cfg(feature = "std")]
+pub use schema_versions::{AdvertisedVersions, SchemaVersions};
+pub use sequence_buffer::{SequenceBuffer, SequenceInsertError, SequenceIterator, SequenceNumber};
+#[cfg(feature = "std")]
 pub use shared_config::SharedConfig;
-pub use standard_header::StandardHeader;
+pub use standard_header::{StandardHeader, MAX_PACKET_SIZE};
+pub use strict_checks::STRICT_CHECKS;
+#[cfg(feature = "std")]
+pub use sub_tick::{SubTick, SUB_TICK_BITS};
+#[cfg(feature = "std")]
+pub use timer::Timer;
+pub use wire_format::PROTOCOL_VERSION;
 pub use wrapping_number::{sequence_greater_than, sequence_less_than, wrapping_diff};
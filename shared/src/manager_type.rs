@@ -10,6 +10,8 @@ pub enum ManagerType {
     Actor = 2,
     /// An CommandManager
     Command = 3,
+    /// The payload the Server set for a tick
+    TickPayload = 4,
     /// Unknown Manager
     Unknown = 255,
 }
@@ -20,6 +22,7 @@ impl From<u8> for ManagerType {
             1 => return ManagerType::Event,
             2 => return ManagerType::Actor,
             3 => return ManagerType::Command,
+            4 => return ManagerType::TickPayload,
             _ => return ManagerType::Unknown,
         };
     }
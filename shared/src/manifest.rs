@@ -1,24 +1,63 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    mem,
+    time::Duration,
+};
 
 use crate::{
-    actors::{actor_builder::ActorBuilder, actor_type::ActorType},
-    events::{event_builder::EventBuilder, event_type::EventType},
+    actors::{
+        actor::Actor,
+        actor_builder::ActorBuilder,
+        actor_type::ActorType,
+        archetype::{Archetype, ArchetypeId},
+        state_mask::StateMask,
+    },
+    error::{ManifestError, MergeError, NaiaError},
+    events::{
+        event::Event, event_builder::EventBuilder, event_type::EventType,
+        ordered_channel::ChannelIndex,
+    },
+    protocol_plugin::ProtocolPlugin,
+    protocol_schema::{ActorSchema, ArchetypeSchema, EventSchema, ProtocolSchema, TypeDescription},
+    schema_versions::SchemaVersions,
     PacketReader,
 };
 
+// The most Event types which can be registered. NaiaIds are written as u16
+// varints, shifted up by the bit which marks an Event sent on an ordered
+// channel
+const MAX_EVENT_TYPES: u32 = 1 << 15;
+
+// The most Actor types which can be registered, as NaiaIds are written as u16s
+const MAX_ACTOR_TYPES: u32 = u16::MAX as u32 + 1;
+
+// The most Archetypes which can be registered, as ArchetypeIds are written as
+// u16 varints
+const MAX_ARCHETYPES: u32 = u16::MAX as u32 + 1;
+
 /// Contains the shared protocol between Client & Server, with a data that is
 /// able to map Event/Actor TypeIds to their representation within specified
 /// enums. Also is able to create new Event/Actors using registered Builders,
 /// given a specific TypeId.
 #[derive(Debug)]
 pub struct Manifest<T: EventType, U: ActorType> {
-    event_naia_id_count: u16,
+    event_naia_id_count: u32,
     event_builder_map: HashMap<u16, Box<dyn EventBuilder<T>>>,
     event_type_map: HashMap<TypeId, u16>,
+    event_channel_map: HashMap<TypeId, ChannelIndex>,
+    event_raw_receive_set: HashSet<u16>,
+    event_expiry_map: HashMap<TypeId, Duration>,
+    event_timestamp_set: HashSet<u16>,
+    event_actor_ordered_set: HashSet<TypeId>,
+    event_actor_scoped_set: HashSet<u16>,
+    command_sub_ticks: bool,
     ////
-    actor_naia_id_count: u16,
+    actor_naia_id_count: u32,
     actor_builder_map: HashMap<u16, Box<dyn ActorBuilder<U>>>,
     actor_type_map: HashMap<TypeId, u16>,
+    archetypes: Vec<Archetype<U>>,
+    deterministic_ids: bool,
 }
 
 impl<T: EventType, U: ActorType> Manifest<T, U> {
@@ -28,77 +67,644 @@ impl<T: EventType, U: ActorType> Manifest<T, U> {
             event_naia_id_count: 0,
             event_builder_map: HashMap::new(),
             event_type_map: HashMap::new(),
+            event_channel_map: HashMap::new(),
+            event_raw_receive_set: HashSet::new(),
+            event_expiry_map: HashMap::new(),
+            event_timestamp_set: HashSet::new(),
+            event_actor_ordered_set: HashSet::new(),
+            event_actor_scoped_set: HashSet::new(),
+            command_sub_ticks: false,
             ///
             actor_naia_id_count: 0,
             actor_builder_map: HashMap::new(),
             actor_type_map: HashMap::new(),
+            archetypes: Vec::new(),
+            deterministic_ids: false,
         }
     }
 
-    /// Register an EventBuilder to handle the creation of Event instances
+    /// Register an EventBuilder to handle the creation of Event instances.
+    /// Panics if every NaiaId an Event can be given is already in use
     pub fn register_event(&mut self, event_builder: Box<dyn EventBuilder<T>>) {
-        let new_naia_id = self.event_naia_id_count;
+        let new_naia_id = next_naia_id(&mut self.event_naia_id_count, MAX_EVENT_TYPES, "Event");
         let type_id = event_builder.get_type_id();
         self.event_type_map.insert(type_id, new_naia_id);
         self.event_builder_map.insert(new_naia_id, event_builder);
-        self.event_naia_id_count += 1;
+    }
+
+    /// Register an EventBuilder to handle the creation of Event instances, where
+    /// Events of this type will always be sent on the given ordered channel
+    pub fn register_event_on_channel(
+        &mut self,
+        event_builder: Box<dyn EventBuilder<T>>,
+        channel_index: ChannelIndex,
+    ) {
+        self.event_channel_map
+            .insert(event_builder.get_type_id(), channel_index);
+        self.register_event(event_builder);
+    }
+
+    /// Register an EventBuilder to handle the creation of Event instances.
+    /// Every Event is now written into packets along with its size, which lets
+    /// a host which receives them as RawEvents, such as a relay, take their
+    /// bytes without reading them, so this is the same as `register_event()`
+    pub fn register_passthrough_event(&mut self, event_builder: Box<dyn EventBuilder<T>>) {
+        self.register_event(event_builder);
+    }
+
+    /// Sets Events of the given type to be received as RawEvents, holding the
+    /// bytes they were written as, rather than being read into the EventType.
+    /// Returns an Error if the Event type has not been registered. Only Event
+    /// types can be given, anything else, such as an Actor, doesn't compile:
+    ///
+    /// ```compile_fail
+    /// # use naia_shared::{ActorType, EventType, Manifest};
+    /// struct NotAnEvent;
+    /// fn receive_raw<T: EventType, U: ActorType>(manifest: &mut Manifest<T, U>) {
+    ///     manifest.set_event_receive_raw::<NotAnEvent>().unwrap();
+    /// }
+    /// ```
+    pub fn set_event_receive_raw<E: Event<T> + 'static>(&mut self) -> Result<(), NaiaError> {
+        let naia_id = self.get_event_naia_id(&TypeId::of::<E>())?;
+        self.event_raw_receive_set.insert(naia_id);
+        return Ok(());
+    }
+
+    /// Given an Event's NaiaId, returns whether Events of that type are
+    /// received as RawEvents
+    pub fn is_event_received_raw(&self, naia_id: u16) -> bool {
+        return self.event_raw_receive_set.contains(&naia_id);
+    }
+
+    /// Sets how long Events of the given type may wait in the outgoing queue
+    /// before they are dropped instead of sent, if they are not re-transmitted
+    /// when lost. Guaranteed Events, and Events sent on an ordered channel,
+    /// never expire. Returns an Error if the Event type has not been
+    /// registered. Only Event types can be given:
+    ///
+    /// ```compile_fail
+    /// # use std::time::Duration;
+    /// # use naia_shared::{ActorType, EventType, Manifest};
+    /// struct NotAnEvent;
+    /// fn expire<T: EventType, U: ActorType>(manifest: &mut Manifest<T, U>) {
+    ///     manifest.set_event_expiry::<NotAnEvent>(Duration::from_millis(100)).unwrap();
+    /// }
+    /// ```
+    pub fn set_event_expiry<E: Event<T> + 'static>(
+        &mut self,
+        expiry: Duration,
+    ) -> Result<(), NaiaError> {
+        let type_id = TypeId::of::<E>();
+        self.get_event_naia_id(&type_id)?;
+        self.event_expiry_map.insert(type_id, expiry);
+        return Ok(());
+    }
+
+    /// Given an Event's TypeId, get how long Events of that type may wait in
+    /// the outgoing queue before they are dropped, if they are not
+    /// re-transmitted when lost
+    pub fn get_event_expiry(&self, type_id: &TypeId) -> Option<Duration> {
+        return self.event_expiry_map.get(type_id).copied();
+    }
+
+    /// Sets Events of the given type to be written along with how long they
+    /// waited to be sent, so that the receiver can estimate how long ago they
+    /// were sent, as a MessageReceipt. This costs a byte or two on every
+    /// Event of the type, Events of other types are written as they always
+    /// are. Must be set on both hosts alike. Returns an Error if the Event
+    /// type has not been registered. Only Event types can be given:
+    ///
+    /// ```compile_fail
+    /// # use naia_shared::{ActorType, EventType, Manifest};
+    /// struct NotAnEvent;
+    /// fn timestamp<T: EventType, U: ActorType>(manifest: &mut Manifest<T, U>) {
+    ///     manifest.set_event_timestamped::<NotAnEvent>().unwrap();
+    /// }
+    /// ```
+    pub fn set_event_timestamped<E: Event<T> + 'static>(&mut self) -> Result<(), NaiaError> {
+        let naia_id = self.get_event_naia_id(&TypeId::of::<E>())?;
+        self.event_timestamp_set.insert(naia_id);
+        return Ok(());
+    }
+
+    /// Given an Event's NaiaId, returns whether Events of that type are written
+    /// along with how long they waited to be sent
+    pub fn is_event_timestamped(&self, naia_id: u16) -> bool {
+        return self.event_timestamp_set.contains(&naia_id);
+    }
+
+    /// Sets Events of the given type to be held by the receiver until every
+    /// Actor they reference with an ActorRef Property has come into scope, so
+    /// that an Event sent along with the spawn of an Actor it references is
+    /// never handed over before the Actor. Events held past the receiver's
+    /// timeout are handed over anyway, flagged as unresolved. Only the
+    /// receiver needs this set, and only Clients hold Events. Returns an
+    /// Error if the Event type has not been registered. Only Event types can
+    /// be given:
+    ///
+    /// ```compile_fail
+    /// # use naia_shared::{ActorType, EventType, Manifest};
+    /// struct NotAnEvent;
+    /// fn order<T: EventType, U: ActorType>(manifest: &mut Manifest<T, U>) {
+    ///     manifest.set_event_actor_ordered::<NotAnEvent>().unwrap();
+    /// }
+    /// ```
+    pub fn set_event_actor_ordered<E: Event<T> + 'static>(&mut self) -> Result<(), NaiaError> {
+        let type_id = TypeId::of::<E>();
+        self.get_event_naia_id(&type_id)?;
+        self.event_actor_ordered_set.insert(type_id);
+        return Ok(());
+    }
+
+    /// Given an Event's TypeId, returns whether Events of that type are held
+    /// until the Actors they reference have come into scope
+    pub fn is_event_actor_ordered(&self, type_id: &TypeId) -> bool {
+        return self.event_actor_ordered_set.contains(type_id);
+    }
+
+    /// Sets Events of the given type to be sent about an Actor, with
+    /// `NaiaServer::queue_actor_event()`, only to the Clients which have the
+    /// Actor in scope, which receive it along with the Actor's LocalActorKey.
+    /// This costs a byte or two on every Event of the type, Events of other
+    /// types are written as they always are. Must be set on both hosts alike.
+    /// Returns an Error if the Event type has not been registered. Only Event
+    /// types can be given:
+    ///
+    /// ```compile_fail
+    /// # use naia_shared::{ActorType, EventType, Manifest};
+    /// struct NotAnEvent;
+    /// fn scope<T: EventType, U: ActorType>(manifest: &mut Manifest<T, U>) {
+    ///     manifest.set_event_actor_scoped::<NotAnEvent>().unwrap();
+    /// }
+    /// ```
+    pub fn set_event_actor_scoped<E: Event<T> + 'static>(&mut self) -> Result<(), NaiaError> {
+        let naia_id = self.get_event_naia_id(&TypeId::of::<E>())?;
+        self.event_actor_scoped_set.insert(naia_id);
+        return Ok(());
+    }
+
+    /// Given an Event's NaiaId, returns whether Events of that type are written
+    /// along with the Actor they're about
+    pub fn is_event_actor_scoped(&self, naia_id: u16) -> bool {
+        return self.event_actor_scoped_set.contains(&naia_id);
+    }
+
+    /// Sets whether every Command is written along with the SubTick it was
+    /// issued at, the fraction of the Client's tick which had passed, for the
+    /// Server to simulate & rewind to within a tick. This costs two bytes on
+    /// every Command, including past Commands sent along with it, so it's off
+    /// by default. Must be set on both hosts alike
+    pub fn set_command_sub_ticks(&mut self, command_sub_ticks: bool) {
+        self.command_sub_ticks = command_sub_ticks;
+    }
+
+    /// Returns whether every Command is written along with the SubTick it was
+    /// issued at
+    pub fn has_command_sub_ticks(&self) -> bool {
+        return self.command_sub_ticks;
+    }
+
+    /// Given an Event's NaiaId, get its TypeId. Returns an Error if no Event
+    /// type has been registered with the NaiaId
+    pub fn get_event_type_id(&self, naia_id: u16) -> Result<TypeId, NaiaError> {
+        match self.event_builder_map.get(&naia_id) {
+            Some(event_builder) => return Ok(event_builder.get_type_id()),
+            None => return Err(ManifestError::UnregisteredEvent.into()),
+        }
+    }
+
+    /// Given an Event's NaiaId, get the name its builder describes its type
+    /// with, as derived types' builders do. Returns None if no Event type has
+    /// been registered with the NaiaId, or its builder doesn't describe it
+    pub fn get_event_name(&self, naia_id: u16) -> Option<String> {
+        let event_builder = self.event_builder_map.get(&naia_id)?;
+        return described_name(event_builder.describe());
+    }
+
+    /// Given an Event's TypeId, get the ordered channel Events of that type are
+    /// sent on, if any
+    pub fn get_event_channel(&self, type_id: &TypeId) -> Option<ChannelIndex> {
+        return self.event_channel_map.get(type_id).copied();
     }
 
     /// Given an Event's TypeId, get a NaiaId (that can be written/read from
-    /// packets)
-    pub fn get_event_naia_id(&self, type_id: &TypeId) -> u16 {
-        let naia_id = self
-            .event_type_map
-            .get(type_id)
-            .expect("hey I should get a TypeId here...");
-        return *naia_id;
+    /// packets). Returns an Error if the Event type has not been registered
+    pub fn get_event_naia_id(&self, type_id: &TypeId) -> Result<u16, NaiaError> {
+        match self.event_type_map.get(type_id) {
+            Some(naia_id) => return Ok(*naia_id),
+            None => return Err(ManifestError::UnregisteredEvent.into()),
+        }
     }
 
     /// Creates an Event instance, given a NaiaId and a payload, typically from
-    /// an incoming packet
-    pub fn create_event(&self, naia_id: u16, reader: &mut PacketReader) -> Option<T> {
+    /// an incoming packet, written as the schema version of its type used on
+    /// the connection
+    pub fn create_event(
+        &self,
+        naia_id: u16,
+        reader: &mut PacketReader,
+        schema_versions: &SchemaVersions,
+    ) -> Option<T> {
         match self.event_builder_map.get(&naia_id) {
-            Some(event_builder) => {
-                return Some(event_builder.as_ref().build(reader));
-            }
+            Some(event_builder) => match schema_versions.get_event_version(naia_id) {
+                Some(version) => return Some(event_builder.build_versioned(reader, version)),
+                None => return Some(event_builder.build(reader)),
+            },
             None => {}
         }
 
         return None;
     }
 
-    /// Register an ActorBuilder to handle the creation of Actor instances
+    /// Register an ActorBuilder to handle the creation of Actor instances.
+    /// Panics if every NaiaId an Actor can be given is already in use
     pub fn register_actor(&mut self, actor_builder: Box<dyn ActorBuilder<U>>) {
-        let new_naia_id = self.actor_naia_id_count;
+        let new_naia_id = next_naia_id(&mut self.actor_naia_id_count, MAX_ACTOR_TYPES, "Actor");
         let type_id = actor_builder.get_type_id();
         self.actor_type_map.insert(type_id, new_naia_id);
         self.actor_builder_map.insert(new_naia_id, actor_builder);
-        self.actor_naia_id_count += 1;
     }
 
     /// Given an Actor's TypeId, get a NaiaId (that can be written/read from
-    /// packets)
-    pub fn get_actor_naia_id(&self, type_id: &TypeId) -> u16 {
-        let naia_id = self
-            .actor_type_map
-            .get(type_id)
-            .expect("hey I should get a TypeId here...");
-        return *naia_id;
+    /// packets). Returns an Error if the Actor type has not been registered
+    pub fn get_actor_naia_id(&self, type_id: &TypeId) -> Result<u16, NaiaError> {
+        match self.actor_type_map.get(type_id) {
+            Some(naia_id) => return Ok(*naia_id),
+            None => return Err(ManifestError::UnregisteredActor.into()),
+        }
     }
 
-    /// Creates an Event instance, given a NaiaId and a payload, typically from
-    /// an incoming packet
-    pub fn create_actor(&self, naia_id: u16, reader: &mut PacketReader) -> Option<U> {
+    /// Creates an Actor instance, given a NaiaId and a payload, typically from
+    /// an incoming packet, written as the schema version of its type used on
+    /// the connection
+    pub fn create_actor(
+        &self,
+        naia_id: u16,
+        reader: &mut PacketReader,
+        schema_versions: &SchemaVersions,
+    ) -> Option<U> {
         match self.actor_builder_map.get(&naia_id) {
-            Some(actor_builder) => {
-                return Some(actor_builder.as_ref().build(reader));
-            }
+            Some(actor_builder) => match schema_versions.get_actor_version(naia_id) {
+                Some(version) => return Some(actor_builder.build_versioned(reader, version)),
+                None => return Some(actor_builder.build(reader)),
+            },
             None => {}
         }
 
         return None;
     }
 
+    /// Registers an Archetype, a named baseline state of a registered Actor
+    /// type, returning the ArchetypeId it is written as. An Actor spawned
+    /// close to the baseline is written as only the Properties which differ
+    /// from it, to a remote host which has registered the same Archetype, in
+    /// the same order. Returns an Error if the Actor type has not been
+    /// registered. Panics if every ArchetypeId is already in use
+    pub fn register_archetype(
+        &mut self,
+        name: &str,
+        baseline: U,
+    ) -> Result<ArchetypeId, NaiaError> {
+        let type_id = baseline.inner_ref().as_ref().borrow().get_type_id();
+        let naia_id = self.get_actor_naia_id(&type_id)?;
+        let schema_version = match self.actor_builder_map.get(&naia_id) {
+            Some(actor_builder) => actor_builder.get_schema_version(),
+            None => return Err(ManifestError::UnregisteredActor.into()),
+        };
+        let mut count = self.archetypes.len() as u32;
+        let archetype_id = next_naia_id(&mut count, MAX_ARCHETYPES, "Archetype");
+        self.archetypes
+            .push(Archetype::new(name, naia_id, schema_version, baseline));
+        return Ok(archetype_id);
+    }
+
+    /// Given an Actor of the type with the given NaiaId, finds the Archetype
+    /// agreed on for the connection which it is written the smallest against,
+    /// returning its ArchetypeId, the mask of the Properties which differ from
+    /// its baseline, and those Properties written as a payload. Returns None
+    /// if no Archetype of the type is agreed on
+    pub fn get_closest_archetype(
+        &self,
+        naia_id: u16,
+        actor: &dyn Actor<U>,
+        schema_versions: &SchemaVersions,
+    ) -> Option<(ArchetypeId, StateMask, Vec<u8>)> {
+        let mut closest: Option<(ArchetypeId, StateMask, Vec<u8>)> = None;
+        for (archetype_id, archetype) in self.archetypes.iter().enumerate() {
+            let archetype_id = archetype_id as ArchetypeId;
+            if archetype.get_naia_id() != naia_id || !schema_versions.has_archetype(archetype_id) {
+                continue;
+            }
+            let diff_mask = archetype.diff(actor);
+            let mut payload = Vec::new();
+            actor.write_partial(&diff_mask, &mut payload);
+            let is_closer = match &closest {
+                Some((_, _, closest_payload)) => payload.len() < closest_payload.len(),
+                None => true,
+            };
+            if is_closer {
+                closest = Some((archetype_id, diff_mask, payload));
+            }
+        }
+        return closest;
+    }
+
+    /// Creates an Actor instance from the baseline of the Archetype with the
+    /// given ArchetypeId, with the Properties set in the given mask read from
+    /// the payload, typically from an incoming packet
+    pub fn create_archetype_actor(
+        &self,
+        archetype_id: ArchetypeId,
+        diff_mask: &StateMask,
+        reader: &mut PacketReader,
+    ) -> Option<U> {
+        return self
+            .archetypes
+            .get(usize::from(archetype_id))
+            .map(|archetype| archetype.build(diff_mask, reader));
+    }
+
+    /// Writes the schema version of every registered type past the first
+    /// version, to advertise them to the remote host in the handshake: the
+    /// number of Event types, followed by the NaiaId & version of each, then
+    /// the same for Actor types. Types left out are at the first version.
+    /// These are followed by the number of Archetypes, and the ArchetypeId &
+    /// digest of each
+    pub fn write_schema_versions(&self, out_bytes: &mut Vec<u8>) {
+        SchemaVersions::write_advertised(
+            &self.get_versioned_event_types(),
+            &self.get_versioned_actor_types(),
+            &self.get_archetype_digests(),
+            out_bytes,
+        );
+    }
+
+    /// Describes every registered type & how it's sent, along with every
+    /// Archetype, as a ProtocolSchema, for tooling such as packet dissectors.
+    /// The names & Properties of types are only given for those whose builders
+    /// describe them, as derived types' builders do
+    pub fn export_schema(&self) -> ProtocolSchema {
+        let mut events: Vec<EventSchema> = self
+            .event_builder_map
+            .iter()
+            .map(|(naia_id, builder)| {
+                let type_id = builder.get_type_id();
+                return EventSchema {
+                    naia_id: *naia_id,
+                    schema_version: builder.get_schema_version(),
+                    channel: self.get_event_channel(&type_id),
+                    expiry_millis: self
+                        .get_event_expiry(&type_id)
+                        .map(|expiry| expiry.as_millis() as u64),
+                    received_raw: self.is_event_received_raw(*naia_id),
+                    timestamped: self.is_event_timestamped(*naia_id),
+                    actor_ordered: self.is_event_actor_ordered(&type_id),
+                    actor_scoped: self.is_event_actor_scoped(*naia_id),
+                    description: builder.describe(),
+                };
+            })
+            .collect();
+        events.sort_by_key(|event| event.naia_id);
+
+        let mut actors: Vec<ActorSchema> = self
+            .actor_builder_map
+            .iter()
+            .map(|(naia_id, builder)| ActorSchema {
+                naia_id: *naia_id,
+                schema_version: builder.get_schema_version(),
+                description: builder.describe(),
+            })
+            .collect();
+        actors.sort_by_key(|actor| actor.naia_id);
+
+        let archetypes = self
+            .archetypes
+            .iter()
+            .enumerate()
+            .map(|(archetype_id, archetype)| ArchetypeSchema {
+                archetype_id: archetype_id as ArchetypeId,
+                name: archetype.get_name().to_string(),
+                naia_id: archetype.get_naia_id(),
+                digest: archetype.get_digest(),
+            })
+            .collect();
+
+        return ProtocolSchema::new(events, actors, archetypes, self.command_sub_ticks);
+    }
+
+    // the ArchetypeId & digest of every Archetype, in order of ArchetypeId
+    pub(crate) fn get_archetype_digests(&self) -> Vec<(ArchetypeId, u32)> {
+        return self
+            .archetypes
+            .iter()
+            .enumerate()
+            .map(|(archetype_id, archetype)| (archetype_id as ArchetypeId, archetype.get_digest()))
+            .collect();
+    }
+
+    // the NaiaId & schema version of every Event type past the first version,
+    // in order of NaiaId
+    pub(crate) fn get_versioned_event_types(&self) -> Vec<(u16, u16)> {
+        let mut versions: Vec<(u16, u16)> = self
+            .event_builder_map
+            .iter()
+            .map(|(naia_id, builder)| (*naia_id, builder.get_schema_version()))
+            .filter(|(_, version)| *version > 1)
+            .collect();
+        versions.sort();
+        return versions;
+    }
+
+    // the NaiaId & schema version of every Actor type past the first version,
+    // in order of NaiaId
+    pub(crate) fn get_versioned_actor_types(&self) -> Vec<(u16, u16)> {
+        let mut versions: Vec<(u16, u16)> = self
+            .actor_builder_map
+            .iter()
+            .map(|(naia_id, builder)| (*naia_id, builder.get_schema_version()))
+            .filter(|(_, version)| *version > 1)
+            .collect();
+        versions.sort();
+        return versions;
+    }
+
+    /// Sets whether every type is given its NaiaId in order of its name, and
+    /// every Archetype its ArchetypeId in order of its name, each time another
+    /// Manifest or a ProtocolPlugin is merged in, rather than in the order they
+    /// are registered. Hosts which compose a protocol from the same parts then
+    /// agree on every id, whatever order the parts are added in. Every type
+    /// must be described by its builder, as derived types are
+    pub fn set_deterministic_ids(&mut self, deterministic_ids: bool) {
+        self.deterministic_ids = deterministic_ids;
+    }
+
+    /// Registers the types of the given ProtocolPlugin, by merging a Manifest
+    /// holding only them into this one. Returns an Error if they conflict with
+    /// those already registered, as `merge()` does
+    pub fn add_plugin(&mut self, plugin: &dyn ProtocolPlugin<T, U>) -> Result<(), MergeError> {
+        let mut plugin_manifest = Manifest::new();
+        plugin.register(&mut plugin_manifest);
+        return self.merge(plugin_manifest);
+    }
+
+    /// Merges the types & Archetypes registered in another Manifest into this
+    /// one, along with how each type is sent. They are given the NaiaIds &
+    /// ArchetypeIds after those already in use, in the order they were given in
+    /// the other Manifest, or every id is given afresh in order of name, with
+    /// deterministic ids. Returns an Error, leaving this Manifest as it was, if
+    /// a type or Archetype name is registered in both, if there would be more
+    /// than can be told apart on the wire, or if ids can't be given in order
+    /// of name
+    pub fn merge(&mut self, mut other: Manifest<T, U>) -> Result<(), MergeError> {
+        for (type_id, naia_id) in other.event_type_map.iter() {
+            if self.event_type_map.contains_key(type_id) {
+                let name = described_name(other.event_builder_map[naia_id].describe());
+                return Err(MergeError::DuplicateEvent(name));
+            }
+        }
+        for (type_id, naia_id) in other.actor_type_map.iter() {
+            if self.actor_type_map.contains_key(type_id) {
+                let name = described_name(other.actor_builder_map[naia_id].describe());
+                return Err(MergeError::DuplicateActor(name));
+            }
+        }
+        for archetype in other.archetypes.iter() {
+            if self
+                .archetypes
+                .iter()
+                .any(|own_archetype| own_archetype.get_name() == archetype.get_name())
+            {
+                return Err(MergeError::DuplicateArchetype(
+                    archetype.get_name().to_string(),
+                ));
+            }
+        }
+        if self.event_naia_id_count + other.event_naia_id_count > MAX_EVENT_TYPES
+            || self.actor_naia_id_count + other.actor_naia_id_count > MAX_ACTOR_TYPES
+            || (self.archetypes.len() + other.archetypes.len()) as u32 > MAX_ARCHETYPES
+        {
+            return Err(MergeError::TooManyTypes);
+        }
+        if self.deterministic_ids {
+            check_names(
+                self.event_builder_map
+                    .values()
+                    .chain(other.event_builder_map.values())
+                    .map(|builder| described_name(builder.describe())),
+            )?;
+            check_names(
+                self.actor_builder_map
+                    .values()
+                    .chain(other.actor_builder_map.values())
+                    .map(|builder| described_name(builder.describe())),
+            )?;
+            check_names(
+                self.archetypes
+                    .iter()
+                    .chain(other.archetypes.iter())
+                    .map(|archetype| Some(archetype.get_name().to_string())),
+            )?;
+        }
+
+        // the other Manifest's ids follow on from this one's
+        let event_offset = self.event_naia_id_count as u16;
+        let event_ids: HashMap<u16, u16> = other
+            .event_builder_map
+            .keys()
+            .map(|naia_id| (*naia_id, *naia_id + event_offset))
+            .collect();
+        let actor_offset = self.actor_naia_id_count as u16;
+        let actor_ids: HashMap<u16, u16> = other
+            .actor_builder_map
+            .keys()
+            .map(|naia_id| (*naia_id, *naia_id + actor_offset))
+            .collect();
+        other.renumber(&event_ids, &actor_ids);
+
+        self.event_naia_id_count += other.event_naia_id_count;
+        self.event_builder_map.extend(other.event_builder_map);
+        self.event_type_map.extend(other.event_type_map);
+        self.event_channel_map.extend(other.event_channel_map);
+        self.event_raw_receive_set
+            .extend(other.event_raw_receive_set);
+        self.event_expiry_map.extend(other.event_expiry_map);
+        self.event_timestamp_set.extend(other.event_timestamp_set);
+        self.event_actor_ordered_set
+            .extend(other.event_actor_ordered_set);
+        self.event_actor_scoped_set
+            .extend(other.event_actor_scoped_set);
+        self.command_sub_ticks |= other.command_sub_ticks;
+        self.actor_naia_id_count += other.actor_naia_id_count;
+        self.actor_builder_map.extend(other.actor_builder_map);
+        self.actor_type_map.extend(other.actor_type_map);
+        self.archetypes.extend(other.archetypes);
+
+        if self.deterministic_ids {
+            self.renumber_by_name();
+        }
+        return Ok(());
+    }
+
+    // Gives every type its NaiaId, & every Archetype its ArchetypeId, in order
+    // of name. Every name has been checked to be described & unique
+    fn renumber_by_name(&mut self) {
+        let event_ids = ids_by_name(
+            self.event_builder_map
+                .iter()
+                .map(|(naia_id, builder)| (*naia_id, described_name(builder.describe()))),
+        );
+        let actor_ids = ids_by_name(
+            self.actor_builder_map
+                .iter()
+                .map(|(naia_id, builder)| (*naia_id, described_name(builder.describe()))),
+        );
+        self.renumber(&event_ids, &actor_ids);
+        self.archetypes
+            .sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    }
+
+    // Gives every type the NaiaId it's mapped to, rehashing every Archetype
+    // with the new NaiaId of its Actor type
+    fn renumber(&mut self, event_ids: &HashMap<u16, u16>, actor_ids: &HashMap<u16, u16>) {
+        self.event_builder_map = mem::take(&mut self.event_builder_map)
+            .into_iter()
+            .map(|(naia_id, builder)| (event_ids[&naia_id], builder))
+            .collect();
+        for naia_id in self.event_type_map.values_mut() {
+            *naia_id = event_ids[naia_id];
+        }
+        self.event_raw_receive_set = self
+            .event_raw_receive_set
+            .iter()
+            .map(|naia_id| event_ids[naia_id])
+            .collect();
+        self.event_timestamp_set = self
+            .event_timestamp_set
+            .iter()
+            .map(|naia_id| event_ids[naia_id])
+            .collect();
+        self.event_actor_scoped_set = self
+            .event_actor_scoped_set
+            .iter()
+            .map(|naia_id| event_ids[naia_id])
+            .collect();
+
+        self.actor_builder_map = mem::take(&mut self.actor_builder_map)
+            .into_iter()
+            .map(|(naia_id, builder)| (actor_ids[&naia_id], builder))
+            .collect();
+        for naia_id in self.actor_type_map.values_mut() {
+            *naia_id = actor_ids[naia_id];
+        }
+        let archetypes = mem::take(&mut self.archetypes);
+        for archetype in archetypes {
+            let naia_id = actor_ids[&archetype.get_naia_id()];
+            let schema_version = self.actor_builder_map[&naia_id].get_schema_version();
+            self.archetypes
+                .push(archetype.renumbered(naia_id, schema_version));
+        }
+    }
+
     /// Register both an ActorBuilder and an EventBuilder to handle the
     /// creation of both as a Pawn & Command, respectively. Pawns & Commands
     /// should be used for any player-controlled actor that requires clientside
@@ -112,3 +718,177 @@ impl<T: EventType, U: ActorType> Manifest<T, U> {
         self.register_event(event_builder);
     }
 }
+
+// Gets the name of a type, as described by its builder
+fn described_name(description: Option<TypeDescription>) -> Option<String> {
+    return description.map(|description| description.name);
+}
+
+// Checks that every name is given & that no two are the same, so that ids can
+// be given in order of name
+fn check_names(names: impl Iterator<Item = Option<String>>) -> Result<(), MergeError> {
+    let mut seen = HashSet::new();
+    for name in names {
+        match name {
+            Some(name) => {
+                if !seen.insert(name.clone()) {
+                    return Err(MergeError::DuplicateName(name));
+                }
+            }
+            None => return Err(MergeError::UnnamedType),
+        }
+    }
+    return Ok(());
+}
+
+// Maps each id to its position in order of the name it's given with
+fn ids_by_name(names: impl Iterator<Item = (u16, Option<String>)>) -> HashMap<u16, u16> {
+    let mut names: Vec<(u16, Option<String>)> = names.collect();
+    names.sort_by(|(_, a), (_, b)| a.cmp(b));
+    return names
+        .into_iter()
+        .enumerate()
+        .map(|(new_naia_id, (naia_id, _))| (naia_id, new_naia_id as u16))
+        .collect();
+}
+
+// Takes the next NaiaId from the given count, panicking rather than wrapping
+// around & handing out a NaiaId which is already in use once the limit is hit
+fn next_naia_id(count: &mut u32, limit: u32, kind: &str) -> u16 {
+    if *count >= limit {
+        panic!(
+            "Attempting to register more than {} {} types in the Manifest, which is as many \
+             as can be told apart on the wire",
+            limit, kind
+        );
+    }
+    let naia_id = *count as u16;
+    *count += 1;
+    return naia_id;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::TypeId, collections::HashSet};
+
+    use super::{next_naia_id, MAX_ACTOR_TYPES, MAX_EVENT_TYPES};
+    use crate::{
+        test_support::NoActors, wire_format::assert_golden, EventBuilder, EventType, Manifest,
+        PacketReader, SchemaVersions,
+    };
+
+    #[derive(Clone)]
+    struct NoEvents;
+
+    impl EventType for NoEvents {
+        fn write(&self, _buffer: &mut Vec<u8>) {}
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<NoEvents>();
+        }
+    }
+
+    struct NoEventsBuilder;
+
+    impl EventBuilder<NoEvents> for NoEventsBuilder {
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<NoEvents>();
+        }
+        fn build(&self, _reader: &mut PacketReader) -> NoEvents {
+            return NoEvents;
+        }
+    }
+
+    // builds Events of the given schema version
+    struct VersionedBuilder(u16);
+
+    impl EventBuilder<NoEvents> for VersionedBuilder {
+        fn get_type_id(&self) -> TypeId {
+            return TypeId::of::<NoEvents>();
+        }
+        fn build(&self, _reader: &mut PacketReader) -> NoEvents {
+            return NoEvents;
+        }
+        fn get_schema_version(&self) -> u16 {
+            return self.0;
+        }
+    }
+
+    fn versioned_manifest(versions: &[u16]) -> Manifest<NoEvents, NoActors> {
+        let mut manifest = Manifest::<NoEvents, NoActors>::new();
+        for version in versions.iter() {
+            manifest.register_event(Box::new(VersionedBuilder(*version)));
+        }
+        return manifest;
+    }
+
+    #[test]
+    fn schema_versions_match_golden() {
+        let mut versions = vec![1; 132];
+        versions[0] = 2;
+        versions[131] = 3;
+        let mut bytes = Vec::new();
+        versioned_manifest(&versions).write_schema_versions(&mut bytes);
+        assert_golden("schema_versions", &bytes);
+    }
+
+    #[test]
+    fn connection_uses_lower_schema_versions() {
+        let local_manifest = versioned_manifest(&[2, 3, 2, 1]);
+        let remote_manifest = versioned_manifest(&[1, 2, 5, 4]);
+        let mut bytes = Vec::new();
+        remote_manifest.write_schema_versions(&mut bytes);
+
+        let schema_versions =
+            SchemaVersions::read(&local_manifest, &mut PacketReader::new(&bytes)).unwrap();
+        // not advertised, as the remote host has the first version
+        assert_eq!(schema_versions.get_event_version(0), Some(1));
+        assert_eq!(schema_versions.get_event_version(1), Some(2));
+        // the remote host is the one with the older version of these, so they
+        // are written & read whole
+        assert_eq!(schema_versions.get_event_version(2), None);
+        assert_eq!(schema_versions.get_event_version(3), None);
+
+        // a host with the same versions writes & reads every type whole
+        let mut bytes = Vec::new();
+        local_manifest.write_schema_versions(&mut bytes);
+        let schema_versions =
+            SchemaVersions::read(&local_manifest, &mut PacketReader::new(&bytes)).unwrap();
+        assert_eq!(schema_versions, SchemaVersions::new());
+
+        assert!(
+            SchemaVersions::read(&local_manifest, &mut PacketReader::new(&bytes[..2])).is_err()
+        );
+    }
+
+    #[test]
+    fn naia_ids_are_unique_up_to_limit() {
+        let mut count = 0;
+        let mut naia_ids = HashSet::new();
+        for _ in 0..MAX_ACTOR_TYPES {
+            assert!(naia_ids.insert(next_naia_id(&mut count, MAX_ACTOR_TYPES, "Actor")));
+        }
+        assert_eq!(naia_ids.len(), 65536);
+        assert!(naia_ids.contains(&u16::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "more than 65536 Actor types")]
+    fn naia_ids_run_out_instead_of_wrapping() {
+        let mut count = MAX_ACTOR_TYPES - 1;
+        assert_eq!(next_naia_id(&mut count, MAX_ACTOR_TYPES, "Actor"), u16::MAX);
+        next_naia_id(&mut count, MAX_ACTOR_TYPES, "Actor");
+    }
+
+    #[test]
+    #[should_panic(expected = "more than 32768 Event types")]
+    fn event_naia_ids_stop_below_ordered_flag() {
+        let mut manifest = Manifest::<NoEvents, NoActors>::new();
+        for _ in 0..MAX_EVENT_TYPES {
+            manifest.register_event(Box::new(NoEventsBuilder));
+        }
+        // every NaiaId below the flag is in use, by a builder of its own
+        assert_eq!(manifest.event_builder_map.len(), 32768);
+        assert!(manifest.get_event_type_id(0x7fff).is_ok());
+        manifest.register_event(Box::new(NoEventsBuilder));
+    }
+}
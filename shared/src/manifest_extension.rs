@@ -0,0 +1,160 @@
+use crate::{
+    varint::{self, VarintError},
+    PacketReader,
+};
+
+/// The Event types a Server has registered since it was started, as sent to a
+/// Client in a ManifestExtension packet
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestExtension {
+    /// The number of Event types the Server has registered since it was
+    /// started, as of the last one sent
+    pub epoch: u16,
+    /// The NaiaId & name of each Event type sent, in the order they were
+    /// registered, ending with the last before the epoch
+    pub event_types: Vec<(u16, String)>,
+}
+
+/// A Client's answer to a ManifestExtension
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestExtensionAck {
+    /// The epoch of the ManifestExtension answered
+    pub epoch: u16,
+    /// The NaiaIds of the Event types sent which the Client has registered too
+    pub naia_ids: Vec<u16>,
+}
+
+/// Writes the payload of a ManifestExtension: its epoch & the number of Event
+/// types sent, as varints, then the NaiaId of each as a varint, followed by
+/// the length of its name as a u8 & the name. Names longer than 255 bytes are
+/// cut short
+pub fn write_manifest_extension(epoch: u16, event_types: &[(u16, String)]) -> Vec<u8> {
+    let mut payload_bytes = Vec::new();
+    varint::write_u16(epoch, &mut payload_bytes); // write epoch
+    varint::write_u16(event_types.len() as u16, &mut payload_bytes); // write type count
+    for (naia_id, name) in event_types {
+        varint::write_u16(*naia_id, &mut payload_bytes); // write naia id
+        let name_bytes = name.as_bytes();
+        let name_length = name_bytes.len().min(u8::MAX as usize);
+        payload_bytes.push(name_length as u8); // write name length
+        payload_bytes.extend_from_slice(&name_bytes[..name_length]); // write name
+    }
+    return payload_bytes;
+}
+
+/// Reads the payload of a ManifestExtension, as written by
+/// `write_manifest_extension()`. A payload cut short anywhere is an Error,
+/// and invalid UTF-8 in a name is replaced
+pub fn read_manifest_extension(
+    reader: &mut PacketReader,
+) -> Result<ManifestExtension, VarintError> {
+    let epoch = varint::read_from(reader, varint::read_u16)?;
+    let count = varint::read_from(reader, varint::read_u16)?;
+    let mut event_types = Vec::new();
+    for _ in 0..count {
+        let naia_id = varint::read_from(reader, varint::read_u16)?;
+        let buffer = reader.get_buffer();
+        let start = reader.get_cursor().position() as usize;
+        if buffer.len() <= start {
+            return Err(VarintError::Truncated);
+        }
+        let name_length = buffer[start] as usize;
+        if buffer.len() < start + 1 + name_length {
+            return Err(VarintError::Truncated);
+        }
+        let name = String::from_utf8_lossy(&buffer[start + 1..start + 1 + name_length]);
+        reader
+            .get_cursor()
+            .set_position((start + 1 + name_length) as u64);
+        event_types.push((naia_id, name.to_string()));
+    }
+    return Ok(ManifestExtension { epoch, event_types });
+}
+
+/// Writes the payload of a ManifestExtensionAck: the epoch answered & the
+/// number of NaiaIds, then each NaiaId, all as varints
+pub fn write_manifest_extension_ack(epoch: u16, naia_ids: &[u16]) -> Vec<u8> {
+    let mut payload_bytes = Vec::new();
+    varint::write_u16(epoch, &mut payload_bytes); // write epoch
+    varint::write_u16(naia_ids.len() as u16, &mut payload_bytes); // write id count
+    for naia_id in naia_ids {
+        varint::write_u16(*naia_id, &mut payload_bytes); // write naia id
+    }
+    return payload_bytes;
+}
+
+/// Reads the payload of a ManifestExtensionAck, as written by
+/// `write_manifest_extension_ack()`. A payload cut short anywhere is an Error
+pub fn read_manifest_extension_ack(
+    reader: &mut PacketReader,
+) -> Result<ManifestExtensionAck, VarintError> {
+    let epoch = varint::read_from(reader, varint::read_u16)?;
+    let count = varint::read_from(reader, varint::read_u16)?;
+    let mut naia_ids = Vec::new();
+    for _ in 0..count {
+        naia_ids.push(varint::read_from(reader, varint::read_u16)?);
+    }
+    return Ok(ManifestExtensionAck { epoch, naia_ids });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        read_manifest_extension, read_manifest_extension_ack, write_manifest_extension,
+        write_manifest_extension_ack, ManifestExtension, ManifestExtensionAck,
+    };
+    use crate::{varint::VarintError, wire_format::assert_golden, PacketReader};
+
+    #[test]
+    fn manifest_extension_matches_golden() {
+        let event_types = vec![(4, "hi".to_string())];
+        assert_golden(
+            "manifest_extension",
+            &write_manifest_extension(1, &event_types),
+        );
+        assert_golden(
+            "manifest_extension_ack",
+            &write_manifest_extension_ack(1, &[4]),
+        );
+    }
+
+    #[test]
+    fn payloads_read_back_as_written() {
+        let event_types = vec![(200, "ReloadEvent".to_string()), (201, String::new())];
+        let payload = write_manifest_extension(300, &event_types);
+        let mut reader = PacketReader::new(&payload);
+        assert_eq!(
+            read_manifest_extension(&mut reader),
+            Ok(ManifestExtension {
+                epoch: 300,
+                event_types,
+            })
+        );
+        assert!(!reader.has_more());
+
+        let payload = write_manifest_extension_ack(300, &[200]);
+        assert_eq!(
+            read_manifest_extension_ack(&mut PacketReader::new(&payload)),
+            Ok(ManifestExtensionAck {
+                epoch: 300,
+                naia_ids: vec![200],
+            })
+        );
+    }
+
+    #[test]
+    fn payloads_cut_short_are_an_error() {
+        let payload = write_manifest_extension(1, &[(4, "hi".to_string())]);
+        for length in 0..payload.len() {
+            assert_eq!(
+                read_manifest_extension(&mut PacketReader::new(&payload[..length])),
+                Err(VarintError::Truncated)
+            );
+        }
+        let payload = write_manifest_extension_ack(1, &[4]);
+        assert_eq!(
+            read_manifest_extension_ack(&mut PacketReader::new(&payload[..2])),
+            Err(VarintError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    net::SocketAddr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use log::info;
+
+/// The target trace lines are logged under, so that a logger can let them
+/// through on their own, such as with `RUST_LOG=naia_trace=info`
+pub const TRACE_LOG_TARGET: &str = "naia_trace";
+
+/// The word every trace line starts with
+pub const TRACE_LINE_PREFIX: &str = "naia-trace";
+
+/// The id an Event is traced by, unique among the Events sent by this process
+pub type TraceId = u32;
+
+// shared by every connection, so that the Events sent by a Server to each of
+// its Clients are told apart in its log too
+static NEXT_TRACE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Returns whether this host was built with message tracing, with the
+/// "message-tracing" feature
+pub fn is_supported() -> bool {
+    return cfg!(feature = "message-tracing");
+}
+
+/// A point in the life of a traced Event
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceStage {
+    /// Queued to be sent, by the sending host
+    Queued,
+    /// Written into the packet with the given index, by the sending host
+    Packed,
+    /// Queued to be sent again, after the packet with the given index was
+    /// lost, by the sending host
+    Retransmitted,
+    /// Acknowledged in the packet with the given index, by the sending host
+    Acked,
+    /// Read from a packet, by the receiving host
+    Received,
+    /// Handed to the application's queue, after any Events before it on its
+    /// ordered channel, by the receiving host
+    Delivered,
+}
+
+impl TraceStage {
+    const ALL: [TraceStage; 6] = [
+        TraceStage::Queued,
+        TraceStage::Packed,
+        TraceStage::Retransmitted,
+        TraceStage::Acked,
+        TraceStage::Received,
+        TraceStage::Delivered,
+    ];
+
+    /// Gets the name the stage is logged as
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceStage::Queued => return "queued",
+            TraceStage::Packed => return "packed",
+            TraceStage::Retransmitted => return "retransmitted",
+            TraceStage::Acked => return "acked",
+            TraceStage::Received => return "received",
+            TraceStage::Delivered => return "delivered",
+        }
+    }
+}
+
+/// One line of the trace log, written as
+/// `naia-trace <trace id> <stage> peer=<address>`, followed by
+/// ` packet=<index>` for the stages which happen to a packet. The peer is the
+/// remote host of the connection the line was logged on, and the trace id is
+/// always the one given by the sending host, so grepping both hosts' logs for
+/// `naia-trace <trace id> ` finds the whole life of an Event
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceLine {
+    /// The id of the traced Event
+    pub trace_id: TraceId,
+    /// The point the Event has reached
+    pub stage: TraceStage,
+    /// The address of the remote host
+    pub peer: SocketAddr,
+    /// The index of the packet the stage happened to, if any
+    pub packet_index: Option<u16>,
+}
+
+impl TraceLine {
+    /// Parses a line written as set out above, returning None if it isn't a
+    /// trace line
+    pub fn parse(line: &str) -> Option<TraceLine> {
+        let mut words = line.split_whitespace();
+        if words.next() != Some(TRACE_LINE_PREFIX) {
+            return None;
+        }
+        let trace_id = words.next()?.parse().ok()?;
+        let stage_name = words.next()?;
+        let stage = *TraceStage::ALL
+            .iter()
+            .find(|stage| stage.as_str() == stage_name)?;
+        let peer = words.next()?.strip_prefix("peer=")?.parse().ok()?;
+        let mut packet_index = None;
+        if let Some(word) = words.next() {
+            packet_index = Some(word.strip_prefix("packet=")?.parse().ok()?);
+        }
+        return Some(TraceLine {
+            trace_id,
+            stage,
+            peer,
+            packet_index,
+        });
+    }
+}
+
+impl Display for TraceLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} {} {} peer={}",
+            TRACE_LINE_PREFIX,
+            self.trace_id,
+            self.stage.as_str(),
+            self.peer
+        )?;
+        if let Some(packet_index) = self.packet_index {
+            write!(f, " packet={}", packet_index)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Logs the trace lines of the Events sent & received on a connection
+#[derive(Debug)]
+pub struct MessageTracer {
+    peer: SocketAddr,
+}
+
+impl MessageTracer {
+    /// Creates a new MessageTracer, for the connection to the given address
+    pub fn new(peer: SocketAddr) -> Self {
+        return MessageTracer { peer };
+    }
+
+    /// Gets a new trace id for an Event being queued
+    pub fn next_trace_id(&self) -> TraceId {
+        return NEXT_TRACE_ID.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Logs that the Event with the given trace id has reached a stage
+    pub fn trace(&self, trace_id: TraceId, stage: TraceStage, packet_index: Option<u16>) {
+        let line = TraceLine {
+            trace_id,
+            stage,
+            peer: self.peer,
+            packet_index,
+        };
+        info!(target: TRACE_LOG_TARGET, "{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::{TraceLine, TraceStage};
+
+    #[test]
+    fn lines_parse_back_as_written() {
+        let peer: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let packed = TraceLine {
+            trace_id: 42,
+            stage: TraceStage::Packed,
+            peer,
+            packet_index: Some(7),
+        };
+        assert_eq!(
+            packed.to_string(),
+            "naia-trace 42 packed peer=127.0.0.1:14191 packet=7"
+        );
+        assert_eq!(TraceLine::parse(&packed.to_string()), Some(packed));
+
+        let delivered = TraceLine {
+            trace_id: 3,
+            stage: TraceStage::Delivered,
+            peer,
+            packet_index: None,
+        };
+        assert_eq!(TraceLine::parse(&delivered.to_string()), Some(delivered));
+
+        assert_eq!(TraceLine::parse("naia-trace 3 lost peer=127.0.0.1:1"), None);
+        assert_eq!(TraceLine::parse("something else entirely"), None);
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    fmt::{Debug, Formatter, Result},
+    net::SocketAddr,
+};
+
+/// What a packet hook decides should happen to the packet it was shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookAction {
+    /// The packet is sent or received as usual
+    Pass,
+    /// The packet is dropped, just as if the network had lost it
+    Drop,
+}
+
+/// Holds an optional hook which is shown each packet crossing the transport,
+/// given the address of the remote host & the packet's bytes as they are on
+/// the wire, after padding & compression on send and before either is undone
+/// on receive. The hook can't change the packet, only let it through or drop
+/// it
+#[derive(Default)]
+pub struct PacketHook {
+    hook: Option<Box<dyn FnMut(&SocketAddr, &[u8]) -> HookAction>>,
+}
+
+impl PacketHook {
+    /// Sets the hook, replacing any set before
+    pub fn set(&mut self, hook: Box<dyn FnMut(&SocketAddr, &[u8]) -> HookAction>) {
+        self.hook = Some(hook);
+    }
+
+    /// Shows a packet to the hook, if one is set, and returns whether the
+    /// packet should go on
+    pub fn allows(&mut self, address: &SocketAddr, payload: &[u8]) -> bool {
+        match &mut self.hook {
+            Some(hook) => return hook(address, payload) == HookAction::Pass,
+            None => return true,
+        }
+    }
+}
+
+impl Debug for PacketHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str("PacketHook")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::{HookAction, PacketHook};
+
+    #[test]
+    fn hook_decides_which_packets_go_on() {
+        let address: SocketAddr = "127.0.0.1:14191".parse().unwrap();
+        let mut hook = PacketHook::default();
+        assert!(hook.allows(&address, &[1]));
+
+        hook.set(Box::new(|_, payload| match payload.first() {
+            Some(1) => HookAction::Drop,
+            _ => HookAction::Pass,
+        }));
+        assert!(!hook.allows(&address, &[1]));
+        assert!(hook.allows(&address, &[2]));
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(feature = "std")]
+use alloc::{boxed::Box, vec::Vec};
+
+#[cfg(feature = "std")]
+use crate::{byte_order, standard_header::StandardHeader};
+
+/// Bit set on the packet type byte of the StandardHeader when the packet has
+/// been padded. The header is then followed by the number of padding bytes at
+/// the end of the packet, as a u16
+pub const PADDED_FLAG: u8 = 0x40;
+
+/// The number of bytes the padding length takes up in a padded packet
+pub const PADDING_LENGTH_SIZE: usize = 2;
+
+/// How outgoing packets are padded, so that their sizes give away less about
+/// what they contain to anyone observing the traffic. Padding is never added
+/// past the largest packet which can be sent on the connection, so a packet
+/// which can't be padded as configured without going over it is sent as it is
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PacketPadding {
+    /// Pads every packet up to the next multiple of the given number of bytes
+    ToMultipleOf(u16),
+    /// Pads every packet up to the given number of bytes. Packets which are
+    /// already larger than this are sent as they are, so it's best set to the
+    /// largest packet which will be sent on the connection
+    ToSize(u16),
+}
+
+impl PacketPadding {
+    /// Gets the size a packet of the given size is padded up to, counting the
+    /// padding length, or None if the packet shouldn't be padded, because it
+    /// is already larger than it would be padded to, or would end up larger
+    /// than `max_size`
+    pub fn get_padded_size(&self, size: usize, max_size: usize) -> Option<usize> {
+        let size = size + PADDING_LENGTH_SIZE;
+        let padded_size = match self {
+            PacketPadding::ToMultipleOf(multiple) => {
+                let multiple = usize::from(*multiple).max(1);
+                size.div_ceil(multiple) * multiple
+            }
+            PacketPadding::ToSize(target) => usize::from(*target),
+        };
+        if padded_size < size || padded_size > max_size {
+            return None;
+        }
+        return Some(padded_size);
+    }
+}
+
+/// Pads a packet, made up of a StandardHeader followed by its payload, up to
+/// the given size, as returned by `PacketPadding::get_padded_size()`
+#[cfg(feature = "std")]
+pub(crate) fn pad_packet(packet: &[u8], padded_size: usize) -> Box<[u8]> {
+    let header_size = StandardHeader::bytes_number();
+    let padding_length = padded_size - packet.len() - PADDING_LENGTH_SIZE;
+
+    let mut padded_packet = Vec::with_capacity(padded_size);
+    padded_packet.extend_from_slice(&packet[..header_size]);
+    padded_packet[0] |= PADDED_FLAG;
+    let mut length_bytes = [0; PADDING_LENGTH_SIZE];
+    byte_order::put_u16(&mut length_bytes, padding_length as u16);
+    padded_packet.extend_from_slice(&length_bytes);
+    padded_packet.extend_from_slice(&packet[header_size..]);
+    padded_packet.resize(padded_size, 0);
+    return padded_packet.into_boxed_slice();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PacketPadding;
+
+    #[test]
+    fn padded_size_follows_quantization() {
+        let padding = PacketPadding::ToMultipleOf(64);
+        assert_eq!(padding.get_padded_size(13, 508), Some(64));
+        assert_eq!(padding.get_padded_size(62, 508), Some(64));
+        assert_eq!(padding.get_padded_size(63, 508), Some(128));
+        assert_eq!(padding.get_padded_size(400, 508), Some(448));
+        // the next multiple would be larger than the largest packet
+        assert_eq!(padding.get_padded_size(450, 508), None);
+
+        let padding = PacketPadding::ToSize(508);
+        assert_eq!(padding.get_padded_size(13, 508), Some(508));
+        assert_eq!(padding.get_padded_size(506, 508), Some(508));
+        assert_eq!(padding.get_padded_size(507, 508), None);
+        assert_eq!(PacketPadding::ToSize(200).get_padded_size(300, 508), None);
+    }
+}
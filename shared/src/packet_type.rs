@@ -22,6 +22,41 @@ pub enum PacketType {
     /// A Pong message, used to calculate RTT. Must be the response to all Ping
     /// messages
     Pong = 8,
+    /// A handshake message sent by the Server, indicating that the connection
+    /// has been rejected, followed by a RejectReason
+    ServerRejectResponse = 9,
+    /// A message sent by the Server when it closes an established connection,
+    /// followed by the reason given for closing it
+    ServerDisconnect = 10,
+    /// A message sent by the Server in reply to a packet from an address which
+    /// has no connection, letting a Client know it must handshake again
+    ServerNotConnected = 11,
+    /// An out-of-band query sent to the Server without a connection, such as
+    /// by a server browser, followed by the query bytes
+    Query = 12,
+    /// The Server's reply to a Query, followed by the reply bytes
+    QueryResponse = 13,
+    /// A packet padded out to a given size, sent by the Server to discover the
+    /// largest packet which reaches the Client
+    MtuProbe = 14,
+    /// The Client's reply to an MtuProbe, confirming the size it arrived at
+    MtuProbeAck = 15,
+    /// A report sent by the Server of how many of the Client's Commands it has
+    /// recently received in time for their tick, and how many it missed
+    CommandReport = 16,
+    /// A request sent by the Client, after missing a long run of packets from
+    /// the Server, for the whole state of the Actors it holds, so that none is
+    /// left holding changes which were lost along with the packets
+    ResyncRequest = 17,
+    /// A message sent by the Server, after registering Event types while
+    /// connected, with the NaiaId & name of each, until the Client answers
+    ManifestExtension = 18,
+    /// The Client's answer to a ManifestExtension, listing the Event types in
+    /// it which the Client has registered too
+    ManifestExtensionAck = 19,
+    /// A message sent by the Client when it closes its connection, so that the
+    /// Server can drop it without waiting for it to time out
+    ClientDisconnect = 20,
     /// An unknown packet type
     Unknown = 255,
 }
@@ -37,6 +72,18 @@ impl From<u8> for PacketType {
             6 => return PacketType::ServerConnectResponse,
             7 => return PacketType::Ping,
             8 => return PacketType::Pong,
+            9 => return PacketType::ServerRejectResponse,
+            10 => return PacketType::ServerDisconnect,
+            11 => return PacketType::ServerNotConnected,
+            12 => return PacketType::Query,
+            13 => return PacketType::QueryResponse,
+            14 => return PacketType::MtuProbe,
+            15 => return PacketType::MtuProbeAck,
+            16 => return PacketType::CommandReport,
+            17 => return PacketType::ResyncRequest,
+            18 => return PacketType::ManifestExtension,
+            19 => return PacketType::ManifestExtensionAck,
+            20 => return PacketType::ClientDisconnect,
             _ => return PacketType::Unknown,
         };
     }
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use crate::{
+    byte_order, utils::duration_between, Instant, PacketReader, SequenceBuffer, SequenceNumber,
+    Timer,
+};
+
+#[derive(Clone, Debug)]
+struct SentPing {
+    time_sent: Instant,
+}
+
+/// Pings the remote host on an interval, and answers its pings, to keep an
+/// average of the Round Trip Time to it, and of its Jitter. Both the Client
+/// & the Server keep one for each connection
+#[derive(Debug)]
+pub struct PingManager {
+    ping_timer: Timer,
+    sent_pings: SequenceBuffer<SentPing>,
+    ping_index: SequenceNumber,
+    samples: f32,
+    max_samples: f32,
+    rtt_average: f32,
+    rtt_variance: f32,
+    rtt_deviation: f32,
+}
+
+impl PingManager {
+    /// Create a new PingManager, given the interval to ping on, how many of
+    /// the latest samples the averages are kept over, and the current time
+    pub fn new(ping_interval: Duration, rtt_sample_size: u16, now: &Instant) -> Self {
+        PingManager {
+            ping_index: 0,
+            ping_timer: Timer::new(ping_interval, now),
+            sent_pings: SequenceBuffer::with_capacity(rtt_sample_size),
+            samples: 0.0,
+            max_samples: f32::from(rtt_sample_size),
+            rtt_average: 0.0,
+            rtt_variance: 0.0,
+            rtt_deviation: 0.0,
+        }
+    }
+
+    /// Returns whether a ping message should be sent, given the current time
+    pub fn should_send_ping(&self, now: &Instant) -> bool {
+        self.ping_timer.ringing(now)
+    }
+
+    /// Causes a ping message to be sent as soon as possible, rather than
+    /// waiting for the ping interval to elapse
+    pub fn force_ping(&mut self) {
+        self.ping_timer.ring_manual();
+    }
+
+    /// Forgets the pings awaiting a pong, so that their round trips aren't
+    /// sampled. Pongs for them which arrive later are ignored
+    pub fn discard_sent_pings(&mut self) {
+        self.sent_pings.clear();
+    }
+
+    /// Get an outgoing ping payload, given the current time
+    pub fn get_ping_payload(&mut self, now: &Instant) -> Box<[u8]> {
+        self.ping_timer.reset(now);
+
+        self.sent_pings.insert(
+            self.ping_index,
+            SentPing {
+                time_sent: now.clone(),
+            },
+        );
+
+        let mut out_bytes = Vec::<u8>::new();
+        byte_order::write_u16(&mut out_bytes, self.ping_index); // write index
+
+        // increment ping index
+        self.ping_index = self.ping_index.wrapping_add(1);
+
+        out_bytes.into_boxed_slice()
+    }
+
+    /// Process an incoming ping payload, given the time it was received at
+    /// and the current time. The time the ping was held for before being
+    /// responded to is written into the pong, so that it can be left out of
+    /// the Round Trip Time
+    pub fn process_ping(
+        &self,
+        ping_payload: &[u8],
+        received_at: &Instant,
+        now: &Instant,
+    ) -> Box<[u8]> {
+        // read incoming ping index
+        let mut reader = PacketReader::new(&ping_payload);
+        let ping_index = byte_order::read_u16(&mut reader);
+
+        let processing_delay = duration_between(received_at, now);
+        let processing_micros = processing_delay.as_micros().min(u128::from(u32::MAX)) as u32;
+
+        // write pong payload
+        let mut out_bytes = Vec::<u8>::new();
+        byte_order::write_u16(&mut out_bytes, ping_index); // write index
+        byte_order::write_u32(&mut out_bytes, processing_micros); // write processing delay
+        out_bytes.into_boxed_slice()
+    }
+
+    /// Process an incoming pong payload, given the current time
+    pub fn process_pong(&mut self, pong_payload: &[u8], now: &Instant) {
+        let mut reader = PacketReader::new(&pong_payload);
+        let ping_index = byte_order::read_u16(&mut reader);
+        let processing_delay = read_processing_delay(&mut reader);
+
+        match self.sent_pings.remove(ping_index) {
+            None => {}
+            Some(ping) => {
+                let rtt = duration_between(&ping.time_sent, now).saturating_sub(processing_delay);
+                self.process_new_rtt(rtt.as_secs_f32() * 1000.0);
+            }
+        }
+    }
+
+    fn process_new_rtt(&mut self, elapsed_millis: f32) {
+        if self.samples == 0.0 {
+            self.rtt_average = elapsed_millis;
+            self.samples = 1.0;
+            return;
+        } else {
+            if self.samples < self.max_samples {
+                self.samples += 1.0;
+            }
+
+            self.rtt_average =
+                ((self.rtt_average * self.samples) + elapsed_millis) / (self.samples + 1.0);
+
+            let new_variance = (elapsed_millis - self.rtt_average).powi(2);
+            self.rtt_variance =
+                ((self.rtt_variance * self.samples) + new_variance) / (self.samples + 1.0);
+
+            self.rtt_deviation = self.rtt_variance.sqrt();
+        }
+    }
+
+    /// Gets the current calculated average Round Trip Time to the remote host,
+    /// in milliseconds
+    pub fn get_rtt(&self) -> f32 {
+        return self.rtt_average;
+    }
+
+    /// Gets the current calculated standard deviation of Jitter to the remote
+    /// host, in milliseconds
+    pub fn get_jitter(&self) -> f32 {
+        return self.rtt_deviation;
+    }
+}
+
+// the time the remote host held the ping for before responding, left at zero
+// if the pong doesn't carry it
+fn read_processing_delay(reader: &mut PacketReader) -> Duration {
+    match byte_order::try_read_u32(reader) {
+        Some(processing_micros) => return Duration::from_micros(u64::from(processing_micros)),
+        None => return Duration::from_secs(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::PingManager;
+    use crate::{Clock, SystemClock};
+
+    #[test]
+    fn processing_delay_is_left_out_of_rtt() {
+        let mut now = SystemClock.now();
+        let mut local = PingManager::new(Duration::from_secs(1), 8, &now);
+        let remote = PingManager::new(Duration::from_secs(1), 8, &now);
+
+        for _ in 0..4 {
+            let ping = local.get_ping_payload(&now);
+            now.add_millis(20);
+            // the remote host only gets around to responding a frame later
+            let received_at = now.clone();
+            now.add_millis(16);
+            let pong = remote.process_ping(&ping, &received_at, &now);
+            now.add_millis(20);
+            local.process_pong(&pong, &now);
+        }
+
+        assert!(
+            (local.get_rtt() - 40.0).abs() < 0.01,
+            "rtt {}",
+            local.get_rtt()
+        );
+    }
+
+    #[test]
+    fn forced_ping_is_sent_right_away() {
+        let now = SystemClock.now();
+        let mut ping_manager = PingManager::new(Duration::from_secs(60), 8, &now);
+        ping_manager.get_ping_payload(&now);
+        assert!(!ping_manager.should_send_ping(&now));
+        ping_manager.force_ping();
+        assert!(ping_manager.should_send_ping(&now));
+    }
+}
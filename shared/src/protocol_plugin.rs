@@ -0,0 +1,13 @@
+use crate::{actors::actor_type::ActorType, events::event_type::EventType, manifest::Manifest};
+
+/// A part of a protocol which registers its own types, such as those of one
+/// plugin of a game, so that the protocol can be composed of several parts
+/// without a single function registering every type. Added to a Manifest with
+/// `Manifest::add_plugin()`
+pub trait ProtocolPlugin<T: EventType, U: ActorType> {
+    /// Registers the plugin's types, along with how they are sent & any
+    /// Archetypes of its Actor types, in a Manifest which holds only them.
+    /// The NaiaIds & ArchetypeIds given here change once the Manifest is
+    /// merged into the one the plugin is added to
+    fn register(&self, manifest: &mut Manifest<T, U>);
+}
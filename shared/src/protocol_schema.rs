@@ -0,0 +1,209 @@
+use nanoserde::{DeJson, SerJson};
+
+use crate::{byte_order::WIRE_ORDER_NAME, PROTOCOL_VERSION};
+
+// How every Property is written, as this protocol has no fixed bit widths or
+// quantization of its own. nanoserde's encoding doesn't follow the byte order
+// of the wire format
+const PROPERTY_ENCODING: &str = "the length of the value in bytes as a u8, then the value in \
+                                 nanoserde's binary encoding, which is little endian";
+
+/// A machine-readable description of a protocol, as registered in a Manifest,
+/// for tooling such as packet dissectors, or to diff the protocol between
+/// releases. Got with `Manifest::export_schema()`
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct ProtocolSchema {
+    /// The version of the wire format the protocol is sent in
+    pub protocol_version: u16,
+    /// The byte order of every fixed-width integer in the wire format, either
+    /// "big-endian" or "little-endian"
+    pub byte_order: String,
+    /// How each Property of an Event or Actor is written
+    pub property_encoding: String,
+    /// Whether every Command is written along with the SubTick it was issued
+    /// at
+    pub command_sub_ticks: bool,
+    /// Every registered Event type, in order of NaiaId
+    pub events: Vec<EventSchema>,
+    /// Every registered Actor type, in order of NaiaId
+    pub actors: Vec<ActorSchema>,
+    /// Every registered Archetype, in order of ArchetypeId
+    pub archetypes: Vec<ArchetypeSchema>,
+}
+
+/// A registered Event type, along with how it's sent
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct EventSchema {
+    /// The id the Event type is written as
+    pub naia_id: u16,
+    /// The latest schema version of the Event type
+    pub schema_version: u16,
+    /// The ordered channel Events of the type are always sent on, if any
+    pub channel: Option<u8>,
+    /// How long Events of the type may wait to be sent before they're dropped,
+    /// if they aren't re-transmitted when lost
+    pub expiry_millis: Option<u64>,
+    /// Whether Events of the type are received as RawEvents
+    pub received_raw: bool,
+    /// Whether Events of the type are sent along with the tick they were sent
+    /// on
+    pub timestamped: bool,
+    /// Whether Events of the type wait for the Actors they reference to come
+    /// into scope before they're received
+    pub actor_ordered: bool,
+    /// Whether Events of the type are sent about an Actor, along with its key
+    pub actor_scoped: bool,
+    /// The Event type's name & Properties, if its builder describes them, as
+    /// those of derived Events do
+    pub description: Option<TypeDescription>,
+}
+
+/// A registered Actor type
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct ActorSchema {
+    /// The id the Actor type is written as
+    pub naia_id: u16,
+    /// The latest schema version of the Actor type
+    pub schema_version: u16,
+    /// The Actor type's name & Properties, if its builder describes them, as
+    /// those of derived Actors do. Each Property's bit in a StateMask is its
+    /// position in the list
+    pub description: Option<TypeDescription>,
+}
+
+/// A registered Archetype
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct ArchetypeSchema {
+    /// The id the Archetype is written as
+    pub archetype_id: u16,
+    /// The name the Archetype was registered with
+    pub name: String,
+    /// The NaiaId of the Actor type the Archetype's baseline is of
+    pub naia_id: u16,
+    /// The hash of the Archetype, which both hosts must share to use it
+    pub digest: u32,
+}
+
+/// The name & Properties of an Event or Actor type, as given by the
+/// `describe()` method generated by its derive
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct TypeDescription {
+    /// The name of the type
+    pub name: String,
+    /// Whether Events of the type are re-sent until they are delivered. None
+    /// for Actors
+    pub guaranteed: Option<bool>,
+    /// The type's Properties, in the order they're written
+    pub properties: Vec<PropertySchema>,
+}
+
+/// A Property of an Event or Actor type
+#[derive(Clone, Debug, PartialEq, DeJson, SerJson)]
+pub struct PropertySchema {
+    /// The name of the Property
+    pub name: String,
+    /// The Rust type of the Property's value
+    pub value_type: String,
+    /// The schema version of its type the Property was added in. A host with
+    /// an older version neither writes nor reads it
+    pub added_version: u16,
+    /// The value the Property is given when it's not written, if it has been
+    /// set to something other than the default of its type
+    pub default: Option<String>,
+    /// Whether the Property is interpolated between updates. Always false for
+    /// Events
+    pub interpolated: bool,
+    /// Whether the Property is predicted. Always false for Events
+    pub predicted: bool,
+}
+
+impl ProtocolSchema {
+    pub(crate) fn new(
+        events: Vec<EventSchema>,
+        actors: Vec<ActorSchema>,
+        archetypes: Vec<ArchetypeSchema>,
+        command_sub_ticks: bool,
+    ) -> Self {
+        return ProtocolSchema {
+            protocol_version: PROTOCOL_VERSION,
+            byte_order: WIRE_ORDER_NAME.to_string(),
+            property_encoding: PROPERTY_ENCODING.to_string(),
+            command_sub_ticks,
+            events,
+            actors,
+            archetypes,
+        };
+    }
+
+    /// Writes the ProtocolSchema as JSON, with each type on its own line, so
+    /// that a change to the protocol shows up as a change to the types it
+    /// affects. It can be read back with `ProtocolSchema::from_json()`
+    pub fn to_json(&self) -> String {
+        fn lines<S: SerJson>(items: &[S]) -> String {
+            if items.is_empty() {
+                return "[]".to_string();
+            }
+            let item_lines: Vec<String> = items
+                .iter()
+                .map(|item| format!("    {}", item.serialize_json()))
+                .collect();
+            return format!("[\n{}\n  ]", item_lines.join(",\n"));
+        }
+        return format!(
+            "{{\n  \"protocol_version\": {},\n  \"byte_order\": {},\n  \
+             \"property_encoding\": {},\n  \"command_sub_ticks\": {},\n  \"events\": {},\n  \
+             \"actors\": {},\n  \"archetypes\": {}\n}}\n",
+            self.protocol_version,
+            self.byte_order.serialize_json(),
+            self.property_encoding.serialize_json(),
+            self.command_sub_ticks,
+            lines(&self.events),
+            lines(&self.actors),
+            lines(&self.archetypes)
+        );
+    }
+
+    /// Reads a ProtocolSchema from JSON, as written by
+    /// `ProtocolSchema::to_json()`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        return ProtocolSchema::deserialize_json(json).map_err(|error| error.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActorSchema, ArchetypeSchema, PropertySchema, ProtocolSchema, TypeDescription};
+
+    #[test]
+    fn json_reads_back() {
+        let schema = ProtocolSchema::new(
+            Vec::new(),
+            vec![ActorSchema {
+                naia_id: 0,
+                schema_version: 2,
+                description: Some(TypeDescription {
+                    name: "Point".to_string(),
+                    guaranteed: None,
+                    properties: vec![PropertySchema {
+                        name: "x".to_string(),
+                        value_type: "Option<u16>".to_string(),
+                        added_version: 2,
+                        default: Some("Some(3)".to_string()),
+                        interpolated: true,
+                        predicted: false,
+                    }],
+                }),
+            }],
+            vec![ArchetypeSchema {
+                archetype_id: 0,
+                name: "origin".to_string(),
+                naia_id: 0,
+                digest: u32::MAX,
+            }],
+            true,
+        );
+        let json = schema.to_json();
+        assert!(json.contains("\n  \"events\": [],\n"));
+        assert_eq!(ProtocolSchema::from_json(&json).unwrap(), schema);
+    }
+}
@@ -0,0 +1,22 @@
+/// The reason a Server has given for rejecting a Client's connection request
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
+pub enum RejectReason {
+    /// The Server already has an established connection with the Client's
+    /// address
+    DuplicateConnection = 1,
+    /// The Server already has as many connections as it's configured to take
+    ServerFull = 2,
+    /// An unknown reason
+    Unknown = 255,
+}
+
+impl From<u8> for RejectReason {
+    fn from(orig: u8) -> Self {
+        match orig {
+            1 => return RejectReason::DuplicateConnection,
+            2 => return RejectReason::ServerFull,
+            _ => return RejectReason::Unknown,
+        };
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    actors::{actor_type::ActorType, archetype::ArchetypeId},
+    events::event_type::EventType,
+    manifest::Manifest,
+    varint::{self, VarintError},
+    PacketReader,
+};
+
+/// The NaiaId & schema version of each Event type, then of each Actor type,
+/// then the ArchetypeId & digest of each Archetype, as advertised by a host
+pub type AdvertisedVersions = (Vec<(u16, u16)>, Vec<(u16, u16)>, Vec<(ArchetypeId, u32)>);
+
+/// The schema versions of the Event & Actor types which both hosts on a
+/// connection are able to read & write. Each host advertises the schema
+/// version of its registered types in the handshake, and the lower of the two
+/// is used on the connection. Only the types the remote host has an older
+/// version of are held, every other type is written & read whole. Also holds
+/// the Archetypes both hosts have registered alike, which Actors may be
+/// written against
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaVersions {
+    event_versions: HashMap<u16, u16>,
+    actor_versions: HashMap<u16, u16>,
+    archetypes: HashSet<ArchetypeId>,
+}
+
+impl SchemaVersions {
+    /// Create a new SchemaVersions, for a remote host with the same version of
+    /// every type as this one, and no Archetypes in common
+    pub fn new() -> Self {
+        return SchemaVersions::default();
+    }
+
+    /// Reads the schema versions advertised by the remote host, as written by
+    /// `Manifest::write_schema_versions()`, and finds the versions to use on
+    /// the connection, given the local Manifest, along with the Archetypes
+    /// whose digests match the local ones. Returns an Error if the advertised
+    /// versions have been cut short
+    pub fn read<T: EventType, U: ActorType>(
+        manifest: &Manifest<T, U>,
+        reader: &mut PacketReader,
+    ) -> Result<Self, VarintError> {
+        let (remote_event_versions, remote_actor_versions, remote_archetype_digests) =
+            Self::read_advertised(reader)?;
+        let remote_event_versions: HashMap<u16, u16> = remote_event_versions.into_iter().collect();
+        let remote_actor_versions: HashMap<u16, u16> = remote_actor_versions.into_iter().collect();
+        let remote_archetype_digests: HashSet<(ArchetypeId, u32)> =
+            remote_archetype_digests.into_iter().collect();
+        return Ok(SchemaVersions {
+            event_versions: negotiate(
+                &manifest.get_versioned_event_types(),
+                &remote_event_versions,
+            ),
+            actor_versions: negotiate(
+                &manifest.get_versioned_actor_types(),
+                &remote_actor_versions,
+            ),
+            archetypes: manifest
+                .get_archetype_digests()
+                .into_iter()
+                .filter(|archetype_digest| remote_archetype_digests.contains(archetype_digest))
+                .map(|(archetype_id, _)| archetype_id)
+                .collect(),
+        });
+    }
+
+    /// Writes the NaiaId & schema version of each Event type, then of each
+    /// Actor type, advertised to the remote host in the handshake, followed by
+    /// the ArchetypeId & digest of each Archetype. Each list is written as a
+    /// count, followed by the pairs
+    pub fn write_advertised(
+        event_versions: &[(u16, u16)],
+        actor_versions: &[(u16, u16)],
+        archetype_digests: &[(ArchetypeId, u32)],
+        out_bytes: &mut Vec<u8>,
+    ) {
+        for versions in [event_versions, actor_versions].iter() {
+            varint::write_u16(versions.len() as u16, out_bytes); // write type count
+            for (naia_id, version) in versions.iter() {
+                varint::write_u16(*naia_id, out_bytes); // write naia id
+                varint::write_u16(*version, out_bytes); // write schema version
+            }
+        }
+        varint::write_u16(archetype_digests.len() as u16, out_bytes); // write archetype count
+        for (archetype_id, digest) in archetype_digests.iter() {
+            varint::write_u16(*archetype_id, out_bytes); // write archetype id
+            varint::write_u32(*digest, out_bytes); // write digest
+        }
+    }
+
+    /// Reads the NaiaId & schema version of each Event type, then of each
+    /// Actor type, then the ArchetypeId & digest of each Archetype, advertised
+    /// by the remote host, as written by `write_advertised()`, in the order
+    /// they were written. Returns an Error if they have been cut short
+    pub fn read_advertised(reader: &mut PacketReader) -> Result<AdvertisedVersions, VarintError> {
+        let event_versions = read_versions(reader)?;
+        let actor_versions = read_versions(reader)?;
+        let count = varint::read_from(reader, varint::read_u16)?;
+        let mut archetype_digests = Vec::new();
+        for _ in 0..count {
+            let archetype_id = varint::read_from(reader, varint::read_u16)?;
+            let digest = varint::read_from(reader, varint::read_u32)?;
+            archetype_digests.push((archetype_id, digest));
+        }
+        return Ok((event_versions, actor_versions, archetype_digests));
+    }
+
+    /// Given an Event's NaiaId, get the schema version to write & read Events
+    /// of that type as, if it is older than the local one
+    pub fn get_event_version(&self, naia_id: u16) -> Option<u16> {
+        return self.event_versions.get(&naia_id).copied();
+    }
+
+    /// Given an Actor's NaiaId, get the schema version to write & read Actors
+    /// of that type as, if it is older than the local one
+    pub fn get_actor_version(&self, naia_id: u16) -> Option<u16> {
+        return self.actor_versions.get(&naia_id).copied();
+    }
+
+    /// Returns whether the Archetype with the given ArchetypeId is registered
+    /// alike on both hosts, so that Actors may be written against it
+    pub fn has_archetype(&self, archetype_id: ArchetypeId) -> bool {
+        return self.archetypes.contains(&archetype_id);
+    }
+}
+
+// reads a count of types, followed by the NaiaId & schema version of each
+fn read_versions(reader: &mut PacketReader) -> Result<Vec<(u16, u16)>, VarintError> {
+    let count = varint::read_from(reader, varint::read_u16)?;
+    let mut versions = Vec::new();
+    for _ in 0..count {
+        let naia_id = varint::read_from(reader, varint::read_u16)?;
+        let version = varint::read_from(reader, varint::read_u16)?;
+        versions.push((naia_id, version));
+    }
+    return Ok(versions);
+}
+
+// types the remote host doesn't advertise are at the first version, and any
+// type the remote host has a newer version of is written & read whole
+fn negotiate(
+    local_versions: &[(u16, u16)],
+    remote_versions: &HashMap<u16, u16>,
+) -> HashMap<u16, u16> {
+    let mut versions = HashMap::new();
+    for (naia_id, local_version) in local_versions.iter() {
+        let remote_version = remote_versions.get(naia_id).copied().unwrap_or(1).max(1);
+        if remote_version < *local_version {
+            versions.insert(*naia_id, remote_version);
+        }
+    }
+    return versions;
+}
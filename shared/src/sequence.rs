@@ -0,0 +1,155 @@
+use core::cmp::Ordering;
+
+use crate::{sequence_buffer::SequenceNumber, wrapping_number::sequence_greater_than};
+
+// The number of distinct sequence numbers
+const SEQUENCE_RANGE: i32 = 1 << 16;
+
+/// Gets how far `b` is ahead of `a`, or behind it if negative, taking the
+/// shortest way around the wrap. The result is positive exactly when `b` is
+/// greater according to `sequence_greater_than()`, which orders sequence
+/// numbers exactly half the range apart by their value, so those are 32768
+/// or -32768 apart
+pub fn diff(a: SequenceNumber, b: SequenceNumber) -> i32 {
+    let forward = i32::from(b.wrapping_sub(a));
+    if forward > SEQUENCE_RANGE / 2 || (forward == SEQUENCE_RANGE / 2 && b < a) {
+        return forward - SEQUENCE_RANGE;
+    }
+    return forward;
+}
+
+/// Gets the sequence number `n` after `a`, or before it if negative, wrapping
+/// around. `advance(a, diff(a, b))` is always `b`
+pub fn advance(a: SequenceNumber, n: i32) -> SequenceNumber {
+    return a.wrapping_add(n.rem_euclid(SEQUENCE_RANGE) as u16);
+}
+
+/// Returns whether `x` is one of the `len` sequence numbers starting at `base`,
+/// wrapping around
+pub fn within_window(x: SequenceNumber, base: SequenceNumber, len: u16) -> bool {
+    return x.wrapping_sub(base) < len;
+}
+
+/// Orders sequence numbers by which is ahead of the other, taking the shortest
+/// way around the wrap, so that sequence numbers within half the range of each
+/// other sort correctly even across it. Each comparison is relative to the
+/// pivot on one side, so the order isn't transitive around the whole range,
+/// which is why only PartialOrd is implemented
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SeqOrd(pub SequenceNumber);
+
+impl PartialOrd for SeqOrd {
+    fn partial_cmp(&self, other: &SeqOrd) -> Option<Ordering> {
+        if self.0 == other.0 {
+            return Some(Ordering::Equal);
+        }
+        if sequence_greater_than(self.0, other.0) {
+            return Some(Ordering::Greater);
+        }
+        return Some(Ordering::Less);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{advance, diff, within_window, SeqOrd};
+    use crate::{sequence_greater_than, sequence_less_than};
+
+    // the points where wrapping math tends to go wrong
+    const PIVOTS: [u16; 9] = [0, 1, 2, 32766, 32767, 32768, 32769, 65534, 65535];
+
+    #[test]
+    fn diff_and_advance_round_trip() {
+        for a in PIVOTS.iter().copied() {
+            for n in -32767..=32767 {
+                let b = advance(a, n);
+                assert_eq!(diff(a, b), n, "diff({}, {})", a, b);
+                assert_eq!(advance(b, -n), a);
+            }
+            // half the range apart, either way around reaches the same number
+            let opposite = advance(a, 32768);
+            assert_eq!(advance(a, -32768), opposite);
+            assert_eq!(advance(a, diff(a, opposite)), opposite);
+            assert_eq!(diff(a, opposite), -diff(opposite, a));
+            // advancing by a whole range, or more, wraps back around
+            assert_eq!(advance(a, 65536), a);
+            assert_eq!(advance(a, -65536), a);
+            assert_eq!(advance(a, 65537), a.wrapping_add(1));
+        }
+        assert_eq!(diff(65535, 0), 1);
+        assert_eq!(diff(0, 65535), -1);
+        assert_eq!(diff(0, 32767), 32767);
+        assert_eq!(diff(0, 32768), 32768);
+        assert_eq!(diff(0, 32769), -32767);
+        assert_eq!(diff(32768, 0), -32768);
+        assert_eq!(diff(1, 32769), 32768);
+    }
+
+    #[test]
+    fn diff_agrees_with_comparisons() {
+        for a in PIVOTS.iter().copied() {
+            for b in 0..=u16::MAX {
+                let difference = diff(a, b);
+                assert_eq!(difference > 0, sequence_greater_than(b, a), "{} {}", a, b);
+                assert_eq!(difference < 0, sequence_less_than(b, a), "{} {}", a, b);
+                assert_eq!(difference == 0, a == b);
+                assert_eq!(difference, -diff(b, a));
+            }
+        }
+    }
+
+    #[test]
+    fn window_wraps_around() {
+        assert!(within_window(0, 0, 1));
+        assert!(!within_window(1, 0, 1));
+        assert!(!within_window(0, 0, 0));
+        assert!(within_window(65535, 65534, 4));
+        assert!(within_window(0, 65534, 4));
+        assert!(within_window(1, 65534, 4));
+        assert!(!within_window(2, 65534, 4));
+        assert!(!within_window(65533, 65534, 4));
+        assert!(within_window(32767, 32767, 2));
+        assert!(within_window(32768, 32767, 2));
+        assert!(!within_window(32769, 32767, 2));
+        assert!(within_window(65534, 0, 65535));
+        assert!(!within_window(65535, 0, 65535));
+
+        for base in PIVOTS.iter().copied() {
+            for offset in 0..1000 {
+                let x = advance(base, offset);
+                assert!(within_window(x, base, offset as u16 + 1));
+                assert!(!within_window(x, base, offset as u16));
+            }
+        }
+    }
+
+    #[test]
+    fn ordering_follows_shortest_way_around() {
+        assert!(SeqOrd(1) > SeqOrd(0));
+        assert!(SeqOrd(0) > SeqOrd(65535));
+        assert!(SeqOrd(65535) < SeqOrd(2));
+        assert!(SeqOrd(32768) > SeqOrd(0));
+        assert!(SeqOrd(32769) < SeqOrd(0));
+        assert!(SeqOrd(32768) > SeqOrd(32767));
+        assert!(SeqOrd(7) <= SeqOrd(7));
+
+        let mut sequences = [
+            SeqOrd(2),
+            SeqOrd(65534),
+            SeqOrd(0),
+            SeqOrd(65535),
+            SeqOrd(1),
+        ];
+        sequences.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            sequences,
+            [
+                SeqOrd(65534),
+                SeqOrd(65535),
+                SeqOrd(0),
+                SeqOrd(1),
+                SeqOrd(2)
+            ]
+        );
+    }
+}
@@ -1,28 +1,81 @@
-use std::clone::Clone;
+use alloc::{boxed::Box, vec};
+use core::clone::Clone;
 
-use super::wrapping_number::{sequence_greater_than, sequence_less_than};
+use super::{
+    sequence,
+    wrapping_number::{sequence_greater_than, sequence_less_than, wrapping_diff},
+};
 
 /// Used to index packets that have been sent & received
 pub type SequenceNumber = u16;
 
+/// The reason an entry could not be inserted into a SequenceBuffer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SequenceInsertError {
+    /// The sequence number is older than any the buffer can hold
+    TooOld,
+    /// The sequence number is further ahead of the most recently stored one
+    /// than the buffer's maximum advance. Accepting it would have removed the
+    /// record of the entries in between
+    TooFarAhead,
+}
+
+// The largest capacity a SequenceBuffer can have, being the largest power of
+// two which fits into a SequenceNumber
+const MAX_CAPACITY: u16 = 1 << 15;
+
+// How many sequence numbers too far ahead must be inserted in a row, each
+// following on from the one before, for the buffer to re-anchor at them. A
+// remote host which has moved on past a gap longer than the maximum advance,
+// after a burst of loss, sends such a run, while a single forged or replayed
+// sequence number can't flush the buffer
+const FAR_AHEAD_RUN_TO_REANCHOR: u16 = 3;
+
 /// Collection to store data of any kind.
 #[derive(Debug)]
 pub struct SequenceBuffer<T: Clone> {
     sequence_num: SequenceNumber,
     entry_sequences: Box<[Option<SequenceNumber>]>,
     entries: Box<[Option<T>]>,
+    max_advance: Option<u16>,
+    // the run of sequence numbers too far ahead inserted in a row, & the last
+    // of them
+    far_ahead_run: u16,
+    last_far_ahead: Option<SequenceNumber>,
 }
 
 impl<T: Clone> SequenceBuffer<T> {
-    /// Creates a SequenceBuffer with a desired capacity.
+    /// Creates a SequenceBuffer with at least a desired capacity. The capacity
+    /// is rounded up to a power of two, of at most 32768, so that it divides
+    /// the range of sequence numbers, and each keeps its slot as they wrap
+    /// around
     pub fn with_capacity(size: u16) -> Self {
+        let size = size.max(1).min(MAX_CAPACITY).next_power_of_two() as usize;
         Self {
             sequence_num: 0,
-            entry_sequences: vec![None; size as usize].into_boxed_slice(),
-            entries: vec![None; size as usize].into_boxed_slice(),
+            entry_sequences: vec![None; size].into_boxed_slice(),
+            entries: vec![None; size].into_boxed_slice(),
+            max_advance: None,
+            far_ahead_run: 0,
+            last_far_ahead: None,
         }
     }
 
+    /// Creates a SequenceBuffer with a desired capacity, which rejects any
+    /// sequence number more than `max_advance` ahead of the most recently
+    /// stored one. Meant for buffers of received sequence numbers, so that a
+    /// single forged sequence number can't flush the record of what has been
+    /// received. Half the capacity is large enough to never reject sequence
+    /// numbers which have only been reordered. After a short run of sequence
+    /// numbers too far ahead, each following on from the one before, the
+    /// buffer re-anchors at them, so that a gap longer than `max_advance`
+    /// doesn't leave it stuck
+    pub fn with_max_advance(size: u16, max_advance: u16) -> Self {
+        let mut buffer = Self::with_capacity(size);
+        buffer.max_advance = Some(max_advance);
+        return buffer;
+    }
+
     /// Returns the most recently stored sequence number.
     pub fn sequence_num(&self) -> SequenceNumber {
         self.sequence_num
@@ -47,30 +100,81 @@ impl<T: Clone> SequenceBuffer<T> {
     }
 
     /// Inserts the entry data into the sequence buffer. If the requested
-    /// sequence number is "too old", the entry will not be inserted and will
-    /// return false
+    /// sequence number is "too old", or too far ahead, the entry will not be
+    /// inserted and will return false
     pub fn insert(&mut self, sequence_num: SequenceNumber, entry: T) -> bool {
+        return self.try_insert(sequence_num, entry).is_ok();
+    }
+
+    /// Inserts the entry data into the sequence buffer, returning why it could
+    /// not be inserted if the requested sequence number is "too old", or too
+    /// far ahead
+    pub fn try_insert(
+        &mut self,
+        sequence_num: SequenceNumber,
+        entry: T,
+    ) -> Result<(), SequenceInsertError> {
         // sequence number is too old to insert into the buffer
         if sequence_less_than(
             sequence_num,
             self.sequence_num
                 .wrapping_sub(self.entry_sequences.len() as u16),
         ) {
-            return false;
+            return Err(SequenceInsertError::TooOld);
+        }
+
+        // sequence number is too far ahead of the most recent one
+        if let Some(max_advance) = self.max_advance {
+            let advance = wrapping_diff(self.sequence_num.wrapping_sub(1), sequence_num);
+            if i32::from(advance) > i32::from(max_advance) {
+                let follows_run = self.last_far_ahead.is_some_and(|last_far_ahead| {
+                    let step = wrapping_diff(last_far_ahead, sequence_num);
+                    step > 0 && i32::from(step) <= i32::from(max_advance)
+                });
+                self.far_ahead_run = if follows_run {
+                    self.far_ahead_run + 1
+                } else {
+                    1
+                };
+                self.last_far_ahead = Some(sequence_num);
+                if self.far_ahead_run < FAR_AHEAD_RUN_TO_REANCHOR {
+                    return Err(SequenceInsertError::TooFarAhead);
+                }
+            }
+            self.far_ahead_run = 0;
+            self.last_far_ahead = None;
         }
 
         self.advance_sequence(sequence_num);
 
         let index = self.index(sequence_num);
+        if let Some(stored_sequence) = self.entry_sequences[index] {
+            crate::strict_assert!(
+                stored_sequence == sequence_num
+                    || sequence_less_than(stored_sequence, self.oldest()),
+                "sequence {} would overwrite sequence {}, which is still held",
+                sequence_num,
+                stored_sequence
+            );
+        }
         self.entry_sequences[index] = Some(sequence_num);
         self.entries[index] = Some(entry);
 
-        return true;
+        return Ok(());
     }
 
     /// Returns whether or not we have previously inserted an entry for the
-    /// given sequence number.
+    /// given sequence number. Only sequence numbers within the window the
+    /// buffer holds, from the oldest to the most recently stored, can exist,
+    /// so an entry left behind in a slot past that window is never seen
     pub fn exists(&self, sequence_num: SequenceNumber) -> bool {
+        if !sequence::within_window(
+            sequence_num,
+            self.oldest(),
+            self.entry_sequences.len() as u16,
+        ) {
+            return false;
+        }
         let index = self.index(sequence_num);
         if let Some(s) = self.entry_sequences[index] {
             return s == sequence_num;
@@ -82,7 +186,7 @@ impl<T: Clone> SequenceBuffer<T> {
     pub fn remove(&mut self, sequence_num: SequenceNumber) -> Option<T> {
         if self.exists(sequence_num) {
             let index = self.index(sequence_num);
-            let value = std::mem::replace(&mut self.entries[index], None);
+            let value = core::mem::replace(&mut self.entries[index], None);
             self.entry_sequences[index] = None;
             return value;
         }
@@ -92,20 +196,22 @@ impl<T: Clone> SequenceBuffer<T> {
     // Advances the sequence number while removing older entries.
     fn advance_sequence(&mut self, sequence_num: SequenceNumber) {
         if sequence_greater_than(sequence_num.wrapping_add(1), self.sequence_num) {
-            self.remove_entries(u32::from(sequence_num));
+            self.remove_entries(sequence_num);
             self.sequence_num = sequence_num.wrapping_add(1);
         }
     }
 
-    fn remove_entries(&mut self, mut finish_sequence: u32) {
-        let start_sequence = u32::from(self.sequence_num);
-        if finish_sequence < start_sequence {
-            finish_sequence += 65536;
-        }
+    fn remove_entries(&mut self, finish_sequence: SequenceNumber) {
+        let start_sequence = self.sequence_num;
+        let distance = sequence::diff(start_sequence, finish_sequence);
 
-        if finish_sequence - start_sequence < self.entry_sequences.len() as u32 {
-            for sequence in start_sequence..=finish_sequence {
-                self.remove(sequence as u16);
+        if distance < self.entry_sequences.len() as i32 {
+            // the slots are emptied whatever they hold, as one may still hold
+            // an entry for an older sequence number which aliases it
+            for offset in 0..=distance {
+                let index = self.index(sequence::advance(start_sequence, offset));
+                self.entries[index] = None;
+                self.entry_sequences[index] = None;
             }
         } else {
             for index in 0..self.entry_sequences.len() {
@@ -138,11 +244,20 @@ impl<T: Clone> SequenceBuffer<T> {
     /// Remove entries up until a specific sequence number
     pub fn remove_until(&mut self, finish_sequence: u16) {
         let oldest = self.oldest();
-        for seq in oldest..finish_sequence {
-            self.remove(seq);
+        // no entries are stored before the oldest, or past the whole buffer
+        let count = sequence::diff(oldest, finish_sequence).min(self.entry_sequences.len() as i32);
+        for offset in 0..count {
+            self.remove(sequence::advance(oldest, offset));
         }
     }
 
+    /// Gets the number of bytes allocated for the buffer's slots, whether they
+    /// hold an entry or not. Anything an entry owns on the heap isn't counted
+    pub fn allocated_bytes(&self) -> usize {
+        return self.entry_sequences.len()
+            * (core::mem::size_of::<Option<SequenceNumber>>() + core::mem::size_of::<Option<T>>());
+    }
+
     /// Get a count of entries in the buffer
     pub fn get_entries_count(&self) -> u8 {
         let mut count = 0;
@@ -151,7 +266,7 @@ impl<T: Clone> SequenceBuffer<T> {
             if self.exists(seq) {
                 count += 1;
             }
-            seq = seq.wrapping_add(1);
+            seq = sequence::advance(seq, 1);
             if seq == self.sequence_num {
                 break;
             }
@@ -159,11 +274,13 @@ impl<T: Clone> SequenceBuffer<T> {
         return count;
     }
 
-    /// Get an iterator into the sequence
+    /// Get an iterator over the stored entries, along with the sequence number
+    /// each was stored under, from the oldest to the most recent, or the
+    /// other way around if `reverse` is true
     pub fn iter(&self, reverse: bool) -> SequenceIterator<T> {
         let index = {
             if reverse {
-                self.sequence_num
+                self.sequence_num.wrapping_sub(1)
             } else {
                 self.oldest()
             }
@@ -198,24 +315,294 @@ impl<'s, T: Clone> SequenceIterator<'s, T> {
             reverse,
         }
     }
+}
 
-    /// Get next value in the sequence
-    pub fn next(&mut self) -> Option<(SequenceNumber, &'s T)> {
-        loop {
-            if self.count == 0 {
-                return None;
-            }
-            let current_item = self.buffer.get(self.index);
-            let current_index = self.index;
-            if self.reverse {
-                self.index = self.index.wrapping_sub(1);
-            } else {
-                self.index = self.index.wrapping_add(1);
-            }
+impl<'s, T: Clone> Iterator for SequenceIterator<'s, T> {
+    type Item = (SequenceNumber, &'s T);
+
+    /// Get the next stored entry in the sequence, along with the sequence
+    /// number it was stored under
+    fn next(&mut self) -> Option<Self::Item> {
+        let step = match self.reverse {
+            true => -1,
+            false => 1,
+        };
+        while self.count > 0 {
+            let probe = self.index;
+            let slot = self.buffer.index(probe);
+            self.index = sequence::advance(self.index, step);
             self.count -= 1;
-            if let Some(item) = current_item {
-                return Some((current_index, item));
+            // a slot is shared by every sequence number which aliases it, so
+            // it's only yielded when probing the one it's stored under
+            if let (Some(stored_sequence), Some(entry)) = (
+                self.buffer.entry_sequences[slot],
+                self.buffer.entries[slot].as_ref(),
+            ) {
+                if stored_sequence == probe {
+                    return Some((stored_sequence, entry));
+                }
             }
         }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{SequenceBuffer, SequenceInsertError};
+
+    #[test]
+    fn insert_and_remove() {
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(8);
+        assert!(buffer.insert(3, 30));
+        assert!(buffer.exists(3));
+        assert_eq!(buffer.get(3), Some(&30));
+        assert_eq!(buffer.sequence_num(), 4);
+
+        assert_eq!(buffer.remove(3), Some(30));
+        assert!(!buffer.exists(3));
+        assert_eq!(buffer.get(3), None);
+    }
+
+    #[test]
+    fn capacity_divides_sequence_range() {
+        // with a capacity of 20, 65520 & 0 would share a slot while both held
+        let mut buffer = SequenceBuffer::<u16>::with_capacity(20);
+        for sequence in 65520..=65535 {
+            assert!(buffer.insert(sequence, sequence));
+        }
+        assert!(buffer.insert(0, 0));
+        assert_eq!(buffer.get(65520), Some(&65520));
+        assert_eq!(buffer.get(0), Some(&0));
+        assert_eq!(buffer.oldest(), 65505);
+
+        assert_eq!(SequenceBuffer::<u16>::with_capacity(0).oldest(), 65535);
+        assert_eq!(
+            SequenceBuffer::<u16>::with_capacity(u16::MAX).oldest(),
+            32768
+        );
+    }
+
+    #[test]
+    fn too_old_is_rejected() {
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(8);
+        assert!(buffer.insert(20, 1));
+        assert!(!buffer.insert(11, 2));
+        assert!(buffer.insert(13, 3));
+    }
+
+    #[test]
+    fn advancing_evicts_old_entries() {
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(8);
+        buffer.insert(1, 1);
+        buffer.insert(2, 2);
+        buffer.insert(20, 20);
+        assert!(!buffer.exists(1));
+        assert!(!buffer.exists(2));
+        assert!(buffer.exists(20));
+    }
+
+    #[test]
+    fn far_ahead_is_rejected() {
+        let mut buffer = SequenceBuffer::<u32>::with_max_advance(8, 4);
+        assert!(buffer.insert(0, 0));
+        assert!(buffer.insert(4, 4));
+        assert_eq!(
+            buffer.try_insert(9, 9),
+            Err(SequenceInsertError::TooFarAhead)
+        );
+        assert_eq!(buffer.sequence_num(), 5);
+        assert!(buffer.insert(8, 8));
+
+        // the window wraps around too
+        let mut buffer = SequenceBuffer::<u32>::with_max_advance(8, 4);
+        assert!(buffer.insert(65534, 1));
+        assert!(buffer.insert(2, 2));
+        assert_eq!(
+            buffer.try_insert(7, 3),
+            Err(SequenceInsertError::TooFarAhead)
+        );
+    }
+
+    #[test]
+    fn replays_detected_after_far_ahead_insert() {
+        let mut buffer = SequenceBuffer::<u32>::with_max_advance(32, 16);
+        for sequence in 0..20 {
+            assert!(buffer.insert(sequence, sequence.into()));
+        }
+
+        // a forged sequence number far in the future doesn't flush the window
+        assert_eq!(
+            buffer.try_insert(30000, 0),
+            Err(SequenceInsertError::TooFarAhead)
+        );
+        assert!(!buffer.exists(30000));
+        for sequence in 0..20 {
+            assert!(buffer.exists(sequence), "sequence {} was flushed", sequence);
+        }
+
+        // legitimate packets continue to be recorded, and replays of the
+        // earlier ones are still recognized
+        assert!(buffer.insert(25, 25));
+        assert!(buffer.exists(0));
+        assert!(buffer.exists(19));
+    }
+
+    #[test]
+    fn reanchors_after_a_run_past_max_advance() {
+        let mut buffer = SequenceBuffer::<u32>::with_max_advance(32, 16);
+        for sequence in 0..20 {
+            assert!(buffer.insert(sequence, sequence.into()));
+        }
+
+        // a burst of loss longer than the maximum advance
+        for sequence in 100..102 {
+            assert_eq!(
+                buffer.try_insert(sequence, sequence.into()),
+                Err(SequenceInsertError::TooFarAhead)
+            );
+        }
+        assert_eq!(buffer.sequence_num(), 20);
+        assert!(buffer.insert(103, 103));
+        assert_eq!(buffer.sequence_num(), 104);
+        assert!(buffer.exists(103));
+        assert!(!buffer.exists(19));
+        assert!(buffer.insert(104, 104));
+
+        // far ahead sequence numbers which don't follow on from each other
+        // never make up a run
+        for sequence in [1000, 5000, 1000, 9000, 20000, 1001].iter() {
+            assert_eq!(
+                buffer.try_insert(*sequence, 0),
+                Err(SequenceInsertError::TooFarAhead)
+            );
+        }
+        assert_eq!(buffer.sequence_num(), 105);
+
+        // nor do those broken up by sequence numbers which aren't far ahead
+        for sequence in 2000..2010 {
+            assert!(!buffer.insert(sequence, 0));
+            assert!(buffer.insert(105 + sequence - 2000, 0));
+        }
+        assert!(buffer.exists(104));
+    }
+
+    #[test]
+    fn unguarded_buffer_accepts_far_ahead() {
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(8);
+        assert!(buffer.insert(1, 1));
+        assert!(buffer.insert(30000, 2));
+        assert!(!buffer.exists(1));
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(8);
+        buffer.insert(65534, 1);
+        buffer.insert(65535, 2);
+        buffer.insert(1, 3);
+        assert_eq!(buffer.sequence_num(), 2);
+        assert!(buffer.exists(65535));
+        assert!(buffer.exists(1));
+
+        let mut iter = buffer.iter(false);
+        assert_eq!(iter.next(), Some((65534, &1)));
+        assert_eq!(iter.next(), Some((65535, &2)));
+        assert_eq!(iter.next(), Some((1, &3)));
+        assert_eq!(iter.next(), None);
+
+        let mut iter = buffer.iter(true);
+        assert_eq!(iter.next(), Some((1, &3)));
+        assert_eq!(iter.next(), Some((65535, &2)));
+        assert_eq!(iter.next(), Some((65534, &1)));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn iter_yields_stored_sequences_of_aliased_slots() {
+        // with a capacity of 4, 65534 & 2 share a slot, as do 65535 & 3
+        let mut buffer = SequenceBuffer::<u16>::with_capacity(4);
+        for sequence in [65534, 65535, 0, 1, 2].iter() {
+            assert!(buffer.insert(*sequence, sequence.wrapping_mul(10)));
+        }
+        // 2 has taken over the slot of 65534, while 3 was never stored
+        assert!(!buffer.exists(65534));
+        assert_eq!(buffer.oldest(), 65535);
+
+        let forward: Vec<(u16, u16)> = buffer
+            .iter(false)
+            .map(|(sequence, entry)| (sequence, *entry))
+            .collect();
+        assert_eq!(forward, [(65535, 65526), (0, 0), (1, 10), (2, 20)]);
+
+        // the oldest entry is reached going backwards too
+        let reverse: Vec<u16> = buffer.iter(true).map(|(sequence, _)| sequence).collect();
+        assert_eq!(reverse, [2, 1, 0, 65535]);
+
+        buffer.remove(0);
+        let remaining: Vec<u16> = buffer.iter(false).map(|(sequence, _)| sequence).collect();
+        assert_eq!(remaining, [65535, 1, 2]);
+    }
+
+    #[test]
+    fn aliased_entries_past_the_window_are_never_seen() {
+        // a capacity of 1000 is rounded up to 1024, so 64512 & 0 share a slot
+        let mut buffer = SequenceBuffer::<u16>::with_capacity(1000);
+        assert!(buffer.insert(64512, 1));
+        assert!(buffer.insert(65535, 2));
+        assert_eq!(buffer.oldest(), 64512);
+        assert!(buffer.exists(64512));
+
+        // advancing past the wrap empties the slot of 0, which 64512 was left
+        // in, even though 0 itself was never stored
+        assert!(buffer.insert(100, 3));
+        assert!(!buffer.exists(64512));
+        assert_eq!(buffer.get(64512), None);
+        assert_eq!(buffer.get_mut(64512), None);
+        assert_eq!(buffer.remove(64512), None);
+        assert_eq!(buffer.get_entries_count(), 2);
+        let stored: Vec<u16> = buffer.iter(false).map(|(sequence, _)| sequence).collect();
+        assert_eq!(stored, [65535, 100]);
+    }
+
+    #[test]
+    fn sequences_outside_the_window_are_never_seen() {
+        let mut buffer = SequenceBuffer::<u16>::with_capacity(1000);
+        for sequence in 65000..=65535 {
+            assert!(buffer.insert(sequence, sequence));
+        }
+        for sequence in 0..500 {
+            assert!(buffer.insert(sequence, sequence));
+        }
+        // the window is the 1024 sequence numbers up to & including 499
+        assert_eq!(buffer.oldest(), 65012);
+        assert!(!buffer.exists(65011));
+        assert_eq!(buffer.get(65012), Some(&65012));
+        assert_eq!(buffer.get(499), Some(&499));
+        assert!(!buffer.exists(500));
+    }
+
+    #[test]
+    fn remove_until_wraps_around() {
+        let mut buffer = SequenceBuffer::<u32>::with_capacity(8);
+        for sequence in [65533, 65534, 65535, 0, 1, 2].iter() {
+            assert!(buffer.insert(*sequence, u32::from(*sequence)));
+        }
+        assert_eq!(buffer.sequence_num(), 3);
+
+        // a finishing sequence number past the wrap removes the entries
+        // before it on both sides of the wrap
+        buffer.remove_until(1);
+        for sequence in [65533, 65534, 65535, 0].iter() {
+            assert!(!buffer.exists(*sequence), "{} wasn't removed", sequence);
+        }
+        assert!(buffer.exists(1));
+        assert!(buffer.exists(2));
+
+        // one older than the oldest entry removes nothing
+        buffer.remove_until(buffer.oldest().wrapping_sub(1));
+        assert!(buffer.exists(1));
     }
 }
@@ -1,17 +1,26 @@
+#[cfg(feature = "link-conditioner")]
 use naia_socket_shared::LinkConditionerConfig;
 use std::{default::Default, time::Duration};
 
-/// Contains Config properties which will be shared by Server and Client
+/// Contains Config properties which will be shared by Server and Client.
+/// Without the "link-conditioner" feature, which `new()` needs, it is built
+/// with `SharedConfig { tick_interval, ..Default::default() }`
 #[derive(Clone, Debug)]
 pub struct SharedConfig {
     /// The duration between each tick
     pub tick_interval: Duration,
     /// Configuration used to simulate network conditions
+    #[cfg(feature = "link-conditioner")]
     pub link_condition_config: Option<LinkConditionerConfig>,
+    /// The number of ordered Event channels. Events sent on the same channel
+    /// are received in order, while separate channels do not hold each other
+    /// up. Must be the same on the Server and Client
+    pub ordered_channel_count: u8,
 }
 
 impl SharedConfig {
     /// Creates a new SharedConfig
+    #[cfg(feature = "link-conditioner")]
     pub fn new(
         tick_interval: Duration,
         link_condition_config: Option<LinkConditionerConfig>,
@@ -19,6 +28,7 @@ impl SharedConfig {
         SharedConfig {
             tick_interval,
             link_condition_config,
+            ordered_channel_count: 1,
         }
     }
 }
@@ -27,7 +37,9 @@ impl Default for SharedConfig {
     fn default() -> Self {
         Self {
             tick_interval: Duration::from_secs(1),
+            #[cfg(feature = "link-conditioner")]
             link_condition_config: None,
+            ordered_channel_count: 1,
         }
     }
 }
@@ -1,8 +1,18 @@
-use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use alloc::{boxed::Box, vec::Vec};
 
-use std::io::Read;
+use crate::{
+    byte_order, compression,
+    packet_padding::{PADDED_FLAG, PADDING_LENGTH_SIZE},
+    packet_type::PacketType,
+};
 
-use crate::packet_type::PacketType;
+// The bits of the packet type byte which are flags, rather than the type
+const TYPE_FLAGS: u8 = compression::COMPRESSED_FLAG | PADDED_FLAG;
+
+/// The size of the largest packet ever sent, in bytes, once a larger MTU has
+/// been discovered, being the largest UDP payload which fits into a 1500 byte
+/// Ethernet MTU, after IPv4 & UDP headers
+pub const MAX_PACKET_SIZE: usize = 1472;
 
 #[derive(Copy, Clone, Debug)]
 /// This header provides reliability information.
@@ -83,31 +93,76 @@ impl StandardHeader {
 
     /// Writes the header to an outgoing byte buffer
     pub fn write(&self, buffer: &mut Vec<u8>) {
-        buffer.write_u8(self.p_type as u8).unwrap();
-        buffer
-            .write_u16::<BigEndian>(self.local_packet_index)
-            .unwrap();
-        buffer
-            .write_u16::<BigEndian>(self.last_remote_packet_index)
-            .unwrap();
-        buffer.write_u32::<BigEndian>(self.ack_field).unwrap();
-        buffer.write_u16::<BigEndian>(self.host_tick).unwrap();
-        buffer
-            .write_u16::<BigEndian>(self.last_received_tick)
-            .unwrap();
-    }
-
-    /// Reads the header from an incoming byte slice
-    pub fn read(mut msg: &[u8]) -> (Self, Box<[u8]>) {
-        let p_type: PacketType = msg.read_u8().unwrap().into();
-        let seq = msg.read_u16::<BigEndian>().unwrap();
-        let ack_seq = msg.read_u16::<BigEndian>().unwrap();
-        let ack_field = msg.read_u32::<BigEndian>().unwrap();
-        let host_tick = msg.read_u16::<BigEndian>().unwrap();
-        let last_received_tick = msg.read_u16::<BigEndian>().unwrap();
-
-        let mut buffer = Vec::new();
-        msg.read_to_end(&mut buffer).unwrap();
+        let mut bytes = [0; Self::bytes_number()];
+        bytes[0] = self.p_type as u8;
+        byte_order::put_u16(&mut bytes[1..3], self.local_packet_index);
+        byte_order::put_u16(&mut bytes[3..5], self.last_remote_packet_index);
+        byte_order::put_u32(&mut bytes[5..9], self.ack_field);
+        byte_order::put_u16(&mut bytes[9..11], self.host_tick);
+        byte_order::put_u16(&mut bytes[11..13], self.last_received_tick);
+        buffer.extend_from_slice(&bytes);
+    }
+
+    /// Reads only the PacketType from an incoming byte slice, without reading
+    /// the rest of the header or touching the payload
+    pub fn read_packet_type(msg: &[u8]) -> PacketType {
+        match msg.first() {
+            Some(type_byte) => return (type_byte & !TYPE_FLAGS).into(),
+            None => return PacketType::Unknown,
+        }
+    }
+
+    /// Reads the header from an incoming byte slice, stripping any padding from
+    /// the end of the packet, and decompressing the rest of the payload if the
+    /// header indicates that it has been compressed. If the padding length is
+    /// invalid, or the payload cannot be decompressed, the packet type will be
+    /// Unknown
+    pub fn read(msg: &[u8]) -> (Self, Box<[u8]>) {
+        let type_byte = msg[0];
+        let mut p_type: PacketType = (type_byte & !TYPE_FLAGS).into();
+        let seq = byte_order::get_u16(&msg[1..3]);
+        let ack_seq = byte_order::get_u16(&msg[3..5]);
+        let ack_field = byte_order::get_u32(&msg[5..9]);
+        let host_tick = byte_order::get_u16(&msg[9..11]);
+        let last_received_tick = byte_order::get_u16(&msg[11..13]);
+
+        let mut payload = &msg[Self::bytes_number()..];
+        if type_byte & PADDED_FLAG != 0 {
+            let unpadded_length = payload.get(..PADDING_LENGTH_SIZE).and_then(|length_bytes| {
+                let padding_length = usize::from(byte_order::get_u16(length_bytes));
+                return payload
+                    .len()
+                    .checked_sub(PADDING_LENGTH_SIZE + padding_length);
+            });
+            match unpadded_length {
+                Some(unpadded_length) => {
+                    payload = &payload[PADDING_LENGTH_SIZE..PADDING_LENGTH_SIZE + unpadded_length];
+                }
+                None => {
+                    p_type = PacketType::Unknown;
+                    payload = &[];
+                }
+            }
+        }
+
+        let mut buffer = payload.to_vec();
+
+        if p_type != PacketType::Unknown && type_byte & compression::COMPRESSED_FLAG != 0 {
+            #[cfg(feature = "compression")]
+            let decompressed = compression::decompress(&buffer);
+            // compression is never agreed to by a host built without it
+            #[cfg(not(feature = "compression"))]
+            let decompressed = None;
+            match decompressed {
+                Some(decompressed) => {
+                    buffer = decompressed;
+                }
+                None => {
+                    p_type = PacketType::Unknown;
+                    buffer = Vec::new();
+                }
+            }
+        }
 
         (
             StandardHeader {
@@ -122,3 +177,120 @@ impl StandardHeader {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "std")]
+    use crate::packet_padding::pad_packet;
+    use crate::{compression, PacketType, StandardHeader, PADDED_FLAG};
+
+    #[test]
+    fn write_read_roundtrip() {
+        let header = StandardHeader::new(PacketType::Heartbeat, 65535, 1, 0x8000_0001, 300, 299);
+        let mut bytes = Vec::new();
+        header.write(&mut bytes);
+        assert_eq!(bytes.len(), StandardHeader::bytes_number());
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        let (read_header, payload) = StandardHeader::read(&bytes);
+        assert_eq!(read_header.packet_type(), PacketType::Heartbeat);
+        assert_eq!(read_header.local_packet_index(), 65535);
+        assert_eq!(read_header.last_remote_packet_index(), 1);
+        assert_eq!(read_header.ack_field(), 0x8000_0001);
+        assert_eq!(read_header.host_tick(), 300);
+        assert_eq!(read_header.last_received_tick(), 299);
+        assert_eq!(payload.as_ref(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn packet_type_read_without_payload() {
+        let mut bytes = Vec::new();
+        StandardHeader::new(PacketType::Data, 0, 0, 0, 0, 0).write(&mut bytes);
+        bytes.extend_from_slice(&[7u8; 16]);
+        bytes[0] |= compression::COMPRESSED_FLAG | PADDED_FLAG;
+        assert_eq!(StandardHeader::read_packet_type(&bytes), PacketType::Data);
+        bytes[0] = PacketType::ClientChallengeRequest as u8;
+        assert_eq!(
+            StandardHeader::read_packet_type(&bytes),
+            PacketType::ClientChallengeRequest
+        );
+        assert_eq!(StandardHeader::read_packet_type(&[]), PacketType::Unknown);
+    }
+
+    #[cfg(not(feature = "wire-little-endian"))]
+    #[test]
+    fn big_endian_layout() {
+        let mut bytes = Vec::new();
+        StandardHeader::new(
+            PacketType::Data,
+            0x0102,
+            0x0304,
+            0x0506_0708,
+            0x090a,
+            0x0b0c,
+        )
+        .write(&mut bytes);
+        assert_eq!(
+            bytes.as_slice(),
+            &[1, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c]
+        );
+    }
+
+    #[cfg(feature = "wire-little-endian")]
+    #[test]
+    fn little_endian_layout() {
+        let mut bytes = Vec::new();
+        StandardHeader::new(
+            PacketType::Data,
+            0x0102,
+            0x0304,
+            0x0506_0708,
+            0x090a,
+            0x0b0c,
+        )
+        .write(&mut bytes);
+        assert_eq!(
+            bytes.as_slice(),
+            &[1, 0x02, 0x01, 0x04, 0x03, 0x08, 0x07, 0x06, 0x05, 0x0a, 0x09, 0x0c, 0x0b]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn padding_is_stripped() {
+        let mut bytes = Vec::new();
+        StandardHeader::new(PacketType::Data, 7, 6, 5, 4, 3).write(&mut bytes);
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        for padded_size in [18, 64, 508].iter() {
+            let padded = pad_packet(&bytes, *padded_size);
+            assert_eq!(padded.len(), *padded_size);
+            let (read_header, payload) = StandardHeader::read(&padded);
+            assert_eq!(read_header.packet_type(), PacketType::Data);
+            assert_eq!(read_header.local_packet_index(), 7);
+            assert_eq!(read_header.last_received_tick(), 3);
+            assert_eq!(payload.as_ref(), &[1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn invalid_padding_length_is_unknown() {
+        let mut bytes = Vec::new();
+        StandardHeader::new(PacketType::Data, 0, 0, 0, 0, 0).write(&mut bytes);
+        bytes[0] |= PADDED_FLAG;
+        assert_eq!(StandardHeader::read_packet_type(&bytes), PacketType::Data);
+
+        // no room for the padding length
+        let (read_header, payload) = StandardHeader::read(&bytes);
+        assert_eq!(read_header.packet_type(), PacketType::Unknown);
+        assert!(payload.is_empty());
+
+        // more padding than there are bytes left
+        bytes.extend_from_slice(&[0, 4, 1, 2, 3]);
+        let (read_header, payload) = StandardHeader::read(&bytes);
+        assert_eq!(read_header.packet_type(), PacketType::Unknown);
+        assert!(payload.is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+/// Whether invariants of the protocol are asserted at runtime, which they are
+/// in debug builds with the "strict-checks" feature enabled, as it is by
+/// default. Release builds never check them
+pub const STRICT_CHECKS: bool = cfg!(all(feature = "strict-checks", debug_assertions));
+
+/// Asserts an invariant of the protocol, one which holds however packets are
+/// lost, reordered or duplicated, panicking with the given message if it
+/// doesn't. The message names the connection & sequence number involved.
+/// Only checked if `STRICT_CHECKS` is set, and compiled out otherwise
+#[macro_export]
+macro_rules! strict_assert {
+    ($condition:expr, $($message:tt)+) => {
+        if $crate::STRICT_CHECKS && !$condition {
+            panic!("strict check failed: {}", format_args!($($message)+));
+        }
+    };
+}
@@ -0,0 +1,89 @@
+use crate::{byte_order, PacketReader};
+
+/// The number of bits the fraction of a tick a SubTick holds is quantized to
+pub const SUB_TICK_BITS: u8 = 10;
+
+// the number of steps a tick is divided into
+const SUB_TICK_STEPS: u16 = 1 << SUB_TICK_BITS;
+
+/// When within a tick something happened, such as the input a Command was
+/// issued for, as the fraction of the tick which had passed, quantized to
+/// 1024 steps. Commands are sent along with one when the Manifest is set to
+/// with `Manifest::set_command_sub_ticks()`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubTick {
+    step: u16,
+}
+
+impl SubTick {
+    /// Creates a SubTick from the fraction of a tick which had passed, from 0
+    /// up to but not including 1. Fractions out of that range are clamped to
+    /// it
+    pub fn from_fraction(fraction: f32) -> Self {
+        let step = (fraction * f32::from(SUB_TICK_STEPS)).floor();
+        if step.is_nan() || step <= 0.0 {
+            return SubTick { step: 0 };
+        }
+        return SubTick {
+            step: (step as u16).min(SUB_TICK_STEPS - 1),
+        };
+    }
+
+    /// Gets the fraction of a tick which had passed, from 0 up to but not
+    /// including 1
+    pub fn to_fraction(&self) -> f32 {
+        return f32::from(self.step) / f32::from(SUB_TICK_STEPS);
+    }
+
+    /// Gets the step of the tick the SubTick is at, out of 1024
+    pub fn get_step(&self) -> u16 {
+        return self.step;
+    }
+
+    /// Writes the SubTick into an outgoing byte stream, as a u16 of which only
+    /// the lowest 10 bits are used
+    pub fn write(&self, out_bytes: &mut Vec<u8>) {
+        byte_order::write_u16(out_bytes, self.step);
+    }
+
+    /// Reads a SubTick from an incoming packet, ignoring any bits above the
+    /// lowest 10
+    pub fn read(reader: &mut PacketReader) -> Self {
+        return SubTick {
+            step: byte_order::read_u16(reader) & (SUB_TICK_STEPS - 1),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubTick;
+    use crate::{byte_order, PacketReader};
+
+    #[test]
+    fn fractions_are_quantized_and_clamped() {
+        assert_eq!(SubTick::from_fraction(0.0).get_step(), 0);
+        assert_eq!(SubTick::from_fraction(0.5).get_step(), 512);
+        assert_eq!(SubTick::from_fraction(0.9999).get_step(), 1023);
+        assert_eq!(SubTick::from_fraction(1.5).get_step(), 1023);
+        assert_eq!(SubTick::from_fraction(-0.25).get_step(), 0);
+        assert_eq!(SubTick::from_fraction(f32::NAN).get_step(), 0);
+        let fraction = SubTick::from_fraction(0.3).to_fraction();
+        assert!(fraction <= 0.3 && 0.3 - fraction < 1.0 / 1024.0);
+    }
+
+    #[test]
+    fn reads_back_from_ten_bits() {
+        let mut bytes = Vec::new();
+        SubTick::from_fraction(0.75).write(&mut bytes);
+        assert_eq!(byte_order::get_u16(&bytes), 0x0300);
+        let mut reader = PacketReader::new(&bytes);
+        assert_eq!(SubTick::read(&mut reader), SubTick::from_fraction(0.75));
+
+        // bits above the lowest 10 are ignored
+        let mut bytes = [0; 2];
+        byte_order::put_u16(&mut bytes, 0xfc01);
+        let mut reader = PacketReader::new(&bytes);
+        assert_eq!(SubTick::read(&mut reader).get_step(), 1);
+    }
+}
@@ -0,0 +1,541 @@
+use std::{any::TypeId, cell::RefCell, rc::Rc};
+
+use crate::{
+    byte_order, Actor, ActorMutator, ActorRef, ActorType, Event, EventBuilder, EventType,
+    PacketReader, Property, StateMask,
+};
+
+/// An ActorType with no Actors at all, for tests of the Event & connection
+/// machinery which need an ActorType but never create an Actor
+#[derive(Clone)]
+pub enum NoActors {}
+
+impl ActorType for NoActors {
+    fn read_full(&mut self, _reader: &mut PacketReader, _packet_index: u16) {
+        match *self {}
+    }
+    fn read_partial(
+        &mut self,
+        _state_mask: &StateMask,
+        _reader: &mut PacketReader,
+        _packet_index: u16,
+    ) {
+        match *self {}
+    }
+    fn inner_ref(&self) -> Rc<RefCell<dyn Actor<NoActors>>> {
+        match *self {}
+    }
+    fn equals(&self, _other: &NoActors) -> bool {
+        match *self {}
+    }
+    fn equals_prediction(&self, _other: &NoActors) -> bool {
+        match *self {}
+    }
+    fn set_to_interpolation(&mut self, _old: &NoActors, _new: &NoActors, _fraction: f32) {
+        match *self {}
+    }
+    fn mirror(&mut self, _other: &NoActors) {
+        match *self {}
+    }
+    fn is_interpolated(&self) -> bool {
+        match *self {}
+    }
+    fn is_predicted(&self) -> bool {
+        match *self {}
+    }
+}
+
+/// The EventType of the fixture Events
+#[derive(Clone, Debug, PartialEq)]
+pub enum TestEvents {
+    /// A Ping, carrying its sequence number
+    Ping(u8),
+    /// A Pong, answering the Ping with the same sequence number
+    Pong(u8),
+    /// A Command moving a Position by the given step
+    Move(i16),
+    /// See [Chat]
+    Chat(Chat),
+    /// See [Note]
+    Note(Note),
+    /// See [Blob]
+    Blob(Blob),
+    /// See [Block]
+    Block(Block),
+}
+
+impl EventType for TestEvents {
+    fn write(&self, buffer: &mut Vec<u8>) {
+        match self {
+            TestEvents::Ping(sequence) => Ping(*sequence).write(buffer),
+            TestEvents::Pong(sequence) => Pong(*sequence).write(buffer),
+            TestEvents::Move(step) => Move(*step).write(buffer),
+            TestEvents::Chat(chat) => Event::write(chat, buffer),
+            TestEvents::Note(note) => Event::write(note, buffer),
+            TestEvents::Blob(blob) => Event::write(blob, buffer),
+            TestEvents::Block(block) => Event::write(block, buffer),
+        }
+    }
+    fn get_type_id(&self) -> TypeId {
+        match self {
+            TestEvents::Ping(_) => return TypeId::of::<Ping>(),
+            TestEvents::Pong(_) => return TypeId::of::<Pong>(),
+            TestEvents::Move(_) => return TypeId::of::<Move>(),
+            TestEvents::Chat(_) => return TypeId::of::<Chat>(),
+            TestEvents::Note(_) => return TypeId::of::<Note>(),
+            TestEvents::Blob(_) => return TypeId::of::<Blob>(),
+            TestEvents::Block(_) => return TypeId::of::<Block>(),
+        }
+    }
+}
+
+/// A Ping, carrying its sequence number
+#[derive(Clone)]
+pub struct Ping(pub u8);
+
+impl Event<TestEvents> for Ping {
+    fn is_guaranteed(&self) -> bool {
+        return false;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.push(self.0);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Ping(self.0);
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Ping>();
+    }
+}
+
+/// A Pong, answering the Ping with the same sequence number
+#[derive(Clone)]
+pub struct Pong(pub u8);
+
+impl Event<TestEvents> for Pong {
+    fn is_guaranteed(&self) -> bool {
+        return false;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.push(self.0);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Pong(self.0);
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Pong>();
+    }
+}
+
+/// A Command moving a Position by the given step
+#[derive(Clone)]
+pub struct Move(pub i16);
+
+impl Event<TestEvents> for Move {
+    fn is_guaranteed(&self) -> bool {
+        return false;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        byte_order::write_u16(out_bytes, self.0 as u16);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Move(self.0);
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Move>();
+    }
+}
+
+/// A guaranteed line of text, written as its length, followed by the text
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chat(pub String);
+
+impl Event<TestEvents> for Chat {
+    fn is_guaranteed(&self) -> bool {
+        return true;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.push(self.0.len() as u8);
+        out_bytes.extend_from_slice(self.0.as_bytes());
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Chat(self.clone());
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Chat>();
+    }
+}
+
+/// Reads a Chat
+pub struct ChatBuilder;
+
+impl EventBuilder<TestEvents> for ChatBuilder {
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Chat>();
+    }
+    fn build(&self, reader: &mut PacketReader) -> TestEvents {
+        let length = reader.read_u8();
+        let mut text = String::new();
+        for _ in 0..length {
+            text.push(reader.read_u8() as char);
+        }
+        return TestEvents::Chat(Chat(text));
+    }
+}
+
+/// The first bytes of a Chat's payload, sent as a Chat
+#[derive(Clone)]
+pub struct TruncatedChat(pub Vec<u8>);
+
+impl Event<TestEvents> for TruncatedChat {
+    fn is_guaranteed(&self) -> bool {
+        return false;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.extend_from_slice(&self.0);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Chat(Chat(String::new()));
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Chat>();
+    }
+}
+
+/// A guaranteed Event carrying only its id, written as a u16
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    /// The id the Note is told apart by
+    pub id: u16,
+}
+
+impl Event<TestEvents> for Note {
+    fn is_guaranteed(&self) -> bool {
+        return true;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        byte_order::write_u16(out_bytes, self.id);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Note(self.clone());
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Note>();
+    }
+}
+
+/// Reads a Note
+pub struct NoteBuilder;
+
+impl EventBuilder<TestEvents> for NoteBuilder {
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Note>();
+    }
+    fn build(&self, reader: &mut PacketReader) -> TestEvents {
+        return TestEvents::Note(Note {
+            id: byte_order::read_u16(reader),
+        });
+    }
+}
+
+/// A guaranteed Event written as the given number of zeroes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blob {
+    /// The number of bytes the Blob is written as
+    pub size: usize,
+}
+
+impl Event<TestEvents> for Blob {
+    fn is_guaranteed(&self) -> bool {
+        return true;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.extend_from_slice(&vec![0; self.size]);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Blob(self.clone());
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Blob>();
+    }
+}
+
+/// Builds an empty Blob, without reading anything
+pub struct BlobBuilder;
+
+impl EventBuilder<TestEvents> for BlobBuilder {
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Blob>();
+    }
+    fn build(&self, _reader: &mut PacketReader) -> TestEvents {
+        return TestEvents::Blob(Blob { size: 0 });
+    }
+}
+
+/// An unguaranteed Event, written as its id & size, padded out to the size
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    /// The id the Block is told apart by
+    pub id: u16,
+    /// The number of bytes the Block is written as, at least 4
+    pub size: u16,
+}
+
+impl Event<TestEvents> for Block {
+    fn is_guaranteed(&self) -> bool {
+        return false;
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        byte_order::write_u16(out_bytes, self.id);
+        byte_order::write_u16(out_bytes, self.size);
+        out_bytes.resize(out_bytes.len() + self.size as usize - 4, 0);
+    }
+    fn get_typed_copy(&self) -> TestEvents {
+        return TestEvents::Block(self.clone());
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Block>();
+    }
+}
+
+/// Reads a Block
+pub struct BlockBuilder;
+
+impl EventBuilder<TestEvents> for BlockBuilder {
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Block>();
+    }
+    fn build(&self, reader: &mut PacketReader) -> TestEvents {
+        let id = byte_order::read_u16(reader);
+        let size = byte_order::read_u16(reader);
+        for _ in 4..size {
+            reader.read_u8();
+        }
+        return TestEvents::Block(Block { id, size });
+    }
+}
+
+/// The ActorType of the fixture Actors
+#[derive(Clone)]
+pub enum TestActors {
+    /// See [Prop]
+    Prop(Rc<RefCell<Prop>>),
+    /// See [Gauge]
+    Gauge(Rc<RefCell<Gauge>>),
+    /// See [Position]
+    Position(Rc<RefCell<Position>>),
+}
+
+impl TestActors {
+    /// Creates a new Prop
+    pub fn new_prop() -> Self {
+        return TestActors::Prop(Rc::new(RefCell::new(Prop)));
+    }
+
+    /// Creates a new Gauge, with every Property at 0
+    pub fn new_gauge() -> Self {
+        return TestActors::Gauge(Rc::new(RefCell::new(Gauge::new())));
+    }
+
+    /// Creates a new Position, at the given value
+    pub fn new_position(value: i16) -> Self {
+        return TestActors::Position(Rc::new(RefCell::new(Position(value))));
+    }
+}
+
+impl ActorType for TestActors {
+    fn read_full(&mut self, reader: &mut PacketReader, packet_index: u16) {
+        self.inner_ref()
+            .borrow_mut()
+            .read_full(reader, packet_index);
+    }
+    fn read_partial(
+        &mut self,
+        state_mask: &StateMask,
+        reader: &mut PacketReader,
+        packet_index: u16,
+    ) {
+        self.inner_ref()
+            .borrow_mut()
+            .read_partial(state_mask, reader, packet_index);
+    }
+    fn inner_ref(&self) -> Rc<RefCell<dyn Actor<TestActors>>> {
+        match self {
+            TestActors::Prop(prop) => return prop.clone(),
+            TestActors::Gauge(gauge) => return gauge.clone(),
+            TestActors::Position(position) => return position.clone(),
+        }
+    }
+    fn equals(&self, _other: &TestActors) -> bool {
+        return true;
+    }
+    fn equals_prediction(&self, _other: &TestActors) -> bool {
+        return true;
+    }
+    fn set_to_interpolation(&mut self, _old: &TestActors, _new: &TestActors, _fraction: f32) {}
+    fn mirror(&mut self, _other: &TestActors) {}
+    fn is_interpolated(&self) -> bool {
+        return false;
+    }
+    fn is_predicted(&self) -> bool {
+        return self.inner_ref().borrow().is_predicted();
+    }
+}
+
+/// An Actor with no state, written as a single byte
+pub struct Prop;
+
+impl Actor<TestActors> for Prop {
+    fn get_state_mask_size(&self) -> u8 {
+        return 1;
+    }
+    fn get_typed_copy(&self) -> TestActors {
+        return TestActors::new_prop();
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Prop>();
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        out_bytes.push(0);
+    }
+    fn write_partial(&self, _state_mask: &StateMask, out_bytes: &mut Vec<u8>) {
+        out_bytes.push(0);
+    }
+    fn read_full(&mut self, _reader: &mut PacketReader, _packet_index: u16) {}
+    fn read_partial(
+        &mut self,
+        _state_mask: &StateMask,
+        _reader: &mut PacketReader,
+        _packet_index: u16,
+    ) {
+    }
+    fn set_mutator(&mut self, _mutator: &Rc<RefCell<dyn ActorMutator>>) {}
+    fn is_interpolated(&self) -> bool {
+        return false;
+    }
+    fn is_predicted(&self) -> bool {
+        return false;
+    }
+    fn get_actor_refs(&self) -> Vec<ActorRef> {
+        return Vec::new();
+    }
+}
+
+/// An Actor with a few Properties, written in the order of their bits
+pub struct Gauge {
+    /// The Properties of the Gauge, the index of each being its bit
+    pub properties: Vec<Property<u8>>,
+}
+
+impl Gauge {
+    /// Creates a new Gauge, with every Property at 0
+    pub fn new() -> Self {
+        return Gauge {
+            properties: (0..4).map(|index| Property::new(0, index)).collect(),
+        };
+    }
+
+    /// Gets the value of every Property, in the order of their bits
+    pub fn get_values(&self) -> Vec<u8> {
+        return self
+            .properties
+            .iter()
+            .map(|property| *property.get())
+            .collect();
+    }
+}
+
+impl Default for Gauge {
+    fn default() -> Self {
+        return Gauge::new();
+    }
+}
+
+impl Actor<TestActors> for Gauge {
+    fn get_state_mask_size(&self) -> u8 {
+        return 1;
+    }
+    fn get_typed_copy(&self) -> TestActors {
+        return TestActors::Gauge(Rc::new(RefCell::new(Gauge {
+            properties: self.properties.clone(),
+        })));
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Gauge>();
+    }
+    fn write(&self, out_bytes: &mut Vec<u8>) {
+        for property in self.properties.iter() {
+            property.write(out_bytes);
+        }
+    }
+    fn write_partial(&self, state_mask: &StateMask, out_bytes: &mut Vec<u8>) {
+        for (index, property) in self.properties.iter().enumerate() {
+            if state_mask.get_bit(index as u8) == Some(true) {
+                property.write(out_bytes);
+            }
+        }
+    }
+    fn read_full(&mut self, reader: &mut PacketReader, packet_index: u16) {
+        for property in self.properties.iter_mut() {
+            property.read(reader, packet_index);
+        }
+    }
+    fn read_partial(
+        &mut self,
+        state_mask: &StateMask,
+        reader: &mut PacketReader,
+        packet_index: u16,
+    ) {
+        for (index, property) in self.properties.iter_mut().enumerate() {
+            if state_mask.get_bit(index as u8) == Some(true) {
+                property.read(reader, packet_index);
+            }
+        }
+    }
+    fn set_mutator(&mut self, _mutator: &Rc<RefCell<dyn ActorMutator>>) {}
+    fn is_interpolated(&self) -> bool {
+        return false;
+    }
+    fn is_predicted(&self) -> bool {
+        return false;
+    }
+    fn get_actor_refs(&self) -> Vec<ActorRef> {
+        return Vec::new();
+    }
+}
+
+/// A predicted Actor holding a single value, moved by Move Commands. Its
+/// value isn't written, as only the Commands moving it are
+pub struct Position(pub i16);
+
+impl Actor<TestActors> for Position {
+    fn get_state_mask_size(&self) -> u8 {
+        return 1;
+    }
+    fn get_typed_copy(&self) -> TestActors {
+        return TestActors::new_position(self.0);
+    }
+    fn get_type_id(&self) -> TypeId {
+        return TypeId::of::<Position>();
+    }
+    fn write(&self, _out_bytes: &mut Vec<u8>) {}
+    fn write_partial(&self, _state_mask: &StateMask, _out_bytes: &mut Vec<u8>) {}
+    fn read_full(&mut self, _reader: &mut PacketReader, _packet_index: u16) {}
+    fn read_partial(
+        &mut self,
+        _state_mask: &StateMask,
+        _reader: &mut PacketReader,
+        _packet_index: u16,
+    ) {
+    }
+    fn set_mutator(&mut self, _mutator: &Rc<RefCell<dyn ActorMutator>>) {}
+    fn is_interpolated(&self) -> bool {
+        return false;
+    }
+    fn is_predicted(&self) -> bool {
+        return true;
+    }
+    fn get_actor_refs(&self) -> Vec<ActorRef> {
+        return Vec::new();
+    }
+}
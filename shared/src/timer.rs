@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use crate::{utils::duration_between, Instant};
+
+/// A Timer which rings once a given Duration has passed since it was last
+/// reset, as told by the current moments it's given, so that it runs on the
+/// Clock of the host it belongs to
+#[derive(Debug)]
+pub struct Timer {
+    duration: Duration,
+    last: Instant,
+    rung_manually: bool,
+}
+
+impl Timer {
+    /// Creates a new Timer, started at the given moment, which rings after
+    /// the given Duration
+    pub fn new(duration: Duration, now: &Instant) -> Self {
+        return Timer {
+            duration,
+            last: now.clone(),
+            rung_manually: false,
+        };
+    }
+
+    /// Restarts the Timer from the given moment
+    pub fn reset(&mut self, now: &Instant) {
+        self.last = now.clone();
+        self.rung_manually = false;
+    }
+
+    /// Gets whether the Timer's Duration has passed as of the given moment,
+    /// or it has been rung manually, since it was last reset
+    pub fn ringing(&self, now: &Instant) -> bool {
+        return self.rung_manually || duration_between(&self.last, now) > self.duration;
+    }
+
+    /// Rings the Timer until it's next reset
+    pub fn ring_manual(&mut self) {
+        self.rung_manually = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Timer;
+    use crate::{Clock, ManualClock};
+
+    #[test]
+    fn rings_once_its_duration_has_passed() {
+        let clock = ManualClock::new();
+        let mut timer = Timer::new(Duration::from_millis(100), &clock.now());
+        clock.advance(Duration::from_millis(100));
+        assert!(!timer.ringing(&clock.now()));
+        clock.advance(Duration::from_millis(1));
+        assert!(timer.ringing(&clock.now()));
+
+        timer.reset(&clock.now());
+        assert!(!timer.ringing(&clock.now()));
+        timer.ring_manual();
+        assert!(timer.ringing(&clock.now()));
+    }
+}
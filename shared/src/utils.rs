@@ -1,4 +1,14 @@
-use crate::{packet_type::PacketType, standard_header::StandardHeader};
+use std::{net::SocketAddr, time::Duration};
+
+use byteorder::WriteBytesExt;
+
+use crate::{
+    byte_order,
+    packet_padding::{pad_packet, PacketPadding},
+    packet_type::PacketType,
+    standard_header::StandardHeader,
+    Instant,
+};
 
 /// Write a connectionless packet, that is, one that does not rely on
 /// information normally retrieved from the connection
@@ -14,9 +24,109 @@ pub fn write_connectionless_payload(packet_type: PacketType, payload: &[u8]) ->
         .into_boxed_slice()
 }
 
+/// Write a connectionless packet, as `write_connectionless_payload()` does,
+/// padded up to the given size in bytes, unless it would already be larger
+pub fn write_padded_connectionless_payload(
+    packet_type: PacketType,
+    payload: &[u8],
+    padded_size: usize,
+) -> Box<[u8]> {
+    let packet = write_connectionless_payload(packet_type, payload);
+    match PacketPadding::ToSize(padded_size as u16).get_padded_size(packet.len(), padded_size) {
+        Some(padded_size) => return pad_packet(&packet, padded_size),
+        None => return packet,
+    }
+}
+
 /// Strip the standard header off of a packet's payload and retrieve the payload
 /// bytes
 pub fn read_headerless_payload(payload: &[u8]) -> Box<[u8]> {
     let (_, stripped_message) = StandardHeader::read(payload);
     stripped_message
 }
+
+/// Write the full identity of a socket address into a byte stream, so that it
+/// can be hashed. IPv4 & IPv6 addresses never produce the same bytes, and the
+/// scope id of an IPv6 address is included, so that link-local addresses on
+/// different interfaces are told apart
+pub fn write_socket_address(address: &SocketAddr, buffer: &mut Vec<u8>) {
+    match address {
+        SocketAddr::V4(address_v4) => {
+            buffer.write_u8(4).unwrap();
+            buffer.extend_from_slice(&address_v4.ip().octets());
+        }
+        SocketAddr::V6(address_v6) => {
+            buffer.write_u8(6).unwrap();
+            buffer.extend_from_slice(&address_v6.ip().octets());
+            byte_order::write_u32(buffer, address_v6.scope_id());
+        }
+    }
+    byte_order::write_u16(buffer, address.port());
+}
+
+/// Get the time from one Instant to a later one, or zero if it is actually
+/// earlier. Natively the Instants are subtracted exactly, so that the result
+/// doesn't depend on when it's worked out. Instants can't be subtracted in the
+/// browser, so there this works from the time elapsed since & remaining until
+/// each of them, which also holds for Instants that have been moved into the
+/// future
+pub fn duration_between(earlier: &Instant, later: &Instant) -> Duration {
+    cfg_if::cfg_if! {
+        if #[cfg(any(feature = "wbindgen", feature = "mquad"))] {
+            return (earlier.elapsed() + later.until())
+                .saturating_sub(later.elapsed() + earlier.until());
+        } else {
+            return later.get_inner().saturating_duration_since(earlier.get_inner());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+
+    use super::{write_padded_connectionless_payload, write_socket_address};
+    use crate::{PacketType, StandardHeader};
+
+    fn address_bytes(address: &SocketAddr) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_socket_address(address, &mut bytes);
+        return bytes;
+    }
+
+    #[test]
+    fn padded_connectionless_packets_read_back() {
+        let packet = write_padded_connectionless_payload(PacketType::Query, b"info", 508);
+        assert_eq!(packet.len(), 508);
+        let (header, payload) = StandardHeader::read(&packet);
+        assert_eq!(header.packet_type(), PacketType::Query);
+        assert_eq!(payload.as_ref(), b"info");
+
+        // packets already larger than the size aren't padded
+        let packet = write_padded_connectionless_payload(PacketType::Query, &[1; 600], 508);
+        assert_eq!(packet.len(), StandardHeader::bytes_number() + 600);
+    }
+
+    #[test]
+    fn families_are_distinct() {
+        let v4: SocketAddr = (Ipv4Addr::new(127, 0, 0, 1), 14191).into();
+        let v4_mapped: SocketAddr = (Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped(), 14191).into();
+        assert_ne!(address_bytes(&v4), address_bytes(&v4_mapped));
+    }
+
+    #[test]
+    fn scope_id_is_included() {
+        let ip: Ipv6Addr = "fe80::1".parse().unwrap();
+        let first: SocketAddr = SocketAddrV6::new(ip, 14191, 0, 1).into();
+        let second: SocketAddr = SocketAddrV6::new(ip, 14191, 0, 2).into();
+        assert_ne!(address_bytes(&first), address_bytes(&second));
+        assert_eq!(address_bytes(&first), address_bytes(&first.clone()));
+    }
+
+    #[test]
+    fn port_is_included() {
+        let first: SocketAddr = (Ipv6Addr::LOCALHOST, 14191).into();
+        let second: SocketAddr = (Ipv6Addr::LOCALHOST, 14192).into();
+        assert_ne!(address_bytes(&first), address_bytes(&second));
+    }
+}
@@ -0,0 +1,299 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use naia_socket_shared::PacketReader;
+
+/// The reason bytes could not be read as a varint
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VarintError {
+    /// The bytes ended before the last byte of the varint
+    Truncated,
+    /// The varint runs on for more bytes than the type can ever need, or holds
+    /// a value too large for the type
+    Overlong,
+    /// The varint ends with a byte carrying no bits of the value, so the same
+    /// value has a shorter encoding, which is the only one accepted
+    NonCanonical,
+}
+
+// Set on every byte of a varint but the last
+const CONTINUATION_BIT: u8 = 0x80;
+
+// The bits of the value held in each byte
+const VALUE_BITS: u8 = 0x7f;
+
+/// Gets the number of bytes the given value takes up when written as a varint
+pub fn encoded_len(value: u64) -> usize {
+    let significant_bits = 64 - value.leading_zeros().min(63) as usize;
+    return significant_bits.div_ceil(7);
+}
+
+/// Maps a signed value onto an unsigned one, so that values close to zero,
+/// whether positive or negative, are written in few bytes
+pub fn zigzag_encode(value: i64) -> u64 {
+    return ((value << 1) ^ (value >> 63)) as u64;
+}
+
+/// Maps a value written by `zigzag_encode()` back onto the signed value
+pub fn zigzag_decode(value: u64) -> i64 {
+    return (value >> 1) as i64 ^ -((value & 1) as i64);
+}
+
+/// Writes a u16 as a varint, taking up 1 to 3 bytes
+pub fn write_u16(value: u16, out_bytes: &mut Vec<u8>) {
+    write(u64::from(value), out_bytes);
+}
+
+/// Writes a u32 as a varint, taking up 1 to 5 bytes
+pub fn write_u32(value: u32, out_bytes: &mut Vec<u8>) {
+    write(u64::from(value), out_bytes);
+}
+
+/// Writes a u64 as a varint, taking up 1 to 10 bytes
+pub fn write_u64(value: u64, out_bytes: &mut Vec<u8>) {
+    write(value, out_bytes);
+}
+
+/// Writes an i16 as a zig-zag encoded varint, taking up 1 to 3 bytes
+pub fn write_i16(value: i16, out_bytes: &mut Vec<u8>) {
+    write(zigzag_encode(i64::from(value)), out_bytes);
+}
+
+/// Writes an i32 as a zig-zag encoded varint, taking up 1 to 5 bytes
+pub fn write_i32(value: i32, out_bytes: &mut Vec<u8>) {
+    write(zigzag_encode(i64::from(value)), out_bytes);
+}
+
+/// Writes an i64 as a zig-zag encoded varint, taking up 1 to 10 bytes
+pub fn write_i64(value: i64, out_bytes: &mut Vec<u8>) {
+    write(zigzag_encode(value), out_bytes);
+}
+
+/// Reads a u16 written as a varint from the start of the given bytes,
+/// returning it along with the number of bytes it took up
+pub fn read_u16(bytes: &[u8]) -> Result<(u16, usize), VarintError> {
+    let (value, length) = read(bytes, 16)?;
+    return Ok((value as u16, length));
+}
+
+/// Reads a u32 written as a varint from the start of the given bytes,
+/// returning it along with the number of bytes it took up
+pub fn read_u32(bytes: &[u8]) -> Result<(u32, usize), VarintError> {
+    let (value, length) = read(bytes, 32)?;
+    return Ok((value as u32, length));
+}
+
+/// Reads a u64 written as a varint from the start of the given bytes,
+/// returning it along with the number of bytes it took up
+pub fn read_u64(bytes: &[u8]) -> Result<(u64, usize), VarintError> {
+    return read(bytes, 64);
+}
+
+/// Reads an i16 written as a zig-zag encoded varint from the start of the
+/// given bytes, returning it along with the number of bytes it took up
+pub fn read_i16(bytes: &[u8]) -> Result<(i16, usize), VarintError> {
+    let (value, length) = read(bytes, 16)?;
+    return Ok((zigzag_decode(value) as i16, length));
+}
+
+/// Reads an i32 written as a zig-zag encoded varint from the start of the
+/// given bytes, returning it along with the number of bytes it took up
+pub fn read_i32(bytes: &[u8]) -> Result<(i32, usize), VarintError> {
+    let (value, length) = read(bytes, 32)?;
+    return Ok((zigzag_decode(value) as i32, length));
+}
+
+/// Reads an i64 written as a zig-zag encoded varint from the start of the
+/// given bytes, returning it along with the number of bytes it took up
+pub fn read_i64(bytes: &[u8]) -> Result<(i64, usize), VarintError> {
+    let (value, length) = read(bytes, 64)?;
+    return Ok((zigzag_decode(value), length));
+}
+
+/// Reads a varint from a PacketReader with one of the read functions above,
+/// such as `varint::read_from(reader, varint::read_u16)`, moving the reader
+/// past it. The reader is left where it was if the varint can't be read
+#[cfg(feature = "std")]
+pub fn read_from<T>(
+    reader: &mut PacketReader,
+    read: fn(&[u8]) -> Result<(T, usize), VarintError>,
+) -> Result<T, VarintError> {
+    let buffer = reader.get_buffer();
+    let start = (reader.get_cursor().position() as usize).min(buffer.len());
+    let (value, length) = read(&buffer[start..])?;
+    reader.get_cursor().set_position((start + length) as u64);
+    return Ok(value);
+}
+
+// writes the value 7 bits at a time, lowest bits first
+fn write(mut value: u64, out_bytes: &mut Vec<u8>) {
+    loop {
+        let byte = value as u8 & VALUE_BITS;
+        value >>= 7;
+        if value == 0 {
+            out_bytes.push(byte);
+            return;
+        }
+        out_bytes.push(byte | CONTINUATION_BIT);
+    }
+}
+
+// reads a varint holding a value of the given number of bits, rejecting any
+// encoding but the shortest one of a value which fits into them
+fn read(bytes: &[u8], bits: u32) -> Result<(u64, usize), VarintError> {
+    let max_length = bits.div_ceil(7) as usize;
+    let mut value: u64 = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        let shift = 7 * index as u32;
+        let group = u64::from(byte & VALUE_BITS);
+        let is_last = byte & CONTINUATION_BIT == 0;
+
+        if index + 1 == max_length && (!is_last || group >> (bits - shift) != 0) {
+            return Err(VarintError::Overlong);
+        }
+        value |= group << shift;
+        if is_last {
+            if index > 0 && group == 0 {
+                return Err(VarintError::NonCanonical);
+            }
+            return Ok((value, index + 1));
+        }
+    }
+    Err(VarintError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{
+        encoded_len, read_i16, read_i32, read_i64, read_u16, read_u32, read_u64, write_i16,
+        write_i32, write_i64, write_u16, write_u32, write_u64, zigzag_decode, zigzag_encode,
+        VarintError,
+    };
+
+    // every power of two & its neighbours, followed by a spread of values
+    // between them, which together cover every encoded length
+    fn sample_u64s() -> Vec<u64> {
+        let mut values = Vec::new();
+        for bit in 0..64 {
+            let power = 1u64 << bit;
+            values.push(power - 1);
+            values.push(power);
+            values.push(power + 1);
+        }
+        values.push(u64::MAX);
+        let mut value: u64 = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..10_000 {
+            value = value
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            values.push(value >> (value % 64));
+        }
+        return values;
+    }
+
+    #[test]
+    fn u16_round_trips_over_full_range() {
+        for value in 0..=u16::MAX {
+            let mut bytes = Vec::new();
+            write_u16(value, &mut bytes);
+            assert_eq!(bytes.len(), encoded_len(u64::from(value)));
+            assert_eq!(read_u16(&bytes), Ok((value, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn i16_round_trips_over_full_range() {
+        for value in i16::MIN..=i16::MAX {
+            let mut bytes = Vec::new();
+            write_i16(value, &mut bytes);
+            assert_eq!(read_i16(&bytes), Ok((value, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn wider_types_round_trip() {
+        for value in sample_u64s() {
+            let mut bytes = Vec::new();
+            write_u64(value, &mut bytes);
+            assert_eq!(bytes.len(), encoded_len(value));
+            assert_eq!(read_u64(&bytes), Ok((value, bytes.len())));
+
+            let narrow = value as u32;
+            bytes.clear();
+            write_u32(narrow, &mut bytes);
+            assert_eq!(read_u32(&bytes), Ok((narrow, bytes.len())));
+
+            let signed = value as i64;
+            bytes.clear();
+            write_i64(signed, &mut bytes);
+            assert_eq!(read_i64(&bytes), Ok((signed, bytes.len())));
+
+            let signed = value as i32;
+            bytes.clear();
+            write_i32(signed, &mut bytes);
+            assert_eq!(read_i32(&bytes), Ok((signed, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn zigzag_keeps_small_magnitudes_small() {
+        assert_eq!(zigzag_encode(0), 0);
+        assert_eq!(zigzag_encode(-1), 1);
+        assert_eq!(zigzag_encode(1), 2);
+        assert_eq!(zigzag_encode(-2), 3);
+        assert_eq!(zigzag_encode(i64::MAX), u64::MAX - 1);
+        assert_eq!(zigzag_encode(i64::MIN), u64::MAX);
+        assert_eq!(zigzag_decode(u64::MAX), i64::MIN);
+    }
+
+    #[test]
+    fn bytes_after_varint_are_left_unread() {
+        assert_eq!(read_u16(&[0x96, 0x01, 0xff]), Ok((150, 2)));
+    }
+
+    #[test]
+    fn truncated_varint_is_rejected() {
+        assert_eq!(read_u16(&[]), Err(VarintError::Truncated));
+        assert_eq!(read_u16(&[0x80]), Err(VarintError::Truncated));
+        assert_eq!(read_u64(&[0xff; 9]), Err(VarintError::Truncated));
+    }
+
+    #[test]
+    fn overlong_varint_is_rejected() {
+        // largest values, in as many bytes as the type can need
+        assert_eq!(read_u16(&[0xff, 0xff, 0x03]), Ok((u16::MAX, 3)));
+        assert_eq!(read_u32(&[0xff, 0xff, 0xff, 0xff, 0x0f]), Ok((u32::MAX, 5)));
+        let mut max_u64 = [0xff; 10];
+        max_u64[9] = 0x01;
+        assert_eq!(read_u64(&max_u64), Ok((u64::MAX, 10)));
+
+        // values past the largest
+        assert_eq!(read_u16(&[0xff, 0xff, 0x04]), Err(VarintError::Overlong));
+        assert_eq!(
+            read_u32(&[0xff, 0xff, 0xff, 0xff, 0x1f]),
+            Err(VarintError::Overlong)
+        );
+        max_u64[9] = 0x02;
+        assert_eq!(read_u64(&max_u64), Err(VarintError::Overlong));
+
+        // more bytes than the type can need
+        assert_eq!(
+            read_u16(&[0x80, 0x80, 0x80, 0x01]),
+            Err(VarintError::Overlong)
+        );
+        assert_eq!(read_u64(&[0x80; 11]), Err(VarintError::Overlong));
+    }
+
+    #[test]
+    fn non_canonical_varint_is_rejected() {
+        assert_eq!(read_u16(&[0x80, 0x00]), Err(VarintError::NonCanonical));
+        assert_eq!(
+            read_u16(&[0x81, 0x80, 0x00]),
+            Err(VarintError::NonCanonical)
+        );
+        assert_eq!(read_i32(&[0xff, 0x00]), Err(VarintError::NonCanonical));
+        assert_eq!(read_u64(&[0x00]), Ok((0, 1)));
+    }
+}
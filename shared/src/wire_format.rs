@@ -0,0 +1,260 @@
+/// The version of the wire format written by this crate. Peers with different
+/// versions can't understand each other's packets, so this must be bumped
+/// whenever the bytes of any packet change, along with the golden fixtures in
+/// `fixtures/wire_format.txt`
+pub const PROTOCOL_VERSION: u16 = 14;
+
+// Golden fixtures, which the bytes of each wire construct, written from pinned
+// inputs, are compared against
+#[cfg(all(test, feature = "std"))]
+const FIXTURES: &str = include_str!("../fixtures/wire_format.txt");
+
+#[cfg(all(test, feature = "std"))]
+fn to_hex(bytes: &[u8]) -> String {
+    return bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<String>>()
+        .join(" ");
+}
+
+/// Panics unless the given bytes match the golden fixture with the given name
+#[cfg(all(test, feature = "std"))]
+pub(crate) fn assert_golden(name: &str, bytes: &[u8]) {
+    // the fixtures are all written big endian
+    if crate::byte_order::WIRE_ORDER_NAME != "big-endian" {
+        return;
+    }
+    let fixture = FIXTURES
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            match parts.next() {
+                Some(line_name) if line_name == name => return parts.next(),
+                _ => return None,
+            }
+        })
+        .unwrap_or_else(|| panic!("no golden fixture named '{}'", name));
+
+    let expected = to_hex(
+        &fixture
+            .split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+            .collect::<Vec<u8>>(),
+    );
+    let actual = to_hex(bytes);
+    if actual != expected {
+        panic!(
+            "wire format of '{}' has changed, if this is intended, update the fixture & bump \
+             PROTOCOL_VERSION\n  expected: {}\n    actual: {}",
+            name, expected, actual
+        );
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{assert_golden, FIXTURES, PROTOCOL_VERSION};
+    use crate::{
+        byte_order,
+        handshake::{self, SessionToken, SESSION_TOKEN_BYTES},
+        packet_padding::pad_packet,
+        utils, varint, DespawnReason, PacketType, RejectReason, StandardHeader, StateMask,
+        Timestamp,
+    };
+
+    fn pinned_timestamp() -> Timestamp {
+        return byte_order::timestamp_from_u64(0x0102_0304_0506_0708);
+    }
+
+    fn pinned_digest() -> Vec<u8> {
+        return (0..32).collect();
+    }
+
+    fn pinned_session_token() -> SessionToken {
+        let mut session_token: SessionToken = [0; SESSION_TOKEN_BYTES];
+        for (index, byte) in session_token.iter_mut().enumerate() {
+            *byte = 0xa0 + index as u8;
+        }
+        return session_token;
+    }
+
+    // one Event type, with the NaiaId 3, at the second version, & no Actor
+    // types past the first version
+    const PINNED_SCHEMA_VERSIONS: &[u8] = &[0x01, 0x03, 0x02, 0x00];
+
+    // every type at the first version
+    const NO_SCHEMA_VERSIONS: &[u8] = &[0x00, 0x00];
+
+    #[test]
+    fn fixtures_match_protocol_version() {
+        let version_line = format!("protocol_version {}", PROTOCOL_VERSION);
+        assert!(
+            FIXTURES.lines().any(|line| line == version_line),
+            "golden fixtures were not written for protocol version {}",
+            PROTOCOL_VERSION
+        );
+    }
+
+    #[test]
+    fn fixed_width_integers() {
+        let mut bytes = Vec::new();
+        byte_order::write_u16(&mut bytes, 0x0102);
+        byte_order::write_u32(&mut bytes, 0x0304_0506);
+        byte_order::write_u64(&mut bytes, 0x0708_090a_0b0c_0d0e);
+        assert_golden("fixed_width_integers", &bytes);
+    }
+
+    #[test]
+    fn headers() {
+        let mut bytes = Vec::new();
+        StandardHeader::new(
+            PacketType::Data,
+            0x0102,
+            0xfffe,
+            0x8000_0001,
+            0x1234,
+            0x1233,
+        )
+        .write(&mut bytes);
+        assert_golden("standard_header", &bytes);
+
+        bytes.push(0xaa);
+        assert_golden("padded_header", &pad_packet(&bytes, 20));
+
+        assert_golden(
+            "connectionless_header",
+            &utils::write_connectionless_payload(PacketType::ClientChallengeRequest, &[0xaa]),
+        );
+    }
+
+    #[test]
+    fn state_mask() {
+        let mut state_mask = StateMask::new(2);
+        state_mask.set_bit(0, true);
+        state_mask.set_bit(9, true);
+        let mut bytes = Vec::new();
+        state_mask.write(&mut bytes);
+        assert_golden("state_mask", &bytes);
+    }
+
+    #[test]
+    fn varints() {
+        let mut bytes = Vec::new();
+        for value in [0, 1, 127, 128, 300, u16::MAX].iter() {
+            varint::write_u16(*value, &mut bytes);
+        }
+        assert_golden("varint_u16s", &bytes);
+
+        bytes.clear();
+        varint::write_u32(u32::MAX, &mut bytes);
+        varint::write_u64(u64::MAX, &mut bytes);
+        assert_golden("varint_wide", &bytes);
+
+        bytes.clear();
+        for value in [0, -1, 1, -64, 64, i16::MIN].iter() {
+            varint::write_i16(*value, &mut bytes);
+        }
+        assert_golden("varint_zigzag", &bytes);
+    }
+
+    #[test]
+    fn despawn_reasons() {
+        let mut bytes = Vec::new();
+        DespawnReason::OutOfScope.write(&mut bytes);
+        DespawnReason::Destroyed.write(&mut bytes);
+        DespawnReason::Custom(7).write(&mut bytes);
+        assert_golden("despawn_reasons", &bytes);
+    }
+
+    #[test]
+    fn handshake_packets() {
+        let timestamp = pinned_timestamp();
+        let digest = pinned_digest();
+        let session_token = pinned_session_token();
+
+        assert_golden(
+            "challenge_request",
+            &handshake::write_challenge_request(&timestamp),
+        );
+        assert_golden(
+            "challenge_response",
+            &handshake::write_challenge_response(0x0a0b, &timestamp, &digest),
+        );
+        assert_golden(
+            "connect_request",
+            &handshake::write_connect_request(
+                &timestamp,
+                &digest,
+                false,
+                false,
+                None,
+                NO_SCHEMA_VERSIONS,
+                None,
+            ),
+        );
+        assert_golden(
+            "connect_request_with_auth",
+            &handshake::write_connect_request(
+                &timestamp,
+                &digest,
+                true,
+                false,
+                None,
+                PINNED_SCHEMA_VERSIONS,
+                Some((3, b"\x02hi")),
+            ),
+        );
+        assert_golden(
+            "connect_request_resuming",
+            &handshake::write_connect_request(
+                &timestamp,
+                &digest,
+                true,
+                true,
+                Some((&session_token, &[])),
+                NO_SCHEMA_VERSIONS,
+                Some((3, b"\x02hi")),
+            ),
+        );
+        assert_golden(
+            "connect_request_with_early_data",
+            &handshake::write_connect_request(
+                &timestamp,
+                &digest,
+                false,
+                false,
+                Some((
+                    &session_token,
+                    &[handshake::EarlyData {
+                        client_tick: 0x0102,
+                        perceived_tick: 0x00ff,
+                        payload: vec![0xaa, 0xbb],
+                    }],
+                )),
+                NO_SCHEMA_VERSIONS,
+                None,
+            ),
+        );
+        assert_golden(
+            "connect_response",
+            &handshake::write_connect_response(true, false, false, None, PINNED_SCHEMA_VERSIONS),
+        );
+        assert_golden(
+            "connect_response_resumed",
+            &handshake::write_connect_response(
+                true,
+                true,
+                true,
+                Some(&session_token),
+                NO_SCHEMA_VERSIONS,
+            ),
+        );
+        assert_golden(
+            "reject_response",
+            &handshake::write_reject_response(RejectReason::DuplicateConnection),
+        );
+        assert_golden("disconnect", &handshake::write_disconnect("bye"));
+    }
+}
@@ -0,0 +1,45 @@
+//! Generates the conformance fixtures, or verifies fixtures written by another
+//! implementation of the protocol:
+//!
+//! ```text
+//! cargo run -p naia-integration-tests --bin conformance -- generate conformance
+//! cargo run -p naia-integration-tests --bin conformance -- verify path/to/fixtures
+//! ```
+
+use std::{env, path::Path, process};
+
+use naia_shared::conformance;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, directory) = match args.as_slice() {
+        [command, directory] => (command.as_str(), Path::new(directory)),
+        _ => usage(),
+    };
+    match command {
+        "generate" => {
+            if let Err(error) = conformance::write_fixtures(directory) {
+                eprintln!("couldn't write fixtures: {}", error);
+                process::exit(1);
+            }
+            println!(
+                "wrote {} fixture files to {}",
+                conformance::CONSTRUCTS.len(),
+                directory.display()
+            );
+        }
+        "verify" => match conformance::verify_fixtures(directory) {
+            Ok(case_count) => println!("{} cases verified", case_count),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        },
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: conformance <generate|verify> <directory>");
+    process::exit(2);
+}
@@ -0,0 +1,26 @@
+//! Writes the schema of the integration tests' protocol, as exported from its
+//! Manifest, to the checked in fixture, or to another path:
+//!
+//! ```text
+//! cargo run -p naia-integration-tests --bin protocol_schema -- tests/fixtures/protocol_schema.json
+//! ```
+
+use std::{env, fs, process};
+
+use naia_integration_tests::manifest_load;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let path = match args.as_slice() {
+        [path] => path,
+        _ => {
+            eprintln!("usage: protocol_schema <path>");
+            process::exit(2);
+        }
+    };
+    if let Err(error) = fs::write(path, manifest_load().export_schema().to_json()) {
+        eprintln!("couldn't write the schema: {}", error);
+        process::exit(1);
+    }
+    println!("wrote the protocol schema to {}", path);
+}
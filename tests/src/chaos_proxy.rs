@@ -0,0 +1,193 @@
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+// the largest datagram relayed
+const MAX_DATAGRAM_SIZE: usize = 2048;
+
+/// How the ChaosProxy mistreats the packets it relays, in both directions
+#[derive(Clone, Copy, Debug)]
+pub struct ChaosConfig {
+    /// The chance of a packet being dropped, from 0 to 1
+    pub loss: f32,
+    /// The chance of a packet which isn't dropped being delivered twice, from
+    /// 0 to 1. Each copy is delayed separately
+    pub duplication: f32,
+    /// The longest a packet is held back for. Each is held back for a random
+    /// time up to this, so that packets sent close together swap places
+    pub max_delay: Duration,
+}
+
+/// Relays UDP packets between a single Client & a Server, dropping, delaying
+/// & duplicating them as it goes, to subject a connection to conditions the
+/// link conditioner can't simulate. The Client connects to the proxy's
+/// address, and the Server sees the proxy as the Client
+pub struct ChaosProxy {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ChaosProxy {
+    /// Starts a ChaosProxy listening on the given address, which relays the
+    /// packets it receives to & from the Server at the given address
+    pub fn start(address: SocketAddr, server_address: SocketAddr, config: ChaosConfig) -> Self {
+        return ChaosProxy::start_seeded(address, server_address, config, 0x2545_f491);
+    }
+
+    /// Starts a ChaosProxy as `start()` does, which decides what happens to
+    /// each packet by the random sequence the given seed starts, so that runs
+    /// with different seeds are mistreated differently
+    pub fn start_seeded(
+        address: SocketAddr,
+        server_address: SocketAddr,
+        config: ChaosConfig,
+        seed: u32,
+    ) -> Self {
+        let client_socket = UdpSocket::bind(address).expect("the proxy address is in use");
+        let server_socket = UdpSocket::bind(SocketAddr::new(address.ip(), 0))
+            .expect("the proxy couldn't bind a socket to relay to the Server from");
+        client_socket.set_nonblocking(true).unwrap();
+        server_socket.set_nonblocking(true).unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            relay(
+                client_socket,
+                server_socket,
+                server_address,
+                config,
+                seed,
+                thread_stop,
+            );
+        });
+
+        ChaosProxy {
+            address,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Gets the address the Client is to connect to
+    pub fn address(&self) -> SocketAddr {
+        return self.address;
+    }
+}
+
+impl Drop for ChaosProxy {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// a packet held back until it is due, along with whether it is on its way to
+// the Server
+struct HeldPacket {
+    due_at: Instant,
+    to_server: bool,
+    bytes: Vec<u8>,
+}
+
+fn relay(
+    client_socket: UdpSocket,
+    server_socket: UdpSocket,
+    server_address: SocketAddr,
+    config: ChaosConfig,
+    seed: u32,
+    stop: Arc<AtomicBool>,
+) {
+    // a xorshift generator never leaves a state of 0
+    let mut random = Random::new(seed.max(1));
+    let mut client_address: Option<SocketAddr> = None;
+    let mut held: Vec<HeldPacket> = Vec::new();
+    let mut buffer = [0; MAX_DATAGRAM_SIZE];
+
+    while !stop.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        let mut received = Vec::new();
+        loop {
+            match client_socket.recv_from(&mut buffer) {
+                Ok((length, from)) => {
+                    client_address = Some(from);
+                    received.push((true, buffer[..length].to_vec()));
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+        loop {
+            match server_socket.recv_from(&mut buffer) {
+                Ok((length, _)) => received.push((false, buffer[..length].to_vec())),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+
+        for (to_server, bytes) in received {
+            if random.chance(config.loss) {
+                continue;
+            }
+            let copies = if random.chance(config.duplication) {
+                2
+            } else {
+                1
+            };
+            for _ in 0..copies {
+                let delay = config.max_delay.mul_f32(random.fraction());
+                held.push(HeldPacket {
+                    due_at: now + delay,
+                    to_server,
+                    bytes: bytes.clone(),
+                });
+            }
+        }
+
+        let (due, waiting): (Vec<HeldPacket>, Vec<HeldPacket>) =
+            held.drain(..).partition(|packet| packet.due_at <= now);
+        held = waiting;
+        for packet in due {
+            if packet.to_server {
+                let _ = server_socket.send_to(&packet.bytes, server_address);
+            } else if let Some(client_address) = client_address {
+                let _ = client_socket.send_to(&packet.bytes, client_address);
+            }
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// a xorshift generator, so that every run of a scenario is mistreated alike
+struct Random {
+    state: u32,
+}
+
+impl Random {
+    fn new(seed: u32) -> Self {
+        Random { state: seed }
+    }
+
+    // a random number from 0 up to, but not including, 1
+    fn fraction(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        return (self.state >> 8) as f32 / (1 << 24) as f32;
+    }
+
+    fn chance(&mut self, probability: f32) -> bool {
+        return self.fraction() < probability;
+    }
+}
@@ -0,0 +1,21 @@
+use crate::TestEvent;
+use naia_derive::Event;
+use naia_shared::{Event, Property};
+
+/// Sent by the Test Server about a User's mascot, only to the Client which has
+/// the mascot in scope, which receives it along with the mascot's key
+#[derive(Event, Clone)]
+#[type_name = "TestEvent"]
+pub struct CheerEvent {
+    pub chant: Property<String>,
+}
+
+impl CheerEvent {
+    fn is_guaranteed() -> bool {
+        true
+    }
+
+    pub fn new(chant: &str) -> CheerEvent {
+        return CheerEvent::new_complete(chant.to_string());
+    }
+}
@@ -0,0 +1,57 @@
+use naia_derive::Actor;
+use naia_shared::{Actor, Property};
+
+use crate::TestActor;
+
+/// The value the Test Server counts every CounterActor up to, one step per
+/// tick, after which it stops changing
+pub const COUNTER_LIMIT: u16 = 20;
+
+#[derive(Actor)]
+#[type_name = "TestActor"]
+pub struct CounterActor {
+    pub value: Property<u16>,
+    /// The value the CounterActor stops at, which Clients on the first build
+    /// don't receive. It's predicted, so that a CounterActor can be assigned
+    /// as a Pawn, as it never changes once the CounterActor is created
+    #[added(version = 2, default = COUNTER_LIMIT)]
+    #[predict]
+    pub limit: Property<u16>,
+}
+
+impl CounterActor {
+    pub fn new() -> CounterActor {
+        return CounterActor::new_complete(0, COUNTER_LIMIT);
+    }
+
+    pub fn step(&mut self) {
+        let value = *self.value.get();
+        if value < *self.limit.get() {
+            self.value.set(value + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{Actor, StateMask};
+
+    use super::{CounterActor, CounterActorProp};
+
+    #[test]
+    fn hidden_properties_are_written_as_defaults() {
+        let mut visibility = StateMask::new(1);
+        visibility.set_bit(CounterActorProp::LIMIT as u8, true);
+        let mut masked = Vec::new();
+        CounterActor::new_complete(0xbeef, 7).write_masked(&visibility, &mut masked);
+        let mut expected = Vec::new();
+        CounterActor::new_complete(0, 7).write(&mut expected);
+        assert_eq!(masked, expected);
+
+        let mut masked = Vec::new();
+        CounterActor::new_complete(0xbeef, 7).write_masked_versioned(&visibility, 1, &mut masked);
+        let mut expected = Vec::new();
+        CounterActor::new_complete(0, 7).write_versioned(1, &mut expected);
+        assert_eq!(masked, expected);
+    }
+}
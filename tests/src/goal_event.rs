@@ -0,0 +1,22 @@
+use crate::TestEvent;
+use naia_derive::Event;
+use naia_shared::{ActorRef, Event, Property};
+
+/// Sent by the Test Server to celebrate a goal, referencing the Actor spawned
+/// for the celebration, which comes into scope a tick after the GoalEvent is
+/// sent
+#[derive(Event, Clone)]
+#[type_name = "TestEvent"]
+pub struct GoalEvent {
+    pub celebration: Property<ActorRef>,
+}
+
+impl GoalEvent {
+    fn is_guaranteed() -> bool {
+        true
+    }
+
+    pub fn new(celebration: ActorRef) -> GoalEvent {
+        return GoalEvent::new_complete(celebration);
+    }
+}
@@ -0,0 +1,77 @@
+//! # Naia Integration Tests
+//! A small protocol, along with a Server & Client running it, which the tests
+//! in this crate use to check that a NaiaServer & NaiaClient from this
+//! workspace work together end to end. Each scenario is a test function,
+//! which drives a TestClient against a TestServer running on its own thread
+
+extern crate naia_derive;
+
+mod auth_event;
+mod chaos_proxy;
+mod cheer_event;
+mod counter_actor;
+mod goal_event;
+mod manifest_load;
+mod protocol_plugins;
+mod reload_event;
+mod scripted_transport;
+mod test_actor;
+mod test_client;
+mod test_event;
+mod test_server;
+mod text_event;
+
+pub mod v1;
+
+pub use auth_event::AuthEvent;
+pub use chaos_proxy::{ChaosConfig, ChaosProxy};
+pub use cheer_event::CheerEvent;
+pub use counter_actor::{CounterActor, COUNTER_LIMIT};
+pub use goal_event::GoalEvent;
+pub use manifest_load::manifest_load;
+pub use protocol_plugins::{ChatPlugin, CorePlugin, GoalPlugin};
+pub use reload_event::ReloadEvent;
+pub use scripted_transport::{
+    Direction, Fault, FaultRule, LoggedPacket, PacketClassifier, PacketMatcher, ScriptedTransport,
+};
+pub use test_actor::TestActor;
+pub use test_client::{get_client_config, TestClient};
+pub use test_event::TestEvent;
+pub use test_server::{
+    barrier_done_of, behind_barrier_of, echo_of, flood_of, tick_payload_of, LoggedEvent, ServerLog,
+    TestServer, BARRIER_EVENT_COUNT, BARRIER_KICK_TEXT, BARRIER_TEXT, CHEER_TEXT, ENCORE_TEXT,
+    EXTEND_TEXT, FLOOD_EVENT_COUNT, FLOOD_TEXT, GOAL_TEXT, HISTORY_LOOKBACK, HOLD_TEXT,
+    KICK_REASON, KICK_TEXT, KNOCK_REPLY, KNOCK_TEXT, LACKS_TYPE_TEXT, NO_SUCH_CHANNEL_TEXT,
+    PAUSE_TEXT, PILOT_TEXT, QUERY_REPLY, QUERY_TEXT, RELOAD_TEXT, RESUME_TEXT, REVEAL_TEXT,
+    STRAY_CHANNEL, STRAY_CHANNEL_TEXT, TICK_PAYLOAD_TEXT,
+};
+pub use text_event::{TextEvent, BUILD};
+
+use std::{net::SocketAddr, time::Duration};
+
+use naia_client::ClientEvent;
+use naia_shared::{ActorType, EventType, LinkConditionerConfig, SharedConfig};
+
+/// How long a scenario waits for any single thing it expects to happen
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Gets the SharedConfig both sides of a scenario use, ticking quickly so the
+/// scenario doesn't take long, and with the given simulated network conditions
+pub fn get_shared_config(link_condition: Option<LinkConditionerConfig>) -> SharedConfig {
+    return SharedConfig::new(Duration::from_millis(20), link_condition);
+}
+
+/// Gets the loopback address of the given port. Each scenario listens on its
+/// own port, so that they can run side by side
+pub fn server_address(port: u16) -> SocketAddr {
+    return SocketAddr::new("127.0.0.1".parse().unwrap(), port);
+}
+
+/// Waits for the given TestClient to connect, returning whether it did before
+/// the TIMEOUT
+pub fn wait_for_connection<T: EventType, U: ActorType>(client: &mut TestClient<T, U>) -> bool {
+    return client.wait_for(TIMEOUT, |_, event| match event {
+        ClientEvent::Connection(_) => true,
+        _ => false,
+    });
+}
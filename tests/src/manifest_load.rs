@@ -0,0 +1,111 @@
+use naia_shared::Manifest;
+
+use crate::{AuthEvent, CheerEvent, CounterActor, GoalEvent, TestActor, TestEvent, TextEvent};
+
+pub fn manifest_load() -> Manifest<TestEvent, TestActor> {
+    let mut manifest = Manifest::<TestEvent, TestActor>::new();
+
+    manifest.register_event(AuthEvent::get_builder());
+    // so that the order TextEvents arrive in can be checked
+    manifest.register_event_on_channel(TextEvent::get_builder(), 0);
+    // so that how long TextEvents take to arrive can be checked
+    manifest
+        .set_event_timestamped::<TextEvent>()
+        .expect("the TextEvent was just registered");
+    manifest.register_actor(CounterActor::get_builder());
+    // so that CounterActors are spawned as only the Properties which have
+    // changed since they were created
+    manifest
+        .register_archetype(
+            "new counter",
+            TestActor::CounterActor(CounterActor::new().wrap()),
+        )
+        .expect("the CounterActor was just registered");
+    // so that a GoalEvent is never received before the Actor it references
+    manifest.register_event(GoalEvent::get_builder());
+    manifest
+        .set_event_actor_ordered::<GoalEvent>()
+        .expect("the GoalEvent was just registered");
+    // so that a CheerEvent is only sent to Clients which have its mascot in
+    // scope
+    manifest.register_event(CheerEvent::get_builder());
+    manifest
+        .set_event_actor_scoped::<CheerEvent>()
+        .expect("the CheerEvent was just registered");
+
+    manifest
+}
+
+#[cfg(test)]
+mod tests {
+    use naia_shared::{Actor, ActorEq, PacketReader, ProtocolSchema, SchemaVersions, StateMask};
+
+    use super::manifest_load;
+    use crate::{CounterActor, TestActor, COUNTER_LIMIT};
+
+    #[test]
+    fn spawns_are_written_against_the_archetype() {
+        let manifest = manifest_load();
+        let mut advertised = Vec::new();
+        manifest.write_schema_versions(&mut advertised);
+        let schema_versions =
+            SchemaVersions::read(&manifest, &mut PacketReader::new(&advertised)).unwrap();
+        assert!(schema_versions.has_archetype(0));
+
+        let counter = CounterActor::new_complete(7, COUNTER_LIMIT);
+        // without an Archetype, an Actor is written as its NaiaId & every
+        // Property
+        let mut full_payload = Vec::new();
+        counter.write(&mut full_payload);
+        let full_size = 2 + full_payload.len();
+
+        let (archetype_id, mut diff_mask, diff_payload) = manifest
+            .get_closest_archetype(0, &counter, &schema_versions)
+            .unwrap();
+        assert_eq!(archetype_id, 0);
+        let mut archetype_bytes = Vec::new();
+        diff_mask.write(&mut archetype_bytes);
+        archetype_bytes.extend_from_slice(&diff_payload);
+        // the ArchetypeId, the diff mask, & only the value
+        let archetype_size = 1 + archetype_bytes.len();
+        assert_eq!((full_size, archetype_size), (8, 6));
+
+        let mut reader = PacketReader::new(&archetype_bytes);
+        let diff_mask = StateMask::read(&mut reader);
+        let rebuilt = manifest
+            .create_archetype_actor(archetype_id, &diff_mask, &mut reader)
+            .unwrap();
+        assert!(!reader.has_more());
+        let TestActor::CounterActor(rebuilt) = &rebuilt;
+        assert_eq!(*rebuilt.borrow().value.get(), 7);
+        assert_eq!(*rebuilt.borrow().limit.get(), COUNTER_LIMIT);
+        assert!(rebuilt.borrow().equals(&counter));
+
+        // an Actor the same as the baseline is written as no Properties at all
+        let (_, diff_mask, diff_payload) = manifest
+            .get_closest_archetype(0, &CounterActor::new(), &schema_versions)
+            .unwrap();
+        assert!(diff_mask.is_clear());
+        assert!(diff_payload.is_empty());
+
+        // nor is any Archetype used with a host which hasn't registered it
+        assert!(manifest
+            .get_closest_archetype(0, &counter, &SchemaVersions::new())
+            .is_none());
+    }
+
+    // a change to the protocol must be made to the checked in schema too, by
+    // running the protocol_schema binary
+    #[test]
+    fn schema_matches_fixture() {
+        let schema = manifest_load().export_schema();
+        assert_eq!(
+            schema.to_json(),
+            include_str!("../fixtures/protocol_schema.json")
+        );
+        assert_eq!(
+            ProtocolSchema::from_json(&schema.to_json()).unwrap(),
+            schema
+        );
+    }
+}
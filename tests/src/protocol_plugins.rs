@@ -0,0 +1,176 @@
+use naia_shared::{Manifest, ProtocolPlugin};
+
+use crate::{AuthEvent, CheerEvent, CounterActor, GoalEvent, TestActor, TestEvent, TextEvent};
+
+/// Registers the types every part of the test protocol relies on: the
+/// AuthEvent, & the CounterActor along with its Archetype
+pub struct CorePlugin;
+
+impl ProtocolPlugin<TestEvent, TestActor> for CorePlugin {
+    fn register(&self, manifest: &mut Manifest<TestEvent, TestActor>) {
+        manifest.register_event(AuthEvent::get_builder());
+        manifest.register_actor(CounterActor::get_builder());
+        manifest
+            .register_archetype(
+                "new counter",
+                TestActor::CounterActor(CounterActor::new().wrap()),
+            )
+            .expect("the CounterActor was just registered");
+    }
+}
+
+/// Registers the TextEvent, sent in order & timestamped
+pub struct ChatPlugin;
+
+impl ProtocolPlugin<TestEvent, TestActor> for ChatPlugin {
+    fn register(&self, manifest: &mut Manifest<TestEvent, TestActor>) {
+        manifest.register_event_on_channel(TextEvent::get_builder(), 0);
+        manifest
+            .set_event_timestamped::<TextEvent>()
+            .expect("the TextEvent was just registered");
+    }
+}
+
+/// Registers the Events sent about Actors: the GoalEvent, never received before
+/// the Actor it references, & the CheerEvent, only sent to Clients which have
+/// the Actor it's about in scope
+pub struct GoalPlugin;
+
+impl ProtocolPlugin<TestEvent, TestActor> for GoalPlugin {
+    fn register(&self, manifest: &mut Manifest<TestEvent, TestActor>) {
+        manifest.register_event(GoalEvent::get_builder());
+        manifest
+            .set_event_actor_ordered::<GoalEvent>()
+            .expect("the GoalEvent was just registered");
+        manifest.register_event(CheerEvent::get_builder());
+        manifest
+            .set_event_actor_scoped::<CheerEvent>()
+            .expect("the CheerEvent was just registered");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{any::TypeId, rc::Rc};
+
+    use naia_shared::{
+        byte_order, Clock, Event, EventPacketWriter, Manifest, MergeError, OutgoingEvent,
+        PacketReader, ProtocolPlugin, SchemaVersions, SystemClock,
+    };
+
+    use super::{ChatPlugin, CorePlugin, GoalPlugin};
+    use crate::{
+        manifest_load, AuthEvent, CheerEvent, CounterActor, GoalEvent, TestActor, TestEvent,
+        TextEvent, COUNTER_LIMIT,
+    };
+
+    fn compose(
+        plugins: &[&dyn ProtocolPlugin<TestEvent, TestActor>],
+        deterministic_ids: bool,
+    ) -> Manifest<TestEvent, TestActor> {
+        let mut manifest = Manifest::new();
+        manifest.set_deterministic_ids(deterministic_ids);
+        for plugin in plugins.iter() {
+            manifest.add_plugin(*plugin).unwrap();
+        }
+        return manifest;
+    }
+
+    // what a host with the given Manifest writes in the handshake, and when
+    // sending an AuthEvent & spawning a CounterActor
+    fn wire_bytes(manifest: &Manifest<TestEvent, TestActor>) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut advertised = Vec::new();
+        manifest.write_schema_versions(&mut advertised);
+        let schema_versions =
+            SchemaVersions::read(manifest, &mut PacketReader::new(&advertised)).unwrap();
+
+        let event: Box<dyn Event<TestEvent>> = Box::new(AuthEvent::new("name", "password"));
+        let now = SystemClock.now();
+        let mut writer = EventPacketWriter::new();
+        assert!(writer.write_event(
+            manifest,
+            &schema_versions,
+            &OutgoingEvent {
+                event: Rc::new(event),
+                ordering: None,
+                size: 0,
+                queued_at: now.clone(),
+                grouped: Vec::new(),
+                trace_id: None,
+                actor: None,
+                queue_index: 0,
+            },
+            &now,
+        ));
+        let mut event_bytes = Vec::new();
+        writer.get_bytes(&mut event_bytes);
+
+        let counter = CounterActor::new_complete(7, COUNTER_LIMIT);
+        let naia_id = manifest
+            .get_actor_naia_id(&TypeId::of::<CounterActor>())
+            .unwrap();
+        let (archetype_id, mut diff_mask, diff_payload) = manifest
+            .get_closest_archetype(naia_id, &counter, &schema_versions)
+            .unwrap();
+        let mut spawn_bytes = Vec::new();
+        byte_order::write_u16(&mut spawn_bytes, archetype_id);
+        diff_mask.write(&mut spawn_bytes);
+        spawn_bytes.extend_from_slice(&diff_payload);
+
+        return (advertised, event_bytes, spawn_bytes);
+    }
+
+    #[test]
+    fn plugins_compose_alike_in_any_order() {
+        let (core, chat, goal) = (&CorePlugin, &ChatPlugin, &GoalPlugin);
+        let orders: [[&dyn ProtocolPlugin<TestEvent, TestActor>; 3]; 6] = [
+            [core, chat, goal],
+            [core, goal, chat],
+            [chat, core, goal],
+            [chat, goal, core],
+            [goal, core, chat],
+            [goal, chat, core],
+        ];
+        let expected = compose(&orders[0], true);
+        for order in orders.iter().skip(1) {
+            let manifest = compose(order, true);
+            assert_eq!(manifest.export_schema(), expected.export_schema());
+            assert_eq!(wire_bytes(&manifest), wire_bytes(&expected));
+        }
+        // ids are given in order of name
+        let naia_id = |type_id| expected.get_event_naia_id(&type_id).unwrap();
+        assert_eq!(naia_id(TypeId::of::<AuthEvent>()), 0);
+        assert_eq!(naia_id(TypeId::of::<CheerEvent>()), 1);
+        assert_eq!(naia_id(TypeId::of::<GoalEvent>()), 2);
+        assert_eq!(naia_id(TypeId::of::<TextEvent>()), 3);
+
+        // otherwise, ids follow the order the plugins are added in, so the
+        // plugins added as the types are registered in manifest_load() give
+        // the same protocol
+        let manifest = compose(&orders[0], false);
+        assert_eq!(manifest.export_schema(), manifest_load().export_schema());
+        assert_ne!(
+            compose(&orders[1], false).export_schema(),
+            manifest.export_schema()
+        );
+    }
+
+    #[test]
+    fn merging_a_type_twice_is_rejected() {
+        let mut manifest = compose(&[&CorePlugin, &ChatPlugin], false);
+        let schema = manifest.export_schema();
+        assert_eq!(
+            manifest.add_plugin(&CorePlugin),
+            Err(MergeError::DuplicateEvent(Some("AuthEvent".to_string())))
+        );
+        // the Manifest is left as it was
+        assert_eq!(manifest.export_schema(), schema);
+
+        let mut other = Manifest::<TestEvent, TestActor>::new();
+        other.register_actor(CounterActor::get_builder());
+        assert_eq!(
+            manifest.merge(other),
+            Err(MergeError::DuplicateActor(Some("CounterActor".to_string())))
+        );
+    }
+}
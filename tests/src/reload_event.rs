@@ -0,0 +1,22 @@
+use crate::TestEvent;
+use naia_derive::Event;
+use naia_shared::{Event, Property};
+
+/// Registered by the Test Server while it's running, once asked to with
+/// `EXTEND_TEXT`, as a reloaded module would, so it isn't in the Manifest
+/// either side starts with
+#[derive(Event, Clone)]
+#[type_name = "TestEvent"]
+pub struct ReloadEvent {
+    pub module: Property<String>,
+}
+
+impl ReloadEvent {
+    fn is_guaranteed() -> bool {
+        true
+    }
+
+    pub fn new(module: &str) -> ReloadEvent {
+        return ReloadEvent::new_complete(module.to_string());
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// the largest datagram relayed
+const MAX_DATAGRAM_SIZE: usize = 2048;
+
+/// Which way a packet crosses a ScriptedTransport
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the Client to the Server
+    ToServer,
+    /// From the Server to the Client
+    ToClient,
+}
+
+/// A closure picking out packets, given the index of each among those
+/// crossing the same way & its bytes as they are on the wire
+pub type PacketClassifier = Box<dyn FnMut(usize, &[u8]) -> bool + Send>;
+
+/// Picks out the packets a FaultRule applies to, among those crossing the
+/// ScriptedTransport the rule's way
+pub enum PacketMatcher {
+    /// The packet with the given index, counting from 0
+    Index(usize),
+    /// Every packet of the given size, in bytes
+    Size(usize),
+    /// Every packet the closure picks out
+    Classifier(PacketClassifier),
+}
+
+/// What a FaultRule does to the packets it applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The packet is dropped
+    Drop,
+    /// Every bit of the byte at the given offset is flipped, if the packet is
+    /// that long
+    Corrupt(usize),
+    /// The packet is delivered twice
+    Duplicate,
+    /// The packet is held back until the next packet crossing the same way has
+    /// been delivered, so that the two swap places
+    SwapWithNext,
+}
+
+/// A fault for a ScriptedTransport to inject into the packets crossing it one
+/// way. Each packet has the first rule which matches it applied, if any does
+pub struct FaultRule {
+    /// The way the packets the rule applies to cross
+    pub direction: Direction,
+    /// Picks out the packets the rule applies to
+    pub matcher: PacketMatcher,
+    /// What the rule does to the packets it applies to
+    pub fault: Fault,
+    /// The most packets the rule applies to, after which it stops matching.
+    /// None applies it to every packet it matches
+    pub limit: Option<usize>,
+}
+
+impl FaultRule {
+    /// Creates a FaultRule applying to every packet it matches
+    pub fn new(direction: Direction, matcher: PacketMatcher, fault: Fault) -> Self {
+        FaultRule {
+            direction,
+            matcher,
+            fault,
+            limit: None,
+        }
+    }
+
+    /// Creates a FaultRule applying only to the first packet it matches
+    pub fn once(direction: Direction, matcher: PacketMatcher, fault: Fault) -> Self {
+        FaultRule {
+            direction,
+            matcher,
+            fault,
+            limit: Some(1),
+        }
+    }
+}
+
+/// What a ScriptedTransport did with a packet crossing it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedPacket {
+    /// The way the packet crossed
+    pub direction: Direction,
+    /// The packet's index among those crossing the same way, counting from 0
+    pub index: usize,
+    /// The size of the packet as it arrived, in bytes
+    pub size: usize,
+    /// The index of the FaultRule applied to the packet, and the fault it
+    /// injected, if any rule matched it
+    pub fault: Option<(usize, Fault)>,
+}
+
+/// Relays UDP packets between a single Client & a Server, injecting exactly
+/// the faults set out in a list of FaultRules, so that a scenario can pin down
+/// which packet is lost, corrupted, duplicated or reordered, where the
+/// ChaosProxy & the link conditioner only inject faults at random. Every
+/// packet relayed is logged, along with the fault injected into it. The Client
+/// connects to the transport's address, and the Server sees the transport as
+/// the Client
+pub struct ScriptedTransport {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    log: Arc<Mutex<Vec<LoggedPacket>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ScriptedTransport {
+    /// Starts a ScriptedTransport listening on the given address, which relays
+    /// the packets it receives to & from the Server at the given address,
+    /// injecting the faults set out in the given rules
+    pub fn start(address: SocketAddr, server_address: SocketAddr, rules: Vec<FaultRule>) -> Self {
+        let client_socket = UdpSocket::bind(address).expect("the transport address is in use");
+        let server_socket = UdpSocket::bind(SocketAddr::new(address.ip(), 0))
+            .expect("the transport couldn't bind a socket to relay to the Server from");
+        client_socket.set_nonblocking(true).unwrap();
+        server_socket.set_nonblocking(true).unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let thread_stop = stop.clone();
+        let script = FaultScript::new(rules, log.clone());
+        let thread = thread::spawn(move || {
+            relay(
+                client_socket,
+                server_socket,
+                server_address,
+                script,
+                thread_stop,
+            );
+        });
+
+        ScriptedTransport {
+            address,
+            stop,
+            log,
+            thread: Some(thread),
+        }
+    }
+
+    /// Gets the address the Client is to connect to
+    pub fn address(&self) -> SocketAddr {
+        return self.address;
+    }
+
+    /// Gets every packet relayed so far, in the order they arrived
+    pub fn log(&self) -> Vec<LoggedPacket> {
+        return self.log.lock().unwrap().clone();
+    }
+}
+
+impl Drop for ScriptedTransport {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn relay(
+    client_socket: UdpSocket,
+    server_socket: UdpSocket,
+    server_address: SocketAddr,
+    mut script: FaultScript,
+    stop: Arc<AtomicBool>,
+) {
+    let mut client_address: Option<SocketAddr> = None;
+    let mut buffer = [0; MAX_DATAGRAM_SIZE];
+
+    while !stop.load(Ordering::SeqCst) {
+        loop {
+            match client_socket.recv_from(&mut buffer) {
+                Ok((length, from)) => {
+                    client_address = Some(from);
+                    for bytes in script.process(Direction::ToServer, buffer[..length].to_vec()) {
+                        let _ = server_socket.send_to(&bytes, server_address);
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+        loop {
+            match server_socket.recv_from(&mut buffer) {
+                Ok((length, _)) => {
+                    for bytes in script.process(Direction::ToClient, buffer[..length].to_vec()) {
+                        if let Some(client_address) = client_address {
+                            let _ = client_socket.send_to(&bytes, client_address);
+                        }
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// applies a list of FaultRules to the packets crossing each way, in the order
+// they arrive, logging what was done with each
+struct FaultScript {
+    // along with the number of packets each has been applied to
+    rules: Vec<(FaultRule, usize)>,
+    // the number of packets which have crossed each way
+    counts: [usize; 2],
+    // the packet held back each way, to be delivered after the next
+    held: [Option<Vec<u8>>; 2],
+    log: Arc<Mutex<Vec<LoggedPacket>>>,
+}
+
+impl FaultScript {
+    fn new(rules: Vec<FaultRule>, log: Arc<Mutex<Vec<LoggedPacket>>>) -> Self {
+        FaultScript {
+            rules: rules.into_iter().map(|rule| (rule, 0)).collect(),
+            counts: [0, 0],
+            held: [None, None],
+            log,
+        }
+    }
+
+    // takes a packet crossing the given way, returning the packets to be
+    // delivered that way, in order
+    fn process(&mut self, direction: Direction, mut bytes: Vec<u8>) -> Vec<Vec<u8>> {
+        let way = direction as usize;
+        let index = self.counts[way];
+        self.counts[way] += 1;
+
+        let fault = self.apply_first_match(direction, index, &bytes);
+        self.log.lock().unwrap().push(LoggedPacket {
+            direction,
+            index,
+            size: bytes.len(),
+            fault,
+        });
+
+        let mut delivered = Vec::new();
+        match fault.map(|(_, fault)| fault) {
+            None => delivered.push(bytes),
+            Some(Fault::Drop) => {}
+            Some(Fault::Corrupt(offset)) => {
+                if let Some(byte) = bytes.get_mut(offset) {
+                    *byte = !*byte;
+                }
+                delivered.push(bytes);
+            }
+            Some(Fault::Duplicate) => {
+                delivered.push(bytes.clone());
+                delivered.push(bytes);
+            }
+            Some(Fault::SwapWithNext) => {
+                // anything held back already goes first, as it would have
+                // after any other packet
+                delivered.extend(self.held[way].take());
+                self.held[way] = Some(bytes);
+                return delivered;
+            }
+        }
+        delivered.extend(self.held[way].take());
+        return delivered;
+    }
+
+    // finds the first rule matching the packet, counting it as applied
+    fn apply_first_match(
+        &mut self,
+        direction: Direction,
+        index: usize,
+        bytes: &[u8],
+    ) -> Option<(usize, Fault)> {
+        for (rule_index, (rule, applied)) in self.rules.iter_mut().enumerate() {
+            if rule.direction != direction {
+                continue;
+            }
+            if let Some(limit) = rule.limit {
+                if *applied >= limit {
+                    continue;
+                }
+            }
+            let matches = match &mut rule.matcher {
+                PacketMatcher::Index(matched_index) => *matched_index == index,
+                PacketMatcher::Size(size) => *size == bytes.len(),
+                PacketMatcher::Classifier(classifier) => classifier(index, bytes),
+            };
+            if matches {
+                *applied += 1;
+                return Some((rule_index, rule.fault));
+            }
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{Direction, Fault, FaultRule, FaultScript, LoggedPacket, PacketMatcher};
+
+    // sends packets whose only byte is their index through the script, one
+    // way, returning what was delivered
+    fn run(rules: Vec<FaultRule>, count: u8) -> (Vec<Vec<u8>>, Vec<LoggedPacket>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut script = FaultScript::new(rules, log.clone());
+        let mut delivered = Vec::new();
+        for index in 0..count {
+            delivered.extend(script.process(Direction::ToClient, vec![index]));
+        }
+        let log = log.lock().unwrap().clone();
+        return (delivered, log);
+    }
+
+    #[test]
+    fn faults_are_injected_into_exactly_the_packets_matched() {
+        let (delivered, log) = run(
+            vec![
+                FaultRule::new(Direction::ToClient, PacketMatcher::Index(1), Fault::Drop),
+                FaultRule::new(
+                    Direction::ToClient,
+                    PacketMatcher::Index(2),
+                    Fault::Corrupt(0),
+                ),
+                FaultRule::new(
+                    Direction::ToClient,
+                    PacketMatcher::Index(3),
+                    Fault::Duplicate,
+                ),
+                FaultRule::new(
+                    Direction::ToClient,
+                    PacketMatcher::Index(4),
+                    Fault::SwapWithNext,
+                ),
+                // never matches, crossing the other way
+                FaultRule::new(Direction::ToServer, PacketMatcher::Index(0), Fault::Drop),
+            ],
+            6,
+        );
+        assert_eq!(
+            delivered,
+            [vec![0], vec![!2], vec![3], vec![3], vec![5], vec![4]]
+        );
+        assert_eq!(log.len(), 6);
+        assert_eq!(log[0].fault, None);
+        assert_eq!(log[1].fault, Some((0, Fault::Drop)));
+        assert_eq!(log[4].fault, Some((3, Fault::SwapWithNext)));
+        assert!(log.iter().all(|packet| packet.size == 1));
+    }
+
+    #[test]
+    fn rules_stop_matching_past_their_limit() {
+        let (delivered, log) = run(
+            vec![
+                FaultRule::once(
+                    Direction::ToClient,
+                    PacketMatcher::Classifier(Box::new(|_, bytes| bytes[0] % 2 == 1)),
+                    Fault::Drop,
+                ),
+                FaultRule::new(
+                    Direction::ToClient,
+                    PacketMatcher::Size(1),
+                    Fault::Duplicate,
+                ),
+            ],
+            4,
+        );
+        // the first rule matches the first odd packet alone, while the second
+        // applies to every other packet
+        assert_eq!(
+            delivered,
+            [vec![0], vec![0], vec![2], vec![2], vec![3], vec![3]]
+        );
+        let faults: Vec<Option<(usize, Fault)>> = log.iter().map(|packet| packet.fault).collect();
+        assert_eq!(
+            faults,
+            [
+                Some((1, Fault::Duplicate)),
+                Some((0, Fault::Drop)),
+                Some((1, Fault::Duplicate)),
+                Some((1, Fault::Duplicate)),
+            ]
+        );
+    }
+}
@@ -2,9 +2,9 @@ use std::{cell::RefCell, rc::Rc};
 
 use naia_derive::ActorType;
 
-use crate::PointActor;
+use crate::CounterActor;
 
 #[derive(ActorType, Clone)]
-pub enum ExampleActor {
-    PointActor(Rc<RefCell<PointActor>>),
+pub enum TestActor {
+    CounterActor(Rc<RefCell<CounterActor>>),
 }
@@ -0,0 +1,139 @@
+use std::{
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use naia_client::{ClientConfig, ClientEvent, NaiaClient};
+use naia_shared::{ActorType, EventType, Manifest, SharedConfig};
+
+use crate::{manifest_load, AuthEvent, TestActor, TestEvent};
+
+/// Gets the ClientConfig a TestClient connects with by default, which retries
+/// the handshake quickly, so that losing a handshake packet to a simulated bad
+/// connection doesn't hold up the scenario for long
+pub fn get_client_config() -> ClientConfig {
+    let mut client_config = ClientConfig::default();
+    client_config.send_handshake_interval = Duration::from_millis(100);
+    client_config.max_send_handshake_interval = Duration::from_millis(500);
+    client_config.max_handshake_attempts = 50;
+    return client_config;
+}
+
+// How long to wait before polling the Client again, once it has nothing more
+// to give
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A NaiaClient running the test protocol, or an earlier build of it, which
+/// the test drives by waiting for the events it expects
+pub struct TestClient<T: EventType = TestEvent, U: ActorType = TestActor> {
+    client: NaiaClient<T, U>,
+}
+
+impl TestClient {
+    /// Starts connecting to the Test Server at the given address, with the
+    /// given credentials
+    pub fn connect(
+        server_address: SocketAddr,
+        shared_config: SharedConfig,
+        username: &str,
+        password: &str,
+    ) -> TestClient {
+        return TestClient::connect_with_config(
+            server_address,
+            shared_config,
+            get_client_config(),
+            username,
+            password,
+        );
+    }
+
+    /// Starts connecting to the Test Server at the given address, with the
+    /// given ClientConfig & credentials
+    pub fn connect_with_config(
+        server_address: SocketAddr,
+        shared_config: SharedConfig,
+        client_config: ClientConfig,
+        username: &str,
+        password: &str,
+    ) -> TestClient {
+        let auth = TestEvent::AuthEvent(AuthEvent::new(username, password));
+        let client = NaiaClient::new(
+            server_address,
+            manifest_load(),
+            Some(client_config),
+            shared_config,
+            Some(auth),
+        )
+        .expect("the AuthEvent should be registered in the manifest");
+        TestClient { client }
+    }
+
+    /// Starts connecting to the Test Server through whichever of the given
+    /// addresses it answers first, with the given credentials
+    pub fn connect_to_any(
+        server_addresses: &[SocketAddr],
+        shared_config: SharedConfig,
+        username: &str,
+        password: &str,
+    ) -> TestClient {
+        let auth = TestEvent::AuthEvent(AuthEvent::new(username, password));
+        let client = NaiaClient::new_with_addresses(
+            server_addresses,
+            manifest_load(),
+            Some(get_client_config()),
+            shared_config,
+            Some(auth),
+        )
+        .expect("a socket should open to one of the addresses");
+        TestClient { client }
+    }
+}
+
+impl<T: EventType, U: ActorType> TestClient<T, U> {
+    /// Starts connecting to the Test Server at the given address, running the
+    /// protocol of the given Manifest, and authenticating with the given Event
+    pub fn connect_with(
+        server_address: SocketAddr,
+        shared_config: SharedConfig,
+        manifest: Manifest<T, U>,
+        auth: T,
+    ) -> TestClient<T, U> {
+        let client = NaiaClient::new(
+            server_address,
+            manifest,
+            Some(get_client_config()),
+            shared_config,
+            Some(auth),
+        )
+        .expect("the AuthEvent should be registered in the manifest");
+        TestClient { client }
+    }
+
+    /// Polls the Client, handing every event it gives to `handle` along with
+    /// the Client, until `handle` returns true, or the timeout runs out.
+    /// Returns whether `handle` returned true in time
+    pub fn wait_for<F>(&mut self, timeout: Duration, mut handle: F) -> bool
+    where
+        F: FnMut(&mut NaiaClient<T, U>, ClientEvent<T>) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            match self.client.receive() {
+                Some(Ok(event)) => {
+                    if handle(&mut self.client, event) {
+                        return true;
+                    }
+                }
+                Some(Err(_)) => {}
+                None => thread::sleep(POLL_INTERVAL),
+            }
+        }
+        return false;
+    }
+
+    /// Gets the Client, to send Events or read Actors through
+    pub fn client(&mut self) -> &mut NaiaClient<T, U> {
+        return &mut self.client;
+    }
+}
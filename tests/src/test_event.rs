@@ -0,0 +1,12 @@
+use naia_derive::EventType;
+
+use crate::{AuthEvent, CheerEvent, GoalEvent, ReloadEvent, TextEvent};
+
+#[derive(EventType, Clone)]
+pub enum TestEvent {
+    TextEvent(TextEvent),
+    AuthEvent(AuthEvent),
+    GoalEvent(GoalEvent),
+    CheerEvent(CheerEvent),
+    ReloadEvent(ReloadEvent),
+}
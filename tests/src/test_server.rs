@@ -0,0 +1,925 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    net::SocketAddr,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use naia_server::{
+    ActorEventDelivery, ActorKey, BarrierId, BarrierOutcome, DebugCommand, DebugResponse,
+    EventHandling, MessageReceipt, NaiaServer, OutgoingQueue, PacingStats, RoomKey, ServerConfig,
+    ServerContext, ServerEvent, ServerEventHandler, StateMask, User, UserKey,
+};
+use naia_shared::{
+    ActorRef, ChannelIndex, Clock, ConnectionInfo, Instant as ClockInstant, NaiaError,
+    SharedConfig, SkippedFrame,
+};
+
+use crate::{
+    counter_actor::CounterActorProp, manifest_load, CheerEvent, CounterActor, GoalEvent,
+    ReloadEvent, TestActor, TestEvent, TextEvent,
+};
+
+/// The TextEvent which asks the Test Server to kick the Client that sent it
+pub const KICK_TEXT: &str = "bye";
+
+/// The reason the Test Server gives a Client it kicks
+pub const KICK_REASON: &str = "asked to leave";
+
+/// The TextEvent which asks the Test Server to show the Client that sent it
+/// the value of every other User's CounterActor
+pub const REVEAL_TEXT: &str = "reveal";
+
+/// The TextEvent which asks the Test Server to send the Client that sent it a
+/// GoalEvent, referencing a CounterActor which is spawned for it, yet only
+/// added to the room on the tick after, so that it's created on the Client
+/// after the GoalEvent has arrived
+pub const GOAL_TEXT: &str = "goal";
+
+/// The TextEvent which asks the Test Server to spawn a mascot for the Client
+/// that sent it, a CounterActor which is in scope for that Client alone, and to
+/// send a CheerEvent about it on the tick after it's first sent, held until the
+/// Client has created it
+pub const CHEER_TEXT: &str = "cheer";
+
+/// The TextEvent which asks the Test Server to send a CheerEvent about the
+/// mascot of the Client that sent it straight away, skipping any Client which
+/// hasn't created it
+pub const ENCORE_TEXT: &str = "encore";
+
+/// The TextEvent which asks the Test Server to assign the CounterActor of the
+/// Client that sent it to that Client as its Pawn, so that it can send Commands
+pub const PILOT_TEXT: &str = "pilot";
+
+/// The TextEvent which asks the Test Server to send the Client that sent it
+/// `FLOOD_EVENT_COUNT` TextEvents at once, each long enough that only two fit
+/// in a packet, so that they're written in a single burst of packets
+pub const FLOOD_TEXT: &str = "flood";
+
+/// How many TextEvents the Test Server answers `FLOOD_TEXT` with. The burst of
+/// packets they're written in is kept within the outgoing channel of the UDP
+/// socket, which is only drained while the Server waits on the socket
+pub const FLOOD_EVENT_COUNT: usize = 12;
+
+/// Gets the text of each TextEvent the Test Server answers `FLOOD_TEXT` with
+pub fn flood_of(index: usize) -> String {
+    return format!("{:0>150}", index);
+}
+
+/// The TextEvent which asks the Test Server to set a TextEvent as the payload
+/// of every tick from then on, for every Client, holding the tick it was set
+/// for
+pub const TICK_PAYLOAD_TEXT: &str = "ticks";
+
+/// Gets the text of the TextEvent the Test Server sets as the payload of a
+/// tick, once asked to with `TICK_PAYLOAD_TEXT`
+pub fn tick_payload_of(tick: u16) -> String {
+    return format!("tick {}", tick);
+}
+
+/// The TextEvent which asks the Test Server to register the ReloadEvent while
+/// it's running, unless it has already
+pub const EXTEND_TEXT: &str = "extend";
+
+/// The TextEvent which asks the Test Server to send the Client that sent it a
+/// ReloadEvent. If the Client hasn't answered that it has registered the
+/// ReloadEvent too, the TextEvent is echoed back instead, or if it has
+/// answered that it hasn't, `LACKS_TYPE_TEXT` is sent back
+pub const RELOAD_TEXT: &str = "reload";
+
+/// The TextEvent the Test Server answers `RELOAD_TEXT` with, when the Client
+/// which sent it lacks the ReloadEvent
+pub const LACKS_TYPE_TEXT: &str = "lacks type";
+
+/// The TextEvent which asks the Test Server to send the Client that sent it
+/// `BARRIER_EVENT_COUNT` TextEvents, place a reliable barrier behind them,
+/// then send as many again & place another barrier behind those. As each
+/// barrier completes, the Client is sent `barrier_done_of()` the barrier
+pub const BARRIER_TEXT: &str = "barrier";
+
+/// The TextEvent which asks the Test Server to send the Client that sent it
+/// `BARRIER_EVENT_COUNT` TextEvents, place a reliable barrier behind them, &
+/// kick it straight away, before any of them can be delivered
+pub const BARRIER_KICK_TEXT: &str = "barrier kick";
+
+/// How many TextEvents the Test Server sends behind each barrier
+pub const BARRIER_EVENT_COUNT: usize = 8;
+
+/// Gets the text of each TextEvent the Test Server sends behind a barrier,
+/// given the number of the barrier, starting at 0
+pub fn behind_barrier_of(barrier: usize, index: usize) -> String {
+    return format!("barrier {} event {}", barrier, index);
+}
+
+/// Gets the TextEvent the Test Server sends once a barrier has completed,
+/// given the number of the barrier
+pub fn barrier_done_of(barrier: usize) -> String {
+    return format!("barrier {} done", barrier);
+}
+
+/// The TextEvent which asks the Test Server to send the Client that sent it a
+/// TextEvent on `STRAY_CHANNEL`, which isn't configured, so that it answers
+/// with `NO_SUCH_CHANNEL_TEXT` once the Server has refused to
+pub const STRAY_CHANNEL_TEXT: &str = "stray channel";
+
+/// An ordered channel no SharedConfig of the tests configures
+pub const STRAY_CHANNEL: ChannelIndex = 200;
+
+/// The TextEvent the Test Server answers `STRAY_CHANNEL_TEXT` with
+pub const NO_SUCH_CHANNEL_TEXT: &str = "no such channel";
+
+/// The out-of-band query the Test Server answers, with `QUERY_REPLY`
+pub const QUERY_TEXT: &str = "info";
+
+/// The reply the Test Server sends to `QUERY_TEXT`
+pub const QUERY_REPLY: &str = "naia test server";
+
+/// The TextEvent the Test Server answers from its Event middleware, with
+/// `KNOCK_REPLY`, as soon as it's received rather than on the next tick
+pub const KNOCK_TEXT: &str = "knock knock";
+
+/// The reply the Test Server's Event middleware sends to `KNOCK_TEXT`
+pub const KNOCK_REPLY: &str = "who's there";
+
+/// The TextEvent which asks the Test Server to stop sending updates on its
+/// ticks, so that everything queued from then on is held back, & only
+/// replies from its Event middleware are sent
+pub const HOLD_TEXT: &str = "hold";
+
+/// The TextEvent which asks the Test Server to pause the replication of
+/// Actors to its sender, which it echoes, as Events still flow while paused
+pub const PAUSE_TEXT: &str = "pause";
+
+/// The TextEvent which asks the Test Server to resume the replication of
+/// Actors to its sender, after `PAUSE_TEXT`
+pub const RESUME_TEXT: &str = "resume";
+
+/// How many ticks back the Test Server looks up the history of each
+/// CounterActor, after sending updates
+pub const HISTORY_LOOKBACK: u16 = 5;
+
+/// Gets the TextEvent the Test Server replies to a given TextEvent with
+pub fn echo_of(text: &str) -> String {
+    return format!("echo: {}", text);
+}
+
+/// The events the Test Server logs the order of
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LoggedEvent {
+    Connection,
+    Text,
+    Disconnection,
+}
+
+/// Everything the Test Server saw while it was running
+#[derive(Debug, Default)]
+pub struct ServerLog {
+    pub connections: u32,
+    pub connection_infos: Vec<ConnectionInfo>,
+    pub disconnections: u32,
+    pub received_texts: Vec<String>,
+    /// The build of the host which sent each TextEvent
+    pub received_builds: Vec<u16>,
+    /// When each TextEvent was received, along with its MessageReceipt
+    pub text_receipts: Vec<(Instant, Option<MessageReceipt>)>,
+    /// Every Connection, TextEvent & Disconnection, in the order received
+    pub event_order: Vec<LoggedEvent>,
+    /// The tick & value of every CounterActor each time updates were sent,
+    /// while not handing events to an event handler
+    pub replicated_counts: Vec<(u16, u16)>,
+    /// The tick & value of every CounterActor as kept in its history,
+    /// `HISTORY_LOOKBACK` ticks after each time updates were sent
+    pub historical_counts: Vec<(u16, u16)>,
+    /// A reference to & the value of every CounterActor in the room, as it
+    /// was when the Test Server stopped
+    pub final_counts: Vec<(ActorRef, u16)>,
+    /// Every Event which was skipped over, rather than received
+    pub skipped_frames: Vec<SkippedFrame>,
+    /// How many Users each CheerEvent was queued or held for
+    pub cheer_recipients: Vec<usize>,
+    /// Every TextEvent received as a Command, along with when it was popped,
+    /// by the clock in the ServerConfig
+    pub received_commands: Vec<(String, ClockInstant)>,
+    /// How many packets of early data were released & discarded, as of when
+    /// the Test Server stopped
+    pub early_data_counts: (u64, u64),
+    /// How many packets from addresses without a connection were dropped, as
+    /// of when the Test Server stopped
+    pub unknown_address_packet_count: u64,
+    /// The packets sent with updates, in bursts as written & as paced, as of
+    /// when the Test Server stopped
+    pub pacing_stats: PacingStats,
+    /// The number of each barrier which completed, along with how, in the
+    /// order they completed
+    pub barrier_outcomes: Vec<(usize, BarrierOutcome)>,
+    /// Each outgoing queue which became congested or was cleared, along with
+    /// whether it became congested, in order
+    pub congestion_changes: Vec<(OutgoingQueue, bool)>,
+    /// How long `NaiaServer::shutdown()` took to return, if the Test Server
+    /// was shut down rather than stopped
+    pub shutdown_duration: Option<Duration>,
+}
+
+/// A NaiaServer running the test protocol on its own thread, until stopped.
+/// It accepts Clients which authenticate as "charlie" with the password
+/// "12345", and gives each of them a CounterActor, which counts up once per
+/// tick to `COUNTER_LIMIT`, and has its history tracked. The value of each
+/// CounterActor is hidden from every other User, until they send
+/// `REVEAL_TEXT`. Every TextEvent received is echoed back to its sender, except
+/// `KICK_TEXT`, which kicks the sender instead, `GOAL_TEXT`, which is answered
+/// with a GoalEvent, `CHEER_TEXT` & `ENCORE_TEXT`, which are answered with a
+/// CheerEvent, `PILOT_TEXT`, which makes the sender's CounterActor its Pawn, &
+/// `FLOOD_TEXT`, which is answered with a flood of TextEvents, &
+/// `TICK_PAYLOAD_TEXT`, which sets the payload of every tick from then on,
+/// `RELOAD_TEXT`, which is answered with a ReloadEvent, once it has been
+/// registered with `EXTEND_TEXT`, & `BARRIER_TEXT` & `BARRIER_KICK_TEXT`,
+/// which send TextEvents behind reliable barriers, & `STRAY_CHANNEL_TEXT`,
+/// which is answered once sending on an unconfigured channel is refused, &
+/// `HOLD_TEXT`, which stops it sending updates, & `PAUSE_TEXT` &
+/// `RESUME_TEXT`, which pause & resume the sender's replication. Its
+/// Event middleware answers `KNOCK_TEXT` with `KNOCK_REPLY`, without passing
+/// it on. It answers the out-of-band query `QUERY_TEXT` with `QUERY_REPLY`.
+/// TextEvents sent as Commands are only logged
+pub struct TestServer {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    shutdown_sender: mpsc::Sender<ShutdownRequest>,
+    debug_sender: mpsc::Sender<DebugRequest>,
+    thread: JoinHandle<ServerLog>,
+}
+
+impl TestServer {
+    /// Starts a Test Server listening on the given address, returning once it
+    /// is ready to accept connections. It receives its events one at a time
+    pub fn start(address: SocketAddr, shared_config: SharedConfig) -> TestServer {
+        return TestServer::start_in_mode(
+            address,
+            shared_config,
+            ServerConfig::default(),
+            ServerMode::Receive,
+        );
+    }
+
+    /// Starts a Test Server like `start()`, with the given ServerConfig
+    pub fn start_with_config(
+        address: SocketAddr,
+        shared_config: SharedConfig,
+        server_config: ServerConfig,
+    ) -> TestServer {
+        return TestServer::start_in_mode(
+            address,
+            shared_config,
+            server_config,
+            ServerMode::Receive,
+        );
+    }
+
+    /// Starts a Test Server like `start()`, which has its events handed to an
+    /// event handler instead
+    pub fn start_with_event_handler(
+        address: SocketAddr,
+        shared_config: SharedConfig,
+    ) -> TestServer {
+        return TestServer::start_in_mode(
+            address,
+            shared_config,
+            ServerConfig::default(),
+            ServerMode::EventHandler,
+        );
+    }
+
+    /// Starts a Test Server which receives its events through the
+    /// ServerReceiver it splits the NaiaServer into, replying to each
+    /// TextEvent as it's received through the ServerSender. It only echoes
+    /// TextEvents & logs connections, without spawning any Actors
+    pub fn start_split(address: SocketAddr, shared_config: SharedConfig) -> TestServer {
+        return TestServer::start_in_mode(
+            address,
+            shared_config,
+            ServerConfig::default(),
+            ServerMode::Split,
+        );
+    }
+
+    fn start_in_mode(
+        address: SocketAddr,
+        shared_config: SharedConfig,
+        server_config: ServerConfig,
+        mode: ServerMode,
+    ) -> TestServer {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let (shutdown_sender, shutdown_receiver) = mpsc::channel();
+        let (debug_sender, debug_receiver) = mpsc::channel();
+
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            // a NaiaServer can't be moved between threads, so it's created on
+            // the thread it runs on
+            return smol::block_on(run(
+                address,
+                shared_config,
+                server_config,
+                mode,
+                thread_stop,
+                ready_sender,
+                shutdown_receiver,
+                debug_receiver,
+            ));
+        });
+
+        // the Server may have been bound to an ephemeral port
+        let address = ready_receiver
+            .recv()
+            .expect("the Test Server stopped before it was ready");
+
+        TestServer {
+            address,
+            stop,
+            shutdown_sender,
+            debug_sender,
+            thread,
+        }
+    }
+
+    /// Gets the address the Test Server is bound to, with the port the OS
+    /// picked if it was started on port 0
+    pub fn address(&self) -> SocketAddr {
+        return self.address;
+    }
+
+    /// Has the Test Server carry out a DebugCommand between its events, and
+    /// waits for its response. Test Servers which have been split never answer
+    pub fn debug_command(&self, command: DebugCommand) -> DebugResponse {
+        let (response_sender, response_receiver) = mpsc::channel();
+        self.debug_sender
+            .send((command, response_sender))
+            .expect("the Test Server has stopped");
+        return response_receiver
+            .recv()
+            .expect("the Test Server stopped before answering");
+    }
+
+    /// Stops the Test Server, after its next tick at the latest, and gets
+    /// everything it saw
+    pub fn stop(self) -> ServerLog {
+        self.stop.store(true, Ordering::SeqCst);
+        return self.thread.join().expect("the Test Server panicked");
+    }
+
+    /// Shuts the Test Server down with `NaiaServer::shutdown()`, after its
+    /// next tick at the latest, and gets everything it saw
+    pub fn shutdown(self, reason: &str, drain: Duration) -> ServerLog {
+        self.shutdown_sender
+            .send((reason.to_string(), drain))
+            .expect("the Test Server has stopped");
+        return self.stop();
+    }
+}
+
+// the reason & drain duration to shut the Test Server down with
+type ShutdownRequest = (String, Duration);
+
+// a DebugCommand for the Test Server, along with where to send its response
+type DebugRequest = (DebugCommand, mpsc::Sender<DebugResponse>);
+
+// the ways the Test Server receives its events
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ServerMode {
+    Receive,
+    EventHandler,
+    Split,
+}
+
+// what the Test Server keeps track of between events
+struct ServerState {
+    room_key: RoomKey,
+    counters: HashMap<UserKey, ActorKey>,
+    // the Actors spawned for GoalEvents which are yet to be sent
+    new_celebrations: Vec<ActorKey>,
+    // the Actors spawned for GoalEvents which have been sent, to be added to
+    // the room on the next tick
+    celebrations: Vec<ActorKey>,
+    // the User each mascot is in scope for
+    mascot_owners: Rc<RefCell<HashMap<ActorKey, UserKey>>>,
+    // the mascots which are yet to be sent
+    new_mascots: Vec<ActorKey>,
+    // the mascots which have been sent, to be cheered on the next tick
+    mascots: Vec<ActorKey>,
+    // whether to set the payload of each tick
+    tick_payloads: bool,
+    // whether the ReloadEvent has been registered
+    extended: bool,
+    // whether updates have stopped being sent
+    holding: bool,
+    // the User & number of each barrier which is yet to complete
+    barriers: HashMap<BarrierId, (UserKey, usize)>,
+    clock: Arc<dyn Clock>,
+    log: ServerLog,
+}
+
+async fn run(
+    address: SocketAddr,
+    shared_config: SharedConfig,
+    server_config: ServerConfig,
+    mode: ServerMode,
+    stop: Arc<AtomicBool>,
+    ready_sender: mpsc::Sender<SocketAddr>,
+    shutdown_receiver: mpsc::Receiver<ShutdownRequest>,
+    debug_receiver: mpsc::Receiver<DebugRequest>,
+) -> ServerLog {
+    let clock = server_config.clock.clone();
+    let mut server =
+        NaiaServer::new(address, manifest_load(), Some(server_config), shared_config).await;
+
+    server.on_auth(Rc::new(Box::new(|_, auth_type| {
+        if let TestEvent::AuthEvent(auth_event) = auth_type {
+            return auth_event.username.get() == "charlie" && auth_event.password.get() == "12345";
+        }
+        return false;
+    })));
+
+    server.on_query(|query| {
+        if query == QUERY_TEXT.as_bytes() {
+            return Some(QUERY_REPLY.as_bytes().to_vec());
+        }
+        return None;
+    });
+
+    server
+        .on_receive::<TextEvent, _>(|context, event| {
+            if let TestEvent::TextEvent(text_event) = event {
+                if text_event.text.get() == KNOCK_TEXT {
+                    context.send_event(&TextEvent::new(KNOCK_REPLY));
+                    return EventHandling::Handled;
+                }
+            }
+            return EventHandling::PassThrough;
+        })
+        .expect("the TextEvent should be registered in the manifest");
+
+    // every Actor in the room is in scope for every User in it, except for
+    // mascots, which are in scope for their owner alone
+    let mascot_owners = Rc::new(RefCell::new(HashMap::<ActorKey, UserKey>::new()));
+    let scope_owners = mascot_owners.clone();
+    server.on_scope_actor(Rc::new(Box::new(move |_, user_key, actor_key, _| {
+        return match scope_owners.borrow().get(actor_key) {
+            Some(owner) => owner == user_key,
+            None => true,
+        };
+    })));
+
+    let state = Rc::new(RefCell::new(ServerState {
+        room_key: server.create_room(),
+        counters: HashMap::new(),
+        new_celebrations: Vec::new(),
+        celebrations: Vec::new(),
+        mascot_owners,
+        new_mascots: Vec::new(),
+        mascots: Vec::new(),
+        tick_payloads: false,
+        extended: false,
+        holding: false,
+        barriers: HashMap::new(),
+        clock,
+        log: ServerLog::default(),
+    }));
+
+    ready_sender
+        .send(server.local_addr())
+        .expect("the Test Server was dropped while starting");
+
+    if mode == ServerMode::Split {
+        run_split(&mut server, &mut state.borrow_mut(), &stop).await;
+    } else if mode == ServerMode::EventHandler {
+        server.set_event_handler(TestEventHandler {
+            state: state.clone(),
+        });
+        // the other way of receiving events is refused while the handler is set
+        assert!(server.receive().await.is_err());
+        while !stop.load(Ordering::SeqCst) {
+            server
+                .process()
+                .await
+                .expect("the event handler should be set");
+            answer_debug_commands(&mut server, &debug_receiver);
+        }
+    } else {
+        while !stop.load(Ordering::SeqCst) {
+            if let Ok(event) = server.receive().await {
+                let is_tick = match event {
+                    ServerEvent::Tick => true,
+                    _ => false,
+                };
+                handle_event(&mut server, &mut state.borrow_mut(), event);
+                if is_tick && !state.borrow().holding {
+                    server.send_all_updates().await;
+                    log_counter_history(&server, &mut state.borrow_mut());
+                }
+            }
+            answer_debug_commands(&mut server, &debug_receiver);
+        }
+    }
+
+    log_final_counts(&server, &mut state.borrow_mut());
+    state.borrow_mut().log.early_data_counts = (
+        server.get_released_early_data_count(),
+        server.get_discarded_early_data_count(),
+    );
+    state.borrow_mut().log.unknown_address_packet_count = server.get_unknown_address_packet_count();
+    state.borrow_mut().log.pacing_stats = server.get_pacing_stats().clone();
+    let mut log = std::mem::take(&mut state.borrow_mut().log);
+    if let Ok((reason, drain)) = shutdown_receiver.try_recv() {
+        let shutdown_start = Instant::now();
+        server.shutdown(&reason, drain).await;
+        log.shutdown_duration = Some(shutdown_start.elapsed());
+    }
+    return log;
+}
+
+// receives events through the ServerReceiver, echoing TextEvents in reply
+// through the ServerSender while it's still in use
+async fn run_split(
+    server: &mut NaiaServer<TestEvent, TestActor>,
+    state: &mut ServerState,
+    stop: &AtomicBool,
+) {
+    let log = &mut state.log;
+    let (mut receiver, sender) = server.split();
+    while !stop.load(Ordering::SeqCst) {
+        match receiver.receive().await {
+            Ok(ServerEvent::Connection(_, connection_info)) => {
+                log.connections += 1;
+                log.connection_infos.push(connection_info);
+                log.event_order.push(LoggedEvent::Connection);
+            }
+            Ok(ServerEvent::Disconnection(_, _)) => {
+                log.disconnections += 1;
+                log.event_order.push(LoggedEvent::Disconnection);
+            }
+            Ok(ServerEvent::Event(user_key, TestEvent::TextEvent(text_event))) => {
+                let text = text_event.text.get().clone();
+                log.event_order.push(LoggedEvent::Text);
+                sender.queue_event(&user_key, &TextEvent::new(&echo_of(&text)));
+                log.received_texts.push(text);
+            }
+            Ok(ServerEvent::Tick) => {
+                receiver.send_all_updates().await;
+            }
+            _ => {}
+        }
+    }
+}
+
+// carries out every DebugCommand sent since the last call
+fn answer_debug_commands(
+    server: &mut NaiaServer<TestEvent, TestActor>,
+    debug_receiver: &mpsc::Receiver<DebugRequest>,
+) {
+    while let Ok((command, response_sender)) = debug_receiver.try_recv() {
+        // the test may have stopped waiting for the response
+        let _ = response_sender.send(server.debug_command(command));
+    }
+}
+
+// handles an event the same way whichever way it was received, except for
+// sending updates after a Tick
+fn handle_event(
+    server: &mut NaiaServer<TestEvent, TestActor>,
+    state: &mut ServerState,
+    event: ServerEvent<TestEvent>,
+) {
+    let log = &mut state.log;
+    match event {
+        ServerEvent::Connection(user_key, connection_info) => {
+            log.connections += 1;
+            log.connection_infos.push(connection_info);
+            log.event_order.push(LoggedEvent::Connection);
+            server.room_add_user(&state.room_key, &user_key);
+            let counter = TestActor::CounterActor(CounterActor::new().wrap());
+            let actor_key = server
+                .register_actor(counter)
+                .expect("the CounterActor should be registered in the manifest");
+            server.room_add_actor(&state.room_key, &actor_key);
+            assert!(server.track_history(&actor_key));
+            // the Users connected so far & the new one can't see the value of
+            // each other's CounterActors
+            for (other_user_key, other_actor_key) in state.counters.iter() {
+                server.set_property_visibility(
+                    other_user_key,
+                    &actor_key,
+                    Some(get_hidden_value_mask()),
+                );
+                server.set_property_visibility(
+                    &user_key,
+                    other_actor_key,
+                    Some(get_hidden_value_mask()),
+                );
+            }
+            state.counters.insert(user_key, actor_key);
+        }
+        ServerEvent::Disconnection(user_key, _) => {
+            log.disconnections += 1;
+            log.event_order.push(LoggedEvent::Disconnection);
+            if let Some(actor_key) = state.counters.remove(&user_key) {
+                server.deregister_actor(actor_key);
+            }
+            let mascot_key = get_mascot(&state.mascot_owners, &user_key);
+            if let Some(mascot_key) = mascot_key {
+                state.mascot_owners.borrow_mut().remove(&mascot_key);
+                state.new_mascots.retain(|key| *key != mascot_key);
+                state.mascots.retain(|key| *key != mascot_key);
+                server.deregister_actor(mascot_key);
+            }
+        }
+        ServerEvent::Event(user_key, TestEvent::TextEvent(text_event)) => {
+            let text = text_event.text.get().clone();
+            log.event_order.push(LoggedEvent::Text);
+            log.text_receipts
+                .push((Instant::now(), server.last_event_receipt()));
+            if text == KICK_TEXT {
+                // found as admin tooling would find it, by its connection id
+                let connection_id = server
+                    .users()
+                    .find(|user| user.key() == user_key)
+                    .map(|user| user.connection_id());
+                if let Some(user) =
+                    connection_id.and_then(|id| server.get_user_ref_by_connection_id(id))
+                {
+                    user.kick(KICK_REASON);
+                }
+            } else if text == GOAL_TEXT {
+                let celebration = TestActor::CounterActor(CounterActor::new().wrap());
+                let actor_key = server
+                    .register_actor(celebration)
+                    .expect("the CounterActor should be registered in the manifest");
+                let goal = GoalEvent::new(ActorRef::from(actor_key));
+                server
+                    .queue_event(&user_key, &goal)
+                    .expect("the GoalEvent should be queued");
+                state.new_celebrations.push(actor_key);
+            } else if text == CHEER_TEXT {
+                let mascot = TestActor::CounterActor(CounterActor::new().wrap());
+                let actor_key = server
+                    .register_actor(mascot)
+                    .expect("the CounterActor should be registered in the manifest");
+                state.mascot_owners.borrow_mut().insert(actor_key, user_key);
+                server.room_add_actor(&state.room_key, &actor_key);
+                state.new_mascots.push(actor_key);
+            } else if text == PILOT_TEXT {
+                if let Some(actor_key) = state.counters.get(&user_key) {
+                    server.assign_pawn(&user_key, actor_key);
+                }
+            } else if text == FLOOD_TEXT {
+                for index in 0..FLOOD_EVENT_COUNT {
+                    server
+                        .queue_event(&user_key, &TextEvent::new(&flood_of(index)))
+                        .expect("the flood should be queued");
+                }
+            } else if text == BARRIER_TEXT || text == BARRIER_KICK_TEXT {
+                let barrier_count = if text == BARRIER_TEXT { 2 } else { 1 };
+                for barrier in 0..barrier_count {
+                    for index in 0..BARRIER_EVENT_COUNT {
+                        server
+                            .queue_event(
+                                &user_key,
+                                &TextEvent::new(&behind_barrier_of(barrier, index)),
+                            )
+                            .expect("the TextEvents should be queued");
+                    }
+                    let barrier_id = server
+                        .reliable_barrier(&user_key, None)
+                        .expect("the User should be connected");
+                    state.barriers.insert(barrier_id, (user_key, barrier));
+                }
+                if text == BARRIER_KICK_TEXT {
+                    if let Some(user) = server.get_user_ref(&user_key) {
+                        user.kick(KICK_REASON);
+                    }
+                }
+            } else if text == STRAY_CHANNEL_TEXT {
+                let stray = TextEvent::new(&echo_of(&text));
+                match server.queue_event_on_channel(&user_key, STRAY_CHANNEL, &stray) {
+                    Err(NaiaError::NoSuchChannel { .. }) => {
+                        server
+                            .queue_event(&user_key, &TextEvent::new(NO_SUCH_CHANNEL_TEXT))
+                            .expect("the reply should be queued");
+                    }
+                    _ => panic!("the stray channel should be refused"),
+                }
+            } else if text == TICK_PAYLOAD_TEXT {
+                state.tick_payloads = true;
+            } else if text == HOLD_TEXT {
+                state.holding = true;
+            } else if text == RELOAD_TEXT {
+                match server.queue_event(&user_key, &ReloadEvent::new(RELOAD_TEXT)) {
+                    Ok(()) => {}
+                    Err(NaiaError::PeerLacksType { .. }) => {
+                        let has_type = server
+                            .peer_has_event_type::<ReloadEvent>(&user_key)
+                            .expect("the ReloadEvent should be registered");
+                        let reply = if has_type == Some(false) {
+                            TextEvent::new(LACKS_TYPE_TEXT)
+                        } else {
+                            TextEvent::new(&echo_of(&text))
+                        };
+                        server
+                            .queue_event(&user_key, &reply)
+                            .expect("the reply should be queued");
+                    }
+                    Err(_) => panic!("the ReloadEvent should be registered"),
+                }
+            } else if text == ENCORE_TEXT {
+                if let Some(actor_key) = get_mascot(&state.mascot_owners, &user_key) {
+                    let recipients = server
+                        .queue_actor_event(
+                            &actor_key,
+                            &CheerEvent::new(ENCORE_TEXT),
+                            ActorEventDelivery::SkipUnspawned,
+                        )
+                        .expect("the CheerEvent should be actor-scoped");
+                    log.cheer_recipients.push(recipients);
+                }
+            } else {
+                if text == REVEAL_TEXT {
+                    for actor_key in state.counters.values() {
+                        server.set_property_visibility(&user_key, actor_key, None);
+                    }
+                } else if text == PAUSE_TEXT {
+                    server.pause_replication(&user_key);
+                } else if text == RESUME_TEXT {
+                    server.resume_replication(&user_key);
+                } else if text == EXTEND_TEXT && !state.extended {
+                    server.register_event(ReloadEvent::get_builder());
+                    state.extended = true;
+                }
+                let reply = TextEvent::new(&echo_of(&text));
+                server
+                    .queue_event(&user_key, &reply)
+                    .expect("the echo should be queued");
+            }
+            log.received_texts.push(text);
+            log.received_builds.push(*text_event.build.get());
+        }
+        ServerEvent::Command(_, _, TestEvent::TextEvent(text_event)) => {
+            let text = text_event.text.get().clone();
+            log.received_commands.push((text, state.clock.now()));
+        }
+        ServerEvent::SkippedFrame(_, skipped_frame) => {
+            log.skipped_frames.push(skipped_frame);
+        }
+        ServerEvent::BarrierCompleted(barrier_id, outcome) => {
+            if let Some((user_key, barrier)) = state.barriers.remove(&barrier_id) {
+                log.barrier_outcomes.push((barrier, outcome));
+                if outcome == BarrierOutcome::Delivered {
+                    server
+                        .queue_event(&user_key, &TextEvent::new(&barrier_done_of(barrier)))
+                        .expect("the User should still be connected");
+                }
+            }
+        }
+        ServerEvent::ConnectionCongested(_, queue) => {
+            log.congestion_changes.push((queue, true));
+        }
+        ServerEvent::ConnectionCongestionCleared(_, queue) => {
+            log.congestion_changes.push((queue, false));
+        }
+        ServerEvent::Tick => {
+            // the GoalEvents referencing these were sent on the last tick
+            for actor_key in state.celebrations.drain(..) {
+                server.room_add_actor(&state.room_key, &actor_key);
+            }
+            state.celebrations.append(&mut state.new_celebrations);
+            // these were first sent on the last tick, so are yet to be created
+            for actor_key in state.mascots.drain(..) {
+                let recipients = server
+                    .queue_actor_event(
+                        &actor_key,
+                        &CheerEvent::new(CHEER_TEXT),
+                        ActorEventDelivery::HoldUntilSpawned,
+                    )
+                    .expect("the CheerEvent should be actor-scoped");
+                log.cheer_recipients.push(recipients);
+            }
+            state.mascots.append(&mut state.new_mascots);
+            if state.tick_payloads {
+                let tick = server.get_server_tick();
+                server
+                    .set_tick_payload(tick, &TextEvent::new(&tick_payload_of(tick)))
+                    .expect("the tick payload should be set");
+            }
+            for (_, actor) in server.actors_iter() {
+                match actor {
+                    TestActor::CounterActor(counter) => {
+                        counter.borrow_mut().step();
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// gets the mascot spawned for the given User, if any
+fn get_mascot(
+    mascot_owners: &RefCell<HashMap<ActorKey, UserKey>>,
+    user_key: &UserKey,
+) -> Option<ActorKey> {
+    return mascot_owners
+        .borrow()
+        .iter()
+        .find(|(_, owner)| *owner == user_key)
+        .map(|(actor_key, _)| *actor_key);
+}
+
+// gets the visibility of a CounterActor which hides its value
+fn get_hidden_value_mask() -> StateMask {
+    let mut mask = StateMask::new(1);
+    mask.set_bit(CounterActorProp::LIMIT as u8, true);
+    return mask;
+}
+
+// logs the value of every CounterActor as just sent, along with the value kept
+// in its history from a few ticks before
+fn log_counter_history(server: &NaiaServer<TestEvent, TestActor>, state: &mut ServerState) {
+    let tick = server.get_server_tick();
+    let past_tick = tick.wrapping_sub(HISTORY_LOOKBACK);
+    for actor_key in state.counters.values() {
+        for (key, actor) in server.actors_iter() {
+            if key == *actor_key {
+                let TestActor::CounterActor(counter) = actor;
+                let value = *counter.borrow().value.get();
+                state.log.replicated_counts.push((tick, value));
+            }
+        }
+        if let Some(TestActor::CounterActor(counter)) = server.state_at_tick(actor_key, past_tick) {
+            let value = *counter.borrow().value.get();
+            state.log.historical_counts.push((past_tick, value));
+        }
+    }
+}
+
+// logs the value of every CounterActor in the room, as the world the Clients
+// should end up with
+fn log_final_counts(server: &NaiaServer<TestEvent, TestActor>, state: &mut ServerState) {
+    let room = server
+        .get_room(state.room_key)
+        .expect("the room is never deleted");
+    for (key, actor) in server.actors_iter() {
+        if room
+            .actors_iter()
+            .any(|room_actor_key| *room_actor_key == key)
+        {
+            let TestActor::CounterActor(counter) = actor;
+            let value = *counter.borrow().value.get();
+            state.log.final_counts.push((ActorRef::from(key), value));
+        }
+    }
+}
+
+// hands every event it's called with on to `handle_event()`
+struct TestEventHandler {
+    state: Rc<RefCell<ServerState>>,
+}
+
+impl ServerEventHandler<TestEvent, TestActor> for TestEventHandler {
+    fn on_connect(
+        &mut self,
+        context: &mut ServerContext<TestEvent, TestActor>,
+        user_key: UserKey,
+        connection_info: ConnectionInfo,
+    ) {
+        let event = ServerEvent::Connection(user_key, connection_info);
+        handle_event(context, &mut self.state.borrow_mut(), event);
+    }
+
+    fn on_disconnect(
+        &mut self,
+        context: &mut ServerContext<TestEvent, TestActor>,
+        user_key: UserKey,
+        user: User,
+    ) {
+        let event = ServerEvent::Disconnection(user_key, user);
+        handle_event(context, &mut self.state.borrow_mut(), event);
+    }
+
+    fn on_message(
+        &mut self,
+        context: &mut ServerContext<TestEvent, TestActor>,
+        user_key: UserKey,
+        event: TestEvent,
+    ) {
+        let event = ServerEvent::Event(user_key, event);
+        handle_event(context, &mut self.state.borrow_mut(), event);
+    }
+
+    fn on_tick(&mut self, context: &mut ServerContext<TestEvent, TestActor>) {
+        handle_event(context, &mut self.state.borrow_mut(), ServerEvent::Tick);
+    }
+}
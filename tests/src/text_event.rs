@@ -0,0 +1,27 @@
+use crate::TestEvent;
+use naia_derive::Event;
+use naia_shared::{Event, Property};
+
+/// The build of the test protocol, which has been through one change, adding a
+/// Property to the TextEvent & the CounterActor each
+pub const BUILD: u16 = 2;
+
+#[derive(Event, Clone)]
+#[type_name = "TestEvent"]
+pub struct TextEvent {
+    pub text: Property<String>,
+    /// The build of the host which sent the TextEvent, which hosts on the
+    /// first build don't send
+    #[added(version = 2, default = 1)]
+    pub build: Property<u16>,
+}
+
+impl TextEvent {
+    fn is_guaranteed() -> bool {
+        true
+    }
+
+    pub fn new(text: &str) -> TextEvent {
+        return TextEvent::new_complete(text.to_string(), BUILD);
+    }
+}
@@ -0,0 +1,10 @@
+use naia_derive::Actor;
+use naia_shared::{Actor, Property};
+
+use super::TestActor;
+
+#[derive(Actor)]
+#[type_name = "TestActor"]
+pub struct CounterActor {
+    pub value: Property<u16>,
+}
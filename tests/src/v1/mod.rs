@@ -0,0 +1,28 @@
+//! The test protocol as it was on its first build, before Properties were
+//! added to the TextEvent & the CounterActor, for Clients which haven't been
+//! updated yet
+
+mod auth_event;
+mod counter_actor;
+mod text_event;
+
+pub use auth_event::AuthEvent;
+pub use counter_actor::CounterActor;
+pub use text_event::TextEvent;
+
+use naia_derive::protocol;
+
+protocol! {
+    pub events TestEvent {
+        AuthEvent,
+        #[channel(0)]
+        #[timestamped]
+        TextEvent,
+    }
+    pub actors TestActor {
+        CounterActor,
+    }
+    /// Loads the Manifest of the first build, registering the same types in
+    /// the same order as the current one
+    pub fn manifest_load;
+}
@@ -0,0 +1,19 @@
+use super::TestEvent;
+use naia_derive::Event;
+use naia_shared::{Event, Property};
+
+#[derive(Event, Clone)]
+#[type_name = "TestEvent"]
+pub struct TextEvent {
+    pub text: Property<String>,
+}
+
+impl TextEvent {
+    fn is_guaranteed() -> bool {
+        true
+    }
+
+    pub fn new(text: &str) -> TextEvent {
+        return TextEvent::new_complete(text.to_string());
+    }
+}
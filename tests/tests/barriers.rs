@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    barrier_done_of, behind_barrier_of, get_shared_config, server_address, wait_for_connection,
+    ChaosConfig, ChaosProxy, TestClient, TestEvent, TestServer, TextEvent, BARRIER_EVENT_COUNT,
+    BARRIER_KICK_TEXT, BARRIER_TEXT, KICK_REASON, TIMEOUT,
+};
+use naia_server::BarrierOutcome;
+
+#[test]
+fn barrier_completes_once_events_before_it_are_delivered() {
+    let server = TestServer::start(server_address(14375), get_shared_config(None));
+    // packets are lost & re-transmitted, so the Events behind the first
+    // barrier are still in flight when it completes, and not only those
+    let proxy = ChaosProxy::start(
+        server_address(14376),
+        server.address(),
+        ChaosConfig {
+            loss: 0.3,
+            duplication: 0.1,
+            max_delay: Duration::from_millis(40),
+        },
+    );
+    let mut client =
+        TestClient::connect(proxy.address(), get_shared_config(None), "charlie", "12345");
+    assert!(
+        wait_for_connection(&mut client),
+        "the Client never connected"
+    );
+
+    client
+        .client()
+        .send_event(&TextEvent::new(BARRIER_TEXT))
+        .unwrap();
+    let mut received_texts: Vec<String> = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Event(TestEvent::TextEvent(text_event)) = event {
+                received_texts.push(text_event.text.get().clone());
+            }
+            return received_texts.contains(&barrier_done_of(0))
+                && received_texts.contains(&barrier_done_of(1));
+        }),
+        "the barriers never completed"
+    );
+
+    // each barrier completes after every Event behind it was received, which
+    // the Client receives ahead of the news
+    for barrier in 0..2 {
+        let done_at = received_texts
+            .iter()
+            .position(|text| *text == barrier_done_of(barrier))
+            .unwrap();
+        for index in 0..BARRIER_EVENT_COUNT {
+            let text = behind_barrier_of(barrier, index);
+            assert!(
+                received_texts[..done_at].contains(&text),
+                "{} arrived after its barrier completed",
+                text
+            );
+        }
+    }
+
+    drop(client);
+    drop(proxy);
+    let log = server.stop();
+    // the barrier placed first completes first, without waiting for the
+    // Events queued after it
+    assert_eq!(
+        log.barrier_outcomes,
+        [
+            (0, BarrierOutcome::Delivered),
+            (1, BarrierOutcome::Delivered)
+        ]
+    );
+}
+
+#[test]
+fn barrier_fails_when_connection_is_closed() {
+    let server = TestServer::start(server_address(14377), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        wait_for_connection(&mut client),
+        "the Client never connected"
+    );
+
+    client
+        .client()
+        .send_event(&TextEvent::new(BARRIER_KICK_TEXT))
+        .unwrap();
+    let mut kick_reason = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::DisconnectedByServer(reason) = event {
+                kick_reason = Some(reason);
+                return true;
+            }
+            return false;
+        }),
+        "the Client was never kicked"
+    );
+    assert_eq!(kick_reason.as_deref(), Some(KICK_REASON));
+
+    let log = server.stop();
+    assert_eq!(log.disconnections, 1);
+    assert_eq!(log.barrier_outcomes, [(0, BarrierOutcome::Disconnected)]);
+    assert!(log.barrier_outcomes[0].1.is_failed());
+}
@@ -0,0 +1,72 @@
+use naia_client::{ClientEvent, NaiaError};
+use naia_integration_tests::{
+    echo_of, get_shared_config, server_address, TestClient, TestEvent, TestServer, TextEvent,
+    NO_SUCH_CHANNEL_TEXT, STRAY_CHANNEL, STRAY_CHANNEL_TEXT, TIMEOUT,
+};
+
+fn is_no_such_channel(result: Result<(), NaiaError>) -> bool {
+    match result {
+        Err(NaiaError::NoSuchChannel {
+            channel_index,
+            channel_count,
+        }) => return channel_index == STRAY_CHANNEL && channel_count == 1,
+        _ => return false,
+    }
+}
+
+#[test]
+fn events_on_unconfigured_channels_are_refused() {
+    let server = TestServer::start(server_address(14380), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+
+    // refused before being held until connected
+    let held = client
+        .client()
+        .send_event_on_channel(STRAY_CHANNEL, &TextEvent::new("held"));
+    assert!(is_no_such_channel(held));
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    let sent = client
+        .client()
+        .send_event_on_channel(STRAY_CHANNEL, &TextEvent::new("sent"));
+    assert!(is_no_such_channel(sent));
+
+    // the Server refuses to send on it too, & the connection carries on
+    client
+        .client()
+        .send_event(&TextEvent::new(STRAY_CHANNEL_TEXT))
+        .unwrap();
+    client
+        .client()
+        .send_event(&TextEvent::new("hello"))
+        .unwrap();
+    let mut received = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Event(TestEvent::TextEvent(text_event)) = event {
+                received.push(text_event.text.get().clone());
+            }
+            return received.len() == 2;
+        }),
+        "the replies never arrived"
+    );
+    assert_eq!(
+        received,
+        [NO_SUCH_CHANNEL_TEXT.to_string(), echo_of("hello")]
+    );
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, [STRAY_CHANNEL_TEXT, "hello"]);
+}
@@ -0,0 +1,56 @@
+use std::{fs, path::PathBuf};
+
+use naia_shared::conformance;
+
+// the committed fixtures, at the root of the repository
+fn fixture_directory() -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("conformance");
+}
+
+#[test]
+fn committed_fixtures_are_current() {
+    for (file_name, json) in conformance::generate() {
+        let committed = fs::read_to_string(fixture_directory().join(&file_name))
+            .unwrap_or_else(|_| panic!("no committed fixture {}", file_name));
+        assert_eq!(
+            committed, json,
+            "{} is out of date, regenerate the fixtures with `cargo run -p \
+             naia-integration-tests --bin conformance -- generate conformance`",
+            file_name
+        );
+    }
+}
+
+#[test]
+fn committed_fixtures_verify() {
+    let case_count = conformance::verify_fixtures(&fixture_directory()).unwrap();
+    assert!(case_count > conformance::CONSTRUCTS.len());
+}
+
+#[test]
+fn fixtures_disagreeing_with_the_crate_are_rejected() {
+    let json = fs::read_to_string(fixture_directory().join("standard_header.json")).unwrap();
+
+    // a field which doesn't match the bytes
+    let wrong_field = json.replacen("\"host_tick\":1000", "\"host_tick\":1001", 1);
+    assert_ne!(wrong_field, json);
+    assert!(conformance::verify(&wrong_field)
+        .unwrap_err()
+        .contains("host_tick"));
+
+    // bytes which don't match the fields
+    let wrong_bytes = json.replacen("0102010200deadbeef", "0102010200deadbeee", 1);
+    assert_ne!(wrong_bytes, json);
+    assert!(conformance::verify(&wrong_bytes).is_err());
+
+    let other_version = json.replacen(
+        &format!("\"protocol_version\": {}", naia_shared::PROTOCOL_VERSION),
+        "\"protocol_version\": 0",
+        1,
+    );
+    assert!(conformance::verify(&other_version)
+        .unwrap_err()
+        .contains("protocol version"));
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    flood_of, get_shared_config, manifest_load, server_address, TestClient, TestEvent, TestServer,
+    TextEvent, FLOOD_EVENT_COUNT, FLOOD_TEXT, TIMEOUT,
+};
+use naia_server::{
+    CongestionLimits, DebugCommand, DebugResponse, LinkConditionerConfig, NaiaServer,
+    OutgoingQueue, ServerConfig,
+};
+use naia_shared::NaiaError;
+
+// The latency the Server's link conditioner throttles the Client's
+// acknowledgements with, long enough for the congestion to be noticed
+const THROTTLED_LATENCY: u32 = 1000;
+
+// Receives whatever the Client is sent for long enough for the Server to have
+// ticked since
+fn wait_for_tick(client: &mut TestClient) {
+    client.wait_for(Duration::from_millis(100), |_, _| false);
+}
+
+#[test]
+fn throttled_connection_congests_and_clears() {
+    // the flood is past the soft byte limit, though not the soft message limit
+    let mut server_config = ServerConfig::default();
+    server_config.event_congestion_byte_limits =
+        CongestionLimits::new(flood_of(0).len() * FLOOD_EVENT_COUNT / 2, 64 * 1024);
+    let server = TestServer::start_with_config(
+        server_address(14387),
+        get_shared_config(None),
+        server_config,
+    );
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the flood isn't acknowledged until long after it arrives
+    let config = LinkConditionerConfig::new(THROTTLED_LATENCY, 0, 0.0, 0.0);
+    assert!(matches!(
+        server.debug_command(DebugCommand::SetLinkConditioner(Some(config))),
+        DebugResponse::Scheduled(_)
+    ));
+    wait_for_tick(&mut client);
+    client
+        .client()
+        .send_event(&TextEvent::new(FLOOD_TEXT))
+        .unwrap();
+    let mut flood_count = 0;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Event(TestEvent::TextEvent(_)) = event {
+                flood_count += 1;
+            }
+            return flood_count == FLOOD_EVENT_COUNT;
+        }),
+        "the flood never arrived"
+    );
+
+    // once the link recovers, the acknowledgements drain the queue
+    assert!(matches!(
+        server.debug_command(DebugCommand::SetLinkConditioner(None)),
+        DebugResponse::Scheduled(_)
+    ));
+    client.wait_for(
+        Duration::from_millis(THROTTLED_LATENCY as u64 * 2),
+        |_, _| false,
+    );
+
+    let log = server.stop();
+    assert_eq!(
+        log.congestion_changes,
+        [
+            (OutgoingQueue::Events, true),
+            (OutgoingQueue::Events, false)
+        ]
+    );
+}
+
+#[test]
+fn soft_congestion_limits_must_be_below_hard_limits() {
+    let mut server_config = ServerConfig::default();
+    server_config.actor_congestion_limits = CongestionLimits::new(64, 64);
+    let result = smol::block_on(NaiaServer::try_new(
+        server_address(14388),
+        manifest_load(),
+        Some(server_config),
+        get_shared_config(None),
+    ));
+    match result {
+        Err(NaiaError::InvalidConfig(message)) => {
+            assert!(message.contains("actor_congestion_limits"));
+        }
+        _ => panic!("the invalid limits were accepted"),
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use naia_client::{
+    ClientConfig, ClientEvent, ConnectFailReason, ConnectionState, HookAction, ManualClock,
+    NaiaClient,
+};
+use naia_integration_tests::{
+    get_client_config, get_shared_config, manifest_load, server_address, AuthEvent, TestActor,
+    TestClient, TestEvent, TestServer, TextEvent, TIMEOUT,
+};
+use naia_shared::NaiaError;
+
+// How far the virtual clock is moved between each call to `receive()`
+const STEP: Duration = Duration::from_millis(10);
+
+// How long the Client waits to hear from the Server before giving up on it
+const DISCONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+// A Client on a clock which only moves a step at a time
+struct VirtualClient {
+    clock: Arc<ManualClock>,
+    client: NaiaClient<TestEvent, TestActor>,
+}
+
+impl VirtualClient {
+    fn connect(server_address: SocketAddr, mut client_config: ClientConfig) -> Self {
+        let clock = Arc::new(ManualClock::new());
+        client_config.clock = clock.clone();
+        let client = NaiaClient::new(
+            server_address,
+            manifest_load(),
+            Some(client_config),
+            get_shared_config(None),
+            Some(TestEvent::AuthEvent(AuthEvent::new("charlie", "12345"))),
+        )
+        .expect("the AuthEvent should be registered in the manifest");
+        return VirtualClient { clock, client };
+    }
+
+    // Advances the clock a step at a time, handing every event the Client
+    // emits to `handle` along with how long it took on the virtual clock,
+    // until `handle` returns true, or the timeout on the virtual clock runs
+    // out. Returns how long it took, if `handle` returned true in time
+    fn run_until<F: FnMut(Duration, &ClientEvent<TestEvent>) -> bool>(
+        &mut self,
+        timeout: Duration,
+        mut handle: F,
+    ) -> Option<Duration> {
+        let started_at = self.clock.elapsed();
+        while self.clock.elapsed() - started_at < timeout {
+            while let Some(result) = self.client.receive() {
+                if let Ok(event) = result {
+                    let elapsed = self.clock.elapsed() - started_at;
+                    if handle(elapsed, &event) {
+                        return Some(elapsed);
+                    }
+                }
+            }
+            self.clock.advance(STEP);
+            // give the Server time to answer anything just sent
+            thread::sleep(Duration::from_millis(1));
+        }
+        return None;
+    }
+}
+
+#[test]
+fn handshake_retries_back_off_to_failure_on_the_virtual_clock() {
+    // nothing is ever answered
+    let silent_server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut client_config = ClientConfig::default();
+    client_config.send_handshake_interval = Duration::from_millis(100);
+    client_config.handshake_backoff_multiplier = 2.0;
+    client_config.max_send_handshake_interval = Duration::from_millis(400);
+    client_config.max_handshake_attempts = 4;
+    let mut client = VirtualClient::connect(silent_server.local_addr().unwrap(), client_config);
+
+    // timed from the first attempt, which is made once the socket is ready
+    let mut states = Vec::new();
+    let mut first_attempt_at = None;
+    let failed = ConnectionState::ConnectFailed(ConnectFailReason::Timeout);
+    client.run_until(TIMEOUT, |elapsed, event| {
+        if let ClientEvent::ConnectionStateChanged(state) = event {
+            let first_attempt_at = *first_attempt_at.get_or_insert(elapsed);
+            states.push(((elapsed - first_attempt_at).as_millis(), *state));
+            return *state == failed;
+        }
+        return false;
+    });
+
+    // each attempt waits twice as long as the last, up to the cap, before the
+    // Client gives up for good
+    assert_eq!(
+        states,
+        [
+            (0, ConnectionState::Connecting { attempt: 1 }),
+            (100, ConnectionState::Connecting { attempt: 2 }),
+            (300, ConnectionState::Connecting { attempt: 3 }),
+            (700, ConnectionState::Connecting { attempt: 4 }),
+            (1100, failed),
+        ]
+    );
+    assert!(client
+        .run_until(Duration::from_secs(5), |_, _| true)
+        .is_none());
+    match client.client.send_event(&TextEvent::new("failed")) {
+        Err(NaiaError::NotConnected) => {}
+        result => panic!("an Event was accepted after failing: {:?}", result),
+    }
+}
+
+#[test]
+fn disconnecting_times_out_on_the_virtual_clock_once_the_server_goes_quiet() {
+    let server = TestServer::start(server_address(14389), get_shared_config(None));
+    let mut client_config = get_client_config();
+    client_config.disconnection_timeout_duration = DISCONNECTION_TIMEOUT;
+    let mut client = VirtualClient::connect(server.address(), client_config);
+    assert!(
+        client
+            .run_until(TIMEOUT, |_, event| match event {
+                ClientEvent::ConnectionStateChanged(ConnectionState::Connected) => true,
+                _ => false,
+            })
+            .is_some(),
+        "the Client never connected"
+    );
+
+    // nothing more is heard from the Server, so the Event is never delivered
+    client
+        .client
+        .set_incoming_hook(|_, _| return HookAction::Drop);
+    client
+        .client
+        .send_event(&TextEvent::new("unheard"))
+        .unwrap();
+    client.client.disconnect();
+    assert_eq!(
+        client.client.connection_state(),
+        ConnectionState::Disconnecting
+    );
+    match client.client.send_event(&TextEvent::new("too late")) {
+        Err(NaiaError::NotConnected) => {}
+        result => panic!("an Event was accepted while disconnecting: {:?}", result),
+    }
+
+    // the Client gives up on delivering it once the Server has timed out
+    let mut states = Vec::new();
+    let mut disconnected = false;
+    let took = client.run_until(TIMEOUT, |_, event| match event {
+        ClientEvent::ConnectionStateChanged(state) => {
+            states.push(*state);
+            return *state == ConnectionState::Disconnected;
+        }
+        ClientEvent::Disconnection => {
+            disconnected = true;
+            return false;
+        }
+        _ => false,
+    });
+    let took = took.expect("the Client never disconnected");
+    assert!(disconnected, "the Client never told of its Disconnection");
+    // the Server was last heard from a heartbeat or so before disconnecting
+    assert!(took >= DISCONNECTION_TIMEOUT / 2, "took {:?}", took);
+    assert!(took <= DISCONNECTION_TIMEOUT + STEP, "took {:?}", took);
+    assert_eq!(
+        states,
+        [
+            ConnectionState::Disconnecting,
+            ConnectionState::Disconnected
+        ]
+    );
+
+    // and never connects again
+    states.clear();
+    client.run_until(Duration::from_secs(3), |_, event| {
+        if let ClientEvent::ConnectionStateChanged(state) = event {
+            states.push(*state);
+        }
+        return false;
+    });
+    assert!(states.is_empty(), "the Client went on to {:?}", states);
+
+    server.stop();
+}
+
+#[test]
+fn disconnect_delivers_what_was_sent_then_closes_the_connection() {
+    let server = TestServer::start(server_address(14390), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    client
+        .client()
+        .send_event(&TextEvent::new("goodbye"))
+        .unwrap();
+    client.client().disconnect();
+    assert_eq!(
+        client.client().connection_state(),
+        ConnectionState::Disconnecting
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Disconnection => true,
+            _ => false,
+        }),
+        "the Client never disconnected"
+    );
+    assert_eq!(
+        client.client().connection_state(),
+        ConnectionState::Disconnected
+    );
+
+    // the Server drops the connection as soon as it's told, rather than
+    // waiting for it to time out
+    client.wait_for(Duration::from_millis(500), |_, _| false);
+    let log = server.stop();
+    assert_eq!(log.received_texts, ["goodbye"]);
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.disconnections, 1);
+}
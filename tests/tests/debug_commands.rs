@@ -0,0 +1,142 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    echo_of, get_shared_config, manifest_load, server_address, TestClient, TestEvent, TestServer,
+    TextEvent, TIMEOUT,
+};
+use naia_server::{DebugCommand, DebugResponse, LinkConditionerConfig};
+
+// The latency the Server's link conditioner is given, longer than an echo
+// takes without it
+const CONDITIONED_LATENCY: u32 = 300;
+
+// Gets the text of a response to a command which was carried out
+fn done(response: DebugResponse) -> String {
+    match response {
+        DebugResponse::Done(text) => return text,
+        response => panic!("the command wasn't carried out: {:?}", response),
+    }
+}
+
+// Sends a TextEvent & waits for its echo, returning how long it took
+fn time_echo(client: &mut TestClient, text: &str) -> Duration {
+    let sent_at = Instant::now();
+    client.client().send_event(&TextEvent::new(text)).unwrap();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Event(TestEvent::TextEvent(echo)) => {
+                return *echo.text.get() == echo_of(text);
+            }
+            _ => false,
+        }),
+        "the echo of {:?} never arrived",
+        text
+    );
+    return sent_at.elapsed();
+}
+
+// Receives whatever the Client is sent for long enough for the Server to have
+// ticked since
+fn wait_for_tick(client: &mut TestClient) {
+    client.wait_for(Duration::from_millis(100), |_, _| false);
+}
+
+#[test]
+fn debug_commands_inspect_and_adjust_a_live_server() {
+    let server = TestServer::start(server_address(14343), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the Client is listed by the address the Server knows it by
+    let connections = done(server.debug_command(DebugCommand::ListConnections));
+    assert_eq!(connections.lines().count(), 1);
+    let address: SocketAddr = connections.split(": ").next().unwrap().parse().unwrap();
+    let unknown: SocketAddr = "127.0.0.1:1".parse().unwrap();
+
+    // packets are only traced once tracing is enabled, in both directions
+    assert!(matches!(
+        server.debug_command(DebugCommand::DumpPacketTrace(address)),
+        DebugResponse::Failed(_)
+    ));
+    done(server.debug_command(DebugCommand::SetPacketTracing(address, true)));
+    assert!(done(server.debug_command(DebugCommand::ListConnections)).ends_with(", traced"));
+    time_echo(&mut client, "traced");
+    let trace = done(server.debug_command(DebugCommand::DumpPacketTrace(address)));
+    assert!(trace
+        .lines()
+        .any(|line| line.starts_with("received Data packet")));
+    assert!(trace
+        .lines()
+        .any(|line| line.starts_with("sent Data packet")));
+    done(server.debug_command(DebugCommand::SetPacketTracing(address, false)));
+    assert!(matches!(
+        server.debug_command(DebugCommand::DumpPacketTrace(address)),
+        DebugResponse::Failed(_)
+    ));
+    assert!(matches!(
+        server.debug_command(DebugCommand::SetPacketTracing(unknown, true)),
+        DebugResponse::Failed(_)
+    ));
+
+    assert_eq!(
+        done(server.debug_command(DebugCommand::DumpManifest)),
+        manifest_load().export_schema().to_json()
+    );
+
+    // the unordered channel is listed along with every ordered one
+    let depths = done(server.debug_command(DebugCommand::DumpQueueDepths(address)));
+    assert!(depths.starts_with("outgoing events: "));
+    // the Client's own CounterActor is in scope, so its key is held
+    assert!(depths
+        .lines()
+        .any(|line| line == "local actor keys held: 1"));
+    assert!(depths
+        .lines()
+        .any(|line| line.starts_with("unordered unacknowledged: ")));
+    assert!(depths
+        .lines()
+        .any(|line| line.starts_with("channel 0 unacknowledged: ")));
+    assert!(matches!(
+        server.debug_command(DebugCommand::DumpQueueDepths(unknown)),
+        DebugResponse::Failed(_)
+    ));
+
+    // the link conditioner is set & cleared on the live socket, at the tick
+    // after each command
+    let latency = Duration::from_millis(CONDITIONED_LATENCY as u64);
+    assert!(time_echo(&mut client, "before") < latency);
+    let config = LinkConditionerConfig::new(CONDITIONED_LATENCY, 0, 0.0, 0.0);
+    assert!(matches!(
+        server.debug_command(DebugCommand::SetLinkConditioner(Some(config))),
+        DebugResponse::Scheduled(_)
+    ));
+    wait_for_tick(&mut client);
+    assert!(time_echo(&mut client, "conditioned") >= latency);
+    assert!(matches!(
+        server.debug_command(DebugCommand::SetLinkConditioner(None)),
+        DebugResponse::Scheduled(_)
+    ));
+    wait_for_tick(&mut client);
+    assert!(time_echo(&mut client, "after") < latency);
+
+    let log = server.stop();
+    assert_eq!(
+        log.received_texts,
+        ["traced", "before", "conditioned", "after"]
+    );
+}
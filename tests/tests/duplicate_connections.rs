@@ -0,0 +1,150 @@
+use std::time::{Duration, Instant};
+
+use naia_client::{ClientEvent, ConnectFailReason, ConnectionState};
+use naia_integration_tests::{
+    echo_of, get_client_config, get_shared_config, server_address, wait_for_connection,
+    ChaosConfig, ChaosProxy, LoggedEvent, TestClient, TestEvent, TestServer, TextEvent, TIMEOUT,
+};
+use naia_server::{DuplicateConnectionPolicy, ServerConfig};
+use naia_shared::RejectReason;
+
+// How long the Server waits to hear from a crashed Client before timing it out
+const SERVER_DISCONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Relays packets untouched, so that every Client connecting through the proxy
+// reaches the Server from the same address, as a Client restarted behind the
+// same NAT does
+const NO_CHAOS: ChaosConfig = ChaosConfig {
+    loss: 0.0,
+    duplication: 0.0,
+    max_delay: Duration::from_millis(0),
+};
+
+fn connect(proxy: &ChaosProxy) -> TestClient {
+    return TestClient::connect(proxy.address(), get_shared_config(None), "charlie", "12345");
+}
+
+// Sends a TextEvent, & waits for its echo
+fn wait_for_echo(client: &mut TestClient, text: &str) -> bool {
+    client.client().send_event(&TextEvent::new(text)).unwrap();
+    return client.wait_for(TIMEOUT, |_, event| match event {
+        ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+            return *text_event.text.get() == echo_of(text);
+        }
+        _ => false,
+    });
+}
+
+#[test]
+fn restarted_client_replaces_its_crashed_connection() {
+    let mut server_config = ServerConfig::default();
+    server_config.duplicate_connection_policy = DuplicateConnectionPolicy::ReplaceExisting;
+    server_config.disconnection_timeout_duration = SERVER_DISCONNECTION_TIMEOUT;
+    let server = TestServer::start_with_config(
+        server_address(14392),
+        get_shared_config(None),
+        server_config,
+    );
+    let proxy = ChaosProxy::start(server_address(14393), server.address(), NO_CHAOS);
+    let mut crashed = connect(&proxy);
+    assert!(wait_for_connection(&mut crashed), "never connected");
+
+    // the Client crashes without a word to the Server, & is straight back
+    drop(crashed);
+    let restarted_at = Instant::now();
+    let mut restarted = connect(&proxy);
+    assert!(
+        wait_for_connection(&mut restarted),
+        "the restarted Client never connected"
+    );
+    assert!(
+        restarted_at.elapsed() < SERVER_DISCONNECTION_TIMEOUT,
+        "the restarted Client waited for the crashed one to time out"
+    );
+    assert!(
+        wait_for_echo(&mut restarted, "back again"),
+        "the echo never arrived"
+    );
+
+    let log = server.stop();
+    assert_eq!(
+        log.event_order,
+        [
+            LoggedEvent::Connection,
+            LoggedEvent::Disconnection,
+            LoggedEvent::Connection,
+            LoggedEvent::Text,
+        ]
+    );
+}
+
+#[test]
+fn restarted_client_is_rejected_until_its_crashed_connection_times_out() {
+    let mut server_config = ServerConfig::default();
+    server_config.duplicate_connection_policy = DuplicateConnectionPolicy::RejectNew;
+    server_config.disconnection_timeout_duration = SERVER_DISCONNECTION_TIMEOUT;
+    let server = TestServer::start_with_config(
+        server_address(14394),
+        get_shared_config(None),
+        server_config,
+    );
+    let proxy = ChaosProxy::start(server_address(14395), server.address(), NO_CHAOS);
+    let mut crashed = connect(&proxy);
+    assert!(wait_for_connection(&mut crashed), "never connected");
+
+    // a Client which gives up quickly is told why it was refused
+    drop(crashed);
+    let crashed_at = Instant::now();
+    let mut client_config = get_client_config();
+    client_config.max_handshake_attempts = 3;
+    let mut refused = TestClient::connect_with_config(
+        proxy.address(),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+    let mut fail_reason = None;
+    assert!(
+        refused.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ConnectionStateChanged(ConnectionState::ConnectFailed(reason)) => {
+                fail_reason = Some(reason);
+                return true;
+            }
+            ClientEvent::Connection(_) => panic!("the restarted Client was accepted"),
+            _ => false,
+        }),
+        "the restarted Client never gave up"
+    );
+    assert_eq!(
+        fail_reason,
+        Some(ConnectFailReason::Rejected(
+            RejectReason::DuplicateConnection
+        ))
+    );
+    drop(refused);
+
+    // one which keeps trying is accepted once the crashed connection times
+    // out, its handshakes not keeping that connection alive
+    let mut restarted = connect(&proxy);
+    assert!(
+        wait_for_connection(&mut restarted),
+        "the restarted Client never connected"
+    );
+    assert!(crashed_at.elapsed() >= SERVER_DISCONNECTION_TIMEOUT);
+    assert!(
+        wait_for_echo(&mut restarted, "back again"),
+        "the echo never arrived"
+    );
+
+    let log = server.stop();
+    assert_eq!(
+        log.event_order,
+        [
+            LoggedEvent::Connection,
+            LoggedEvent::Disconnection,
+            LoggedEvent::Connection,
+            LoggedEvent::Text,
+        ]
+    );
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use naia_client::{ClientEvent, ManualClock, NaiaClient, ReconnectPolicy};
+use naia_integration_tests::{
+    get_client_config, get_shared_config, manifest_load, server_address, AuthEvent, ServerLog,
+    TestActor, TestEvent, TestServer, TextEvent, PILOT_TEXT,
+};
+use naia_server::ServerConfig;
+use naia_shared::{utils::duration_between, Clock, Instant, LocalActorKey};
+
+// How far the clock both sides read is advanced on each step
+const STEP: Duration = Duration::from_millis(20);
+
+// How long each step waits for the Server to catch up, in real time. It's
+// longer than a step, so that the Server never ticks more than once per step,
+// as Commands are only popped on the tick they were issued for
+const SETTLE: Duration = Duration::from_millis(25);
+
+// How long every packet takes to cross the link, each way, for a round trip
+// time of 150ms
+const LATENCY: Duration = Duration::from_millis(75);
+
+// The most steps the scenario waits for any single thing it expects to happen
+const MAX_STEPS: usize = 500;
+
+// The text of Commands sent on a live connection, & while reconnecting
+const LIVE_TEXT: &str = "live";
+const EARLY_TEXT: &str = "early";
+
+// Relays UDP packets between a single Client & the Server, delaying each by
+// `LATENCY` of the clock both sides read, & dropping every one while down
+struct SlowLink {
+    client_socket: UdpSocket,
+    server_socket: UdpSocket,
+    server_address: SocketAddr,
+    client_address: Option<SocketAddr>,
+    // when each packet arrives, along with whether it's headed for the Server
+    in_flight: VecDeque<(Duration, bool, Vec<u8>)>,
+    up: bool,
+    // when the first packet from the Client was relayed since it came back up
+    first_sent_since_up: Option<Duration>,
+}
+
+impl SlowLink {
+    fn start(port: u16, server_address: SocketAddr) -> Self {
+        let client_socket = UdpSocket::bind(("127.0.0.1", port)).unwrap();
+        let server_socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        client_socket.set_nonblocking(true).unwrap();
+        server_socket.set_nonblocking(true).unwrap();
+        return SlowLink {
+            client_socket,
+            server_socket,
+            server_address,
+            client_address: None,
+            in_flight: VecDeque::new(),
+            up: true,
+            first_sent_since_up: None,
+        };
+    }
+
+    fn address(&self) -> SocketAddr {
+        return self.client_socket.local_addr().unwrap();
+    }
+
+    fn set_up(&mut self, up: bool) {
+        self.up = up;
+        self.first_sent_since_up = None;
+    }
+
+    // Relays every packet which has arrived by the given time, & sets off
+    // every packet sent since the last pump
+    fn pump(&mut self, now: Duration) {
+        let mut buffer = [0; 2048];
+        loop {
+            match self.client_socket.recv_from(&mut buffer) {
+                Ok((length, address)) => {
+                    self.client_address = Some(address);
+                    if self.up {
+                        self.first_sent_since_up.get_or_insert(now);
+                        self.in_flight
+                            .push_back((now + LATENCY, true, buffer[..length].to_vec()));
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => panic!("the link couldn't receive from the Client: {}", error),
+            }
+        }
+        loop {
+            match self.server_socket.recv_from(&mut buffer) {
+                Ok((length, _)) => {
+                    if self.up {
+                        self.in_flight
+                            .push_back((now + LATENCY, false, buffer[..length].to_vec()));
+                    }
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => panic!("the link couldn't receive from the Server: {}", error),
+            }
+        }
+        while let Some((arrival, _, _)) = self.in_flight.front() {
+            if *arrival > now {
+                break;
+            }
+            let (_, to_server, payload) = self.in_flight.pop_front().unwrap();
+            if !self.up {
+                continue;
+            }
+            if to_server {
+                self.server_socket
+                    .send_to(&payload, self.server_address)
+                    .unwrap();
+            } else if let Some(client_address) = self.client_address {
+                self.client_socket
+                    .send_to(&payload, client_address)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+// A Client steering its Pawn over a SlowLink to a Test Server, on a clock
+// which only moves a step at a time
+struct Scenario {
+    clock: Arc<ManualClock>,
+    server: TestServer,
+    link: SlowLink,
+    client: NaiaClient<TestEvent, TestActor>,
+    pawn: Option<LocalActorKey>,
+}
+
+impl Scenario {
+    fn start(port: u16, session_resumption_grace: Duration, early_data_max_packets: usize) -> Self {
+        let clock = Arc::new(ManualClock::new());
+        let mut server_config = ServerConfig::default();
+        server_config.clock = clock.clone();
+        server_config.disconnection_timeout_duration = Duration::from_secs(1);
+        server_config.session_resumption_grace = Some(session_resumption_grace);
+        let server = TestServer::start_with_config(
+            server_address(port),
+            get_shared_config(None),
+            server_config,
+        );
+        let link = SlowLink::start(port + 1, server.address());
+
+        let mut client_config = get_client_config();
+        client_config.clock = clock.clone();
+        client_config.disconnection_timeout_duration = Duration::from_secs(1);
+        client_config.ping_interval = Duration::from_millis(100);
+        client_config.max_handshake_attempts = 5;
+        client_config.reconnect = Some(ReconnectPolicy {
+            max_attempts: 50,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(100),
+            jitter: 0.0,
+        });
+        client_config.early_data_max_packets = early_data_max_packets;
+        let client = NaiaClient::new(
+            link.address(),
+            manifest_load(),
+            Some(client_config),
+            get_shared_config(None),
+            Some(TestEvent::AuthEvent(AuthEvent::new("charlie", "12345"))),
+        )
+        .expect("the AuthEvent should be registered in the manifest");
+
+        return Scenario {
+            clock,
+            server,
+            link,
+            client,
+            pawn: None,
+        };
+    }
+
+    // Advances the clock a step, sending a Command for the Client's Pawn, &
+    // hands every event the Client receives to `handle`. Returns whether
+    // `handle` returned true for any
+    fn step(&mut self, handle: &mut dyn FnMut(&ClientEvent<TestEvent>) -> bool) -> bool {
+        self.clock.advance(STEP);
+        self.link.pump(self.clock.elapsed());
+        thread::sleep(SETTLE);
+
+        // Commands sent while the Client is reconnecting are only taken if
+        // they can be sent as early data
+        if let Some(pawn) = self.pawn {
+            let text = if self.client.has_connection() {
+                LIVE_TEXT
+            } else {
+                EARLY_TEXT
+            };
+            let _ = self.client.send_command(pawn, &TextEvent::new(text));
+        }
+
+        let mut handled = false;
+        while let Some(result) = self.client.receive() {
+            if let Ok(event) = result {
+                match &event {
+                    ClientEvent::Connection(_) => {
+                        self.pawn = None;
+                        self.client.send_event(&TextEvent::new(PILOT_TEXT)).unwrap();
+                    }
+                    ClientEvent::AssignPawn(pawn) => self.pawn = Some(*pawn),
+                    _ => {}
+                }
+                handled |= handle(&event);
+            }
+        }
+        return handled;
+    }
+
+    // Steps until `handle` returns true for an event the Client receives.
+    // Returns whether it did within `MAX_STEPS`
+    fn step_until<F>(&mut self, mut handle: F) -> bool
+    where
+        F: FnMut(&ClientEvent<TestEvent>) -> bool,
+    {
+        for _ in 0..MAX_STEPS {
+            if self.step(&mut handle) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn step_for(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step(&mut |_| false);
+        }
+    }
+
+    // Connects & takes control of a Pawn, then cuts the link until the Client
+    // notices, & brings it back after the given number of steps more. Returns
+    // when the link came back up
+    fn drop_and_restore(&mut self, outage: usize) -> (Instant, Duration) {
+        assert!(
+            self.step_until(|event| match event {
+                ClientEvent::AssignPawn(_) => true,
+                _ => false,
+            }),
+            "the Client was never assigned a Pawn"
+        );
+        // long enough for the round trip time to be measured
+        self.step_for(50);
+        self.link.set_up(false);
+        assert!(
+            self.step_until(|event| match event {
+                ClientEvent::ConnectionSuspended => true,
+                _ => false,
+            }),
+            "the dead link was never noticed"
+        );
+        self.step_for(outage);
+        self.link.set_up(true);
+        return (self.clock.now(), self.clock.elapsed());
+    }
+
+    // Stops the Test Server, which only happens after its next tick, so the
+    // clock is kept moving until it has
+    fn stop(self) -> ServerLog {
+        drop(self.client);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let ticker = {
+            let clock = self.clock.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                while !stopped.load(Ordering::SeqCst) {
+                    clock.advance(STEP);
+                    thread::sleep(SETTLE);
+                }
+            })
+        };
+        let log = self.server.stop();
+        stopped.store(true, Ordering::SeqCst);
+        ticker.join().unwrap();
+        return log;
+    }
+}
+
+// Drops & resumes a connection, returning how long it took from the Client's
+// first packet after the link came back up to the Server popping its first
+// Command, along with the Test Server's log
+fn reconnect_latency(port: u16, early_data_max_packets: usize) -> (Duration, ServerLog) {
+    let mut scenario = Scenario::start(port, Duration::from_secs(30), early_data_max_packets);
+    let (restored_at, restored_elapsed) = scenario.drop_and_restore(25);
+    assert!(
+        scenario.step_until(|event| match event {
+            ClientEvent::ConnectionResumed => true,
+            ClientEvent::Connection(_) => panic!("the session wasn't resumed"),
+            _ => false,
+        }),
+        "the Client never reconnected"
+    );
+    scenario.step_for(25);
+    let first_sent = scenario
+        .link
+        .first_sent_since_up
+        .expect("the Client never sent anything once the link was back up")
+        - restored_elapsed;
+    let log = scenario.stop();
+
+    let (_, popped_at) = log
+        .received_commands
+        .iter()
+        .find(|(_, popped_at)| *popped_at >= restored_at)
+        .expect("no Commands were popped once the link was back up");
+    let latency = duration_between(&restored_at, popped_at) - first_sent;
+    return (latency, log);
+}
+
+#[test]
+fn early_data_delivers_commands_a_round_trip_sooner_on_resumption() {
+    let (with_early_data, log) = reconnect_latency(14344, 4);
+    assert!(log.early_data_counts.0 > 0);
+    assert_eq!(log.early_data_counts.1, 0);
+    assert!(log
+        .received_commands
+        .iter()
+        .any(|(text, _)| text == EARLY_TEXT));
+
+    let (without_early_data, log) = reconnect_latency(14346, 0);
+    assert_eq!(log.early_data_counts, (0, 0));
+    assert!(log
+        .received_commands
+        .iter()
+        .all(|(text, _)| text == LIVE_TEXT));
+
+    // a round trip over the link is 150ms
+    assert!(
+        with_early_data + Duration::from_millis(100) <= without_early_data,
+        "Commands took {:?} with early data, & {:?} without",
+        with_early_data,
+        without_early_data
+    );
+}
+
+#[test]
+fn early_data_is_discarded_when_the_session_cant_be_resumed() {
+    // the Server forgets the session well before the link comes back up
+    let mut scenario = Scenario::start(14348, Duration::from_millis(500), 4);
+    scenario.drop_and_restore(150);
+    assert!(
+        scenario.step_until(|event| match event {
+            ClientEvent::Connection(_) => true,
+            ClientEvent::ConnectionResumed => panic!("an expired session was resumed"),
+            _ => false,
+        }),
+        "the Client never reconnected"
+    );
+    scenario.step_for(25);
+    let log = scenario.stop();
+
+    assert_eq!(log.early_data_counts.0, 0);
+    assert!(log.early_data_counts.1 > 0);
+    assert!(log
+        .received_commands
+        .iter()
+        .all(|(text, _)| text == LIVE_TEXT));
+}
@@ -0,0 +1,1974 @@
+use std::{
+    any::TypeId,
+    cell::Cell,
+    net::SocketAddr,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use naia_client::{
+    ClientEvent, ConnectFailReason, ConnectionInfo, ConnectionState, FlushMode, HookAction,
+    NaiaClient, NaiaError, ReconnectPolicy,
+};
+use naia_integration_tests::{
+    echo_of, get_client_config, get_shared_config, manifest_load, server_address, tick_payload_of,
+    v1, AuthEvent, ChaosConfig, ChaosProxy, CounterActor, GoalEvent, LoggedEvent, TestActor,
+    TestClient, TestEvent, TestServer, TextEvent, BUILD, CHEER_TEXT, COUNTER_LIMIT, ENCORE_TEXT,
+    GOAL_TEXT, HISTORY_LOOKBACK, KICK_REASON, KICK_TEXT, PAUSE_TEXT, RESUME_TEXT, REVEAL_TEXT,
+    TICK_PAYLOAD_TEXT, TIMEOUT,
+};
+use naia_server::ServerConfig;
+use naia_shared::{
+    sequence_less_than, Event, LinkConditionerConfig, LocalActorKey, Manifest, PacketType,
+    RawEvent, SkippedFrame, StandardHeader, STRICT_CHECKS,
+};
+
+fn get_counter_value(client: &mut TestClient, key: &LocalActorKey) -> Option<u16> {
+    match client.client().get_actor(key) {
+        Some(TestActor::CounterActor(counter)) => {
+            return Some(*counter.borrow().value.get());
+        }
+        None => return None,
+    }
+}
+
+// Waits for the Client's own CounterActor to be created, & then to count all
+// the way up, returning its key. Those of other Users have their values hidden
+// from the Client, so never count up
+fn wait_for_counter(client: &mut TestClient) -> LocalActorKey {
+    let mut counter_key = None;
+    let mut value = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::CreateActor(key) | ClientEvent::UpdateActor(key, _) => {
+                    if let Some(TestActor::CounterActor(counter)) = client.get_actor(&key) {
+                        let key_value = *counter.borrow().value.get();
+                        if key_value > 0 {
+                            counter_key = Some(key);
+                            value = Some(key_value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return value == Some(COUNTER_LIMIT);
+        }),
+        "the CounterActor stopped at {:?}",
+        value
+    );
+    return counter_key.unwrap();
+}
+
+// Sends the given texts, either all at once or one per tick, & waits for
+// their echoes, checking that they arrive in order
+fn send_and_wait_for_echoes(client: &mut TestClient, texts: &[String], one_per_tick: bool) {
+    let mut sent = 0;
+    if !one_per_tick {
+        for text in texts {
+            client.client().send_event(&TextEvent::new(text)).unwrap();
+        }
+        sent = texts.len();
+    }
+
+    let mut received = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::Tick if sent < texts.len() => {
+                    client.send_event(&TextEvent::new(&texts[sent])).unwrap();
+                    sent += 1;
+                }
+                ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                    received.push(text_event.text.get().clone());
+                }
+                _ => {}
+            }
+            return received.len() == texts.len();
+        }),
+        "only {} of {} echoes arrived",
+        received.len(),
+        texts.len()
+    );
+    let expected: Vec<String> = texts.iter().map(|text| echo_of(text)).collect();
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn happy_path() {
+    let server = TestServer::start(server_address(14311), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    let counter_key = wait_for_counter(&mut client);
+    assert_eq!(
+        get_counter_value(&mut client, &counter_key),
+        Some(COUNTER_LIMIT)
+    );
+
+    let texts = vec!["hello".to_string(), "there".to_string()];
+    send_and_wait_for_echoes(&mut client, &texts, false);
+
+    client
+        .client()
+        .send_event(&TextEvent::new(KICK_TEXT))
+        .unwrap();
+    let mut kick_reason = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::DisconnectedByServer(reason) = event {
+                kick_reason = Some(reason);
+                return true;
+            }
+            return false;
+        }),
+        "the Client was never kicked"
+    );
+    assert_eq!(kick_reason.as_deref(), Some(KICK_REASON));
+    assert!(!client.client().has_connection());
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.disconnections, 1);
+    assert_eq!(log.received_texts, ["hello", "there", KICK_TEXT]);
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let server = TestServer::start(server_address(14312), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "54321",
+    );
+
+    assert!(
+        !client.wait_for(Duration::from_secs(1), |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client connected without the right password"
+    );
+
+    let log = server.stop();
+    assert_eq!(log.connections, 0);
+}
+
+#[test]
+fn loss_and_reorder() {
+    // packets in both directions are delayed by between 5 & 35ms, so that
+    // packets sent a tick apart often swap places, and a fifth are dropped
+    let link_condition = LinkConditionerConfig::new(20, 15, 0.2, 0.0);
+    let server = TestServer::start(
+        server_address(14313),
+        get_shared_config(Some(link_condition.clone())),
+    );
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(Some(link_condition)),
+        "charlie",
+        "12345",
+    );
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // every dropped update is made up for, so the Client ends up in sync
+    let counter_key = wait_for_counter(&mut client);
+    assert_eq!(
+        get_counter_value(&mut client, &counter_key),
+        Some(COUNTER_LIMIT)
+    );
+
+    // one Event per tick, so that they're spread over many packets, any of
+    // which may be dropped or overtaken by a later one
+    let texts: Vec<String> = (0..30).map(|index| index.to_string()).collect();
+    send_and_wait_for_echoes(&mut client, &texts, true);
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.received_texts, texts);
+}
+
+#[test]
+fn chaos_with_strict_checks() {
+    // the point of the scenario is that any broken protocol invariant panics
+    assert!(STRICT_CHECKS, "strict checks are only on in debug builds");
+
+    // a third of packets are dropped, a fifth of the rest arrive twice, and
+    // every packet is held back for up to 60ms, so that they're thoroughly
+    // reordered
+    let server = TestServer::start(server_address(14321), get_shared_config(None));
+    let proxy = ChaosProxy::start(
+        server_address(14320),
+        server.address(),
+        ChaosConfig {
+            loss: 0.35,
+            duplication: 0.2,
+            max_delay: Duration::from_millis(60),
+        },
+    );
+    let mut client =
+        TestClient::connect(proxy.address(), get_shared_config(None), "charlie", "12345");
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    let counter_key = wait_for_counter(&mut client);
+    assert_eq!(
+        get_counter_value(&mut client, &counter_key),
+        Some(COUNTER_LIMIT)
+    );
+
+    // Events are neither lost, repeated nor reordered, however their packets
+    // are
+    let texts: Vec<String> = (0..30).map(|index| index.to_string()).collect();
+    send_and_wait_for_echoes(&mut client, &texts, true);
+
+    // a strict check failing on the Server panics its thread, failing this
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.received_texts, texts);
+}
+
+#[test]
+fn packets_dropped_by_hooks_are_recovered() {
+    let server = TestServer::start(server_address(14322), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the first Data packets each way are dropped, as the network might
+    fn drop_first_data_packets(
+        dropped: Rc<Cell<u32>>,
+    ) -> impl FnMut(&SocketAddr, &[u8]) -> HookAction {
+        return move |_, payload| {
+            if StandardHeader::read_packet_type(payload) == PacketType::Data && dropped.get() < 5 {
+                dropped.set(dropped.get() + 1);
+                return HookAction::Drop;
+            }
+            return HookAction::Pass;
+        };
+    }
+    let dropped_outgoing = Rc::new(Cell::new(0));
+    let dropped_incoming = Rc::new(Cell::new(0));
+    client
+        .client()
+        .set_outgoing_hook(drop_first_data_packets(dropped_outgoing.clone()));
+    client
+        .client()
+        .set_incoming_hook(drop_first_data_packets(dropped_incoming.clone()));
+
+    // every Event in a dropped packet is re-sent, so none go missing
+    let texts: Vec<String> = (0..10).map(|index| index.to_string()).collect();
+    send_and_wait_for_echoes(&mut client, &texts, true);
+    assert_eq!(dropped_outgoing.get(), 5);
+    assert_eq!(dropped_incoming.get(), 5);
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.received_texts, texts);
+}
+
+#[test]
+fn coalescing_client_sends_queued_events_together_once_flushed() {
+    let server = TestServer::start(server_address(14386), get_shared_config(None));
+    let mut client_config = get_client_config();
+    client_config.flush_mode = FlushMode::Coalesce;
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    let data_packets = Rc::new(Cell::new(0));
+    let counted_packets = data_packets.clone();
+    client.client().set_outgoing_hook(move |_, payload| {
+        if StandardHeader::read_packet_type(payload) == PacketType::Data {
+            counted_packets.set(counted_packets.get() + 1);
+        }
+        return HookAction::Pass;
+    });
+
+    // queued Events are held, however often the Client receives
+    let texts: Vec<String> = (0..20).map(|index| index.to_string()).collect();
+    for text in &texts {
+        client.client().send_event(&TextEvent::new(text)).unwrap();
+        client.wait_for(Duration::from_millis(10), |_, _| false);
+    }
+    assert_eq!(data_packets.get(), 0);
+
+    // until flushed, when they all go out in the one packet
+    client.client().flush();
+    assert_eq!(data_packets.get(), 1);
+    assert_eq!(client.client().get_messages_per_packet(), Some(20.0));
+
+    let mut received = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Event(TestEvent::TextEvent(text_event)) = event {
+                received.push(text_event.text.get().clone());
+            }
+            return received.len() == texts.len();
+        }),
+        "only {} of {} echoes arrived",
+        received.len(),
+        texts.len()
+    );
+    let expected: Vec<String> = texts.iter().map(|text| echo_of(text)).collect();
+    assert_eq!(received, expected);
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, texts);
+}
+
+#[test]
+fn server_on_ephemeral_port() {
+    // the OS picks the port, which Clients are given by the Server
+    let server = TestServer::start(server_address(0), get_shared_config(None));
+    assert_ne!(server.address().port(), 0);
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+}
+
+#[test]
+fn connection_info_matches_configuration() {
+    let server = TestServer::start(server_address(14314), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+
+    let mut client_info = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Connection(connection_info) = event {
+                client_info = Some(connection_info);
+                return true;
+            }
+            return false;
+        }),
+        "the Client never connected"
+    );
+
+    // neither side has compression or session resumption enabled, nor MTU
+    // discovery, so both stay at the minimum MTU. Both are built with message
+    // tracing, for the tests
+    let expected = ConnectionInfo {
+        compression_enabled: false,
+        message_tracing_enabled: true,
+        mtu: 508,
+        tick_interval: get_shared_config(None).tick_interval,
+        resumable: false,
+    };
+    assert_eq!(client_info.as_ref(), Some(&expected));
+    assert_eq!(client.client().connection_info(), Some(expected.clone()));
+
+    let log = server.stop();
+    assert_eq!(log.connection_infos, [expected]);
+}
+
+#[test]
+fn event_age_matches_link_latency() {
+    // packets in both directions are delayed by between 40 & 60ms, and none
+    // are dropped
+    let latency = Duration::from_millis(50);
+    let jitter = Duration::from_millis(10);
+    let link_condition = LinkConditionerConfig::new(50, 10, 0.0, 0.0);
+    let server = TestServer::start(
+        server_address(14315),
+        get_shared_config(Some(link_condition.clone())),
+    );
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(Some(link_condition)),
+        "charlie",
+        "12345",
+    );
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the Server pings once a second, so its RTT to the Client is measured
+    // a few times before any TextEvents are sent
+    let measured_by = Instant::now() + Duration::from_millis(3500);
+    client.wait_for(TIMEOUT, |_, _| Instant::now() >= measured_by);
+
+    let texts: Vec<String> = (0..10).map(|index| index.to_string()).collect();
+    let mut sent_at = Vec::new();
+    let mut echo_receipts = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::Tick if sent_at.len() < texts.len() => {
+                    client
+                        .send_event(&TextEvent::new(&texts[sent_at.len()]))
+                        .unwrap();
+                    sent_at.push(Instant::now());
+                }
+                ClientEvent::Event(TestEvent::TextEvent(_)) => {
+                    echo_receipts.push(client.last_event_receipt());
+                }
+                _ => {}
+            }
+            return echo_receipts.len() == texts.len();
+        }),
+        "only {} of {} echoes arrived",
+        echo_receipts.len(),
+        texts.len()
+    );
+    // the echoes are timestamped too, having crossed the same link
+    for receipt in echo_receipts {
+        let age_estimate = receipt.expect("an echo had no receipt").age_estimate;
+        assert!(
+            age_estimate >= latency - jitter,
+            "echo age was {:?}",
+            age_estimate
+        );
+    }
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, texts);
+    // what's left over once the link's delay is accounted for is the time
+    // taken to get around to sending & reading the packets, which is short
+    let tolerance = jitter + Duration::from_millis(15);
+    for ((received_at, receipt), sent_at) in log.text_receipts.iter().zip(sent_at.iter()) {
+        let age = received_at.duration_since(*sent_at);
+        let age_estimate = receipt.expect("a TextEvent had no receipt").age_estimate;
+        let error = match age > age_estimate {
+            true => age - age_estimate,
+            false => age_estimate - age,
+        };
+        assert!(
+            error <= tolerance,
+            "estimated an age of {:?} for a TextEvent {:?} old",
+            age_estimate,
+            age
+        );
+    }
+}
+
+#[test]
+fn connection_events_are_ordered_in_both_event_modes() {
+    let servers = vec![
+        TestServer::start(server_address(14316), get_shared_config(None)),
+        TestServer::start_with_event_handler(server_address(14317), get_shared_config(None)),
+    ];
+    for server in servers {
+        let mut client = TestClient::connect(
+            server.address(),
+            get_shared_config(None),
+            "charlie",
+            "12345",
+        );
+        assert!(
+            client.wait_for(TIMEOUT, |_, event| match event {
+                ClientEvent::Connection(_) => true,
+                _ => false,
+            }),
+            "the Client never connected"
+        );
+
+        let texts = vec!["hello".to_string(), "there".to_string()];
+        send_and_wait_for_echoes(&mut client, &texts, false);
+        client
+            .client()
+            .send_event(&TextEvent::new(KICK_TEXT))
+            .unwrap();
+        assert!(
+            client.wait_for(TIMEOUT, |_, event| match event {
+                ClientEvent::DisconnectedByServer(_) => true,
+                _ => false,
+            }),
+            "the Client was never kicked"
+        );
+
+        let log = server.stop();
+        assert_eq!(log.received_texts, ["hello", "there", KICK_TEXT]);
+        assert_eq!(
+            log.event_order,
+            [
+                LoggedEvent::Connection,
+                LoggedEvent::Text,
+                LoggedEvent::Text,
+                LoggedEvent::Text,
+                LoggedEvent::Disconnection,
+            ]
+        );
+    }
+}
+
+#[test]
+fn first_build_client_works_with_current_server() {
+    let server = TestServer::start(server_address(14318), get_shared_config(None));
+    let mut client = TestClient::connect_with(
+        server.address(),
+        get_shared_config(None),
+        v1::manifest_load(),
+        v1::TestEvent::AuthEvent(v1::AuthEvent::new("charlie", "12345")),
+    );
+
+    // the CounterActor is written without its limit, which the Client doesn't
+    // know of, and still counts all the way up
+    let mut counter_key = None;
+    let mut value = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::CreateActor(key) => counter_key = Some(key),
+                ClientEvent::UpdateActor(key, _) if Some(key) != counter_key => return false,
+                ClientEvent::UpdateActor(..) => {}
+                _ => return false,
+            }
+            if let Some(v1::TestActor::CounterActor(counter)) =
+                client.get_actor(&counter_key.unwrap())
+            {
+                value = Some(*counter.borrow().value.get());
+            }
+            return value == Some(COUNTER_LIMIT);
+        }),
+        "the CounterActor stopped at {:?}",
+        value
+    );
+
+    // the echo is written without the build, or the Client couldn't read it
+    client
+        .client()
+        .send_event(&v1::TextEvent::new("hello"))
+        .unwrap();
+    let mut echo = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Event(v1::TestEvent::TextEvent(text_event)) = event {
+                echo = Some(text_event.text.get().clone());
+                return true;
+            }
+            return false;
+        }),
+        "the echo never arrived"
+    );
+    assert_eq!(echo, Some(echo_of("hello")));
+
+    // a current Client alongside it still sends its build
+    let mut current_client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut current_client);
+    send_and_wait_for_echoes(&mut current_client, &["there".to_string()], false);
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, ["hello", "there"]);
+    assert_eq!(log.received_builds, [1, BUILD]);
+}
+
+#[test]
+fn actor_history_matches_replicated_state() {
+    let server = TestServer::start(server_address(14319), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut client);
+    // let the history catch up with the counter reaching its limit
+    let start = Instant::now();
+    client.wait_for(TIMEOUT, |_, _| {
+        return start.elapsed() > Duration::from_millis(500);
+    });
+
+    let log = server.stop();
+    assert!(
+        log.historical_counts
+            .iter()
+            .any(|(_, value)| *value < COUNTER_LIMIT),
+        "the history never held the counter on its way up"
+    );
+    assert_eq!(
+        log.historical_counts.last().map(|(_, value)| *value),
+        Some(COUNTER_LIMIT)
+    );
+    for (tick, value) in log.historical_counts.iter() {
+        let replicated = log
+            .replicated_counts
+            .iter()
+            .rev()
+            .find(|(replicated_tick, _)| replicated_tick == tick);
+        assert_eq!(
+            replicated,
+            Some(&(*tick, *value)),
+            "the history doesn't match what was sent {} ticks before",
+            HISTORY_LOOKBACK
+        );
+    }
+}
+
+#[test]
+fn hidden_properties_are_never_sent() {
+    let server = TestServer::start(server_address(14323), get_shared_config(None));
+    let mut first = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    let first_counter = wait_for_counter(&mut first);
+    let mut second = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+
+    // the second Client sees its own CounterActor count up, while the first
+    // Client's has its value hidden, so only its creation arrives
+    let mut created = Vec::new();
+    let mut updated = Vec::new();
+    let mut own_counter = None;
+    assert!(
+        second.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::CreateActor(key) => created.push(key),
+                ClientEvent::UpdateActor(key, _) => {
+                    updated.push(key);
+                    if let Some(TestActor::CounterActor(counter)) = client.get_actor(&key) {
+                        if *counter.borrow().value.get() == COUNTER_LIMIT {
+                            own_counter = Some(key);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return created.len() == 2 && own_counter.is_some();
+        }),
+        "the second Client's CounterActor never counted up"
+    );
+    let own_counter = own_counter.unwrap();
+    let other_counter = *created.iter().find(|key| **key != own_counter).unwrap();
+    assert!(!updated.contains(&other_counter));
+    assert_eq!(get_counter_value(&mut second, &other_counter), Some(0));
+
+    // the same goes for the second Client's CounterActor on the first Client
+    let mut created = None;
+    let mut updated = Vec::new();
+    let mut echoed = false;
+    first.client().send_event(&TextEvent::new("hello")).unwrap();
+    assert!(
+        first.wait_for(TIMEOUT, |_, event| {
+            match event {
+                ClientEvent::CreateActor(key) => created = Some(key),
+                ClientEvent::UpdateActor(key, _) => updated.push(key),
+                ClientEvent::Event(TestEvent::TextEvent(_)) => echoed = true,
+                _ => {}
+            }
+            return created.is_some() && echoed;
+        }),
+        "the second Client's CounterActor was never created on the first"
+    );
+    let hidden_counter = created.unwrap();
+    assert_ne!(hidden_counter, first_counter);
+    assert!(!updated.contains(&hidden_counter));
+    assert_eq!(get_counter_value(&mut first, &hidden_counter), Some(0));
+
+    // once revealed, the value is sent
+    first
+        .client()
+        .send_event(&TextEvent::new(REVEAL_TEXT))
+        .unwrap();
+    assert!(
+        first.wait_for(TIMEOUT, |client, event| {
+            if let ClientEvent::UpdateActor(key, _) = event {
+                if key == hidden_counter {
+                    if let Some(TestActor::CounterActor(counter)) = client.get_actor(&key) {
+                        return *counter.borrow().value.get() == COUNTER_LIMIT;
+                    }
+                }
+            }
+            return false;
+        }),
+        "the revealed value never arrived"
+    );
+
+    // while it stays hidden from the Client which didn't ask
+    send_and_wait_for_echoes(&mut second, &["hello".to_string()], false);
+    assert_eq!(get_counter_value(&mut second, &other_counter), Some(0));
+
+    let log = server.stop();
+    assert_eq!(log.connections, 2);
+}
+
+// Cuts the link between the Client & the Server both ways while the returned
+// flag is false
+fn cut_link_while_down(client: &mut TestClient) -> Rc<Cell<bool>> {
+    let link_up = Rc::new(Cell::new(true));
+    let outgoing_link_up = link_up.clone();
+    client.client().set_outgoing_hook(move |_, _| {
+        if outgoing_link_up.get() {
+            return HookAction::Pass;
+        }
+        return HookAction::Drop;
+    });
+    let incoming_link_up = link_up.clone();
+    client.client().set_incoming_hook(move |_, _| {
+        if incoming_link_up.get() {
+            return HookAction::Pass;
+        }
+        return HookAction::Drop;
+    });
+    return link_up;
+}
+
+// Gets a ClientConfig which notices a dead link & gives up on each handshake
+// quickly, reconnecting as set by the given ReconnectPolicy
+fn get_reconnecting_client_config(policy: ReconnectPolicy) -> naia_client::ClientConfig {
+    let mut client_config = get_client_config();
+    client_config.disconnection_timeout_duration = Duration::from_secs(1);
+    client_config.max_handshake_attempts = 2;
+    client_config.reconnect = Some(policy);
+    return client_config;
+}
+
+#[test]
+fn reconnect_resumes_the_kept_session() {
+    let mut server_config = ServerConfig::default();
+    server_config.session_resumption_grace = Some(Duration::from_secs(30));
+    let server = TestServer::start_with_config(
+        server_address(14324),
+        get_shared_config(None),
+        server_config,
+    );
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        get_reconnecting_client_config(ReconnectPolicy {
+            max_attempts: 5,
+            base_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(1),
+            jitter: 0.0,
+        }),
+        "charlie",
+        "12345",
+    );
+    let counter_key = wait_for_counter(&mut client);
+    let link_up = cut_link_while_down(&mut client);
+
+    link_up.set(false);
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ConnectionSuspended => true,
+            _ => false,
+        }),
+        "the dead link was never noticed"
+    );
+    let dropped_at = Instant::now();
+
+    // each attempt waits out its backoff, after the drop for the first, &
+    // after the attempt before fails for the others
+    let mut attempts = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Reconnecting(attempt) = event {
+                attempts.push((attempt, dropped_at.elapsed()));
+            }
+            return attempts.len() == 2;
+        }),
+        "only {} reconnect attempts were made",
+        attempts.len()
+    );
+    assert_eq!(attempts[0].0, 1);
+    assert!(attempts[0].1 >= Duration::from_millis(200));
+    assert_eq!(attempts[1].0, 2);
+    assert!(attempts[1].1 - attempts[0].1 >= Duration::from_millis(400));
+
+    // once the link is back, the Server resumes the session it kept
+    link_up.set(true);
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ConnectionResumed => true,
+            ClientEvent::Connection(_) => panic!("the session wasn't resumed"),
+            _ => false,
+        }),
+        "the Client never reconnected"
+    );
+    assert_eq!(
+        get_counter_value(&mut client, &counter_key),
+        Some(COUNTER_LIMIT)
+    );
+    send_and_wait_for_echoes(&mut client, &["hello".to_string()], false);
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+}
+
+#[test]
+fn reconnect_skips_kicks_and_can_be_cancelled() {
+    let server = TestServer::start(server_address(14325), get_shared_config(None));
+    let policy = ReconnectPolicy {
+        max_attempts: 5,
+        base_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+        jitter: 0.5,
+    };
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        get_reconnecting_client_config(policy.clone()),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut client);
+    let link_up = cut_link_while_down(&mut client);
+
+    // a dropped connection the Server can't resume is reconnected afresh,
+    // until cancelled
+    link_up.set(false);
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| match event {
+            ClientEvent::Reconnecting(1) => {
+                assert!(client.cancel_reconnect());
+                return true;
+            }
+            _ => false,
+        }),
+        "the Client never started reconnecting"
+    );
+    assert!(!client.client().cancel_reconnect());
+    assert_eq!(
+        client.client().connection_state(),
+        ConnectionState::ConnectFailed(ConnectFailReason::Cancelled)
+    );
+    let start = Instant::now();
+    client.wait_for(Duration::from_millis(500), |_, event| match event {
+        ClientEvent::Reconnecting(_) => panic!("reconnecting carried on after being cancelled"),
+        _ => false,
+    });
+    assert!(start.elapsed() >= Duration::from_millis(500));
+
+    // a Client kicked by the Server isn't reconnected
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        get_reconnecting_client_config(policy),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut client);
+    client
+        .client()
+        .send_event(&TextEvent::new(KICK_TEXT))
+        .unwrap();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::DisconnectedByServer(_) => true,
+            _ => false,
+        }),
+        "the Client was never kicked"
+    );
+    client.wait_for(Duration::from_millis(500), |_, event| match event {
+        ClientEvent::Reconnecting(_) | ClientEvent::ReconnectFailed => {
+            panic!("a kicked Client was reconnected")
+        }
+        _ => false,
+    });
+    assert!(!client.client().cancel_reconnect());
+
+    server.stop();
+}
+
+#[test]
+fn client_timed_out_by_the_server_reconnects_once_told_it_is_not_connected() {
+    let server_timeout = Duration::from_secs(1);
+    let mut server_config = ServerConfig::default();
+    server_config.disconnection_timeout_duration = server_timeout;
+    // connections are checked for timing out as heartbeats are sent
+    server_config.heartbeat_interval = Duration::from_millis(100);
+    let server = TestServer::start_with_config(
+        server_address(14401),
+        get_shared_config(None),
+        server_config,
+    );
+    let mut client_config = get_client_config();
+    client_config.reconnect = Some(ReconnectPolicy {
+        max_attempts: 5,
+        base_backoff: Duration::from_millis(50),
+        max_backoff: Duration::from_secs(1),
+        jitter: 0.0,
+    });
+    let client_timeout = client_config.disconnection_timeout_duration;
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the Client stalls for long enough to be timed out by the Server, but
+    // not to time the Server out itself
+    std::thread::sleep(server_timeout * 2);
+    let resumed_at = Instant::now();
+    client
+        .client()
+        .send_event(&TextEvent::new("still here?"))
+        .unwrap();
+    let mut hinted_after = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Disconnection => {
+                hinted_after = Some(resumed_at.elapsed());
+                return false;
+            }
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never reconnected"
+    );
+    let reconnected_after = resumed_at.elapsed();
+    assert!(hinted_after.unwrap() < Duration::from_millis(500));
+    assert!(reconnected_after < client_timeout / 4);
+    send_and_wait_for_echoes(&mut client, &["back again".to_string()], false);
+
+    let log = server.stop();
+    assert_eq!(
+        log.event_order,
+        [
+            LoggedEvent::Connection,
+            LoggedEvent::Disconnection,
+            LoggedEvent::Connection,
+            LoggedEvent::Text,
+        ]
+    );
+    assert!(log.unknown_address_packet_count > 0);
+}
+
+#[test]
+fn dscp_marked_packets_flow_both_ways() {
+    // expedited forwarding
+    let dscp = 46;
+    let mut server_config = ServerConfig::default();
+    server_config.dscp = Some(dscp);
+    let server = TestServer::start_with_config(
+        server_address(14402),
+        get_shared_config(None),
+        server_config,
+    );
+    let mut client_config = get_client_config();
+    client_config.dscp = Some(dscp);
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    send_and_wait_for_echoes(&mut client, &["marked".to_string()], false);
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, ["marked"]);
+}
+
+// Asks for a GoalEvent & waits for it, returning the key the Actor it
+// references resolved to when it was received, if any, along with whether
+// that Actor had been created by then
+fn wait_for_goal(client: &mut TestClient) -> (Option<LocalActorKey>, bool) {
+    client
+        .client()
+        .send_event(&TextEvent::new(GOAL_TEXT))
+        .unwrap();
+    let mut created = Vec::new();
+    let mut goal = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| match event {
+            ClientEvent::CreateActor(key) => {
+                created.push(key);
+                return false;
+            }
+            ClientEvent::Event(TestEvent::GoalEvent(goal_event)) => {
+                let resolved = client.resolve_actor_ref(goal_event.celebration.get());
+                goal = Some((
+                    resolved,
+                    resolved.map_or(false, |key| created.contains(&key)),
+                ));
+                return true;
+            }
+            _ => false,
+        }),
+        "the GoalEvent never arrived"
+    );
+    return goal.unwrap();
+}
+
+#[test]
+fn actor_ordered_event_waits_for_the_actor_it_references() {
+    let server = TestServer::start(server_address(14327), get_shared_config(None));
+
+    // a Client which hasn't set the GoalEvent to be actor-ordered receives it
+    // before the Actor it references is created
+    let mut unordered_manifest = Manifest::<TestEvent, TestActor>::new();
+    unordered_manifest.register_event(AuthEvent::get_builder());
+    unordered_manifest.register_event_on_channel(TextEvent::get_builder(), 0);
+    unordered_manifest
+        .set_event_timestamped::<TextEvent>()
+        .unwrap();
+    unordered_manifest.register_actor(CounterActor::get_builder());
+    unordered_manifest.register_event(GoalEvent::get_builder());
+    let mut unordered_client = TestClient::connect_with(
+        server.address(),
+        get_shared_config(None),
+        unordered_manifest,
+        TestEvent::AuthEvent(AuthEvent::new("charlie", "12345")),
+    );
+    wait_for_counter(&mut unordered_client);
+    assert_eq!(wait_for_goal(&mut unordered_client), (None, false));
+
+    // one which has holds it until then
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut client);
+    let (resolved, created) = wait_for_goal(&mut client);
+    assert!(resolved.is_some());
+    assert!(created);
+
+    server.stop();
+}
+
+// The values of every Actor in the Client's mirror of the world
+fn get_mirrored_counts(client: &NaiaClient<TestEvent, TestActor>) -> Vec<u16> {
+    return client
+        .actors_iter()
+        .map(|(_, actor)| {
+            let TestActor::CounterActor(counter) = actor;
+            return *counter.borrow().value.get();
+        })
+        .collect();
+}
+
+#[test]
+fn actor_mirror_matches_the_server_world_after_chaos() {
+    let server = TestServer::start(server_address(14328), get_shared_config(None));
+    let proxy = ChaosProxy::start(
+        server_address(14329),
+        server.address(),
+        ChaosConfig {
+            loss: 0.35,
+            duplication: 0.2,
+            max_delay: Duration::from_millis(60),
+        },
+    );
+    let mut client =
+        TestClient::connect(proxy.address(), get_shared_config(None), "charlie", "12345");
+    wait_for_counter(&mut client);
+
+    // another User comes & goes, so that its CounterActor is created & then
+    // deleted on the Client
+    let mut other_client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut other_client);
+    assert!(
+        client.wait_for(TIMEOUT, |client, _| {
+            return client.actors_iter().count() == 2;
+        }),
+        "the other User's CounterActor was never created"
+    );
+    other_client
+        .client()
+        .send_event(&TextEvent::new(KICK_TEXT))
+        .unwrap();
+    assert!(
+        other_client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::DisconnectedByServer(_) => true,
+            _ => false,
+        }),
+        "the other Client was never kicked"
+    );
+    // & Actors are spawned for the Client
+    for _ in 0..3 {
+        client
+            .client()
+            .send_event(&TextEvent::new(GOAL_TEXT))
+            .unwrap();
+    }
+
+    // the other User's CounterActor has its value hidden, so the mirror only
+    // has every value counted up once it has been deleted
+    assert!(
+        client.wait_for(TIMEOUT, |client, _| {
+            let counts = get_mirrored_counts(client);
+            return counts.len() == 4 && counts.iter().all(|count| *count == COUNTER_LIMIT);
+        }),
+        "the mirror never settled, at {:?}",
+        get_mirrored_counts(client.client())
+    );
+
+    let log = server.stop();
+    assert_eq!(log.disconnections, 1);
+    assert_eq!(log.final_counts.len(), 4);
+    for (actor_ref, value) in log.final_counts.iter() {
+        let key = client
+            .client()
+            .resolve_actor_ref(actor_ref)
+            .expect("every Actor in the room is in the mirror");
+        match client.client().get_received_actor(&key) {
+            Some(TestActor::CounterActor(counter)) => {
+                assert_eq!(counter.borrow().value.get(), value);
+            }
+            None => panic!("the resolved Actor isn't in the mirror"),
+        }
+    }
+    assert_eq!(
+        client.client().actors_iter().count(),
+        log.final_counts.len()
+    );
+}
+
+// Sends the Server the first bytes of a TextEvent's payload, as RawEvents of
+// its type, at every length short of the whole payload, followed by the whole
+// payload. Returns the NaiaId of the type & how many were cut short
+fn send_truncated_texts(client: &mut TestClient, text: &str) -> (u16, usize) {
+    let naia_id = manifest_load()
+        .get_event_naia_id(&TypeId::of::<TextEvent>())
+        .unwrap();
+    let mut payload = Vec::new();
+    Event::write(&TextEvent::new(text), &mut payload);
+    for length in 0..=payload.len() {
+        let raw_event = RawEvent {
+            naia_id,
+            bytes: payload[..length].into(),
+        };
+        client.client().send_raw_event(&raw_event, true).unwrap();
+    }
+    return (naia_id, payload.len());
+}
+
+#[test]
+fn truncated_events_are_skipped_by_the_server() {
+    let server = TestServer::start(server_address(14330), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    let (naia_id, truncated_count) = send_truncated_texts(&mut client, "whole");
+    // only the whole one is received, & the Server carries on
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                *text_event.text.get() == echo_of("whole")
+            }
+            _ => false,
+        }),
+        "the echo never arrived"
+    );
+
+    let log = server.stop();
+    assert_eq!(log.disconnections, 0);
+    assert_eq!(log.received_texts, ["whole"]);
+    assert_eq!(
+        log.skipped_frames,
+        vec![SkippedFrame::DecodeFailed(naia_id); truncated_count]
+    );
+}
+
+#[test]
+fn clients_are_kicked_past_the_malformed_frame_limit() {
+    let server = TestServer::start_with_config(
+        server_address(14331),
+        get_shared_config(None),
+        ServerConfig {
+            malformed_frame_limit: Some(3),
+            ..Default::default()
+        },
+    );
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    let (naia_id, truncated_count) = send_truncated_texts(&mut client, "whole");
+    assert!(truncated_count > 4);
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::DisconnectedByServer(_) => true,
+            _ => false,
+        }),
+        "the Client was never kicked"
+    );
+
+    // kicked on the malformed Event past the limit, before any more are taken
+    let log = server.stop();
+    assert_eq!(log.disconnections, 1);
+    assert_eq!(
+        log.skipped_frames,
+        vec![SkippedFrame::DecodeFailed(naia_id); 4]
+    );
+}
+
+#[test]
+fn split_halves_reply_to_events_as_they_are_received() {
+    let server = TestServer::start_split(server_address(14332), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // each echo is replied to with the next count, while still receiving
+    let (mut receiver, sender) = client.client().split();
+    sender.send_event(&TextEvent::new("0"));
+    let mut count = 0;
+    let deadline = Instant::now() + TIMEOUT;
+    while count < 10 && Instant::now() < deadline {
+        match receiver.next() {
+            Some(Ok(ClientEvent::Event(TestEvent::TextEvent(text_event)))) => {
+                assert_eq!(*text_event.text.get(), echo_of(&count.to_string()));
+                count += 1;
+                sender.send_event(&TextEvent::new(&count.to_string()));
+            }
+            Some(Ok(_)) => {}
+            Some(Err(error)) => panic!("the reply couldn't be sent: {}", error),
+            None => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+    assert_eq!(count, 10, "the echoes stopped coming");
+    drop(receiver);
+
+    // the last reply is sent once the Client is polled again
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                *text_event.text.get() == echo_of("10")
+            }
+            _ => false,
+        }),
+        "the last reply was never echoed"
+    );
+    let log = server.stop();
+    let expected: Vec<String> = (0..=10).map(|count| count.to_string()).collect();
+    assert_eq!(log.received_texts, expected);
+}
+
+#[test]
+fn actors_resync_after_a_long_receive_gap() {
+    let server = TestServer::start(server_address(14333), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    let mut counter_key = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            if let ClientEvent::CreateActor(key) = event {
+                if let Some(TestActor::CounterActor(_)) = client.get_actor(&key) {
+                    counter_key = Some(key);
+                }
+            }
+            return counter_key.is_some();
+        }),
+        "the CounterActor was never created"
+    );
+    let counter_key = counter_key.unwrap();
+
+    // every packet from the Server is lost while the CounterActor counts up,
+    // far more than the Server can learn the fate of from acknowledgements,
+    // so the changes they held are never resent
+    let link_up = Rc::new(Cell::new(false));
+    let incoming_link_up = link_up.clone();
+    client.client().set_incoming_hook(move |_, _| {
+        if incoming_link_up.get() {
+            return HookAction::Pass;
+        }
+        return HookAction::Drop;
+    });
+    let blackhole_until = Instant::now() + Duration::from_millis(1500);
+    client.wait_for(TIMEOUT, |client, event| {
+        if let ClientEvent::Tick = event {
+            client.send_event(&TextEvent::new("ping")).unwrap();
+        }
+        return Instant::now() >= blackhole_until;
+    });
+    link_up.set(true);
+
+    // the Client notices the gap, & asks for the whole state instead
+    let mut value = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, _| {
+            if let Some(TestActor::CounterActor(counter)) = client.get_actor(&counter_key) {
+                value = Some(*counter.borrow().value.get());
+            }
+            return value == Some(COUNTER_LIMIT);
+        }),
+        "the CounterActor stopped at {:?}",
+        value
+    );
+    assert!(client.client().get_resync_count().unwrap() >= 1);
+    server.stop();
+}
+
+// Waits for the echoes of the given texts, checking that they arrive in order
+fn wait_for_echoes(client: &mut TestClient, texts: &[&str]) {
+    let mut received = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::Event(TestEvent::TextEvent(text_event)) = event {
+                received.push(text_event.text.get().clone());
+            }
+            return received.len() == texts.len();
+        }),
+        "only {} of {} echoes arrived",
+        received.len(),
+        texts.len()
+    );
+    let expected: Vec<String> = texts.iter().map(|text| echo_of(text)).collect();
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn events_sent_before_connecting_are_held_until_connected() {
+    let server = TestServer::start(server_address(14334), get_shared_config(None));
+    let mut client_config = get_client_config();
+    client_config.pre_connection_queue_max = 4;
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+
+    // the Server's response to each step of the handshake is held back until
+    // the Client has sent in the state before it
+    let held_back = Rc::new(Cell::new(Some(PacketType::ServerChallengeResponse)));
+    let incoming_held_back = held_back.clone();
+    client.client().set_incoming_hook(move |_, payload| {
+        if Some(StandardHeader::read_packet_type(payload)) == incoming_held_back.get() {
+            return HookAction::Drop;
+        }
+        return HookAction::Pass;
+    });
+    let wait_for_state = |client: &mut TestClient, state: ConnectionState| {
+        assert!(
+            client.wait_for(TIMEOUT, |client, _| client.connection_state() == state),
+            "the Client never reached {:?}",
+            state
+        );
+    };
+
+    // guaranteed Events are held in every state before the connection is
+    // established, up to the cap, while unguaranteed ones are refused
+    let unguaranteed = AuthEvent::new("charlie", "12345");
+    assert_eq!(
+        client.client().connection_state(),
+        ConnectionState::Disconnected
+    );
+    client
+        .client()
+        .send_event(&TextEvent::new("disconnected"))
+        .unwrap();
+    match client.client().send_event(&unguaranteed) {
+        Err(NaiaError::NotConnected) => {}
+        result => panic!("an unguaranteed Event was held: {:?}", result),
+    }
+
+    wait_for_state(&mut client, ConnectionState::Connecting { attempt: 1 });
+    client
+        .client()
+        .send_event(&TextEvent::new("connecting"))
+        .unwrap();
+    held_back.set(Some(PacketType::ServerConnectResponse));
+
+    wait_for_state(&mut client, ConnectionState::Handshaking { attempt: 1 });
+    client
+        .client()
+        .send_event(&TextEvent::new("handshaking"))
+        .unwrap();
+    client.client().send_event(&TextEvent::new("last")).unwrap();
+    match client.client().send_event(&TextEvent::new("overflow")) {
+        Err(NaiaError::PreConnectionQueueFull { max_messages: 4 }) => {}
+        result => panic!("the held Events overflowed the cap: {:?}", result),
+    }
+    held_back.set(None);
+
+    // held Events are sent first, ahead of any sent once connected
+    wait_for_state(&mut client, ConnectionState::Connected);
+    client
+        .client()
+        .send_event(&TextEvent::new("connected"))
+        .unwrap();
+    wait_for_echoes(
+        &mut client,
+        &[
+            "disconnected",
+            "connecting",
+            "handshaking",
+            "last",
+            "connected",
+        ],
+    );
+    server.stop();
+
+    // nothing is held once the Client has given up on connecting
+    let mut client_config = get_client_config();
+    client_config.max_handshake_attempts = 2;
+    let mut client = TestClient::connect_with_config(
+        server_address(14334),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ConnectionStateChanged(ConnectionState::ConnectFailed(_)) => true,
+            _ => false,
+        }),
+        "the Client never gave up"
+    );
+    match client.client().send_event(&TextEvent::new("failed")) {
+        Err(NaiaError::NotConnected) => {}
+        result => panic!("an Event was held after giving up: {:?}", result),
+    }
+}
+
+#[test]
+fn events_sent_while_reconnecting_are_held_until_resumed() {
+    let mut server_config = ServerConfig::default();
+    server_config.session_resumption_grace = Some(Duration::from_secs(30));
+    let server = TestServer::start_with_config(
+        server_address(14335),
+        get_shared_config(None),
+        server_config,
+    );
+    let mut client = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        get_reconnecting_client_config(ReconnectPolicy {
+            max_attempts: 5,
+            base_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(1),
+            jitter: 0.0,
+        }),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut client);
+    let link_up = cut_link_while_down(&mut client);
+
+    link_up.set(false);
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ConnectionSuspended => true,
+            _ => false,
+        }),
+        "the dead link was never noticed"
+    );
+    client
+        .client()
+        .send_event(&TextEvent::new("suspended"))
+        .unwrap();
+    let unguaranteed = AuthEvent::new("charlie", "12345");
+    match client.client().send_event(&unguaranteed) {
+        Err(NaiaError::NotConnected) => {}
+        result => panic!("an unguaranteed Event was held: {:?}", result),
+    }
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| match event {
+            ClientEvent::Reconnecting(_) => {
+                client.send_event(&TextEvent::new("reconnecting")).unwrap();
+                true
+            }
+            _ => false,
+        }),
+        "the Client never tried to reconnect"
+    );
+
+    link_up.set(true);
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ConnectionResumed => true,
+            _ => false,
+        }),
+        "the Client never reconnected"
+    );
+    wait_for_echoes(&mut client, &["suspended", "reconnecting"]);
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.received_texts, ["suspended", "reconnecting"]);
+}
+
+// Sends the given text & waits for the CheerEvent it's answered with,
+// returning the key of the mascot it's about, along with whether the mascot
+// had been created by then
+fn wait_for_cheer(client: &mut TestClient, text: &str) -> (LocalActorKey, bool) {
+    client.client().send_event(&TextEvent::new(text)).unwrap();
+    let mut created = Vec::new();
+    let mut cheer = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| match event {
+            ClientEvent::CreateActor(key) => {
+                created.push(key);
+                return false;
+            }
+            ClientEvent::ActorEvent(key, TestEvent::CheerEvent(cheer_event)) => {
+                assert_eq!(cheer_event.chant.get(), text);
+                assert!(client.get_actor(&key).is_some());
+                cheer = Some((key, created.contains(&key)));
+                return true;
+            }
+            _ => false,
+        }),
+        "the CheerEvent never arrived"
+    );
+    return cheer.unwrap();
+}
+
+// Receives everything the Client is sent for a while, checking that none of it
+// is about a mascot, which is never in scope for it, & that it ends up with
+// just the two Users' CounterActors
+fn assert_no_cheers(client: &mut TestClient) {
+    client.wait_for(Duration::from_secs(1), |_, event| match event {
+        ClientEvent::ActorEvent(_, _) => panic!("an Event about another User's mascot arrived"),
+        _ => false,
+    });
+    assert_eq!(client.client().actors_iter().count(), 2);
+}
+
+#[test]
+fn actor_events_are_only_sent_to_clients_with_the_actor_in_scope() {
+    let server = TestServer::start(server_address(14341), get_shared_config(None));
+    let mut fan = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut fan);
+    let mut bystander = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut bystander);
+
+    // the first CheerEvent is held until the mascot is created, & the second
+    // is sent straight away, as the mascot has been created by then
+    let (mascot_key, created) = wait_for_cheer(&mut fan, CHEER_TEXT);
+    assert!(created);
+    assert_eq!(wait_for_cheer(&mut fan, ENCORE_TEXT), (mascot_key, false));
+    assert_no_cheers(&mut bystander);
+
+    let log = server.stop();
+    assert_eq!(log.cheer_recipients, [1, 1]);
+}
+
+#[test]
+fn held_actor_events_arrive_after_the_actor_under_loss() {
+    let link_condition = LinkConditionerConfig::new(20, 15, 0.2, 0.0);
+    let server = TestServer::start(
+        server_address(14342),
+        get_shared_config(Some(link_condition.clone())),
+    );
+    let mut fan = TestClient::connect(
+        server.address(),
+        get_shared_config(Some(link_condition.clone())),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut fan);
+    let mut bystander = TestClient::connect(
+        server.address(),
+        get_shared_config(Some(link_condition)),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut bystander);
+
+    // however many times the mascot's creation is resent, the CheerEvent about
+    // it is only sent once it has been acknowledged
+    let (_, created) = wait_for_cheer(&mut fan, CHEER_TEXT);
+    assert!(created);
+    assert_no_cheers(&mut bystander);
+
+    let log = server.stop();
+    assert_eq!(log.cheer_recipients, [1]);
+}
+
+#[test]
+fn tick_payloads_arrive_once_in_tick_order_under_chaos() {
+    let server = TestServer::start(server_address(14355), get_shared_config(None));
+    let proxy = ChaosProxy::start(
+        server_address(14354),
+        server.address(),
+        ChaosConfig {
+            loss: 0.35,
+            duplication: 0.2,
+            max_delay: Duration::from_millis(60),
+        },
+    );
+    let mut client =
+        TestClient::connect(proxy.address(), get_shared_config(None), "charlie", "12345");
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    client
+        .client()
+        .send_event(&TextEvent::new(TICK_PAYLOAD_TEXT))
+        .unwrap();
+
+    // each tick is either received once or reported missing, as the first or
+    // last of a gap, in tick order
+    let mut payload_count = 0;
+    let mut gap_count = 0;
+    let mut next_tick: Option<u16> = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| {
+            match event {
+                ClientEvent::TickPayload(tick, TestEvent::TextEvent(text_event)) => {
+                    if let Some(next_tick) = next_tick {
+                        assert_eq!(tick, next_tick);
+                    }
+                    assert_eq!(*text_event.text.get(), tick_payload_of(tick));
+                    payload_count += 1;
+                    next_tick = Some(tick.wrapping_add(1));
+                }
+                ClientEvent::TickPayloadGap(first_tick, last_tick) => {
+                    if let Some(next_tick) = next_tick {
+                        assert_eq!(first_tick, next_tick);
+                    }
+                    assert!(!sequence_less_than(last_tick, first_tick));
+                    gap_count += 1;
+                    next_tick = Some(last_tick.wrapping_add(1));
+                }
+                _ => {}
+            }
+            return payload_count >= 100;
+        }),
+        "the tick payloads stopped arriving"
+    );
+    assert!(gap_count > 0, "no tick payload was ever missed");
+
+    server.stop();
+}
+
+#[test]
+fn paused_replication_catches_up_on_resume() {
+    let server = TestServer::start(server_address(14391), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    wait_for_counter(&mut client);
+
+    // Events still flow while paused, though the mascot counting all the way
+    // up in the meantime is never sent
+    send_and_wait_for_echoes(&mut client, &[PAUSE_TEXT.to_string()], false);
+    client
+        .client()
+        .send_event(&TextEvent::new(CHEER_TEXT))
+        .unwrap();
+    client.wait_for(
+        get_shared_config(None).tick_interval * u32::from(COUNTER_LIMIT) * 2,
+        |_, event| match event {
+            ClientEvent::CreateActor(_)
+            | ClientEvent::UpdateActor(_, _)
+            | ClientEvent::DeleteActor(_, _)
+            | ClientEvent::ActorEvent(_, _) => panic!("an Actor was replicated while paused"),
+            _ => false,
+        },
+    );
+
+    // on resume, the mascot is created as it is now, rather than being
+    // replayed through every value it counted up through. Its CheerEvent was
+    // sent while it was out of scope, so is gone
+    send_and_wait_for_echoes(&mut client, &[RESUME_TEXT.to_string()], false);
+    let mut mascot = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| match event {
+            ClientEvent::CreateActor(key) => {
+                if let Some(TestActor::CounterActor(counter)) = client.get_actor(&key) {
+                    mascot = Some((key, *counter.borrow().value.get()));
+                }
+                return true;
+            }
+            ClientEvent::UpdateActor(_, _) => panic!("an update arrived before the mascot"),
+            _ => false,
+        }),
+        "the mascot was never created"
+    );
+    let (mascot_key, created_value) = mascot.unwrap();
+    assert_eq!(created_value, COUNTER_LIMIT);
+    client.wait_for(Duration::from_millis(500), |_, event| match event {
+        ClientEvent::UpdateActor(_, _) | ClientEvent::ActorEvent(_, _) => {
+            panic!("the mascot was replicated again")
+        }
+        _ => false,
+    });
+    assert_eq!(
+        get_counter_value(&mut client, &mascot_key),
+        Some(COUNTER_LIMIT)
+    );
+
+    server.stop();
+}
+
+#[test]
+fn kicked_client_whose_disconnect_is_lost_is_still_disconnected() {
+    let server = TestServer::start(server_address(14396), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the Client never hears why it was kicked, but the Server answers
+    // whatever it sends next by telling it it isn't connected
+    client.client().set_incoming_hook(|_, payload| {
+        if StandardHeader::read_packet_type(payload) == PacketType::ServerDisconnect {
+            return HookAction::Drop;
+        }
+        return HookAction::Pass;
+    });
+    client
+        .client()
+        .send_event(&TextEvent::new(KICK_TEXT))
+        .unwrap();
+    let kicked_at = Instant::now();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::DisconnectedByServer(_) => panic!("the ServerDisconnect got through"),
+            ClientEvent::Disconnection => true,
+            _ => false,
+        }),
+        "the Client never disconnected"
+    );
+    assert!(
+        kicked_at.elapsed() < get_client_config().disconnection_timeout_duration / 2,
+        "the Client waited to time out"
+    );
+    assert!(!client.client().has_connection());
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.disconnections, 1);
+}
+
+#[test]
+fn connection_metrics_are_unavailable_while_not_connected() {
+    let server = TestServer::start(server_address(14403), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(matches!(
+        client.client().get_upload_limit(),
+        Err(NaiaError::NotConnected)
+    ));
+    assert!(matches!(
+        client.client().get_upload_utilization(),
+        Err(NaiaError::NotConnected)
+    ));
+    assert!(matches!(
+        client.client().get_command_redundancy(),
+        Err(NaiaError::NotConnected)
+    ));
+    assert!(client.client().get_one_way_delay().is_none());
+    assert!(client.client().get_messages_per_packet().is_none());
+    assert!(client.client().get_expired_event_count().is_none());
+    assert!(client.client().get_deferred_packet_count().is_none());
+    assert!(client.client().get_padding_bytes_sent().is_none());
+    assert!(client.client().get_cover_heartbeat_bytes_sent().is_none());
+    assert!(client.client().get_protocol_memory().is_none());
+    assert!(client.client().get_command_miss_rate().is_none());
+    assert!(client.client().get_clock_gap_count().is_none());
+    assert!(client.client().get_resync_count().is_none());
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    assert!(matches!(client.client().get_upload_limit(), Ok(_)));
+    assert!(matches!(client.client().get_upload_utilization(), Ok(_)));
+    assert!(matches!(client.client().get_command_redundancy(), Ok(_)));
+    assert!(client.client().get_one_way_delay().is_some());
+    assert!(client.client().get_messages_per_packet().is_some());
+    assert!(client.client().get_expired_event_count().is_some());
+    assert!(client.client().get_deferred_packet_count().is_some());
+    assert!(client.client().get_padding_bytes_sent().is_some());
+    assert!(client.client().get_cover_heartbeat_bytes_sent().is_some());
+    assert!(client.client().get_protocol_memory().is_some());
+    assert!(client.client().get_clock_gap_count().is_some());
+    assert!(client.client().get_resync_count().is_some());
+
+    // the connection is dropped once the Client is kicked
+    client
+        .client()
+        .send_event(&TextEvent::new(KICK_TEXT))
+        .unwrap();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::DisconnectedByServer(_) => true,
+            _ => false,
+        }),
+        "the Client was never kicked"
+    );
+    assert!(matches!(
+        client.client().get_upload_limit(),
+        Err(NaiaError::NotConnected)
+    ));
+    assert!(matches!(
+        client.client().get_upload_utilization(),
+        Err(NaiaError::NotConnected)
+    ));
+    assert!(matches!(
+        client.client().get_command_redundancy(),
+        Err(NaiaError::NotConnected)
+    ));
+    assert!(client.client().get_one_way_delay().is_none());
+    assert!(client.client().get_messages_per_packet().is_none());
+    assert!(client.client().get_expired_event_count().is_none());
+    assert!(client.client().get_deferred_packet_count().is_none());
+    assert!(client.client().get_padding_bytes_sent().is_none());
+    assert!(client.client().get_cover_heartbeat_bytes_sent().is_none());
+    assert!(client.client().get_protocol_memory().is_none());
+    assert!(client.client().get_command_miss_rate().is_none());
+    assert!(client.client().get_clock_gap_count().is_none());
+    assert!(client.client().get_resync_count().is_none());
+
+    server.stop();
+}
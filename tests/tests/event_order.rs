@@ -0,0 +1,135 @@
+use std::{collections::HashSet, time::Duration};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    get_shared_config, server_address, ChaosConfig, ChaosProxy, TestClient, TestEvent, TestServer,
+    TIMEOUT,
+};
+use naia_shared::LocalActorKey;
+
+// The seeds of the runs, each losing & reordering different packets
+const SEEDS: [u32; 6] = [
+    0x2545_f491,
+    0x9e37_79b9,
+    0x85eb_ca6b,
+    0xc2b2_ae35,
+    0x27d4_eb2f,
+    0x1656_67b1,
+];
+
+// The Users already connected when the Client under test connects, whose
+// CounterActors are in its initial world sync along with its own
+const OTHER_USERS: usize = 2;
+
+// How many Ticks are received after WorldSyncComplete before a run ends
+const TICKS_AFTER_SYNC: usize = 30;
+
+// whether an event is one a connection gives, rather than one about the
+// connection itself
+fn is_of_connection(event: &ClientEvent<TestEvent>) -> bool {
+    match event {
+        ClientEvent::Connection(_)
+        | ClientEvent::ConnectionResumed
+        | ClientEvent::ConnectionSuspended
+        | ClientEvent::Disconnection
+        | ClientEvent::DisconnectedByServer(_)
+        | ClientEvent::ReconnectFailed
+        | ClientEvent::Reconnecting(_)
+        | ClientEvent::ConnectionStateChanged(_) => return false,
+        _ => return true,
+    }
+}
+
+#[test]
+fn events_keep_their_order_under_heavy_loss_and_reordering() {
+    for (run, seed) in SEEDS.iter().enumerate() {
+        let port = 14363 + 2 * run as u16;
+        let server = TestServer::start(server_address(port), get_shared_config(None));
+        let mut other_clients = Vec::new();
+        for _ in 0..OTHER_USERS {
+            let mut other_client = TestClient::connect(
+                server.address(),
+                get_shared_config(None),
+                "charlie",
+                "12345",
+            );
+            assert!(
+                other_client.wait_for(TIMEOUT, |_, event| match event {
+                    ClientEvent::WorldSyncComplete => true,
+                    _ => false,
+                }),
+                "another Client never synced"
+            );
+            other_clients.push(other_client);
+        }
+
+        let proxy = ChaosProxy::start_seeded(
+            server_address(port + 1),
+            server.address(),
+            ChaosConfig {
+                loss: 0.4,
+                duplication: 0.2,
+                max_delay: Duration::from_millis(80),
+            },
+            *seed,
+        );
+        let mut client =
+            TestClient::connect(proxy.address(), get_shared_config(None), "charlie", "12345");
+
+        let mut connected = false;
+        let mut created: HashSet<LocalActorKey> = HashSet::new();
+        let mut world_synced = false;
+        let mut ticks_after_sync = 0;
+        assert!(
+            client.wait_for(Duration::from_secs(20), |_, event| {
+                // the other Clients are kept connected, so that their
+                // CounterActors stay in the world
+                for other_client in other_clients.iter_mut() {
+                    while let Some(_) = other_client.client().receive() {}
+                }
+                if is_of_connection(&event) {
+                    assert!(connected, "run {}: an event before Connection", run);
+                }
+                match event {
+                    ClientEvent::Connection(_) => {
+                        assert!(!connected, "run {}: connected twice", run);
+                        connected = true;
+                    }
+                    ClientEvent::CreateActor(key) => {
+                        assert!(created.insert(key), "run {}: created twice", run);
+                    }
+                    ClientEvent::DeleteActor(key, _) => {
+                        assert!(created.remove(&key), "run {}: deleted before created", run);
+                    }
+                    ClientEvent::UpdateActor(key, _) | ClientEvent::ActorEvent(key, _) => {
+                        assert!(
+                            created.contains(&key),
+                            "run {}: updated before created",
+                            run
+                        );
+                    }
+                    ClientEvent::WorldSyncComplete => {
+                        assert!(!world_synced, "run {}: synced twice", run);
+                        assert_eq!(
+                            created.len(),
+                            OTHER_USERS + 1,
+                            "run {}: synced before the initial world arrived",
+                            run
+                        );
+                        world_synced = true;
+                    }
+                    ClientEvent::Tick if world_synced => ticks_after_sync += 1,
+                    _ => {}
+                }
+                return ticks_after_sync == TICKS_AFTER_SYNC;
+            }),
+            "run {}: the Client never synced",
+            run
+        );
+
+        drop(client);
+        drop(other_clients);
+        drop(proxy);
+        server.stop();
+    }
+}
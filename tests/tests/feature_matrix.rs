@@ -0,0 +1,86 @@
+use std::{path::PathBuf, process::Command};
+
+// Each crate, along with combinations of its optional features, starting with
+// all of them off, which must each build on their own, along with the crate's
+// tests. The transport features which need a wasm target to build are left out
+const FEATURE_SETS: &[(&str, &[&str])] = &[
+    (
+        "naia-shared",
+        &[
+            "alloc",
+            "alloc compression",
+            "alloc strict-checks",
+            "alloc wire-little-endian",
+            "std",
+            "std compression",
+            "std diagnostics",
+            "std link-conditioner",
+            "std conformance",
+            "std message-tracing",
+            "std wire-little-endian",
+            "std compression diagnostics link-conditioner strict-checks",
+        ],
+    ),
+    (
+        "naia-client",
+        &[
+            "",
+            "compression",
+            "link-conditioner",
+            "strict-checks",
+            "message-tracing",
+            "wire-little-endian",
+            "compression link-conditioner strict-checks",
+        ],
+    ),
+    (
+        "naia-server",
+        &[
+            "use-udp",
+            "use-udp compression",
+            "use-udp diagnostics",
+            "use-udp link-conditioner",
+            "use-udp strict-checks",
+            "use-udp message-tracing",
+            "use-udp wire-little-endian",
+            "use-udp compression diagnostics link-conditioner strict-checks",
+        ],
+    ),
+];
+
+// runs the given cargo command on the crate with only the given features,
+// returning the compiler output if it fails to build
+fn build(command: &[&str], package: &str, features: &str) -> Result<(), String> {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir.join(".."))
+        .args(command)
+        .args(&["--quiet", "--package", package, "--no-default-features"])
+        .args(&["--features", features])
+        // kept apart from the test build, so neither invalidates the other
+        .arg("--target-dir")
+        .arg(manifest_dir.join("../target/feature-matrix"))
+        .output()
+        .expect("cargo could not be run");
+    if output.status.success() {
+        return Ok(());
+    }
+    return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+}
+
+#[test]
+fn every_feature_set_builds() {
+    let mut failures = Vec::new();
+    for (package, feature_sets) in FEATURE_SETS.iter() {
+        for features in feature_sets.iter() {
+            if let Err(output) = build(&["check"], package, features) {
+                failures.push(format!("{} [{}]:\n{}", package, features, output));
+            }
+            // the tests must build with each set too, not just the library
+            if let Err(output) = build(&["test", "--no-run"], package, features) {
+                failures.push(format!("{} tests [{}]:\n{}", package, features, output));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,72 @@
+use std::net::{SocketAddr, UdpSocket};
+
+use naia_client::find_my_ip_address;
+use naia_integration_tests::{get_shared_config, wait_for_connection, TestClient, TestServer};
+
+fn address(ip: &str, port: u16) -> SocketAddr {
+    return SocketAddr::new(ip.parse().unwrap(), port);
+}
+
+// whether the platform can talk over IPv6 loopback, which these scenarios are
+// skipped without
+fn ipv6_supported() -> bool {
+    return UdpSocket::bind("[::1]:0").is_ok();
+}
+
+#[test]
+fn dual_stack_server_accepts_both_families_and_ipv6_is_preferred() {
+    if !ipv6_supported() {
+        return;
+    }
+    let server = TestServer::start(address("::", 14384), get_shared_config(None));
+
+    // naia-client-socket binds to the host's own IPv4 address, which the
+    // Server's replies to it come from, so IPv4 Clients reach the Server there
+    // rather than at the loopback address
+    let ipv4_address = SocketAddr::new(find_my_ip_address().unwrap(), 14384);
+
+    // the IPv6 address wins, whatever order the addresses are given in
+    let mut client = TestClient::connect_to_any(
+        &[ipv4_address, address("::1", 14384)],
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        wait_for_connection(&mut client),
+        "never connected over IPv6"
+    );
+    assert_eq!(client.client().server_address(), address("::1", 14384));
+
+    // an IPv4 Client is accepted at the same socket
+    let mut client = TestClient::connect(ipv4_address, get_shared_config(None), "charlie", "12345");
+    assert!(
+        wait_for_connection(&mut client),
+        "never connected over IPv4"
+    );
+
+    server.stop();
+}
+
+#[test]
+fn falls_back_to_ipv4_when_ipv6_goes_unanswered() {
+    if !ipv6_supported() {
+        return;
+    }
+    // nothing listens at the IPv6 address
+    let server = TestServer::start(address("127.0.0.1", 14385), get_shared_config(None));
+
+    let mut client = TestClient::connect_to_any(
+        &[address("::1", 14385), address("127.0.0.1", 14385)],
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(wait_for_connection(&mut client), "never fell back to IPv4");
+    assert_eq!(
+        client.client().server_address(),
+        address("127.0.0.1", 14385)
+    );
+
+    server.stop();
+}
@@ -0,0 +1,111 @@
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    echo_of, get_shared_config, manifest_load, server_address, wait_for_connection, AuthEvent,
+    ReloadEvent, TestClient, TestEvent, TestServer, TextEvent, EXTEND_TEXT, LACKS_TYPE_TEXT,
+    RELOAD_TEXT, TIMEOUT,
+};
+
+// How many ticks a Client waits for an answer to `RELOAD_TEXT` before asking
+// again
+const RELOAD_INTERVAL_TICKS: u32 = 5;
+
+fn wait_for_text(client: &mut TestClient, expected: &str) {
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                text_event.text.get() == expected
+            }
+            _ => false,
+        }),
+        "'{}' never arrived",
+        expected
+    );
+}
+
+// Sends `RELOAD_TEXT` every few ticks, while the Server answers that the
+// Client has yet to say whether it has the ReloadEvent, until it answers with
+// anything else, which it returns
+fn reload(client: &mut TestClient) -> TestEvent {
+    let mut ticks = RELOAD_INTERVAL_TICKS;
+    let mut answer = None;
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::Tick => {
+                    ticks += 1;
+                    if ticks >= RELOAD_INTERVAL_TICKS {
+                        ticks = 0;
+                        client.send_event(&TextEvent::new(RELOAD_TEXT)).unwrap();
+                    }
+                }
+                ClientEvent::Event(TestEvent::TextEvent(text_event))
+                    if *text_event.text.get() == echo_of(RELOAD_TEXT) => {}
+                ClientEvent::Event(event) => answer = Some(event),
+                ClientEvent::SkippedFrame(_) => panic!("an Event was skipped over"),
+                _ => {}
+            }
+            return answer.is_some();
+        }),
+        "the Server never answered"
+    );
+    return answer.unwrap();
+}
+
+#[test]
+fn event_types_registered_while_running_reach_only_clients_which_have_them() {
+    let server = TestServer::start(server_address(14362), get_shared_config(None));
+    // one Client is from after the reload, registering the ReloadEvent last,
+    // as the Server will, & the other from before it
+    let mut manifest = manifest_load();
+    manifest.register_event(ReloadEvent::get_builder());
+    let mut capable_client = TestClient::connect_with(
+        server.address(),
+        get_shared_config(None),
+        manifest,
+        TestEvent::AuthEvent(AuthEvent::new("charlie", "12345")),
+    );
+    let mut incapable_client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        wait_for_connection(&mut capable_client),
+        "the Client never connected"
+    );
+    assert!(
+        wait_for_connection(&mut incapable_client),
+        "the Client never connected"
+    );
+
+    // both are connected when the Server registers the ReloadEvent
+    capable_client
+        .client()
+        .send_event(&TextEvent::new(EXTEND_TEXT))
+        .unwrap();
+    wait_for_text(&mut capable_client, &echo_of(EXTEND_TEXT));
+
+    match reload(&mut capable_client) {
+        TestEvent::ReloadEvent(reload_event) => {
+            assert_eq!(reload_event.module.get(), RELOAD_TEXT);
+        }
+        _ => panic!("the Client which has the ReloadEvent wasn't sent one"),
+    }
+    match reload(&mut incapable_client) {
+        TestEvent::TextEvent(text_event) => {
+            assert_eq!(text_event.text.get(), LACKS_TYPE_TEXT);
+        }
+        _ => panic!("the Client which lacks the ReloadEvent wasn't told so"),
+    }
+
+    // the Client which lacks the ReloadEvent carries on as before
+    incapable_client
+        .client()
+        .send_event(&TextEvent::new("still here"))
+        .unwrap();
+    wait_for_text(&mut incapable_client, &echo_of("still here"));
+
+    let log = server.stop();
+    assert!(log.skipped_frames.is_empty());
+}
@@ -0,0 +1,157 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use naia_client::{ClientEvent, HookAction};
+use naia_integration_tests::{
+    echo_of, get_shared_config, server_address, TestClient, TestEvent, TestServer, TextEvent,
+    TIMEOUT,
+};
+use naia_shared::{
+    message_tracing::{TraceLine, TraceStage, TRACE_LOG_TARGET},
+    PacketType, StandardHeader,
+};
+
+// Keeps every trace line logged by the Client & the Server, which run in this
+// same process, as a grep of their logs would find them
+struct TraceLogger {
+    lines: Mutex<Vec<String>>,
+}
+
+impl Log for TraceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        return metadata.target() == TRACE_LOG_TARGET;
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.lines.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: TraceLogger = TraceLogger {
+    lines: Mutex::new(Vec::new()),
+};
+
+// every trace line logged so far for the given trace id
+fn grep_trace_id(trace_id: u32) -> Vec<TraceLine> {
+    let prefix = format!("naia-trace {} ", trace_id);
+    return LOGGER
+        .lines
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.starts_with(&prefix))
+        .map(|line| TraceLine::parse(line).expect("trace lines follow the format"))
+        .collect();
+}
+
+fn stages(lines: &[&TraceLine]) -> Vec<(TraceStage, Option<u16>)> {
+    return lines
+        .iter()
+        .map(|line| (line.stage, line.packet_index))
+        .collect();
+}
+
+#[test]
+fn retransmitted_event_is_traced_across_both_hosts() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Info);
+    assert!(log::log_enabled!(target: TRACE_LOG_TARGET, Level::Info));
+
+    let server = TestServer::start(server_address(14326), get_shared_config(None));
+    let server_address = server.address();
+    let mut client =
+        TestClient::connect(server_address, get_shared_config(None), "charlie", "12345");
+    let mut connection_info = None;
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(info) => {
+                connection_info = Some(info);
+                true
+            }
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    assert!(connection_info.unwrap().message_tracing_enabled);
+
+    // the first Data packet the Event is written into is lost, so it is sent
+    // again in another
+    let dropped = Rc::new(Cell::new(false));
+    let dropped_clone = dropped.clone();
+    client.client().set_outgoing_hook(move |_, payload| {
+        if StandardHeader::read_packet_type(payload) == PacketType::Data && !dropped_clone.get() {
+            dropped_clone.set(true);
+            return HookAction::Drop;
+        }
+        return HookAction::Pass;
+    });
+    let text = "traced".to_string();
+    client.client().send_event(&TextEvent::new(&text)).unwrap();
+
+    let trace_id = LOGGER
+        .lines
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|line| TraceLine::parse(line))
+        .find(|line| line.stage == TraceStage::Queued && line.peer == server_address)
+        .expect("the Event was traced as queued")
+        .trace_id;
+
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                *text_event.text.get() == echo_of(&text)
+            }
+            _ => false,
+        }),
+        "the echo never arrived"
+    );
+    assert!(dropped.get());
+    // the Client may yet be waiting for the ack of the packet it was resent in
+    let deadline = Instant::now() + TIMEOUT;
+    while !grep_trace_id(trace_id)
+        .iter()
+        .any(|line| line.stage == TraceStage::Acked)
+    {
+        assert!(Instant::now() < deadline, "the Event was never acked");
+        client.wait_for(Duration::from_millis(50), |_, _| false);
+    }
+    server.stop();
+
+    // the Client logs its lines with the Server as the peer, & the Server
+    // with the Client
+    let lines = grep_trace_id(trace_id);
+    let (client_lines, server_lines): (Vec<&TraceLine>, Vec<&TraceLine>) =
+        lines.iter().partition(|line| line.peer == server_address);
+
+    let client_stages = stages(&client_lines);
+    assert_eq!(client_stages.len(), 5, "{:?}", client_stages);
+    let lost_packet = client_stages[1].1.unwrap();
+    let resent_packet = client_stages[3].1.unwrap();
+    assert_ne!(lost_packet, resent_packet);
+    assert_eq!(
+        client_stages,
+        vec![
+            (TraceStage::Queued, None),
+            (TraceStage::Packed, Some(lost_packet)),
+            (TraceStage::Retransmitted, Some(lost_packet)),
+            (TraceStage::Packed, Some(resent_packet)),
+            (TraceStage::Acked, Some(resent_packet)),
+        ]
+    );
+    assert_eq!(
+        stages(&server_lines),
+        vec![(TraceStage::Received, None), (TraceStage::Delivered, None)]
+    );
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use metrics_util::{
+    debugging::{DebugValue, DebuggingRecorder, Snapshotter},
+    MetricKind,
+};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    echo_of, get_shared_config, server_address, TestClient, TestEvent, TestServer, TextEvent,
+    TIMEOUT,
+};
+use naia_server::{MetricsConfig, ServerConfig};
+
+// A metric as last exported: its kind & labels, and its value, or every value
+// recorded into it since the last snapshot for a histogram
+struct Exported {
+    kind: MetricKind,
+    labels: Vec<(String, String)>,
+    values: Vec<f64>,
+}
+
+// Gets every metric exported with a name starting with the given prefix, by
+// name. Only one series per name is kept, the last
+fn snapshot(snapshotter: &Snapshotter, prefix: &str) -> HashMap<String, Exported> {
+    let mut metrics = HashMap::new();
+    for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+        let name = key.key().name().to_string();
+        if !name.starts_with(prefix) {
+            continue;
+        }
+        let labels = key
+            .key()
+            .labels()
+            .map(|label| (label.key().to_string(), label.value().to_string()))
+            .collect();
+        let values = match value {
+            DebugValue::Counter(value) => vec![value as f64],
+            DebugValue::Gauge(value) => vec![value.into_inner()],
+            DebugValue::Histogram(values) => {
+                values.iter().map(|value| value.into_inner()).collect()
+            }
+        };
+        metrics.insert(
+            name,
+            Exported {
+                kind: key.kind(),
+                labels,
+                values,
+            },
+        );
+    }
+    return metrics;
+}
+
+// Connects a Client to a Test Server exporting its stats as configured, &
+// exchanges some Events with it, so that there's traffic to export
+fn run_session(port: u16, metrics_config: MetricsConfig) {
+    let mut server_config = ServerConfig::default();
+    server_config.metrics = metrics_config;
+    let server =
+        TestServer::start_with_config(server_address(port), get_shared_config(None), server_config);
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    client
+        .client()
+        .send_event(&TextEvent::new("hello"))
+        .unwrap();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                *text_event.text.get() == echo_of("hello")
+            }
+            _ => false,
+        }),
+        "the echo never arrived"
+    );
+
+    // a few more ticks go by, exporting the stats as of after the exchange
+    let mut ticks = 0;
+    client.wait_for(TIMEOUT, |_, event| {
+        if let ClientEvent::Tick = event {
+            ticks += 1;
+        }
+        return ticks == 10;
+    });
+    server.stop();
+}
+
+#[test]
+fn server_stats_are_exported_through_the_metrics_facade() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder
+        .install()
+        .expect("no other recorder should be installed");
+
+    // aggregated across connections, by default
+    run_session(
+        14336,
+        MetricsConfig {
+            prefix: "game".to_string(),
+            per_connection: false,
+        },
+    );
+    let metrics = snapshot(&snapshotter, "game_");
+    let mut names: Vec<&str> = metrics.keys().map(|name| name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        [
+            "game_bytes_received_total",
+            "game_bytes_sent_total",
+            "game_congested_connections",
+            "game_connections",
+            "game_packet_loss_ratio",
+            "game_rtt_seconds",
+            "game_tick_duration_seconds",
+        ]
+    );
+    for exported in metrics.values() {
+        assert!(exported.labels.is_empty());
+    }
+
+    let value = |name: &str| metrics[name].values[0];
+    assert_eq!(metrics["game_connections"].kind, MetricKind::Gauge);
+    assert_eq!(value("game_connections"), 1.0);
+    assert_eq!(value("game_congested_connections"), 0.0);
+    assert_eq!(metrics["game_bytes_sent_total"].kind, MetricKind::Counter);
+    assert!(value("game_bytes_sent_total") > 0.0);
+    assert!(value("game_bytes_received_total") > 0.0);
+    let packet_loss = value("game_packet_loss_ratio");
+    assert!(packet_loss >= 0.0 && packet_loss <= 1.0);
+    let rtts = &metrics["game_rtt_seconds"];
+    assert_eq!(rtts.kind, MetricKind::Histogram);
+    assert!(!rtts.values.is_empty());
+    assert!(rtts.values.iter().all(|rtt| *rtt >= 0.0 && *rtt < 1.0));
+    let tick_durations = &metrics["game_tick_duration_seconds"];
+    assert!(!tick_durations.values.is_empty());
+    assert!(tick_durations
+        .values
+        .iter()
+        .all(|duration| *duration >= 0.0 && *duration < 1.0));
+
+    // & for each connection, once opted into
+    run_session(
+        14337,
+        MetricsConfig {
+            prefix: "each".to_string(),
+            per_connection: true,
+        },
+    );
+    let metrics = snapshot(&snapshotter, "each_connection_");
+    let mut names: Vec<&str> = metrics.keys().map(|name| name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        [
+            "each_connection_bytes_received_total",
+            "each_connection_bytes_sent_total",
+            "each_connection_packet_loss_ratio",
+            "each_connection_protocol_memory_bytes",
+            "each_connection_rtt_seconds",
+        ]
+    );
+    for exported in metrics.values() {
+        assert_eq!(exported.labels.len(), 1);
+        assert_eq!(exported.labels[0].0, "connection");
+    }
+    assert!(metrics["each_connection_bytes_sent_total"].values[0] > 0.0);
+    assert!(metrics["each_connection_protocol_memory_bytes"].values[0] > 0.0);
+}
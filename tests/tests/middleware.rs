@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    get_shared_config, server_address, TestClient, TestEvent, TestServer, TextEvent, CHEER_TEXT,
+    ENCORE_TEXT, HOLD_TEXT, KNOCK_REPLY, KNOCK_TEXT, TIMEOUT,
+};
+
+// receives for the given time, returning the text of each TextEvent & the
+// chant of each CheerEvent which arrived
+fn receive_texts(client: &mut TestClient, duration: Duration) -> Vec<String> {
+    let mut received = Vec::new();
+    client.wait_for(duration, |_, event| {
+        match event {
+            ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                received.push(text_event.text.get().clone());
+            }
+            ClientEvent::ActorEvent(_, TestEvent::CheerEvent(cheer_event)) => {
+                received.push(cheer_event.chant.get().clone());
+            }
+            _ => {}
+        }
+        return false;
+    });
+    return received;
+}
+
+#[test]
+fn middleware_replies_are_sent_alone_as_they_are_received() {
+    let server = TestServer::start(server_address(14383), get_shared_config(None));
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+
+    // a mascot is spawned for the Client, & cheered once it has been created
+    client
+        .client()
+        .send_event(&TextEvent::new(CHEER_TEXT))
+        .unwrap();
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::ActorEvent(_, TestEvent::CheerEvent(_)) => true,
+            _ => false,
+        }),
+        "the CheerEvent never arrived"
+    );
+
+    // once the Server has stopped sending updates, the next CheerEvent is
+    // queued & held back
+    client
+        .client()
+        .send_event(&TextEvent::new(HOLD_TEXT))
+        .unwrap();
+    receive_texts(&mut client, Duration::from_millis(300));
+    client
+        .client()
+        .send_event(&TextEvent::new(ENCORE_TEXT))
+        .unwrap();
+    assert!(receive_texts(&mut client, Duration::from_millis(300)).is_empty());
+
+    // the reply is sent while the knock is received, without the CheerEvent
+    // queued before it
+    client
+        .client()
+        .send_event(&TextEvent::new(KNOCK_TEXT))
+        .unwrap();
+    assert_eq!(
+        receive_texts(&mut client, Duration::from_millis(500)),
+        [KNOCK_REPLY]
+    );
+
+    // the middleware handled the knock, so it went no further
+    let log = server.stop();
+    assert_eq!(log.received_texts, [CHEER_TEXT, HOLD_TEXT, ENCORE_TEXT]);
+    assert_eq!(log.cheer_recipients, [1, 1]);
+}
@@ -0,0 +1,163 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    echo_of, get_shared_config, server_address, v1, TestActor, TestClient, TestEvent, TestServer,
+    TextEvent, TIMEOUT,
+};
+use naia_server::{NaiaServer, ServerEvent};
+
+// The reply the chat Server sends to each TextEvent
+fn chat_reply_of(text: &str) -> String {
+    return format!("chat: {}", text);
+}
+
+// A Server running the first build of the test protocol on its own thread,
+// which only answers TextEvents, never spawning any Actors
+struct ChatServer {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Vec<String>>,
+}
+
+impl ChatServer {
+    fn start(address: SocketAddr) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            return smol::block_on(async move {
+                let mut server =
+                    NaiaServer::new(address, v1::manifest_load(), None, get_shared_config(None))
+                        .await;
+                ready_sender.send(()).unwrap();
+                let mut received_texts = Vec::new();
+                // the Server ticks, so this is checked regularly
+                while !thread_stop.load(Ordering::SeqCst) {
+                    match server.receive().await {
+                        Ok(ServerEvent::Event(user_key, v1::TestEvent::TextEvent(text_event))) => {
+                            let text = text_event.text.get().clone();
+                            server
+                                .queue_event(&user_key, &v1::TextEvent::new(&chat_reply_of(&text)))
+                                .expect("the reply should be queued");
+                            received_texts.push(text);
+                        }
+                        Ok(ServerEvent::Tick) => {
+                            server.send_all_updates().await;
+                        }
+                        _ => {}
+                    }
+                }
+                return received_texts;
+            });
+        });
+        ready_receiver.recv().unwrap();
+        return ChatServer { stop, thread };
+    }
+
+    // Stops the chat Server, returning every text it received
+    fn stop(self) -> Vec<String> {
+        self.stop.store(true, Ordering::SeqCst);
+        return self.thread.join().unwrap();
+    }
+}
+
+#[test]
+fn one_process_connects_to_two_servers_with_different_manifests() {
+    let game_server = TestServer::start(server_address(14397), get_shared_config(None));
+    let chat_server = ChatServer::start(server_address(14398));
+
+    // each Client has its own socket & Manifest, so they run side by side
+    let mut game_client = TestClient::connect(
+        game_server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    let mut chat_client: TestClient<v1::TestEvent, v1::TestActor> = TestClient::connect_with(
+        server_address(14398),
+        get_shared_config(None),
+        v1::manifest_load(),
+        v1::TestEvent::AuthEvent(v1::AuthEvent::new("charlie", "12345")),
+    );
+    let mut game_connected = false;
+    let mut chat_connected = false;
+    for _ in 0..(TIMEOUT.as_millis() / 10) {
+        game_client.wait_for(Duration::from_millis(5), |_, event| {
+            game_connected |= matches!(event, ClientEvent::Connection(_));
+            return game_connected;
+        });
+        chat_client.wait_for(Duration::from_millis(5), |_, event| {
+            chat_connected |= matches!(event, ClientEvent::Connection(_));
+            return chat_connected;
+        });
+        if game_connected && chat_connected {
+            break;
+        }
+    }
+    assert!(game_connected, "the game Client never connected");
+    assert!(chat_connected, "the chat Client never connected");
+    assert_ne!(
+        game_client.client().server_address(),
+        chat_client.client().server_address()
+    );
+
+    // each hears only from its own Server, in its own protocol
+    game_client
+        .client()
+        .send_event(&TextEvent::new("move"))
+        .unwrap();
+    chat_client
+        .client()
+        .send_event(&v1::TextEvent::new("hello"))
+        .unwrap();
+    let mut game_reply = None;
+    let mut counter_created = false;
+    let mut chat_reply = None;
+    for _ in 0..(TIMEOUT.as_millis() / 10) {
+        game_client.wait_for(Duration::from_millis(5), |client, event| {
+            match event {
+                ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                    game_reply = Some(text_event.text.get().clone());
+                }
+                ClientEvent::CreateActor(key) => {
+                    counter_created |=
+                        matches!(client.get_actor(&key), Some(TestActor::CounterActor(_)));
+                }
+                _ => {}
+            }
+            return false;
+        });
+        chat_client.wait_for(Duration::from_millis(5), |_, event| {
+            match event {
+                ClientEvent::Event(v1::TestEvent::TextEvent(text_event)) => {
+                    chat_reply = Some(text_event.text.get().clone());
+                }
+                ClientEvent::CreateActor(_) => panic!("the chat Server spawned an Actor"),
+                _ => {}
+            }
+            return false;
+        });
+        if game_reply.is_some() && counter_created && chat_reply.is_some() {
+            break;
+        }
+    }
+    assert_eq!(game_reply, Some(echo_of("move")));
+    assert!(
+        counter_created,
+        "the game Server's CounterActor never arrived"
+    );
+    assert_eq!(chat_reply, Some(chat_reply_of("hello")));
+
+    assert_eq!(chat_server.stop(), ["hello"]);
+    let log = game_server.stop();
+    assert_eq!(log.connections, 1);
+    assert_eq!(log.received_texts, ["move"]);
+}
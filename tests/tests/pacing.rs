@@ -0,0 +1,195 @@
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    flood_of, get_shared_config, server_address, TestClient, TestEvent, TestServer, TextEvent,
+    FLOOD_EVENT_COUNT, FLOOD_TEXT, TIMEOUT,
+};
+use naia_server::{PacingConfig, PacingStats, ServerConfig};
+
+// How many times the Client asks for a flood of TextEvents
+const FLOODS: usize = 5;
+
+// How long the bottleneck takes to pass on each packet
+const DRAIN_INTERVAL: Duration = Duration::from_millis(1);
+
+// How many packets the bottleneck holds, past which any more are dropped
+const BOTTLENECK_CAPACITY: f64 = 3.0;
+
+// Relays UDP packets between a single Client & the Server. Packets headed for
+// the Client pass through a bottleneck, like the queue of a router along the
+// way, which drains at a fixed rate & drops every packet arriving while it's
+// full, so that bursts are punished while packets spread out get through
+struct Bottleneck {
+    address: SocketAddr,
+    stop: Arc<AtomicBool>,
+    relayed: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Bottleneck {
+    fn start(port: u16, server_address: SocketAddr) -> Self {
+        let client_socket = UdpSocket::bind(("127.0.0.1", port)).unwrap();
+        let server_socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        client_socket.set_nonblocking(true).unwrap();
+        server_socket.set_nonblocking(true).unwrap();
+        let address = client_socket.local_addr().unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let relayed = Arc::new(AtomicU64::new(0));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let thread = {
+            let stop = stop.clone();
+            let relayed = relayed.clone();
+            let dropped = dropped.clone();
+            thread::spawn(move || {
+                let mut client_address = None;
+                // how many packets the bottleneck holds, as of `drained_at`
+                let mut level: f64 = 0.0;
+                let mut drained_at = Instant::now();
+                let mut buffer = [0; 2048];
+                while !stop.load(Ordering::SeqCst) {
+                    loop {
+                        match client_socket.recv_from(&mut buffer) {
+                            Ok((length, address)) => {
+                                client_address = Some(address);
+                                let _ = server_socket.send_to(&buffer[..length], server_address);
+                            }
+                            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                            Err(error) => panic!("couldn't receive from the Client: {}", error),
+                        }
+                    }
+                    loop {
+                        match server_socket.recv_from(&mut buffer) {
+                            Ok((length, _)) => {
+                                let now = Instant::now();
+                                let drained = now.duration_since(drained_at).as_secs_f64()
+                                    / DRAIN_INTERVAL.as_secs_f64();
+                                level = (level - drained).max(0.0);
+                                drained_at = now;
+                                if level + 1.0 > BOTTLENECK_CAPACITY {
+                                    dropped.fetch_add(1, Ordering::SeqCst);
+                                    continue;
+                                }
+                                level += 1.0;
+                                relayed.fetch_add(1, Ordering::SeqCst);
+                                if let Some(client_address) = client_address {
+                                    let _ =
+                                        client_socket.send_to(&buffer[..length], client_address);
+                                }
+                            }
+                            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                            Err(error) => panic!("couldn't receive from the Server: {}", error),
+                        }
+                    }
+                    thread::sleep(Duration::from_micros(50));
+                }
+            })
+        };
+
+        return Bottleneck {
+            address,
+            stop,
+            relayed,
+            dropped,
+            thread: Some(thread),
+        };
+    }
+
+    // Gets the share of the packets sent to the Client which were dropped
+    fn loss(&self) -> f64 {
+        let dropped = self.dropped.load(Ordering::SeqCst) as f64;
+        let relayed = self.relayed.load(Ordering::SeqCst) as f64;
+        return dropped / (dropped + relayed);
+    }
+}
+
+impl Drop for Bottleneck {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Has a Client behind a Bottleneck ask for a few floods of TextEvents, waiting
+// for each to arrive in full. Returns the share of packets the Bottleneck
+// dropped, along with the Server's pacing stats
+fn flood(port: u16, pacing: Option<PacingConfig>) -> (f64, PacingStats) {
+    let mut server_config = ServerConfig::default();
+    server_config.pacing = pacing;
+    let server =
+        TestServer::start_with_config(server_address(port), get_shared_config(None), server_config);
+    let bottleneck = Bottleneck::start(port + 1, server.address());
+    let mut client = TestClient::connect(
+        bottleneck.address,
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    for _ in 0..FLOODS {
+        client
+            .client()
+            .send_event(&TextEvent::new(FLOOD_TEXT))
+            .unwrap();
+        let mut expected: HashSet<String> = (0..FLOOD_EVENT_COUNT).map(flood_of).collect();
+        assert!(
+            client.wait_for(TIMEOUT, |_, event| {
+                if let ClientEvent::Event(TestEvent::TextEvent(text_event)) = event {
+                    expected.remove(text_event.text.get());
+                }
+                return expected.is_empty();
+            }),
+            "the flood never arrived in full"
+        );
+    }
+
+    let loss = bottleneck.loss();
+    drop(client);
+    let log = server.stop();
+    return (loss, log.pacing_stats);
+}
+
+#[test]
+fn pacing_reduces_loss_through_a_bottleneck() {
+    let (unpaced_loss, unpaced_stats) = flood(14350, None);
+    let (paced_loss, paced_stats) = flood(14352, Some(PacingConfig::default()));
+
+    // without pacing, every packet written at once is sent at once
+    assert_eq!(
+        unpaced_stats.average_burst_before_pacing(),
+        unpaced_stats.average_burst_after_pacing()
+    );
+    assert!(
+        paced_stats.average_burst_after_pacing() * 2.0 < paced_stats.average_burst_before_pacing(),
+        "bursts averaged {} packets before pacing, & {} after",
+        paced_stats.average_burst_before_pacing(),
+        paced_stats.average_burst_after_pacing()
+    );
+    assert!(
+        paced_loss * 2.0 < unpaced_loss,
+        "{:.0}% of packets were lost with pacing, & {:.0}% without",
+        paced_loss * 100.0,
+        unpaced_loss * 100.0
+    );
+}
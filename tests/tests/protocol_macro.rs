@@ -0,0 +1,13 @@
+// The protocol! macro, both expanding a whole protocol, and reporting the
+// mistakes commonly made in one with errors pointing at them
+#[test]
+fn protocol_macro_reports_mistakes() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("ui/protocol/pass.rs");
+    cases.compile_fail("ui/protocol/not_an_event.rs");
+    cases.compile_fail("ui/protocol/actor_listed_as_event.rs");
+    cases.compile_fail("ui/protocol/missing_section.rs");
+    cases.compile_fail("ui/protocol/empty_section.rs");
+    cases.compile_fail("ui/protocol/listed_twice.rs");
+    cases.compile_fail("ui/protocol/unknown_attribute.rs");
+}
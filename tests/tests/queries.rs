@@ -0,0 +1,107 @@
+use std::{
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use naia_client::{
+    ClientEvent, ConnectFailReason, ConnectionState, NaiaClient, QueryStatus, RejectReason,
+};
+use naia_integration_tests::{
+    get_client_config, get_shared_config, server_address, TestActor, TestClient, TestEvent,
+    TestServer, QUERY_REPLY, QUERY_TEXT, TIMEOUT,
+};
+use naia_server::ServerConfig;
+use naia_shared::{utils::write_connectionless_payload, PacketType};
+
+fn wait_for_reply(server_address: SocketAddr, query: &[u8]) -> Option<Box<[u8]>> {
+    let mut query = NaiaClient::<TestEvent, TestActor>::query(server_address, query, TIMEOUT);
+    loop {
+        match query.poll() {
+            QueryStatus::Pending => std::thread::sleep(Duration::from_millis(5)),
+            QueryStatus::Reply(reply) => return Some(reply),
+            QueryStatus::TimedOut => return None,
+        }
+    }
+}
+
+#[test]
+fn full_server_still_answers_queries() {
+    let mut server_config = ServerConfig::default();
+    server_config.max_clients = Some(1);
+    let server = TestServer::start_with_config(
+        server_address(14381),
+        get_shared_config(None),
+        server_config,
+    );
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+
+    // the Server is full, so turns away another Client
+    let mut client_config = get_client_config();
+    client_config.max_handshake_attempts = 2;
+    let mut turned_away = TestClient::connect_with_config(
+        server.address(),
+        get_shared_config(None),
+        client_config,
+        "charlie",
+        "12345",
+    );
+    let mut fail_reason = None;
+    assert!(
+        turned_away.wait_for(TIMEOUT, |_, event| {
+            if let ClientEvent::ConnectionStateChanged(ConnectionState::ConnectFailed(reason)) =
+                event
+            {
+                fail_reason = Some(reason);
+                return true;
+            }
+            return false;
+        }),
+        "the other Client was never turned away"
+    );
+    assert_eq!(
+        fail_reason,
+        Some(ConnectFailReason::Rejected(RejectReason::ServerFull))
+    );
+
+    // yet still answers queries, without disturbing the connected Client
+    let reply = wait_for_reply(server.address(), QUERY_TEXT.as_bytes());
+    assert_eq!(reply.as_deref(), Some(QUERY_REPLY.as_bytes()));
+    assert!(client.client().has_connection());
+
+    let log = server.stop();
+    assert_eq!(log.connections, 1);
+}
+
+#[test]
+fn unpadded_queries_are_never_answered() {
+    let server = TestServer::start(server_address(14382), get_shared_config(None));
+
+    // a query smaller than its reply would be, as a spoofed query amplifying
+    // traffic towards its victim would be
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    let query = write_connectionless_payload(PacketType::Query, QUERY_TEXT.as_bytes());
+    socket.send_to(&query, server.address()).unwrap();
+    let mut buffer = [0; 1500];
+    assert!(socket.recv_from(&mut buffer).is_err());
+
+    // the same query, padded by the Client, is answered
+    let reply = wait_for_reply(server.address(), QUERY_TEXT.as_bytes());
+    assert_eq!(reply.as_deref(), Some(QUERY_REPLY.as_bytes()));
+
+    server.stop();
+}
@@ -0,0 +1,157 @@
+use std::{
+    cell::{Cell, RefCell},
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    rc::Rc,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use naia_client::{ClientEvent, HookAction, ManualClock, NaiaClient};
+use naia_integration_tests::{
+    get_client_config, get_shared_config, manifest_load, server_address, AuthEvent, TestActor,
+    TestEvent, TestServer,
+};
+
+// How far the Client's clock is advanced on each step of the session
+const STEP: Duration = Duration::from_millis(20);
+
+// How many steps the session runs for
+const STEPS: usize = 100;
+
+// How long each step waits for packets in flight to arrive, in real time
+const SETTLE: Duration = Duration::from_millis(5);
+
+// The packets a Client was sent, or sent itself, along with the step they
+// were seen on
+type Packets = Rc<RefCell<Vec<(usize, Vec<u8>)>>>;
+
+// Creates a Client on a ManualClock, recording every packet it's sent or
+// sends into `packets` with the step it was seen on
+fn client_on_manual_clock(
+    server_address: SocketAddr,
+    clock: Arc<ManualClock>,
+    step: Rc<Cell<usize>>,
+    packets: Packets,
+    record_incoming: bool,
+) -> NaiaClient<TestEvent, TestActor> {
+    let mut client_config = get_client_config();
+    client_config.clock = clock;
+    let mut client = NaiaClient::new(
+        server_address,
+        manifest_load(),
+        Some(client_config),
+        get_shared_config(None),
+        Some(TestEvent::AuthEvent(AuthEvent::new("charlie", "12345"))),
+    )
+    .expect("the AuthEvent should be registered in the manifest");
+    let hook = move |_: &SocketAddr, payload: &[u8]| {
+        packets.borrow_mut().push((step.get(), payload.to_vec()));
+        return HookAction::Pass;
+    };
+    if record_incoming {
+        client.set_incoming_hook(hook);
+    } else {
+        client.set_outgoing_hook(hook);
+    }
+    return client;
+}
+
+// Drains every event the Client has, returning whether it connected. The
+// Client sends no Events of its own, as the trace ids they'd be sent with are
+// unique across the process, rather than to a session
+fn drain(client: &mut NaiaClient<TestEvent, TestActor>) -> bool {
+    let mut connected = false;
+    while let Some(result) = client.receive() {
+        if let Ok(ClientEvent::Connection(_)) = result {
+            connected = true;
+        }
+    }
+    return connected;
+}
+
+// Runs a session against a real Test Server, getting every packet the Client
+// was sent, by step
+fn record(port: u16) -> Vec<(usize, Vec<u8>)> {
+    let server = TestServer::start(server_address(port), get_shared_config(None));
+    let clock = Arc::new(ManualClock::new());
+    let step = Rc::new(Cell::new(0));
+    let incoming: Packets = Rc::new(RefCell::new(Vec::new()));
+    let mut client = client_on_manual_clock(
+        server.address(),
+        clock.clone(),
+        step.clone(),
+        incoming.clone(),
+        true,
+    );
+
+    let mut connected = false;
+    for current in 0..STEPS {
+        step.set(current);
+        clock.advance(STEP);
+        connected |= drain(&mut client);
+        thread::sleep(SETTLE);
+    }
+    drop(client);
+    server.stop();
+
+    assert!(connected, "the recorded Client never connected");
+    return incoming.take();
+}
+
+// Replays a recorded session on a fresh Client, from a socket standing in for
+// the Server which sends it what it was sent on each step, getting every
+// packet the Client sent, by step
+fn replay(port: u16, recording: &[(usize, Vec<u8>)]) -> Vec<(usize, Vec<u8>)> {
+    let server_socket = UdpSocket::bind(("127.0.0.1", port)).unwrap();
+    server_socket.set_nonblocking(true).unwrap();
+    let clock = Arc::new(ManualClock::new());
+    let step = Rc::new(Cell::new(0));
+    let outgoing: Packets = Rc::new(RefCell::new(Vec::new()));
+    let mut client = client_on_manual_clock(
+        server_socket.local_addr().unwrap(),
+        clock.clone(),
+        step.clone(),
+        outgoing.clone(),
+        false,
+    );
+
+    let mut connected = false;
+    let mut client_address = None;
+    let mut buffer = [0; 2048];
+    for current in 0..STEPS {
+        step.set(current);
+        clock.advance(STEP);
+
+        // the Client's address is learned from the first packet it sends
+        loop {
+            match server_socket.recv_from(&mut buffer) {
+                Ok((_, address)) => client_address = Some(address),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => panic!("the stand-in Server couldn't receive: {}", error),
+            }
+        }
+        for (_, payload) in recording.iter().filter(|(at, _)| *at == current) {
+            let address = client_address.expect("the Client was sent a packet before it sent one");
+            server_socket.send_to(payload, address).unwrap();
+        }
+        thread::sleep(SETTLE);
+
+        connected |= drain(&mut client);
+    }
+
+    assert!(connected, "the replayed Client never connected");
+    return outgoing.take();
+}
+
+#[test]
+fn a_session_on_a_manual_clock_replays_identically() {
+    let recording = record(14338);
+    assert!(!recording.is_empty());
+
+    let first = replay(14339, &recording);
+    let second = replay(14340, &recording);
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    echo_of, get_shared_config, server_address, Direction, Fault, FaultRule, PacketMatcher,
+    ScriptedTransport, TestActor, TestClient, TestEvent, TestServer, TextEvent, COUNTER_LIMIT,
+    KICK_TEXT, TIMEOUT,
+};
+use naia_shared::{LocalActorKey, PacketType, StandardHeader};
+
+// Picks out the packets carrying the given text, which TextEvents hold as is
+fn carrying(text: &str) -> PacketMatcher {
+    let text = text.as_bytes().to_vec();
+    return PacketMatcher::Classifier(Box::new(move |_, bytes| {
+        return bytes
+            .windows(text.len())
+            .any(|window| window == text.as_slice());
+    }));
+}
+
+fn connect(transport: &ScriptedTransport) -> TestClient {
+    let mut client = TestClient::connect(
+        transport.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    return client;
+}
+
+// Sends the given texts one per tick, so that each is written into a packet of
+// its own, & waits for their echoes, returning them in the order they arrived
+fn send_and_wait_for_echoes(client: &mut TestClient, texts: &[String]) -> Vec<String> {
+    let mut sent = 0;
+    let mut received = Vec::new();
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            match event {
+                ClientEvent::Tick if sent < texts.len() => {
+                    client.send_event(&TextEvent::new(&texts[sent])).unwrap();
+                    sent += 1;
+                }
+                ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                    received.push(text_event.text.get().clone());
+                }
+                _ => {}
+            }
+            return received.len() == texts.len();
+        }),
+        "only {} of {} echoes arrived",
+        received.len(),
+        texts.len()
+    );
+    return received;
+}
+
+// Gets the faults the transport injected, in the order it injected them
+fn injected_faults(transport: &ScriptedTransport) -> Vec<(Direction, Fault)> {
+    return transport
+        .log()
+        .iter()
+        .filter_map(|packet| packet.fault.map(|(_, fault)| (packet.direction, fault)))
+        .collect();
+}
+
+#[test]
+fn ordered_events_wait_for_the_one_whose_packet_was_dropped() {
+    let server = TestServer::start(server_address(14357), get_shared_config(None));
+    // the packet carrying one text is lost on its way to the Server, & the
+    // packet carrying the echo of another on its way back, so that every
+    // Event on the ordered channel behind either is held up until it's resent
+    let transport = ScriptedTransport::start(
+        server_address(14356),
+        server.address(),
+        vec![
+            FaultRule::once(Direction::ToServer, carrying("hol-3"), Fault::Drop),
+            FaultRule::once(
+                Direction::ToClient,
+                carrying(&echo_of("hol-5")),
+                Fault::Drop,
+            ),
+        ],
+    );
+    let mut client = connect(&transport);
+
+    let texts: Vec<String> = (0..10).map(|index| format!("hol-{}", index)).collect();
+    let echoes = send_and_wait_for_echoes(&mut client, &texts);
+    let expected: Vec<String> = texts.iter().map(|text| echo_of(text)).collect();
+    assert_eq!(echoes, expected);
+    assert_eq!(
+        injected_faults(&transport),
+        [
+            (Direction::ToServer, Fault::Drop),
+            (Direction::ToClient, Fault::Drop),
+        ]
+    );
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, texts);
+}
+
+#[test]
+fn duplicated_and_swapped_packets_deliver_each_event_once_in_order() {
+    let server = TestServer::start(server_address(14359), get_shared_config(None));
+    let transport = ScriptedTransport::start(
+        server_address(14358),
+        server.address(),
+        vec![
+            FaultRule::once(Direction::ToServer, carrying("dup-2"), Fault::Duplicate),
+            FaultRule::once(Direction::ToServer, carrying("dup-4"), Fault::SwapWithNext),
+            FaultRule::once(
+                Direction::ToClient,
+                carrying(&echo_of("dup-6")),
+                Fault::SwapWithNext,
+            ),
+        ],
+    );
+    let mut client = connect(&transport);
+
+    let texts: Vec<String> = (0..10).map(|index| format!("dup-{}", index)).collect();
+    let echoes = send_and_wait_for_echoes(&mut client, &texts);
+    let expected: Vec<String> = texts.iter().map(|text| echo_of(text)).collect();
+    assert_eq!(echoes, expected);
+    assert_eq!(injected_faults(&transport).len(), 3);
+
+    let log = server.stop();
+    assert_eq!(log.received_texts, texts);
+}
+
+#[test]
+fn actor_created_and_deleted_while_unacknowledged_leaves_no_trace() {
+    let server = TestServer::start(server_address(14361), get_shared_config(None));
+    // while the blackout lasts, every Data packet headed for the Client is
+    // dropped, so that it hears of neither the creation nor the deletion of
+    // the Actor spawned & despawned in the meantime
+    let blackout = Arc::new(AtomicBool::new(false));
+    let classifier_blackout = blackout.clone();
+    let transport = ScriptedTransport::start(
+        server_address(14360),
+        server.address(),
+        vec![FaultRule::new(
+            Direction::ToClient,
+            PacketMatcher::Classifier(Box::new(move |_, bytes| {
+                return classifier_blackout.load(Ordering::SeqCst)
+                    && StandardHeader::read_packet_type(bytes) == PacketType::Data;
+            })),
+            Fault::Drop,
+        )],
+    );
+    let mut client = connect(&transport);
+
+    // the Client's own CounterActor counts all the way up first
+    let mut created: HashSet<LocalActorKey> = HashSet::new();
+    assert!(
+        client.wait_for(TIMEOUT, |client, event| {
+            if let ClientEvent::CreateActor(key) = event {
+                created.insert(key);
+            }
+            return client.actors_iter().any(|(_, actor)| {
+                let TestActor::CounterActor(counter) = actor;
+                return *counter.borrow().value.get() == COUNTER_LIMIT;
+            });
+        }),
+        "the Client's CounterActor never counted up"
+    );
+
+    // another User comes & goes during the blackout, so its CounterActor is
+    // spawned & despawned on the Server while the Client has yet to
+    // acknowledge creating it
+    blackout.store(true, Ordering::SeqCst);
+    let mut other_client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        other_client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the other Client never connected"
+    );
+    // a few ticks pass, for the CounterActor to be sent to the Client
+    let mut ticks = 0;
+    client.wait_for(TIMEOUT, |_, event| {
+        if let ClientEvent::Tick = event {
+            ticks += 1;
+        }
+        return ticks == 5;
+    });
+    other_client
+        .client()
+        .send_event(&TextEvent::new(KICK_TEXT))
+        .unwrap();
+    assert!(
+        other_client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::DisconnectedByServer(_) => true,
+            _ => false,
+        }),
+        "the other Client was never kicked"
+    );
+    blackout.store(false, Ordering::SeqCst);
+
+    // once the blackout lifts, the Client is left with its own CounterActor
+    // alone, never having deleted an Actor it didn't create
+    let mut ticks = 0;
+    client.wait_for(TIMEOUT, |_, event| {
+        match event {
+            ClientEvent::CreateActor(key) => {
+                created.insert(key);
+            }
+            ClientEvent::DeleteActor(key, _) => {
+                assert!(created.remove(&key), "an Actor never created was deleted");
+            }
+            ClientEvent::Tick => ticks += 1,
+            _ => {}
+        }
+        return ticks == 25;
+    });
+    assert_eq!(client.client().actors_iter().count(), 1);
+    assert_eq!(created.len(), 1);
+    assert!(injected_faults(&transport)
+        .iter()
+        .all(|fault| *fault == (Direction::ToClient, Fault::Drop)));
+    assert!(!injected_faults(&transport).is_empty());
+
+    let log = server.stop();
+    assert_eq!(log.disconnections, 1);
+}
@@ -0,0 +1,130 @@
+use std::{
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use naia_client::ClientEvent;
+use naia_integration_tests::{
+    echo_of, get_client_config, get_shared_config, server_address, TestClient, TestEvent,
+    TestServer, TextEvent, HOLD_TEXT, TIMEOUT,
+};
+use naia_server::{DebugCommand, DebugResponse};
+
+// How long the Server is given to shut down
+const DRAIN: Duration = Duration::from_millis(600);
+
+// How far past the drain duration shutting down may run, for scheduling
+const DRAIN_SLACK: Duration = Duration::from_millis(100);
+
+const SHUTDOWN_REASON: &str = "closing for the night";
+
+fn connect(server: &TestServer) -> TestClient {
+    let mut client = TestClient::connect(
+        server.address(),
+        get_shared_config(None),
+        "charlie",
+        "12345",
+    );
+    assert!(
+        client.wait_for(TIMEOUT, |_, event| match event {
+            ClientEvent::Connection(_) => true,
+            _ => false,
+        }),
+        "the Client never connected"
+    );
+    return client;
+}
+
+// Has the Server stop sending updates, then sends the only connected Client's
+// TextEvent, waiting until its echo is held back on the Server
+fn hold_echo(server: &TestServer, client: &mut TestClient, text: &str) {
+    let connections = match server.debug_command(DebugCommand::ListConnections) {
+        DebugResponse::Done(connections) => connections,
+        response => panic!("the connections weren't listed: {}", response),
+    };
+    let address: SocketAddr = connections.split(": ").next().unwrap().parse().unwrap();
+
+    client
+        .client()
+        .send_event(&TextEvent::new(HOLD_TEXT))
+        .unwrap();
+    client.client().send_event(&TextEvent::new(text)).unwrap();
+    let start = Instant::now();
+    loop {
+        let depths = server.debug_command(DebugCommand::DumpQueueDepths(address));
+        if depths.to_string().lines().next() == Some("outgoing events: 1") {
+            return;
+        }
+        assert!(start.elapsed() < TIMEOUT, "the echo was never held");
+        client.wait_for(Duration::from_millis(5), |_, _| false);
+    }
+}
+
+#[test]
+fn shutdown_flushes_then_notifies_every_client_well_before_it_would_time_out() {
+    let server = TestServer::start(server_address(14399), get_shared_config(None));
+    let mut first = connect(&server);
+    hold_echo(&server, &mut first, "last orders");
+    let mut second = connect(&server);
+
+    let shutdown_start = Instant::now();
+    let shutdown = thread::spawn(move || server.shutdown(SHUTDOWN_REASON, DRAIN));
+
+    // the echo held back is flushed before the notification
+    let mut first_texts = Vec::new();
+    let mut first_notified = None;
+    let mut second_notified = None;
+    while first_notified.is_none() || second_notified.is_none() {
+        assert!(
+            shutdown_start.elapsed() < TIMEOUT,
+            "the shutdown never reached every Client"
+        );
+        first.wait_for(Duration::from_millis(5), |_, event| {
+            match event {
+                ClientEvent::Event(TestEvent::TextEvent(text_event)) => {
+                    first_texts.push(text_event.text.get().clone());
+                }
+                ClientEvent::DisconnectedByServer(reason) => {
+                    assert_eq!(reason, SHUTDOWN_REASON);
+                    first_notified = Some(shutdown_start.elapsed());
+                    return true;
+                }
+                _ => {}
+            }
+            return false;
+        });
+        second.wait_for(Duration::from_millis(5), |_, event| {
+            if let ClientEvent::DisconnectedByServer(reason) = event {
+                assert_eq!(reason, SHUTDOWN_REASON);
+                second_notified = Some(shutdown_start.elapsed());
+                return true;
+            }
+            return false;
+        });
+    }
+    assert_eq!(first_texts, [echo_of("last orders")]);
+
+    // both hear of it within the drain, long before they'd have timed out
+    let client_timeout = get_client_config().disconnection_timeout_duration;
+    assert!(DRAIN < client_timeout / 10);
+    assert!(first_notified.unwrap() < DRAIN + DRAIN_SLACK);
+    assert!(second_notified.unwrap() < DRAIN + DRAIN_SLACK);
+
+    let log = shutdown.join().unwrap();
+    assert!(log.shutdown_duration.unwrap() < DRAIN + DRAIN_SLACK);
+    assert_eq!(log.connections, 2);
+}
+
+#[test]
+fn shutdown_returns_within_the_drain_duration_with_unresponsive_clients() {
+    let server = TestServer::start(server_address(14400), get_shared_config(None));
+    let mut client = connect(&server);
+    hold_echo(&server, &mut client, "never acknowledged");
+
+    // the Client goes silent with an echo still to be delivered to it
+    drop(client);
+    let log = server.shutdown(SHUTDOWN_REASON, DRAIN);
+    assert!(log.shutdown_duration.unwrap() < DRAIN + DRAIN_SLACK);
+    assert_eq!(log.disconnections, 0);
+}
@@ -0,0 +1,42 @@
+use naia_derive::protocol;
+
+mod ball_actor {
+    use naia_derive::Actor;
+    use naia_shared::{Actor, Property};
+
+    use super::GameActor;
+
+    #[derive(Actor)]
+    #[type_name = "GameActor"]
+    pub struct BallActor {
+        pub x: Property<u8>,
+    }
+}
+
+mod paddle_actor {
+    use naia_derive::Actor;
+    use naia_shared::{Actor, Property};
+
+    use super::GameActor;
+
+    #[derive(Actor)]
+    #[type_name = "GameActor"]
+    pub struct PaddleActor {
+        pub y: Property<u8>,
+    }
+}
+
+use ball_actor::BallActor;
+use paddle_actor::PaddleActor;
+
+protocol! {
+    pub events GameEvent {
+        BallActor,
+    }
+    pub actors GameActor {
+        PaddleActor,
+    }
+    pub fn manifest_load;
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use naia_derive::protocol;
+
+protocol! {
+    pub events GameEvent {
+        ChatEvent,
+    }
+    pub actors GameActor {}
+    pub fn manifest_load;
+}
+
+fn main() {}
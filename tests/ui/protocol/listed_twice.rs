@@ -0,0 +1,15 @@
+use naia_derive::protocol;
+
+protocol! {
+    pub events GameEvent {
+        ChatEvent,
+        #[channel(0)]
+        ChatEvent,
+    }
+    pub actors GameActor {
+        BallActor,
+    }
+    pub fn manifest_load;
+}
+
+fn main() {}
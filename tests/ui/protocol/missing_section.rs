@@ -0,0 +1,10 @@
+use naia_derive::protocol;
+
+protocol! {
+    pub events GameEvent {
+        ChatEvent,
+    }
+    pub fn manifest_load;
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use naia_derive::{protocol, Actor};
+use naia_shared::{Actor, Property};
+
+// holds what should be sent, but was never derived as an Event, so can't be
+// written into a packet
+#[derive(Clone)]
+pub struct ChatEvent {
+    pub text: String,
+}
+
+#[derive(Actor)]
+#[type_name = "GameActor"]
+pub struct BallActor {
+    pub x: Property<u8>,
+}
+
+protocol! {
+    pub events GameEvent {
+        ChatEvent,
+    }
+    pub actors GameActor {
+        BallActor,
+    }
+    pub fn manifest_load;
+}
+
+fn main() {}
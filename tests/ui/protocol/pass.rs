@@ -0,0 +1,56 @@
+use naia_derive::protocol;
+
+// each derived type is kept in a module of its own, as it would be in a file
+// of its own
+mod chat_event {
+    use naia_derive::Event;
+    use naia_shared::{Event, Property};
+
+    use super::GameEvent;
+
+    #[derive(Event, Clone)]
+    #[type_name = "GameEvent"]
+    pub struct ChatEvent {
+        pub text: Property<String>,
+    }
+
+    impl ChatEvent {
+        fn is_guaranteed() -> bool {
+            true
+        }
+    }
+}
+
+mod ball_actor {
+    use naia_derive::Actor;
+    use naia_shared::{Actor, Property};
+
+    use super::GameActor;
+
+    #[derive(Actor)]
+    #[type_name = "GameActor"]
+    pub struct BallActor {
+        pub x: Property<u8>,
+    }
+}
+
+use ball_actor::BallActor;
+use chat_event::ChatEvent;
+
+protocol! {
+    pub events GameEvent {
+        #[channel(0)]
+        #[timestamped]
+        ChatEvent,
+    }
+    pub actors GameActor {
+        BallActor,
+    }
+    pub fn manifest_load;
+}
+
+fn main() {
+    let manifest = manifest_load();
+    let channel = manifest.get_event_channel(&std::any::TypeId::of::<ChatEvent>());
+    assert_eq!(channel, Some(0));
+}
@@ -0,0 +1,14 @@
+use naia_derive::protocol;
+
+protocol! {
+    pub events GameEvent {
+        #[reliable]
+        ChatEvent,
+    }
+    pub actors GameActor {
+        BallActor,
+    }
+    pub fn manifest_load;
+}
+
+fn main() {}